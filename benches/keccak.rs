@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rlp::RlpStream;
+
+// NOTE: A trie key, a full branch node's worth of hashes & a log-heavy receipt respectively.
+const INPUT_SIZES: [usize; 3] = [32, 532, 4096];
+
+fn tiny_keccak_hash(bytes: &[u8]) -> [u8; 32] {
+    tiny_keccak::keccak256(bytes)
+}
+
+#[cfg(feature = "keccak-sha3")]
+fn sha3_keccak_hash(bytes: &[u8]) -> [u8; 32] {
+    use sha3::{Digest, Keccak256};
+    Keccak256::digest(bytes).into()
+}
+
+fn rlp_encode_receipt_shaped_list(num_logs: usize) -> Vec<u8> {
+    let mut rlp_stream = RlpStream::new_list(4);
+    rlp_stream
+        .append(&1u8)
+        .append(&0x78f028u64)
+        .append(&vec![0u8; 256]);
+    rlp_stream.begin_list(num_logs);
+    (0..num_logs).for_each(|_| {
+        rlp_stream.begin_list(3).append(&vec![0xc0u8; 20]);
+        rlp_stream.begin_list(3);
+        (0..3).for_each(|_| {
+            rlp_stream.append(&vec![0xeeu8; 32]);
+        });
+        rlp_stream.append(&vec![0xffu8; 96]);
+    });
+    rlp_stream.out()
+}
+
+fn bench_keccak_backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("keccak/{}", std::env::consts::ARCH));
+    INPUT_SIZES.iter().for_each(|size| {
+        let bytes = vec![0xc0u8; *size];
+        group.throughput(Throughput::Bytes(*size as u64));
+        group.bench_with_input(BenchmarkId::new("tiny-keccak", size), &bytes, |b, bytes| {
+            b.iter(|| tiny_keccak_hash(black_box(bytes)))
+        });
+        #[cfg(feature = "keccak-sha3")]
+        group.bench_with_input(BenchmarkId::new("sha3", size), &bytes, |b, bytes| {
+            b.iter(|| sha3_keccak_hash(black_box(bytes)))
+        });
+    });
+    group.finish();
+}
+
+fn bench_rlp_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("rlp/{}", std::env::consts::ARCH));
+    [1, 10, 100].iter().for_each(|num_logs| {
+        group.bench_with_input(
+            BenchmarkId::new("receipt", num_logs),
+            num_logs,
+            |b, num_logs| b.iter(|| rlp_encode_receipt_shaped_list(black_box(*num_logs))),
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_keccak_backends, bench_rlp_encoding);
+criterion_main!(benches);
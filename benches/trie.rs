@@ -0,0 +1,115 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ethereum_types::U256;
+use rusty_receipt_proof_maker::get_branch_from_trie::get_branch_from_trie;
+use rusty_receipt_proof_maker::get_hex_proof_from_branch::rlp_encode_node_stack;
+use rusty_receipt_proof_maker::get_receipts::deserialize_receipt_json_to_receipt_struct;
+use rusty_receipt_proof_maker::get_receipts_trie::get_receipts_trie_from_receipts;
+use rusty_receipt_proof_maker::make_rpc_call::deserialize_to_receipt_rpc_response;
+use rusty_receipt_proof_maker::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
+use rusty_receipt_proof_maker::types::Receipt;
+use std::fs;
+
+const BLOCK_SIZES: [usize; 3] = [100, 500, 1500];
+const RECEIPT_FIXTURE_DIRS: [&str; 3] = [
+    "./test_utils/sample_receipt_jsons_1/",
+    "./test_utils/sample_receipt_jsons_2/",
+    "./test_utils/sample_receipt_jsons_3/",
+];
+
+fn get_fixture_receipts() -> Vec<Receipt> {
+    let mut paths = RECEIPT_FIXTURE_DIRS
+        .iter()
+        .flat_map(|dir| fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("0x")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| fs::read_to_string(path).unwrap())
+        .map(|rpc_string| deserialize_to_receipt_rpc_response(rpc_string).unwrap())
+        .map(|receipt_json| {
+            deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap()
+        })
+        .collect()
+}
+
+// NOTE: The fixtures hold fewer receipts than the largest block ∴ they're cycled & re-indexed.
+fn get_block_of_receipts(fixture_receipts: &[Receipt], num_receipts: usize) -> Vec<Receipt> {
+    fixture_receipts
+        .iter()
+        .cycle()
+        .take(num_receipts)
+        .enumerate()
+        .map(|(index, receipt)| Receipt {
+            transaction_index: U256::from(index),
+            ..receipt.clone()
+        })
+        .collect()
+}
+
+fn bench_trie_construction(c: &mut Criterion) {
+    let fixture_receipts = get_fixture_receipts();
+    let mut group = c.benchmark_group(format!("trie-build/{}", std::env::consts::ARCH));
+    group.sample_size(20);
+    BLOCK_SIZES.iter().for_each(|num_receipts| {
+        let receipts = get_block_of_receipts(&fixture_receipts, *num_receipts);
+        group.bench_with_input(
+            BenchmarkId::new("receipts", num_receipts),
+            &receipts,
+            |b, receipts| b.iter(|| get_receipts_trie_from_receipts(black_box(receipts)).unwrap()),
+        );
+    });
+    group.finish();
+}
+
+fn bench_receipt_encoding(c: &mut Criterion) {
+    let fixture_receipts = get_fixture_receipts();
+    let mut group = c.benchmark_group(format!("receipt-encode/{}", std::env::consts::ARCH));
+    BLOCK_SIZES.iter().for_each(|num_receipts| {
+        let receipts = get_block_of_receipts(&fixture_receipts, *num_receipts);
+        group.bench_with_input(
+            BenchmarkId::new("receipts", num_receipts),
+            &receipts,
+            |b, receipts| {
+                b.iter(|| get_rlp_encoded_receipts_and_key_tuples(black_box(receipts)).unwrap())
+            },
+        );
+    });
+    group.finish();
+}
+
+fn bench_proof_extraction(c: &mut Criterion) {
+    let fixture_receipts = get_fixture_receipts();
+    let mut group = c.benchmark_group(format!("trie-prove/{}", std::env::consts::ARCH));
+    BLOCK_SIZES.iter().for_each(|num_receipts| {
+        let receipts = get_block_of_receipts(&fixture_receipts, *num_receipts);
+        let trie = get_receipts_trie_from_receipts(&receipts).unwrap();
+        let index = num_receipts / 2;
+        group.bench_with_input(
+            BenchmarkId::new("receipts", num_receipts),
+            &trie,
+            |b, trie| {
+                b.iter(|| {
+                    get_branch_from_trie(trie, black_box(index))
+                        .and_then(|branch| rlp_encode_node_stack(&branch))
+                        .unwrap()
+                })
+            },
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_trie_construction,
+    bench_receipt_encoding,
+    bench_proof_extraction
+);
+criterion_main!(benches);
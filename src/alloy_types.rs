@@ -0,0 +1,289 @@
+use crate::errors::AppError;
+use crate::proof_bundle::ProofBundle;
+use crate::types::{Bytes, Log, Receipt, ReceiptRpcFields, Result};
+use crate::utils::{convert_hex_to_h256, decode_prefixed_hex};
+use alloy_primitives::{Address as AlloyAddress, Bloom as AlloyBloom, B256, U256 as AlloyU256};
+use ethereum_types::{Address, Bloom, H256, U256};
+use std::convert::TryFrom;
+
+/**
+ * Alloy Types:
+ *
+ * The public API speaks `ethereum-types`, so downstream projects on the alloy
+ * stack had to convert every hash they got out of, or put into, the tool. W/
+ * the `alloy` feature, the `ToAlloy` & `FromAlloy` traits convert hashes,
+ * addresses, blooms & `U256`s between the two, & receipts, logs & proof
+ * bundles convert into alloy typed mirrors of themselves & back. Swapping the
+ * types wholesale would break every existing caller, hence the mirrors, & the
+ * orphan rule forbids `From` impls between two foreign crates' types, hence
+ * the traits.
+ */
+pub trait ToAlloy {
+    type Alloy;
+    fn to_alloy(&self) -> Self::Alloy;
+}
+
+pub trait FromAlloy<T> {
+    fn from_alloy(value: &T) -> Self;
+}
+
+impl ToAlloy for H256 {
+    type Alloy = B256;
+    fn to_alloy(&self) -> B256 {
+        B256::from_slice(self.as_bytes())
+    }
+}
+
+impl FromAlloy<B256> for H256 {
+    fn from_alloy(value: &B256) -> H256 {
+        H256::from_slice(value.as_slice())
+    }
+}
+
+impl ToAlloy for Address {
+    type Alloy = AlloyAddress;
+    fn to_alloy(&self) -> AlloyAddress {
+        AlloyAddress::from_slice(self.as_bytes())
+    }
+}
+
+impl FromAlloy<AlloyAddress> for Address {
+    fn from_alloy(value: &AlloyAddress) -> Address {
+        Address::from_slice(value.as_slice())
+    }
+}
+
+impl ToAlloy for Bloom {
+    type Alloy = AlloyBloom;
+    fn to_alloy(&self) -> AlloyBloom {
+        AlloyBloom::from_slice(self.as_bytes())
+    }
+}
+
+impl FromAlloy<AlloyBloom> for Bloom {
+    fn from_alloy(value: &AlloyBloom) -> Bloom {
+        Bloom::from_slice(value.as_slice())
+    }
+}
+
+impl ToAlloy for U256 {
+    type Alloy = AlloyU256;
+    fn to_alloy(&self) -> AlloyU256 {
+        let mut bytes = [0u8; 32];
+        self.to_big_endian(&mut bytes);
+        AlloyU256::from_be_bytes(bytes)
+    }
+}
+
+impl FromAlloy<AlloyU256> for U256 {
+    fn from_alloy(value: &AlloyU256) -> U256 {
+        U256::from_big_endian(&value.to_be_bytes::<32>())
+    }
+}
+
+impl<T: ToAlloy> ToAlloy for Option<T> {
+    type Alloy = Option<T::Alloy>;
+    fn to_alloy(&self) -> Option<T::Alloy> {
+        self.as_ref().map(ToAlloy::to_alloy)
+    }
+}
+
+impl<A, T: FromAlloy<A>> FromAlloy<Option<A>> for Option<T> {
+    fn from_alloy(value: &Option<A>) -> Option<T> {
+        value.as_ref().map(T::from_alloy)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlloyLog {
+    pub address: AlloyAddress,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+impl From<&Log> for AlloyLog {
+    fn from(log: &Log) -> AlloyLog {
+        AlloyLog {
+            address: log.address.to_alloy(),
+            topics: log.topics.iter().map(ToAlloy::to_alloy).collect(),
+            data: log.data.clone(),
+        }
+    }
+}
+
+impl From<&AlloyLog> for Log {
+    fn from(log: &AlloyLog) -> Log {
+        Log {
+            address: Address::from_alloy(&log.address),
+            topics: log.topics.iter().map(H256::from_alloy).collect(),
+            data: log.data.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlloyReceipt {
+    pub to: Option<AlloyAddress>,
+    pub from: AlloyAddress,
+    pub status: bool,
+    pub gas_used: AlloyU256,
+    pub block_hash: B256,
+    pub transaction_hash: B256,
+    pub cumulative_gas_used: AlloyU256,
+    pub block_number: AlloyU256,
+    pub transaction_index: AlloyU256,
+    pub contract_address: Option<AlloyAddress>,
+    pub logs: Vec<AlloyLog>,
+    pub root: Option<B256>,
+    pub logs_bloom: AlloyBloom,
+    pub tx_type: u8,
+    pub deposit_nonce: Option<AlloyU256>,
+    pub deposit_receipt_version: Option<AlloyU256>,
+    pub effective_gas_price: Option<AlloyU256>,
+}
+
+impl From<&Receipt> for AlloyReceipt {
+    fn from(receipt: &Receipt) -> AlloyReceipt {
+        AlloyReceipt {
+            to: receipt.to.to_alloy(),
+            from: receipt.from.to_alloy(),
+            status: receipt.status,
+            gas_used: receipt.gas_used.to_alloy(),
+            block_hash: receipt.block_hash.to_alloy(),
+            transaction_hash: receipt.transaction_hash.to_alloy(),
+            cumulative_gas_used: receipt.cumulative_gas_used.to_alloy(),
+            block_number: receipt.block_number.to_alloy(),
+            transaction_index: receipt.transaction_index.to_alloy(),
+            contract_address: receipt.contract_address.to_alloy(),
+            logs: receipt.logs.iter().map(AlloyLog::from).collect(),
+            root: receipt.root.to_alloy(),
+            logs_bloom: receipt.logs_bloom.to_alloy(),
+            tx_type: receipt.tx_type,
+            deposit_nonce: receipt.deposit_nonce.to_alloy(),
+            deposit_receipt_version: receipt.deposit_receipt_version.to_alloy(),
+            effective_gas_price: receipt.rpc_fields.effective_gas_price.to_alloy(),
+        }
+    }
+}
+
+impl From<&AlloyReceipt> for Receipt {
+    fn from(receipt: &AlloyReceipt) -> Receipt {
+        Receipt {
+            to: FromAlloy::from_alloy(&receipt.to),
+            from: Address::from_alloy(&receipt.from),
+            status: receipt.status,
+            gas_used: U256::from_alloy(&receipt.gas_used),
+            block_hash: H256::from_alloy(&receipt.block_hash),
+            transaction_hash: H256::from_alloy(&receipt.transaction_hash),
+            cumulative_gas_used: U256::from_alloy(&receipt.cumulative_gas_used),
+            block_number: U256::from_alloy(&receipt.block_number),
+            transaction_index: U256::from_alloy(&receipt.transaction_index),
+            contract_address: FromAlloy::from_alloy(&receipt.contract_address),
+            logs: receipt.logs.iter().map(Log::from).collect(),
+            root: FromAlloy::from_alloy(&receipt.root),
+            logs_bloom: Bloom::from_alloy(&receipt.logs_bloom),
+            tx_type: receipt.tx_type,
+            deposit_nonce: FromAlloy::from_alloy(&receipt.deposit_nonce),
+            deposit_receipt_version: FromAlloy::from_alloy(&receipt.deposit_receipt_version),
+            rpc_fields: ReceiptRpcFields {
+                effective_gas_price: FromAlloy::from_alloy(&receipt.effective_gas_price),
+                ..ReceiptRpcFields::default()
+            },
+        }
+    }
+}
+
+// NOTE: The fields a verifier needs, decoded from the bundle's hex strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlloyProofBundle {
+    pub tx_hash: B256,
+    pub tx_index: usize,
+    pub block_hash: B256,
+    pub block_number: u64,
+    pub receipts_root: B256,
+    pub key: Bytes,
+    pub value: Option<Bytes>,
+    pub value_hash: Option<B256>,
+    pub proof: Vec<Bytes>,
+    pub header: Option<Bytes>,
+}
+
+fn convert_hex_to_b256(hex: &str) -> Result<B256> {
+    convert_hex_to_h256(hex.to_string()).map(|hash| hash.to_alloy())
+}
+
+impl TryFrom<&ProofBundle> for AlloyProofBundle {
+    type Error = AppError;
+
+    fn try_from(bundle: &ProofBundle) -> Result<AlloyProofBundle> {
+        Ok(AlloyProofBundle {
+            tx_hash: convert_hex_to_b256(&bundle.tx_hash)?,
+            tx_index: bundle.tx_index,
+            block_hash: convert_hex_to_b256(&bundle.block_hash)?,
+            block_number: bundle.block_number,
+            receipts_root: convert_hex_to_b256(&bundle.receipts_root)?,
+            key: decode_prefixed_hex(bundle.key.clone())?,
+            value: match bundle.value.is_empty() {
+                true => None,
+                false => Some(decode_prefixed_hex(bundle.value.clone())?),
+            },
+            value_hash: bundle
+                .value_hash
+                .as_ref()
+                .map(|hash| convert_hex_to_b256(hash))
+                .transpose()?,
+            proof: bundle
+                .proof
+                .iter()
+                .map(|node| decode_prefixed_hex(node.clone()))
+                .collect::<Result<Vec<Bytes>>>()?,
+            header: bundle
+                .header
+                .as_ref()
+                .map(|header| decode_prefixed_hex(header.clone()))
+                .transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{get_expected_receipt, get_sample_proof_bundle_1};
+    use crate::utils::convert_bytes_to_prefixed_hex;
+
+    #[test]
+    fn should_round_trip_primitives_via_alloy() {
+        let hash = H256::from_low_u64_be(0xc0ffee);
+        let number = U256::from(u64::MAX) * U256::from(1337u64);
+        assert!(H256::from_alloy(&hash.to_alloy()) == hash);
+        assert!(U256::from_alloy(&number.to_alloy()) == number);
+        assert!(number.to_alloy() == AlloyU256::from(u64::MAX) * AlloyU256::from(1337u64));
+        assert!(hash.to_alloy().as_slice() == hash.as_bytes());
+    }
+
+    #[test]
+    fn should_round_trip_receipt_via_alloy() {
+        let receipt = get_expected_receipt();
+        let alloy_receipt = AlloyReceipt::from(&receipt);
+        assert!(alloy_receipt.transaction_hash.as_slice() == receipt.transaction_hash.as_bytes());
+        assert!(alloy_receipt.logs.len() == receipt.logs.len());
+        assert!(Receipt::from(&alloy_receipt) == receipt);
+    }
+
+    #[test]
+    fn should_convert_proof_bundle_to_alloy() {
+        let bundle = get_sample_proof_bundle_1();
+        let result = AlloyProofBundle::try_from(&bundle).unwrap();
+        assert!(
+            convert_bytes_to_prefixed_hex(result.receipts_root.as_slice()) == bundle.receipts_root
+        );
+        assert!(result.proof.len() == bundle.proof.len());
+        assert!(result.value.is_some());
+        let bad_bundle = ProofBundle {
+            tx_hash: "0xc0ffee".to_string(),
+            ..bundle
+        };
+        assert!(AlloyProofBundle::try_from(&bad_bundle).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::proof_bundle::{rlp_encode_proof_nodes_from_proof_bundle, ProofBundle};
+use crate::state::State;
+use crate::types::Result;
+use crate::utils::convert_h256_to_prefixed_hex;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub timestamp: String,
+    pub requester: Option<String>,
+    pub tx_hash: String,
+    pub block_hash: Option<String>,
+    pub tx_index: usize,
+    pub receipts_root: String,
+    pub proof_hash: String,
+}
+
+pub fn append_entry_to_audit_log(path: &str, entry: &AuditLogEntry) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(file.sync_data()?)
+}
+
+pub fn get_audit_log_entry_from_state(
+    state: &State,
+    requester: Option<String>,
+) -> Result<AuditLogEntry> {
+    Ok(AuditLogEntry {
+        requester,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        tx_hash: convert_h256_to_prefixed_hex(state.tx_hash)?,
+        block_hash: match state.get_block_from_state() {
+            Ok(block) => Some(convert_h256_to_prefixed_hex(block.hash)?),
+            Err(_) => None,
+        },
+        tx_index: *state.get_index_from_state()?,
+        receipts_root: convert_h256_to_prefixed_hex(state.get_receipts_trie_from_state()?.root)?,
+        proof_hash: rlp_encode_node_stack(state.get_branch_from_state()?)
+            .and_then(|proof_bytes| keccak_hash_bytes(&proof_bytes))
+            .and_then(convert_h256_to_prefixed_hex)?,
+    })
+}
+
+pub fn get_audit_log_entry_from_proof_bundle(
+    bundle: &ProofBundle,
+    requester: Option<String>,
+) -> Result<AuditLogEntry> {
+    Ok(AuditLogEntry {
+        requester,
+        tx_index: bundle.tx_index,
+        tx_hash: bundle.tx_hash.clone(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        receipts_root: bundle.receipts_root.clone(),
+        block_hash: Some(bundle.block_hash.clone()),
+        proof_hash: rlp_encode_proof_nodes_from_proof_bundle(bundle)
+            .and_then(|proof_bytes| keccak_hash_bytes(&proof_bytes))
+            .and_then(convert_h256_to_prefixed_hex)?,
+    })
+}
+
+pub fn maybe_append_proof_bundles_to_audit_log(
+    audit_log_path: &Option<String>,
+    bundles: &[ProofBundle],
+) -> Result<()> {
+    match audit_log_path {
+        None => Ok(()),
+        Some(path) => {
+            info!(
+                "✔ Appending {} proof(s) to audit log at: {}",
+                bundles.len(),
+                path
+            );
+            bundles.iter().try_for_each(|bundle| {
+                get_audit_log_entry_from_proof_bundle(bundle, None)
+                    .and_then(|entry| append_entry_to_audit_log(path, &entry))
+            })
+        }
+    }
+}
+
+pub fn maybe_append_proof_to_audit_log(state: State) -> Result<State> {
+    match &state.audit_log_path {
+        None => Ok(state),
+        Some(path) => {
+            info!("✔ Appending proof to audit log at: {}", path);
+            get_audit_log_entry_from_state(&state, None)
+                .and_then(|entry| append_entry_to_audit_log(path, &entry))
+                .map(|_| state)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        get_sample_proof_bundle_1, get_sample_tx_hashes_1,
+        get_valid_state_with_receipts_trie_index_and_branch, RECEIPTS_ROOT_1,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use std::fs;
+
+    fn get_sample_audit_log_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-audit-log-{}.jsonl", name));
+        if path.exists() {
+            fs::remove_file(&path).unwrap();
+        }
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn should_get_audit_log_entry_from_state() {
+        let state = get_valid_state_with_receipts_trie_index_and_branch(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .unwrap();
+        let result = get_audit_log_entry_from_state(&state, None).unwrap();
+        assert!(result.tx_index == 14);
+        assert!(result.block_hash.is_none());
+        assert!(result.receipts_root == RECEIPTS_ROOT_1);
+        assert!(result.proof_hash.len() == 66);
+    }
+
+    #[test]
+    fn should_append_entries_to_audit_log() {
+        let path = get_sample_audit_log_path("append");
+        let state = get_valid_state_with_receipts_trie_index_and_branch(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .unwrap();
+        let entry = get_audit_log_entry_from_state(&state, Some("127.0.0.1".to_string())).unwrap();
+        append_entry_to_audit_log(&path, &entry).unwrap();
+        append_entry_to_audit_log(&path, &entry).unwrap();
+        let lines = fs::read_to_string(&path).unwrap();
+        let results: Vec<AuditLogEntry> = lines
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(results.len() == 2);
+        assert!(results[1].proof_hash == entry.proof_hash);
+        assert!(results[1].requester == Some("127.0.0.1".to_string()));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_not_touch_audit_log_if_no_path_in_state() {
+        let state = get_valid_state_with_receipts_trie_index_and_branch(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .unwrap();
+        let result = maybe_append_proof_to_audit_log(state).unwrap();
+        assert!(result.audit_log_path.is_none());
+    }
+
+    #[test]
+    fn should_get_same_proof_hash_from_proof_bundle_as_from_state() {
+        let state = get_valid_state_with_receipts_trie_index_and_branch(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .unwrap();
+        let bundle = get_sample_proof_bundle_1();
+        let entry_from_state = get_audit_log_entry_from_state(&state, None).unwrap();
+        let entry_from_bundle = get_audit_log_entry_from_proof_bundle(&bundle, None).unwrap();
+        assert!(entry_from_bundle.proof_hash == entry_from_state.proof_hash);
+        assert!(entry_from_bundle.block_hash == Some(bundle.block_hash));
+    }
+
+    #[test]
+    fn should_append_proof_bundles_to_audit_log() {
+        let path = get_sample_audit_log_path("bundles");
+        let bundles = vec![get_sample_proof_bundle_1(), get_sample_proof_bundle_1()];
+        maybe_append_proof_bundles_to_audit_log(&Some(path.clone()), &bundles).unwrap();
+        let num_lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert!(num_lines == 2);
+        fs::remove_file(&path).unwrap();
+    }
+}
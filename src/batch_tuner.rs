@@ -0,0 +1,214 @@
+use crate::secrets::redact_secrets;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+pub const INITIAL_BATCH_SIZE: usize = 16;
+pub const MAX_BATCH_SIZE: usize = 512;
+pub const INITIAL_CONCURRENCY: usize = 2;
+pub const MAX_CONCURRENCY: usize = 16;
+const TARGET_BATCH_LATENCY: Duration = Duration::from_secs(1);
+
+/**
+ * Batch Tuner:
+ *
+ * Local nodes happily answer hundreds of receipts per JSON-RPC batch, many
+ * batches at once, whilst flaky or rate limited providers fail or crawl under
+ * the same load. So the batch size & concurrency of receipt fetches are tuned
+ * per endpoint from how its batches fare: each fast batch doubles the batch
+ * size & adds one to the concurrency, a slow one (over a second) halves the
+ * batch size & takes one off the concurrency, & a failed one halves both &
+ * caps the batch size at half the failed one's for the rest of the run, so
+ * batches a provider rejects aren't tried again. At a batch size of one,
+ * receipts are fetched w/o batching, which suits providers rejecting batches
+ * outright. `--timings` reports what was chosen.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct BatchParams {
+    pub batch_size: usize,
+    pub concurrency: usize,
+}
+
+impl Default for BatchParams {
+    fn default() -> Self {
+        BatchParams {
+            batch_size: INITIAL_BATCH_SIZE,
+            concurrency: INITIAL_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EndpointTuning {
+    pub params: BatchParams,
+    pub max_batch_size: usize,
+    pub num_batches: u64,
+    pub num_failed_batches: u64,
+    pub total_batch_seconds: f64,
+}
+
+impl Default for EndpointTuning {
+    fn default() -> Self {
+        EndpointTuning {
+            params: BatchParams::default(),
+            max_batch_size: MAX_BATCH_SIZE,
+            num_batches: 0,
+            num_failed_batches: 0,
+            total_batch_seconds: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchOutcome {
+    Fast,
+    Slow,
+    Failed,
+}
+
+// NOTE: Tunings are only ever whole values, so a poisoned lock still holds usable ones.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn get_batch_outcome(latency: Duration, succeeded: bool) -> BatchOutcome {
+    match (succeeded, latency <= TARGET_BATCH_LATENCY) {
+        (false, _) => BatchOutcome::Failed,
+        (true, true) => BatchOutcome::Fast,
+        (true, false) => BatchOutcome::Slow,
+    }
+}
+
+pub fn tune_batch_params(params: BatchParams, outcome: BatchOutcome) -> BatchParams {
+    let (batch_size, concurrency) = match outcome {
+        BatchOutcome::Fast => (params.batch_size * 2, params.concurrency + 1),
+        BatchOutcome::Slow => (params.batch_size / 2, params.concurrency.saturating_sub(1)),
+        BatchOutcome::Failed => (params.batch_size / 2, params.concurrency / 2),
+    };
+    BatchParams {
+        batch_size: batch_size.clamp(1, MAX_BATCH_SIZE),
+        concurrency: concurrency.clamp(1, MAX_CONCURRENCY),
+    }
+}
+
+// NOTE: Owned by each run rather than the process, so embedders' runs don't tune each other.
+#[derive(Debug, Default)]
+pub struct BatchTuner {
+    endpoint_tunings: Mutex<BTreeMap<String, EndpointTuning>>,
+}
+
+impl BatchTuner {
+    pub fn get_batch_params(&self, endpoint: &str) -> BatchParams {
+        lock(&self.endpoint_tunings)
+            .get(endpoint)
+            .map(|tuning| tuning.params)
+            .unwrap_or_default()
+    }
+
+    pub fn record_batch_result(
+        &self,
+        endpoint: &str,
+        batch_size: usize,
+        latency: Duration,
+        succeeded: bool,
+    ) {
+        let mut tunings = lock(&self.endpoint_tunings);
+        let tuning = tunings.entry(endpoint.to_string()).or_default();
+        if !succeeded {
+            tuning.max_batch_size = tuning.max_batch_size.min((batch_size / 2).max(1));
+        }
+        let outcome = get_batch_outcome(latency, succeeded);
+        let tuned_params = tune_batch_params(tuning.params, outcome);
+        let params = BatchParams {
+            batch_size: tuned_params.batch_size.min(tuning.max_batch_size),
+            ..tuned_params
+        };
+        if params != tuning.params {
+            debug!(
+                "✔ {:?} batch, now batching {} receipts, {} batches at once",
+                outcome, params.batch_size, params.concurrency
+            );
+        }
+        tuning.params = params;
+        tuning.num_batches += 1;
+        tuning.total_batch_seconds += latency.as_secs_f64();
+        if !succeeded {
+            tuning.num_failed_batches += 1;
+        }
+    }
+
+    // NOTE: Keyed by the redacted endpoint, since they're for reporting.
+    pub fn get_endpoint_tunings(&self) -> BTreeMap<String, EndpointTuning> {
+        lock(&self.endpoint_tunings)
+            .iter()
+            .map(|(endpoint, tuning)| (redact_secrets(endpoint), tuning.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_grow_batch_params_after_fast_batches() {
+        let result = (0..20).fold(BatchParams::default(), |params, _| {
+            tune_batch_params(params, BatchOutcome::Fast)
+        });
+        assert!(result.batch_size == MAX_BATCH_SIZE);
+        assert!(result.concurrency == MAX_CONCURRENCY);
+    }
+
+    #[test]
+    fn should_shrink_batch_params_after_failed_batches() {
+        let params = BatchParams {
+            batch_size: 64,
+            concurrency: 8,
+        };
+        let result = tune_batch_params(params, BatchOutcome::Failed);
+        assert!(result.batch_size == 32 && result.concurrency == 4);
+        let result = (0..10).fold(result, |params, _| {
+            tune_batch_params(params, BatchOutcome::Failed)
+        });
+        assert!(result.batch_size == 1 && result.concurrency == 1);
+    }
+
+    #[test]
+    fn should_shrink_batch_size_gently_after_slow_batches() {
+        let result = tune_batch_params(BatchParams::default(), BatchOutcome::Slow);
+        assert!(result.batch_size == INITIAL_BATCH_SIZE / 2);
+        assert!(result.concurrency == INITIAL_CONCURRENCY - 1);
+    }
+
+    #[test]
+    fn should_get_batch_outcome_from_latency_and_success() {
+        assert!(get_batch_outcome(Duration::from_millis(50), true) == BatchOutcome::Fast);
+        assert!(get_batch_outcome(Duration::from_secs(3), true) == BatchOutcome::Slow);
+        assert!(get_batch_outcome(Duration::from_millis(50), false) == BatchOutcome::Failed);
+    }
+
+    #[test]
+    fn should_tune_batch_params_per_endpoint() {
+        let tuner = BatchTuner::default();
+        let flaky_endpoint = "http://flaky.example.com/batch-tuner-test";
+        let local_endpoint = "http://localhost:8545/batch-tuner-test";
+        tuner.record_batch_result(flaky_endpoint, 16, Duration::from_millis(10), false);
+        tuner.record_batch_result(local_endpoint, 16, Duration::from_millis(10), true);
+        assert!(tuner.get_batch_params(flaky_endpoint).batch_size < INITIAL_BATCH_SIZE);
+        assert!(tuner.get_batch_params(local_endpoint).batch_size > INITIAL_BATCH_SIZE);
+        let tunings = tuner.get_endpoint_tunings();
+        assert!(tunings[flaky_endpoint].num_failed_batches == 1);
+        assert!(tunings[local_endpoint].num_batches == 1);
+    }
+
+    #[test]
+    fn should_cap_batch_size_below_failed_batches() {
+        let tuner = BatchTuner::default();
+        let endpoint = "http://capped.example.com/batch-tuner-test";
+        tuner.record_batch_result(endpoint, 16, Duration::from_millis(10), false);
+        (0..10)
+            .for_each(|_| tuner.record_batch_result(endpoint, 8, Duration::from_millis(10), true));
+        assert!(tuner.get_batch_params(endpoint).batch_size == 8);
+        assert!(tuner.get_batch_params(endpoint).concurrency > INITIAL_CONCURRENCY);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::binary_proof::decode_binary_proof_bundles;
+use crate::errors::AppError;
+use crate::parse_cli_args::OutputFormat;
+use crate::proof_bundle::ProofBundle;
+use crate::types::{Byte, Bytes, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, decode_prefixed_hex};
+use clap::ValueEnum;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+const ENVELOPE_MAGIC: [Byte; 3] = *b"RPZ";
+const ENVELOPE_HEADER_LENGTH: usize = ENVELOPE_MAGIC.len() + 1;
+const ZLIB_CODEC_BYTE: Byte = 1;
+const SNAPPY_CODEC_BYTE: Byte = 2;
+const MAX_DECOMPRESSED_SIZE: usize = 1024 * 1024 * 1024;
+
+/**
+ * Binary Envelopes:
+ *
+ * Binary proofs are RLP nodes & hashes, & the proofs of a backfill's blocks
+ * share much of their structure, so archives of them compress well. So w/
+ * `--compress <zlib|snappy>`, binary proofs are wrapped in an envelope: the
+ * magic bytes `RPZ`, a byte identifying the codec, `1` for zlib & `2` for
+ * snappy, then the binary proof compressed w/ it, output as one hex string as
+ * before. Unwrapped binary proofs start w/ their version byte instead, so
+ * readers tell the two apart by the magic, & `verify` reads either. Inflating
+ * an envelope stops at 1 GiB, so a malicious one can't exhaust memory.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProofCodec {
+    Zlib,
+    Snappy,
+}
+
+impl ProofCodec {
+    fn to_byte(self) -> Byte {
+        match self {
+            ProofCodec::Zlib => ZLIB_CODEC_BYTE,
+            ProofCodec::Snappy => SNAPPY_CODEC_BYTE,
+        }
+    }
+
+    fn from_byte(byte: Byte) -> Result<Self> {
+        match byte {
+            ZLIB_CODEC_BYTE => Ok(ProofCodec::Zlib),
+            SNAPPY_CODEC_BYTE => Ok(ProofCodec::Snappy),
+            _ => Err(AppError::Custom(format!(
+                "✘ Unknown binary proof envelope codec: {}!",
+                byte
+            ))),
+        }
+    }
+
+    fn compress(self, bytes: &[Byte]) -> Result<Bytes> {
+        match self {
+            ProofCodec::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            ProofCodec::Snappy => snap::raw::Encoder::new()
+                .compress_vec(bytes)
+                .map_err(|e| AppError::Custom(format!("✘ Error compressing w/ snappy: {}", e))),
+        }
+    }
+
+    fn decompress(self, bytes: &[Byte]) -> Result<Bytes> {
+        let too_large_err = || {
+            AppError::Custom(format!(
+                "✘ Binary proof envelope inflates beyond {} bytes!",
+                MAX_DECOMPRESSED_SIZE
+            ))
+        };
+        match self {
+            ProofCodec::Zlib => {
+                let mut decompressed = Vec::new();
+                ZlibDecoder::new(bytes)
+                    .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+                match decompressed.len() > MAX_DECOMPRESSED_SIZE {
+                    true => Err(too_large_err()),
+                    false => Ok(decompressed),
+                }
+            }
+            ProofCodec::Snappy => {
+                let decompressed_length = snap::raw::decompress_len(bytes).map_err(|e| {
+                    AppError::Custom(format!("✘ Error decompressing w/ snappy: {}", e))
+                })?;
+                if decompressed_length > MAX_DECOMPRESSED_SIZE {
+                    return Err(too_large_err());
+                }
+                snap::raw::Decoder::new()
+                    .decompress_vec(bytes)
+                    .map_err(|e| {
+                        AppError::Custom(format!("✘ Error decompressing w/ snappy: {}", e))
+                    })
+            }
+        }
+    }
+}
+
+pub fn wrap_binary_proof_in_envelope(bytes: &[Byte], codec: ProofCodec) -> Result<Bytes> {
+    let compressed = codec.compress(bytes)?;
+    info!(
+        "✔ Compressed binary proof w/ {:?} from {} to {} bytes",
+        codec,
+        bytes.len(),
+        compressed.len() + ENVELOPE_HEADER_LENGTH
+    );
+    Ok([&ENVELOPE_MAGIC[..], &[codec.to_byte()], &compressed].concat())
+}
+
+pub fn is_binary_proof_envelope(bytes: &[Byte]) -> bool {
+    bytes.starts_with(&ENVELOPE_MAGIC)
+}
+
+pub fn maybe_unwrap_binary_proof_envelope(bytes: Bytes) -> Result<Bytes> {
+    if !is_binary_proof_envelope(&bytes) {
+        return Ok(bytes);
+    }
+    match bytes.get(ENVELOPE_MAGIC.len()) {
+        Some(codec_byte) => {
+            ProofCodec::from_byte(*codec_byte)?.decompress(&bytes[ENVELOPE_HEADER_LENGTH..])
+        }
+        None => Err(AppError::Custom(
+            "✘ Binary proof envelope has no codec!".to_string(),
+        )),
+    }
+}
+
+// NOTE: Other formats, & other commands' binary output, being text, are never wrapped.
+pub fn maybe_compress_binary_proof_output(
+    output: String,
+    format: OutputFormat,
+    maybe_codec: Option<ProofCodec>,
+) -> Result<String> {
+    match (format, maybe_codec) {
+        (OutputFormat::Binary, Some(codec)) => decode_prefixed_hex(output)
+            .and_then(|bytes| wrap_binary_proof_in_envelope(&bytes, codec))
+            .map(|wrapped| convert_bytes_to_prefixed_hex(&wrapped)),
+        _ => Ok(output),
+    }
+}
+
+pub fn decode_binary_proof_output(hex: &str) -> Result<Vec<ProofBundle>> {
+    decode_prefixed_hex(hex.trim().to_string())
+        .and_then(maybe_unwrap_binary_proof_envelope)
+        .and_then(|bytes| decode_binary_proof_bundles(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_proof::format_proof_bundles_as_binary;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    #[test]
+    fn should_round_trip_binary_proofs_through_envelopes_w_either_codec() {
+        let bundles = vec![get_sample_proof_bundle_1(); 4];
+        let binary = format_proof_bundles_as_binary(&bundles).unwrap();
+        [ProofCodec::Zlib, ProofCodec::Snappy]
+            .iter()
+            .for_each(|codec| {
+                let result = maybe_compress_binary_proof_output(
+                    binary.clone(),
+                    OutputFormat::Binary,
+                    Some(*codec),
+                )
+                .unwrap();
+                assert!(result.len() < binary.len());
+                let bytes = decode_prefixed_hex(result.clone()).unwrap();
+                assert!(is_binary_proof_envelope(&bytes));
+                assert!(bytes[ENVELOPE_MAGIC.len()] == codec.to_byte());
+                assert!(decode_binary_proof_output(&result).unwrap() == bundles);
+            });
+        assert!(decode_binary_proof_output(&binary).unwrap() == bundles);
+    }
+
+    #[test]
+    fn should_only_compress_binary_output() {
+        let output = "{\"not\": \"binary\"}".to_string();
+        let result = maybe_compress_binary_proof_output(
+            output.clone(),
+            OutputFormat::Json,
+            Some(ProofCodec::Zlib),
+        )
+        .unwrap();
+        assert!(result == output);
+        let hex = "0xc0ffee".to_string();
+        let result = maybe_compress_binary_proof_output(hex.clone(), OutputFormat::Binary, None);
+        assert!(result.unwrap() == hex);
+    }
+
+    #[test]
+    fn should_fail_to_unwrap_malformed_envelopes() {
+        let no_codec = ENVELOPE_MAGIC.to_vec();
+        let unknown_codec = [&ENVELOPE_MAGIC[..], &[0xff], &[0x00]].concat();
+        let bad_payload = [&ENVELOPE_MAGIC[..], &[ZLIB_CODEC_BYTE], &[0xde, 0xad]].concat();
+        [no_codec, unknown_codec, bad_payload]
+            .iter()
+            .for_each(|bytes| assert!(maybe_unwrap_binary_proof_envelope(bytes.clone()).is_err()));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::constants::HASH_LENGTH;
+use crate::errors::AppError;
+use crate::proof_bundle::ProofBundle;
+use crate::types::{Byte, Bytes, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, decode_prefixed_hex};
+use std::collections::HashMap;
+
+pub const BINARY_PROOF_VERSION: Byte = 1;
+
+const COMPRESSED_FLAG: Byte = 0b01;
+const VALUE_HASH_FLAG: Byte = 0b10;
+
+/**
+ * Binary Proofs:
+ *
+ * JSON proofs hex encode every byte, doubling their size, which on-chain
+ * verifiers paying for calldata by the byte feel. So `--format binary` packs
+ * proof bundles into a compact encoding, output as one hex string: a version
+ * byte, a table of the distinct proof nodes, each length prefixed, then the
+ * bundles. Each bundle is a flags byte (compressed, value is a hash), the tx
+ * hash, block hash & receipts root as raw 32 bytes, the block number & tx
+ * index, the length prefixed key, value & header (empty if none), & its proof
+ * as indices into the node table. So nodes shared by the proofs of a block,
+ * e.g. the root, are only encoded once. Lengths & numbers are unsigned LEB128
+ * varints. Proof expiries & assertions' results are JSON only.
+ */
+fn encode_varint(mut value: u64, encoded: &mut Bytes) {
+    while value >= 0x80 {
+        encoded.push((value as Byte & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    encoded.push(value as Byte);
+}
+
+fn encode_length_prefixed(bytes: &[Byte], encoded: &mut Bytes) {
+    encode_varint(bytes.len() as u64, encoded);
+    encoded.extend_from_slice(bytes);
+}
+
+fn encode_hash(hex: &str, encoded: &mut Bytes) -> Result<()> {
+    let bytes = decode_prefixed_hex(hex.to_string())?;
+    match bytes.len() {
+        HASH_LENGTH => {
+            encoded.extend_from_slice(&bytes);
+            Ok(())
+        }
+        _ => Err(AppError::Custom(format!(
+            "✘ Cannot encode {} as a 32 byte hash!",
+            hex
+        ))),
+    }
+}
+
+fn get_flags(bundle: &ProofBundle) -> Byte {
+    let compressed_flag = if bundle.compressed {
+        COMPRESSED_FLAG
+    } else {
+        0
+    };
+    let value_hash_flag = if bundle.value_hash.is_some() {
+        VALUE_HASH_FLAG
+    } else {
+        0
+    };
+    compressed_flag | value_hash_flag
+}
+
+pub fn encode_proof_bundles_to_binary(bundles: &[ProofBundle]) -> Result<Bytes> {
+    let mut nodes: Vec<Bytes> = Vec::new();
+    let mut node_indices: HashMap<Bytes, usize> = HashMap::new();
+    let proofs_node_indices = bundles
+        .iter()
+        .map(|bundle| {
+            bundle
+                .proof
+                .iter()
+                .map(|hex| {
+                    let node = decode_prefixed_hex(hex.clone())?;
+                    Ok(*node_indices.entry(node.clone()).or_insert_with(|| {
+                        nodes.push(node);
+                        nodes.len() - 1
+                    }))
+                })
+                .collect::<Result<Vec<usize>>>()
+        })
+        .collect::<Result<Vec<Vec<usize>>>>()?;
+    let mut encoded = vec![BINARY_PROOF_VERSION];
+    encode_varint(nodes.len() as u64, &mut encoded);
+    nodes
+        .iter()
+        .for_each(|node| encode_length_prefixed(node, &mut encoded));
+    encode_varint(bundles.len() as u64, &mut encoded);
+    bundles.iter().zip(proofs_node_indices).try_for_each(
+        |(bundle, proof_node_indices)| -> Result<()> {
+            encoded.push(get_flags(bundle));
+            encode_hash(&bundle.tx_hash, &mut encoded)?;
+            encode_hash(&bundle.block_hash, &mut encoded)?;
+            encode_hash(&bundle.receipts_root, &mut encoded)?;
+            encode_varint(bundle.block_number, &mut encoded);
+            encode_varint(bundle.tx_index as u64, &mut encoded);
+            encode_length_prefixed(&decode_prefixed_hex(bundle.key.clone())?, &mut encoded);
+            let value = bundle.value_hash.as_ref().unwrap_or(&bundle.value);
+            encode_length_prefixed(&decode_prefixed_hex(value.clone())?, &mut encoded);
+            let header = match &bundle.header {
+                Some(header) => decode_prefixed_hex(header.clone())?,
+                None => Vec::new(),
+            };
+            encode_length_prefixed(&header, &mut encoded);
+            encode_varint(proof_node_indices.len() as u64, &mut encoded);
+            proof_node_indices
+                .into_iter()
+                .for_each(|index| encode_varint(index as u64, &mut encoded));
+            Ok(())
+        },
+    )?;
+    Ok(encoded)
+}
+
+pub fn format_proof_bundles_as_binary(bundles: &[ProofBundle]) -> Result<String> {
+    encode_proof_bundles_to_binary(bundles).map(|encoded| convert_bytes_to_prefixed_hex(&encoded))
+}
+
+struct BinaryDecoder<'a> {
+    bytes: &'a [Byte],
+    position: usize,
+}
+
+impl<'a> BinaryDecoder<'a> {
+    fn get_truncated_err(&self) -> AppError {
+        AppError::Custom(format!(
+            "✘ Binary proof truncated at byte {} of {}!",
+            self.position,
+            self.bytes.len()
+        ))
+    }
+
+    fn take(&mut self, num_bytes: usize) -> Result<&'a [Byte]> {
+        match self.position.checked_add(num_bytes) {
+            Some(end) if end <= self.bytes.len() => {
+                let taken = &self.bytes[self.position..end];
+                self.position = end;
+                Ok(taken)
+            }
+            _ => Err(self.get_truncated_err()),
+        }
+    }
+
+    fn take_byte(&mut self) -> Result<Byte> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn take_varint(&mut self) -> Result<u64> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take_byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(AppError::Custom(format!(
+            "✘ Binary proof has an overlong varint before byte {}!",
+            self.position
+        )))
+    }
+
+    fn take_length(&mut self) -> Result<usize> {
+        let length = self.take_varint()? as usize;
+        // NOTE: Guards against allocating for lengths no remaining bytes could hold.
+        match length <= self.bytes.len() - self.position {
+            true => Ok(length),
+            false => Err(self.get_truncated_err()),
+        }
+    }
+
+    fn take_length_prefixed(&mut self) -> Result<&'a [Byte]> {
+        let length = self.take_length()?;
+        self.take(length)
+    }
+
+    fn take_hash_hex(&mut self) -> Result<String> {
+        self.take(HASH_LENGTH).map(convert_bytes_to_prefixed_hex)
+    }
+}
+
+fn decode_proof_bundle(decoder: &mut BinaryDecoder, nodes: &[String]) -> Result<ProofBundle> {
+    let flags = decoder.take_byte()?;
+    let tx_hash = decoder.take_hash_hex()?;
+    let block_hash = decoder.take_hash_hex()?;
+    let receipts_root = decoder.take_hash_hex()?;
+    let block_number = decoder.take_varint()?;
+    let tx_index = decoder.take_varint()? as usize;
+    let key = convert_bytes_to_prefixed_hex(decoder.take_length_prefixed()?);
+    let value = convert_bytes_to_prefixed_hex(decoder.take_length_prefixed()?);
+    let header = decoder.take_length_prefixed()?;
+    let num_proof_nodes = decoder.take_length()?;
+    let proof = (0..num_proof_nodes)
+        .map(|_| {
+            let index = decoder.take_varint()? as usize;
+            nodes.get(index).cloned().ok_or_else(|| {
+                AppError::Custom(format!(
+                    "✘ Binary proof references node {} of {}!",
+                    index,
+                    nodes.len()
+                ))
+            })
+        })
+        .collect::<Result<Vec<String>>>()?;
+    let value_is_hash = flags & VALUE_HASH_FLAG != 0;
+    Ok(ProofBundle {
+        tx_hash,
+        tx_index,
+        block_hash,
+        block_number,
+        receipts_root,
+        key,
+        value: if value_is_hash {
+            String::new()
+        } else {
+            value.clone()
+        },
+        value_hash: if value_is_hash { Some(value) } else { None },
+        proof,
+        header: match header.is_empty() {
+            true => None,
+            false => Some(convert_bytes_to_prefixed_hex(header)),
+        },
+        head_block_number: None,
+        valid_until: None,
+        compressed: flags & COMPRESSED_FLAG != 0,
+        assertions: Vec::new(),
+        chain_id: None,
+        net_version: None,
+        finality: None,
+        metadata: None,
+        signature: None,
+    })
+}
+
+pub fn decode_binary_proof_bundles(bytes: &[Byte]) -> Result<Vec<ProofBundle>> {
+    let mut decoder = BinaryDecoder { bytes, position: 0 };
+    let version = decoder.take_byte()?;
+    if version != BINARY_PROOF_VERSION {
+        return Err(AppError::Custom(format!(
+            "✘ Binary proof version {} unsupported, this binary reads version {}!",
+            version, BINARY_PROOF_VERSION
+        )));
+    }
+    let num_nodes = decoder.take_length()?;
+    let nodes = (0..num_nodes)
+        .map(|_| {
+            decoder
+                .take_length_prefixed()
+                .map(convert_bytes_to_prefixed_hex)
+        })
+        .collect::<Result<Vec<String>>>()?;
+    let num_bundles = decoder.take_length()?;
+    let bundles = (0..num_bundles)
+        .map(|_| decode_proof_bundle(&mut decoder, &nodes))
+        .collect::<Result<Vec<ProofBundle>>>()?;
+    match decoder.position == bytes.len() {
+        true => Ok(bundles),
+        false => Err(AppError::Custom(format!(
+            "✘ Binary proof has {} trailing byte(s)!",
+            bytes.len() - decoder.position
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+    use crate::proof_bundle::{compress_proof_in_proof_bundle, maybe_hash_value_in_proof_bundle};
+    use crate::prove_block::get_proof_bundles_for_all_receipts;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, PROOF_1_INDEX,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::convert_hex_strings_to_h256s;
+
+    fn get_sample_proof_bundles() -> Vec<ProofBundle> {
+        let mut block = get_expected_block();
+        let tx_hashes = get_sample_tx_hashes_1();
+        let receipts =
+            get_sample_receipts(SAMPLE_RECEIPT_JSONS_1_PATH.to_string(), tx_hashes.clone());
+        block.transactions = convert_hex_strings_to_h256s(tx_hashes).unwrap();
+        let trie = get_receipts_trie_from_receipts(&receipts).unwrap();
+        get_proof_bundles_for_all_receipts(&block, &trie).unwrap()
+    }
+
+    fn get_sample_proof_bundle() -> ProofBundle {
+        get_sample_proof_bundles().remove(PROOF_1_INDEX)
+    }
+
+    #[test]
+    fn should_round_trip_varints() {
+        [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX]
+            .iter()
+            .for_each(|value| {
+                let mut encoded = Vec::new();
+                encode_varint(*value, &mut encoded);
+                let mut decoder = BinaryDecoder {
+                    bytes: &encoded,
+                    position: 0,
+                };
+                assert!(decoder.take_varint().unwrap() == *value);
+                assert!(decoder.position == encoded.len());
+            });
+    }
+
+    #[test]
+    fn should_round_trip_proof_bundles_through_binary() {
+        let bundle = get_sample_proof_bundle();
+        let hashed_and_compressed = maybe_hash_value_in_proof_bundle(bundle.clone(), true)
+            .and_then(compress_proof_in_proof_bundle)
+            .unwrap();
+        let light = ProofBundle {
+            header: None,
+            ..bundle.clone()
+        };
+        let bundles = vec![bundle, hashed_and_compressed, light];
+        let encoded = encode_proof_bundles_to_binary(&bundles).unwrap();
+        let result = decode_binary_proof_bundles(&encoded).unwrap();
+        assert!(result == bundles);
+    }
+
+    #[test]
+    fn should_deduplicate_proof_nodes_shared_by_bundles() {
+        let bundles = get_sample_proof_bundles();
+        let json_size = serde_json::to_string(&bundles).unwrap().len();
+        let encoded = encode_proof_bundles_to_binary(&bundles).unwrap();
+        let encoded_separately_size: usize = bundles
+            .iter()
+            .map(|bundle| {
+                encode_proof_bundles_to_binary(std::slice::from_ref(bundle))
+                    .unwrap()
+                    .len()
+            })
+            .sum();
+        assert!(encoded.len() * 2 < json_size);
+        assert!(encoded.len() < encoded_separately_size);
+        assert!(decode_binary_proof_bundles(&encoded).unwrap() == bundles);
+    }
+
+    #[test]
+    fn should_fail_to_decode_malformed_binary_proofs() {
+        let encoded = encode_proof_bundles_to_binary(&[get_sample_proof_bundle()]).unwrap();
+        let mut wrong_version = encoded.clone();
+        wrong_version[0] = BINARY_PROOF_VERSION + 1;
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(decode_binary_proof_bundles(&encoded[..encoded.len() - 1]).is_err());
+        assert!(decode_binary_proof_bundles(&wrong_version).is_err());
+        assert!(decode_binary_proof_bundles(&trailing).is_err());
+        assert!(decode_binary_proof_bundles(&[]).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::errors::AppError;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::rlp_codec::{rlp_encode, Decoder, Encoder, RlpValue};
+use crate::types::{Block, Byte, Bytes, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+use ethereum_types::{H256, U256};
+
+const HEADER_PARENT_HASH_INDEX: usize = 0;
+const HEADER_RECEIPTS_ROOT_INDEX: usize = 5;
+const HEADER_BLOCK_NUMBER_INDEX: usize = 8;
+const HEADER_NONCE_LENGTH: usize = 8;
+
+/**
+ * Block Header Encoding:
+ *
+ * A block's hash is the keccak hash of the RLP encoding of its header, whose
+ * fields are appended to by hard forks: London adds the base fee, Shanghai
+ * the withdrawals root, Cancun the blob gas fields & the parent beacon block
+ * root, & Prague the requests hash. A header only has a later fork's fields
+ * if it has all earlier forks' fields too.
+ */
+fn get_rlp_encoded_post_london_fields(block: &Block) -> Result<Vec<Bytes>> {
+    let fields = vec![
+        block.base_fee_per_gas.as_ref().map(rlp_encode),
+        block.withdrawals_root.as_ref().map(rlp_encode),
+        block.blob_gas_used.as_ref().map(rlp_encode),
+        block.excess_blob_gas.as_ref().map(rlp_encode),
+        block.parent_beacon_block_root.as_ref().map(rlp_encode),
+        block.requests_hash.as_ref().map(rlp_encode),
+    ];
+    let num_present_fields = fields.iter().take_while(|field| field.is_some()).count();
+    match fields[num_present_fields..]
+        .iter()
+        .all(|field| field.is_none())
+    {
+        true => Ok(fields.into_iter().flatten().collect()),
+        false => Err(AppError::Custom(format!(
+            "✘ Block {} has header fields of a hard fork w/o those of an earlier one!",
+            block.number
+        ))),
+    }
+}
+
+fn get_nonce_bytes(nonce: &U256) -> Bytes {
+    let mut bytes = [0u8; 32];
+    nonce.to_big_endian(&mut bytes);
+    bytes[32 - HEADER_NONCE_LENGTH..].to_vec()
+}
+
+pub fn rlp_encode_block_header(block: &Block) -> Result<Bytes> {
+    let post_london_fields = get_rlp_encoded_post_london_fields(block)?;
+    let mut encoder = Encoder::new_list(15 + post_london_fields.len());
+    encoder
+        .append(&block.parent_hash)
+        .append(&block.sha3_uncles)
+        .append(&block.miner)
+        .append(&block.state_root)
+        .append(&block.transactions_root)
+        .append(&block.receipts_root)
+        .append(&block.logs_bloom)
+        .append(&block.difficulty)
+        .append(&block.number)
+        .append(&block.gas_limit)
+        .append(&block.gas_used)
+        .append(&block.timestamp)
+        .append(&block.extra_data)
+        .append(&block.mix_hash)
+        .append(&get_nonce_bytes(&block.nonce));
+    post_london_fields.iter().for_each(|field| {
+        encoder.append_raw(field);
+    });
+    Ok(encoder.out())
+}
+
+fn check_header_hash(rlp_encoded_header: &[Byte], block_hash: &H256) -> Result<()> {
+    let header_hash = keccak_hash_bytes(rlp_encoded_header)?;
+    match header_hash == *block_hash {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Block header hashes to {} not to block hash {}!",
+            convert_h256_to_prefixed_hex(header_hash)?,
+            convert_h256_to_prefixed_hex(*block_hash)?
+        ))),
+    }
+}
+
+pub fn get_verified_rlp_encoded_block_header(block: &Block) -> Result<Bytes> {
+    info!("✔ Verifying block header hashes to block hash...");
+    rlp_encode_block_header(block).and_then(|rlp_encoded_header| {
+        check_header_hash(&rlp_encoded_header, &block.hash).map(|_| rlp_encoded_header)
+    })
+}
+
+fn decode_header_field<T: RlpValue>(rlp_encoded_header: &[Byte], index: usize) -> Result<T> {
+    Decoder::new(rlp_encoded_header)
+        .val_at(index)
+        .map_err(|e| AppError::Custom(format!("✘ Error decoding block header: {}", e)))
+}
+
+pub fn check_rlp_encoded_block_header(
+    rlp_encoded_header: &[Byte],
+    block_hash: &H256,
+    receipts_root: &H256,
+    block_number: u64,
+) -> Result<()> {
+    check_header_hash(rlp_encoded_header, block_hash)?;
+    let header_receipts_root: H256 =
+        decode_header_field(rlp_encoded_header, HEADER_RECEIPTS_ROOT_INDEX)?;
+    let header_block_number: U256 =
+        decode_header_field(rlp_encoded_header, HEADER_BLOCK_NUMBER_INDEX)?;
+    match header_receipts_root == *receipts_root && header_block_number == block_number.into() {
+        true => Ok(()),
+        false => Err(AppError::Custom(
+            "✘ Block header's receipts root or number does not match proof's!".to_string(),
+        )),
+    }
+}
+
+pub fn get_verified_receipts_root_from_rlp_encoded_block_header(
+    rlp_encoded_header: &[Byte],
+    block_hash: &H256,
+) -> Result<H256> {
+    check_header_hash(rlp_encoded_header, block_hash)
+        .and_then(|_| decode_header_field(rlp_encoded_header, HEADER_RECEIPTS_ROOT_INDEX))
+}
+
+pub fn get_verified_parent_hash_and_number_from_rlp_encoded_block_header(
+    rlp_encoded_header: &[Byte],
+    block_hash: &H256,
+) -> Result<(H256, U256)> {
+    check_header_hash(rlp_encoded_header, block_hash)?;
+    Ok((
+        decode_header_field(rlp_encoded_header, HEADER_PARENT_HASH_INDEX)?,
+        decode_header_field(rlp_encoded_header, HEADER_BLOCK_NUMBER_INDEX)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_expected_block;
+
+    #[test]
+    fn should_rlp_encode_block_header_hashing_to_block_hash() {
+        let block = get_expected_block();
+        let result = rlp_encode_block_header(&block).unwrap();
+        assert!(keccak_hash_bytes(&result).unwrap() == block.hash);
+        assert!(Decoder::new(&result).get_item_count().unwrap() == 15);
+    }
+
+    #[test]
+    fn should_append_post_london_fields_to_block_header() {
+        let mut block = get_expected_block();
+        block.base_fee_per_gas = Some(U256::from(7));
+        block.withdrawals_root = Some(H256::zero());
+        let result = rlp_encode_block_header(&block).unwrap();
+        assert!(Decoder::new(&result).get_item_count().unwrap() == 17);
+        assert!(decode_header_field::<U256>(&result, 15).unwrap() == U256::from(7));
+    }
+
+    #[test]
+    fn should_fail_to_encode_block_header_with_fork_fields_out_of_order() {
+        let mut block = get_expected_block();
+        block.withdrawals_root = Some(H256::zero());
+        assert!(rlp_encode_block_header(&block).is_err());
+    }
+
+    #[test]
+    fn should_get_verified_receipts_root_from_rlp_encoded_block_header() {
+        let block = get_expected_block();
+        let header = rlp_encode_block_header(&block).unwrap();
+        let result =
+            get_verified_receipts_root_from_rlp_encoded_block_header(&header, &block.hash).unwrap();
+        assert!(result == block.receipts_root);
+        assert!(
+            get_verified_receipts_root_from_rlp_encoded_block_header(&header, &H256::zero())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn should_fail_to_verify_tampered_block_header() {
+        let mut block = get_expected_block();
+        block.receipts_root = H256::zero();
+        match get_verified_rlp_encoded_block_header(&block) {
+            Err(AppError::Custom(e)) => assert!(e.contains("not to block hash")),
+            _ => panic!("Should not verify tampered block header!"),
+        }
+    }
+
+    #[test]
+    fn should_check_rlp_encoded_block_header_against_proof_fields() {
+        let block = get_expected_block();
+        let header = get_verified_rlp_encoded_block_header(&block).unwrap();
+        let number = block.number.as_u64();
+        check_rlp_encoded_block_header(&header, &block.hash, &block.receipts_root, number).unwrap();
+        assert!(
+            check_rlp_encoded_block_header(&header, &block.hash, &H256::zero(), number).is_err()
+        );
+        assert!(check_rlp_encoded_block_header(
+            &header,
+            &block.hash,
+            &block.receipts_root,
+            number + 1
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::errors::AppError;
+use crate::types::Result;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Mutex;
+use std::thread;
+
+/**
+ * Block Pipeline:
+ *
+ * Proving a range one block at a time leaves the node idle while tries are
+ * built, & the CPU idle while receipts are fetched, so large backfills take
+ * days. So range proofs run as a pipeline of stages, each on its own thread,
+ * passing blocks along bounded channels: a block fetcher, then pools of
+ * `--jobs` receipt fetchers, trie builders & proof extractors. Each block's
+ * trie is independent of every other's, so up to `--jobs` of them are built
+ * at once, keeping many cores busy. Blocks can finish out of order, so their
+ * proofs are reordered & handed over in block order, which keeps checkpoints
+ * meaningful. The fetcher never runs more than twice `--jobs` blocks ahead of
+ * the last one handed over, so at most that many blocks are in flight across
+ * the channels & workers of every stage, bounding memory. A failure at a
+ * block surfaces once all blocks before it are handed over, stopping the rest.
+ */
+pub trait BlockPipelineStages: Sync {
+    type Block: Send;
+    type Receipts: Send;
+    type Trie: Send;
+    type Proofs: Send;
+
+    fn fetch_block(&self, block_number: u64) -> Result<Self::Block>;
+    fn fetch_receipts(&self, block: Self::Block) -> Result<Self::Receipts>;
+    fn build_trie(&self, receipts: Self::Receipts) -> Result<Self::Trie>;
+    fn extract_proofs(&self, trie: Self::Trie) -> Result<Self::Proofs>;
+}
+
+type Staged<T> = (u64, Result<T>);
+
+fn run_stage<I, O>(
+    receiver: &Mutex<Receiver<Staged<I>>>,
+    sender: SyncSender<Staged<O>>,
+    stage: impl Fn(I) -> Result<O>,
+) {
+    loop {
+        // NOTE: The lock is only held while waiting on the next block, not while processing it.
+        let next = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        let (block_number, input) = match next {
+            Ok(staged) => staged,
+            Err(_) => return,
+        };
+        if sender.send((block_number, input.and_then(&stage))).is_err() {
+            return;
+        }
+    }
+}
+
+fn hand_over_in_order<P>(
+    block_numbers: RangeInclusive<u64>,
+    proofs_receiver: Receiver<Staged<P>>,
+    window_sender: SyncSender<()>,
+    mut on_proofs: impl FnMut(u64, P) -> Result<()>,
+) -> Result<()> {
+    let mut pending = BTreeMap::new();
+    let mut next_block = *block_numbers.start();
+    for (block_number, proofs) in proofs_receiver.iter() {
+        pending.insert(block_number, proofs);
+        while let Some(proofs) = pending.remove(&next_block) {
+            on_proofs(next_block, proofs?)?;
+            if next_block == *block_numbers.end() {
+                return Ok(());
+            }
+            next_block += 1;
+            // NOTE: Lets the fetcher move a block further ahead.
+            let _ = window_sender.send(());
+        }
+    }
+    Err(AppError::Custom(format!(
+        "✘ Block pipeline stopped before block {}!",
+        next_block
+    )))
+}
+
+pub fn run_block_pipeline<S: BlockPipelineStages>(
+    stages: &S,
+    block_numbers: RangeInclusive<u64>,
+    jobs: usize,
+    on_proofs: impl FnMut(u64, S::Proofs) -> Result<()>,
+) -> Result<()> {
+    if block_numbers.is_empty() {
+        return Ok(());
+    }
+    let jobs = jobs.max(1);
+    let window = jobs * 2;
+    let (window_sender, window_receiver) = sync_channel(window);
+    (0..window).for_each(|_| {
+        let _ = window_sender.send(());
+    });
+    let (block_sender, block_receiver) = sync_channel(jobs);
+    let (receipts_sender, receipts_receiver) = sync_channel(jobs);
+    let (trie_sender, trie_receiver) = sync_channel(jobs);
+    let (proofs_sender, proofs_receiver) = sync_channel(jobs);
+    let block_receiver = Mutex::new(block_receiver);
+    let receipts_receiver = Mutex::new(receipts_receiver);
+    let trie_receiver = Mutex::new(trie_receiver);
+    thread::scope(|scope| {
+        let fetched_block_numbers = block_numbers.clone();
+        scope.spawn(move || {
+            for block_number in fetched_block_numbers {
+                if window_receiver.recv().is_err()
+                    || block_sender
+                        .send((block_number, stages.fetch_block(block_number)))
+                        .is_err()
+                {
+                    return;
+                }
+            }
+        });
+        let block_receiver = &block_receiver;
+        (0..jobs).for_each(|_| {
+            let receipts_sender = receipts_sender.clone();
+            scope.spawn(move || {
+                run_stage(block_receiver, receipts_sender, |block| {
+                    stages.fetch_receipts(block)
+                })
+            });
+        });
+        drop(receipts_sender);
+        let receipts_receiver = &receipts_receiver;
+        (0..jobs).for_each(|_| {
+            let trie_sender = trie_sender.clone();
+            scope.spawn(move || {
+                run_stage(receipts_receiver, trie_sender, |receipts| {
+                    stages.build_trie(receipts)
+                })
+            });
+        });
+        drop(trie_sender);
+        let trie_receiver = &trie_receiver;
+        (0..jobs).for_each(|_| {
+            let proofs_sender = proofs_sender.clone();
+            scope.spawn(move || {
+                run_stage(trie_receiver, proofs_sender, |trie| {
+                    stages.extract_proofs(trie)
+                })
+            });
+        });
+        drop(proofs_sender);
+        hand_over_in_order(block_numbers, proofs_receiver, window_sender, on_proofs)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    struct SampleStages {
+        failing_block: Option<u64>,
+        max_fetched_block: AtomicU64,
+        num_building: AtomicUsize,
+        max_num_building: AtomicUsize,
+    }
+
+    impl BlockPipelineStages for SampleStages {
+        type Block = u64;
+        type Receipts = u64;
+        type Trie = u64;
+        type Proofs = String;
+
+        fn fetch_block(&self, block_number: u64) -> Result<u64> {
+            self.max_fetched_block
+                .fetch_max(block_number, Ordering::SeqCst);
+            Ok(block_number)
+        }
+
+        // NOTE: Earlier blocks take longest, so they finish out of order.
+        fn fetch_receipts(&self, block: u64) -> Result<u64> {
+            thread::sleep(Duration::from_millis(20 - block % 4 * 5));
+            match self.failing_block == Some(block) {
+                true => Err(AppError::Custom("✘ Receipts unavailable!".to_string())),
+                false => Ok(block),
+            }
+        }
+
+        fn build_trie(&self, receipts: u64) -> Result<u64> {
+            let num_building = self.num_building.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_num_building
+                .fetch_max(num_building, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(10));
+            self.num_building.fetch_sub(1, Ordering::SeqCst);
+            Ok(receipts)
+        }
+
+        fn extract_proofs(&self, trie: u64) -> Result<String> {
+            Ok(format!("proofs of block {}", trie))
+        }
+    }
+
+    fn get_sample_stages(failing_block: Option<u64>) -> SampleStages {
+        SampleStages {
+            failing_block,
+            max_fetched_block: AtomicU64::new(0),
+            num_building: AtomicUsize::new(0),
+            max_num_building: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn should_hand_over_proofs_in_block_order() {
+        let stages = get_sample_stages(None);
+        let mut handed_over = vec![];
+        run_block_pipeline(&stages, 1..=12, 4, |block_number, proofs| {
+            handed_over.push((block_number, proofs));
+            Ok(())
+        })
+        .unwrap();
+        let expected = (1..=12)
+            .map(|n| (n, format!("proofs of block {}", n)))
+            .collect::<Vec<(u64, String)>>();
+        assert!(handed_over == expected);
+    }
+
+    #[test]
+    fn should_build_tries_of_different_blocks_at_once_up_to_jobs() {
+        let stages = get_sample_stages(None);
+        run_block_pipeline(&stages, 1..=32, 4, |_, _| Ok(())).unwrap();
+        let max_num_building = stages.max_num_building.load(Ordering::SeqCst);
+        assert!(max_num_building > 1);
+        assert!(max_num_building <= 4);
+    }
+
+    #[test]
+    fn should_hand_over_blocks_before_a_failed_one_then_stop() {
+        let stages = get_sample_stages(Some(5));
+        let mut handed_over = vec![];
+        let result = run_block_pipeline(&stages, 1..=1000, 2, |block_number, _| {
+            handed_over.push(block_number);
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert!(handed_over == vec![1, 2, 3, 4]);
+        assert!(stages.max_fetched_block.load(Ordering::SeqCst) < 5 + 2 * 2);
+    }
+
+    #[test]
+    fn should_stop_pipeline_when_handing_over_fails() {
+        let stages = get_sample_stages(None);
+        let result =
+            run_block_pipeline(&stages, 1..=1000, 3, |block_number, _| match block_number {
+                3 => Err(AppError::Custom("✘ Disk full!".to_string())),
+                _ => Ok(()),
+            });
+        assert!(result.is_err());
+        assert!(stages.max_fetched_block.load(Ordering::SeqCst) < 1000);
+    }
+}
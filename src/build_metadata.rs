@@ -0,0 +1,203 @@
+use crate::constants::{HASHED_NULL_NODE, HASH_LENGTH};
+use crate::errors::AppError;
+use crate::gas_estimate::GasEstimate;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::network::get_detected_network;
+use crate::nibble_utils::Nibbles;
+use crate::proof_bundle::ProofBundle;
+use crate::rlp_codec::Decoder;
+use crate::secrets::redact_secrets;
+use crate::trie::Trie;
+use crate::trie_checkpoint::get_receipts_hash;
+use crate::trie_keys::ReceiptKey;
+use crate::trie_nodes::{get_node_from_database, rlp_decode_node, Node};
+use crate::types::{Byte, Bytes, Result};
+use crate::utils::{convert_bytes_to_h256, convert_h256_to_prefixed_hex};
+
+/**
+ * Build Metadata:
+ *
+ * A proof bundle alone says nothing of how it was made, so one which fails to
+ * verify, or differs from another made of the same receipt, can't be traced
+ * back to its cause. So full JSON bundles carry a `metadata` section: the
+ * version of the crate which made them, the chain id of the node it queried,
+ * a fingerprint of that node's endpoint, when the proof was made, & a digest
+ * of the raw receipt inputs. The fingerprint is the keccak hash of the
+ * endpoint w/ any secrets in it redacted, so it identifies the provider w/o
+ * leaking API keys. The digest is the keccak hash of the RLP list of the
+ * block's encoded receipts in tx index order, which is recomputed from the
+ * receipts trie's leaves, so tries served from the `--cache-dir` get one too.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildMetadata {
+    pub crate_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub endpoint_fingerprint: String,
+    pub timestamp: String,
+    pub receipts_digest: String,
+    // NOTE: Set per bundle once its proof is final, since it's the proof's gas, not the block's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<GasEstimate>,
+}
+
+pub fn get_endpoint_fingerprint(endpoint: &str) -> Result<String> {
+    convert_h256_to_prefixed_hex(keccak_hash_bytes(redact_secrets(endpoint).as_bytes())?)
+}
+
+fn get_key_bytes(path: &[Byte]) -> Result<Bytes> {
+    Ok(Nibbles::from_nibble_values(path)?.data)
+}
+
+fn get_tx_index_from_path(path: &[Byte]) -> Result<usize> {
+    Ok(Decoder::new(&get_key_bytes(path)?).as_val::<usize>()?)
+}
+
+// NOTE: Children shorter than a hash are nodes inlined in their parent rather than stored.
+fn get_child_node(trie: &Trie, child: &[Byte]) -> Result<Node> {
+    match child.len() == HASH_LENGTH {
+        false => rlp_decode_node(child.to_vec()),
+        true => {
+            let hash = convert_bytes_to_h256(child)?;
+            get_node_from_database(&trie.database, &hash)?.ok_or_else(|| {
+                AppError::Custom(format!(
+                    "✘ No node in trie w/ hash: {}",
+                    convert_h256_to_prefixed_hex(hash).unwrap_or_default()
+                ))
+            })
+        }
+    }
+}
+
+pub fn get_receipts_in_trie(trie: &Trie) -> Result<Vec<(usize, Bytes)>> {
+    let mut receipts = Vec::new();
+    let mut nodes_to_visit = match trie.root == HASHED_NULL_NODE {
+        true => vec![],
+        false => vec![(get_child_node(trie, trie.root.as_bytes())?, vec![])],
+    };
+    while let Some((node, path)) = nodes_to_visit.pop() {
+        match (node.get_leaf(), node.get_extension(), node.get_branch()) {
+            (Some(leaf), _, _) => {
+                let path = [path, leaf.path_nibbles.to_nibble_values()?].concat();
+                receipts.push((get_tx_index_from_path(&path)?, leaf.value.clone()));
+            }
+            (_, Some(extension), _) => {
+                let path = [path, extension.path_nibbles.to_nibble_values()?].concat();
+                nodes_to_visit.push((get_child_node(trie, &extension.value)?, path));
+            }
+            (_, _, Some(branch)) => {
+                if let Some(value) = &branch.value {
+                    receipts.push((get_tx_index_from_path(&path)?, value.clone()));
+                }
+                for (nibble, child) in branch.branches.iter().enumerate() {
+                    if let Some(child) = child {
+                        let child_path = [path.clone(), vec![nibble as Byte]].concat();
+                        nodes_to_visit.push((get_child_node(trie, child)?, child_path));
+                    }
+                }
+            }
+            _ => {
+                return Err(AppError::Custom(
+                    "✘ Cannot walk trie through an empty node!".to_string(),
+                ))
+            }
+        }
+    }
+    receipts.sort_by_key(|(index, _)| *index);
+    Ok(receipts)
+}
+
+pub fn get_receipts_digest(trie: &Trie) -> Result<String> {
+    get_receipts_in_trie(trie)?
+        .into_iter()
+        .map(|(index, receipt)| Ok((ReceiptKey::from_index(index)?, receipt)))
+        .collect::<Result<Vec<(ReceiptKey, Bytes)>>>()
+        .and_then(|key_value_tuples| get_receipts_hash(&key_value_tuples))
+}
+
+pub fn get_build_metadata(endpoint: &str, trie: &Trie) -> Result<BuildMetadata> {
+    Ok(BuildMetadata {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        chain_id: get_detected_network().map(|network| network.chain_id),
+        endpoint_fingerprint: get_endpoint_fingerprint(endpoint)?,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        receipts_digest: get_receipts_digest(trie)?,
+        gas_estimate: None,
+    })
+}
+
+pub fn set_build_metadata_in_proof_bundle(
+    bundle: ProofBundle,
+    maybe_metadata: &Option<BuildMetadata>,
+) -> ProofBundle {
+    match maybe_metadata {
+        Some(metadata) => ProofBundle {
+            metadata: Some(metadata.clone()),
+            ..bundle
+        },
+        None => bundle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
+    use crate::secrets::register_secret;
+    use crate::test_utils::{
+        get_sample_proof_bundle_1, get_sample_receipts, get_sample_trie_with_sample_receipts,
+        get_sample_tx_hashes_1, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    #[test]
+    fn should_get_receipts_digest_from_trie() {
+        let mut receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        let key_value_tuples = get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap();
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let receipts_in_trie = get_receipts_in_trie(&trie).unwrap();
+        assert!(receipts_in_trie.len() == key_value_tuples.len());
+        assert!(receipts_in_trie[1].1 == key_value_tuples[1].1);
+        assert!(
+            get_receipts_digest(&trie).unwrap() == get_receipts_hash(&key_value_tuples).unwrap()
+        );
+        let empty_trie = Trie::get_new_trie().unwrap();
+        assert!(get_receipts_in_trie(&empty_trie).unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_fingerprint_endpoint_w_secrets_redacted() {
+        let secret = "build-metadata-test-api-key";
+        let endpoint = format!("https://node.example.com/v3/{}", secret);
+        let fingerprint = get_endpoint_fingerprint(&endpoint).unwrap();
+        assert!(fingerprint.len() == 66);
+        register_secret(secret);
+        let redacted_fingerprint = get_endpoint_fingerprint(&endpoint).unwrap();
+        assert!(redacted_fingerprint != fingerprint);
+        assert!(
+            redacted_fingerprint == get_endpoint_fingerprint(&redact_secrets(&endpoint)).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_set_build_metadata_in_proof_bundle() {
+        let bundle = get_sample_proof_bundle_1();
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let metadata = get_build_metadata("http://localhost:8545", &trie).unwrap();
+        assert!(metadata.crate_version == env!("CARGO_PKG_VERSION"));
+        let result = set_build_metadata_in_proof_bundle(bundle.clone(), &Some(metadata.clone()));
+        assert!(result.metadata == Some(metadata));
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["metadata"]["receipts_digest"].is_string());
+        assert!(set_build_metadata_in_proof_bundle(bundle.clone(), &None) == bundle);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::errors::AppError;
+use crate::get_database::get_thing_from_database;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::types::{Database, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+use ethereum_types::H256;
+
+/**
+ * Byte Order Audit:
+ *
+ * Hashes handed over by other libraries sometimes arrive w/ their bytes
+ * reversed, e.g. from little-endian `U256` conversions, which otherwise only
+ * surfaces as a baffling "node not found" deep inside the trie. A lookup that
+ * misses ∴ also checks the byte-reversed key, & finding the thing there is
+ * an explicit error naming both keys. The thing itself is never returned, as
+ * it can't hash to the key asked for. In lenient mode, a trie merely warns of
+ * that error & carries on as if the lookup missed.
+ */
+pub fn reverse_h256_byte_order(hash: &H256) -> H256 {
+    let mut bytes = hash.to_fixed_bytes();
+    bytes.reverse();
+    H256::from(bytes)
+}
+
+// NOTE: A palindromic hash is its own reversal, so it's never considered reversed.
+pub fn is_byte_reversed(hash: &H256, other_hash: &H256) -> bool {
+    hash != other_hash && reverse_h256_byte_order(hash) == *other_hash
+}
+
+pub fn check_key_is_not_byte_reversed(database: &Database, key: &H256) -> Result<()> {
+    let reversed_key = reverse_h256_byte_order(key);
+    match get_thing_from_database(database, &reversed_key) {
+        Some(_) if reversed_key != *key => Err(AppError::Custom(format!(
+            "✘ No thing in database under key {}, but there is under its byte-reversed key {} - check the key's byte order!",
+            convert_h256_to_prefixed_hex(*key)?,
+            convert_h256_to_prefixed_hex(reversed_key)?,
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// NOTE: Only catches things keyed by their own hash, which is everything the trie stores.
+pub fn debug_assert_key_byte_order(key: &H256, value: &[u8]) {
+    if cfg!(debug_assertions) {
+        if let Ok(hash) = keccak_hash_bytes(value) {
+            debug_assert!(
+                !is_byte_reversed(&hash, key),
+                "✘ Database key {:?} is the byte-reversed hash of its value!",
+                key
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_database::{get_new_database, put_thing_in_database};
+    use crate::test_utils::get_thing_to_put_in_database;
+
+    fn get_database_with_thing_under_reversed_key() -> (Database, H256) {
+        let mut database = get_new_database().unwrap();
+        let thing = get_thing_to_put_in_database();
+        let key = keccak_hash_bytes(&thing).unwrap();
+        database.insert(reverse_h256_byte_order(&key), thing);
+        (database, key)
+    }
+
+    #[test]
+    fn should_reverse_h256_byte_order() {
+        let hash = H256::from_low_u64_be(1);
+        let result = reverse_h256_byte_order(&hash);
+        assert!(result.as_bytes()[0] == 1);
+        assert!(is_byte_reversed(&hash, &result));
+        assert!(!is_byte_reversed(&hash, &hash));
+        assert!(reverse_h256_byte_order(&result) == hash);
+    }
+
+    #[test]
+    fn should_report_byte_reversed_key() {
+        let (database, key) = get_database_with_thing_under_reversed_key();
+        match check_key_is_not_byte_reversed(&database, &key) {
+            Err(AppError::Custom(e)) => {
+                assert!(e.contains(&convert_h256_to_prefixed_hex(key).unwrap()));
+                assert!(e.contains(
+                    &convert_h256_to_prefixed_hex(reverse_h256_byte_order(&key)).unwrap()
+                ));
+            }
+            _ => panic!("Should report byte-reversed key!"),
+        }
+    }
+
+    #[test]
+    fn should_not_report_key_that_is_not_byte_reversed() {
+        let mut database = get_new_database().unwrap();
+        let thing = get_thing_to_put_in_database();
+        let key = keccak_hash_bytes(&thing).unwrap();
+        put_thing_in_database(&mut database, key, thing).unwrap();
+        assert!(check_key_is_not_byte_reversed(&database, &H256::zero()).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "byte-reversed hash")]
+    fn should_debug_assert_against_byte_reversed_keys() {
+        let thing = get_thing_to_put_in_database();
+        let key = reverse_h256_byte_order(&keccak_hash_bytes(&thing).unwrap());
+        debug_assert_key_byte_order(&key, &thing);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::errors::AppError;
+use crate::types::{Byte, Receipt, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+use clap::ValueEnum;
+
+pub const LEGACY_TX_TYPE: Byte = 0x00;
+pub const OPTIMISM_DEPOSIT_TX_TYPE: Byte = 0x7e;
+const MAX_ETHEREUM_TX_TYPE: Byte = 0x04;
+const ARBITRUM_TX_TYPES: [Byte; 7] = [0x64, 0x65, 0x66, 0x68, 0x69, 0x6a, 0x78];
+
+/**
+ * Chain Profiles:
+ *
+ * L2s add their own EIP-2718 transaction types to Ethereum's, some of whose
+ * receipts carry extra consensus fields. Optimism's deposit receipts append
+ * the deposit nonce & receipt version to the usual four fields. Arbitrum's
+ * receipts are encoded as Ethereum's are, but under its own tx types. A
+ * profile says which tx types are expected on a chain, so a receipt we'd
+ * otherwise encode wrongly fails loudly rather than via a bad receipts root.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChainProfile {
+    Mainnet,
+    Optimism,
+    Arbitrum,
+}
+
+impl ChainProfile {
+    pub fn from_chain_id(chain_id: u64) -> Option<ChainProfile> {
+        match chain_id {
+            1 | 3 | 4 | 5 | 42 | 17_000 | 11_155_111 => Some(ChainProfile::Mainnet),
+            10 | 11_155_420 => Some(ChainProfile::Optimism),
+            42_161 | 421_614 => Some(ChainProfile::Arbitrum),
+            _ => None,
+        }
+    }
+
+    pub fn is_supported_tx_type(&self, tx_type: Byte) -> bool {
+        tx_type <= MAX_ETHEREUM_TX_TYPE
+            || match self {
+                ChainProfile::Mainnet => false,
+                ChainProfile::Optimism => tx_type == OPTIMISM_DEPOSIT_TX_TYPE,
+                ChainProfile::Arbitrum => ARBITRUM_TX_TYPES.contains(&tx_type),
+            }
+    }
+
+    fn has_deposit_fields(&self, tx_type: Byte) -> bool {
+        *self == ChainProfile::Optimism && tx_type == OPTIMISM_DEPOSIT_TX_TYPE
+    }
+}
+
+pub fn apply_chain_profile_to_receipts(
+    receipts: Vec<Receipt>,
+    chain_profile: ChainProfile,
+) -> Result<Vec<Receipt>> {
+    receipts
+        .into_iter()
+        .map(|mut receipt| match chain_profile.is_supported_tx_type(receipt.tx_type) {
+            true => {
+                if !chain_profile.has_deposit_fields(receipt.tx_type) {
+                    receipt.deposit_nonce = None;
+                    receipt.deposit_receipt_version = None;
+                }
+                Ok(receipt)
+            }
+            false => Err(AppError::Custom(format!(
+                "✘ Receipt for tx {} has type 0x{:02x}, unsupported by the {:?} chain profile!\n✘ Try the `--chain` flag.",
+                convert_h256_to_prefixed_hex(receipt.transaction_hash)?,
+                receipt.tx_type,
+                chain_profile
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_expected_receipt;
+    use ethereum_types::U256;
+
+    fn get_sample_deposit_receipt() -> Receipt {
+        let mut receipt = get_expected_receipt();
+        receipt.tx_type = OPTIMISM_DEPOSIT_TX_TYPE;
+        receipt.deposit_nonce = Some(U256::from(1337));
+        receipt.deposit_receipt_version = Some(U256::one());
+        receipt
+    }
+
+    #[test]
+    fn should_support_ethereum_tx_types_on_all_chain_profiles() {
+        [
+            ChainProfile::Mainnet,
+            ChainProfile::Optimism,
+            ChainProfile::Arbitrum,
+        ]
+        .iter()
+        .for_each(|profile| {
+            (0..=MAX_ETHEREUM_TX_TYPE).for_each(|tx_type| {
+                assert!(profile.is_supported_tx_type(tx_type));
+            })
+        });
+    }
+
+    #[test]
+    fn should_only_support_l2_tx_types_on_their_chain_profiles() {
+        assert!(!ChainProfile::Mainnet.is_supported_tx_type(OPTIMISM_DEPOSIT_TX_TYPE));
+        assert!(!ChainProfile::Arbitrum.is_supported_tx_type(OPTIMISM_DEPOSIT_TX_TYPE));
+        assert!(ChainProfile::Optimism.is_supported_tx_type(OPTIMISM_DEPOSIT_TX_TYPE));
+        assert!(!ChainProfile::Optimism.is_supported_tx_type(0x6a));
+        assert!(ChainProfile::Arbitrum.is_supported_tx_type(0x6a));
+    }
+
+    #[test]
+    fn should_get_chain_profile_from_chain_id() {
+        assert!(ChainProfile::from_chain_id(1) == Some(ChainProfile::Mainnet));
+        assert!(ChainProfile::from_chain_id(10) == Some(ChainProfile::Optimism));
+        assert!(ChainProfile::from_chain_id(42_161) == Some(ChainProfile::Arbitrum));
+        assert!(ChainProfile::from_chain_id(1337).is_none());
+    }
+
+    #[test]
+    fn should_keep_deposit_fields_of_optimism_deposit_receipts() {
+        let receipts = vec![get_sample_deposit_receipt()];
+        let result = apply_chain_profile_to_receipts(receipts, ChainProfile::Optimism).unwrap();
+        assert!(result[0].deposit_nonce == Some(U256::from(1337)));
+        assert!(result[0].deposit_receipt_version == Some(U256::one()));
+    }
+
+    #[test]
+    fn should_fail_to_apply_mainnet_chain_profile_to_deposit_receipts() {
+        let receipts = vec![get_sample_deposit_receipt()];
+        match apply_chain_profile_to_receipts(receipts, ChainProfile::Mainnet) {
+            Err(AppError::Custom(e)) => assert!(e.contains("type 0x7e")),
+            _ => panic!("Should not apply mainnet chain profile to deposit receipts!"),
+        }
+    }
+}
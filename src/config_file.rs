@@ -0,0 +1,273 @@
+use crate::chain_profile::ChainProfile;
+use crate::constants::{
+    CONFIG_FILE_PATH, RPC_CONNECT_TIMEOUT_ENV_VAR, RPC_DOH_URL_ENV_VAR,
+    RPC_MAX_REQUESTS_PER_SECOND_ENV_VAR, RPC_NO_KEEP_ALIVE_ENV_VAR,
+    RPC_POOL_MAX_IDLE_PER_HOST_ENV_VAR, RPC_RESOLVE_ENV_VAR, RPC_TIMEOUT_ENV_VAR,
+};
+use crate::errors::AppError;
+use crate::get_endpoint::maybe_run_dot_env;
+use crate::hardfork::Hardfork;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::types::Result;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use std::fs;
+use std::path::Path;
+
+/**
+ * Config File:
+ *
+ * Operators deploying the tool declaratively, e.g. to k8s, would rather mount
+ * one TOML file than maintain a growing pile of flags. The file given via
+ * `--config`, else `./config.toml` if present, sets defaults for the endpoints,
+ * chain, cache dir, RPC client & output. Precedence is flags, then env vars
+ * (incl. those in `.env`), then the config file, then the built-in defaults.
+ * Listing several endpoints makes the first reachable one be used. Unknown
+ * keys are errors, so a typo doesn't silently fall back to a default.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcSettings {
+    pub timeout: Option<u64>,
+    pub connect_timeout: Option<u64>,
+    pub pool_max_idle_per_host: Option<usize>,
+    pub keep_alive: Option<bool>,
+    pub max_requests_per_second: Option<u32>,
+    pub resolve: Option<Vec<String>>,
+    pub doh_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSettings {
+    pub format: Option<OutputFormat>,
+    pub light: Option<bool>,
+    pub value_hash: Option<bool>,
+    pub compress_proofs: Option<bool>,
+    pub audit_log: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub endpoints: Vec<String>,
+    pub cross_check: Option<String>,
+    pub chain: Option<ChainProfile>,
+    pub hardfork: Option<Hardfork>,
+    pub cache_dir: Option<String>,
+    pub rpc: RpcSettings,
+    pub output: OutputSettings,
+}
+
+pub fn parse_config_file(toml_string: &str) -> Result<ConfigFile> {
+    toml::from_str(toml_string)
+        .map_err(|e| AppError::Custom(format!("✘ Error parsing config file!\n✘ {}", e)))
+}
+
+pub fn maybe_read_config_file(maybe_path: &Option<String>) -> Result<Option<ConfigFile>> {
+    let path = match maybe_path {
+        Some(path) => path.as_str(),
+        None if Path::new(CONFIG_FILE_PATH).exists() => CONFIG_FILE_PATH,
+        None => return Ok(None),
+    };
+    info!("✔ Reading config file: {}", path);
+    let toml_string = fs::read_to_string(path)
+        .map_err(|e| AppError::Custom(format!("✘ Error reading config file {}!\n✘ {}", path, e)))?;
+    parse_config_file(&toml_string).map(Some)
+}
+
+fn is_set_on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+fn is_env_var_set(env_var: &str) -> bool {
+    std::env::var(env_var).is_ok()
+}
+
+// NOTE: RPC settings fill in flags only where unset, & only if their env vars are unset too.
+fn get_rpc_setting<T>(cli_value: Option<T>, env_var: &str, config_value: Option<T>) -> Option<T> {
+    match (cli_value, is_env_var_set(env_var)) {
+        (Some(value), _) => Some(value),
+        (None, true) => None,
+        (None, false) => config_value,
+    }
+}
+
+fn get_flag_setting(matches: &ArgMatches, id: &str, cli_value: bool, config: Option<bool>) -> bool {
+    match is_set_on_command_line(matches, id) {
+        true => cli_value,
+        false => config.unwrap_or(cli_value),
+    }
+}
+
+pub fn apply_config_file_to_cli_args(
+    cli_args: CliArgs,
+    matches: &ArgMatches,
+    config_file: &ConfigFile,
+) -> Result<CliArgs> {
+    maybe_run_dot_env()?;
+    let rpc = &config_file.rpc;
+    let output = &config_file.output;
+    let cli_args = CliArgs {
+        chain: match (is_set_on_command_line(matches, "chain"), config_file.chain) {
+            (false, Some(chain)) => chain,
+            _ => cli_args.chain,
+        },
+        hardfork: cli_args.hardfork.or(config_file.hardfork),
+        cross_check: cli_args
+            .cross_check
+            .or_else(|| config_file.cross_check.clone()),
+        cache_dir: cli_args.cache_dir.or_else(|| config_file.cache_dir.clone()),
+        config_endpoints: config_file.endpoints.clone(),
+        timeout: get_rpc_setting(cli_args.timeout, RPC_TIMEOUT_ENV_VAR, rpc.timeout),
+        connect_timeout: get_rpc_setting(
+            cli_args.connect_timeout,
+            RPC_CONNECT_TIMEOUT_ENV_VAR,
+            rpc.connect_timeout,
+        ),
+        pool_max_idle_per_host: get_rpc_setting(
+            cli_args.pool_max_idle_per_host,
+            RPC_POOL_MAX_IDLE_PER_HOST_ENV_VAR,
+            rpc.pool_max_idle_per_host,
+        ),
+        no_keep_alive: cli_args.no_keep_alive
+            || (!is_env_var_set(RPC_NO_KEEP_ALIVE_ENV_VAR) && rpc.keep_alive == Some(false)),
+        max_requests_per_second: get_rpc_setting(
+            cli_args.max_requests_per_second,
+            RPC_MAX_REQUESTS_PER_SECOND_ENV_VAR,
+            rpc.max_requests_per_second,
+        ),
+        resolve: get_rpc_setting(
+            Some(cli_args.resolve).filter(|host_pins| !host_pins.is_empty()),
+            RPC_RESOLVE_ENV_VAR,
+            rpc.resolve.clone(),
+        )
+        .unwrap_or_default(),
+        doh_url: get_rpc_setting(cli_args.doh_url, RPC_DOH_URL_ENV_VAR, rpc.doh_url.clone()),
+        format: match (is_set_on_command_line(matches, "format"), output.format) {
+            (false, Some(format)) => format,
+            _ => cli_args.format,
+        },
+        light: get_flag_setting(matches, "light", cli_args.light, output.light),
+        value_hash: get_flag_setting(
+            matches,
+            "value_hash",
+            cli_args.value_hash,
+            output.value_hash,
+        ),
+        compress_proofs: get_flag_setting(
+            matches,
+            "compress_proofs",
+            cli_args.compress_proofs,
+            output.compress_proofs,
+        ),
+        audit_log: cli_args.audit_log.or_else(|| output.audit_log.clone()),
+        ..cli_args
+    };
+    match cli_args.light && cli_args.value_hash {
+        true => Err(AppError::Custom(
+            "✘ Light proof bundles carry no value, so can't carry its hash either!".to_string(),
+        )),
+        false => Ok(cli_args),
+    }
+}
+
+pub fn maybe_apply_config_file_to_cli_args(
+    cli_args: CliArgs,
+    matches: &ArgMatches,
+) -> Result<CliArgs> {
+    match maybe_read_config_file(&cli_args.config)? {
+        Some(config_file) => apply_config_file_to_cli_args(cli_args, matches, &config_file),
+        None => Ok(cli_args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    const SAMPLE_CONFIG_FILE: &str = r#"
+endpoints = ["http://localhost:8545", "https://mainnet.infura.io/v3/{API_KEY}"]
+chain = "optimism"
+cache_dir = "/var/cache/rrpm"
+
+[rpc]
+timeout = 30
+keep_alive = false
+resolve = ["mainnet.infura.io=203.0.113.7"]
+
+[output]
+format = "json"
+light = true
+"#;
+
+    fn get_cli_args_and_matches(flags: &[&str]) -> (CliArgs, ArgMatches) {
+        let mut args = vec!["rusty-receipt-proof-maker", "root", "latest"];
+        args.extend_from_slice(flags);
+        let matches = CliArgs::command().try_get_matches_from(args).unwrap();
+        (CliArgs::from_arg_matches(&matches).unwrap(), matches)
+    }
+
+    #[test]
+    fn should_parse_config_file() {
+        let result = parse_config_file(SAMPLE_CONFIG_FILE).unwrap();
+        assert!(result.endpoints.len() == 2);
+        assert!(result.chain == Some(ChainProfile::Optimism));
+        assert!(result.cache_dir == Some("/var/cache/rrpm".to_string()));
+        assert!(result.rpc.timeout == Some(30));
+        assert!(result.rpc.keep_alive == Some(false));
+        assert!(result.output.format == Some(OutputFormat::Json));
+        assert!(result.output.light == Some(true));
+        assert!(result.hardfork.is_none());
+    }
+
+    #[test]
+    fn should_fail_to_parse_config_file_w_unknown_key() {
+        match parse_config_file("endpiont = \"http://localhost:8545\"") {
+            Err(AppError::Custom(e)) => assert!(e.contains("unknown field")),
+            _ => panic!("Should fail w/ unknown key!"),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn should_apply_config_file_beneath_cli_flags() {
+        let config_file = parse_config_file(SAMPLE_CONFIG_FILE).unwrap();
+        let (cli_args, matches) = get_cli_args_and_matches(&["--format", "hex", "--timeout", "60"]);
+        let result = apply_config_file_to_cli_args(cli_args, &matches, &config_file).unwrap();
+        assert!(result.config_endpoints == config_file.endpoints);
+        assert!(result.chain == ChainProfile::Optimism);
+        assert!(result.cache_dir == Some("/var/cache/rrpm".to_string()));
+        assert!(result.format == OutputFormat::Hex);
+        assert!(result.light);
+        assert!(result.timeout == Some(60));
+        assert!(result.no_keep_alive);
+        assert!(result.resolve == vec!["mainnet.infura.io=203.0.113.7".to_string()]);
+    }
+
+    #[test]
+    #[serial]
+    fn should_apply_config_file_beneath_env_vars() {
+        let config_file = parse_config_file(SAMPLE_CONFIG_FILE).unwrap();
+        let (cli_args, matches) = get_cli_args_and_matches(&[]);
+        std::env::set_var(RPC_TIMEOUT_ENV_VAR, "90");
+        let result = apply_config_file_to_cli_args(cli_args, &matches, &config_file);
+        std::env::remove_var(RPC_TIMEOUT_ENV_VAR);
+        assert!(result.unwrap().timeout.is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn should_fail_to_apply_config_file_w_conflicting_output_settings() {
+        let config_file = parse_config_file("[output]\nlight = true\nvalue_hash = true").unwrap();
+        let (cli_args, matches) = get_cli_args_and_matches(&[]);
+        assert!(apply_config_file_to_cli_args(cli_args, &matches, &config_file).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_read_non_existent_config_file() {
+        let path = Some("./non-existent-config.toml".to_string());
+        assert!(maybe_read_config_file(&path).is_err());
+    }
+}
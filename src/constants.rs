@@ -5,17 +5,32 @@ use ethereum_types::H256;
 pub const ZERO_BYTE: u8 = 0u8;
 pub const HASH_LENGTH: usize = 32;
 pub const HASH_HEX_CHARS: usize = 64;
+pub const ADDRESS_HEX_CHARS: usize = 40;
 pub const HEX_PREFIX_LENGTH: usize = 2;
 pub const NUM_BITS_IN_NIBBLE: usize = 4;
 pub const REQWEST_TIMEOUT_TIME: u64 = 5;
 pub const NUM_NIBBLES_IN_BYTE: usize = 2;
 pub const HIGH_NIBBLE_MASK: Byte = 15u8; // NOTE: 15u8 == [0,0,0,0,1,1,1,1]
 pub static DOT_ENV_PATH: &str = "./.env";
-pub static LEAF_NODE_STRING: &str = "leaf";
-pub static BRANCH_NODE_STRING: &str = "branch";
-pub static EXTENSION_NODE_STRING: &str = "extension";
 pub const HASHED_NULL_NODE: H256 = H256(HASHED_NULL_NODE_BYTES);
 pub static DEFAULT_ENDPOINT: &str = "http://localhost:8545/";
+pub static API_KEY_ENV_VAR: &str = "API_KEY";
+pub static RPC_TIMEOUT_ENV_VAR: &str = "RPC_TIMEOUT";
+pub static RPC_CONNECT_TIMEOUT_ENV_VAR: &str = "RPC_CONNECT_TIMEOUT";
+pub static RPC_POOL_MAX_IDLE_PER_HOST_ENV_VAR: &str = "RPC_POOL_MAX_IDLE_PER_HOST";
+pub static RPC_NO_KEEP_ALIVE_ENV_VAR: &str = "RPC_NO_KEEP_ALIVE";
+pub static RPC_MAX_REQUESTS_PER_SECOND_ENV_VAR: &str = "RPC_MAX_REQUESTS_PER_SECOND";
+pub static RPC_RESOLVE_ENV_VAR: &str = "RPC_RESOLVE";
+pub static RPC_DOH_URL_ENV_VAR: &str = "RPC_DOH_URL";
+pub static CONFIG_FILE_PATH: &str = "./config.toml";
+pub static API_KEY_PLACEHOLDER: &str = "{API_KEY}";
+pub static REDACTED_STRING: &str = "<redacted>";
+pub const MIN_SECRET_LENGTH_IN_URL: usize = 16;
+pub const TRIE_SNAPSHOT_VERSION: u8 = 1;
+pub static MANIFEST_FILE_SUFFIX: &str = ".manifest.json";
+pub static RPC_ACCEPT_ENCODING: &str = "gzip, deflate";
+pub const AES_GCM_KEY_LENGTH: usize = 32;
+pub const AES_GCM_NONCE_LENGTH: usize = 12;
 pub const EMPTY_NIBBLES: Nibbles = Nibbles {
     data: Vec::new(),
     offset: 0,
@@ -0,0 +1,145 @@
+use crate::errors::AppError;
+use crate::get_block::get_block_by_number;
+use crate::get_receipts_root::check_receipts_roots_match;
+use crate::secrets::register_secrets_in_endpoint;
+use crate::state::State;
+use crate::trie::Trie;
+use crate::types::{Block, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+
+/**
+ * Cross Check:
+ *
+ * A single malicious or buggy endpoint could feed wrong receipts into the
+ * trie & so yield a proof of something that never happened. W/ a second,
+ * independent endpoint given via `--cross-check`, the header of every block
+ * proven is fetched from it too, & both must agree on the block's hash &
+ * receipts root. The trie built from the first endpoint's receipts must then
+ * match that agreed root before any proof is made from it. Near the chain tip,
+ * a reorg seen by only one of the two shows as a disagreement.
+ */
+pub fn check_blocks_agree(block: &Block, cross_check_block: &Block) -> Result<()> {
+    match (
+        block.hash == cross_check_block.hash,
+        block.receipts_root == cross_check_block.receipts_root,
+    ) {
+        (true, true) => {
+            info!("✔ Cross-check endpoint agrees on block {}!", block.number);
+            Ok(())
+        }
+        (hashes_agree, _) => Err(AppError::Custom(format!(
+            "✘ Cross-check endpoint disagrees on the {} of block {}!\n✘ Endpoint:    {}\n✘ Cross-check: {}",
+            if hashes_agree { "receipts root" } else { "hash" },
+            block.number,
+            convert_h256_to_prefixed_hex(match hashes_agree {
+                true => block.receipts_root,
+                false => block.hash,
+            })?,
+            convert_h256_to_prefixed_hex(match hashes_agree {
+                true => cross_check_block.receipts_root,
+                false => cross_check_block.hash,
+            })?,
+        ))),
+    }
+}
+
+pub fn cross_check_block(cross_check_endpoint: &str, block: &Block) -> Result<()> {
+    register_secrets_in_endpoint(cross_check_endpoint);
+    info!("✔ Cross-checking block {}...", block.number);
+    get_block_by_number(cross_check_endpoint, &block.number.to_string())
+        .and_then(|cross_check_block| check_blocks_agree(block, &cross_check_block))
+}
+
+pub fn maybe_cross_check_block(block: &Block, maybe_endpoint: &Option<String>) -> Result<()> {
+    match maybe_endpoint {
+        Some(endpoint) => cross_check_block(endpoint, block),
+        None => Ok(()),
+    }
+}
+
+pub fn maybe_cross_check_receipts_trie(
+    block: &Block,
+    trie: &Trie,
+    maybe_endpoint: &Option<String>,
+) -> Result<()> {
+    match maybe_endpoint {
+        Some(_) => check_receipts_roots_match(block, trie),
+        None => Ok(()),
+    }
+}
+
+pub fn maybe_cross_check_block_and_receipts_trie_in_state(state: State) -> Result<State> {
+    let block = state.get_block_from_state()?;
+    maybe_cross_check_block(block, &state.cross_check_endpoint)?;
+    maybe_cross_check_receipts_trie(
+        block,
+        state.get_receipts_trie_from_state()?,
+        &state.cross_check_endpoint,
+    )?;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{get_expected_block, SAMPLE_BLOCK_JSON_PATH};
+    use ethereum_types::H256;
+    use std::fs;
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    fn serve_block_json_once(block_json: String) -> (String, thread::JoinHandle<()>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            request.respond(Response::from_string(block_json)).unwrap();
+        });
+        (endpoint, handle)
+    }
+
+    #[test]
+    fn should_pass_cross_check_if_endpoints_agree() {
+        let block_json = fs::read_to_string(SAMPLE_BLOCK_JSON_PATH).unwrap();
+        let (endpoint, handle) = serve_block_json_once(block_json);
+        let result = cross_check_block(&endpoint, &get_expected_block());
+        handle.join().unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_fail_cross_check_if_endpoints_disagree_on_receipts_root() {
+        let block_json = fs::read_to_string(SAMPLE_BLOCK_JSON_PATH).unwrap();
+        let (endpoint, handle) = serve_block_json_once(block_json);
+        let mut block = get_expected_block();
+        block.receipts_root = H256::zero();
+        let result = cross_check_block(&endpoint, &block);
+        handle.join().unwrap();
+        match result {
+            Err(AppError::Custom(e)) => assert!(e.contains("disagrees on the receipts root")),
+            _ => panic!("Should fail cross-check w/ different receipts roots!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_cross_check_if_endpoints_disagree_on_block_hash() {
+        let block = get_expected_block();
+        let mut cross_check_block = block.clone();
+        cross_check_block.hash = H256::zero();
+        match check_blocks_agree(&block, &cross_check_block) {
+            Err(AppError::Custom(e)) => assert!(e.contains("disagrees on the hash")),
+            _ => panic!("Should fail cross-check w/ different block hashes!"),
+        }
+    }
+
+    #[test]
+    fn should_only_check_receipts_trie_root_w_a_cross_check_endpoint() {
+        let mut block = get_expected_block();
+        block.receipts_root = H256::zero();
+        let trie = Trie::get_new_trie().unwrap();
+        let endpoint = Some("http://localhost:8547".to_string());
+        assert!(maybe_cross_check_block(&block, &None).is_ok());
+        assert!(maybe_cross_check_receipts_trie(&block, &trie, &None).is_ok());
+        assert!(maybe_cross_check_receipts_trie(&block, &trie, &endpoint).is_err());
+    }
+}
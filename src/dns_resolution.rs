@@ -0,0 +1,203 @@
+use crate::errors::AppError;
+use crate::types::Result;
+use reqwest::header::ACCEPT;
+use reqwest::Url;
+use serde_json::Value as Json;
+use std::error::Error;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const DNS_JSON_MEDIA_TYPE: &str = "application/dns-json";
+const DNS_RECORD_TYPE_A: u64 = 1;
+const DNS_RECORD_TYPE_AAAA: u64 = 28;
+
+/**
+ * DNS Resolution:
+ *
+ * Where DNS is unreliable or hijack-prone, an endpoint's host can be pinned to
+ * an IP via `--resolve <host>=<ip>`, or resolved via DNS-over-HTTPS w/
+ * `--doh-url`, using the JSON API most public resolvers serve. Either way the
+ * RPC client connects to that IP whilst still sending the host name, so TLS
+ * certs are checked against the host as usual. A DoH answer is pinned for the
+ * rest of the run, ignoring its TTL. Lookups that fail outright are reported
+ * as such, rather than as generic HTTP errors midway through a batch.
+ */
+pub fn parse_host_pin(host_pin: &str) -> Result<(String, IpAddr)> {
+    match host_pin.split_once('=') {
+        Some((host, ip)) if !host.trim().is_empty() => ip
+            .trim()
+            .parse::<IpAddr>()
+            .map(|ip| (host.trim().to_lowercase(), ip))
+            .map_err(|_| {
+                AppError::Custom(format!("✘ Invalid IP address in host pin: {}", host_pin))
+            }),
+        _ => Err(AppError::Custom(format!(
+            "✘ Host pins must be of the form `<host>=<ip>`, not: {}",
+            host_pin
+        ))),
+    }
+}
+
+// NOTE: IP literals & `localhost` need no resolving, so only other domains are returned.
+pub fn get_endpoint_domain(endpoint: &str) -> Option<String> {
+    Url::parse(endpoint)
+        .ok()
+        .and_then(|url| url.domain().map(|domain| domain.to_lowercase()))
+        .filter(|domain| domain != "localhost")
+}
+
+pub fn parse_doh_response(doh_response: &Json, host: &str) -> Result<Vec<IpAddr>> {
+    match doh_response["Status"].as_u64() {
+        Some(0) => Ok(doh_response["Answer"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|answer| {
+                matches!(
+                    answer["type"].as_u64(),
+                    Some(DNS_RECORD_TYPE_A) | Some(DNS_RECORD_TYPE_AAAA)
+                )
+            })
+            .filter_map(|answer| answer["data"].as_str()?.parse::<IpAddr>().ok())
+            .collect()),
+        Some(status) => Err(AppError::Custom(format!(
+            "✘ DoH lookup of {} failed w/ DNS status {}!",
+            host, status
+        ))),
+        None => Err(AppError::Custom(format!(
+            "✘ Malformed DoH response for {}: {}",
+            host, doh_response
+        ))),
+    }
+}
+
+fn query_doh(
+    client: &reqwest::blocking::Client,
+    doh_url: &str,
+    host: &str,
+    record_type: &str,
+) -> Result<Vec<IpAddr>> {
+    let doh_response: Json = client
+        .get(doh_url)
+        .query(&[("name", host), ("type", record_type)])
+        .header(ACCEPT, DNS_JSON_MEDIA_TYPE)
+        .send()?
+        .error_for_status()?
+        .json()?;
+    parse_doh_response(&doh_response, host)
+}
+
+pub fn resolve_host_via_doh(doh_url: &str, host: &str, timeout: Duration) -> Result<IpAddr> {
+    info!("✔ Resolving {} via DoH at {}", host, doh_url);
+    let client = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()?;
+    let ips = match query_doh(&client, doh_url, host, "A")? {
+        ips if ips.is_empty() => query_doh(&client, doh_url, host, "AAAA")?,
+        ips => ips,
+    };
+    match ips.first() {
+        Some(ip) => {
+            info!("✔ Resolved {} to {} via DoH", host, ip);
+            Ok(*ip)
+        }
+        None => Err(AppError::Custom(format!(
+            "✘ DoH lookup of {} returned no addresses!",
+            host
+        ))),
+    }
+}
+
+pub fn is_dns_error(e: &reqwest::Error) -> bool {
+    let mut maybe_error: Option<&dyn Error> = Some(e);
+    while let Some(error) = maybe_error {
+        if error.to_string().starts_with("dns error") {
+            return true;
+        }
+        maybe_error = error.source();
+    }
+    false
+}
+
+pub fn get_dns_error(endpoint: &str) -> AppError {
+    AppError::Custom(format!(
+        "✘ Could not resolve the host of the RPC endpoint: {}\n✘ Pin its IP w/ `--resolve <host>=<ip>` or resolve it via `--doh-url <url>`.",
+        get_endpoint_domain(endpoint).unwrap_or_else(|| "unknown".to_string())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tiny_http::{Header, Response, Server};
+
+    #[test]
+    fn should_parse_host_pin() {
+        let result = parse_host_pin("Mainnet.Infura.io=203.0.113.7").unwrap();
+        assert!(
+            result
+                == (
+                    "mainnet.infura.io".to_string(),
+                    "203.0.113.7".parse().unwrap()
+                )
+        );
+        assert!(parse_host_pin("rpc.example.com=2001:db8::1").is_ok());
+    }
+
+    #[test]
+    fn should_fail_to_parse_malformed_host_pins() {
+        assert!(parse_host_pin("mainnet.infura.io").is_err());
+        assert!(parse_host_pin("=203.0.113.7").is_err());
+        assert!(parse_host_pin("mainnet.infura.io=not-an-ip").is_err());
+    }
+
+    #[test]
+    fn should_only_get_endpoint_domains_needing_resolution() {
+        let endpoint = "https://mainnet.infura.io/v3/abc";
+        assert!(get_endpoint_domain(endpoint) == Some("mainnet.infura.io".to_string()));
+        assert!(get_endpoint_domain("http://localhost:8545/").is_none());
+        assert!(get_endpoint_domain("http://127.0.0.1:8545/").is_none());
+    }
+
+    #[test]
+    fn should_parse_doh_response_skipping_cnames() {
+        let doh_response = json!({
+            "Status": 0,
+            "Answer": [
+                {"name": "rpc.example.com", "type": 5, "data": "lb.example.com."},
+                {"name": "lb.example.com", "type": 1, "data": "203.0.113.7"},
+            ]
+        });
+        let result = parse_doh_response(&doh_response, "rpc.example.com").unwrap();
+        assert!(result == vec!["203.0.113.7".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn should_fail_to_parse_doh_response_w_error_status() {
+        let doh_response = json!({"Status": 3});
+        match parse_doh_response(&doh_response, "rpc.example.invalid") {
+            Err(AppError::Custom(e)) => assert!(e.contains("DNS status 3")),
+            _ => panic!("Should fail w/ NXDOMAIN status!"),
+        }
+    }
+
+    #[test]
+    fn should_resolve_host_via_doh() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let doh_url = format!("http://{}/dns-query", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            assert!(request.url().contains("name=rpc.example.com"));
+            let body = json!({"Status": 0, "Answer": [{"type": 1, "data": "203.0.113.7"}]});
+            let header = Header::from_bytes("Content-Type", DNS_JSON_MEDIA_TYPE).unwrap();
+            request
+                .respond(Response::from_string(body.to_string()).with_header(header))
+                .unwrap();
+        });
+        let result =
+            resolve_host_via_doh(&doh_url, "rpc.example.com", Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        assert!(result == "203.0.113.7".parse::<IpAddr>().unwrap());
+    }
+}
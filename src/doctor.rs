@@ -0,0 +1,545 @@
+use crate::chain_profile::ChainProfile;
+use crate::errors::AppError;
+use crate::get_block::get_block_by_number;
+use crate::get_endpoint::get_endpoint;
+use crate::get_rpc_call_jsons::{
+    get_balance_json, get_proof_json, get_raw_receipts_json, get_transaction_receipt_json,
+};
+use crate::hardfork::{get_byzantium_block_number, get_chain_id};
+use crate::json_rpc_client::call_json_rpc;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::secrets::redact_secrets;
+use crate::types::Result;
+use crate::write_output::write_output;
+use serde_json::Value as Json;
+use std::fs;
+use std::path::Path;
+
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+const ZERO_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32601;
+const METHOD_NOT_FOUND_MESSAGES: [&str; 4] = [
+    "method not found",
+    "not supported",
+    "does not exist",
+    "not available",
+];
+// NOTE: Geth full nodes keep the state of the last 128 blocks only.
+const ARCHIVE_DEPTHS: [u64; 3] = [128, 10_000, 1_000_000];
+const MAX_CLOCK_SKEW_IN_SECONDS: u64 = 30;
+const MAX_HEAD_AGE_IN_SECONDS: u64 = 300;
+
+/**
+ * Doctor:
+ *
+ * Misconfigured environments otherwise surface as cryptic errors halfway
+ * through a pipeline. The doctor runs every check it can up front: that an
+ * endpoint is configured & reachable, what chain it serves, which of the
+ * RPC methods the tool relies on it supports, how far back its state goes,
+ * whether our clock agrees w/ its head, & that the output paths given are
+ * writable. Each failure or warning comes w/ a fix to try.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: &'static str, detail: String) -> Self {
+        DoctorCheck {
+            name,
+            status: CheckStatus::Pass,
+            detail,
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: String, fix: &str) -> Self {
+        DoctorCheck {
+            name,
+            status: CheckStatus::Warn,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: String, fix: &str) -> Self {
+        DoctorCheck {
+            name,
+            status: CheckStatus::Fail,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+fn get_one_line_error(error: &AppError) -> String {
+    error
+        .to_string()
+        .lines()
+        .map(|line| line.trim().trim_start_matches("✘ "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn get_rpc_response_json(endpoint: &str, rpc_json: Json) -> Result<Json> {
+    call_json_rpc(endpoint, rpc_json).and_then(|text| Ok(serde_json::from_str::<Json>(&text)?))
+}
+
+pub fn is_method_unsupported(response: &Json) -> bool {
+    let error = &response["error"];
+    error["code"].as_i64() == Some(METHOD_NOT_FOUND_ERROR_CODE)
+        || error["message"]
+            .as_str()
+            .map(|message| message.to_lowercase())
+            .map(|message| {
+                METHOD_NOT_FOUND_MESSAGES
+                    .iter()
+                    .any(|substring| message.contains(substring))
+            })
+            .unwrap_or(false)
+}
+
+pub fn check_chain_id(
+    chain_id: u64,
+    chain_profile: ChainProfile,
+    has_hardfork: bool,
+) -> DoctorCheck {
+    match ChainProfile::from_chain_id(chain_id) {
+        Some(expected_profile) if expected_profile != chain_profile => DoctorCheck::fail(
+            "chain id",
+            format!(
+                "Chain id {} expects the {:?} chain profile, but {:?} is in use",
+                chain_id, expected_profile, chain_profile
+            ),
+            &format!(
+                "Pass `--chain {}`",
+                format!("{:?}", expected_profile).to_lowercase()
+            ),
+        ),
+        None if get_byzantium_block_number(chain_id).is_none() && !has_hardfork => {
+            DoctorCheck::warn(
+                "chain id",
+                format!(
+                    "Chain id {} is unknown, so all its blocks are assumed post-Byzantium",
+                    chain_id
+                ),
+                "Pass `--hardfork pre-byzantium` when proving blocks older than its Byzantium fork",
+            )
+        }
+        _ => DoctorCheck::pass("chain id", format!("Chain id {}", chain_id)),
+    }
+}
+
+fn check_method_support(
+    endpoint: &str,
+    method: &'static str,
+    rpc_json: Json,
+    needed_by: &str,
+    is_required: bool,
+) -> DoctorCheck {
+    match get_rpc_response_json(endpoint, rpc_json) {
+        Ok(ref response) if is_method_unsupported(response) => {
+            let detail = format!(
+                "`{}` is unsupported, but is needed by {}",
+                method, needed_by
+            );
+            let fix =
+                "Use a node or provider serving the method, e.g. a self-hosted Geth or Erigon";
+            match is_required {
+                true => DoctorCheck::fail(method, detail, fix),
+                false => DoctorCheck::warn(method, detail, fix),
+            }
+        }
+        Ok(_) => DoctorCheck::pass(method, format!("`{}` is supported", method)),
+        Err(e) => DoctorCheck::fail(
+            method,
+            format!("Could not call `{}`: {}", method, get_one_line_error(&e)),
+            "Check the node is healthy & not rate limiting requests, then retry",
+        ),
+    }
+}
+
+fn check_methods_are_supported(endpoint: &str, head_block_number: u64) -> Result<Vec<DoctorCheck>> {
+    let latest = format!("0x{:x}", head_block_number);
+    Ok(vec![
+        check_method_support(
+            endpoint,
+            "eth_getTransactionReceipt",
+            get_transaction_receipt_json(ZERO_HASH)?,
+            "every receipt proof",
+            true,
+        ),
+        check_method_support(
+            endpoint,
+            "eth_getProof",
+            get_proof_json(ZERO_ADDRESS, "0x0", latest.clone())?,
+            "`prove-storage`",
+            false,
+        ),
+        check_method_support(
+            endpoint,
+            "debug_getRawReceipts",
+            get_raw_receipts_json(latest)?,
+            "`explain-root-mismatch`'s raw receipt diffing",
+            false,
+        ),
+    ])
+}
+
+fn is_state_available_at_block(endpoint: &str, block_number: u64) -> Result<bool> {
+    get_balance_json(ZERO_ADDRESS, format!("0x{:x}", block_number))
+        .and_then(|rpc_json| get_rpc_response_json(endpoint, rpc_json))
+        .map(|response| response["error"].is_null() && !response["result"].is_null())
+}
+
+pub fn check_archive_depth(
+    head_block_number: u64,
+    is_available_at_depth: impl Fn(u64) -> Result<bool>,
+) -> DoctorCheck {
+    let fix = "Use an archive node to prove storage in older blocks";
+    let mut deepest_available_depth = None;
+    // NOTE: Depths past genesis can't be checked, so young chains pass on the depths that can.
+    for depth in ARCHIVE_DEPTHS
+        .iter()
+        .filter(|depth| **depth <= head_block_number)
+    {
+        match is_available_at_depth(*depth) {
+            Ok(true) => deepest_available_depth = Some(*depth),
+            Ok(false) => {
+                return DoctorCheck::warn(
+                    "archive depth",
+                    match deepest_available_depth {
+                        None => format!(
+                            "State is unavailable {} blocks back: `prove-storage` only works on recent blocks",
+                            depth
+                        ),
+                        Some(available_depth) => format!(
+                            "State is available {} blocks back, but not {}",
+                            available_depth, depth
+                        ),
+                    },
+                    fix,
+                )
+            }
+            Err(e) => {
+                return DoctorCheck::fail(
+                    "archive depth",
+                    format!(
+                        "Could not get state {} blocks back: {}",
+                        depth,
+                        get_one_line_error(&e)
+                    ),
+                    "Check the node is healthy & not rate limiting requests, then retry",
+                )
+            }
+        }
+    }
+    DoctorCheck::pass(
+        "archive depth",
+        match deepest_available_depth {
+            None => "State is available back to genesis".to_string(),
+            Some(depth) => format!("State is available at least {} blocks back", depth),
+        },
+    )
+}
+
+pub fn check_clock_skew(head_timestamp: u64, now: u64) -> DoctorCheck {
+    if head_timestamp > now + MAX_CLOCK_SKEW_IN_SECONDS {
+        return DoctorCheck::fail(
+            "clock skew",
+            format!(
+                "The head block is {}s in the future: the local clock is behind",
+                head_timestamp - now
+            ),
+            "Sync the local clock, e.g. by enabling NTP",
+        );
+    }
+    match now.saturating_sub(head_timestamp) {
+        age if age > MAX_HEAD_AGE_IN_SECONDS => DoctorCheck::warn(
+            "clock skew",
+            format!(
+                "The head block is {}s old: the node may be syncing, or the local clock ahead",
+                age
+            ),
+            "Wait for the node to sync, or sync the local clock, e.g. by enabling NTP",
+        ),
+        age => DoctorCheck::pass("clock skew", format!("The head block is {}s old", age)),
+    }
+}
+
+fn get_unwritable_path_reason(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    if let Ok(metadata) = fs::metadata(path) {
+        return match metadata.is_dir() {
+            true => Some("is a directory".to_string()),
+            false if metadata.permissions().readonly() => Some("is read-only".to_string()),
+            false => None,
+        };
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match fs::metadata(parent) {
+        Err(_) => Some(format!("has no such directory as {}", parent.display())),
+        Ok(metadata) if metadata.permissions().readonly() => Some(format!(
+            "is in the read-only directory {}",
+            parent.display()
+        )),
+        Ok(_) => None,
+    }
+}
+
+pub fn check_paths_are_writable(paths: &[&Option<String>]) -> DoctorCheck {
+    let paths = paths
+        .iter()
+        .filter_map(|maybe_path| maybe_path.as_ref())
+        .collect::<Vec<&String>>();
+    let problems = paths
+        .iter()
+        .filter_map(|path| {
+            get_unwritable_path_reason(path).map(|reason| format!("{} {}", path, reason))
+        })
+        .collect::<Vec<String>>();
+    match (paths.is_empty(), problems.is_empty()) {
+        (true, _) => DoctorCheck::pass("paths", "No output paths given".to_string()),
+        (false, true) => DoctorCheck::pass(
+            "paths",
+            format!("{} output path(s) are writable", paths.len()),
+        ),
+        (false, false) => DoctorCheck::fail(
+            "paths",
+            problems.join("; "),
+            "Create the missing directories or fix their permissions, or choose other paths",
+        ),
+    }
+}
+
+fn check_node(cli_args: &CliArgs, endpoint: &str) -> Result<Vec<DoctorCheck>> {
+    let block = match get_block_by_number(endpoint, "latest") {
+        Ok(block) => block,
+        Err(e) => {
+            return Ok(vec![DoctorCheck::fail(
+                "reachability",
+                format!("Could not get the latest block: {}", get_one_line_error(&e)),
+                "Check the endpoint URL & that the node is up, then pass it via `--endpoint`",
+            )])
+        }
+    };
+    let head_block_number = block.number.as_u64();
+    let chain_id_check = match get_chain_id(endpoint) {
+        Ok(chain_id) => check_chain_id(chain_id, cli_args.chain, cli_args.hardfork.is_some()),
+        Err(e) => DoctorCheck::fail(
+            "chain id",
+            format!("Could not get the chain id: {}", get_one_line_error(&e)),
+            "Use a node or provider serving `eth_chainId`, or pass `--hardfork` to skip needing it",
+        ),
+    };
+    Ok([
+        vec![
+            DoctorCheck::pass(
+                "reachability",
+                format!("Reachable, w/ head block {}", head_block_number),
+            ),
+            chain_id_check,
+        ],
+        check_methods_are_supported(endpoint, head_block_number)?,
+        vec![
+            check_archive_depth(head_block_number, |depth| {
+                is_state_available_at_block(endpoint, head_block_number - depth)
+            }),
+            check_clock_skew(
+                block.timestamp.as_u64(),
+                chrono::Utc::now().timestamp() as u64,
+            ),
+        ],
+    ]
+    .concat())
+}
+
+fn get_doctor_checks(cli_args: &CliArgs) -> Result<Vec<DoctorCheck>> {
+    let node_checks = match get_endpoint(cli_args) {
+        Ok(endpoint) => [
+            vec![DoctorCheck::pass("endpoint", format!("Using {}", endpoint))],
+            check_node(cli_args, &endpoint)?,
+        ]
+        .concat(),
+        Err(e) => vec![DoctorCheck::fail(
+            "endpoint",
+            get_one_line_error(&e),
+            "Pass `--endpoint`, or set `ENDPOINT` in the environment or a `.env` file",
+        )],
+    };
+    Ok([
+        node_checks,
+        vec![check_paths_are_writable(&[
+            &cli_args.output,
+            &cli_args.audit_log,
+            &cli_args.telemetry_log,
+            &cli_args.metrics_file,
+        ])],
+    ]
+    .concat())
+}
+
+fn count_checks_with_status(checks: &[DoctorCheck], status: CheckStatus) -> usize {
+    checks.iter().filter(|check| check.status == status).count()
+}
+
+pub fn format_doctor_checks(checks: &[DoctorCheck], format: OutputFormat) -> Result<String> {
+    let num_failures = count_checks_with_status(checks, CheckStatus::Fail);
+    let num_warnings = count_checks_with_status(checks, CheckStatus::Warn);
+    let output = match format {
+        OutputFormat::Json | OutputFormat::Eip1186 => json!({
+            "checks": checks,
+            "healthy": num_failures == 0,
+        })
+        .to_string(),
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => checks
+            .iter()
+            .map(|check| {
+                let symbol = match check.status {
+                    CheckStatus::Pass => "✔",
+                    CheckStatus::Warn => "⚠",
+                    CheckStatus::Fail => "✘",
+                };
+                match &check.fix {
+                    Some(fix) => {
+                        format!("{} {}: {}\n  ➜ {}", symbol, check.name, check.detail, fix)
+                    }
+                    None => format!("{} {}: {}", symbol, check.name, check.detail),
+                }
+            })
+            .chain(std::iter::once(format!(
+                "{} check(s) failed, {} warning(s)",
+                num_failures, num_warnings
+            )))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+    Ok(redact_secrets(&output))
+}
+
+pub fn check_doctor_checks_passed(checks: &[DoctorCheck]) -> Result<()> {
+    match count_checks_with_status(checks, CheckStatus::Fail) {
+        0 => Ok(()),
+        num_failures => Err(AppError::Custom(format!(
+            "✘ {} doctor check(s) failed!",
+            num_failures
+        ))),
+    }
+}
+
+pub fn run_doctor(cli_args: &CliArgs) -> Result<String> {
+    let checks = get_doctor_checks(cli_args)?;
+    let output = format_doctor_checks(&checks, cli_args.format)?;
+    match check_doctor_checks_passed(&checks) {
+        Ok(_) => Ok(output),
+        Err(e) => write_output(&output, &cli_args.output).and(Err(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[test]
+    fn should_detect_unsupported_methods() {
+        let not_found = json!({"error": {"code": -32601, "message": "whatever"}});
+        let not_supported = json!({"error": {"code": -32000, "message": "Method Not Supported"}});
+        let bad_params = json!({"error": {"code": -32602, "message": "invalid argument 0"}});
+        let result = json!({"result": null});
+        assert!(is_method_unsupported(&not_found));
+        assert!(is_method_unsupported(&not_supported));
+        assert!(!is_method_unsupported(&bad_params));
+        assert!(!is_method_unsupported(&result));
+    }
+
+    #[test]
+    fn should_check_chain_id() {
+        let mainnet = check_chain_id(1, ChainProfile::Mainnet, false);
+        let wrong_profile = check_chain_id(10, ChainProfile::Mainnet, false);
+        let unknown = check_chain_id(1337, ChainProfile::Mainnet, false);
+        let unknown_w_hardfork = check_chain_id(1337, ChainProfile::Mainnet, true);
+        assert!(mainnet.status == CheckStatus::Pass);
+        assert!(wrong_profile.status == CheckStatus::Fail);
+        assert!(wrong_profile.fix == Some("Pass `--chain optimism`".to_string()));
+        assert!(unknown.status == CheckStatus::Warn);
+        assert!(unknown_w_hardfork.status == CheckStatus::Pass);
+    }
+
+    #[test]
+    fn should_check_archive_depth() {
+        let archive = check_archive_depth(20_000_000, |_| Ok(true));
+        let full = check_archive_depth(20_000_000, |depth| Ok(depth < 10_000));
+        let pruned = check_archive_depth(20_000_000, |_| Ok(false));
+        let young_archive = check_archive_depth(5_000, |_| Ok(true));
+        let erroring = check_archive_depth(20_000_000, |_| Err(AppError::Custom("✘ x".into())));
+        assert!(archive.status == CheckStatus::Pass);
+        assert!(full.status == CheckStatus::Warn);
+        assert!(full.detail.contains("128 blocks back, but not 10000"));
+        assert!(pruned.status == CheckStatus::Warn);
+        assert!(young_archive.status == CheckStatus::Pass);
+        assert!(erroring.status == CheckStatus::Fail);
+    }
+
+    #[test]
+    fn should_check_clock_skew() {
+        let now = 1_700_000_000;
+        assert!(check_clock_skew(now - 12, now).status == CheckStatus::Pass);
+        assert!(check_clock_skew(now + 5, now).status == CheckStatus::Pass);
+        assert!(check_clock_skew(now + 60, now).status == CheckStatus::Fail);
+        assert!(check_clock_skew(now - 3_600, now).status == CheckStatus::Warn);
+    }
+
+    #[test]
+    fn should_check_paths_are_writable() {
+        let dir = temp_dir();
+        let writable = Some(dir.join("doctor-output.json").display().to_string());
+        let directory = Some(dir.display().to_string());
+        let missing_dir = Some(dir.join("no-such-dir/out.json").display().to_string());
+        assert!(check_paths_are_writable(&[&None, &None]).status == CheckStatus::Pass);
+        assert!(check_paths_are_writable(&[&writable, &None]).status == CheckStatus::Pass);
+        let result = check_paths_are_writable(&[&writable, &directory, &missing_dir]);
+        assert!(result.status == CheckStatus::Fail);
+        assert!(result.detail.contains("is a directory"));
+        assert!(result.detail.contains("has no such directory"));
+    }
+
+    #[test]
+    fn should_format_doctor_checks() {
+        let checks = vec![
+            DoctorCheck::pass("endpoint", "Using http://localhost:8545/".to_string()),
+            DoctorCheck::fail("reachability", "Timed out".to_string(), "Check the node"),
+        ];
+        let hex = format_doctor_checks(&checks, OutputFormat::Hex).unwrap();
+        let lines: Vec<&str> = hex.lines().collect();
+        assert!(lines[0] == "✔ endpoint: Using http://localhost:8545/");
+        assert!(lines[1] == "✘ reachability: Timed out");
+        assert!(lines[2] == "  ➜ Check the node");
+        assert!(lines[3] == "1 check(s) failed, 0 warning(s)");
+        let json: Json =
+            serde_json::from_str(&format_doctor_checks(&checks, OutputFormat::Json).unwrap())
+                .unwrap();
+        assert!(json["healthy"] == false);
+        assert!(json["checks"][1]["status"] == "fail");
+        assert!(json["checks"][0].get("fix").is_none());
+        assert!(check_doctor_checks_passed(&checks).is_err());
+        assert!(check_doctor_checks_passed(&checks[..1]).is_ok());
+    }
+}
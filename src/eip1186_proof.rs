@@ -0,0 +1,129 @@
+use crate::errors::AppError;
+use crate::proof_bundle::ProofBundle;
+use crate::types::Result;
+
+/**
+ * EIP-1186 Proofs:
+ *
+ * Verifier libraries built for `eth_getProof` responses expect an object w/
+ * the trie's root & an array of `{ key, value, proof }` entries, the proof
+ * being the 0x-prefixed RLP encoded nodes from root to leaf. So w/ `--format
+ * eip1186`, receipt proofs are output in that shape, the receipts root in
+ * place of the storage root & the receipts' proofs in place of the storage
+ * slots', one entry per proven receipt. Unlike storage slots, receipts'
+ * keys aren't hashed, so the key is the trie path itself: the RLP encoded tx
+ * index. Quantities are hex, as in JSON-RPC responses.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip1186ReceiptProof {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
+    pub transaction_hash: String,
+    pub transaction_index: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip1186ReceiptsProof {
+    pub block_hash: String,
+    pub block_number: String,
+    pub receipts_root: String,
+    pub receipt_proof: Vec<Eip1186ReceiptProof>,
+}
+
+fn get_eip1186_receipt_proof(bundle: &ProofBundle) -> Result<Eip1186ReceiptProof> {
+    // NOTE: Verifiers need the receipt itself & uncompressed nodes to walk the proof.
+    match bundle.value.is_empty() || bundle.compressed {
+        true => Err(AppError::Custom(
+            "✘ EIP-1186 proofs need the receipt & uncompressed proof nodes!".to_string(),
+        )),
+        false => Ok(Eip1186ReceiptProof {
+            key: bundle.key.clone(),
+            value: bundle.value.clone(),
+            proof: bundle.proof.clone(),
+            transaction_hash: bundle.tx_hash.clone(),
+            transaction_index: format!("0x{:x}", bundle.tx_index),
+        }),
+    }
+}
+
+pub fn get_eip1186_receipts_proof(bundles: &[ProofBundle]) -> Result<Eip1186ReceiptsProof> {
+    let first_bundle = bundles.first().ok_or_else(|| {
+        AppError::Custom("✘ No receipt proofs to output in EIP-1186 format!".to_string())
+    })?;
+    if bundles
+        .iter()
+        .any(|bundle| bundle.receipts_root != first_bundle.receipts_root)
+    {
+        return Err(AppError::Custom(
+            "✘ EIP-1186 proofs can only hold receipt proofs against one receipts root!".to_string(),
+        ));
+    }
+    Ok(Eip1186ReceiptsProof {
+        block_hash: first_bundle.block_hash.clone(),
+        block_number: format!("0x{:x}", first_bundle.block_number),
+        receipts_root: first_bundle.receipts_root.clone(),
+        receipt_proof: bundles
+            .iter()
+            .map(get_eip1186_receipt_proof)
+            .collect::<Result<Vec<Eip1186ReceiptProof>>>()?,
+    })
+}
+
+pub fn format_proof_bundles_as_eip1186(bundles: &[ProofBundle]) -> Result<String> {
+    get_eip1186_receipts_proof(bundles)
+        .and_then(|eip1186_proof| Ok(serde_json::to_string_pretty(&eip1186_proof)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+    use crate::proof_bundle::compress_proof_in_proof_bundle;
+    use crate::prove_block::get_proof_bundles_for_all_receipts;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, PROOF_1_INDEX,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::convert_hex_strings_to_h256s;
+    use serde_json::Value as Json;
+
+    fn get_sample_proof_bundles() -> Vec<ProofBundle> {
+        let mut block = get_expected_block();
+        let tx_hashes = get_sample_tx_hashes_1();
+        let receipts =
+            get_sample_receipts(SAMPLE_RECEIPT_JSONS_1_PATH.to_string(), tx_hashes.clone());
+        block.transactions = convert_hex_strings_to_h256s(tx_hashes).unwrap();
+        let trie = get_receipts_trie_from_receipts(&receipts).unwrap();
+        get_proof_bundles_for_all_receipts(&block, &trie).unwrap()
+    }
+
+    #[test]
+    fn should_format_proof_bundles_in_eth_get_proof_shape() {
+        let bundles = get_sample_proof_bundles();
+        let bundle = &bundles[PROOF_1_INDEX];
+        let result: Json =
+            serde_json::from_str(&format_proof_bundles_as_eip1186(&bundles).unwrap()).unwrap();
+        assert!(result["receiptsRoot"] == json!(bundle.receipts_root));
+        assert!(result["blockNumber"] == json!(format!("0x{:x}", bundle.block_number)));
+        let entry = &result["receiptProof"][PROOF_1_INDEX];
+        assert!(result["receiptProof"].as_array().unwrap().len() == bundles.len());
+        assert!(entry["key"] == json!(bundle.key));
+        assert!(entry["value"] == json!(bundle.value));
+        assert!(entry["proof"] == json!(bundle.proof));
+        assert!(entry["transactionIndex"] == json!(format!("0x{:x}", PROOF_1_INDEX)));
+    }
+
+    #[test]
+    fn should_fail_to_get_eip1186_proof_of_compressed_proof_bundles() {
+        let bundles = get_sample_proof_bundles()
+            .into_iter()
+            .map(compress_proof_in_proof_bundle)
+            .collect::<Result<Vec<ProofBundle>>>()
+            .unwrap();
+        assert!(get_eip1186_receipts_proof(&bundles).is_err());
+        assert!(get_eip1186_receipts_proof(&[]).is_err());
+    }
+}
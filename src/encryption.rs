@@ -0,0 +1,250 @@
+use crate::constants::{AES_GCM_KEY_LENGTH, AES_GCM_NONCE_LENGTH};
+use crate::errors::AppError;
+use crate::parse_cli_args::{CliArgs, CliCommand};
+use crate::secrets::register_secret;
+use crate::types::{Bytes, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, decode_prefixed_hex};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use std::fs;
+use std::str::FromStr;
+
+pub enum Encryption {
+    Age(age::x25519::Recipient),
+    AesGcm(Bytes),
+}
+
+impl Encryption {
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String> {
+        match self {
+            Encryption::Age(recipient) => encrypt_with_age(recipient, plaintext),
+            Encryption::AesGcm(key) => encrypt_with_aes_gcm(key, plaintext),
+        }
+    }
+}
+
+pub enum Decryption {
+    Age(age::x25519::Identity),
+    AesGcm(Bytes),
+}
+
+impl Decryption {
+    pub fn decrypt(&self, ciphertext: &str) -> Result<Bytes> {
+        match self {
+            Decryption::Age(identity) => decrypt_with_age(identity, ciphertext),
+            Decryption::AesGcm(key) => decrypt_with_aes_gcm(key, ciphertext),
+        }
+    }
+}
+
+fn encrypt_with_age(recipient: &age::x25519::Recipient, plaintext: &[u8]) -> Result<String> {
+    age::encrypt_and_armor(recipient, plaintext)
+        .map_err(|e| AppError::Custom(format!("✘ Error encrypting with age: {}", e)))
+}
+
+fn decrypt_with_age(identity: &age::x25519::Identity, ciphertext: &str) -> Result<Bytes> {
+    age::decrypt(identity, ciphertext.trim().as_bytes())
+        .map_err(|e| AppError::Custom(format!("✘ Error decrypting with age: {}", e)))
+}
+
+fn get_aes_gcm_cipher(key: &[u8]) -> Result<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|_| {
+        AppError::Custom(format!(
+            "✘ AES-GCM key must be {} bytes long!",
+            AES_GCM_KEY_LENGTH
+        ))
+    })
+}
+
+fn encrypt_with_aes_gcm(key: &[u8], plaintext: &[u8]) -> Result<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    get_aes_gcm_cipher(key)?
+        .encrypt(&nonce, plaintext)
+        .map(|ciphertext| convert_bytes_to_prefixed_hex(&[nonce.to_vec(), ciphertext].concat()))
+        .map_err(|_| AppError::Custom("✘ Error encrypting with AES-GCM!".to_string()))
+}
+
+fn decrypt_with_aes_gcm(key: &[u8], ciphertext: &str) -> Result<Bytes> {
+    let bytes = decode_prefixed_hex(ciphertext.trim().to_string())?;
+    if bytes.len() < AES_GCM_NONCE_LENGTH {
+        return Err(AppError::Custom(
+            "✘ AES-GCM ciphertext is too short!".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = bytes.split_at(AES_GCM_NONCE_LENGTH);
+    get_aes_gcm_cipher(key)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            AppError::Custom(
+                "✘ Error decrypting with AES-GCM - wrong key or tampered ciphertext!".to_string(),
+            )
+        })
+}
+
+fn read_secret_from_file(path: &str) -> Result<String> {
+    // NOTE: Skips the comment lines `age-keygen` writes above the key.
+    match fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        Some(secret) => {
+            register_secret(secret);
+            Ok(secret.to_string())
+        }
+        None => Err(AppError::Custom(format!("✘ Key file is empty: {}", path))),
+    }
+}
+
+fn read_aes_gcm_key_from_file(path: &str) -> Result<Bytes> {
+    read_secret_from_file(path)
+        .and_then(decode_prefixed_hex)
+        .and_then(|key| match key.len() == AES_GCM_KEY_LENGTH {
+            true => Ok(key),
+            false => Err(AppError::Custom(format!(
+                "✘ AES-GCM key in {} must be {} bytes of hex!",
+                path, AES_GCM_KEY_LENGTH
+            ))),
+        })
+}
+
+fn read_age_identity_from_file(path: &str) -> Result<age::x25519::Identity> {
+    read_secret_from_file(path).and_then(|secret| {
+        age::x25519::Identity::from_str(&secret)
+            .map_err(|e| AppError::Custom(format!("✘ Invalid age identity in {}: {}", path, e)))
+    })
+}
+
+pub fn get_encryption(
+    encrypt_to: &Option<String>,
+    encryption_key_file: &Option<String>,
+) -> Result<Option<Encryption>> {
+    match (encrypt_to, encryption_key_file) {
+        (Some(_), Some(_)) => Err(AppError::Custom(
+            "✘ Cannot encrypt to an age recipient & with an AES-GCM key at once!".to_string(),
+        )),
+        (Some(recipient), None) => age::x25519::Recipient::from_str(recipient)
+            .map(|recipient| Some(Encryption::Age(recipient)))
+            .map_err(|e| AppError::Custom(format!("✘ Invalid age recipient {}: {}", recipient, e))),
+        (None, Some(path)) => {
+            read_aes_gcm_key_from_file(path).map(|key| Some(Encryption::AesGcm(key)))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+pub fn get_decryption(
+    identity_file: &Option<String>,
+    encryption_key_file: &Option<String>,
+) -> Result<Decryption> {
+    match (identity_file, encryption_key_file) {
+        (Some(path), _) => read_age_identity_from_file(path).map(Decryption::Age),
+        (None, Some(path)) => read_aes_gcm_key_from_file(path).map(Decryption::AesGcm),
+        (None, None) => Err(AppError::Custom(
+            "✘ Need an `--identity-file` or `--encryption-key-file` to decrypt with!".to_string(),
+        )),
+    }
+}
+
+pub fn maybe_encrypt_output(cli_args: &CliArgs, output: String) -> Result<String> {
+    if let CliCommand::Decrypt { .. } = cli_args.command {
+        return Ok(output);
+    };
+    match get_encryption(&cli_args.encrypt_to, &cli_args.encryption_key_file)? {
+        None => Ok(output),
+        Some(encryption) => {
+            info!("✔ Encrypting output...");
+            encryption.encrypt(output.as_bytes())
+        }
+    }
+}
+
+pub fn decrypt_file(
+    cli_args: &CliArgs,
+    path: &str,
+    identity_file: &Option<String>,
+) -> Result<String> {
+    info!("✔ Decrypting: {}", path);
+    get_decryption(identity_file, &cli_args.encryption_key_file)
+        .and_then(|decryption| decryption.decrypt(&fs::read_to_string(path)?))
+        .and_then(|plaintext| {
+            String::from_utf8(plaintext)
+                .map_err(|_| AppError::Custom("✘ Decrypted output is not valid UTF-8!".to_string()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use age::secrecy::ExposeSecret;
+
+    const SAMPLE_PLAINTEXT: &str = "{\"tx_index\":14}";
+    const SAMPLE_AES_GCM_KEY: &str =
+        "0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee0000";
+
+    fn write_sample_key_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-encryption-key-{}", name));
+        fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn should_encrypt_and_decrypt_with_age() {
+        let identity = age::x25519::Identity::generate();
+        let path = write_sample_key_file(
+            "age",
+            &format!(
+                "# public key: x\n{}\n",
+                identity.to_string().expose_secret()
+            ),
+        );
+        let encryption = get_encryption(&Some(identity.to_public().to_string()), &None)
+            .unwrap()
+            .unwrap();
+        let ciphertext = encryption.encrypt(SAMPLE_PLAINTEXT.as_bytes()).unwrap();
+        assert!(ciphertext.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert!(!ciphertext.contains("tx_index"));
+        let result = get_decryption(&Some(path.clone()), &None)
+            .unwrap()
+            .decrypt(&ciphertext)
+            .unwrap();
+        assert!(result == SAMPLE_PLAINTEXT.as_bytes());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_encrypt_and_decrypt_with_aes_gcm() {
+        let path = write_sample_key_file("aes-gcm", SAMPLE_AES_GCM_KEY);
+        let encryption = get_encryption(&None, &Some(path.clone())).unwrap().unwrap();
+        let ciphertext = encryption.encrypt(SAMPLE_PLAINTEXT.as_bytes()).unwrap();
+        assert!(ciphertext != encryption.encrypt(SAMPLE_PLAINTEXT.as_bytes()).unwrap());
+        let result = get_decryption(&None, &Some(path.clone()))
+            .unwrap()
+            .decrypt(&ciphertext)
+            .unwrap();
+        assert!(result == SAMPLE_PLAINTEXT.as_bytes());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_decrypt_with_wrong_key() {
+        let age_ciphertext = encrypt_with_age(
+            &age::x25519::Identity::generate().to_public(),
+            SAMPLE_PLAINTEXT.as_bytes(),
+        )
+        .unwrap();
+        let aes_gcm_ciphertext =
+            encrypt_with_aes_gcm(&[0xc0; AES_GCM_KEY_LENGTH], SAMPLE_PLAINTEXT.as_bytes()).unwrap();
+        assert!(decrypt_with_age(&age::x25519::Identity::generate(), &age_ciphertext).is_err());
+        assert!(decrypt_with_aes_gcm(&[0xee; AES_GCM_KEY_LENGTH], &aes_gcm_ciphertext).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_get_encryption_with_bad_recipient_or_key() {
+        let path = write_sample_key_file("short", "0xc0ffee");
+        assert!(get_encryption(&Some("age1c0ffee".to_string()), &None).is_err());
+        assert!(get_encryption(&None, &Some(path.clone())).is_err());
+        assert!(get_encryption(&None, &None).unwrap().is_none());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,15 +1,17 @@
+use crate::secrets::redact_secrets;
+use crate::verifier::VerifierError;
 use std::{error::Error, fmt};
 
 #[derive(Debug)]
 pub enum AppError {
     Custom(String),
     NoneError(String),
+    DuplicateTxInBlock(Vec<String>),
     IOError(std::io::Error),
     HexError(hex::FromHexError),
     ReqwestError(reqwest::Error),
     SerdeJsonError(serde_json::Error),
     SetLoggerError(log::SetLoggerError),
-    TermLogError(simplelog::TermLogError),
 }
 
 impl fmt::Display for AppError {
@@ -19,15 +21,18 @@ impl fmt::Display for AppError {
             AppError::HexError(ref e) => format!("✘ Hex Error!\n✘ {}", e),
             AppError::IOError(ref e) => format!("✘ I/O Error!\n✘ {}", e),
             AppError::NoneError(ref e) => format!("✘ Nothing to unwrap!\n✘ {:?}", e),
+            AppError::DuplicateTxInBlock(ref duplicates) => format!(
+                "✘ Duplicate tx(s) in block - the provider's response is corrupt!\n✘ {}",
+                duplicates.join("\n✘ ")
+            ),
             AppError::SerdeJsonError(ref e) => format!("✘ Serde-Json Error!\n✘ {}", e),
-            AppError::TermLogError(ref e) => format!("✘ Terminal logger error: {}", e),
             AppError::SetLoggerError(ref e) => format!("✘ Error setting up logger!\n✘ {}", e),
             AppError::ReqwestError(ref e) => format!(
                 "\n✘ HTTP Reqwest Error!\n✘ {}\n{}",
                 e, "✘ Please check your node & port settings and retry.\n"
             ),
         };
-        f.write_fmt(format_args!("{}", msg))
+        f.write_fmt(format_args!("{}", redact_secrets(&msg)))
     }
 }
 
@@ -67,8 +72,8 @@ impl From<log::SetLoggerError> for AppError {
     }
 }
 
-impl From<simplelog::TermLogError> for AppError {
-    fn from(e: simplelog::TermLogError) -> AppError {
-        AppError::TermLogError(e)
+impl From<VerifierError> for AppError {
+    fn from(e: VerifierError) -> AppError {
+        AppError::Custom(e.to_string())
     }
 }
@@ -0,0 +1,528 @@
+use crate::chain_profile::{apply_chain_profile_to_receipts, ChainProfile, LEGACY_TX_TYPE};
+use crate::get_block::get_block_by_block_id;
+use crate::get_endpoint::get_endpoint;
+use crate::get_receipts::{check_receipts_fields, get_receipts_from_tx_hashes};
+use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+use crate::get_rpc_call_jsons::get_raw_receipts_json;
+use crate::hardfork::{apply_hardfork_to_receipts, get_hardfork, Hardfork};
+use crate::json_rpc_client::call_json_rpc;
+use crate::make_rpc_call::check_response_text;
+use crate::ordered_trie::compute_ordered_root;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
+use crate::telemetry::{Telemetry, TelemetryEvent};
+use crate::types::{Block, Byte, Bytes, Receipt, Result};
+use crate::utils::{
+    convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex, decode_prefixed_hex,
+};
+use clap::ValueEnum;
+use ethereum_types::H256;
+use serde_json::Value as Json;
+use std::cmp::Reverse;
+
+const MAX_INDICES_TO_LIST: usize = 10;
+
+/**
+ * Root Mismatch Triage:
+ *
+ * A computed receipts root differing from the header's is almost always down
+ * to encoding the receipts under the wrong rules, rather than a bug in the
+ * trie. So we re-encode the receipts under every hardfork & chain profile,
+ * look for the usual suspects in the receipts themselves, & if the node will
+ * give us its raw receipts, diff our encodings against them one by one. Each
+ * finding is a likely cause, ranked by how strongly it explains the mismatch.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LikelyCause {
+    pub likelihood: u8,
+    pub cause: String,
+}
+
+impl LikelyCause {
+    fn new(likelihood: u8, cause: String) -> Self {
+        LikelyCause { likelihood, cause }
+    }
+}
+
+fn get_root_with_rules(
+    receipts: &[Receipt],
+    hardfork: Hardfork,
+    chain_profile: ChainProfile,
+) -> Result<H256> {
+    apply_hardfork_to_receipts(receipts.to_vec(), hardfork)
+        .and_then(|receipts| apply_chain_profile_to_receipts(receipts, chain_profile))
+        .and_then(|receipts| get_receipts_trie_from_receipts(&receipts))
+        .map(|trie| trie.root)
+}
+
+fn get_value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|possible_value| possible_value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn format_indices(indices: &[usize]) -> String {
+    let listed = indices
+        .iter()
+        .take(MAX_INDICES_TO_LIST)
+        .map(|index| index.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    match indices.len() > MAX_INDICES_TO_LIST {
+        true => format!("{}, ... ({} in all)", listed, indices.len()),
+        false => listed,
+    }
+}
+
+fn check_receipts_are_from_block(block: &Block, receipts: &[Receipt]) -> Vec<LikelyCause> {
+    let mut causes = Vec::new();
+    if receipts.len() != block.transactions.len() {
+        causes.push(LikelyCause::new(
+            90,
+            format!(
+                "Got {} receipts for a block of {} txs. The node may be missing receipts.",
+                receipts.len(),
+                block.transactions.len()
+            ),
+        ));
+    }
+    let foreign_indices: Vec<usize> = receipts
+        .iter()
+        .enumerate()
+        .filter(|(_, receipt)| receipt.block_hash != block.hash)
+        .map(|(index, _)| index)
+        .collect();
+    if !foreign_indices.is_empty() {
+        causes.push(LikelyCause::new(
+            85,
+            format!(
+                "Receipts at indices [{}] are from another block. The chain may have reorged mid-fetch, so retry.",
+                format_indices(&foreign_indices)
+            ),
+        ));
+    }
+    let misindexed_indices: Vec<usize> = receipts
+        .iter()
+        .enumerate()
+        .filter(|(index, receipt)| receipt.transaction_index.as_usize() != *index)
+        .map(|(index, _)| index)
+        .collect();
+    if !misindexed_indices.is_empty() {
+        causes.push(LikelyCause::new(
+            80,
+            format!(
+                "Receipts at indices [{}] claim a different tx index. The node's tx ordering is inconsistent.",
+                format_indices(&misindexed_indices)
+            ),
+        ));
+    }
+    causes
+}
+
+fn check_hardforks(
+    block: &Block,
+    receipts: &[Receipt],
+    hardfork: Hardfork,
+    chain_profile: ChainProfile,
+) -> Vec<LikelyCause> {
+    let mut causes: Vec<LikelyCause> = Hardfork::value_variants()
+        .iter()
+        .filter(|other_hardfork| **other_hardfork != hardfork)
+        .filter(|other_hardfork| {
+            get_root_with_rules(receipts, **other_hardfork, chain_profile).ok()
+                == Some(block.receipts_root)
+        })
+        .map(|other_hardfork| {
+            LikelyCause::new(
+                95,
+                format!(
+                    "Receipts match the header when encoded under {:?} rules. Try `--hardfork {}`.",
+                    other_hardfork,
+                    get_value_name(other_hardfork)
+                ),
+            )
+        })
+        .collect();
+    let num_with_root = receipts
+        .iter()
+        .filter(|receipt| receipt.root.is_some())
+        .count();
+    if num_with_root > 0 && num_with_root < receipts.len() {
+        causes.push(LikelyCause::new(
+            60,
+            format!(
+                "Only {} of {} receipts have a pre-Byzantium post-state root. The node mixes pre & post-Byzantium fields.",
+                num_with_root,
+                receipts.len()
+            ),
+        ));
+    }
+    causes
+}
+
+fn check_chain_profiles(
+    block: &Block,
+    receipts: &[Receipt],
+    hardfork: Hardfork,
+    chain_profile: ChainProfile,
+) -> Vec<LikelyCause> {
+    let mut causes: Vec<LikelyCause> = ChainProfile::value_variants()
+        .iter()
+        .filter(|other_profile| **other_profile != chain_profile)
+        .filter(|other_profile| {
+            get_root_with_rules(receipts, hardfork, **other_profile).ok()
+                == Some(block.receipts_root)
+        })
+        .map(|other_profile| {
+            LikelyCause::new(
+                90,
+                format!(
+                    "Receipts match the header under the {:?} chain profile. Try `--chain {}`.",
+                    other_profile,
+                    get_value_name(other_profile)
+                ),
+            )
+        })
+        .collect();
+    let mut unsupported_tx_types: Vec<Byte> = receipts
+        .iter()
+        .map(|receipt| receipt.tx_type)
+        .filter(|tx_type| !chain_profile.is_supported_tx_type(*tx_type))
+        .collect();
+    unsupported_tx_types.sort_unstable();
+    unsupported_tx_types.dedup();
+    if !unsupported_tx_types.is_empty() {
+        causes.push(LikelyCause::new(
+            75,
+            format!(
+                "Receipts have tx types {:02x?} unknown to the {:?} chain profile. Is this an L2?",
+                unsupported_tx_types, chain_profile
+            ),
+        ));
+    }
+    causes
+}
+
+fn check_typed_envelopes(
+    block: &Block,
+    receipts: &[Receipt],
+    hardfork: Hardfork,
+    chain_profile: ChainProfile,
+) -> Vec<LikelyCause> {
+    let typed_indices: Vec<usize> = receipts
+        .iter()
+        .enumerate()
+        .filter(|(_, receipt)| receipt.tx_type != LEGACY_TX_TYPE)
+        .map(|(index, _)| index)
+        .collect();
+    if typed_indices.is_empty() {
+        return vec![];
+    }
+    let untyped_receipts: Vec<Receipt> = receipts
+        .iter()
+        .cloned()
+        .map(|mut receipt| {
+            receipt.tx_type = LEGACY_TX_TYPE;
+            receipt
+        })
+        .collect();
+    match get_root_with_rules(&untyped_receipts, hardfork, chain_profile).ok()
+        == Some(block.receipts_root)
+    {
+        true => vec![LikelyCause::new(
+            95,
+            format!(
+                "Receipts match the header w/o their EIP-2718 type prefixes, so the node reports types for the {} typed receipts [{}] its chain doesn't envelope.",
+                typed_indices.len(),
+                format_indices(&typed_indices)
+            ),
+        )],
+        false => vec![LikelyCause::new(
+            20,
+            format!(
+                "{} receipts at indices [{}] are EIP-2718 typed. A wrongly reported type would cause a mismatch.",
+                typed_indices.len(),
+                format_indices(&typed_indices)
+            ),
+        )],
+    }
+}
+
+fn check_raw_receipts(
+    block: &Block,
+    receipts: &[Receipt],
+    hardfork: Hardfork,
+    chain_profile: ChainProfile,
+    maybe_raw_receipts: &Option<Vec<Bytes>>,
+) -> Result<Vec<LikelyCause>> {
+    let raw_receipts = match maybe_raw_receipts {
+        Some(raw_receipts) => raw_receipts,
+        None => {
+            return Ok(vec![LikelyCause::new(
+                0,
+                "Node did not serve raw receipts, so receipts weren't compared one by one. Use a node supporting `debug_getRawReceipts` to do so.".to_string(),
+            )])
+        }
+    };
+    if compute_ordered_root(raw_receipts)? != block.receipts_root {
+        return Ok(vec![LikelyCause::new(
+            70,
+            "The node's own raw receipts don't match the header either. Its receipts data is inconsistent, so try another node.".to_string(),
+        )]);
+    }
+    let encoded_receipts = apply_hardfork_to_receipts(receipts.to_vec(), hardfork)
+        .and_then(|receipts| apply_chain_profile_to_receipts(receipts, chain_profile))
+        .and_then(|receipts| get_rlp_encoded_receipts_and_key_tuples(&receipts))?;
+    let differing_indices: Vec<usize> = (0..raw_receipts.len().max(encoded_receipts.len()))
+        .filter(|index| {
+            raw_receipts.get(*index) != encoded_receipts.get(*index).map(|(_, encoded)| encoded)
+        })
+        .collect();
+    Ok(match differing_indices.first() {
+        None => vec![],
+        Some(first_index) => vec![LikelyCause::new(
+            100,
+            format!(
+                "Re-encoded receipts differ from the node's raw receipts at indices [{}]. At index {} we encode {} but the node has {}.",
+                format_indices(&differing_indices),
+                first_index,
+                encoded_receipts
+                    .get(*first_index)
+                    .map(|(_, encoded)| convert_bytes_to_prefixed_hex(encoded))
+                    .unwrap_or_else(|| "nothing".to_string()),
+                raw_receipts
+                    .get(*first_index)
+                    .map(|raw| convert_bytes_to_prefixed_hex(raw))
+                    .unwrap_or_else(|| "nothing".to_string()),
+            ),
+        )],
+    })
+}
+
+pub fn get_likely_causes_of_root_mismatch(
+    block: &Block,
+    receipts: &[Receipt],
+    hardfork: Hardfork,
+    chain_profile: ChainProfile,
+    maybe_raw_receipts: &Option<Vec<Bytes>>,
+) -> Result<Vec<LikelyCause>> {
+    if get_root_with_rules(receipts, hardfork, chain_profile).ok() == Some(block.receipts_root) {
+        return Ok(vec![]);
+    }
+    let mut causes = [
+        check_receipts_are_from_block(block, receipts),
+        check_hardforks(block, receipts, hardfork, chain_profile),
+        check_chain_profiles(block, receipts, hardfork, chain_profile),
+        check_typed_envelopes(block, receipts, hardfork, chain_profile),
+        check_raw_receipts(block, receipts, hardfork, chain_profile, maybe_raw_receipts)?,
+    ]
+    .concat();
+    causes.sort_by_key(|cause| Reverse(cause.likelihood));
+    Ok(causes)
+}
+
+pub fn get_raw_receipts(
+    endpoint: &str,
+    block: &Block,
+    telemetry: &Telemetry,
+) -> Option<Vec<Bytes>> {
+    info!("✔ Getting raw receipts from node...");
+    get_raw_receipts_json(format!("0x{:x}", block.number))
+        .and_then(|rpc_json| call_json_rpc(endpoint, rpc_json))
+        .and_then(check_response_text)
+        .and_then(|text| Ok(serde_json::from_str::<Json>(&text)?))
+        .ok()
+        .and_then(|json| {
+            json["result"]
+                .as_array()?
+                .iter()
+                .map(|raw_receipt| decode_prefixed_hex(raw_receipt.as_str()?.to_string()).ok())
+                .collect()
+        })
+        .or_else(|| {
+            warn!("✘ Could not get raw receipts from node!");
+            telemetry.emit_telemetry_event(TelemetryEvent::RpcFallbackUsed {
+                fallback: "no_raw_receipts",
+            });
+            None
+        })
+}
+
+pub fn format_likely_causes(
+    block: &Block,
+    maybe_computed_root: Option<H256>,
+    causes: &[LikelyCause],
+    format: OutputFormat,
+) -> Result<String> {
+    let matches = maybe_computed_root == Some(block.receipts_root);
+    Ok(match format {
+        OutputFormat::Json | OutputFormat::Eip1186 => json!({
+            "block_number": block.number.as_u64(),
+            "block_hash": convert_h256_to_prefixed_hex(block.hash)?,
+            "computed_receipts_root": maybe_computed_root
+                .map(convert_h256_to_prefixed_hex)
+                .transpose()?,
+            "header_receipts_root": convert_h256_to_prefixed_hex(block.receipts_root)?,
+            "matches": matches,
+            "likely_causes": causes,
+        })
+        .to_string(),
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz if matches => format!(
+            "✔ Computed receipts root matches the header of block {}: nothing to explain!",
+            block.number
+        ),
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => causes
+            .iter()
+            .enumerate()
+            .map(|(i, cause)| format!("{}. [{:>3}%] {}", i + 1, cause.likelihood, cause.cause))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    })
+}
+
+pub fn explain_root_mismatch(cli_args: &CliArgs, block_id: &str) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    let block = get_block_by_block_id(&endpoint, block_id)?;
+    let hardfork = get_hardfork(&endpoint, &cli_args.hardfork, block.number)?;
+    let receipts =
+        get_receipts_from_tx_hashes(&endpoint, &block.transactions, &cli_args.batch_tuner)
+            .and_then(|receipts| check_receipts_fields(receipts, cli_args.strict_receipts))?;
+    // NOTE: Receipts we can't even encode under the current rules are a mismatch to triage too.
+    let maybe_computed_root = get_root_with_rules(&receipts, hardfork, cli_args.chain).ok();
+    let causes = match maybe_computed_root == Some(block.receipts_root) {
+        true => vec![],
+        false => {
+            info!("✔ Receipts root mismatch: triaging likely causes...");
+            get_likely_causes_of_root_mismatch(
+                &block,
+                &receipts,
+                hardfork,
+                cli_args.chain,
+                &get_raw_receipts(&endpoint, &block, &cli_args.telemetry),
+            )?
+        }
+    };
+    format_likely_causes(&block, maybe_computed_root, &causes, cli_args.format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_profile::OPTIMISM_DEPOSIT_TX_TYPE;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, RECEIPTS_ROOT_1,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::convert_hex_to_h256;
+    use ethereum_types::U256;
+
+    fn get_sample_block_and_receipts() -> (Block, Vec<Receipt>) {
+        let mut receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        let mut block = get_expected_block();
+        block.hash = receipts[0].block_hash;
+        block.receipts_root = convert_hex_to_h256(RECEIPTS_ROOT_1.to_string()).unwrap();
+        block.transactions = receipts
+            .iter()
+            .map(|receipt| receipt.transaction_hash)
+            .collect();
+        (block, receipts)
+    }
+
+    fn get_causes(
+        block: &Block,
+        receipts: &[Receipt],
+        raw: &Option<Vec<Bytes>>,
+    ) -> Vec<LikelyCause> {
+        get_likely_causes_of_root_mismatch(
+            block,
+            receipts,
+            Hardfork::Byzantium,
+            ChainProfile::Mainnet,
+            raw,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn should_find_no_likely_causes_for_matching_root() {
+        let (block, receipts) = get_sample_block_and_receipts();
+        let computed_root =
+            get_root_with_rules(&receipts, Hardfork::Byzantium, ChainProfile::Mainnet).unwrap();
+        assert!(computed_root == block.receipts_root);
+        let result = get_causes(&block, &receipts, &None);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn should_rank_wrong_chain_profile_as_likely_cause() {
+        let (mut block, mut receipts) = get_sample_block_and_receipts();
+        receipts[0].tx_type = OPTIMISM_DEPOSIT_TX_TYPE;
+        receipts[0].deposit_nonce = Some(U256::from(1337));
+        receipts[0].deposit_receipt_version = Some(U256::one());
+        block.receipts_root =
+            get_root_with_rules(&receipts, Hardfork::Byzantium, ChainProfile::Optimism).unwrap();
+        let result = get_causes(&block, &receipts, &None);
+        assert!(result[0].cause.contains("`--chain optimism`"));
+        assert!(result.iter().any(|cause| cause.cause.contains("[7e]")));
+    }
+
+    #[test]
+    fn should_rank_wrong_hardfork_as_likely_cause() {
+        let (mut block, mut receipts) = get_sample_block_and_receipts();
+        receipts
+            .iter_mut()
+            .for_each(|receipt| receipt.root = Some(receipt.block_hash));
+        block.receipts_root =
+            get_root_with_rules(&receipts, Hardfork::PreByzantium, ChainProfile::Mainnet).unwrap();
+        let result = get_causes(&block, &receipts, &None);
+        assert!(result[0].cause.contains("`--hardfork pre-byzantium`"));
+    }
+
+    #[test]
+    fn should_rank_receipts_from_another_block_as_likely_cause() {
+        let (block, mut receipts) = get_sample_block_and_receipts();
+        receipts[3].block_hash = H256::zero();
+        receipts[3].cumulative_gas_used = U256::zero();
+        let result = get_causes(&block, &receipts, &None);
+        assert!(result[0]
+            .cause
+            .contains("indices [3] are from another block"));
+    }
+
+    #[test]
+    fn should_pinpoint_receipts_differing_from_raw_receipts() {
+        let (mut block, receipts) = get_sample_block_and_receipts();
+        let mut raw_receipts: Vec<Bytes> = get_rlp_encoded_receipts_and_key_tuples(&receipts)
+            .unwrap()
+            .into_iter()
+            .map(|(_, encoded)| encoded)
+            .collect();
+        raw_receipts[2].insert(0, 0x02);
+        block.receipts_root = compute_ordered_root(&raw_receipts).unwrap();
+        let result = get_causes(&block, &receipts, &Some(raw_receipts));
+        assert!(result[0].likelihood == 100);
+        assert!(result[0].cause.contains("indices [2]"));
+    }
+
+    #[test]
+    fn should_format_likely_causes_as_ranked_list() {
+        let (block, _) = get_sample_block_and_receipts();
+        let causes = vec![
+            LikelyCause::new(95, "Wrong hardfork".to_string()),
+            LikelyCause::new(20, "Typed receipts".to_string()),
+        ];
+        let result = format_likely_causes(&block, None, &causes, OutputFormat::Hex).unwrap();
+        assert!(result == "1. [ 95%] Wrong hardfork\n2. [ 20%] Typed receipts");
+        let json: Json = serde_json::from_str(
+            &format_likely_causes(&block, Some(H256::zero()), &causes, OutputFormat::Json).unwrap(),
+        )
+        .unwrap();
+        assert!(json["matches"] == false);
+        assert!(json["likely_causes"][0]["likelihood"] == 95);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::errors::AppError;
+use crate::get_block::{check_block_is_canonical, get_block_by_number};
+use crate::parse_cli_args::CliArgs;
+use crate::proof_bundle::ProofBundle;
+use crate::proof_expiry::get_chain_head_block_number;
+use crate::types::{Block, Result};
+
+/**
+ * Block Finality:
+ *
+ * Proofs of blocks that later reorg out are worthless, & relayers submitting
+ * them have been burned. So w/ `--min-confirmations` or `--require-finalized`,
+ * the proven block is first checked to still be canonical, i.e. to be the
+ * node's block at its height, then its confirmations are counted from the
+ * chain head & it's compared against the node's `finalized` block, failing
+ * the proof if either requirement isn't met. The finality found is embedded
+ * in full JSON proof bundles, so consumers needn't take it on trust that the
+ * requirements were checked.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finality {
+    pub confirmations: u64,
+    pub finalized: bool,
+    pub finalized_block_number: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FinalityRequirements {
+    pub min_confirmations: Option<u64>,
+    pub require_finalized: bool,
+}
+
+impl FinalityRequirements {
+    pub fn from_cli_args(cli_args: &CliArgs) -> Self {
+        Self {
+            min_confirmations: cli_args.min_confirmations,
+            require_finalized: cli_args.require_finalized,
+        }
+    }
+
+    fn is_required(&self) -> bool {
+        self.min_confirmations.is_some() || self.require_finalized
+    }
+}
+
+pub fn get_finality(
+    block_number: u64,
+    head_block_number: u64,
+    finalized_block_number: u64,
+) -> Finality {
+    Finality {
+        confirmations: head_block_number.saturating_sub(block_number),
+        finalized: block_number <= finalized_block_number,
+        finalized_block_number,
+    }
+}
+
+pub fn check_finality(
+    finality: &Finality,
+    requirements: &FinalityRequirements,
+    block_number: u64,
+) -> Result<()> {
+    if let Some(min_confirmations) = requirements.min_confirmations {
+        if finality.confirmations < min_confirmations {
+            return Err(AppError::Custom(format!(
+                "✘ Block {} has {} confirmations, but at least {} are required!",
+                block_number, finality.confirmations, min_confirmations
+            )));
+        }
+    }
+    match requirements.require_finalized && !finality.finalized {
+        true => Err(AppError::Custom(format!(
+            "✘ Block {} is not yet finalized, the node's finalized block being {}!",
+            block_number, finality.finalized_block_number
+        ))),
+        false => Ok(()),
+    }
+}
+
+pub fn get_finalized_block_number(endpoint: &str) -> Result<u64> {
+    get_block_by_number(endpoint, "finalized")
+        .map(|block| block.number.as_u64())
+        .map_err(|e| {
+            AppError::Custom(format!(
+                "✘ Could not get the node's `finalized` block, does it support the tag?\n{}",
+                e
+            ))
+        })
+}
+
+pub fn get_block_finality(endpoint: &str, block: &Block) -> Result<Finality> {
+    check_block_is_canonical(endpoint, block)?;
+    let finalized_block_number = get_finalized_block_number(endpoint)?;
+    let head_block_number = get_chain_head_block_number(endpoint)?;
+    Ok(get_finality(
+        block.number.as_u64(),
+        head_block_number,
+        finalized_block_number,
+    ))
+}
+
+pub fn maybe_check_block_finality(
+    endpoint: &str,
+    block: &Block,
+    cli_args: &CliArgs,
+) -> Result<Option<Finality>> {
+    let requirements = FinalityRequirements::from_cli_args(cli_args);
+    if !requirements.is_required() {
+        return Ok(None);
+    }
+    let finality = get_block_finality(endpoint, block)?;
+    check_finality(&finality, &requirements, block.number.as_u64())?;
+    info!(
+        "✔ Block {} has {} confirmations & is {}finalized",
+        block.number,
+        finality.confirmations,
+        if finality.finalized { "" } else { "not yet " }
+    );
+    Ok(Some(finality))
+}
+
+pub fn set_finality_in_proof_bundle(
+    bundle: ProofBundle,
+    maybe_finality: &Option<Finality>,
+) -> ProofBundle {
+    match maybe_finality {
+        Some(finality) => ProofBundle {
+            finality: Some(*finality),
+            ..bundle
+        },
+        None => bundle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    #[test]
+    fn should_get_finality() {
+        let result = get_finality(100, 112, 100);
+        assert!(result.confirmations == 12);
+        assert!(result.finalized);
+        assert!(!get_finality(101, 112, 100).finalized);
+    }
+
+    #[test]
+    fn should_check_finality_against_requirements() {
+        let finality = get_finality(100, 105, 90);
+        let requirements = |min_confirmations, require_finalized| FinalityRequirements {
+            min_confirmations,
+            require_finalized,
+        };
+        assert!(check_finality(&finality, &requirements(Some(5), false), 100).is_ok());
+        match check_finality(&finality, &requirements(Some(6), false), 100) {
+            Err(AppError::Custom(e)) => assert!(e.contains("has 5 confirmations")),
+            _ => panic!("Should fail w/ too few confirmations!"),
+        }
+        match check_finality(&finality, &requirements(None, true), 100) {
+            Err(AppError::Custom(e)) => assert!(e.contains("not yet finalized")),
+            _ => panic!("Should fail w/ unfinalized block!"),
+        }
+    }
+
+    #[test]
+    fn should_set_finality_in_proof_bundle() {
+        let bundle = get_sample_proof_bundle_1();
+        let finality = get_finality(100, 112, 100);
+        let result = set_finality_in_proof_bundle(bundle.clone(), &Some(finality));
+        assert!(result.finality == Some(finality));
+        assert!(set_finality_in_proof_bundle(bundle.clone(), &None) == bundle);
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["finality"]["confirmations"] == 12 && json["finality"]["finalized"] == true);
+    }
+}
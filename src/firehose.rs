@@ -0,0 +1,518 @@
+use crate::binary_envelope::maybe_compress_binary_proof_output;
+use crate::binary_proof::format_proof_bundles_as_binary;
+use crate::chain_profile::apply_chain_profile_to_receipts;
+use crate::eip1186_proof::format_proof_bundles_as_eip1186;
+use crate::errors::AppError;
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::get_receipts_root::check_receipts_roots_match;
+use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+use crate::hardfork::apply_hardfork_to_receipts;
+use crate::logs_bloom::{convert_bytes_to_bloom, get_verified_logs_bloom_from_logs};
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::proof_bundle::{
+    get_proof_bundle, get_proof_bundle_json, maybe_compress_proof_in_proof_bundle,
+    maybe_hash_value_in_proof_bundle,
+};
+use crate::ssz_proof::format_proof_bundles_as_ssz;
+use crate::telemetry::TelemetryEvent;
+use crate::types::{Block, Byte, Log, Receipt, ReceiptRpcFields, Result};
+use crate::utils::{convert_bytes_to_h256, convert_bytes_to_hex};
+use ethereum_types::{Address, H256, U256};
+use prost::Message;
+use std::convert::TryFrom;
+use std::fs;
+
+const ADDRESS_LENGTH: usize = 20;
+const SUCCEEDED_TX_STATUS: i32 = 1;
+
+/**
+ * Firehose Blocks:
+ *
+ * StreamingFast's Firehose streams each block as an `sf.ethereum.type.v2.Block`
+ * protobuf, whose transaction traces carry everything needed to rebuild the
+ * receipts trie. Only the fields we need are declared below, using the tags
+ * of the upstream `type.proto`, since protobuf decoding skips unknown fields.
+ * The header is converted into our `Block` so proofs built from Firehose
+ * payloads embed the same hash-verified header as those built via RPC.
+ */
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseBlock {
+    #[prost(bytes = "vec", tag = "2")]
+    pub hash: Vec<u8>,
+    #[prost(uint64, tag = "3")]
+    pub number: u64,
+    #[prost(uint64, tag = "4")]
+    pub size: u64,
+    #[prost(message, optional, tag = "5")]
+    pub header: Option<FirehoseBlockHeader>,
+    #[prost(message, repeated, tag = "6")]
+    pub uncles: Vec<FirehoseBlockHeader>,
+    #[prost(message, repeated, tag = "10")]
+    pub transaction_traces: Vec<FirehoseTransactionTrace>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseBlockHeader {
+    #[prost(bytes = "vec", tag = "1")]
+    pub parent_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub uncle_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub coinbase: Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub state_root: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub transactions_root: Vec<u8>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub receipt_root: Vec<u8>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub logs_bloom: Vec<u8>,
+    #[prost(message, optional, tag = "8")]
+    pub difficulty: Option<FirehoseBigInt>,
+    #[prost(uint64, tag = "9")]
+    pub number: u64,
+    #[prost(uint64, tag = "10")]
+    pub gas_limit: u64,
+    #[prost(uint64, tag = "11")]
+    pub gas_used: u64,
+    #[prost(message, optional, tag = "12")]
+    pub timestamp: Option<FirehoseTimestamp>,
+    #[prost(bytes = "vec", tag = "13")]
+    pub extra_data: Vec<u8>,
+    #[prost(bytes = "vec", tag = "14")]
+    pub mix_hash: Vec<u8>,
+    #[prost(uint64, tag = "15")]
+    pub nonce: u64,
+    #[prost(bytes = "vec", tag = "16")]
+    pub hash: Vec<u8>,
+    #[prost(message, optional, tag = "17")]
+    pub total_difficulty: Option<FirehoseBigInt>,
+    #[prost(message, optional, tag = "18")]
+    pub base_fee_per_gas: Option<FirehoseBigInt>,
+    #[prost(bytes = "vec", tag = "19")]
+    pub withdrawals_root: Vec<u8>,
+    #[prost(uint64, optional, tag = "22")]
+    pub blob_gas_used: Option<u64>,
+    #[prost(uint64, optional, tag = "23")]
+    pub excess_blob_gas: Option<u64>,
+    #[prost(bytes = "vec", tag = "24")]
+    pub parent_beacon_root: Vec<u8>,
+    #[prost(bytes = "vec", tag = "25")]
+    pub requests_hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseTransactionTrace {
+    #[prost(bytes = "vec", tag = "1")]
+    pub to: Vec<u8>,
+    #[prost(uint64, tag = "10")]
+    pub gas_used: u64,
+    #[prost(int32, tag = "12")]
+    pub r#type: i32,
+    #[prost(uint32, tag = "20")]
+    pub index: u32,
+    #[prost(bytes = "vec", tag = "21")]
+    pub hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "22")]
+    pub from: Vec<u8>,
+    #[prost(int32, tag = "30")]
+    pub status: i32,
+    #[prost(message, optional, tag = "31")]
+    pub receipt: Option<FirehoseTransactionReceipt>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseTransactionReceipt {
+    #[prost(bytes = "vec", tag = "1")]
+    pub state_root: Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub cumulative_gas_used: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub logs_bloom: Vec<u8>,
+    #[prost(message, repeated, tag = "4")]
+    pub logs: Vec<FirehoseLog>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseLog {
+    #[prost(bytes = "vec", tag = "1")]
+    pub address: Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub topics: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseBigInt {
+    #[prost(bytes = "vec", tag = "1")]
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FirehoseTimestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+fn convert_bytes_to_address(bytes: &[Byte]) -> Result<Address> {
+    match bytes.len() {
+        0 => Ok(Address::zero()),
+        ADDRESS_LENGTH => Ok(Address::from_slice(bytes)),
+        _ => Err(AppError::Custom(
+            "✘ Wrong number of bytes for address!".to_string(),
+        )),
+    }
+}
+
+fn convert_big_int_to_u256(big_int: &Option<FirehoseBigInt>) -> U256 {
+    big_int
+        .as_ref()
+        .map(|big_int| U256::from_big_endian(&big_int.bytes))
+        .unwrap_or_default()
+}
+
+fn convert_maybe_empty_bytes_to_h256(bytes: &[Byte]) -> Result<Option<H256>> {
+    match bytes.is_empty() {
+        true => Ok(None),
+        false => convert_bytes_to_h256(bytes).map(Some),
+    }
+}
+
+pub fn decode_firehose_block(bytes: &[Byte]) -> Result<FirehoseBlock> {
+    FirehoseBlock::decode(bytes)
+        .map_err(|e| AppError::Custom(format!("✘ Error decoding Firehose block: {}", e)))
+}
+
+fn get_firehose_block_header(firehose_block: &FirehoseBlock) -> Result<&FirehoseBlockHeader> {
+    firehose_block
+        .header
+        .as_ref()
+        .ok_or_else(|| AppError::Custom("✘ Firehose block has no header!".to_string()))
+}
+
+pub fn convert_firehose_block_to_block(firehose_block: &FirehoseBlock) -> Result<Block> {
+    let header = get_firehose_block_header(firehose_block)?;
+    Ok(Block {
+        author: None,
+        seal_fields: None,
+        size: U256::from(firehose_block.size),
+        hash: convert_bytes_to_h256(&firehose_block.hash)?,
+        number: U256::from(header.number),
+        nonce: U256::from(header.nonce),
+        gas_used: U256::from(header.gas_used),
+        gas_limit: U256::from(header.gas_limit),
+        timestamp: U256::from(header.timestamp.as_ref().map_or(0, |t| t.seconds as u64)),
+        difficulty: convert_big_int_to_u256(&header.difficulty),
+        total_difficulty: header
+            .total_difficulty
+            .as_ref()
+            .map(|big_int| U256::from_big_endian(&big_int.bytes)),
+        miner: convert_bytes_to_address(&header.coinbase)?,
+        logs_bloom: convert_bytes_to_bloom(&header.logs_bloom)?,
+        extra_data: header.extra_data.clone(),
+        mix_hash: convert_bytes_to_h256(&header.mix_hash)?,
+        parent_hash: convert_bytes_to_h256(&header.parent_hash)?,
+        sha3_uncles: convert_bytes_to_h256(&header.uncle_hash)?,
+        state_root: convert_bytes_to_h256(&header.state_root)?,
+        receipts_root: convert_bytes_to_h256(&header.receipt_root)?,
+        transactions_root: convert_bytes_to_h256(&header.transactions_root)?,
+        transactions: firehose_block
+            .transaction_traces
+            .iter()
+            .map(|trace| convert_bytes_to_h256(&trace.hash))
+            .collect::<Result<Vec<H256>>>()?,
+        uncles: firehose_block
+            .uncles
+            .iter()
+            .map(|uncle| convert_bytes_to_h256(&uncle.hash))
+            .collect::<Result<Vec<H256>>>()?,
+        base_fee_per_gas: header
+            .base_fee_per_gas
+            .as_ref()
+            .map(|big_int| U256::from_big_endian(&big_int.bytes)),
+        withdrawals_root: convert_maybe_empty_bytes_to_h256(&header.withdrawals_root)?,
+        blob_gas_used: header.blob_gas_used.map(U256::from),
+        excess_blob_gas: header.excess_blob_gas.map(U256::from),
+        parent_beacon_block_root: convert_maybe_empty_bytes_to_h256(&header.parent_beacon_root)?,
+        requests_hash: convert_maybe_empty_bytes_to_h256(&header.requests_hash)?,
+    })
+}
+
+fn convert_firehose_log_to_log(firehose_log: &FirehoseLog) -> Result<Log> {
+    Ok(Log {
+        address: convert_bytes_to_address(&firehose_log.address)?,
+        topics: firehose_log
+            .topics
+            .iter()
+            .map(|topic| convert_bytes_to_h256(topic))
+            .collect::<Result<Vec<H256>>>()?,
+        data: firehose_log.data.clone(),
+    })
+}
+
+fn convert_firehose_trace_to_receipt(
+    firehose_block: &FirehoseBlock,
+    trace: &FirehoseTransactionTrace,
+) -> Result<Receipt> {
+    let firehose_receipt = trace.receipt.as_ref().ok_or_else(|| {
+        AppError::Custom(format!(
+            "✘ Firehose trace at index {} has no receipt!",
+            trace.index
+        ))
+    })?;
+    let logs = firehose_receipt
+        .logs
+        .iter()
+        .map(convert_firehose_log_to_log)
+        .collect::<Result<Vec<Log>>>()?;
+    Ok(Receipt {
+        logs_bloom: get_verified_logs_bloom_from_logs(
+            &logs,
+            &convert_bytes_to_bloom(&firehose_receipt.logs_bloom)?,
+        )?,
+        to: match trace.to.is_empty() {
+            true => None,
+            false => Some(convert_bytes_to_address(&trace.to)?),
+        },
+        from: convert_bytes_to_address(&trace.from)?,
+        status: trace.status == SUCCEEDED_TX_STATUS,
+        gas_used: U256::from(trace.gas_used),
+        block_hash: convert_bytes_to_h256(&firehose_block.hash)?,
+        block_number: U256::from(firehose_block.number),
+        transaction_hash: convert_bytes_to_h256(&trace.hash)?,
+        transaction_index: U256::from(trace.index),
+        cumulative_gas_used: U256::from(firehose_receipt.cumulative_gas_used),
+        // NOTE: Firehose traces don't carry the created contract's address.
+        contract_address: None,
+        // NOTE: Only pre-Byzantium receipts carry a post-state root.
+        root: convert_maybe_empty_bytes_to_h256(&firehose_receipt.state_root)?,
+        tx_type: Byte::try_from(trace.r#type).map_err(|_| {
+            AppError::Custom(format!(
+                "✘ Firehose trace at index {} has unknown tx type: {}",
+                trace.index, trace.r#type
+            ))
+        })?,
+        // NOTE: Firehose receipts don't carry Optimism's deposit fields, nor those only in RPC receipts.
+        deposit_nonce: None,
+        deposit_receipt_version: None,
+        rpc_fields: ReceiptRpcFields::default(),
+        logs,
+    })
+}
+
+pub fn get_receipts_from_firehose_block(firehose_block: &FirehoseBlock) -> Result<Vec<Receipt>> {
+    firehose_block
+        .transaction_traces
+        .iter()
+        .map(|trace| convert_firehose_trace_to_receipt(firehose_block, trace))
+        .collect()
+}
+
+pub fn prove_from_firehose_block(cli_args: &CliArgs, path: &str, index: usize) -> Result<String> {
+    info!("✔ Reading Firehose block from: {}", path);
+    let firehose_block = decode_firehose_block(&fs::read(path)?)?;
+    let block = convert_firehose_block_to_block(&firehose_block)?;
+    let receipts = get_receipts_from_firehose_block(&firehose_block).and_then(|receipts| {
+        match cli_args.hardfork {
+            Some(hardfork) => apply_hardfork_to_receipts(receipts, hardfork),
+            None => Ok(receipts),
+        }
+    })?;
+    let trie = apply_chain_profile_to_receipts(receipts, cli_args.chain)
+        .and_then(|receipts| get_receipts_trie_from_receipts(&receipts))?;
+    check_receipts_roots_match(&block, &trie)?;
+    let root = trie.root;
+    let branch = get_branch_from_trie(&trie, index)?;
+    match cli_args.format {
+        OutputFormat::Hex => rlp_encode_node_stack(&branch).map(convert_bytes_to_hex),
+        OutputFormat::Json => get_proof_bundle(&block, root, index, &branch)
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, cli_args.value_hash))
+            .and_then(|bundle| {
+                maybe_compress_proof_in_proof_bundle(bundle, cli_args.compress_proofs)
+            })
+            .and_then(|bundle| get_proof_bundle_json(&bundle, cli_args.light))
+            .and_then(|json| Ok(serde_json::to_string_pretty(&json)?)),
+        OutputFormat::Binary => get_proof_bundle(&block, root, index, &branch)
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, cli_args.value_hash))
+            .and_then(|bundle| {
+                maybe_compress_proof_in_proof_bundle(bundle, cli_args.compress_proofs)
+            })
+            .and_then(|bundle| format_proof_bundles_as_binary(&[bundle]))
+            .and_then(|output| {
+                maybe_compress_binary_proof_output(output, cli_args.format, cli_args.compress)
+            }),
+        OutputFormat::Eip1186 => get_proof_bundle(&block, root, index, &branch)
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, cli_args.value_hash))
+            .and_then(|bundle| {
+                maybe_compress_proof_in_proof_bundle(bundle, cli_args.compress_proofs)
+            })
+            .and_then(|bundle| format_proof_bundles_as_eip1186(&[bundle])),
+        OutputFormat::Ssz => get_proof_bundle(&block, root, index, &branch)
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, cli_args.value_hash))
+            .and_then(|bundle| {
+                maybe_compress_proof_in_proof_bundle(bundle, cli_args.compress_proofs)
+            })
+            .and_then(|bundle| format_proof_bundles_as_ssz(&[bundle])),
+    }
+    .inspect(|_| {
+        cli_args
+            .telemetry
+            .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                proof_type: "receipt",
+                num_proofs: 1,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_header::get_verified_rlp_encoded_block_header;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, RECEIPTS_ROOT_1,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::{convert_h256_to_bytes, convert_h256_to_prefixed_hex, convert_hex_to_h256};
+
+    fn convert_u256_to_big_int(value: &U256) -> FirehoseBigInt {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        let first_non_zero_byte = bytes.iter().position(|byte| *byte != 0).unwrap_or(32);
+        FirehoseBigInt {
+            bytes: bytes[first_non_zero_byte..].to_vec(),
+        }
+    }
+
+    fn get_sample_firehose_block_header(block: &Block) -> FirehoseBlockHeader {
+        FirehoseBlockHeader {
+            parent_hash: convert_h256_to_bytes(block.parent_hash),
+            uncle_hash: convert_h256_to_bytes(block.sha3_uncles),
+            coinbase: block.miner.as_bytes().to_vec(),
+            state_root: convert_h256_to_bytes(block.state_root),
+            transactions_root: convert_h256_to_bytes(block.transactions_root),
+            receipt_root: convert_h256_to_bytes(block.receipts_root),
+            logs_bloom: block.logs_bloom.as_bytes().to_vec(),
+            difficulty: Some(convert_u256_to_big_int(&block.difficulty)),
+            number: block.number.as_u64(),
+            gas_limit: block.gas_limit.as_u64(),
+            gas_used: block.gas_used.as_u64(),
+            timestamp: Some(FirehoseTimestamp {
+                seconds: block.timestamp.as_u64() as i64,
+                nanos: 0,
+            }),
+            extra_data: block.extra_data.clone(),
+            mix_hash: convert_h256_to_bytes(block.mix_hash),
+            nonce: block.nonce.as_u64(),
+            hash: convert_h256_to_bytes(block.hash),
+            total_difficulty: None,
+            base_fee_per_gas: None,
+            withdrawals_root: vec![],
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_root: vec![],
+            requests_hash: vec![],
+        }
+    }
+
+    fn convert_receipt_to_firehose_trace(receipt: &Receipt) -> FirehoseTransactionTrace {
+        FirehoseTransactionTrace {
+            to: receipt.to.map_or(vec![], |to| to.as_bytes().to_vec()),
+            gas_used: receipt.gas_used.as_u64(),
+            r#type: receipt.tx_type as i32,
+            index: receipt.transaction_index.as_u32(),
+            hash: convert_h256_to_bytes(receipt.transaction_hash),
+            from: receipt.from.as_bytes().to_vec(),
+            status: match receipt.status {
+                true => SUCCEEDED_TX_STATUS,
+                false => 2,
+            },
+            receipt: Some(FirehoseTransactionReceipt {
+                state_root: vec![],
+                cumulative_gas_used: receipt.cumulative_gas_used.as_u64(),
+                logs_bloom: receipt.logs_bloom.as_bytes().to_vec(),
+                logs: receipt
+                    .logs
+                    .iter()
+                    .map(|log| FirehoseLog {
+                        address: log.address.as_bytes().to_vec(),
+                        topics: log
+                            .topics
+                            .iter()
+                            .map(|topic| convert_h256_to_bytes(*topic))
+                            .collect(),
+                        data: log.data.clone(),
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    fn get_sample_firehose_block_with_receipts_1() -> FirehoseBlock {
+        let receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let mut header = get_sample_firehose_block_header(&get_expected_block());
+        header.receipt_root =
+            convert_h256_to_bytes(convert_hex_to_h256(RECEIPTS_ROOT_1.to_string()).unwrap());
+        FirehoseBlock {
+            hash: convert_h256_to_bytes(receipts[0].block_hash),
+            number: receipts[0].block_number.as_u64(),
+            size: 0,
+            header: Some(header),
+            uncles: vec![],
+            transaction_traces: receipts
+                .iter()
+                .map(convert_receipt_to_firehose_trace)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn should_convert_firehose_block_header_to_block_hashing_to_block_hash() {
+        let block = get_expected_block();
+        let firehose_block = FirehoseBlock {
+            hash: convert_h256_to_bytes(block.hash),
+            number: block.number.as_u64(),
+            size: block.size.as_u64(),
+            header: Some(get_sample_firehose_block_header(&block)),
+            uncles: vec![],
+            transaction_traces: vec![],
+        };
+        let decoded = decode_firehose_block(&firehose_block.encode_to_vec()).unwrap();
+        let result = convert_firehose_block_to_block(&decoded).unwrap();
+        get_verified_rlp_encoded_block_header(&result).unwrap();
+        assert!(result.receipts_root == block.receipts_root);
+    }
+
+    #[test]
+    fn should_build_receipts_trie_from_firehose_block() {
+        let firehose_block = get_sample_firehose_block_with_receipts_1();
+        let decoded = decode_firehose_block(&firehose_block.encode_to_vec()).unwrap();
+        let receipts = get_receipts_from_firehose_block(&decoded).unwrap();
+        let result = get_receipts_trie_from_receipts(&receipts).unwrap();
+        assert!(convert_h256_to_prefixed_hex(result.root).unwrap() == RECEIPTS_ROOT_1);
+    }
+
+    #[test]
+    fn should_fail_to_decode_garbage_as_firehose_block() {
+        match decode_firehose_block(&[0xc0, 0xff, 0xee]) {
+            Err(AppError::Custom(e)) => assert!(e.contains("Error decoding Firehose block")),
+            _ => panic!("Should not decode garbage as Firehose block!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_convert_firehose_trace_with_unknown_tx_type() {
+        let mut firehose_block = get_sample_firehose_block_with_receipts_1();
+        firehose_block.transaction_traces[0].r#type = 1337;
+        match get_receipts_from_firehose_block(&firehose_block) {
+            Err(AppError::Custom(e)) => assert!(e.contains("unknown tx type")),
+            _ => panic!("Should not convert trace with unknown tx type!"),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::constants::HASH_LENGTH;
+use crate::errors::AppError;
+use crate::proof_bundle::{decode_proof_nodes, rlp_encode_proof_nodes, ProofBundle};
+use crate::types::{Bytes, Result};
+use std::fs;
+
+/**
+ * Gas Estimates:
+ *
+ * Before relaying a proof on-chain, bridge teams want to know what verifying
+ * it will cost. So full JSON bundles' `metadata` carries a `gas_estimate` of
+ * a Solidity verifier checking the proof, as submitted in the bundle, i.e.
+ * after any `--compress-proofs`. The proof is taken as calldata the way the
+ * hex format outputs it, the RLP list of its nodes, ABI encoded as a single
+ * `bytes` argument, each zero & non-zero byte priced as per EIP-2028. Each
+ * node is then hashed, to check it against its parent's reference to it, &
+ * copied into memory to be RLP decoded, w/ a flat cost per node for decoding
+ * it & matching its path against the key. The intrinsic cost of the tx
+ * submitting the proof is added on top. These are estimates, not gas used,
+ * since verifiers differ, so every price is configurable via the TOML file
+ * given via `--gas-model`. Keys left out of it keep their defaults.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasModel {
+    pub calldata_zero_byte_gas: u64,
+    pub calldata_non_zero_byte_gas: u64,
+    pub keccak_gas: u64,
+    pub keccak_word_gas: u64,
+    pub copy_word_gas: u64,
+    pub node_gas: u64,
+    pub base_gas: u64,
+}
+
+impl Default for GasModel {
+    fn default() -> Self {
+        GasModel {
+            calldata_zero_byte_gas: 4,
+            calldata_non_zero_byte_gas: 16,
+            keccak_gas: 30,
+            keccak_word_gas: 6,
+            copy_word_gas: 3,
+            node_gas: 2_000,
+            base_gas: 21_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasEstimate {
+    pub calldata_bytes: usize,
+    pub calldata_gas: u64,
+    pub keccak_gas: u64,
+    pub execution_gas: u64,
+    pub total_gas: u64,
+}
+
+fn get_num_words(num_bytes: usize) -> u64 {
+    num_bytes.div_ceil(HASH_LENGTH) as u64
+}
+
+fn get_abi_word(value: usize) -> Bytes {
+    let mut word = vec![0u8; HASH_LENGTH];
+    word[HASH_LENGTH - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+// NOTE: The head's offset to the `bytes`, then its length, then its data right padded to a word.
+pub fn get_abi_encoded_proof(proof: &[String]) -> Result<Bytes> {
+    let encoded_proof = rlp_encode_proof_nodes(proof)?;
+    let padding =
+        vec![0u8; get_num_words(encoded_proof.len()) as usize * HASH_LENGTH - encoded_proof.len()];
+    Ok([
+        get_abi_word(HASH_LENGTH),
+        get_abi_word(encoded_proof.len()),
+        encoded_proof,
+        padding,
+    ]
+    .concat())
+}
+
+pub fn get_calldata_gas(calldata: &[u8], model: &GasModel) -> u64 {
+    calldata
+        .iter()
+        .map(|byte| match byte {
+            0 => model.calldata_zero_byte_gas,
+            _ => model.calldata_non_zero_byte_gas,
+        })
+        .sum()
+}
+
+pub fn estimate_verification_gas(proof: &[String], model: &GasModel) -> Result<GasEstimate> {
+    let calldata = get_abi_encoded_proof(proof)?;
+    let node_lengths = decode_proof_nodes(proof)?
+        .iter()
+        .map(|node| node.len())
+        .collect::<Vec<usize>>();
+    let calldata_gas = get_calldata_gas(&calldata, model);
+    let keccak_gas = node_lengths
+        .iter()
+        .map(|length| model.keccak_gas + model.keccak_word_gas * get_num_words(*length))
+        .sum::<u64>();
+    let execution_gas = node_lengths
+        .iter()
+        .map(|length| model.node_gas + model.copy_word_gas * get_num_words(*length))
+        .sum::<u64>();
+    Ok(GasEstimate {
+        calldata_bytes: calldata.len(),
+        calldata_gas,
+        keccak_gas,
+        execution_gas,
+        total_gas: model.base_gas + calldata_gas + keccak_gas + execution_gas,
+    })
+}
+
+pub fn parse_gas_model_file(toml_string: &str) -> Result<GasModel> {
+    toml::from_str(toml_string)
+        .map_err(|e| AppError::Custom(format!("✘ Error parsing gas model file!\n✘ {}", e)))
+}
+
+pub fn maybe_read_gas_model_file(maybe_path: &Option<String>) -> Result<Option<GasModel>> {
+    match maybe_path {
+        None => Ok(None),
+        Some(path) => {
+            info!("✔ Reading gas model from: {}", path);
+            let toml_string = fs::read_to_string(path).map_err(|e| {
+                AppError::Custom(format!("✘ Error reading gas model file {}!\n✘ {}", path, e))
+            })?;
+            parse_gas_model_file(&toml_string).map(Some)
+        }
+    }
+}
+
+// NOTE: Only bundles w/ a `metadata` section, i.e. full JSON bundles, have somewhere to put it.
+pub fn maybe_set_gas_estimate_in_proof_bundle(
+    bundle: ProofBundle,
+    gas_model: &GasModel,
+) -> Result<ProofBundle> {
+    match bundle.metadata {
+        None => Ok(bundle),
+        Some(ref metadata) => {
+            let mut metadata = metadata.clone();
+            metadata.gas_estimate = Some(estimate_verification_gas(&bundle.proof, gas_model)?);
+            Ok(ProofBundle {
+                metadata: Some(metadata),
+                ..bundle
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_metadata::{get_build_metadata, set_build_metadata_in_proof_bundle};
+    use crate::proof_bundle::compress_proof_in_proof_bundle;
+    use crate::test_utils::{
+        get_sample_proof_bundle_1, get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    fn get_sample_proof_bundle_w_metadata() -> ProofBundle {
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let metadata = get_build_metadata("http://localhost:8545", &trie).unwrap();
+        set_build_metadata_in_proof_bundle(get_sample_proof_bundle_1(), &Some(metadata))
+    }
+
+    #[test]
+    fn should_abi_encode_proof_as_single_bytes_argument() {
+        let bundle = get_sample_proof_bundle_1();
+        let encoded_proof = rlp_encode_proof_nodes(&bundle.proof).unwrap();
+        let result = get_abi_encoded_proof(&bundle.proof).unwrap();
+        assert!(result.len().is_multiple_of(HASH_LENGTH));
+        assert!(result[..HASH_LENGTH] == get_abi_word(HASH_LENGTH)[..]);
+        assert!(result[HASH_LENGTH..2 * HASH_LENGTH] == get_abi_word(encoded_proof.len())[..]);
+        assert!(
+            result[2 * HASH_LENGTH..2 * HASH_LENGTH + encoded_proof.len()] == encoded_proof[..]
+        );
+    }
+
+    #[test]
+    fn should_estimate_verification_gas_of_proof() {
+        let bundle = get_sample_proof_bundle_1();
+        let model = GasModel::default();
+        let result = estimate_verification_gas(&bundle.proof, &model).unwrap();
+        let calldata = get_abi_encoded_proof(&bundle.proof).unwrap();
+        let num_zero_bytes = calldata.iter().filter(|byte| **byte == 0).count() as u64;
+        let expected_calldata_gas =
+            num_zero_bytes * 4 + (calldata.len() as u64 - num_zero_bytes) * 16;
+        assert!(result.calldata_bytes == calldata.len());
+        assert!(result.calldata_gas == expected_calldata_gas);
+        assert!(result.keccak_gas > 30 * bundle.proof.len() as u64);
+        assert!(result.execution_gas > 2_000 * bundle.proof.len() as u64);
+        assert!(
+            result.total_gas
+                == 21_000 + result.calldata_gas + result.keccak_gas + result.execution_gas
+        );
+    }
+
+    #[test]
+    fn should_estimate_less_gas_for_compressed_proof() {
+        let bundle = get_sample_proof_bundle_1();
+        let compressed_bundle = compress_proof_in_proof_bundle(bundle.clone()).unwrap();
+        let model = GasModel::default();
+        let result = estimate_verification_gas(&compressed_bundle.proof, &model).unwrap();
+        let expected_max = estimate_verification_gas(&bundle.proof, &model).unwrap();
+        assert!(result.calldata_gas < expected_max.calldata_gas);
+        assert!(result.total_gas < expected_max.total_gas);
+    }
+
+    #[test]
+    fn should_parse_gas_model_file_w_defaults_for_missing_keys() {
+        let result = parse_gas_model_file("base_gas = 0\nnode_gas = 500").unwrap();
+        let expected_result = GasModel {
+            base_gas: 0,
+            node_gas: 500,
+            ..GasModel::default()
+        };
+        assert!(result == expected_result);
+        assert!(parse_gas_model_file("bas_gas = 0").is_err());
+        assert!(parse_gas_model_file("").unwrap() == GasModel::default());
+    }
+
+    #[test]
+    fn should_set_gas_estimate_in_proof_bundle_metadata() {
+        let bundle = get_sample_proof_bundle_w_metadata();
+        let gas_model = GasModel {
+            base_gas: 0,
+            ..GasModel::default()
+        };
+        let result = maybe_set_gas_estimate_in_proof_bundle(bundle.clone(), &gas_model).unwrap();
+        let expected_estimate = estimate_verification_gas(&bundle.proof, &gas_model).unwrap();
+        assert!(result.metadata.clone().unwrap().gas_estimate == Some(expected_estimate));
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["metadata"]["gas_estimate"]["total_gas"].is_u64());
+        let bundle_without_metadata = get_sample_proof_bundle_1();
+        assert!(
+            maybe_set_gas_estimate_in_proof_bundle(
+                bundle_without_metadata.clone(),
+                &GasModel::default()
+            )
+            .unwrap()
+                == bundle_without_metadata
+        );
+    }
+}
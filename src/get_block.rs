@@ -1,19 +1,23 @@
+use crate::errors::AppError;
 use crate::get_receipts::get_receipt_from_tx_hash;
 use crate::get_rpc_call_jsons::{get_block_by_block_hash_json, get_block_by_block_number_json};
-use crate::make_rpc_call::{deserialize_to_block_rpc_response, get_response_text, make_rpc_call};
+use crate::input_parsing::{parse_block_id, BlockId};
+use crate::json_rpc_client::call_json_rpc;
+use crate::make_rpc_call::{check_response_text, deserialize_to_block_rpc_response};
+use crate::single_flight::{run_rpc_flight, SingleFlight};
 use crate::state::State;
 use crate::types::{Block, BlockJson, Result};
 use crate::utils::{
     convert_h256_to_prefixed_hex, convert_hex_strings_to_h256s, convert_hex_to_address,
-    convert_hex_to_bytes, convert_hex_to_h256, convert_hex_to_u256, convert_num_string_to_usize,
-    convert_num_to_prefixed_hex,
+    convert_hex_to_bytes, convert_hex_to_h256, convert_hex_to_u256, get_duplicates_with_positions,
 };
 use ethereum_types::{Bloom, H256};
 use serde_json::Value as Json;
+use tracing::info_span;
 
 pub fn deserialize_block_json_to_block_struct(block_json: BlockJson) -> Result<Block> {
     Ok(Block {
-        author: convert_hex_to_address(block_json.author)?,
+        author: block_json.author.map(convert_hex_to_address).transpose()?,
         difficulty: convert_hex_to_u256(block_json.difficulty)?,
         extra_data: convert_hex_to_bytes(block_json.extraData)?,
         gas_limit: convert_hex_to_u256(block_json.gasLimit)?,
@@ -26,26 +30,79 @@ pub fn deserialize_block_json_to_block_struct(block_json: BlockJson) -> Result<B
         number: convert_hex_to_u256(block_json.number)?,
         parent_hash: convert_hex_to_h256(block_json.parentHash)?,
         receipts_root: convert_hex_to_h256(block_json.receiptsRoot)?,
-        seal_fields: (
-            convert_hex_to_bytes(block_json.sealFields.0)?,
-            convert_hex_to_u256(block_json.sealFields.1)?,
-        ),
+        seal_fields: match block_json.sealFields {
+            Some((mix_hash, nonce)) => {
+                Some((convert_hex_to_bytes(mix_hash)?, convert_hex_to_u256(nonce)?))
+            }
+            None => None,
+        },
         sha3_uncles: convert_hex_to_h256(block_json.sha3Uncles)?,
         size: convert_hex_to_u256(block_json.size)?,
         state_root: convert_hex_to_h256(block_json.stateRoot)?,
         timestamp: convert_hex_to_u256(block_json.timestamp)?,
-        total_difficulty: convert_hex_to_u256(block_json.totalDifficulty)?,
+        total_difficulty: block_json
+            .totalDifficulty
+            .map(convert_hex_to_u256)
+            .transpose()?,
         transactions: convert_hex_strings_to_h256s(block_json.transactions)?,
         transactions_root: convert_hex_to_h256(block_json.transactionsRoot)?,
         uncles: convert_hex_strings_to_h256s(block_json.uncles)?,
+        base_fee_per_gas: block_json
+            .baseFeePerGas
+            .map(convert_hex_to_u256)
+            .transpose()?,
+        withdrawals_root: block_json
+            .withdrawalsRoot
+            .map(convert_hex_to_h256)
+            .transpose()?,
+        blob_gas_used: block_json
+            .blobGasUsed
+            .map(convert_hex_to_u256)
+            .transpose()?,
+        excess_blob_gas: block_json
+            .excessBlobGas
+            .map(convert_hex_to_u256)
+            .transpose()?,
+        parent_beacon_block_root: block_json
+            .parentBeaconBlockRoot
+            .map(convert_hex_to_h256)
+            .transpose()?,
+        requests_hash: block_json
+            .requestsHash
+            .map(convert_hex_to_h256)
+            .transpose()?,
     })
 }
 
+// NOTE: Some load-balanced providers have been seen splicing responses, duplicating txs.
+pub fn check_block_has_no_duplicate_txs(block: Block) -> Result<Block> {
+    let duplicates = get_duplicates_with_positions(&block.transactions)
+        .into_iter()
+        .map(|(tx_hash, indices)| {
+            Ok(format!(
+                "Tx hash {} at indices: {:?}",
+                convert_h256_to_prefixed_hex(tx_hash)?,
+                indices
+            ))
+        })
+        .collect::<Result<Vec<String>>>()?;
+    match duplicates.is_empty() {
+        true => Ok(block),
+        false => Err(AppError::DuplicateTxInBlock(duplicates)),
+    }
+}
+
+static BLOCK_FETCHES: SingleFlight<Block> = SingleFlight::new();
+
 fn get_block(endpoint: &str, rpc_json: Json) -> Result<Block> {
-    make_rpc_call(endpoint, rpc_json)
-        .and_then(get_response_text)
-        .and_then(deserialize_to_block_rpc_response)
-        .and_then(|res| deserialize_block_json_to_block_struct(res.result))
+    let _span = info_span!("fetch_block").entered();
+    run_rpc_flight(&BLOCK_FETCHES, endpoint, rpc_json, |rpc_json| {
+        call_json_rpc(endpoint, rpc_json)
+            .and_then(check_response_text)
+            .and_then(deserialize_to_block_rpc_response)
+            .and_then(|res| deserialize_block_json_to_block_struct(res.result))
+            .and_then(check_block_has_no_duplicate_txs)
+    })
 }
 
 pub fn get_block_by_blockhash(endpoint: &str, block_hash: H256) -> Result<Block> {
@@ -53,14 +110,52 @@ pub fn get_block_by_blockhash(endpoint: &str, block_hash: H256) -> Result<Block>
         .and_then(|json| get_block(endpoint, json))
 }
 
+fn get_block_number_hex(block_num: &str) -> Result<String> {
+    parse_block_id(block_num)?
+        .to_block_number_param()
+        .ok_or_else(|| AppError::Custom(format!("✘ Expected a block number, got: {}", block_num)))
+}
+
 pub fn get_block_by_number(endpoint: &str, block_num: &str) -> Result<Block> {
-    let num_hex: String;
-    if block_num == "latest" {
-        num_hex = block_num.to_string();
-    } else {
-        num_hex = convert_num_to_prefixed_hex(convert_num_string_to_usize(block_num)?)?;
+    get_block_by_block_number_json(get_block_number_hex(block_num)?)
+        .and_then(|json| get_block(endpoint, json))
+}
+
+pub fn get_canonical_block_hash(endpoint: &str, block_number: u64) -> Result<H256> {
+    get_block_by_number(endpoint, &block_number.to_string()).map(|block| block.hash)
+}
+
+// NOTE: A block is canonical if it's still the node's block at its height, i.e. not reorged out.
+pub fn check_block_hash_is_canonical(block: &Block, canonical_block_hash: H256) -> Result<()> {
+    match canonical_block_hash == block.hash {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Block {} w/ hash {} has been reorged out by {}!",
+            block.number,
+            convert_h256_to_prefixed_hex(block.hash)?,
+            convert_h256_to_prefixed_hex(canonical_block_hash)?
+        ))),
+    }
+}
+
+pub fn check_block_is_canonical(endpoint: &str, block: &Block) -> Result<()> {
+    get_canonical_block_hash(endpoint, block.number.as_u64())
+        .and_then(|canonical_block_hash| check_block_hash_is_canonical(block, canonical_block_hash))
+}
+
+pub fn get_block_by_block_id_json(block_id: &str) -> Result<Json> {
+    match parse_block_id(block_id)? {
+        BlockId::Hash(hash) => get_block_by_block_hash_json(convert_h256_to_prefixed_hex(hash)?),
+        _ => get_block_by_block_number_json(get_block_number_hex(block_id)?),
+    }
+}
+
+pub fn get_block_by_block_id(endpoint: &str, block_id: &str) -> Result<Block> {
+    info!("✔ Getting block: {}", block_id);
+    match parse_block_id(block_id)? {
+        BlockId::Hash(hash) => get_block_by_blockhash(endpoint, hash),
+        _ => get_block_by_number(endpoint, block_id),
     }
-    get_block_by_block_number_json(num_hex).and_then(|json| get_block(endpoint, json))
 }
 
 fn add_block_to_state(state: State, block: Block) -> Result<State> {
@@ -79,11 +174,47 @@ pub fn get_block_from_tx_hash_in_state_and_set_in_state(state: State) -> Result<
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::json_rpc_client::{remove_json_rpc_client, set_json_rpc_client, MockJsonRpcClient};
     use crate::test_utils::{
-        assert_block_is_correct, get_valid_block_hash_h256, get_valid_state_with_endpoint,
-        SAMPLE_BLOCK_HASH, SAMPLE_BLOCK_JSON_PATH, WORKING_ENDPOINT,
+        assert_block_is_correct, get_expected_block, get_sample_rpc_result,
+        get_valid_block_hash_h256, get_valid_state_with_endpoint, SAMPLE_BLOCK_HASH,
+        SAMPLE_BLOCK_JSON_PATH, WORKING_ENDPOINT,
     };
     use std::fs;
+    use std::sync::Arc;
+
+    #[test]
+    fn should_error_on_block_with_duplicate_txs() {
+        let mut block = get_expected_block();
+        assert!(check_block_has_no_duplicate_txs(block.clone()).is_ok());
+        let duplicate_tx_hash = block.transactions[1];
+        block.transactions.push(duplicate_tx_hash);
+        match check_block_has_no_duplicate_txs(block) {
+            Err(AppError::DuplicateTxInBlock(duplicates)) => {
+                assert!(duplicates.len() == 1);
+                assert!(duplicates[0]
+                    .contains(&convert_h256_to_prefixed_hex(duplicate_tx_hash).unwrap()));
+            }
+            _ => panic!("Should error on block w/ duplicate txs!"),
+        }
+    }
+
+    #[test]
+    fn should_get_block_by_block_hash_via_mock_json_rpc_client() {
+        let endpoint = "mock://should-get-block-by-block-hash";
+        let client = Arc::new(MockJsonRpcClient::new().with_result_for_params(
+            "eth_getBlockByHash",
+            json!([SAMPLE_BLOCK_HASH, false]),
+            get_sample_rpc_result(SAMPLE_BLOCK_JSON_PATH),
+        ));
+        set_json_rpc_client(endpoint, client.clone());
+        let result = get_block_by_blockhash(endpoint, get_valid_block_hash_h256().unwrap());
+        let other_block_result = get_block_by_blockhash(endpoint, H256::zero());
+        remove_json_rpc_client(endpoint);
+        assert_block_is_correct(result.unwrap());
+        assert!(other_block_result.is_err());
+        assert!(client.get_requests().len() == 2);
+    }
 
     #[test]
     fn should_deserialize_block_json_to_struct_correctly() {
@@ -93,6 +224,22 @@ mod tests {
         assert_block_is_correct(result)
     }
 
+    #[test]
+    fn should_deserialize_block_json_without_parity_specific_fields() {
+        let mut block_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_BLOCK_JSON_PATH).unwrap()).unwrap();
+        let result_json = block_json["result"].as_object_mut().unwrap();
+        result_json.remove("author");
+        result_json.remove("sealFields");
+        result_json.remove("totalDifficulty");
+        let block_rpc = deserialize_to_block_rpc_response(block_json.to_string()).unwrap();
+        let result = deserialize_block_json_to_block_struct(block_rpc.result).unwrap();
+        assert!(result.author.is_none());
+        assert!(result.seal_fields.is_none());
+        assert!(result.total_difficulty.is_none());
+        assert!(result.hash == get_valid_block_hash_h256().unwrap());
+    }
+
     #[test]
     fn should_get_block_by_block_hash() {
         let result =
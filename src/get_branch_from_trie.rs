@@ -1,26 +1,35 @@
 use crate::{
     errors::AppError,
-    nibble_utils::get_nibbles_from_bytes,
-    nibble_utils::Nibbles,
-    rlp_codec::rlp_encode_transaction_index,
+    proof_size_report::maybe_record_proof_size,
     state::State,
     trie::Trie,
+    trie_keys::{ReceiptKey, TrieKey},
     types::{NodeStack, Result},
-    utils::convert_hex_to_u256,
 };
+use tracing::info_span;
 
-fn convert_usize_index_to_trie_key(index: usize) -> Result<Nibbles> {
-    convert_hex_to_u256(hex::encode(index.to_be_bytes()))
-        .and_then(|u256| rlp_encode_transaction_index(&u256))
-        .map(get_nibbles_from_bytes)
-}
-
-pub fn get_branch_from_trie(receipts_trie: Trie, index: usize) -> Result<NodeStack> {
+/**
+ * Degenerate Tries:
+ *
+ * A block w/ a single tx has a receipts trie whose root is the one leaf, so
+ * that leaf alone is the branch, & the proof is just the one node. A block
+ * w/ no txs has an empty receipts trie, whose root is the hash of the null
+ * node & which has no nodes in the database at all, so there's no receipt to
+ * prove & that's said plainly, rather than failing to find the root node.
+ */
+pub fn get_branch_from_trie(receipts_trie: &Trie, index: usize) -> Result<NodeStack> {
+    let _span = info_span!("extract_proof", tx_index = index).entered();
+    if receipts_trie.is_empty() {
+        return Err(AppError::Custom(format!(
+            "✘ Error! No receipt in trie at given index: {} - the trie is empty, as its block has no transactions!",
+            index
+        )));
+    }
     receipts_trie
-        .find(convert_usize_index_to_trie_key(index)?)
+        .find(ReceiptKey::from_index(index)?.to_nibbles())
         .and_then(
-            |(_, _, found_stack, remaining_key)| match remaining_key.len() {
-                0 => Ok(found_stack),
+            |(_, found_stack, remaining_key)| match remaining_key.len() {
+                0 => maybe_record_proof_size(index, &found_stack).map(|_| found_stack),
                 _ => Err(AppError::Custom(format!(
                     "✘ Error! No receipt in trie at given index: {}",
                     index
@@ -32,7 +41,7 @@ pub fn get_branch_from_trie(receipts_trie: Trie, index: usize) -> Result<NodeSta
 pub fn get_branch_from_trie_and_put_in_state(state: State) -> Result<State> {
     info!("✔ Pulling branch from trie...");
     get_branch_from_trie(
-        state.get_receipts_trie_from_state()?.clone(),
+        state.get_receipts_trie_from_state()?,
         *state.get_index_from_state()?,
     )
     .and_then(|branch| state.set_branch_in_state(branch))
@@ -41,21 +50,14 @@ pub fn get_branch_from_trie_and_put_in_state(state: State) -> Result<State> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+    use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
     use crate::test_utils::{
-        get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1,
+        get_sample_receipts, get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1,
         get_valid_state_with_receipts_trie_and_index, SAMPLE_RECEIPT_JSONS_1_PATH,
     };
-
-    #[test]
-    fn should_convert_usize_to_trie_key() {
-        let index = 10;
-        let expected_result = Nibbles {
-            data: vec![0x0a],
-            offset: 0,
-        };
-        let result = convert_usize_index_to_trie_key(index).unwrap();
-        assert!(result == expected_result)
-    }
+    use crate::verifier::verify_receipt_proof;
+    use ethereum_types::U256;
 
     #[test]
     fn should_get_branch_from_trie() {
@@ -64,7 +66,7 @@ mod tests {
             SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
             get_sample_tx_hashes_1(),
         );
-        get_branch_from_trie(trie, index).unwrap();
+        get_branch_from_trie(&trie, index).unwrap();
     }
 
     #[test]
@@ -78,7 +80,7 @@ mod tests {
             SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
             get_sample_tx_hashes_1(),
         );
-        match get_branch_from_trie(trie, non_existent_index) {
+        match get_branch_from_trie(&trie, non_existent_index) {
             Err(AppError::Custom(e)) => assert!(e == expected_error),
             _ => panic!("Getting branch should not have succeeded!"),
         }
@@ -96,7 +98,7 @@ mod tests {
         )
         .unwrap();
         let index = state_before.get_index_from_state().unwrap();
-        let expected_branch = get_branch_from_trie(trie, *index).unwrap();
+        let expected_branch = get_branch_from_trie(&trie, *index).unwrap();
         if let Ok(_) = state_before.get_branch_from_state() {
             panic!("Should not have branch in state yet!")
         };
@@ -110,4 +112,40 @@ mod tests {
             }
         };
     }
+
+    #[test]
+    fn should_get_single_leaf_branch_from_trie_of_single_receipt() {
+        let mut receipt = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .remove(0);
+        receipt.transaction_index = U256::zero();
+        let receipts = vec![receipt];
+        let trie = get_receipts_trie_from_receipts(&receipts).unwrap();
+        let branch = get_branch_from_trie(&trie, 0).unwrap();
+        assert!(branch.len() == 1);
+        assert!(branch[0].get_hash().unwrap() == trie.root);
+        let proof = branch
+            .iter()
+            .map(|node| node.get_rlp_encoding())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let expected_value = get_rlp_encoded_receipts_and_key_tuples(&receipts)
+            .unwrap()
+            .remove(0)
+            .1;
+        let result = verify_receipt_proof(trie.root.as_fixed_bytes(), 0, &proof).unwrap();
+        assert!(result == expected_value);
+        assert!(get_branch_from_trie(&trie, 1).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_get_branch_from_empty_trie_w_clear_message() {
+        let trie = get_receipts_trie_from_receipts(&[]).unwrap();
+        match get_branch_from_trie(&trie, 0) {
+            Err(AppError::Custom(e)) => assert!(e.contains("block has no transactions")),
+            _ => panic!("Getting branch from empty trie should not have succeeded!"),
+        }
+    }
 }
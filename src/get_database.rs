@@ -1,19 +1,56 @@
-use crate::types::{Bytes, Database, Result};
+use crate::byte_order::debug_assert_key_byte_order;
+use crate::types::{Bytes, Database, RefCounts, Result};
 use ethereum_types::H256;
 
 pub fn get_new_database() -> Result<Database> {
     Ok(std::collections::HashMap::new())
 }
 
-pub fn put_thing_in_database(mut database: Database, key: H256, value: Bytes) -> Result<Database> {
+pub fn put_thing_in_database(database: &mut Database, key: H256, value: Bytes) -> Result<()> {
+    debug_assert_key_byte_order(&key, &value);
     database.insert(key, value);
-    Ok(database)
+    Ok(())
 }
 
-pub fn remove_thing_from_database(mut database: Database, key: &H256) -> Result<Database> {
-    match database.remove(key) {
-        Some(_) => Ok(database),
-        None => Ok(database),
+pub fn remove_thing_from_database(database: &mut Database, key: &H256) -> Result<()> {
+    database.remove(key);
+    Ok(())
+}
+
+/**
+ * Reference Counting:
+ *
+ * Nodes are keyed by their hash, so structurally identical subtrees, whether
+ * in one trie or across the many blocks' tries absorbed into one database,
+ * share the same nodes. Each put of a node thus takes a reference to it, &
+ * each removal drops one, w/ the node only removed from the database once no
+ * references to it remain. This way, a put making a node stale in one trie
+ * doesn't delete it from under another trie still referencing it.
+ */
+pub fn put_counted_thing_in_database(
+    database: &mut Database,
+    ref_counts: &mut RefCounts,
+    key: H256,
+    value: Bytes,
+) -> Result<()> {
+    *ref_counts.entry(key).or_insert(0) += 1;
+    put_thing_in_database(database, key, value)
+}
+
+pub fn remove_counted_thing_from_database(
+    database: &mut Database,
+    ref_counts: &mut RefCounts,
+    key: &H256,
+) -> Result<()> {
+    match ref_counts.get_mut(key) {
+        Some(ref_count) if *ref_count > 1 => {
+            *ref_count -= 1;
+            Ok(())
+        }
+        _ => {
+            ref_counts.remove(key);
+            remove_thing_from_database(database, key)
+        }
     }
 }
 
@@ -37,14 +74,11 @@ mod tests {
 
     #[test]
     fn should_insert_thing_in_database() {
-        let database = get_new_database().unwrap();
+        let mut database = get_new_database().unwrap();
         let expected_result = get_thing_to_put_in_database();
-        put_thing_in_database(
-            database,
-            get_expected_key_of_thing_in_database(),
-            expected_result.clone(),
-        )
-        .unwrap();
+        let key = get_expected_key_of_thing_in_database();
+        put_thing_in_database(&mut database, key, expected_result.clone()).unwrap();
+        assert!(database[&key] == expected_result);
     }
 
     #[test]
@@ -59,11 +93,27 @@ mod tests {
     #[test]
     fn should_remove_thing_from_database() {
         let key = get_expected_key_of_thing_in_database();
-        let database = get_new_database().unwrap();
-        let updated_database =
-            put_thing_in_database(database, key.clone(), get_thing_to_put_in_database()).unwrap();
-        assert!(updated_database.contains_key(&key));
-        let result = remove_thing_from_database(updated_database, &key).unwrap();
-        assert!(!result.contains_key(&key));
+        let mut database = get_new_database().unwrap();
+        put_thing_in_database(&mut database, key, get_thing_to_put_in_database()).unwrap();
+        assert!(database.contains_key(&key));
+        remove_thing_from_database(&mut database, &key).unwrap();
+        assert!(!database.contains_key(&key));
+    }
+
+    #[test]
+    fn should_only_remove_counted_thing_from_database_once_unreferenced() {
+        let key = get_expected_key_of_thing_in_database();
+        let thing = get_thing_to_put_in_database();
+        let mut database = get_new_database().unwrap();
+        let mut ref_counts = RefCounts::new();
+        put_counted_thing_in_database(&mut database, &mut ref_counts, key, thing.clone()).unwrap();
+        put_counted_thing_in_database(&mut database, &mut ref_counts, key, thing).unwrap();
+        assert!(ref_counts[&key] == 2);
+        remove_counted_thing_from_database(&mut database, &mut ref_counts, &key).unwrap();
+        assert!(database.contains_key(&key));
+        assert!(ref_counts[&key] == 1);
+        remove_counted_thing_from_database(&mut database, &mut ref_counts, &key).unwrap();
+        assert!(!database.contains_key(&key));
+        assert!(!ref_counts.contains_key(&key));
     }
 }
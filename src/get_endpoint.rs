@@ -1,9 +1,18 @@
 use crate::{
-    constants::DEFAULT_ENDPOINT, errors::AppError, state::State, types::Result,
+    constants::DEFAULT_ENDPOINT,
+    errors::AppError,
+    hardfork::get_chain_id,
+    json_rpc_client::register_http_json_rpc_client,
+    parse_cli_args::CliArgs,
+    rpc_client::RpcClient,
+    secrets::{get_api_key, insert_api_key_into_endpoint, register_secrets_in_endpoint},
+    state::State,
+    types::Result,
     utils::dot_env_file_exists,
 };
+use std::sync::Arc;
 
-fn maybe_run_dot_env() -> Result<()> {
+pub fn maybe_run_dot_env() -> Result<()> {
     match dot_env_file_exists() {
         true => match dotenv::dotenv() {
             Ok(_) => Ok(()),
@@ -13,20 +22,113 @@ fn maybe_run_dot_env() -> Result<()> {
     }
 }
 
-fn get_endpoint_from_env_vars() -> Result<String> {
+fn get_endpoints_from_env_vars(config_endpoints: &[String]) -> Result<Vec<String>> {
     maybe_run_dot_env().map(|_| match std::env::var("ENDPOINT") {
-        Ok(endpoint) => endpoint,
-        Err(_) => DEFAULT_ENDPOINT.to_string(),
+        Ok(endpoint) => vec![endpoint],
+        Err(_) => match config_endpoints.is_empty() {
+            true => vec![DEFAULT_ENDPOINT.to_string()],
+            false => config_endpoints.to_vec(),
+        },
     })
 }
 
+fn get_endpoint_from_env_vars(config_endpoints: &[String]) -> Result<String> {
+    get_endpoints_from_env_vars(config_endpoints).map(|endpoints| endpoints[0].clone())
+}
+
+fn get_endpoint_templates(
+    cli_endpoint: &Option<String>,
+    config_endpoints: &[String],
+) -> Result<Vec<String>> {
+    match cli_endpoint {
+        Some(endpoint) => maybe_run_dot_env().map(|_| vec![endpoint.clone()]),
+        None => {
+            info!("✔ Getting RPC endpoint from environment variables...");
+            get_endpoints_from_env_vars(config_endpoints)
+        }
+    }
+}
+
+// NOTE: The endpoint w/ any `{API_KEY}` placeholder still in it, for modes reloading the key.
+pub fn get_endpoint_template(cli_args: &CliArgs) -> Result<String> {
+    match &cli_args.endpoint {
+        Some(endpoint) => maybe_run_dot_env().map(|_| endpoint.clone()),
+        None => {
+            info!("✔ Getting RPC endpoint from environment variables...");
+            get_endpoint_from_env_vars(&cli_args.config_endpoints)
+        }
+    }
+}
+
+// NOTE: Only probed when the config file lists several, so a lone endpoint costs no extra call.
+fn get_first_reachable_endpoint(endpoints: Vec<String>) -> Result<String> {
+    if endpoints.len() == 1 {
+        return Ok(endpoints[0].clone());
+    }
+    endpoints
+        .into_iter()
+        .find(|endpoint| match get_chain_id(endpoint) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!(
+                    "✘ Endpoint {} unreachable, trying the next: {}",
+                    endpoint, e
+                );
+                false
+            }
+        })
+        .ok_or_else(|| {
+            AppError::Custom("✘ None of the configured endpoints are reachable!".to_string())
+        })
+}
+
+fn get_endpoints_from_templates(
+    templates: Vec<String>,
+    api_key_file: &Option<String>,
+) -> Result<Vec<String>> {
+    let api_key = get_api_key(api_key_file)?;
+    templates
+        .iter()
+        .map(|template| insert_api_key_into_endpoint(template, &api_key))
+        .collect::<Result<Vec<String>>>()
+        .inspect(|endpoints| {
+            endpoints
+                .iter()
+                .for_each(|endpoint| register_secrets_in_endpoint(endpoint))
+        })
+}
+
+// NOTE: So calls to the endpoints, & any cross-check one, use the RPC settings of the CLI args.
+pub fn register_rpc_client_for_endpoints(cli_args: &CliArgs, endpoints: &[String]) -> Result<()> {
+    let rpc_client = Arc::new(RpcClient::from_cli_args(cli_args)?);
+    endpoints
+        .iter()
+        .chain(cli_args.cross_check.iter())
+        .for_each(|endpoint| register_http_json_rpc_client(endpoint, &rpc_client));
+    Ok(())
+}
+
+pub fn get_endpoint(cli_args: &CliArgs) -> Result<String> {
+    get_endpoint_templates(&cli_args.endpoint, &cli_args.config_endpoints)
+        .and_then(|templates| get_endpoints_from_templates(templates, &cli_args.api_key_file))
+        .and_then(|endpoints| {
+            register_rpc_client_for_endpoints(cli_args, &endpoints)?;
+            get_first_reachable_endpoint(endpoints)
+        })
+}
+
 pub fn get_endpoint_and_set_in_state(state: State) -> Result<State> {
-    info!("✔ Getting RPC endpoint from environment variables...");
-    println!("✔ Getting RPC endpoint from environment variables...");
-    get_endpoint_from_env_vars().and_then(|endpoint| {
-        info!("✔ Endpoint retrieved: {}", endpoint);
-        State::set_endpoint_in_state(state, endpoint)
-    })
+    if let Ok(endpoint) = state.get_endpoint_from_state() {
+        info!("✔ Using endpoint from CLI args: {}", endpoint);
+        return Ok(state);
+    }
+    get_endpoint_templates(&None, &[])
+        .and_then(|templates| get_endpoints_from_templates(templates, &None))
+        .and_then(get_first_reachable_endpoint)
+        .and_then(|endpoint| {
+            info!("✔ Endpoint retrieved: {}", endpoint);
+            State::set_endpoint_in_state(state, endpoint)
+        })
 }
 
 #[cfg(test)]
@@ -36,8 +138,15 @@ mod tests {
     use crate::test_utils::{
         delete_env_file, get_valid_initial_state, read_env_file, restore_env_file, write_env_file,
     };
+    use clap::Parser;
     use std::fs;
 
+    fn get_cli_args(flags: &[&str]) -> CliArgs {
+        let mut args = vec!["rusty-receipt-proof-maker", "root", "latest"];
+        args.extend_from_slice(flags);
+        CliArgs::try_parse_from(args).unwrap()
+    }
+
     #[test]
     #[serial]
     fn maybe_run_dot_env_should_not_fail_if_no_env_file_present() {
@@ -117,7 +226,7 @@ mod tests {
     #[serial]
     fn should_get_default_endpoint_correctly_if_no_env_file_exists() {
         if !dot_env_file_exists() {
-            let result = get_endpoint_from_env_vars().unwrap();
+            let result = get_endpoint_from_env_vars(&[]).unwrap();
             assert!(result == DEFAULT_ENDPOINT);
             assert!(!dot_env_file_exists());
         }
@@ -128,7 +237,7 @@ mod tests {
     fn should_get_endpoint_from_env_file_if_extant_correctly() {
         if dot_env_file_exists() {
             let file = read_env_file().unwrap();
-            let result = get_endpoint_from_env_vars().unwrap();
+            let result = get_endpoint_from_env_vars(&[]).unwrap();
             assert!(file.contains(&result));
         }
     }
@@ -151,6 +260,65 @@ mod tests {
         }
     }
 
+    #[test]
+    #[serial]
+    fn should_get_first_config_endpoint_if_no_endpoint_env_var() {
+        maybe_run_dot_env().unwrap();
+        if std::env::var("ENDPOINT").is_err() {
+            let config_endpoints = vec!["http://localhost:8547".to_string()];
+            let result = get_endpoint_from_env_vars(&config_endpoints);
+            assert!(result.unwrap() == config_endpoints[0]);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn should_fail_if_no_config_endpoint_is_reachable() {
+        let endpoints = vec![
+            "http://localhost:1".to_string(),
+            "http://localhost:2".to_string(),
+        ];
+        assert!(get_first_reachable_endpoint(endpoints).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn should_prefer_endpoint_from_cli_args() {
+        let cli_endpoint = "http://localhost:8546".to_string();
+        let cli_args = get_cli_args(&["--endpoint", &cli_endpoint]);
+        let result = get_endpoint(&cli_args).unwrap();
+        assert!(result == cli_endpoint);
+    }
+
+    #[test]
+    #[serial]
+    fn should_insert_api_key_from_file_into_endpoint_and_redact_it() {
+        let api_key = "9aa3d95b3bc440fa88ea12eaa4456162";
+        let path = std::env::temp_dir().join("rrpm-endpoint-api-key");
+        fs::write(&path, api_key).unwrap();
+        let cli_args = get_cli_args(&[
+            "--endpoint",
+            "https://mainnet.infura.io/v3/{API_KEY}",
+            "--api-key-file",
+            path.to_str().unwrap(),
+        ]);
+        let result = get_endpoint(&cli_args).unwrap();
+        assert!(result == format!("https://mainnet.infura.io/v3/{}", api_key));
+        assert!(!AppError::Custom(result).to_string().contains(api_key));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn should_not_overwrite_endpoint_already_in_state() {
+        let cli_endpoint = "http://localhost:8546".to_string();
+        let initial_state = get_valid_initial_state()
+            .and_then(|state| state.set_endpoint_in_state(cli_endpoint.clone()))
+            .unwrap();
+        let result_state = get_endpoint_and_set_in_state(initial_state).unwrap();
+        assert!(result_state.get_endpoint_from_state().unwrap() == cli_endpoint);
+    }
+
     #[test]
     #[serial]
     fn should_get_custom_endpoint_and_set_in_state_if_env_file() {
@@ -1,23 +1,22 @@
 use crate::{
+    rlp_codec::Encoder,
     state::State,
     trie_nodes::Node,
     types::{Bytes, HexProof, Result},
     utils::convert_bytes_to_hex,
 };
-use rlp::RlpStream;
 
-fn rlp_encode_node_stack(node_stack: &[Node]) -> Result<Bytes> {
-    let mut rlp_stream = RlpStream::new();
-    rlp_stream.begin_list(node_stack.len());
+pub fn rlp_encode_node_stack(node_stack: &[Node]) -> Result<Bytes> {
+    let mut encoder = Encoder::new_list(node_stack.len());
     node_stack
         .iter()
         .map(|node| node.get_rlp_encoding())
         .collect::<Result<Vec<_>>>()?
         .iter()
         .for_each(|rlp_encoded_node| {
-            rlp_stream.append_raw(rlp_encoded_node, 1);
+            encoder.append_raw(rlp_encoded_node);
         });
-    Ok(rlp_stream.out())
+    Ok(encoder.out())
 }
 
 fn get_hex_proof_from_branch(branch: &[Node]) -> Result<HexProof> {
@@ -49,7 +48,7 @@ mod tests {
             SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
             get_sample_tx_hashes_1(),
         );
-        let branch = get_branch_from_trie(trie, PROOF_1_INDEX).unwrap();
+        let branch = get_branch_from_trie(&trie, PROOF_1_INDEX).unwrap();
         let result = get_hex_proof_from_branch(&branch).unwrap();
         assert!(result == expected_result);
     }
@@ -61,7 +60,7 @@ mod tests {
             SAMPLE_RECEIPT_JSONS_3_PATH.to_string(),
             get_sample_tx_hashes_3(),
         );
-        let branch = get_branch_from_trie(trie, PROOF_3_INDEX).unwrap();
+        let branch = get_branch_from_trie(&trie, PROOF_3_INDEX).unwrap();
         let result = get_hex_proof_from_branch(&branch).unwrap();
         assert!(result == expected_result);
     }
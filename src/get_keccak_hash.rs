@@ -1,14 +1,25 @@
 use crate::types::{Byte, Result};
 use ethereum_types::H256;
+#[cfg(feature = "keccak-sha3")]
+use sha3::{Digest, Keccak256};
+#[cfg(not(feature = "keccak-sha3"))]
 use tiny_keccak::keccak256;
 
+#[cfg(not(feature = "keccak-sha3"))]
 pub fn keccak_hash_bytes(bytes: &[Byte]) -> Result<H256> {
     Ok(keccak256(bytes).into())
 }
 
+#[cfg(feature = "keccak-sha3")]
+pub fn keccak_hash_bytes(bytes: &[Byte]) -> Result<H256> {
+    Ok(H256::from_slice(&Keccak256::digest(bytes)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::HASHED_NULL_NODE;
+    use crate::rlp_codec::RLP_EMPTY_STRING;
     use crate::utils::convert_hex_to_h256;
 
     fn get_expected_hash() -> H256 {
@@ -22,4 +33,18 @@ mod tests {
         let result = keccak_hash_bytes(&bytes).unwrap();
         assert!(result == get_expected_hash());
     }
+
+    #[test]
+    fn should_get_keccak_hash_of_empty_bytes_correctly() {
+        let expected_hash = "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+        let result = keccak_hash_bytes(&[]).unwrap();
+        assert!(result == convert_hex_to_h256(expected_hash.to_string()).unwrap());
+    }
+
+    // NOTE: The empty trie's root is hardcoded, so whichever backend is selected must agree w/ it.
+    #[test]
+    fn should_hash_rlp_of_null_to_hashed_null_node() {
+        let result = keccak_hash_bytes(&RLP_EMPTY_STRING).unwrap();
+        assert!(result == HASHED_NULL_NODE);
+    }
 }
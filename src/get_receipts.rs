@@ -1,76 +1,340 @@
-use crate::get_log::{get_logs_bloom_from_logs, get_logs_from_receipt_json};
-use crate::get_rpc_call_jsons::get_transaction_receipt_json;
-use crate::make_rpc_call::{deserialize_to_receipt_rpc_response, get_response_text, make_rpc_call};
+use crate::batch_tuner::BatchTuner;
+use crate::chain_profile::{apply_chain_profile_to_receipts, LEGACY_TX_TYPE};
+use crate::constants::HEX_PREFIX_LENGTH;
+use crate::errors::AppError;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::get_log::get_logs_from_receipt_json;
+use crate::get_rpc_call_jsons::{
+    get_transaction_receipt_json, get_transaction_receipts_batch_json,
+};
+use crate::hardfork::{apply_hardfork_to_receipts, get_hardfork};
+use crate::json_rpc_client::call_json_rpc;
+use crate::logs_bloom::{convert_bytes_to_bloom, get_verified_logs_bloom_from_logs};
+use crate::make_rpc_call::{
+    check_response_text, deserialize_to_batched_receipt_rpc_responses,
+    deserialize_to_receipt_rpc_response,
+};
+use crate::single_flight::{run_counted_flight, run_rpc_flight, SingleFlight};
 use crate::state::State;
-use crate::types::{Receipt, ReceiptJson, Result};
+use crate::types::{
+    BatchedReceiptRpcResponse, Byte, Receipt, ReceiptJson, ReceiptRpcFields, Result,
+};
 use crate::utils::{
-    convert_h256_to_prefixed_hex, convert_hex_to_address, convert_hex_to_h256, convert_hex_to_u256,
-    convert_json_value_to_string,
+    convert_h256_to_prefixed_hex, convert_hex_to_address, convert_hex_to_bytes,
+    convert_hex_to_h256, convert_hex_to_u256, convert_json_value_to_string,
 };
-use ethereum_types::{Address, H160, H256};
+use ethereum_types::{Address, H256, U256};
+use std::thread;
+use std::time::Instant;
+use tracing::info_span;
+
+fn convert_json_value_to_maybe_address(value: serde_json::Value) -> Result<Option<Address>> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        _ => convert_hex_to_address(convert_json_value_to_string(value)?).map(Some),
+    }
+}
+
+/**
+ * Strict Receipt Parsing:
+ *
+ * Forks & L2s keep adding fields to the receipts nodes return, so by default
+ * receipts are parsed leniently: fields we don't model are kept verbatim in
+ * the receipt's RPC fields, & merely logged. W/ `--strict-receipts` a receipt
+ * w/ any such field is refused instead, naming them, as if its JSON had been
+ * parsed w/ `deny_unknown_fields`, for pipelines that would rather fail than
+ * silently carry data they don't understand. Neither mode touches how the
+ * receipt is encoded, since unknown fields are never consensus fields here.
+ * The check runs on receipts once fetched, so fetches shared between callers
+ * serve both modes.
+ */
+pub fn check_receipt_fields(receipt: &Receipt, strict: bool) -> Result<()> {
+    match receipt.rpc_fields.other.is_empty() {
+        true => Ok(()),
+        false => {
+            let message = format!(
+                "Receipt for tx {} has unknown fields: {}",
+                convert_h256_to_prefixed_hex(receipt.transaction_hash)?,
+                receipt
+                    .rpc_fields
+                    .other
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+            match strict {
+                true => Err(AppError::Custom(format!(
+                    "✘ {} - drop `--strict-receipts` to parse it leniently!",
+                    message
+                ))),
+                false => {
+                    debug!("✔ {}, keeping them as is", message);
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+pub fn check_receipts_fields(receipts: Vec<Receipt>, strict: bool) -> Result<Vec<Receipt>> {
+    receipts
+        .iter()
+        .try_for_each(|receipt| check_receipt_fields(receipt, strict))
+        .and(Ok(receipts))
+}
+
+// NOTE: Pre-Byzantium receipts commit to a state root instead, so have no status.
+fn get_status_from_receipt_json(maybe_status: &Option<String>) -> Result<bool> {
+    match maybe_status {
+        None => Ok(false),
+        Some(status) => match convert_hex_to_u256(status.clone()) {
+            Ok(status) if status.is_zero() => Ok(false),
+            Ok(status) if status == U256::one() => Ok(true),
+            _ => Err(AppError::Custom(format!(
+                "✘ Unknown receipt status: {}",
+                status
+            ))),
+        },
+    }
+}
+
+fn get_tx_type_from_receipt_json(maybe_tx_type: &Option<String>) -> Result<Byte> {
+    match maybe_tx_type {
+        None => Ok(LEGACY_TX_TYPE),
+        Some(tx_type) => match convert_hex_to_u256(tx_type.clone())? {
+            tx_type_u256 if tx_type_u256 <= U256::from(Byte::MAX) => {
+                Ok(tx_type_u256.low_u32() as Byte)
+            }
+            _ => Err(AppError::Custom(format!(
+                "✘ Receipt tx type doesn't fit in a byte: {}",
+                tx_type
+            ))),
+        },
+    }
+}
 
 pub fn deserialize_receipt_json_to_receipt_struct(receipt: ReceiptJson) -> Result<Receipt> {
     let logs = get_logs_from_receipt_json(&receipt)?;
     Ok(Receipt {
         from: convert_hex_to_address(receipt.from)?,
-        logs_bloom: get_logs_bloom_from_logs(&logs)?,
+        logs_bloom: get_verified_logs_bloom_from_logs(
+            &logs,
+            &convert_bytes_to_bloom(&convert_hex_to_bytes(receipt.logsBloom)?)?,
+        )?,
         gas_used: convert_hex_to_u256(receipt.gasUsed)?,
         block_hash: convert_hex_to_h256(receipt.blockHash)?,
         block_number: convert_hex_to_u256(receipt.blockNumber)?,
         transaction_hash: convert_hex_to_h256(receipt.transactionHash)?,
         transaction_index: convert_hex_to_u256(receipt.transactionIndex)?,
         cumulative_gas_used: convert_hex_to_u256(receipt.cumulativeGasUsed)?,
-        status: match receipt.status.as_ref() {
-            "0x1" => true,
-            "0x0" => false,
-            _ => false,
-        },
-        to: match receipt.to {
-            serde_json::Value::Null => H160::zero(),
-            _ => convert_hex_to_address(convert_json_value_to_string(receipt.to)?)?,
+        status: get_status_from_receipt_json(&receipt.status)?,
+        tx_type: get_tx_type_from_receipt_json(&receipt.r#type)?,
+        deposit_nonce: receipt.depositNonce.map(convert_hex_to_u256).transpose()?,
+        deposit_receipt_version: receipt
+            .depositReceiptVersion
+            .map(convert_hex_to_u256)
+            .transpose()?,
+        rpc_fields: ReceiptRpcFields {
+            effective_gas_price: receipt
+                .effectiveGasPrice
+                .map(convert_hex_to_u256)
+                .transpose()?,
+            blob_gas_used: receipt.blobGasUsed.map(convert_hex_to_u256).transpose()?,
+            blob_gas_price: receipt.blobGasPrice.map(convert_hex_to_u256).transpose()?,
+            other: receipt.other,
         },
-        contract_address: match receipt.contractAddress {
-            serde_json::Value::Null => Address::zero(),
-            _ => convert_hex_to_address(convert_json_value_to_string(receipt.contractAddress)?)?,
+        root: match receipt.root {
+            Some(ref root) if root.len() > HEX_PREFIX_LENGTH => {
+                Some(convert_hex_to_h256(root.to_string())?)
+            }
+            _ => None,
         },
+        to: convert_json_value_to_maybe_address(receipt.to)?,
+        contract_address: convert_json_value_to_maybe_address(receipt.contractAddress)?,
         logs,
     })
 }
 
+static RECEIPT_FETCHES: SingleFlight<Receipt> = SingleFlight::new();
+
 pub fn get_receipt_from_tx_hash(endpoint: &str, tx_hash: &str) -> Result<Receipt> {
-    get_transaction_receipt_json(tx_hash)
-        .and_then(|rpc_json| make_rpc_call(endpoint, rpc_json))
-        .and_then(get_response_text)
-        .and_then(deserialize_to_receipt_rpc_response)
-        .and_then(|res| deserialize_receipt_json_to_receipt_struct(res.result))
+    get_transaction_receipt_json(tx_hash).and_then(|rpc_json| {
+        run_rpc_flight(&RECEIPT_FETCHES, endpoint, rpc_json, |rpc_json| {
+            call_json_rpc(endpoint, rpc_json)
+                .and_then(check_response_text)
+                .and_then(deserialize_to_receipt_rpc_response)
+                .and_then(|res| deserialize_receipt_json_to_receipt_struct(res.result))
+        })
+    })
+}
+
+// NOTE: Checks the batch holds exactly the receipts asked for, putting them back in request order.
+fn get_receipts_from_batched_responses(
+    tx_hashes: &[String],
+    mut responses: Vec<BatchedReceiptRpcResponse>,
+) -> Result<Vec<Receipt>> {
+    responses.sort_by_key(|response| response.id);
+    if responses.len() != tx_hashes.len()
+        || responses
+            .iter()
+            .enumerate()
+            .any(|(i, response)| response.id != i)
+    {
+        return Err(AppError::Custom(format!(
+            "✘ Batched RPC response holds {} receipts, not the {} asked for!",
+            responses.len(),
+            tx_hashes.len()
+        )));
+    }
+    responses
+        .into_iter()
+        .zip(tx_hashes.iter())
+        .map(|(response, tx_hash)| {
+            deserialize_receipt_json_to_receipt_struct(response.result).and_then(|receipt| {
+                match convert_h256_to_prefixed_hex(receipt.transaction_hash)? == *tx_hash {
+                    true => Ok(receipt),
+                    false => Err(AppError::Custom(format!(
+                        "✘ Batched RPC response holds the wrong receipt for tx {}!",
+                        tx_hash
+                    ))),
+                }
+            })
+        })
+        .collect()
 }
 
-fn get_receipts_from_tx_hashes(endpoint: &str, tx_hashes: &[H256]) -> Result<Vec<Receipt>> {
-    tx_hashes
+fn get_receipts_batch_from_tx_hashes(endpoint: &str, tx_hashes: &[String]) -> Result<Vec<Receipt>> {
+    get_transaction_receipts_batch_json(tx_hashes)
+        .and_then(|rpc_json| call_json_rpc(endpoint, rpc_json))
+        .and_then(check_response_text)
+        .and_then(deserialize_to_batched_receipt_rpc_responses)
+        .and_then(|responses| get_receipts_from_batched_responses(tx_hashes, responses))
+}
+
+fn get_receipts_chunk_from_tx_hashes(
+    endpoint: &str,
+    tx_hashes: &[String],
+    batch_tuner: &BatchTuner,
+) -> Result<Vec<Receipt>> {
+    let start_time = Instant::now();
+    let result = match tx_hashes {
+        [tx_hash] => get_receipt_from_tx_hash(endpoint, tx_hash).map(|receipt| vec![receipt]),
+        _ => get_receipts_batch_from_tx_hashes(endpoint, tx_hashes),
+    };
+    batch_tuner.record_batch_result(
+        endpoint,
+        tx_hashes.len(),
+        start_time.elapsed(),
+        result.is_ok(),
+    );
+    result
+}
+
+fn get_receipts_in_batches(
+    endpoint: &str,
+    tx_hashes: &[String],
+    batch_tuner: &BatchTuner,
+) -> Result<Vec<Receipt>> {
+    let mut receipts = Vec::with_capacity(tx_hashes.len());
+    while receipts.len() < tx_hashes.len() {
+        let params = batch_tuner.get_batch_params(endpoint);
+        let batch_size = params.batch_size;
+        let remaining_tx_hashes = &tx_hashes[receipts.len()..];
+        let num_tx_hashes = remaining_tx_hashes
+            .len()
+            .min(batch_size * params.concurrency);
+        let chunks = remaining_tx_hashes[..num_tx_hashes]
+            .chunks(batch_size)
+            .collect::<Vec<&[String]>>();
+        let results = thread::scope(|scope| {
+            chunks
+                .iter()
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        get_receipts_chunk_from_tx_hashes(endpoint, chunk, batch_tuner)
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        Err(AppError::Custom(
+                            "✘ Receipt fetching thread panicked!".to_string(),
+                        ))
+                    })
+                })
+                .collect::<Vec<Result<Vec<Receipt>>>>()
+        });
+        for (chunk, result) in chunks.iter().zip(results) {
+            match result {
+                Ok(chunk_receipts) => receipts.extend(chunk_receipts),
+                Err(e) if chunk.len() == 1 => return Err(e),
+                // NOTE: The tuner caps the batch size below a failed one's, so it's refetched smaller.
+                Err(e) => {
+                    debug!("✘ Batch of {} receipts failed: {}", chunk.len(), e);
+                    break;
+                }
+            }
+        }
+    }
+    Ok(receipts)
+}
+
+static RECEIPTS_FETCHES: SingleFlight<Vec<Receipt>> = SingleFlight::new();
+
+pub fn get_receipts_from_tx_hashes(
+    endpoint: &str,
+    tx_hashes: &[H256],
+    batch_tuner: &BatchTuner,
+) -> Result<Vec<Receipt>> {
+    let _span = info_span!("fetch_receipts", num_receipts = tx_hashes.len()).entered();
+    let tx_hashes = tx_hashes
         .iter()
-        .map(|tx_hash| get_receipt_from_tx_hash(endpoint, &convert_h256_to_prefixed_hex(*tx_hash)?))
-        .collect::<Result<Vec<Receipt>>>()
+        .map(|tx_hash| convert_h256_to_prefixed_hex(*tx_hash))
+        .collect::<Result<Vec<String>>>()?;
+    let key = format!(
+        "{} receipts {}",
+        endpoint,
+        convert_h256_to_prefixed_hex(keccak_hash_bytes(tx_hashes.concat().as_bytes())?)?
+    );
+    run_counted_flight(&RECEIPTS_FETCHES, key, "eth_getTransactionReceipt", || {
+        get_receipts_in_batches(endpoint, &tx_hashes, batch_tuner)
+    })
 }
 
 pub fn get_all_receipts_from_block_in_state_and_set_in_state(state: State) -> Result<State> {
     info!("✔ Getting all receipts from block...");
-    get_receipts_from_tx_hashes(
-        State::get_endpoint_from_state(&state)?,
-        &State::get_block_from_state(&state)?.transactions,
-    )
-    .and_then(|receipts| State::set_receipts_in_state(state, receipts))
+    let endpoint = State::get_endpoint_from_state(&state)?;
+    let block = State::get_block_from_state(&state)?;
+    get_receipts_from_tx_hashes(endpoint, &block.transactions, &state.batch_tuner)
+        .and_then(|receipts| check_receipts_fields(receipts, state.strict_receipts))
+        .and_then(|receipts| {
+            get_hardfork(endpoint, &state.hardfork, block.number)
+                .and_then(|hardfork| apply_hardfork_to_receipts(receipts, hardfork))
+        })
+        .and_then(|receipts| {
+            apply_chain_profile_to_receipts(receipts, *state.get_chain_profile_from_state()?)
+        })
+        .and_then(|receipts| State::set_receipts_in_state(state, receipts))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::batch_tuner::INITIAL_BATCH_SIZE;
+    use crate::json_rpc_client::{remove_json_rpc_client, set_json_rpc_client, MockJsonRpcClient};
     use crate::make_rpc_call::deserialize_to_receipt_rpc_response;
     use crate::test_utils::{
-        assert_receipt_is_correct, get_expected_block, get_valid_state_with_endpoint,
-        get_valid_tx_hash_h256, SAMPLE_RECEIPT_JSON_PATH, SAMPLE_TX_HASH, TX_INDEX,
+        assert_receipt_is_correct, get_expected_block, get_sample_receipts, get_sample_rpc_result,
+        get_sample_tx_hashes_1, get_valid_state_with_endpoint, get_valid_tx_hash_h256,
+        SAMPLE_RECEIPT_JSONS_1_PATH, SAMPLE_RECEIPT_JSON_PATH, SAMPLE_TX_HASH, TX_INDEX,
         WORKING_ENDPOINT,
     };
+    use serde_json::Value as Json;
     use std::fs;
+    use std::sync::Arc;
+    use tiny_http::{Response, Server};
 
     #[test]
     fn should_get_receipt_from_tx_hash() {
@@ -78,6 +342,33 @@ mod tests {
         assert_receipt_is_correct(result)
     }
 
+    #[test]
+    fn should_get_receipt_from_tx_hash_via_mock_json_rpc_client() {
+        let endpoint = "mock://should-get-receipt-from-tx-hash";
+        let client = Arc::new(MockJsonRpcClient::new().with_result(
+            "eth_getTransactionReceipt",
+            get_sample_rpc_result(SAMPLE_RECEIPT_JSON_PATH),
+        ));
+        set_json_rpc_client(endpoint, client.clone());
+        let result = get_receipt_from_tx_hash(endpoint, SAMPLE_TX_HASH);
+        remove_json_rpc_client(endpoint);
+        assert_receipt_is_correct(result.unwrap());
+        assert!(client.get_requests()[0]["params"][0] == SAMPLE_TX_HASH);
+    }
+
+    #[test]
+    fn should_fail_to_get_receipt_unknown_to_mock_json_rpc_client() {
+        let endpoint = "mock://should-fail-to-get-unknown-receipt";
+        let client = MockJsonRpcClient::new().with_result("eth_getTransactionReceipt", Json::Null);
+        set_json_rpc_client(endpoint, Arc::new(client));
+        let result = get_receipt_from_tx_hash(endpoint, SAMPLE_TX_HASH);
+        remove_json_rpc_client(endpoint);
+        match result {
+            Err(AppError::Custom(message)) => assert!(message.contains("No receipt found")),
+            _ => panic!("Should fail to get receipt the node doesn't have!"),
+        }
+    }
+
     #[test]
     fn should_deserialize_receipt_json_to_receipt_struct_correctly() {
         let receipt_string = fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap();
@@ -86,17 +377,228 @@ mod tests {
         assert_receipt_is_correct(result)
     }
 
+    #[test]
+    fn should_deserialize_pre_byzantium_receipt_json_with_root_and_no_status() {
+        let root = "0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee0000";
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"].as_object_mut().unwrap().remove("status");
+        json["result"]["root"] = serde_json::json!(root);
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        let result = deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap();
+        assert!(!result.status);
+        assert!(result.root == Some(convert_hex_to_h256(root.to_string()).unwrap()));
+    }
+
+    #[test]
+    fn should_fail_to_deserialize_receipt_json_with_unknown_status() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"]["status"] = serde_json::json!("0x2");
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        match deserialize_receipt_json_to_receipt_struct(receipt_json.result) {
+            Err(crate::errors::AppError::Custom(e)) => {
+                assert!(e.contains("Unknown receipt status"))
+            }
+            _ => panic!("Should not deserialize receipt json with unknown status!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_deserialize_receipt_json_with_tx_type_over_a_byte() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"]["type"] = serde_json::json!("0x17e");
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        match deserialize_receipt_json_to_receipt_struct(receipt_json.result) {
+            Err(crate::errors::AppError::Custom(e)) => assert!(e.contains("tx type")),
+            _ => panic!("Should not deserialize receipt json with tx type over a byte!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_deserialize_receipt_json_with_mangled_logs_bloom() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"]["logsBloom"] = serde_json::json!(format!("0x{}", "00".repeat(256)));
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        match deserialize_receipt_json_to_receipt_struct(receipt_json.result) {
+            Err(crate::errors::AppError::Custom(e)) => assert!(e.contains("logs bloom")),
+            _ => panic!("Should not deserialize receipt json with mangled logs bloom!"),
+        }
+    }
+
+    #[test]
+    fn should_deserialize_optimism_deposit_receipt_json() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"]["type"] = serde_json::json!("0x7e");
+        json["result"]["depositNonce"] = serde_json::json!("0x539");
+        json["result"]["depositReceiptVersion"] = serde_json::json!("0x1");
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        let result = deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap();
+        assert!(result.tx_type == 0x7e);
+        assert!(result.deposit_nonce == Some(1337.into()));
+        assert!(result.deposit_receipt_version == Some(1.into()));
+    }
+
+    #[test]
+    fn should_deserialize_receipt_json_with_effective_gas_price_to_serializable_receipt() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"]["effectiveGasPrice"] = serde_json::json!("0x3b9aca00");
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        let result = deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap();
+        assert!(result.rpc_fields.effective_gas_price == Some(1_000_000_000.into()));
+        let serialized = serde_json::to_string(&result).unwrap();
+        let deserialized: Receipt = serde_json::from_str(&serialized).unwrap();
+        assert!(deserialized == result);
+        assert!(deserialized.logs[0].topics == result.logs[0].topics);
+    }
+
+    #[test]
+    fn should_keep_unknown_receipt_json_fields_through_re_serialization() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        json["result"]["type"] = serde_json::json!("0x3");
+        json["result"]["blobGasUsed"] = serde_json::json!("0x20000");
+        json["result"]["blobGasPrice"] = serde_json::json!("0x1");
+        json["result"]["l1Fee"] = serde_json::json!("0x2a");
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        let result = deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap();
+        assert!(check_receipt_fields(&result, true).is_err());
+        assert!(check_receipt_fields(&result, false).is_ok());
+        assert!(result.tx_type == 3);
+        assert!(result.rpc_fields.blob_gas_used == Some(131_072.into()));
+        assert!(result.rpc_fields.blob_gas_price == Some(1.into()));
+        assert!(result.rpc_fields.other["l1Fee"] == "0x2a");
+        let deserialized: Receipt =
+            serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+        assert!(deserialized == result);
+        // NOTE: RPC fields are never part of the receipt's encoding.
+        let consensus_receipt = Receipt {
+            rpc_fields: ReceiptRpcFields::default(),
+            ..result.clone()
+        };
+        assert!(rlp::encode(&result) == rlp::encode(&consensus_receipt));
+    }
+
+    #[test]
+    fn should_refuse_receipt_w_unknown_fields_only_when_strict() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap()).unwrap();
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        let receipt = deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap();
+        assert!(check_receipt_fields(&receipt, true).is_ok());
+        json["result"]["someFutureField"] = serde_json::json!("0x1");
+        let receipt_json = deserialize_to_receipt_rpc_response(json.to_string()).unwrap();
+        let receipt = deserialize_receipt_json_to_receipt_struct(receipt_json.result).unwrap();
+        assert!(check_receipt_fields(&receipt, false).is_ok());
+        match check_receipts_fields(vec![receipt], true) {
+            Err(AppError::Custom(message)) => assert!(message.contains("someFutureField")),
+            _ => panic!("Should refuse receipt w/ unknown fields when strict!"),
+        }
+    }
+
     #[test]
     fn should_get_receipts_from_tx_hashes_correctly() {
         let tx_hash_h256 = get_valid_tx_hash_h256().unwrap();
         let mut tx_hashes = Vec::new();
         tx_hashes.push(tx_hash_h256);
         tx_hashes.push(tx_hash_h256);
-        let result = get_receipts_from_tx_hashes(WORKING_ENDPOINT, &tx_hashes).unwrap();
+        let result =
+            get_receipts_from_tx_hashes(WORKING_ENDPOINT, &tx_hashes, &BatchTuner::default())
+                .unwrap();
         assert_receipt_is_correct(result[0].clone());
         assert_receipt_is_correct(result[1].clone());
     }
 
+    // NOTE: Serves the sample receipts, batched in reverse order, or rejects batches if told to.
+    fn serve_sample_receipts(
+        rejects_batches: bool,
+    ) -> (String, Arc<Server>, thread::JoinHandle<()>) {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let endpoint = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let get_response = |rpc_json: &Json| {
+            let path = format!(
+                "{}{}",
+                SAMPLE_RECEIPT_JSONS_1_PATH,
+                rpc_json["params"][0].as_str().unwrap()
+            );
+            let mut response: Json =
+                serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            response["id"] = rpc_json["id"].clone();
+            response
+        };
+        let server_clone = server.clone();
+        let handle = thread::spawn(move || {
+            for mut request in server_clone.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let rpc_json: Json = serde_json::from_str(&body).unwrap();
+                let response = match (rpc_json.as_array(), rejects_batches) {
+                    (Some(_), true) => {
+                        json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "batches not supported"}})
+                    }
+                    (Some(batch), false) => {
+                        Json::Array(batch.iter().rev().map(get_response).collect())
+                    }
+                    (None, _) => get_response(&rpc_json),
+                };
+                request
+                    .respond(Response::from_string(response.to_string()))
+                    .unwrap();
+            }
+        });
+        (endpoint, server, handle)
+    }
+
+    fn get_sample_tx_hashes_1_h256() -> Vec<H256> {
+        get_sample_tx_hashes_1()
+            .into_iter()
+            .map(|tx_hash| convert_hex_to_h256(tx_hash).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn should_get_receipts_from_tx_hashes_in_batches() {
+        let batch_tuner = BatchTuner::default();
+        let (endpoint, server, handle) = serve_sample_receipts(false);
+        let result =
+            get_receipts_from_tx_hashes(&endpoint, &get_sample_tx_hashes_1_h256(), &batch_tuner);
+        server.unblock();
+        handle.join().unwrap();
+        let expected_result = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        assert!(result.unwrap() == expected_result);
+        assert!(batch_tuner.get_batch_params(&endpoint).batch_size > INITIAL_BATCH_SIZE);
+    }
+
+    #[test]
+    fn should_fall_back_to_unbatched_receipts_if_batches_are_rejected() {
+        let batch_tuner = BatchTuner::default();
+        let (endpoint, server, handle) = serve_sample_receipts(true);
+        let result =
+            get_receipts_from_tx_hashes(&endpoint, &get_sample_tx_hashes_1_h256(), &batch_tuner);
+        server.unblock();
+        handle.join().unwrap();
+        assert!(result.unwrap().len() == get_sample_tx_hashes_1().len());
+        assert!(batch_tuner.get_batch_params(&endpoint).batch_size == 1);
+    }
+
+    #[test]
+    fn should_fail_to_get_receipts_from_incomplete_batched_responses() {
+        let tx_hashes = get_sample_tx_hashes_1();
+        let path = format!("{}{}", SAMPLE_RECEIPT_JSONS_1_PATH, tx_hashes[0]);
+        let mut response: Json = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        response["id"] = json!(0);
+        let responses =
+            deserialize_to_batched_receipt_rpc_responses(format!("[{}]", response)).unwrap();
+        assert!(get_receipts_from_batched_responses(&tx_hashes[..2], responses).is_err());
+    }
+
     #[test]
     #[ignore] // ~100 receipts to get ∴ too expensive! Run w/ cargo +nightly test --ignored
     fn should_get_all_receipts_and_set_in_state() {
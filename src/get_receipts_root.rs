@@ -0,0 +1,126 @@
+use crate::errors::AppError;
+use crate::get_endpoint::get_endpoint;
+use crate::get_receipts_trie::get_block_and_receipts_trie;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::trie::Trie;
+use crate::trie_root_audit::maybe_bisect_root_mismatch;
+use crate::types::{Block, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+use crate::write_output::write_output;
+
+pub fn format_receipts_root(block: &Block, trie: &Trie, format: OutputFormat) -> Result<String> {
+    let computed_root = convert_h256_to_prefixed_hex(trie.root)?;
+    let header_root = convert_h256_to_prefixed_hex(block.receipts_root)?;
+    info!("✔ Computed receipts root: {}", computed_root);
+    info!("✔ Header receipts root: {}", header_root);
+    Ok(match format {
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => {
+            format!("computed: {}\nheader:   {}", computed_root, header_root)
+        }
+        OutputFormat::Json | OutputFormat::Eip1186 => json!({
+            "computed_receipts_root": computed_root,
+            "header_receipts_root": header_root,
+            "matches": trie.root == block.receipts_root,
+            "block_number": block.number.as_u64(),
+            "block_hash": convert_h256_to_prefixed_hex(block.hash)?,
+        })
+        .to_string(),
+    })
+}
+
+pub fn check_receipts_roots_match(block: &Block, trie: &Trie) -> Result<()> {
+    match trie.root == block.receipts_root {
+        true => {
+            info!("✔ Computed receipts root matches the block header!");
+            Ok(())
+        }
+        false => Err(AppError::Custom(format!(
+            "✘ Receipts root mismatch in block {}!\n✘ Computed: {}\n✘ Header:   {}",
+            block.number,
+            convert_h256_to_prefixed_hex(trie.root)?,
+            convert_h256_to_prefixed_hex(block.receipts_root)?,
+        ))),
+    }
+}
+
+pub fn get_receipts_root(cli_args: &CliArgs, block_id: &str) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    get_block_and_receipts_trie(&endpoint, block_id, cli_args).and_then(|(block, trie)| {
+        let output = format_receipts_root(&block, &trie, cli_args.format)?;
+        match check_receipts_roots_match(&block, &trie) {
+            Ok(_) => Ok(output),
+            Err(e) => write_output(&output, &cli_args.output)
+                .and_then(|_| {
+                    maybe_bisect_root_mismatch(
+                        &endpoint,
+                        &block,
+                        &cli_args.audit_trie_roots,
+                        &cli_args.telemetry,
+                    )
+                })
+                .and_then(|maybe_finding| match maybe_finding {
+                    Some(finding) => Err(AppError::Custom(format!("{}\n{}", e, finding))),
+                    None => Err(e),
+                }),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        get_expected_block, get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1,
+        RECEIPTS_ROOT_1, SAMPLE_BLOCK_HASH, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    fn get_sample_trie() -> Trie {
+        get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+    }
+
+    #[test]
+    fn should_format_receipts_roots_as_hex() {
+        let block = get_expected_block();
+        let result = format_receipts_root(&block, &get_sample_trie(), OutputFormat::Hex).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert!(lines.len() == 2);
+        assert!(lines[0].ends_with(RECEIPTS_ROOT_1));
+        assert!(lines[1].ends_with(&convert_h256_to_prefixed_hex(block.receipts_root).unwrap()));
+    }
+
+    #[test]
+    fn should_format_receipts_roots_as_json() {
+        let result = format_receipts_root(
+            &get_expected_block(),
+            &get_sample_trie(),
+            OutputFormat::Json,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(json["computed_receipts_root"] == RECEIPTS_ROOT_1);
+        assert!(json["matches"] == false);
+        assert!(json["block_hash"] == SAMPLE_BLOCK_HASH);
+    }
+
+    #[test]
+    fn should_pass_receipts_roots_check_if_roots_match() {
+        let trie = get_sample_trie();
+        let mut block = get_expected_block();
+        block.receipts_root = trie.root;
+        assert!(check_receipts_roots_match(&block, &trie).is_ok());
+    }
+
+    #[test]
+    fn should_fail_receipts_roots_check_if_roots_mismatch() {
+        match check_receipts_roots_match(&get_expected_block(), &get_sample_trie()) {
+            Err(AppError::Custom(e)) => {
+                assert!(e.contains("mismatch"));
+                assert!(e.contains(RECEIPTS_ROOT_1));
+            }
+            _ => panic!("Receipts roots should not match!"),
+        }
+    }
+}
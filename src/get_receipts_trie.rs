@@ -1,29 +1,253 @@
-use crate::rlp_codec::get_rlp_encoded_receipts_and_nibble_tuples;
+use crate::chain_profile::apply_chain_profile_to_receipts;
+use crate::cross_check::{maybe_cross_check_block, maybe_cross_check_receipts_trie};
+use crate::errors::AppError;
+use crate::get_block::{
+    check_block_hash_is_canonical, get_block_by_block_id, get_canonical_block_hash,
+};
+use crate::get_receipts::{check_receipts_fields, get_receipts_from_tx_hashes};
+use crate::hardfork::{apply_hardfork_to_receipts, get_hardfork};
+use crate::metrics::{time_histogram, TRIE_BUILD_DURATION_SECONDS};
+use crate::parse_cli_args::CliArgs;
+use crate::rlp_codec::{get_raw_receipts_and_key_tuples, get_rlp_encoded_receipts_and_key_tuples};
 use crate::state::State;
 use crate::trie::{put_in_trie_recursively, Trie};
-use crate::types::{Receipt, Result};
+use crate::trie_checkpoint::{put_in_trie_w_checkpoints, TRIE_CHECKPOINT_INTERVAL};
+use crate::trie_root_audit::put_in_trie_w_root_audit;
+use crate::trie_snapshot::{
+    maybe_cache_receipts_trie, maybe_get_cached_receipts_trie, remove_cached_receipts_trie,
+};
+use crate::trusted_headers::{
+    maybe_check_block_against_trusted_headers, maybe_read_trusted_headers_file, TrustedHeaders,
+};
+use crate::types::{Block, Bytes, Receipt, Result};
+use crate::utils::{convert_h256_to_prefixed_hex, get_duplicates_with_positions};
+use ethereum_types::{H256, U256};
+use tracing::info_span;
 
-fn get_receipts_trie_from_receipts(receipts: &[Receipt]) -> Result<Trie> {
-    get_rlp_encoded_receipts_and_nibble_tuples(receipts).and_then(|key_value_tuples| {
-        put_in_trie_recursively(Trie::get_new_trie()?, key_value_tuples, 0)
+// NOTE: Receipts sharing a tx index share a key too, so all but one would silently be lost.
+pub fn check_receipts_have_no_duplicate_indices(receipts: &[Receipt]) -> Result<()> {
+    let indices = receipts
+        .iter()
+        .map(|receipt| receipt.transaction_index)
+        .collect::<Vec<U256>>();
+    let duplicates = get_duplicates_with_positions(&indices)
+        .into_iter()
+        .map(|(index, positions)| {
+            Ok(format!(
+                "Tx index {} in receipts of tx hashes: {}",
+                index,
+                positions
+                    .iter()
+                    .map(|position| {
+                        convert_h256_to_prefixed_hex(receipts[*position].transaction_hash)
+                    })
+                    .collect::<Result<Vec<String>>>()?
+                    .join(", ")
+            ))
+        })
+        .collect::<Result<Vec<String>>>()?;
+    match duplicates.is_empty() {
+        true => Ok(()),
+        false => Err(AppError::DuplicateTxInBlock(duplicates)),
+    }
+}
+
+pub fn get_receipts_trie_from_receipts(receipts: &[Receipt]) -> Result<Trie> {
+    let _span = info_span!("build_receipts_trie", num_receipts = receipts.len()).entered();
+    check_receipts_have_no_duplicate_indices(receipts)?;
+    time_histogram(TRIE_BUILD_DURATION_SECONDS, &[], || {
+        get_rlp_encoded_receipts_and_key_tuples(receipts).and_then(|key_value_tuples| {
+            put_in_trie_recursively(Trie::get_new_trie()?, key_value_tuples, 0)
+        })
+    })
+}
+
+/**
+ * Raw Receipts:
+ *
+ * Archive tooling, e.g. that exporting from an Erigon snapshot, already holds
+ * a block's receipts RLP encoded, typed ones prefixed by their type byte. So
+ * rather than round trip them through JSON, this builds the receipts trie
+ * from those encodings directly. They must be in tx index order, since that's
+ * what keys them, & each is decoded once purely to reject junk early.
+ */
+pub fn build_receipts_trie_from_raw(raw_receipts: Vec<Bytes>) -> Result<Trie> {
+    let _span = info_span!("build_receipts_trie", num_receipts = raw_receipts.len()).entered();
+    time_histogram(TRIE_BUILD_DURATION_SECONDS, &[], || {
+        get_raw_receipts_and_key_tuples(raw_receipts).and_then(|key_value_tuples| {
+            put_in_trie_recursively(Trie::get_new_trie()?, key_value_tuples, 0)
+        })
+    })
+}
+
+// NOTE: As above, but checkpointing the build to the trie cache dir, if any, so it can resume,
+// or auditing the root after each insertion w/ `--audit-trie-roots`, in which case it doesn't.
+pub fn get_receipts_trie_of_block_from_receipts(
+    block: &Block,
+    receipts: &[Receipt],
+    maybe_audit_path: &Option<String>,
+    maybe_cache_dir: &Option<String>,
+) -> Result<Trie> {
+    let _span = info_span!("build_receipts_trie", num_receipts = receipts.len()).entered();
+    check_receipts_have_no_duplicate_indices(receipts)?;
+    time_histogram(TRIE_BUILD_DURATION_SECONDS, &[], || {
+        get_rlp_encoded_receipts_and_key_tuples(receipts).and_then(|key_value_tuples| {
+            match maybe_audit_path {
+                Some(path) => put_in_trie_w_root_audit(block, key_value_tuples, path),
+                None => put_in_trie_w_checkpoints(
+                    maybe_cache_dir,
+                    block,
+                    key_value_tuples,
+                    TRIE_CHECKPOINT_INTERVAL,
+                ),
+            }
+        })
     })
 }
 
 pub fn get_receipts_trie_and_set_in_state(state: State) -> Result<State> {
     info!("✔ Building merkle-patricia trie from receipts...");
-    get_receipts_trie_from_receipts(state.get_receipts_from_state()?)
-        .and_then(|trie| state.set_receipts_trie_in_state(trie))
+    let receipts = state.get_receipts_from_state()?;
+    // NOTE: Only builds of a known block can be checkpointed, since checkpoints are per block.
+    match state.get_block_from_state() {
+        Ok(block) => get_receipts_trie_of_block_from_receipts(
+            block,
+            receipts,
+            &state.trie_root_audit_path,
+            &state.trie_cache_dir,
+        ),
+        Err(_) => get_receipts_trie_from_receipts(receipts),
+    }
+    .and_then(|trie| {
+        let lenient_lookups = state.lenient_lookups;
+        state.set_receipts_trie_in_state(trie.set_lenient_lookups(lenient_lookups))
+    })
+}
+
+pub fn get_block_and_receipts_trie(
+    endpoint: &str,
+    block_id: &str,
+    cli_args: &CliArgs,
+) -> Result<(Block, Trie)> {
+    let maybe_trusted_headers = maybe_read_trusted_headers_file(&cli_args.trusted_headers)?;
+    let block = get_block_by_block_id(endpoint, block_id)?;
+    get_receipts_trie_of_block(endpoint, &block, cli_args, &maybe_trusted_headers)
+        .map(|trie| (block, trie))
+}
+
+// NOTE: A cached trie outlives any reorg of its block, so must be dropped once its block isn't canonical.
+pub fn check_cached_receipts_trie_block_is_canonical(
+    maybe_cache_dir: &Option<String>,
+    block: &Block,
+    canonical_block_hash: H256,
+) -> Result<()> {
+    check_block_hash_is_canonical(block, canonical_block_hash)
+        .inspect_err(|_| remove_cached_receipts_trie(maybe_cache_dir, block))
+}
+
+pub fn maybe_get_cached_receipts_trie_of_block(
+    endpoint: &str,
+    block: &Block,
+    cli_args: &CliArgs,
+    maybe_trusted_headers: &Option<TrustedHeaders>,
+) -> Result<Option<Trie>> {
+    maybe_cross_check_block(block, &cli_args.cross_check)?;
+    maybe_check_block_against_trusted_headers(endpoint, block, maybe_trusted_headers)?;
+    match maybe_get_cached_receipts_trie(&cli_args.cache_dir, block) {
+        Some(trie) => get_canonical_block_hash(endpoint, block.number.as_u64())
+            .and_then(|canonical_block_hash| {
+                check_cached_receipts_trie_block_is_canonical(
+                    &cli_args.cache_dir,
+                    block,
+                    canonical_block_hash,
+                )
+            })
+            .map(|_| Some(trie)),
+        None => Ok(None),
+    }
+}
+
+pub fn get_receipts_of_block(
+    endpoint: &str,
+    block: &Block,
+    cli_args: &CliArgs,
+) -> Result<Vec<Receipt>> {
+    let hardfork = get_hardfork(endpoint, &cli_args.hardfork, block.number)?;
+    info!(
+        "✔ Getting all {} receipts from block...",
+        block.transactions.len()
+    );
+    get_receipts_from_tx_hashes(endpoint, &block.transactions, &cli_args.batch_tuner)
+        .and_then(|receipts| check_receipts_fields(receipts, cli_args.strict_receipts))
+        .and_then(|receipts| apply_hardfork_to_receipts(receipts, hardfork))
+        .and_then(|receipts| apply_chain_profile_to_receipts(receipts, cli_args.chain))
+}
+
+pub fn build_receipts_trie_of_block(
+    block: &Block,
+    receipts: &[Receipt],
+    cli_args: &CliArgs,
+) -> Result<Trie> {
+    info!("✔ Building merkle-patricia trie from receipts...");
+    get_receipts_trie_of_block_from_receipts(
+        block,
+        receipts,
+        &cli_args.audit_trie_roots,
+        &cli_args.cache_dir,
+    )
+    .and_then(|trie| {
+        maybe_cross_check_receipts_trie(block, &trie, &cli_args.cross_check).and(Ok(trie))
+    })
+    .inspect(|trie| maybe_cache_receipts_trie(&cli_args.cache_dir, block, trie))
+}
+
+pub fn get_receipts_trie_of_block(
+    endpoint: &str,
+    block: &Block,
+    cli_args: &CliArgs,
+    maybe_trusted_headers: &Option<TrustedHeaders>,
+) -> Result<Trie> {
+    if let Some(trie) =
+        maybe_get_cached_receipts_trie_of_block(endpoint, block, cli_args, maybe_trusted_headers)?
+    {
+        return Ok(trie);
+    }
+    get_receipts_of_block(endpoint, block, cli_args)
+        .and_then(|receipts| build_receipts_trie_of_block(block, &receipts, cli_args))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::get_branch_from_trie::get_branch_from_trie;
+    use crate::rlp_codec::get_rlp_encoded_receipt_and_encoded_key_tuple;
     use crate::test_utils::{
-        get_sample_receipts, get_sample_tx_hashes_1, get_sample_tx_hashes_2,
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, get_sample_tx_hashes_2,
         get_valid_initial_state, RECEIPTS_ROOT_1, RECEIPTS_ROOT_2, SAMPLE_RECEIPT_JSONS_1_PATH,
         SAMPLE_RECEIPT_JSONS_2_PATH,
     };
-    use crate::utils::convert_h256_to_prefixed_hex;
+    use crate::utils::decode_prefixed_hex;
+    use crate::verifier::verify_receipt_proof;
+    use ethereum_types::Address;
+    use std::fs;
+
+    #[test]
+    fn should_error_on_receipts_with_duplicate_indices() {
+        let mut receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        receipts[1].transaction_index = receipts[0].transaction_index;
+        match get_receipts_trie_from_receipts(&receipts) {
+            Err(AppError::DuplicateTxInBlock(duplicates)) => {
+                assert!(duplicates.len() == 1);
+                assert!(duplicates[0].contains(
+                    &convert_h256_to_prefixed_hex(receipts[1].transaction_hash).unwrap()
+                ));
+            }
+            _ => panic!("Should error on receipts w/ duplicate indices!"),
+        }
+    }
 
     #[test]
     fn should_get_receipts_trie_1_from_receipts() {
@@ -36,6 +260,32 @@ mod tests {
         assert!(root_hex == RECEIPTS_ROOT_1);
     }
 
+    #[test]
+    fn should_build_receipts_trie_1_from_raw_receipts() {
+        let mut receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        let raw_receipts = get_rlp_encoded_receipts_and_key_tuples(&receipts)
+            .unwrap()
+            .into_iter()
+            .map(|(_, raw_receipt)| raw_receipt)
+            .collect::<Vec<Bytes>>();
+        let result = build_receipts_trie_from_raw(raw_receipts).unwrap();
+        let root_hex = convert_h256_to_prefixed_hex(result.root).unwrap();
+        assert!(root_hex == RECEIPTS_ROOT_1);
+    }
+
+    #[test]
+    fn should_error_building_receipts_trie_from_invalid_raw_receipts() {
+        let raw_receipts = vec![vec![0x02, 0xc0], vec![0xde, 0xad]];
+        match build_receipts_trie_from_raw(raw_receipts) {
+            Err(AppError::Custom(message)) => assert!(message.contains("Raw receipt #0")),
+            _ => panic!("Should error on invalid raw receipts!"),
+        }
+    }
+
     #[test]
     fn should_get_receipts_trie_1_from_state() {
         let state = get_valid_initial_state().unwrap();
@@ -74,4 +324,72 @@ mod tests {
         let root_hex = convert_h256_to_prefixed_hex(trie_from_state.root).unwrap();
         assert!(root_hex == RECEIPTS_ROOT_2);
     }
+
+    #[test]
+    fn should_prove_contract_creation_receipt_in_receipts_trie_2() {
+        let receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_2_PATH.to_string(),
+            get_sample_tx_hashes_2(),
+        );
+        let creation_receipt = receipts
+            .iter()
+            .find(|receipt| receipt.to.is_none())
+            .unwrap();
+        let tx_index = creation_receipt.transaction_index.as_usize();
+        let trie = get_receipts_trie_from_receipts(&receipts).unwrap();
+        let proof = get_branch_from_trie(&trie, tx_index)
+            .unwrap()
+            .iter()
+            .map(|node| node.get_rlp_encoding())
+            .collect::<Result<Vec<Bytes>>>()
+            .unwrap();
+        let result = verify_receipt_proof(trie.root.as_fixed_bytes(), tx_index, &proof).unwrap();
+        let expected_address = "0x405b13e1e2922b8cbd2c9b4ec66c1314f94ee320";
+        assert!(tx_index == 0x16);
+        assert!(
+            creation_receipt.contract_address
+                == Some(Address::from_slice(
+                    &decode_prefixed_hex(expected_address.to_string()).unwrap()
+                ))
+        );
+        assert!(
+            result
+                == get_rlp_encoded_receipt_and_encoded_key_tuple(creation_receipt)
+                    .unwrap()
+                    .1
+        );
+    }
+
+    #[test]
+    fn should_remove_cached_receipts_trie_of_reorged_block() {
+        let dir = std::env::temp_dir().join("rrpm-reorged-trie-cache");
+        let trie = get_receipts_trie_from_receipts(&get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        ))
+        .unwrap();
+        let block = Block {
+            receipts_root: trie.root,
+            ..get_expected_block()
+        };
+        let cache_dir = Some(dir.to_str().unwrap().to_string());
+        maybe_cache_receipts_trie(&cache_dir, &block, &trie);
+        let canonical_result =
+            check_cached_receipts_trie_block_is_canonical(&cache_dir, &block, block.hash);
+        let kept_trie = maybe_get_cached_receipts_trie(&cache_dir, &block);
+        let reorged_result = check_cached_receipts_trie_block_is_canonical(
+            &cache_dir,
+            &block,
+            H256::from_low_u64_be(1),
+        );
+        let removed_trie = maybe_get_cached_receipts_trie(&cache_dir, &block);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(canonical_result.is_ok());
+        assert!(kept_trie.is_some());
+        match reorged_result {
+            Err(AppError::Custom(e)) => assert!(e.contains("has been reorged out")),
+            _ => panic!("Should error for cached trie of reorged block!"),
+        }
+        assert!(removed_trie.is_none());
+    }
 }
@@ -19,6 +19,51 @@ pub fn get_block_by_block_number_json(block_number: String) -> Result<Value> {
     }))
 }
 
+pub fn get_balance_json(address: &str, block_number: String) -> Result<Value> {
+    Ok(json!({
+        "id": "1",
+        "jsonrpc": "2.0",
+        "method": "eth_getBalance",
+        "params": [ address, block_number ],
+    }))
+}
+
+pub fn get_chain_id_json() -> Result<Value> {
+    Ok(json!({
+        "id": "1",
+        "jsonrpc": "2.0",
+        "method": "eth_chainId",
+        "params": [],
+    }))
+}
+
+pub fn get_net_version_json() -> Result<Value> {
+    Ok(json!({
+        "id": "1",
+        "jsonrpc": "2.0",
+        "method": "net_version",
+        "params": [],
+    }))
+}
+
+pub fn get_proof_json(address: &str, slot: &str, block_number: String) -> Result<Value> {
+    Ok(json!({
+        "id": "1",
+        "jsonrpc": "2.0",
+        "method": "eth_getProof",
+        "params": [ address, [ slot ], block_number ],
+    }))
+}
+
+pub fn get_raw_receipts_json(block_number: String) -> Result<Value> {
+    Ok(json!({
+        "id": "1",
+        "jsonrpc": "2.0",
+        "method": "debug_getRawReceipts",
+        "params": [ block_number ],
+    }))
+}
+
 pub fn get_transaction_receipt_json(tx_hash: &str) -> Result<Value> {
     Ok(json!({
         "id": "1",
@@ -28,6 +73,22 @@ pub fn get_transaction_receipt_json(tx_hash: &str) -> Result<Value> {
     }))
 }
 
+// NOTE: Ids are the requests' positions, since batched responses may come back in any order.
+pub fn get_transaction_receipts_batch_json(tx_hashes: &[String]) -> Result<Value> {
+    Ok(Value::Array(
+        tx_hashes
+            .iter()
+            .enumerate()
+            .map(|(id, tx_hash)| {
+                get_transaction_receipt_json(tx_hash).map(|mut rpc_json| {
+                    rpc_json["id"] = json!(id);
+                    rpc_json
+                })
+            })
+            .collect::<Result<Vec<Value>>>()?,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,4 +127,45 @@ mod tests {
         assert!("\"eth_getTransactionReceipt\"" == result["method"].to_string());
         assert!(expected_result == result["params"][0].to_string());
     }
+
+    #[test]
+    fn should_get_transaction_receipts_batch_json_w_positional_ids() {
+        let dummy_hashes = vec!["0xc0ffee".to_string(), "0xdecaf".to_string()];
+        let result = get_transaction_receipts_batch_json(&dummy_hashes).unwrap();
+        assert!(result.as_array().unwrap().len() == 2);
+        assert!(result[1]["id"] == 1);
+        assert!(result[1]["method"] == "eth_getTransactionReceipt");
+        assert!(result[1]["params"][0] == "0xdecaf");
+    }
+
+    #[test]
+    fn should_get_proof_json_correctly() {
+        let result = get_proof_json("0xc0ffee", "0x00", "0x539".to_string()).unwrap();
+        assert!(result["method"] == "eth_getProof");
+        assert!(result["params"][0] == "0xc0ffee");
+        assert!(result["params"][1] == json!(["0x00"]));
+        assert!(result["params"][2] == "0x539");
+    }
+
+    #[test]
+    fn should_get_balance_json_correctly() {
+        let result = get_balance_json("0xc0ffee", "0x539".to_string()).unwrap();
+        assert!(result["method"] == "eth_getBalance");
+        assert!(result["params"][0] == "0xc0ffee");
+        assert!(result["params"][1] == "0x539");
+    }
+
+    #[test]
+    fn should_get_chain_id_json_correctly() {
+        let result = get_chain_id_json().unwrap();
+        assert!(result["method"] == "eth_chainId");
+        assert!(result["params"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_get_net_version_json_correctly() {
+        let result = get_net_version_json().unwrap();
+        assert!(result["method"] == "net_version");
+        assert!(result["params"].as_array().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,315 @@
+use crate::block_header::rlp_encode_block_header;
+use crate::chain_profile::LEGACY_TX_TYPE;
+use crate::constants::HASHED_NULL_NODE;
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+use crate::logs_bloom::get_logs_bloom_from_logs;
+use crate::proof_bundle::{get_proof_bundle, get_proof_bundle_json, ProofBundle};
+use crate::rlp_codec::RLP_EMPTY_LIST;
+use crate::types::{Block, Byte, Log, Receipt, ReceiptRpcFields, Result};
+use crate::verify_proof::verify_proof_bundle;
+use ethereum_types::{Address, Bloom, H256, U256};
+use std::fs;
+use std::path::Path;
+
+const EIP_2930_TX_TYPE: Byte = 1;
+const EIP_1559_TX_TYPE: Byte = 2;
+const EIP_4844_TX_TYPE: Byte = 3;
+const FIRST_VECTOR_BLOCK_NUMBER: u64 = 20_000_000;
+const LARGE_BLOCK_NUM_RECEIPTS: usize = 200;
+const HUGE_RECEIPT_NUM_LOGS: usize = 128;
+const HUGE_RECEIPT_LOG_DATA_LENGTH: usize = 1024;
+
+/**
+ * Golden Vectors:
+ *
+ * A curated set of receipt proofs covering the edge shapes a verifier must
+ * handle, for teams writing verifiers in other languages to test theirs
+ * against. Each vector is built from a synthetic block, so the set is fully
+ * deterministic & needs no RPC access. Each is written as a full JSON proof
+ * bundle, exactly as `prove-receipt --format json` outputs them, alongside an
+ * `index.json` describing them. Note there is no inline node vector: every
+ * receipt's encoding includes its 256-byte logs bloom, so no node of a
+ * receipts trie can ever be under the 32 bytes needed to be inlined.
+ */
+struct GoldenVector {
+    name: &'static str,
+    description: &'static str,
+    receipts: Vec<Receipt>,
+    index: usize,
+}
+
+fn get_synthetic_hash(label: &str, seed: usize) -> Result<H256> {
+    keccak_hash_bytes(format!("golden-vector-{}-{}", label, seed).as_bytes())
+}
+
+fn get_synthetic_log(seed: usize, data_length: usize) -> Result<Log> {
+    Ok(Log {
+        address: Address::from_low_u64_be(seed as u64 + 1),
+        topics: vec![
+            get_synthetic_hash("topic-0", seed)?,
+            get_synthetic_hash("topic-1", seed)?,
+        ],
+        data: vec![(seed % 256) as Byte; data_length],
+    })
+}
+
+fn get_synthetic_receipts(
+    tx_types: &[Byte],
+    huge_receipt_index: Option<usize>,
+) -> Result<Vec<Receipt>> {
+    tx_types
+        .iter()
+        .enumerate()
+        .map(|(index, tx_type)| {
+            let logs = match huge_receipt_index == Some(index) {
+                true => (0..HUGE_RECEIPT_NUM_LOGS)
+                    .map(|seed| get_synthetic_log(seed, HUGE_RECEIPT_LOG_DATA_LENGTH))
+                    .collect::<Result<Vec<Log>>>()?,
+                false => vec![get_synthetic_log(index, 32)?],
+            };
+            Ok(Receipt {
+                to: Some(Address::from_low_u64_be(0xc0ffee)),
+                from: Address::from_low_u64_be(0xdecaf),
+                status: true,
+                gas_used: U256::from(50_000),
+                block_hash: H256::zero(),
+                transaction_hash: get_synthetic_hash("tx", index)?,
+                cumulative_gas_used: U256::from(50_000 * (index + 1)),
+                block_number: U256::zero(),
+                transaction_index: U256::from(index),
+                contract_address: None,
+                logs_bloom: get_logs_bloom_from_logs(&logs)?,
+                logs,
+                root: None,
+                tx_type: *tx_type,
+                deposit_nonce: None,
+                deposit_receipt_version: None,
+                rpc_fields: ReceiptRpcFields::default(),
+            })
+        })
+        .collect()
+}
+
+// NOTE: A Cancun shaped header, w/ its hash computed so the vector's proof bundle embeds it.
+fn get_synthetic_block(
+    block_number: u64,
+    receipts: &[Receipt],
+    receipts_root: H256,
+) -> Result<Block> {
+    let mut block = Block {
+        author: None,
+        difficulty: U256::zero(),
+        extra_data: b"golden-vector".to_vec(),
+        gas_limit: U256::from(30_000_000),
+        gas_used: receipts
+            .last()
+            .map(|receipt| receipt.cumulative_gas_used)
+            .unwrap_or_default(),
+        hash: H256::zero(),
+        logs_bloom: receipts
+            .iter()
+            .fold(Bloom::default(), |mut bloom, receipt| {
+                bloom.accrue_bloom(&receipt.logs_bloom);
+                bloom
+            }),
+        miner: Address::from_low_u64_be(0xfee),
+        mix_hash: get_synthetic_hash("mix-hash", block_number as usize)?,
+        nonce: U256::zero(),
+        number: U256::from(block_number),
+        parent_hash: get_synthetic_hash("block", block_number as usize - 1)?,
+        receipts_root,
+        seal_fields: None,
+        sha3_uncles: keccak_hash_bytes(&RLP_EMPTY_LIST)?,
+        size: U256::zero(),
+        state_root: get_synthetic_hash("state-root", block_number as usize)?,
+        timestamp: U256::from(1_700_000_000 + block_number * 12),
+        total_difficulty: None,
+        transactions: receipts
+            .iter()
+            .map(|receipt| receipt.transaction_hash)
+            .collect(),
+        transactions_root: get_synthetic_hash("transactions-root", block_number as usize)?,
+        uncles: vec![],
+        base_fee_per_gas: Some(U256::from(7_000_000_000u64)),
+        withdrawals_root: Some(HASHED_NULL_NODE),
+        blob_gas_used: Some(U256::zero()),
+        excess_blob_gas: Some(U256::zero()),
+        parent_beacon_block_root: Some(get_synthetic_hash("beacon-root", block_number as usize)?),
+        requests_hash: None,
+    };
+    block.hash = keccak_hash_bytes(&rlp_encode_block_header(&block)?)?;
+    Ok(block)
+}
+
+fn get_golden_vectors() -> Result<Vec<GoldenVector>> {
+    let large_block_receipts =
+        get_synthetic_receipts(&[LEGACY_TX_TYPE; LARGE_BLOCK_NUM_RECEIPTS], None)?;
+    let typed_block_receipts = get_synthetic_receipts(
+        &[
+            LEGACY_TX_TYPE,
+            EIP_2930_TX_TYPE,
+            EIP_1559_TX_TYPE,
+            EIP_4844_TX_TYPE,
+        ],
+        None,
+    )?;
+    Ok(vec![
+        GoldenVector {
+            name: "single-tx-block",
+            description: "Only receipt in its block: the proof is a lone leaf node.",
+            receipts: get_synthetic_receipts(&[LEGACY_TX_TYPE], None)?,
+            index: 0,
+        },
+        GoldenVector {
+            name: "index-0",
+            description: "First receipt in a large block: its key is RLP(0) == 0x80.",
+            receipts: large_block_receipts.clone(),
+            index: 0,
+        },
+        GoldenVector {
+            name: "index-127",
+            description: "Index 0x7f: the last index whose key is a single byte.",
+            receipts: large_block_receipts.clone(),
+            index: 0x7f,
+        },
+        GoldenVector {
+            name: "index-128",
+            description: "Index 0x80: the first index whose key is two bytes, 0x8180.",
+            receipts: large_block_receipts,
+            index: 0x80,
+        },
+        GoldenVector {
+            name: "huge-receipt",
+            description: "Receipt w/ many large logs, encoded w/ a multi-byte RLP length prefix.",
+            receipts: get_synthetic_receipts(&[LEGACY_TX_TYPE; 3], Some(1))?,
+            index: 1,
+        },
+        GoldenVector {
+            name: "typed-receipt-eip2930",
+            description: "EIP-2930 receipt: its value is prefixed w/ the 0x01 type byte.",
+            receipts: typed_block_receipts.clone(),
+            index: 1,
+        },
+        GoldenVector {
+            name: "typed-receipt-eip1559",
+            description: "EIP-1559 receipt: its value is prefixed w/ the 0x02 type byte.",
+            receipts: typed_block_receipts.clone(),
+            index: 2,
+        },
+        GoldenVector {
+            name: "typed-receipt-eip4844",
+            description: "EIP-4844 receipt: its value is prefixed w/ the 0x03 type byte.",
+            receipts: typed_block_receipts,
+            index: 3,
+        },
+    ])
+}
+
+fn get_golden_vector_proof_bundle(vector: &GoldenVector, block_number: u64) -> Result<ProofBundle> {
+    let trie = get_receipts_trie_from_receipts(&vector.receipts)?;
+    let block = get_synthetic_block(block_number, &vector.receipts, trie.root)?;
+    let receipts_root = trie.root;
+    let branch = get_branch_from_trie(&trie, vector.index)?;
+    let bundle = get_proof_bundle(&block, receipts_root, vector.index, &branch)?;
+    verify_proof_bundle(&bundle).map(|_| bundle)
+}
+
+pub fn gen_vectors(out_dir: &str) -> Result<String> {
+    info!("✔ Generating golden vectors in: {}", out_dir);
+    fs::create_dir_all(out_dir)?;
+    let index = get_golden_vectors()?
+        .iter()
+        .enumerate()
+        .map(|(i, vector)| {
+            let bundle =
+                get_golden_vector_proof_bundle(vector, FIRST_VECTOR_BLOCK_NUMBER + i as u64)?;
+            let file_name = format!("{}.json", vector.name);
+            fs::write(
+                Path::new(out_dir).join(&file_name),
+                serde_json::to_string_pretty(&get_proof_bundle_json(&bundle, false)?)?,
+            )?;
+            Ok(json!({
+                "name": vector.name,
+                "description": vector.description,
+                "file": file_name,
+                "tx_index": bundle.tx_index,
+                "receipts_root": bundle.receipts_root,
+            }))
+        })
+        .collect::<Result<Vec<serde_json::Value>>>()?;
+    fs::write(
+        Path::new(out_dir).join("index.json"),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(format!(
+        "✔ {} golden vectors written to: {}",
+        index.len(),
+        out_dir
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_bundle::AnyProofBundle;
+    use crate::verify_proof::{read_proof_bundles_from_file, verify_any_proof_bundle};
+
+    fn get_temp_vectors_dir(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-golden-vectors-{}", name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn read_vector(dir: &str, name: &str) -> ProofBundle {
+        let path = Path::new(dir).join(format!("{}.json", name));
+        match read_proof_bundles_from_file(path.to_str().unwrap())
+            .unwrap()
+            .remove(0)
+        {
+            AnyProofBundle::Full(bundle) => *bundle,
+            _ => panic!("Golden vector should be a full proof bundle!"),
+        }
+    }
+
+    #[test]
+    fn should_write_golden_vectors_which_all_verify() {
+        let dir = get_temp_vectors_dir("verify");
+        gen_vectors(&dir).unwrap();
+        let index: Vec<serde_json::Value> =
+            serde_json::from_str(&fs::read_to_string(Path::new(&dir).join("index.json")).unwrap())
+                .unwrap();
+        assert!(index.len() == get_golden_vectors().unwrap().len());
+        index.iter().for_each(|entry| {
+            let path = Path::new(&dir).join(entry["file"].as_str().unwrap());
+            read_proof_bundles_from_file(path.to_str().unwrap())
+                .unwrap()
+                .iter()
+                .for_each(|bundle| verify_any_proof_bundle(bundle).unwrap());
+        });
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_generate_golden_vectors_covering_edge_shapes() {
+        let dir = get_temp_vectors_dir("shapes");
+        gen_vectors(&dir).unwrap();
+        assert!(read_vector(&dir, "single-tx-block").proof.len() == 1);
+        assert!(read_vector(&dir, "index-0").key == "0x80");
+        assert!(read_vector(&dir, "index-127").key == "0x7f");
+        assert!(read_vector(&dir, "index-128").key == "0x8180");
+        assert!(read_vector(&dir, "huge-receipt").value.len() > 2 * 0xffff);
+        assert!(read_vector(&dir, "typed-receipt-eip1559")
+            .value
+            .starts_with("0x02"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn should_generate_deterministic_golden_vectors() {
+        let vectors = get_golden_vectors().unwrap();
+        let bundle_1 = get_golden_vector_proof_bundle(&vectors[1], 1).unwrap();
+        let bundle_2 = get_golden_vector_proof_bundle(&vectors[1], 1).unwrap();
+        assert!(bundle_1 == bundle_2);
+    }
+}
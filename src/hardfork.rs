@@ -0,0 +1,145 @@
+use crate::errors::AppError;
+use crate::get_rpc_call_jsons::get_chain_id_json;
+use crate::json_rpc_client::call_json_rpc;
+use crate::make_rpc_call::check_response_text;
+use crate::types::{Receipt, Result};
+use crate::utils::{convert_h256_to_prefixed_hex, convert_hex_to_u256};
+use clap::ValueEnum;
+use ethereum_types::U256;
+use serde_json::Value as Json;
+
+/**
+ * Hardforks:
+ *
+ * The only hardfork affecting how receipts are encoded for the receipts trie
+ * is Byzantium (EIP-658). Before it, a receipt's first item is the post-tx
+ * state root. After it, the first item is the status of the tx instead.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hardfork {
+    PreByzantium,
+    Byzantium,
+}
+
+pub fn get_byzantium_block_number(chain_id: u64) -> Option<u64> {
+    match chain_id {
+        1 => Some(4_370_000),
+        3 => Some(1_700_000),
+        4 => Some(1_035_301),
+        42 => Some(5_067_000),
+        61 => Some(8_772_000),
+        _ => None,
+    }
+}
+
+pub fn get_hardfork_from_chain_id_and_block_number(chain_id: u64, block_number: u64) -> Hardfork {
+    match get_byzantium_block_number(chain_id) {
+        Some(byzantium_block_number) if block_number < byzantium_block_number => {
+            Hardfork::PreByzantium
+        }
+        // NOTE: Chains we don't know of are assumed to have launched post-Byzantium.
+        _ => Hardfork::Byzantium,
+    }
+}
+
+pub fn get_chain_id(endpoint: &str) -> Result<u64> {
+    get_chain_id_json()
+        .and_then(|rpc_json| call_json_rpc(endpoint, rpc_json))
+        .and_then(check_response_text)
+        .and_then(|text| Ok(serde_json::from_str::<Json>(&text)?))
+        .and_then(|json| match json["result"].as_str() {
+            Some(hex) => convert_hex_to_u256(hex.to_string()).map(|chain_id| chain_id.as_u64()),
+            None => Err(AppError::Custom(
+                "✘ Could not get chain id from node!".to_string(),
+            )),
+        })
+}
+
+pub fn get_hardfork(
+    endpoint: &str,
+    maybe_hardfork: &Option<Hardfork>,
+    block_number: U256,
+) -> Result<Hardfork> {
+    match maybe_hardfork {
+        Some(hardfork) => Ok(*hardfork),
+        None => get_chain_id(endpoint).map(|chain_id| {
+            let hardfork =
+                get_hardfork_from_chain_id_and_block_number(chain_id, block_number.as_u64());
+            info!(
+                "✔ Detected hardfork {:?} for block {} on chain id {}",
+                hardfork, block_number, chain_id
+            );
+            hardfork
+        }),
+    }
+}
+
+pub fn apply_hardfork_to_receipts(
+    receipts: Vec<Receipt>,
+    hardfork: Hardfork,
+) -> Result<Vec<Receipt>> {
+    receipts
+        .into_iter()
+        .map(|mut receipt| match hardfork {
+            Hardfork::Byzantium => {
+                receipt.root = None;
+                Ok(receipt)
+            }
+            Hardfork::PreByzantium => match receipt.root {
+                Some(_) => Ok(receipt),
+                None => Err(AppError::Custom(format!(
+                    "✘ Pre-Byzantium receipt for tx {} has no post-state root!",
+                    convert_h256_to_prefixed_hex(receipt.transaction_hash)?
+                ))),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_expected_receipt;
+    use ethereum_types::H256;
+
+    #[test]
+    fn should_get_hardforks_either_side_of_mainnet_byzantium() {
+        assert!(
+            get_hardfork_from_chain_id_and_block_number(1, 4_369_999) == Hardfork::PreByzantium
+        );
+        assert!(get_hardfork_from_chain_id_and_block_number(1, 4_370_000) == Hardfork::Byzantium);
+    }
+
+    #[test]
+    fn should_default_to_byzantium_for_unknown_chain() {
+        assert!(get_hardfork_from_chain_id_and_block_number(31337, 1) == Hardfork::Byzantium);
+    }
+
+    #[test]
+    fn should_get_hardfork_from_flag_without_rpc_call() {
+        let result = get_hardfork(
+            "http://not-a-node",
+            &Some(Hardfork::PreByzantium),
+            U256::zero(),
+        );
+        assert!(result.unwrap() == Hardfork::PreByzantium);
+    }
+
+    #[test]
+    fn should_drop_post_state_roots_from_byzantium_receipts() {
+        let mut receipt = get_expected_receipt();
+        receipt.root = Some(H256::zero());
+        let result = apply_hardfork_to_receipts(vec![receipt], Hardfork::Byzantium).unwrap();
+        assert!(result[0].root.is_none());
+    }
+
+    #[test]
+    fn should_fail_to_apply_pre_byzantium_to_receipts_without_post_state_roots() {
+        let receipt = get_expected_receipt();
+        match apply_hardfork_to_receipts(vec![receipt], Hardfork::PreByzantium) {
+            Err(AppError::Custom(e)) => assert!(e.contains("has no post-state root")),
+            _ => panic!("Should not apply pre-Byzantium rules to receipt w/o root!"),
+        }
+    }
+}
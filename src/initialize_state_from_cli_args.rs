@@ -1,31 +1,130 @@
+use crate::gas_estimate::maybe_read_gas_model_file;
+use crate::get_endpoint::get_endpoint;
 use crate::parse_cli_args::CliArgs;
+use crate::proof_signing::maybe_read_proof_signing_key;
 use crate::state::State;
+use crate::trusted_headers::maybe_read_trusted_headers_file;
 use crate::types::Result;
 use crate::utils::convert_hex_to_h256;
 
-pub fn initialize_state_from_cli_args(cli_args: CliArgs) -> Result<State> {
+pub fn initialize_state_from_cli_args(cli_args: &CliArgs, tx_hash: &str) -> Result<State> {
     info!("✔ Initializing state from CLI args...");
     State::init(
-        convert_hex_to_h256(cli_args.arg_txhash.clone())?,
-        cli_args.arg_txhash,
+        convert_hex_to_h256(tx_hash.to_string())?,
+        tx_hash.to_string(),
     )
+    .and_then(|state| match &cli_args.audit_log {
+        Some(path) => state.set_audit_log_path_in_state(path.clone()),
+        None => Ok(state),
+    })
+    .and_then(|state| match &cli_args.cross_check {
+        Some(endpoint) => state.set_cross_check_endpoint_in_state(endpoint.clone()),
+        None => Ok(state),
+    })
+    .and_then(|state| match &cli_args.audit_trie_roots {
+        Some(path) => state.set_trie_root_audit_path_in_state(path.clone()),
+        None => Ok(state),
+    })
+    .and_then(|state| match &cli_args.cache_dir {
+        Some(dir) => state.set_trie_cache_dir_in_state(dir.clone()),
+        None => Ok(state),
+    })
+    .and_then(|state| state.set_chain_profile_in_state(cli_args.chain))
+    .and_then(|state| state.set_lenient_lookups_in_state(cli_args.lenient_lookups))
+    .and_then(|state| state.set_strict_receipts_in_state(cli_args.strict_receipts))
+    .and_then(|state| state.set_batch_tuner_in_state(cli_args.batch_tuner.clone()))
+    .and_then(|state| {
+        match maybe_read_proof_signing_key(&cli_args.sign_key, cli_args.sign_scheme)? {
+            Some(key) => state.set_proof_signing_key_in_state(key),
+            None => Ok(state),
+        }
+    })
+    .and_then(
+        |state| match maybe_read_gas_model_file(&cli_args.gas_model)? {
+            Some(gas_model) => state.set_gas_model_in_state(gas_model),
+            None => Ok(state),
+        },
+    )
+    .and_then(
+        |state| match maybe_read_trusted_headers_file(&cli_args.trusted_headers)? {
+            Some(trusted_headers) => state.set_trusted_headers_in_state(trusted_headers),
+            None => Ok(state),
+        },
+    )
+    .and_then(|state| match cli_args.hardfork {
+        Some(hardfork) => state.set_hardfork_in_state(hardfork),
+        None => Ok(state),
+    })
+    .and_then(|state| {
+        get_endpoint(cli_args).and_then(|endpoint| state.set_endpoint_in_state(endpoint))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chain_profile::ChainProfile;
     use crate::test_utils::get_valid_tx_hash_hex;
+    use clap::Parser;
 
     #[test]
+    #[serial]
     fn should_create_state_from_cli_args() {
-        let expected_verbosity = true;
         let tx_hash = get_valid_tx_hash_hex();
         let expected_tx_hash = convert_hex_to_h256(tx_hash.clone()).unwrap();
-        let cli_args = CliArgs {
-            arg_txhash: tx_hash,
-            flag_verbose: expected_verbosity,
-        };
-        let state = initialize_state_from_cli_args(cli_args).unwrap();
+        let cli_args =
+            CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker", "prove-receipt", &tx_hash])
+                .unwrap();
+        let state = initialize_state_from_cli_args(&cli_args, &tx_hash).unwrap();
         assert!(state.tx_hash == expected_tx_hash);
+        assert!(state.audit_log_path.is_none());
+        assert!(state.trie_root_audit_path.is_none());
+        assert!(state.trie_cache_dir.is_none());
+        assert!(state.cross_check_endpoint.is_none());
+        assert!(state.hardfork.is_none());
+        assert!(state.chain_profile == Some(ChainProfile::Mainnet));
+        assert!(!state.lenient_lookups);
+        assert!(!state.strict_receipts);
+        assert!(state.proof_signing_key.is_none());
+        assert!(state.gas_model.is_none());
+        assert!(state.trusted_headers.is_none());
+        assert!(state.endpoint.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn should_create_state_with_audit_log_path_and_endpoint_from_cli_args() {
+        let tx_hash = get_valid_tx_hash_hex();
+        let expected_path = "./audit.jsonl".to_string();
+        let expected_endpoint = "http://localhost:8546".to_string();
+        let expected_audit_path = "./trie-root-audit.json".to_string();
+        let expected_cross_check_endpoint = "http://localhost:8547".to_string();
+        let expected_cache_dir = "./trie-cache".to_string();
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-receipt",
+            &tx_hash,
+            "--audit-log",
+            &expected_path,
+            "--endpoint",
+            &expected_endpoint,
+            "--lenient-lookups",
+            "--strict-receipts",
+            "--audit-trie-roots",
+            &expected_audit_path,
+            "--cross-check",
+            &expected_cross_check_endpoint,
+            "--cache-dir",
+            &expected_cache_dir,
+        ])
+        .unwrap();
+        let state = initialize_state_from_cli_args(&cli_args, &tx_hash).unwrap();
+        assert!(state.audit_log_path == Some(expected_path));
+        assert!(state.endpoint == Some(expected_endpoint));
+        assert!(state.lenient_lookups);
+        assert!(state.strict_receipts);
+        assert!(state.trie_root_audit_path == Some(expected_audit_path));
+        assert!(state.cross_check_endpoint == Some(expected_cross_check_endpoint));
+        assert!(state.trie_cache_dir == Some(expected_cache_dir));
     }
 }
@@ -0,0 +1,278 @@
+use crate::constants::{ADDRESS_HEX_CHARS, HASH_HEX_CHARS};
+use crate::errors::AppError;
+use crate::utils::convert_bytes_to_prefixed_hex;
+use ethereum_types::{Address, H256};
+use std::fmt;
+
+const MAX_BLOCK_NUMBER_HEX_CHARS: usize = 16;
+
+/**
+ * Input Parsing:
+ *
+ * Tx hashes, block ids & addresses reach us from the CLI, the HTTP server &
+ * library callers alike, so they're all parsed here, into types rather than
+ * strings, w/ errors saying exactly what was wrong. Hex is accepted w/ or w/o
+ * its `0x` prefix & in any case. A block id is a decimal or `0x` prefixed hex
+ * block number, a block hash, or one of the `latest`, `finalized` & `safe`
+ * tags. Unprefixed block ids are decimal numbers unless 32 bytes of hex, so
+ * unprefixed hex block numbers aren't accepted. Addresses are only ever hex:
+ * ENS names are refused rather than resolved via whatever node is to hand.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    TxHash,
+    BlockHash,
+    Address,
+}
+
+impl fmt::Display for InputKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputKind::TxHash => write!(f, "Transaction hash"),
+            InputKind::BlockHash => write!(f, "Block hash"),
+            InputKind::Address => write!(f, "Address"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputError {
+    InvalidHex(InputKind, String),
+    WrongLength(InputKind, usize, String),
+    InvalidBlockId(String),
+    EnsName(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::InvalidHex(kind, input) => {
+                write!(f, "✘ {} is not valid hex: {}", kind, input)
+            }
+            InputError::WrongLength(kind, expected_hex_chars, input) => write!(
+                f,
+                "✘ {} must be {} bytes of hex, got: {}",
+                kind,
+                expected_hex_chars / 2,
+                input
+            ),
+            InputError::InvalidBlockId(input) => write!(
+                f,
+                "✘ Block must be a number, a block hash, `latest`, `finalized` or `safe`, got: {}",
+                input
+            ),
+            InputError::EnsName(input) => write!(
+                f,
+                "✘ ENS names aren't resolved, pass the address as hex instead of: {}",
+                input
+            ),
+        }
+    }
+}
+
+impl From<InputError> for AppError {
+    fn from(e: InputError) -> AppError {
+        AppError::Custom(e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockTag {
+    Latest,
+    Finalized,
+    Safe,
+}
+
+impl fmt::Display for BlockTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockTag::Latest => write!(f, "latest"),
+            BlockTag::Finalized => write!(f, "finalized"),
+            BlockTag::Safe => write!(f, "safe"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockId {
+    Number(u64),
+    Hash(H256),
+    Tag(BlockTag),
+}
+
+impl BlockId {
+    // NOTE: The block param of `eth_getBlockByNumber`, so not for block hashes.
+    pub fn to_block_number_param(&self) -> Option<String> {
+        match self {
+            BlockId::Number(number) => Some(format!("0x{:x}", number)),
+            BlockId::Tag(tag) => Some(tag.to_string()),
+            BlockId::Hash(_) => None,
+        }
+    }
+}
+
+// NOTE: The canonical form of the block id, as passed around as a string.
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockId::Number(number) => write!(f, "{}", number),
+            BlockId::Hash(hash) => write!(f, "{}", convert_bytes_to_prefixed_hex(hash.as_bytes())),
+            BlockId::Tag(tag) => write!(f, "{}", tag),
+        }
+    }
+}
+
+fn strip_optional_hex_prefix(input: &str) -> &str {
+    input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input)
+}
+
+fn parse_hex_of_length(
+    kind: InputKind,
+    input: &str,
+    hex_chars: usize,
+) -> Result<Vec<u8>, InputError> {
+    let hex = strip_optional_hex_prefix(input.trim());
+    if hex.len() != hex_chars {
+        return Err(InputError::WrongLength(kind, hex_chars, input.to_string()));
+    }
+    hex::decode(hex).map_err(|_| InputError::InvalidHex(kind, input.to_string()))
+}
+
+fn parse_hash(kind: InputKind, input: &str) -> Result<H256, InputError> {
+    parse_hex_of_length(kind, input, HASH_HEX_CHARS).map(|bytes| H256::from_slice(&bytes))
+}
+
+pub fn parse_tx_hash(input: &str) -> Result<H256, InputError> {
+    parse_hash(InputKind::TxHash, input)
+}
+
+pub fn parse_address(input: &str) -> Result<Address, InputError> {
+    if input.contains('.') {
+        return Err(InputError::EnsName(input.to_string()));
+    }
+    parse_hex_of_length(InputKind::Address, input, ADDRESS_HEX_CHARS)
+        .map(|bytes| Address::from_slice(&bytes))
+}
+
+pub fn parse_block_id(input: &str) -> Result<BlockId, InputError> {
+    let trimmed = input.trim();
+    let invalid_block_id = || InputError::InvalidBlockId(input.to_string());
+    match trimmed.to_lowercase().as_str() {
+        "latest" => return Ok(BlockId::Tag(BlockTag::Latest)),
+        "finalized" => return Ok(BlockId::Tag(BlockTag::Finalized)),
+        "safe" => return Ok(BlockId::Tag(BlockTag::Safe)),
+        _ => (),
+    }
+    let hex = strip_optional_hex_prefix(trimmed);
+    let is_prefixed = hex.len() < trimmed.len();
+    match (is_prefixed, hex.len()) {
+        (_, HASH_HEX_CHARS) => parse_hash(InputKind::BlockHash, trimmed).map(BlockId::Hash),
+        (true, 1..=MAX_BLOCK_NUMBER_HEX_CHARS) => u64::from_str_radix(hex, 16)
+            .map(BlockId::Number)
+            .map_err(|_| invalid_block_id()),
+        (false, _) if trimmed.chars().all(|c| c.is_ascii_digit()) => trimmed
+            .parse::<u64>()
+            .map(BlockId::Number)
+            .map_err(|_| invalid_block_id()),
+        _ => Err(invalid_block_id()),
+    }
+}
+
+pub fn normalize_tx_hash(input: &str) -> Result<String, InputError> {
+    parse_tx_hash(input).map(|hash| convert_bytes_to_prefixed_hex(hash.as_bytes()))
+}
+
+pub fn normalize_block_id(input: &str) -> Result<String, InputError> {
+    parse_block_id(input).map(|block_id| block_id.to_string())
+}
+
+pub fn normalize_address(input: &str) -> Result<String, InputError> {
+    parse_address(input).map(|address| convert_bytes_to_prefixed_hex(address.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{SAMPLE_BLOCK_HASH, SAMPLE_TX_HASH};
+
+    #[test]
+    fn should_parse_tx_hash_w_or_wo_prefix_in_any_case() {
+        let expected = parse_tx_hash(SAMPLE_TX_HASH).unwrap();
+        let unprefixed = &SAMPLE_TX_HASH[2..];
+        assert!(parse_tx_hash(unprefixed).unwrap() == expected);
+        assert!(parse_tx_hash(&unprefixed.to_uppercase()).unwrap() == expected);
+        assert!(parse_tx_hash(&format!("0X{}", unprefixed)).unwrap() == expected);
+        assert!(normalize_tx_hash(unprefixed).unwrap() == SAMPLE_TX_HASH);
+    }
+
+    #[test]
+    fn should_fail_to_parse_malformed_tx_hashes_w_typed_errors() {
+        assert!(
+            parse_tx_hash("0xc0ffee")
+                == Err(InputError::WrongLength(
+                    InputKind::TxHash,
+                    HASH_HEX_CHARS,
+                    "0xc0ffee".to_string()
+                ))
+        );
+        let non_hex = format!("0x{}", "g".repeat(HASH_HEX_CHARS));
+        assert!(parse_tx_hash(&non_hex) == Err(InputError::InvalidHex(InputKind::TxHash, non_hex)));
+    }
+
+    #[test]
+    fn should_parse_block_ids() {
+        let hash = parse_hash(InputKind::BlockHash, SAMPLE_BLOCK_HASH).unwrap();
+        [
+            ("8233333", BlockId::Number(8_233_333)),
+            ("0x7da275", BlockId::Number(8_233_589)),
+            ("0X7DA275", BlockId::Number(8_233_589)),
+            ("latest", BlockId::Tag(BlockTag::Latest)),
+            ("Finalized", BlockId::Tag(BlockTag::Finalized)),
+            ("safe", BlockId::Tag(BlockTag::Safe)),
+            (SAMPLE_BLOCK_HASH, BlockId::Hash(hash)),
+            (&SAMPLE_BLOCK_HASH[2..], BlockId::Hash(hash)),
+        ]
+        .iter()
+        .for_each(|(input, expected)| assert!(parse_block_id(input).unwrap() == *expected));
+    }
+
+    #[test]
+    fn should_fail_to_parse_malformed_block_ids() {
+        [
+            "",
+            "earliest",
+            "-1",
+            "7da275",
+            "0x",
+            "0xg",
+            "0x10000000000000000",
+            "1.5",
+        ]
+        .iter()
+        .for_each(|input| assert!(parse_block_id(input).is_err()));
+    }
+
+    #[test]
+    fn should_get_block_number_params_of_block_ids() {
+        assert!(BlockId::Number(16).to_block_number_param() == Some("0x10".to_string()));
+        assert!(BlockId::Tag(BlockTag::Safe).to_block_number_param() == Some("safe".to_string()));
+        assert!(BlockId::Hash(H256::zero())
+            .to_block_number_param()
+            .is_none());
+        assert!(normalize_block_id("0x10").unwrap() == "16");
+    }
+
+    #[test]
+    fn should_parse_addresses_w_or_wo_prefix_but_not_ens_names() {
+        let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+        assert!(parse_address(&address[2..]).unwrap() == parse_address(address).unwrap());
+        assert!(normalize_address(&address.to_uppercase()[2..]).unwrap() == address);
+        assert!(
+            parse_address("vitalik.eth") == Err(InputError::EnsName("vitalik.eth".to_string()))
+        );
+        assert!(parse_address("0xc0ffee").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::errors::AppError;
+use crate::make_rpc_call::{get_response_body_text, make_rpc_call_w_rpc_client};
+use crate::rpc_client::{RpcClient, RpcClientConfig};
+use crate::types::Result;
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/**
+ * JSON-RPC Clients:
+ *
+ * Every RPC call goes through a `JsonRpcClient`, which sends a request, or a
+ * batch of them, & returns the raw response body, leaving checking it for
+ * errors & deserializing it to the caller. An endpoint's client is an HTTP
+ * one, w/ the connection pool, rate limit, compression & metrics of the
+ * `RpcClient` it's registered w/ via `register_http_json_rpc_client`, else
+ * w/ the default RPC settings. But any client can be registered against an
+ * endpoint via `set_json_rpc_client`, so calls to it go through that client
+ * instead: e.g. an alternate transport, or the `MockJsonRpcClient`, which
+ * answers calls from canned results, so code fetching blocks & receipts can
+ * be unit tested w/o a node. Clients are keyed by endpoint, so tests each
+ * registering a mock under their own endpoint can run in parallel.
+ */
+pub trait JsonRpcClient: Send + Sync {
+    fn send(&self, request: Json) -> Result<String>;
+}
+
+static JSON_RPC_CLIENTS: Mutex<BTreeMap<String, Arc<dyn JsonRpcClient>>> =
+    Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Debug)]
+pub struct HttpJsonRpcClient {
+    endpoint: String,
+    rpc_client: Arc<RpcClient>,
+}
+
+impl HttpJsonRpcClient {
+    pub fn new(endpoint: &str, rpc_client: Arc<RpcClient>) -> Self {
+        HttpJsonRpcClient {
+            endpoint: endpoint.to_string(),
+            rpc_client,
+        }
+    }
+}
+
+impl JsonRpcClient for HttpJsonRpcClient {
+    fn send(&self, request: Json) -> Result<String> {
+        make_rpc_call_w_rpc_client(&self.rpc_client, &self.endpoint, request)
+            .and_then(|res| get_response_body_text(res, self.rpc_client.get_telemetry()))
+    }
+}
+
+// NOTE: Results w/o params answer calls to their method whatever their params.
+#[derive(Debug, Default)]
+pub struct MockJsonRpcClient {
+    results: Vec<(String, Option<Json>, Json)>,
+    requests: Mutex<Vec<Json>>,
+}
+
+impl MockJsonRpcClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_result(mut self, method: &str, result: Json) -> Self {
+        self.results.push((method.to_string(), None, result));
+        self
+    }
+
+    pub fn with_result_for_params(mut self, method: &str, params: Json, result: Json) -> Self {
+        self.results
+            .push((method.to_string(), Some(params), result));
+        self
+    }
+
+    pub fn get_requests(&self) -> Vec<Json> {
+        self.requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+
+    fn get_result(&self, request: &Json) -> Option<&Json> {
+        self.results
+            .iter()
+            .find(|(method, maybe_params, _)| {
+                request["method"] == json!(method)
+                    && maybe_params
+                        .as_ref()
+                        .is_none_or(|params| request["params"] == *params)
+            })
+            .map(|(_, _, result)| result)
+    }
+
+    fn get_response(&self, request: &Json) -> Json {
+        match self.get_result(request) {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32601, "message": "the method does not exist/is not available" },
+            }),
+        }
+    }
+}
+
+impl JsonRpcClient for MockJsonRpcClient {
+    fn send(&self, request: Json) -> Result<String> {
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(request.clone());
+        }
+        let response = match request.as_array() {
+            Some(batch) => Json::Array(
+                batch
+                    .iter()
+                    .map(|request| self.get_response(request))
+                    .collect(),
+            ),
+            None => self.get_response(&request),
+        };
+        Ok(response.to_string())
+    }
+}
+
+pub fn set_json_rpc_client(endpoint: &str, client: Arc<dyn JsonRpcClient>) {
+    if let Ok(mut clients) = JSON_RPC_CLIENTS.lock() {
+        clients.insert(endpoint.to_string(), client);
+    }
+}
+
+pub fn remove_json_rpc_client(endpoint: &str) {
+    if let Ok(mut clients) = JSON_RPC_CLIENTS.lock() {
+        clients.remove(endpoint);
+    }
+}
+
+// NOTE: Keeps any client already registered, e.g. a mock, or one whose pool is warmed up.
+pub fn register_http_json_rpc_client(endpoint: &str, rpc_client: &Arc<RpcClient>) {
+    if let Ok(mut clients) = JSON_RPC_CLIENTS.lock() {
+        clients
+            .entry(endpoint.to_string())
+            .or_insert_with(|| Arc::new(HttpJsonRpcClient::new(endpoint, rpc_client.clone())));
+    }
+}
+
+pub fn get_json_rpc_client(endpoint: &str) -> Result<Arc<dyn JsonRpcClient>> {
+    let mut clients = JSON_RPC_CLIENTS
+        .lock()
+        .map_err(|_| AppError::Custom("✘ JSON-RPC clients lock poisoned!".to_string()))?;
+    match clients.get(endpoint) {
+        Some(client) => Ok(client.clone()),
+        None => {
+            let rpc_client = Arc::new(RpcClient::new(RpcClientConfig::default())?);
+            let client: Arc<dyn JsonRpcClient> =
+                Arc::new(HttpJsonRpcClient::new(endpoint, rpc_client));
+            clients.insert(endpoint.to_string(), client.clone());
+            Ok(client)
+        }
+    }
+}
+
+pub fn call_json_rpc(endpoint: &str, request: Json) -> Result<String> {
+    get_json_rpc_client(endpoint).and_then(|client| client.send(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_answer_calls_from_mock_by_method_and_params() {
+        let client = MockJsonRpcClient::new()
+            .with_result("eth_chainId", json!("0x1"))
+            .with_result_for_params("eth_getBalance", json!(["0xab", "latest"]), json!("0x2"));
+        let request = json!({ "jsonrpc": "2.0", "id": 7, "method": "eth_chainId", "params": [] });
+        let response: Json = serde_json::from_str(&client.send(request.clone()).unwrap()).unwrap();
+        assert!(response["id"] == 7);
+        assert!(response["result"] == "0x1");
+        let balance_request =
+            json!({ "id": 1, "method": "eth_getBalance", "params": ["0xab", "latest"] });
+        let response: Json = serde_json::from_str(&client.send(balance_request).unwrap()).unwrap();
+        assert!(response["result"] == "0x2");
+        let other_request =
+            json!({ "id": 1, "method": "eth_getBalance", "params": ["0xcd", "latest"] });
+        let response: Json = serde_json::from_str(&client.send(other_request).unwrap()).unwrap();
+        assert!(response["error"]["code"] == -32601);
+        assert!(client.get_requests().len() == 3);
+        assert!(client.get_requests()[0] == request);
+    }
+
+    #[test]
+    fn should_answer_batched_calls_from_mock_in_order() {
+        let client = MockJsonRpcClient::new().with_result("net_version", json!("1"));
+        let batch = json!([
+            { "id": 1, "method": "net_version", "params": [] },
+            { "id": 2, "method": "eth_chainId", "params": [] },
+        ]);
+        let response: Json = serde_json::from_str(&client.send(batch).unwrap()).unwrap();
+        assert!(response[0]["id"] == 1);
+        assert!(response[0]["result"] == "1");
+        assert!(response[1]["id"] == 2);
+        assert!(response[1]["error"].is_object());
+    }
+
+    #[test]
+    fn should_route_calls_to_client_registered_for_endpoint() {
+        let endpoint = "mock://json-rpc-client-test";
+        let client = Arc::new(MockJsonRpcClient::new().with_result("net_version", json!("5")));
+        set_json_rpc_client(endpoint, client.clone());
+        let request = json!({ "id": 1, "method": "net_version", "params": [] });
+        let response: Json =
+            serde_json::from_str(&call_json_rpc(endpoint, request).unwrap()).unwrap();
+        assert!(response["result"] == "5");
+        assert!(client.get_requests().len() == 1);
+        remove_json_rpc_client(endpoint);
+        // NOTE: W/ the mock removed, the call goes over HTTP, which can't reach a `mock://` url.
+        assert!(call_json_rpc(endpoint, json!({})).is_err());
+    }
+
+    #[test]
+    fn should_not_replace_registered_client_w_http_one() {
+        let endpoint = "mock://json-rpc-client-registration-test";
+        let client = Arc::new(MockJsonRpcClient::new().with_result("net_version", json!("5")));
+        set_json_rpc_client(endpoint, client.clone());
+        let rpc_client = Arc::new(RpcClient::new(RpcClientConfig::default()).unwrap());
+        register_http_json_rpc_client(endpoint, &rpc_client);
+        let request = json!({ "id": 1, "method": "net_version", "params": [] });
+        let result = call_json_rpc(endpoint, request);
+        remove_json_rpc_client(endpoint);
+        assert!(result.is_ok());
+        assert!(client.get_requests().len() == 1);
+    }
+}
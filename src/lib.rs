@@ -0,0 +1,219 @@
+/*!
+ * Rusty Receipt Proof Maker:
+ *
+ * The library behind the CLI, for applications embedding proof generation &
+ * verification directly, & for the benchmarks under `benches/`. Everything
+ * bar the `verifier` module needs the default `std` feature.
+ */
+#![cfg_attr(not(any(feature = "std", feature = "wasm")), no_std)]
+
+#[cfg(feature = "alloy")]
+pub mod alloy_types;
+#[cfg(feature = "std")]
+pub mod audit_log;
+#[cfg(feature = "std")]
+pub mod batch_tuner;
+#[cfg(feature = "std")]
+pub mod binary_envelope;
+#[cfg(feature = "std")]
+pub mod binary_proof;
+#[cfg(feature = "std")]
+pub mod block_header;
+#[cfg(feature = "std")]
+pub mod block_pipeline;
+#[cfg(feature = "std")]
+pub mod build_metadata;
+#[cfg(feature = "std")]
+pub mod byte_order;
+#[cfg(feature = "std")]
+pub mod chain_profile;
+#[cfg(feature = "std")]
+pub mod config_file;
+#[cfg(feature = "std")]
+pub mod connect_to_node;
+#[cfg(feature = "std")]
+pub mod constants;
+#[cfg(feature = "std")]
+pub mod cross_check;
+#[cfg(feature = "std")]
+pub mod dns_resolution;
+#[cfg(feature = "std")]
+pub mod doctor;
+#[cfg(feature = "std")]
+pub mod eip1186_proof;
+#[cfg(feature = "std")]
+pub mod encryption;
+#[cfg(feature = "std")]
+pub mod errors;
+#[cfg(feature = "std")]
+pub mod explain_root_mismatch;
+#[cfg(feature = "std")]
+pub mod finality;
+#[cfg(feature = "firehose")]
+pub mod firehose;
+#[cfg(feature = "std")]
+pub mod gas_estimate;
+#[cfg(feature = "std")]
+pub mod get_block;
+#[cfg(feature = "std")]
+pub mod get_branch_from_trie;
+#[cfg(feature = "std")]
+pub mod get_database;
+#[cfg(feature = "std")]
+pub mod get_endpoint;
+#[cfg(feature = "std")]
+pub mod get_hex_proof_from_branch;
+#[cfg(feature = "std")]
+pub mod get_keccak_hash;
+#[cfg(feature = "std")]
+pub mod get_log;
+#[cfg(feature = "std")]
+pub mod get_receipts;
+#[cfg(feature = "std")]
+pub mod get_receipts_root;
+#[cfg(feature = "std")]
+pub mod get_receipts_trie;
+#[cfg(feature = "std")]
+pub mod get_rpc_call_jsons;
+#[cfg(feature = "std")]
+pub mod get_tx_index;
+#[cfg(feature = "std")]
+pub mod golden_vectors;
+#[cfg(feature = "std")]
+pub mod hardfork;
+#[cfg(feature = "std")]
+pub mod initialize_state_from_cli_args;
+#[cfg(feature = "std")]
+pub mod input_parsing;
+#[cfg(feature = "std")]
+pub mod json_rpc_client;
+#[cfg(feature = "std")]
+pub mod logger;
+#[cfg(feature = "std")]
+pub mod logs_bloom;
+#[cfg(feature = "std")]
+pub mod make_rpc_call;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod network;
+#[cfg(feature = "std")]
+pub mod nibble_utils;
+#[cfg(feature = "std")]
+pub mod node_stack;
+#[cfg(feature = "std")]
+pub mod ordered_trie;
+#[cfg(feature = "std")]
+pub mod parse_cli_args;
+#[cfg(feature = "std")]
+pub mod path_codec;
+#[cfg(feature = "std")]
+pub mod pipe;
+#[cfg(feature = "std")]
+pub mod proof_bundle;
+#[cfg(feature = "std")]
+pub mod proof_compression;
+#[cfg(feature = "std")]
+pub mod proof_expiry;
+#[cfg(feature = "std")]
+pub mod proof_formats;
+#[cfg(feature = "std")]
+pub mod proof_signing;
+#[cfg(feature = "std")]
+pub mod proof_size_report;
+#[cfg(feature = "std")]
+pub mod prove_block;
+#[cfg(feature = "std")]
+pub mod prove_mapping;
+#[cfg(feature = "std")]
+pub mod prove_range;
+#[cfg(feature = "std")]
+pub mod prove_receipt;
+#[cfg(feature = "std")]
+pub mod prove_storage;
+#[cfg(feature = "std")]
+pub mod receipt_assertions;
+#[cfg(feature = "std")]
+pub mod record_fixtures;
+#[cfg(feature = "std")]
+pub mod repl;
+#[cfg(feature = "std")]
+pub mod rlp_codec;
+#[cfg(feature = "std")]
+pub mod rpc_client;
+#[cfg(feature = "std")]
+pub mod secrets;
+#[cfg(feature = "std")]
+pub mod secure_trie;
+#[cfg(feature = "std")]
+pub mod serve;
+#[cfg(feature = "std")]
+pub mod single_flight;
+#[cfg(feature = "std")]
+pub mod ssz_proof;
+#[cfg(feature = "std")]
+pub mod state;
+#[cfg(feature = "std")]
+pub mod telemetry;
+#[cfg(feature = "std")]
+mod test_utils;
+#[cfg(feature = "std")]
+pub mod timings;
+#[cfg(feature = "std")]
+pub mod trie;
+#[cfg(feature = "std")]
+pub mod trie_cache;
+#[cfg(feature = "std")]
+pub mod trie_checkpoint;
+#[cfg(feature = "std")]
+pub mod trie_keys;
+#[cfg(feature = "std")]
+pub mod trie_nodes;
+#[cfg(feature = "std")]
+pub mod trie_repl;
+#[cfg(feature = "std")]
+pub mod trie_root_audit;
+#[cfg(feature = "std")]
+pub mod trie_snapshot;
+#[cfg(feature = "std")]
+pub mod trusted_headers;
+#[cfg(feature = "std")]
+pub mod types;
+#[cfg(feature = "std")]
+pub mod usage_info;
+#[cfg(feature = "std")]
+pub mod utils;
+#[cfg(feature = "std")]
+pub mod validate_block_id;
+#[cfg(feature = "std")]
+pub mod validate_cli_args;
+#[cfg(feature = "std")]
+pub mod validate_storage_args;
+#[cfg(feature = "std")]
+pub mod validate_tx_hash;
+#[cfg(any(feature = "std", feature = "verifier"))]
+pub mod verifier;
+#[cfg(feature = "std")]
+pub mod verify_proof;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod what_if;
+#[cfg(feature = "std")]
+pub mod write_output;
+
+extern crate alloc;
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_json;
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "std"))]
+#[macro_use]
+extern crate serial_test_derive;
@@ -0,0 +1,75 @@
+use crate::errors::AppError;
+use crate::parse_cli_args::LogFormat;
+use crate::secrets::redact_secrets;
+use crate::types::Result;
+use log::LevelFilter;
+use std::io::{self, IsTerminal, Write};
+use tracing_log::AsTrace;
+
+/**
+ * Logger:
+ *
+ * Logs are `tracing` events, so those emitted inside a span, e.g. an RPC
+ * call, a trie build or a proof extraction, carry its fields, which is what
+ * makes a failure in a batch run traceable to the block or tx it concerned.
+ * The crate's `log` macros are forwarded into `tracing` as they are. Every
+ * line is written to stderr as text or, w/ `--log-format json`, as a JSON
+ * object, after any registered secrets in it are redacted.
+ */
+struct RedactingWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    // NOTE: The formatter writes each event whole, so no secret is ever split across writes.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner
+            .write_all(redact_secrets(&String::from_utf8_lossy(buf)).as_bytes())
+            .map(|_| buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn get_redacting_stderr() -> RedactingWriter<io::Stderr> {
+    RedactingWriter {
+        inner: io::stderr(),
+    }
+}
+
+pub fn initialize_logger(log_level: LevelFilter, log_format: LogFormat) -> Result<()> {
+    let subscriber_builder = tracing_subscriber::fmt()
+        .with_max_level(log_level.as_trace())
+        .with_writer(get_redacting_stderr);
+    match log_format {
+        LogFormat::Text => subscriber_builder
+            .with_ansi(io::stderr().is_terminal())
+            .try_init(),
+        LogFormat::Json => subscriber_builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .try_init(),
+    }
+    .map_err(|e| AppError::Custom(format!("✘ Error setting up logger!\n✘ {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::register_secret;
+
+    #[test]
+    fn should_redact_secrets_in_written_log_lines() {
+        register_secret("c0ffee-logger-secret");
+        let mut writer = RedactingWriter { inner: Vec::new() };
+        let line = "✔ Calling http://node/c0ffee-logger-secret\n";
+        let num_bytes = writer.write(line.as_bytes()).unwrap();
+        let result = String::from_utf8(writer.inner).unwrap();
+        assert!(num_bytes == line.len());
+        assert!(!result.contains("c0ffee-logger-secret"));
+        assert!(result.starts_with("✔ Calling http://node/"));
+    }
+}
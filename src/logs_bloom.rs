@@ -0,0 +1,102 @@
+use crate::errors::AppError;
+use crate::types::{Byte, Log, Result};
+use crate::utils::convert_bytes_to_prefixed_hex;
+use ethereum_types::{Bloom, BloomInput};
+
+const BLOOM_LENGTH: usize = 256;
+
+/**
+ * Logs Blooms:
+ *
+ * A receipt's 2048-bit logs bloom is derived wholly from its logs, with each
+ * log's address & topics accrued into it. Since the bloom is part of the
+ * receipt's encoding, we recompute it from the logs rather than trusting the
+ * one the RPC returns. But a reported bloom differing from the recomputed one
+ * means the provider has mangled the receipt, so we check them against each
+ * other & fail loudly, before a bad receipt can poison the receipts trie.
+ */
+fn calculate_bloom_from_log(log: &Log) -> Bloom {
+    log.topics.iter().fold(
+        Bloom::from(BloomInput::Raw(log.address.as_bytes())),
+        |mut bloom, topic| {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+            bloom
+        },
+    )
+}
+
+pub fn get_logs_bloom_from_logs(logs: &[Log]) -> Result<Bloom> {
+    Ok(logs.iter().fold(Bloom::default(), |mut bloom, log| {
+        bloom.accrue_bloom(&calculate_bloom_from_log(log));
+        bloom
+    }))
+}
+
+pub fn convert_bytes_to_bloom(bytes: &[Byte]) -> Result<Bloom> {
+    match bytes.len() {
+        BLOOM_LENGTH => Ok(Bloom::from_slice(bytes)),
+        _ => Err(AppError::Custom(
+            "✘ Wrong number of bytes for logs bloom!".to_string(),
+        )),
+    }
+}
+
+pub fn get_verified_logs_bloom_from_logs(logs: &[Log], reported_bloom: &Bloom) -> Result<Bloom> {
+    let bloom = get_logs_bloom_from_logs(logs)?;
+    match bloom == *reported_bloom {
+        true => Ok(bloom),
+        false => Err(AppError::Custom(format!(
+            "✘ Receipt's logs bloom does not match the one recomputed from its logs!\n✘ Reported:   {}\n✘ Recomputed: {}\n✘ The RPC provider may be returning mangled receipts.",
+            convert_bytes_to_prefixed_hex(reported_bloom.as_bytes()),
+            convert_bytes_to_prefixed_hex(bloom.as_bytes()),
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_expected_receipt;
+
+    #[test]
+    fn should_get_logs_bloom_from_logs_correctly() {
+        let receipt = get_expected_receipt();
+        let logs = receipt.logs.clone();
+        let result = get_logs_bloom_from_logs(&logs).unwrap();
+        assert!(result == receipt.logs_bloom);
+    }
+
+    #[test]
+    fn should_calculate_bloom_from_log_correctly() {
+        let expected_bloom = "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000100000000000000000000000000080000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000800000000000000000000000000000000000000000000000000000000000000080000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000800000200000000000000000000000000000";
+        let expected_bloom_bytes = &hex::decode(expected_bloom).unwrap()[..];
+        let receipt = get_expected_receipt();
+        let log = receipt.logs[0].clone();
+        let result = calculate_bloom_from_log(&log);
+        assert!(result.as_bytes() == expected_bloom_bytes)
+    }
+
+    #[test]
+    fn should_verify_logs_bloom_matching_logs() {
+        let receipt = get_expected_receipt();
+        let result = get_verified_logs_bloom_from_logs(&receipt.logs, &receipt.logs_bloom).unwrap();
+        assert!(result == receipt.logs_bloom);
+    }
+
+    #[test]
+    fn should_fail_to_verify_mangled_logs_bloom() {
+        let receipt = get_expected_receipt();
+        let mut mangled_bloom = receipt.logs_bloom;
+        mangled_bloom.accrue(BloomInput::Raw(b"mangled"));
+        match get_verified_logs_bloom_from_logs(&receipt.logs, &mangled_bloom) {
+            Err(AppError::Custom(e)) => assert!(e.contains("mangled receipts")),
+            _ => panic!("Should not verify mangled logs bloom!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_convert_wrong_number_of_bytes_to_bloom() {
+        assert!(convert_bytes_to_bloom(&[0u8; BLOOM_LENGTH]).is_ok());
+        assert!(convert_bytes_to_bloom(&[0u8; 32]).is_err());
+    }
+}
@@ -1,80 +1,148 @@
-mod connect_to_node;
-mod constants;
-mod errors;
-mod get_block;
-mod get_branch_from_trie;
-mod get_database;
-mod get_endpoint;
-mod get_hex_proof_from_branch;
-mod get_keccak_hash;
-mod get_log;
-mod get_receipts;
-mod get_receipts_trie;
-mod get_rpc_call_jsons;
-mod get_tx_index;
-mod initialize_state_from_cli_args;
-mod make_rpc_call;
-mod nibble_utils;
-mod parse_cli_args;
-mod path_codec;
-mod rlp_codec;
-mod state;
-mod test_utils;
-mod trie;
-mod trie_nodes;
-mod types;
-mod usage_info;
-mod utils;
-mod validate_cli_args;
-mod validate_tx_hash;
-
 #[macro_use]
 extern crate log;
-#[macro_use]
-extern crate serde_json;
-#[macro_use]
-extern crate serde_derive;
-#[cfg(test)]
-#[macro_use]
-extern crate serial_test_derive;
 
-use crate::connect_to_node::connect_to_node;
-use crate::get_block::get_block_from_tx_hash_in_state_and_set_in_state;
-use crate::get_branch_from_trie::get_branch_from_trie_and_put_in_state;
-use crate::get_endpoint::get_endpoint_and_set_in_state;
-use crate::get_hex_proof_from_branch::get_hex_proof_from_branch_in_state;
-use crate::get_receipts::get_all_receipts_from_block_in_state_and_set_in_state;
-use crate::get_receipts_trie::get_receipts_trie_and_set_in_state;
-use crate::get_tx_index::get_tx_index_and_add_to_state;
-use crate::initialize_state_from_cli_args::initialize_state_from_cli_args;
-use crate::parse_cli_args::parse_cli_args;
-use crate::validate_cli_args::validate_cli_args;
+use rusty_receipt_proof_maker::doctor::run_doctor;
+use rusty_receipt_proof_maker::encryption::{decrypt_file, maybe_encrypt_output};
+use rusty_receipt_proof_maker::explain_root_mismatch::explain_root_mismatch;
+#[cfg(feature = "firehose")]
+use rusty_receipt_proof_maker::firehose::prove_from_firehose_block;
+use rusty_receipt_proof_maker::get_receipts_root::get_receipts_root;
+use rusty_receipt_proof_maker::golden_vectors::gen_vectors;
+use rusty_receipt_proof_maker::metrics::maybe_write_metrics_file;
+use rusty_receipt_proof_maker::network::maybe_detect_network;
+use rusty_receipt_proof_maker::parse_cli_args::{
+    parse_cli_args, CliArgs, CliCommand, DebugCommand,
+};
+use rusty_receipt_proof_maker::pipe::pipe;
+use rusty_receipt_proof_maker::proof_expiry::check_proof_bundles_in_file_are_fresh;
+use rusty_receipt_proof_maker::proof_formats::format_proof_formats;
+use rusty_receipt_proof_maker::proof_size_report::maybe_print_proof_size_report;
+use rusty_receipt_proof_maker::prove_block::prove_block;
+use rusty_receipt_proof_maker::prove_mapping::prove_mapping;
+use rusty_receipt_proof_maker::prove_range::{prove_range, RangeOptions, RangeOutput};
+use rusty_receipt_proof_maker::prove_receipt::{prove_index, prove_log, prove_receipt};
+use rusty_receipt_proof_maker::prove_storage::prove_storage;
+use rusty_receipt_proof_maker::record_fixtures::record_fixtures;
+use rusty_receipt_proof_maker::repl::run_repl;
+use rusty_receipt_proof_maker::serve::serve;
+use rusty_receipt_proof_maker::timings::maybe_print_timings;
+use rusty_receipt_proof_maker::trie_repl::run_trie_repl;
+use rusty_receipt_proof_maker::trie_snapshot::{
+    dump_receipts_trie_as_dot, export_receipts_trie, prove_from_trie_snapshot,
+};
+use rusty_receipt_proof_maker::types::Result;
+use rusty_receipt_proof_maker::validate_cli_args::validate_cli_args;
+use rusty_receipt_proof_maker::verify_proof::verify;
+use rusty_receipt_proof_maker::write_output::write_output;
+
+fn run_cli_command(cli_args: &CliArgs) -> Result<String> {
+    match &cli_args.command {
+        CliCommand::ProveReceipt { tx_hash } => prove_receipt(cli_args, tx_hash),
+        CliCommand::ProveLog { tx_hash, log_index } => prove_log(cli_args, tx_hash, *log_index),
+        CliCommand::ProveIndex { block, index } => prove_index(cli_args, block, *index),
+        CliCommand::ProveBlock {
+            block,
+            topic0,
+            address,
+        } => prove_block(cli_args, block, topic0, address),
+        CliCommand::ProveRange {
+            from,
+            to,
+            topic0,
+            address,
+            out_dir,
+            ndjson,
+            jobs,
+            max_tries_in_memory,
+            resume,
+        } => RangeOutput::from_cli_args(out_dir, ndjson).and_then(|output| {
+            let options = RangeOptions {
+                jobs: *jobs as usize,
+                max_tries_in_memory: *max_tries_in_memory as usize,
+                resume: *resume,
+            };
+            prove_range(cli_args, *from, *to, topic0, address, &output, options)
+        }),
+        CliCommand::Verify {
+            proof_path,
+            root,
+            block,
+            signer,
+        } => verify(cli_args, proof_path, root, block, signer),
+        CliCommand::CheckStale { proof_path } => {
+            check_proof_bundles_in_file_are_fresh(cli_args, proof_path)
+        }
+        CliCommand::Root { block } => get_receipts_root(cli_args, block),
+        CliCommand::ExplainRootMismatch { block } => explain_root_mismatch(cli_args, block),
+        CliCommand::ExportTrie {
+            block,
+            snapshot_path,
+        } => export_receipts_trie(cli_args, block, snapshot_path),
+        CliCommand::ProveFromTrie {
+            snapshot_path,
+            tx_index,
+        } => prove_from_trie_snapshot(
+            snapshot_path,
+            *tx_index,
+            cli_args.format,
+            &cli_args.telemetry,
+        ),
+        #[cfg(feature = "firehose")]
+        CliCommand::ProveFromFirehose {
+            block_path,
+            tx_index,
+        } => prove_from_firehose_block(cli_args, block_path, *tx_index),
+        CliCommand::ProveStorage {
+            address,
+            slot,
+            block,
+        } => prove_storage(cli_args, address, slot, block),
+        CliCommand::ProveMapping {
+            mapping_path,
+            keys,
+            secure,
+        } => prove_mapping(cli_args, mapping_path, keys, *secure),
+        CliCommand::Doctor => run_doctor(cli_args),
+        CliCommand::Serve { host, port } => serve(cli_args, host, *port),
+        CliCommand::Pipe => pipe(cli_args),
+        CliCommand::Repl => run_repl(cli_args),
+        CliCommand::GenVectors { out } => gen_vectors(out),
+        CliCommand::RecordFixtures { block, out } => record_fixtures(cli_args, block, out),
+        CliCommand::Formats => format_proof_formats(cli_args.format),
+        CliCommand::Debug { command } => match command {
+            DebugCommand::DumpTrie { block, out } => {
+                dump_receipts_trie_as_dot(cli_args, block, out)
+            }
+            DebugCommand::Repl => run_trie_repl(),
+        },
+        CliCommand::Decrypt {
+            encrypted_path,
+            identity_file,
+        } => decrypt_file(cli_args, encrypted_path, identity_file),
+    }
+}
 
 fn main() {
-    println!("x");
     match parse_cli_args()
-        .and_then(|state| {
-            println!("poop");
-            validate_cli_args(state)
-        })
-        .and_then(initialize_state_from_cli_args)
-        .and_then(get_endpoint_and_set_in_state)
-        .and_then(connect_to_node)
-        .and_then(get_block_from_tx_hash_in_state_and_set_in_state)
-        .and_then(get_all_receipts_from_block_in_state_and_set_in_state)
-        .and_then(get_tx_index_and_add_to_state)
-        .and_then(get_receipts_trie_and_set_in_state)
-        .and_then(get_branch_from_trie_and_put_in_state)
-        .and_then(get_hex_proof_from_branch_in_state)
-    {
-        Ok(hex_proof) => {
-            info!("✔ Hex Proof:\n");
-            trace!("{}", hex_proof);
-            println!("{}", hex_proof);
-        }
+        .and_then(validate_cli_args)
+        .and_then(maybe_detect_network)
+        .and_then(|cli_args| {
+            let result = run_cli_command(&cli_args)
+                .and_then(|output| maybe_encrypt_output(&cli_args, output))
+                .and_then(|output| write_output(&output, &cli_args.output));
+            maybe_print_timings(cli_args.timings, &cli_args.batch_tuner);
+            maybe_print_proof_size_report(cli_args.size_report);
+            // NOTE: Written even if the command failed, since failed runs' metrics matter most.
+            let metrics_result = maybe_write_metrics_file(&cli_args.metrics_file);
+            if let (Err(_), Err(e)) = (&result, &metrics_result) {
+                error!("✘ Also failed to write metrics file: {}", e);
+            }
+            result.and(metrics_result)
+        }) {
+        Ok(_) => info!("✔ Done!"),
         Err(e) => {
             error!("{}", e);
-            println!("{}", e);
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     }
@@ -1,25 +1,139 @@
 use crate::{
-    constants::REQWEST_TIMEOUT_TIME,
+    constants::RPC_ACCEPT_ENCODING,
+    dns_resolution::{get_dns_error, is_dns_error},
     errors::AppError,
-    types::{BlockRpcResponse, ReceiptRpcResponse, Result},
+    metrics::{
+        increment_counter, observe_histogram, RPC_DURATION_SECONDS, RPC_ERRORS_TOTAL,
+        RPC_REQUESTS_TOTAL,
+    },
+    rpc_client::{RpcClient, RpcClientConfig},
+    telemetry::{Telemetry, TelemetryEvent},
+    types::{
+        AccountProofRpcResponse, BatchedReceiptRpcResponse, BlockRpcResponse, Bytes,
+        ReceiptRpcResponse, Result,
+    },
 };
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use reqwest::blocking::Response;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
 use serde_json::Value as Json;
-use std::time::Duration;
+use std::io::Read;
+use std::time::Instant;
+use tracing::info_span;
 
-pub fn make_rpc_call(endpoint: &str, json: Json) -> Result<reqwest::Response> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(REQWEST_TIMEOUT_TIME))
-        .build()?;
-    Ok(client.post(endpoint).json(&json).send()?)
+// NOTE: W/ a client of its own & default settings, so only for one-off calls.
+pub fn make_rpc_call(endpoint: &str, json: Json) -> Result<Response> {
+    make_rpc_call_w_rpc_client(&RpcClient::new(RpcClientConfig::default())?, endpoint, json)
 }
 
-pub fn get_response_text(mut res: reqwest::Response) -> Result<String> {
-    let res_text = res.text()?;
-    match res_text.contains("error") {
-        true => Err(AppError::Custom(format!(
-            "✘ RPC call failed!\n✘ {}",
-            res_text
+pub fn make_rpc_call_w_rpc_client(
+    rpc_client: &RpcClient,
+    endpoint: &str,
+    json: Json,
+) -> Result<Response> {
+    let method = match json.is_array() {
+        true => "batch",
+        false => json["method"].as_str().unwrap_or("unknown"),
+    }
+    .to_string();
+    let _span = info_span!("rpc_call", method = method.as_str()).entered();
+    rpc_client.wait_for_rate_limit()?;
+    let request = rpc_client.get_client(endpoint)?.post(endpoint).json(&json);
+    let start_time = Instant::now();
+    let result = match rpc_client.is_compression_enabled() {
+        true => request.header(ACCEPT_ENCODING, RPC_ACCEPT_ENCODING),
+        false => request,
+    }
+    .send();
+    let labels = [("method", method.as_str())];
+    increment_counter(RPC_REQUESTS_TOTAL, &labels);
+    observe_histogram(
+        RPC_DURATION_SECONDS,
+        &labels,
+        start_time.elapsed().as_secs_f64(),
+    );
+    match result {
+        Ok(res) => {
+            if !res.status().is_success() {
+                increment_counter(RPC_ERRORS_TOTAL, &[("type", "http_status")]);
+            }
+            Ok(res)
+        }
+        Err(e) if is_dns_error(&e) => {
+            increment_counter(RPC_ERRORS_TOTAL, &[("type", "dns")]);
+            Err(get_dns_error(endpoint))
+        }
+        Err(e) => {
+            let error_type = if e.is_timeout() { "timeout" } else { "http" };
+            increment_counter(RPC_ERRORS_TOTAL, &[("type", error_type)]);
+            Err(e.into())
+        }
+    }
+}
+
+fn read_all_from_decoder<R: Read>(mut decoder: R) -> Result<Bytes> {
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+pub fn decompress_response_body(
+    content_encoding: Option<&str>,
+    body: Bytes,
+    telemetry: &Telemetry,
+) -> Result<Bytes> {
+    trace!("✔ RPC response content encoding: {:?}", content_encoding);
+    match content_encoding.map(|encoding| encoding.trim().to_lowercase()) {
+        None => Ok(body),
+        Some(ref encoding) if encoding == "identity" => Ok(body),
+        Some(ref encoding) if encoding == "gzip" => {
+            read_all_from_decoder(GzDecoder::new(&body[..]))
+        }
+        // NOTE: HTTP deflate is zlib-wrapped, but some servers send raw deflate streams anyway.
+        Some(ref encoding) if encoding == "deflate" => {
+            read_all_from_decoder(ZlibDecoder::new(&body[..])).or_else(|_| {
+                telemetry.emit_telemetry_event(TelemetryEvent::RpcFallbackUsed {
+                    fallback: "raw_deflate",
+                });
+                read_all_from_decoder(DeflateDecoder::new(&body[..]))
+            })
+        }
+        Some(encoding) => Err(AppError::Custom(format!(
+            "✘ Unsupported RPC response content encoding: {}",
+            encoding
         ))),
+    }
+}
+
+pub fn get_response_body_text(mut res: Response, telemetry: &Telemetry) -> Result<String> {
+    let content_encoding = res
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|header| header.to_str().ok())
+        .map(|encoding| encoding.to_string());
+    let mut body = Vec::new();
+    res.copy_to(&mut body)?;
+    String::from_utf8(decompress_response_body(
+        content_encoding.as_deref(),
+        body,
+        telemetry,
+    )?)
+    .map_err(|e| AppError::Custom(format!("✘ RPC response is not valid UTF-8: {}", e)))
+}
+
+pub fn get_response_text(res: Response) -> Result<String> {
+    get_response_body_text(res, &Telemetry::default()).and_then(check_response_text)
+}
+
+pub fn check_response_text(res_text: String) -> Result<String> {
+    match res_text.contains("error") {
+        true => {
+            increment_counter(RPC_ERRORS_TOTAL, &[("type", "rpc_error")]);
+            Err(AppError::Custom(format!(
+                "✘ RPC call failed!\n✘ {}",
+                res_text
+            )))
+        }
         false => match res_text.contains("\"result\":null") {
             true => Err(AppError::Custom(
                 "✘ No receipt found for that transaction hash!".into(),
@@ -29,6 +143,12 @@ pub fn get_response_text(mut res: reqwest::Response) -> Result<String> {
     }
 }
 
+pub fn deserialize_to_account_proof_rpc_response(
+    rpc_call_result: String,
+) -> Result<AccountProofRpcResponse> {
+    Ok(serde_json::from_str(&rpc_call_result)?)
+}
+
 pub fn deserialize_to_block_rpc_response(rpc_call_result: String) -> Result<BlockRpcResponse> {
     Ok(serde_json::from_str(&rpc_call_result)?)
 }
@@ -37,6 +157,12 @@ pub fn deserialize_to_receipt_rpc_response(rpc_call_result: String) -> Result<Re
     Ok(serde_json::from_str(&rpc_call_result)?)
 }
 
+pub fn deserialize_to_batched_receipt_rpc_responses(
+    rpc_call_result: String,
+) -> Result<Vec<BatchedReceiptRpcResponse>> {
+    Ok(serde_json::from_str(&rpc_call_result)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,6 +173,109 @@ mod tests {
         assert_block_is_correct, assert_receipt_is_correct, SAMPLE_BLOCK_HASH, SAMPLE_TX_HASH,
         WORKING_ENDPOINT,
     };
+    use crate::types::Byte;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    fn compress_with<W: Write>(mut encoder: W, bytes: &[Byte]) -> W {
+        encoder.write_all(bytes).unwrap();
+        encoder
+    }
+
+    #[test]
+    fn should_pass_through_uncompressed_response_body() {
+        let body = b"{\"result\":\"0x1\"}".to_vec();
+        assert!(
+            decompress_response_body(None, body.clone(), &Telemetry::default()).unwrap() == body
+        );
+        assert!(
+            decompress_response_body(Some("identity"), body.clone(), &Telemetry::default())
+                .unwrap()
+                == body
+        );
+    }
+
+    #[test]
+    fn should_decompress_gzip_response_body() {
+        let body = b"{\"result\":\"0x1\"}".to_vec();
+        let compressed = compress_with(GzEncoder::new(Vec::new(), Compression::default()), &body)
+            .finish()
+            .unwrap();
+        let result =
+            decompress_response_body(Some("gzip"), compressed, &Telemetry::default()).unwrap();
+        assert!(result == body);
+    }
+
+    #[test]
+    fn should_decompress_zlib_and_raw_deflate_response_bodies() {
+        let body = b"{\"result\":\"0x1\"}".to_vec();
+        let zlib = compress_with(ZlibEncoder::new(Vec::new(), Compression::default()), &body)
+            .finish()
+            .unwrap();
+        let raw = compress_with(
+            DeflateEncoder::new(Vec::new(), Compression::default()),
+            &body,
+        )
+        .finish()
+        .unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let telemetry = Telemetry::new(Arc::new(move |event: &TelemetryEvent| {
+            events_clone.lock().unwrap().push(event.clone())
+        }));
+        assert!(decompress_response_body(Some("deflate"), zlib, &telemetry).unwrap() == body);
+        assert!(events.lock().unwrap().is_empty());
+        assert!(decompress_response_body(Some("Deflate"), raw, &telemetry).unwrap() == body);
+        assert!(
+            *events.lock().unwrap()
+                == vec![TelemetryEvent::RpcFallbackUsed {
+                    fallback: "raw_deflate"
+                }]
+        );
+    }
+
+    #[test]
+    fn should_fail_to_decompress_unsupported_content_encoding() {
+        match decompress_response_body(Some("br"), vec![0xc0, 0xff, 0xee], &Telemetry::default()) {
+            Err(AppError::Custom(e)) => assert!(e.contains("Unsupported")),
+            _ => panic!("Should not decompress unsupported content encoding!"),
+        }
+    }
+
+    #[test]
+    fn should_only_ask_for_compressed_responses_if_enabled() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let handle = std::thread::spawn(move || {
+            (0..2)
+                .map(|_| {
+                    let request = server.recv().unwrap();
+                    let accept_encoding = request
+                        .headers()
+                        .iter()
+                        .find(|header| header.field.equiv("Accept-Encoding"))
+                        .map(|header| header.value.to_string());
+                    request.respond(tiny_http::Response::empty(200)).unwrap();
+                    accept_encoding
+                })
+                .collect::<Vec<Option<String>>>()
+        });
+        let uncompressed_config = RpcClientConfig {
+            compression: false,
+            ..RpcClientConfig::default()
+        };
+        vec![RpcClientConfig::default(), uncompressed_config]
+            .into_iter()
+            .for_each(|config| {
+                let rpc_client = RpcClient::new(config).unwrap();
+                make_rpc_call_w_rpc_client(&rpc_client, &endpoint, json!({})).unwrap();
+            });
+        let result = handle.join().unwrap();
+        assert!(result[0] == Some(RPC_ACCEPT_ENCODING.to_string()));
+        assert!(result[1].is_none());
+    }
 
     #[test]
     fn should_make_rpc_call_correctly() {
@@ -0,0 +1,157 @@
+use crate::constants::MANIFEST_FILE_SUFFIX;
+use crate::errors::AppError;
+use crate::types::Result;
+use crate::utils::convert_h256_to_prefixed_hex;
+use ethereum_types::H256;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/**
+ * Manifests:
+ *
+ * Exported dumps are accompanied by a JSON manifest holding the SHA-256 of
+ * each file in the dump, plus the receipts root they're expected to build.
+ * File names are stored relative to the manifest so the whole dump can be
+ * moved around, e.g. to an offline machine, as one directory.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub sha256: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub receipts_root: String,
+    pub files: Vec<ManifestFile>,
+}
+
+pub fn get_manifest_path(path: &str) -> String {
+    format!("{}{}", path, MANIFEST_FILE_SUFFIX)
+}
+
+pub fn get_sha256_of_file(path: &str) -> Result<String> {
+    Ok(hex::encode(Sha256::digest(&fs::read(path)?)))
+}
+
+fn get_file_name(path: &str) -> Result<String> {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| AppError::Custom(format!("✘ Not a path to a file: {}", path)))
+}
+
+fn get_sibling_path(manifest_path: &str, name: &str) -> String {
+    Path::new(manifest_path)
+        .with_file_name(name)
+        .to_string_lossy()
+        .to_string()
+}
+
+pub fn write_manifest(manifest_path: &str, paths: &[&str], receipts_root: H256) -> Result<()> {
+    info!("✔ Writing checksum manifest to: {}", manifest_path);
+    let manifest = Manifest {
+        receipts_root: convert_h256_to_prefixed_hex(receipts_root)?,
+        files: paths
+            .iter()
+            .map(|path| {
+                Ok(ManifestFile {
+                    name: get_file_name(path)?,
+                    sha256: get_sha256_of_file(path)?,
+                })
+            })
+            .collect::<Result<Vec<ManifestFile>>>()?,
+    };
+    Ok(fs::write(
+        manifest_path,
+        serde_json::to_string_pretty(&manifest)?,
+    )?)
+}
+
+pub fn verify_manifest(manifest_path: &str) -> Result<Manifest> {
+    info!("✔ Verifying checksum manifest at: {}", manifest_path);
+    let manifest: Manifest = serde_json::from_str(&fs::read_to_string(manifest_path)?)?;
+    manifest.files.iter().try_for_each(|file| {
+        let sha256 = get_sha256_of_file(&get_sibling_path(manifest_path, &file.name))?;
+        match sha256 == file.sha256 {
+            true => Ok(()),
+            false => Err(AppError::Custom(format!(
+                "✘ Checksum mismatch for {}!\n✘ Expected: {}\n✘ Got:      {}",
+                file.name, file.sha256, sha256
+            ))),
+        }
+    })?;
+    Ok(manifest)
+}
+
+pub fn check_receipts_root_against_manifest(
+    manifest: &Manifest,
+    receipts_root: H256,
+) -> Result<()> {
+    let receipts_root_hex = convert_h256_to_prefixed_hex(receipts_root)?;
+    match receipts_root_hex == manifest.receipts_root {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Receipts root does not match manifest!\n✘ Expected: {}\n✘ Got:      {}",
+            manifest.receipts_root, receipts_root_hex
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_sample_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-manifest-{}", name));
+        fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn should_get_sha256_of_file() {
+        let path = write_sample_file("sha256", "abc");
+        let result = get_sha256_of_file(&path).unwrap();
+        assert!(result == "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_write_and_verify_manifest() {
+        let path = write_sample_file("verify", "c0ffee");
+        let manifest_path = get_manifest_path(&path);
+        write_manifest(&manifest_path, &[&path], H256::zero()).unwrap();
+        let result = verify_manifest(&manifest_path).unwrap();
+        assert!(result.files.len() == 1);
+        assert!(result.files[0].name == "rrpm-manifest-verify");
+        assert!(check_receipts_root_against_manifest(&result, H256::zero()).is_ok());
+        fs::remove_file(path).unwrap();
+        fs::remove_file(manifest_path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_verify_manifest_of_corrupted_file() {
+        let path = write_sample_file("corrupt", "c0ffee");
+        let manifest_path = get_manifest_path(&path);
+        write_manifest(&manifest_path, &[&path], H256::zero()).unwrap();
+        fs::write(&path, "decaf").unwrap();
+        match verify_manifest(&manifest_path) {
+            Err(AppError::Custom(e)) => assert!(e.contains("Checksum mismatch")),
+            _ => panic!("Should not verify manifest of corrupted file!"),
+        }
+        fs::remove_file(path).unwrap();
+        fs::remove_file(manifest_path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_receipts_root_check_against_manifest_with_other_root() {
+        let manifest = Manifest {
+            receipts_root: convert_h256_to_prefixed_hex(H256::zero()).unwrap(),
+            files: vec![],
+        };
+        let result = check_receipts_root_against_manifest(&manifest, H256::repeat_byte(1));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use crate::telemetry::TelemetryEvent;
+use crate::types::Result;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+pub const RPC_REQUESTS_TOTAL: &str = "rrpm_rpc_requests_total";
+pub const RPC_ERRORS_TOTAL: &str = "rrpm_rpc_errors_total";
+pub const RPC_DURATION_SECONDS: &str = "rrpm_rpc_duration_seconds";
+pub const RPC_FALLBACKS_TOTAL: &str = "rrpm_rpc_fallbacks_total";
+pub const RPC_DEDUPLICATED_TOTAL: &str = "rrpm_rpc_deduplicated_total";
+pub const TRIE_BUILD_DURATION_SECONDS: &str = "rrpm_trie_build_duration_seconds";
+pub const TRIE_CACHE_HITS_TOTAL: &str = "rrpm_trie_cache_hits_total";
+pub const TRIE_CACHE_MISSES_TOTAL: &str = "rrpm_trie_cache_misses_total";
+pub const PROOFS_GENERATED_TOTAL: &str = "rrpm_proofs_generated_total";
+pub const VERIFICATION_FAILURES_TOTAL: &str = "rrpm_verification_failures_total";
+pub const HTTP_REQUESTS_TOTAL: &str = "rrpm_http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "rrpm_http_request_duration_seconds";
+
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+const HISTOGRAM_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/**
+ * Metrics:
+ *
+ * Process-wide counters & histograms, rendered in the Prometheus text
+ * exposition format. The `serve` daemon exposes them on `GET /metrics`, &
+ * one-shot & batch runs can dump them to a file via `--metrics-file` for
+ * the node exporter's textfile collector to pick up. Recording a metric
+ * never fails the operation being measured.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MetricKind {
+    Counter,
+    Histogram,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+struct MetricDefinition {
+    name: &'static str,
+    kind: MetricKind,
+    help: &'static str,
+}
+
+static METRIC_DEFINITIONS: [MetricDefinition; 12] = [
+    MetricDefinition {
+        name: RPC_REQUESTS_TOTAL,
+        kind: MetricKind::Counter,
+        help: "JSON-RPC requests made to the node, by method.",
+    },
+    MetricDefinition {
+        name: RPC_ERRORS_TOTAL,
+        kind: MetricKind::Counter,
+        help: "Failed JSON-RPC requests, by error type.",
+    },
+    MetricDefinition {
+        name: RPC_DURATION_SECONDS,
+        kind: MetricKind::Histogram,
+        help: "JSON-RPC request latency in seconds, by method.",
+    },
+    MetricDefinition {
+        name: RPC_FALLBACKS_TOTAL,
+        kind: MetricKind::Counter,
+        help: "Fallbacks used to work around quirky node responses, by fallback.",
+    },
+    MetricDefinition {
+        name: RPC_DEDUPLICATED_TOTAL,
+        kind: MetricKind::Counter,
+        help: "JSON-RPC requests saved by sharing an identical in-flight one, by method.",
+    },
+    MetricDefinition {
+        name: TRIE_BUILD_DURATION_SECONDS,
+        kind: MetricKind::Histogram,
+        help: "Time taken to build a receipts trie in seconds.",
+    },
+    MetricDefinition {
+        name: TRIE_CACHE_HITS_TOTAL,
+        kind: MetricKind::Counter,
+        help: "Receipts tries served from the daemon's trie cache.",
+    },
+    MetricDefinition {
+        name: TRIE_CACHE_MISSES_TOTAL,
+        kind: MetricKind::Counter,
+        help: "Receipts tries the daemon had to build as they weren't cached.",
+    },
+    MetricDefinition {
+        name: PROOFS_GENERATED_TOTAL,
+        kind: MetricKind::Counter,
+        help: "Proofs generated, by proof type.",
+    },
+    MetricDefinition {
+        name: VERIFICATION_FAILURES_TOTAL,
+        kind: MetricKind::Counter,
+        help: "Proofs that failed verification.",
+    },
+    MetricDefinition {
+        name: HTTP_REQUESTS_TOTAL,
+        kind: MetricKind::Counter,
+        help: "HTTP requests handled by the daemon, by route & status code.",
+    },
+    MetricDefinition {
+        name: HTTP_REQUEST_DURATION_SECONDS,
+        kind: MetricKind::Histogram,
+        help: "Time taken to handle an HTTP request in seconds, by route.",
+    },
+];
+
+type Labels = Vec<(&'static str, String)>;
+type SeriesKey = (&'static str, Labels);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Histogram {
+    cumulative_bucket_counts: [u64; 11],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        HISTOGRAM_BUCKETS
+            .iter()
+            .zip(self.cumulative_bucket_counts.iter_mut())
+            .filter(|(upper_bound, _)| value <= **upper_bound)
+            .for_each(|(_, bucket_count)| *bucket_count += 1);
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+static COUNTERS: Mutex<BTreeMap<SeriesKey, u64>> = Mutex::new(BTreeMap::new());
+static HISTOGRAMS: Mutex<BTreeMap<SeriesKey, Histogram>> = Mutex::new(BTreeMap::new());
+
+// NOTE: A panic mid-update can't leave a counter half-written, so a poisoned lock is still usable.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn get_series_key(name: &'static str, labels: &[(&'static str, &str)]) -> SeriesKey {
+    (
+        name,
+        labels
+            .iter()
+            .map(|(label, value)| (*label, value.to_string()))
+            .collect(),
+    )
+}
+
+pub fn increment_counter_by(name: &'static str, labels: &[(&'static str, &str)], amount: u64) {
+    *lock(&COUNTERS)
+        .entry(get_series_key(name, labels))
+        .or_insert(0) += amount;
+}
+
+pub fn increment_counter(name: &'static str, labels: &[(&'static str, &str)]) {
+    increment_counter_by(name, labels, 1)
+}
+
+pub fn observe_histogram(name: &'static str, labels: &[(&'static str, &str)], seconds: f64) {
+    lock(&HISTOGRAMS)
+        .entry(get_series_key(name, labels))
+        .or_default()
+        .observe(seconds)
+}
+
+pub fn time_histogram<T>(
+    name: &'static str,
+    labels: &[(&'static str, &str)],
+    f: impl FnOnce() -> T,
+) -> T {
+    let start_time = Instant::now();
+    let result = f();
+    observe_histogram(name, labels, start_time.elapsed().as_secs_f64());
+    result
+}
+
+// NOTE: Each of a histogram's series' label values, observation count & sum, for summaries.
+pub fn get_histogram_totals(name: &'static str) -> Vec<(Vec<String>, u64, f64)> {
+    lock(&HISTOGRAMS)
+        .iter()
+        .filter(|((series_name, _), _)| *series_name == name)
+        .map(|((_, labels), histogram)| {
+            (
+                labels.iter().map(|(_, value)| value.clone()).collect(),
+                histogram.count,
+                histogram.sum,
+            )
+        })
+        .collect()
+}
+
+pub fn record_telemetry_event(event: &TelemetryEvent) {
+    match event {
+        TelemetryEvent::ProofGenerated {
+            proof_type,
+            num_proofs,
+        } => increment_counter_by(
+            PROOFS_GENERATED_TOTAL,
+            &[("proof_type", proof_type)],
+            *num_proofs as u64,
+        ),
+        TelemetryEvent::VerificationFailed { num_proofs } => {
+            increment_counter_by(VERIFICATION_FAILURES_TOTAL, &[], *num_proofs as u64)
+        }
+        TelemetryEvent::RpcFallbackUsed { fallback } => {
+            increment_counter(RPC_FALLBACKS_TOTAL, &[("fallback", fallback)])
+        }
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_labels(labels: &[(&'static str, String)]) -> String {
+    match labels.is_empty() {
+        true => String::new(),
+        false => format!(
+            "{{{}}}",
+            labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+    }
+}
+
+fn format_histogram(name: &str, labels: &Labels, histogram: &Histogram) -> Vec<String> {
+    let get_bucket_labels = |upper_bound: String| {
+        let mut bucket_labels = labels.clone();
+        bucket_labels.push(("le", upper_bound));
+        format_labels(&bucket_labels)
+    };
+    HISTOGRAM_BUCKETS
+        .iter()
+        .zip(histogram.cumulative_bucket_counts.iter())
+        .map(|(upper_bound, count)| (upper_bound.to_string(), *count))
+        .chain(std::iter::once(("+Inf".to_string(), histogram.count)))
+        .map(|(upper_bound, count)| {
+            format!(
+                "{}_bucket{} {}",
+                name,
+                get_bucket_labels(upper_bound),
+                count
+            )
+        })
+        .chain(vec![
+            format!("{}_sum{} {}", name, format_labels(labels), histogram.sum),
+            format!(
+                "{}_count{} {}",
+                name,
+                format_labels(labels),
+                histogram.count
+            ),
+        ])
+        .collect()
+}
+
+pub fn render_metrics() -> String {
+    let counters = lock(&COUNTERS).clone();
+    let histograms = lock(&HISTOGRAMS).clone();
+    METRIC_DEFINITIONS
+        .iter()
+        .flat_map(|definition| {
+            let header = vec![
+                format!("# HELP {} {}", definition.name, definition.help),
+                format!("# TYPE {} {}", definition.name, definition.kind.as_str()),
+            ];
+            let series = match definition.kind {
+                MetricKind::Counter => counters
+                    .iter()
+                    .filter(|((name, _), _)| *name == definition.name)
+                    .map(|((name, labels), count)| {
+                        format!("{}{} {}", name, format_labels(labels), count)
+                    })
+                    .collect::<Vec<String>>(),
+                MetricKind::Histogram => histograms
+                    .iter()
+                    .filter(|((name, _), _)| *name == definition.name)
+                    .flat_map(|((name, labels), histogram)| {
+                        format_histogram(name, labels, histogram)
+                    })
+                    .collect(),
+            };
+            header.into_iter().chain(series)
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+pub fn maybe_write_metrics_file(maybe_path: &Option<String>) -> Result<()> {
+    match maybe_path {
+        None => Ok(()),
+        Some(path) => {
+            info!("✔ Writing metrics to: {}", path);
+            Ok(std::fs::write(path, render_metrics())?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_metric_line(metrics: &str, prefix: &str) -> Option<String> {
+        metrics
+            .lines()
+            .find(|line| line.starts_with(prefix))
+            .map(|line| line.to_string())
+    }
+
+    #[test]
+    fn should_increment_counters_per_label_set() {
+        increment_counter(RPC_REQUESTS_TOTAL, &[("method", "test_counterA")]);
+        increment_counter(RPC_REQUESTS_TOTAL, &[("method", "test_counterA")]);
+        increment_counter_by(RPC_REQUESTS_TOTAL, &[("method", "test_counterB")], 5);
+        let metrics = render_metrics();
+        let line_a = format!("{}{{method=\"test_counterA\"}} ", RPC_REQUESTS_TOTAL);
+        let line_b = format!("{}{{method=\"test_counterB\"}} ", RPC_REQUESTS_TOTAL);
+        assert!(get_metric_line(&metrics, &line_a).unwrap().ends_with(" 2"));
+        assert!(get_metric_line(&metrics, &line_b).unwrap().ends_with(" 5"));
+    }
+
+    #[test]
+    fn should_render_histograms_with_cumulative_buckets() {
+        let labels = [("method", "test_histogram")];
+        observe_histogram(RPC_DURATION_SECONDS, &labels, 0.007);
+        observe_histogram(RPC_DURATION_SECONDS, &labels, 0.3);
+        observe_histogram(RPC_DURATION_SECONDS, &labels, 60.0);
+        let metrics = render_metrics();
+        let get_value = |suffix: &str| {
+            let prefix = format!("{}{}", RPC_DURATION_SECONDS, suffix);
+            get_metric_line(&metrics, &prefix)
+                .unwrap()
+                .rsplit(' ')
+                .next()
+                .unwrap()
+                .to_string()
+        };
+        assert!(get_value("_bucket{method=\"test_histogram\",le=\"0.005\"}") == "0");
+        assert!(get_value("_bucket{method=\"test_histogram\",le=\"0.01\"}") == "1");
+        assert!(get_value("_bucket{method=\"test_histogram\",le=\"0.5\"}") == "2");
+        assert!(get_value("_bucket{method=\"test_histogram\",le=\"10\"}") == "2");
+        assert!(get_value("_bucket{method=\"test_histogram\",le=\"+Inf\"}") == "3");
+        assert!(get_value("_count{method=\"test_histogram\"}") == "3");
+        assert!(get_value("_sum{method=\"test_histogram\"}").starts_with("60.307"));
+    }
+
+    #[test]
+    fn should_render_help_and_type_of_every_metric() {
+        let metrics = render_metrics();
+        METRIC_DEFINITIONS.iter().for_each(|definition| {
+            let type_line = format!("# TYPE {} {}", definition.name, definition.kind.as_str());
+            assert!(metrics.contains(&format!("# HELP {} ", definition.name)));
+            assert!(metrics.contains(&type_line));
+        });
+    }
+
+    #[test]
+    fn should_escape_label_values() {
+        let labels = vec![("fallback", "a \"quoted\\\" \nvalue".to_string())];
+        let result = format_labels(&labels);
+        assert!(result == "{fallback=\"a \\\"quoted\\\\\\\" \\nvalue\"}");
+    }
+
+    #[test]
+    fn should_record_telemetry_events_as_metrics() {
+        record_telemetry_event(&TelemetryEvent::ProofGenerated {
+            proof_type: "test_telemetry",
+            num_proofs: 3,
+        });
+        let prefix = format!(
+            "{}{{proof_type=\"test_telemetry\"}} ",
+            PROOFS_GENERATED_TOTAL
+        );
+        let line = get_metric_line(&render_metrics(), &prefix).unwrap();
+        assert!(line.ends_with(" 3"));
+    }
+
+    #[test]
+    fn should_write_metrics_file() {
+        let path = std::env::temp_dir().join("rrpm-metrics-test.prom");
+        let path_str = path.to_str().unwrap().to_string();
+        maybe_write_metrics_file(&Some(path_str)).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains(&format!("# TYPE {} counter", RPC_REQUESTS_TOTAL)));
+        std::fs::remove_file(&path).unwrap();
+        assert!(maybe_write_metrics_file(&None).is_ok());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::chain_profile::ChainProfile;
+use crate::errors::AppError;
+use crate::get_endpoint::get_endpoint;
+use crate::get_rpc_call_jsons::get_net_version_json;
+use crate::hardfork::get_chain_id;
+use crate::json_rpc_client::call_json_rpc;
+use crate::make_rpc_call::check_response_text;
+use crate::parse_cli_args::{CliArgs, CliCommand, DebugCommand};
+use crate::proof_bundle::ProofBundle;
+use crate::trie_checkpoint::write_atomically;
+use crate::types::Result;
+use serde_json::Value as Json;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+pub const CACHE_DIR_NETWORK_FILE_NAME: &str = "network.json";
+
+static DETECTED_NETWORK: Mutex<Option<Network>> = Mutex::new(None);
+
+/**
+ * Network Detection:
+ *
+ * Proofs carried no sign of which network they were made on, & a `--cache-dir`
+ * shared by endpoints of different chains could serve one chain's tries when
+ * proving another's. So on startup, commands querying a node first fetch its
+ * `eth_chainId` & `net_version`, warning if they disagree w/ each other or w/
+ * the `--chain` profile, & record both in the full JSON proof bundles made.
+ * The cache dir records the chain id it was first used w/ in its
+ * `network.json`, & refuses to be used w/ any other.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Network {
+    pub chain_id: u64,
+    pub net_version: String,
+}
+
+pub fn get_net_version(endpoint: &str) -> Result<String> {
+    get_net_version_json()
+        .and_then(|rpc_json| call_json_rpc(endpoint, rpc_json))
+        .and_then(check_response_text)
+        .and_then(|text| Ok(serde_json::from_str::<Json>(&text)?))
+        .and_then(|json| match json["result"].as_str() {
+            Some(net_version) => Ok(net_version.to_string()),
+            None => Err(AppError::Custom(
+                "✘ Could not get net version from node!".to_string(),
+            )),
+        })
+}
+
+pub fn warn_of_network_inconsistencies(network: &Network, chain_profile: ChainProfile) {
+    // NOTE: Some chains legitimately differ here, e.g. Ethereum Classic's chain id 61 & net version 1.
+    if network.net_version != network.chain_id.to_string() {
+        warn!(
+            "✘ Node's chain id {} differs from its net version {}!",
+            network.chain_id, network.net_version
+        );
+    }
+    match ChainProfile::from_chain_id(network.chain_id) {
+        Some(expected_profile) if expected_profile != chain_profile => warn!(
+            "✘ Node's chain id {} expects the {:?} chain profile, but {:?} is in use!",
+            network.chain_id, expected_profile, chain_profile
+        ),
+        _ => (),
+    }
+}
+
+pub fn detect_network(endpoint: &str, chain_profile: ChainProfile) -> Result<Network> {
+    let network = Network {
+        chain_id: get_chain_id(endpoint)?,
+        net_version: get_net_version(endpoint)?,
+    };
+    info!(
+        "✔ Detected network w/ chain id {} & net version {}",
+        network.chain_id, network.net_version
+    );
+    warn_of_network_inconsistencies(&network, chain_profile);
+    Ok(network)
+}
+
+pub fn check_cache_dir_network(cache_dir: &str, network: &Network) -> Result<()> {
+    let path = Path::new(cache_dir).join(CACHE_DIR_NETWORK_FILE_NAME);
+    if !path.exists() {
+        fs::create_dir_all(cache_dir)?;
+        return write_atomically(&path.to_string_lossy(), |temp_path| {
+            Ok(fs::write(temp_path, serde_json::to_string(network)?)?)
+        });
+    }
+    let cached_network: Network = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    match cached_network.chain_id == network.chain_id {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Cache dir {} holds data of chain id {}, but the node is on chain id {}!\n✘ Use another `--cache-dir` for this chain.",
+            cache_dir, cached_network.chain_id, network.chain_id
+        ))),
+    }
+}
+
+// NOTE: Commands that work offline, or diagnose the node themselves, skip detection.
+fn command_queries_node(command: &CliCommand) -> bool {
+    match command {
+        CliCommand::ProveReceipt { .. }
+        | CliCommand::ProveLog { .. }
+        | CliCommand::ProveIndex { .. }
+        | CliCommand::ProveBlock { .. }
+        | CliCommand::ProveRange { .. }
+        | CliCommand::CheckStale { .. }
+        | CliCommand::Root { .. }
+        | CliCommand::ExplainRootMismatch { .. }
+        | CliCommand::ExportTrie { .. }
+        | CliCommand::ProveStorage { .. }
+        | CliCommand::Serve { .. }
+        | CliCommand::Pipe
+        | CliCommand::RecordFixtures { .. }
+        | CliCommand::Debug {
+            command: DebugCommand::DumpTrie { .. },
+        } => true,
+        CliCommand::Verify { .. }
+        | CliCommand::ProveFromTrie { .. }
+        | CliCommand::ProveMapping { .. }
+        | CliCommand::Doctor
+        | CliCommand::Repl
+        | CliCommand::GenVectors { .. }
+        | CliCommand::Formats
+        | CliCommand::Decrypt { .. }
+        | CliCommand::Debug {
+            command: DebugCommand::Repl,
+        } => false,
+        #[cfg(feature = "firehose")]
+        CliCommand::ProveFromFirehose { .. } => false,
+    }
+}
+
+pub fn set_detected_network(maybe_network: &Option<Network>) {
+    if let Ok(mut detected_network) = DETECTED_NETWORK.lock() {
+        *detected_network = maybe_network.clone();
+    }
+}
+
+pub fn get_detected_network() -> Option<Network> {
+    DETECTED_NETWORK.lock().ok()?.clone()
+}
+
+pub fn maybe_detect_network(cli_args: CliArgs) -> Result<CliArgs> {
+    if !command_queries_node(&cli_args.command) {
+        return Ok(cli_args);
+    }
+    let endpoint = get_endpoint(&cli_args)?;
+    let network = detect_network(&endpoint, cli_args.chain)?;
+    if let Some(cache_dir) = &cli_args.cache_dir {
+        check_cache_dir_network(cache_dir, &network)?;
+    }
+    set_detected_network(&Some(network));
+    Ok(cli_args)
+}
+
+pub fn set_network_in_proof_bundle(
+    bundle: ProofBundle,
+    maybe_network: &Option<Network>,
+) -> ProofBundle {
+    match maybe_network {
+        Some(network) => ProofBundle {
+            chain_id: Some(network.chain_id),
+            net_version: Some(network.net_version.clone()),
+            ..bundle
+        },
+        None => bundle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    fn get_sample_network(chain_id: u64) -> Network {
+        Network {
+            chain_id,
+            net_version: chain_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_refuse_cache_dir_of_another_chain_id() {
+        let dir = std::env::temp_dir().join("rrpm-network-cache-dir");
+        let dir = dir.to_str().unwrap().to_string();
+        let _ = fs::remove_dir_all(&dir);
+        let first_use = check_cache_dir_network(&dir, &get_sample_network(1));
+        let same_chain = check_cache_dir_network(&dir, &get_sample_network(1));
+        let other_chain = check_cache_dir_network(&dir, &get_sample_network(10));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(first_use.is_ok());
+        assert!(same_chain.is_ok());
+        match other_chain {
+            Err(AppError::Custom(e)) => assert!(e.contains("holds data of chain id 1")),
+            _ => panic!("Should refuse cache dir of another chain id!"),
+        }
+    }
+
+    #[test]
+    fn should_set_network_in_proof_bundle() {
+        let bundle = get_sample_proof_bundle_1();
+        let result = set_network_in_proof_bundle(bundle.clone(), &Some(get_sample_network(1)));
+        assert!(result.chain_id == Some(1));
+        assert!(result.net_version == Some("1".to_string()));
+        assert!(set_network_in_proof_bundle(bundle.clone(), &None) == bundle);
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["chain_id"] == 1 && json["net_version"] == "1");
+    }
+}
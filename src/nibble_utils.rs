@@ -1,14 +1,76 @@
+/*!
+ * Nibble Utils:
+ *
+ * The nibble paths & hex-prefix (aka compact) encoding the trie keys & stores
+ * its nodes' paths with. Downstream verifiers, e.g. on-chain or in another
+ * language, must walk proofs' paths exactly as the trie laid them out, so
+ * rather than re-deriving the encoding & risking diverging from it, they can
+ * reuse this module's `Nibbles`, whose methods are its stable API:
+ *
+ * - `from_bytes`, `from_offset_bytes` & `from_nibble_values` build nibbles,
+ *   checking each is in its canonical form.
+ * - `len`, `get`, `to_nibble_values`, `slice`, `take`, `skip` & `concat` read
+ *   & combine them.
+ * - `to_hex_prefix_encoding` & `from_hex_prefix_encoding` are the compact
+ *   codec of leaf & extension nodes' paths.
+ *
+ * E.g. to encode a leaf's path & decode it again:
+ *
+ * ```
+ * use rusty_receipt_proof_maker::nibble_utils::Nibbles;
+ *
+ * let path = Nibbles::from_nibble_values(&[0x0f, 0x01, 0x0c, 0x0b, 0x08])?;
+ * let encoded_path = path.to_hex_prefix_encoding(true)?;
+ * assert_eq!(encoded_path, vec![0x3f, 0x1c, 0xb8]);
+ *
+ * let (decoded_path, is_leaf) = Nibbles::from_hex_prefix_encoding(&encoded_path)?;
+ * assert!(is_leaf);
+ * assert_eq!(decoded_path, path);
+ * assert_eq!(decoded_path.skip(1)?.to_nibble_values()?, vec![0x01, 0x0c, 0x0b, 0x08]);
+ * # Ok::<(), rusty_receipt_proof_maker::errors::AppError>(())
+ * ```
+ *
+ * Or to get the nibble path of a receipt's key, i.e. its RLP encoded tx index:
+ *
+ * ```
+ * use rusty_receipt_proof_maker::nibble_utils::Nibbles;
+ *
+ * let key = Nibbles::from_bytes(&[0x81, 0x80]);
+ * assert_eq!(key.len(), 4);
+ * assert_eq!(key.to_nibble_values()?, vec![0x08, 0x01, 0x08, 0x00]);
+ * # Ok::<(), rusty_receipt_proof_maker::errors::AppError>(())
+ * ```
+ *
+ * The free functions below are the trie's own plumbing & are crate private.
+ */
 use crate::constants::{
     EMPTY_NIBBLES, HIGH_NIBBLE_MASK, NUM_BITS_IN_NIBBLE, NUM_NIBBLES_IN_BYTE, ZERO_BYTE,
 };
 use crate::errors::AppError;
 use crate::types::{Byte, Bytes, Result};
 use std::fmt;
-
+use std::ops::Range;
+
+const LEAF_FLAG: u8 = 2u8; // [00000010]
+const ODD_LENGTH_FLAG: u8 = 1u8; // [00000001]
+const ODD_LENGTH_LEAF_PREFIX_NIBBLE: u8 = 3u8; // [00000011]
+const EVEN_LENGTH_LEAF_PREFIX_NIBBLE: u8 = 2u8; // [00000010]
+const ODD_LENGTH_EXTENSION_PREFIX_NIBBLE: u8 = 1u8; // [00000001]
+const EVEN_LENGTH_EXTENSION_PREFIX_NIBBLE: u8 = 0u8; // [00000000]
+
+/**
+ * Nibbles:
+ *
+ * A sequence of 4-bit nibbles packed two to a byte. An odd-length sequence
+ * has an `offset` of one, meaning its first byte's high nibble is unused
+ * padding, which must be zero for two equal sequences to compare equal. The
+ * checked constructors below enforce that, & the slicing & concatenation
+ * methods only ever return sequences in that canonical form.
+ */
 #[derive(Clone, Eq)]
 pub struct Nibbles {
-    pub data: Bytes,
-    pub offset: usize,
+    pub(crate) data: Bytes,
+    pub(crate) offset: usize,
 }
 
 impl PartialEq for Nibbles {
@@ -35,9 +97,135 @@ impl Nibbles {
     pub fn len(&self) -> usize {
         get_length_in_nibbles(self)
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_odd_length(&self) -> bool {
+        self.len() % 2 == 1
+    }
+
+    pub fn from_bytes(bytes: &[Byte]) -> Self {
+        get_nibbles_from_bytes(bytes.to_vec())
+    }
+
+    pub fn from_offset_bytes(bytes: &[Byte]) -> Result<Self> {
+        match bytes.first() {
+            None => Err(AppError::Custom(
+                "✘ Cannot make offset nibbles from empty bytes!".to_string(),
+            )),
+            Some(first_byte) if shift_nibble_right(*first_byte) != ZERO_BYTE => {
+                Err(AppError::Custom(format!(
+                    "✘ Offset nibbles must have a zero high nibble in their first byte, not 0x{:02x}!",
+                    first_byte
+                )))
+            }
+            Some(_) => Ok(get_nibbles_from_offset_bytes(bytes.to_vec())),
+        }
+    }
+
+    pub fn from_nibble_values(nibble_values: &[Byte]) -> Result<Self> {
+        if let Some(nibble) = nibble_values
+            .iter()
+            .find(|nibble| **nibble > HIGH_NIBBLE_MASK)
+        {
+            return Err(AppError::Custom(format!(
+                "✘ 0x{:02x} is too large to be a nibble!",
+                nibble
+            )));
+        };
+        let (padding, offset): (Bytes, usize) = match nibble_values.len() % 2 {
+            0 => (vec![], 0),
+            _ => (vec![ZERO_BYTE], 1),
+        };
+        let data: Bytes = padding
+            .iter()
+            .chain(nibble_values.iter())
+            .collect::<Vec<&Byte>>()
+            .chunks(NUM_NIBBLES_IN_BYTE)
+            .map(|pair| shift_nibble_left(*pair[0]) | pair[1])
+            .collect();
+        match (nibble_values.is_empty(), offset) {
+            (true, _) => Ok(EMPTY_NIBBLES),
+            (false, 0) => Ok(Nibbles::from_bytes(&data)),
+            (false, _) => Nibbles::from_offset_bytes(&data),
+        }
+    }
+
+    pub fn to_nibble_values(&self) -> Result<Vec<Byte>> {
+        (0..self.len())
+            .map(|i| get_nibble_at_index(self, i))
+            .collect()
+    }
+
+    pub fn get(&self, nibble_index: usize) -> Result<Byte> {
+        get_nibble_at_index(self, nibble_index)
+    }
+
+    pub fn slice(&self, range: Range<usize>) -> Result<Self> {
+        match range.start <= range.end && range.end <= self.len() {
+            true => Nibbles::from_nibble_values(&self.to_nibble_values()?[range]),
+            false => Err(AppError::Custom(format!(
+                "✘ Range {:?} is out-of-bounds in nibble vector of length {}!",
+                range,
+                self.len()
+            ))),
+        }
+    }
+
+    pub fn take(&self, num_nibbles: usize) -> Result<Self> {
+        self.slice(0..num_nibbles)
+    }
+
+    pub fn skip(&self, num_nibbles: usize) -> Result<Self> {
+        self.slice(num_nibbles..self.len())
+    }
+
+    pub fn concat(&self, other: &Nibbles) -> Result<Self> {
+        let mut nibble_values = self.to_nibble_values()?;
+        nibble_values.append(&mut other.to_nibble_values()?);
+        Nibbles::from_nibble_values(&nibble_values)
+    }
+
+    /**
+     * Hex-Prefix Encoding:
+     *
+     * A flag nibble marking whether the path is a leaf's or an extension's &
+     * whether it's of odd length, followed by a zero padding nibble if it
+     * isn't, then the path's nibbles, packed two to a byte.
+     */
+    pub fn to_hex_prefix_encoding(&self, is_leaf: bool) -> Result<Bytes> {
+        let flag = if is_leaf { LEAF_FLAG } else { 0 };
+        let prefix = match self.is_odd_length() {
+            true => Nibbles::from_nibble_values(&[flag | ODD_LENGTH_FLAG])?,
+            false => Nibbles::from_nibble_values(&[flag, 0])?,
+        };
+        prefix.concat(self).map(|nibbles| nibbles.data)
+    }
+
+    // NOTE: Returns the path's nibbles & whether it belongs to a leaf node.
+    pub fn from_hex_prefix_encoding(path: &[Byte]) -> Result<(Nibbles, bool)> {
+        let nibbles = Nibbles::from_bytes(path);
+        let (is_leaf, num_prefix_nibbles) = match nibbles.get(0) {
+            Ok(EVEN_LENGTH_LEAF_PREFIX_NIBBLE) => Ok((true, 2)),
+            Ok(EVEN_LENGTH_EXTENSION_PREFIX_NIBBLE) => Ok((false, 2)),
+            Ok(ODD_LENGTH_LEAF_PREFIX_NIBBLE) => Ok((true, 1)),
+            Ok(ODD_LENGTH_EXTENSION_PREFIX_NIBBLE) => Ok((false, 1)),
+            _ => Err(AppError::Custom(
+                "✘ Malformed path - cannot determine node type!".to_string(),
+            )),
+        }?;
+        match num_prefix_nibbles == 2 && nibbles.get(1)? != 0 {
+            true => Err(AppError::Custom(
+                "✘ Malformed path - even length path has non-zero padding nibble!".to_string(),
+            )),
+            false => Ok((nibbles.skip(num_prefix_nibbles)?, is_leaf)),
+        }
+    }
 }
 
-pub fn get_common_prefix_nibbles(
+pub(crate) fn get_common_prefix_nibbles(
     nibbles_a: Nibbles,
     nibbles_b: Nibbles,
 ) -> Result<(Nibbles, Nibbles, Nibbles)> {
@@ -172,7 +360,7 @@ fn remove_first_nibble(nibbles: Nibbles) -> Result<Nibbles> {
     }
 }
 
-pub fn get_zero_nibble() -> Nibbles {
+pub(crate) fn get_zero_nibble() -> Nibbles {
     Nibbles {
         data: vec![ZERO_BYTE],
         offset: 1,
@@ -199,35 +387,28 @@ fn remove_last_byte_from_nibbles(nibbles: Nibbles) -> Nibbles {
     }
 }
 
-pub fn set_nibble_offset_to_zero(nibbles: Nibbles) -> Nibbles {
-    Nibbles {
-        data: nibbles.data,
-        offset: 0,
-    }
-}
-
-pub fn set_nibble_offset_to_one(nibbles: Nibbles) -> Nibbles {
+pub(crate) fn set_nibble_offset_to_one(nibbles: Nibbles) -> Nibbles {
     Nibbles {
         data: nibbles.data,
         offset: 1,
     }
 }
 
-pub fn get_nibbles_from_bytes(nibbles: Bytes) -> Nibbles {
+pub(crate) fn get_nibbles_from_bytes(nibbles: Bytes) -> Nibbles {
     Nibbles {
         data: nibbles,
         offset: 0,
     }
 }
 
-pub fn get_nibbles_from_offset_bytes(nibbles: Bytes) -> Nibbles {
+pub(crate) fn get_nibbles_from_offset_bytes(nibbles: Bytes) -> Nibbles {
     Nibbles {
         data: nibbles,
         offset: 1,
     }
 }
 
-pub fn replace_nibble_in_nibbles_at_nibble_index(
+pub(crate) fn replace_nibble_in_nibbles_at_nibble_index(
     nibbles: Nibbles,
     replacement_nibble: Nibbles,
     nibble_index: usize,
@@ -265,14 +446,14 @@ fn replace_byte_in_nibbles_at_byte_index(index: usize, nibbles: Nibbles, byte: B
     }
 }
 
-pub fn replace_high_nibble_in_byte(byte: Byte, replacement_nibble: Nibbles) -> Byte {
+pub(crate) fn replace_high_nibble_in_byte(byte: Byte, replacement_nibble: Nibbles) -> Byte {
     match replacement_nibble.offset {
         0 => merge_nibbles_from_bytes(byte, replacement_nibble.data[0]),
         _ => merge_nibbles_from_bytes(byte, shift_nibble_left(replacement_nibble.data[0])),
     }
 }
 
-pub fn replace_low_nibble_in_byte(byte: Byte, replacement_nibble: Nibbles) -> Byte {
+pub(crate) fn replace_low_nibble_in_byte(byte: Byte, replacement_nibble: Nibbles) -> Byte {
     match replacement_nibble.offset {
         1 => merge_nibbles_from_bytes(replacement_nibble.data[0], byte),
         _ => merge_nibbles_from_bytes(shift_nibble_right(replacement_nibble.data[0]), byte),
@@ -283,24 +464,22 @@ fn merge_nibbles_from_bytes(low_nibble_byte: Byte, high_nibble_byte: Byte) -> By
     high_nibble_byte ^ ((high_nibble_byte ^ low_nibble_byte) & HIGH_NIBBLE_MASK)
 }
 
-pub fn get_length_in_nibbles(nibbles: &Nibbles) -> usize {
+pub(crate) fn get_length_in_nibbles(nibbles: &Nibbles) -> usize {
     nibbles.data.len() * 2 - nibbles.offset
 }
 
-pub fn split_at_first_nibble(nibbles: &Nibbles) -> Result<(Nibbles, Nibbles)> {
+pub(crate) fn split_at_first_nibble(nibbles: &Nibbles) -> Result<(Nibbles, Nibbles)> {
     match get_length_in_nibbles(nibbles) > 0 {
         false => Ok((EMPTY_NIBBLES, EMPTY_NIBBLES)),
-        true => get_nibble_at_index(nibbles, 0).and_then(|first_nibble| {
-            Ok((
-                get_nibbles_from_offset_bytes(vec![first_nibble]),
-                slice_nibbles_at_nibble_index(nibbles.clone(), 1)?,
-            ))
-        }),
+        true => Ok((
+            nibbles.take(1)?,
+            slice_nibbles_at_nibble_index(nibbles.clone(), 1)?,
+        )),
     }
 }
 
-pub fn get_nibble_at_index(nibbles: &Nibbles, nibble_index: usize) -> Result<Byte> {
-    match nibble_index > get_length_in_nibbles(nibbles) {
+pub(crate) fn get_nibble_at_index(nibbles: &Nibbles, nibble_index: usize) -> Result<Byte> {
+    match nibble_index >= get_length_in_nibbles(nibbles) {
         true => Err(AppError::Custom(format!(
             "✘ Index {} is out-of-bounds in nibble vector!",
             nibble_index
@@ -345,25 +524,14 @@ fn get_high_nibble_from_byte(nibbles: &Nibbles, nibble_index: &usize) -> Result<
     get_byte_containing_nibble_at_nibble_index(nibbles, nibble_index).map(shift_nibble_right)
 }
 
-pub fn prefix_nibbles_with_byte(
-    nibbles: Nibbles,
-    mut vec_including_prefix_byte: Vec<u8>,
-) -> Result<Bytes> {
-    convert_nibble_to_bytes(nibbles).map(|mut bytes| {
-        vec_including_prefix_byte.append(&mut bytes);
-        vec_including_prefix_byte
-    })
-}
-
-pub fn convert_nibble_to_bytes(nibbles: Nibbles) -> Result<Bytes> {
-    Ok(nibbles.data)
-}
-
 fn slice_nibbles_at_byte_index(nibbles: Nibbles, byte_index: usize) -> Result<Nibbles> {
     Ok(get_nibbles_from_bytes(nibbles.data[byte_index..].to_vec()))
 }
 
-pub fn slice_nibbles_at_nibble_index(nibbles: Nibbles, nibble_index: usize) -> Result<Nibbles> {
+pub(crate) fn slice_nibbles_at_nibble_index(
+    nibbles: Nibbles,
+    nibble_index: usize,
+) -> Result<Nibbles> {
     match nibble_index {
         // NOTE: The following pattern guard is ∵ we compare to a runtime var!
         x if (x >= get_length_in_nibbles(&nibbles)) => Ok(EMPTY_NIBBLES),
@@ -373,7 +541,7 @@ pub fn slice_nibbles_at_nibble_index(nibbles: Nibbles, nibble_index: usize) -> R
             let offset = nibbles.offset;
             let byte_index = convert_nibble_index_to_byte_index(&nibbles, &nibble_index);
             let sliced_nibbles = slice_nibbles_at_byte_index(nibbles, byte_index)?;
-            match (nibble_index + offset) % 2 == 0 {
+            match (nibble_index + offset).is_multiple_of(2) {
                 true => Ok(sliced_nibbles),
                 false => {
                     replace_nibble_in_nibbles_at_nibble_index(sliced_nibbles, get_zero_nibble(), 0)
@@ -384,8 +552,8 @@ pub fn slice_nibbles_at_nibble_index(nibbles: Nibbles, nibble_index: usize) -> R
     }
 }
 
-pub fn convert_nibble_to_usize(nibbles: Nibbles) -> usize {
-    match nibbles.len() == 0 {
+pub(crate) fn convert_nibble_to_usize(nibbles: Nibbles) -> usize {
+    match nibbles.is_empty() {
         true => 0,
         false => nibbles.data[0] as usize,
     }
@@ -808,17 +976,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn should_set_first_nibble_flag_in_nibbles_to_zero_correctly() {
-        let expected_result = 0;
-        let nibbles = get_sample_offset_nibbles();
-        let nibble_flag_before = nibbles.offset;
-        assert!(nibble_flag_before != expected_result);
-        let updated_nibbles = set_nibble_offset_to_zero(nibbles);
-        let result = updated_nibbles.offset;
-        assert!(result == expected_result);
-    }
-
     #[test]
     fn should_set_first_nibble_flag_in_nibbles_to_one_correctly() {
         let expected_result = 1;
@@ -918,44 +1075,6 @@ mod tests {
         assert!(result == EMPTY_NIBBLES);
     }
 
-    #[test]
-    fn should_prefix_nibble_with_byte_correctly() {
-        let nibbles = get_sample_nibbles();
-        let prefix = vec![0xff];
-        let mut expected_result = prefix.clone();
-        let result = prefix_nibbles_with_byte(nibbles, prefix).unwrap();
-        let bytes = get_bytes_with_nibbles_from_index_zero();
-        expected_result.append(&mut bytes.clone());
-        assert!(result == expected_result);
-    }
-
-    #[test]
-    fn should_prefix_offset_nibble_with_byte_correctly() {
-        let nibbles = get_sample_offset_nibbles();
-        let prefix = vec![0xff];
-        let mut expected_result = prefix.clone();
-        let result = prefix_nibbles_with_byte(nibbles, prefix).unwrap();
-        let bytes = get_bytes_with_nibbles_from_index_one();
-        expected_result.append(&mut bytes.clone());
-        assert!(result == expected_result);
-    }
-
-    #[test]
-    fn should_convert_nibbles_to_bytes_correctly() {
-        let nibbles = get_sample_nibbles();
-        let expected_result = get_bytes_with_nibbles_from_index_zero();
-        let result = convert_nibble_to_bytes(nibbles).unwrap();
-        assert!(result == expected_result);
-    }
-
-    #[test]
-    fn should_convert_offset_nibbles_to_bytes_correctly() {
-        let nibbles = get_sample_offset_nibbles();
-        let expected_result = get_bytes_with_nibbles_from_index_one();
-        let result = convert_nibble_to_bytes(nibbles).unwrap();
-        assert!(result == expected_result);
-    }
-
     #[test]
     fn should_slice_nibbles_at_byte_index_correctly() {
         let byte_index = 2;
@@ -1476,4 +1595,61 @@ mod tests {
         assert!(result_nibble == EMPTY_NIBBLES);
         assert!(result_nibbles == EMPTY_NIBBLES);
     }
+
+    #[test]
+    fn should_make_nibbles_from_nibble_values() {
+        let result = Nibbles::from_nibble_values(&EXPECTED_NIBBLES[1..]).unwrap();
+        assert!(result == get_sample_nibbles().skip(1).unwrap());
+        assert!(result.is_odd_length());
+        assert!(result.to_nibble_values().unwrap() == EXPECTED_NIBBLES[1..].to_vec());
+        assert!(Nibbles::from_nibble_values(&[]).unwrap() == EMPTY_NIBBLES);
+    }
+
+    #[test]
+    fn should_err_when_making_nibbles_from_too_large_nibble_values() {
+        match Nibbles::from_nibble_values(&[0x01, 0x10]) {
+            Err(AppError::Custom(e)) => assert!(e.contains("too large to be a nibble")),
+            _ => panic!("Should not make nibbles from too large values!"),
+        }
+    }
+
+    #[test]
+    fn should_err_when_making_offset_nibbles_with_non_zero_high_bits() {
+        assert!(Nibbles::from_offset_bytes(&get_bytes_with_nibbles_from_index_one()).is_ok());
+        assert!(Nibbles::from_offset_bytes(&[]).is_err());
+        match Nibbles::from_offset_bytes(&get_bytes_with_nibbles_from_index_zero()) {
+            Err(AppError::Custom(e)) => assert!(e.contains("zero high nibble")),
+            _ => panic!("Should not make offset nibbles w/ non-zero high bits!"),
+        }
+    }
+
+    #[test]
+    fn should_take_skip_and_slice_nibbles() {
+        let nibbles = get_sample_nibbles();
+        assert!(nibbles.take(3).unwrap().to_nibble_values().unwrap() == EXPECTED_NIBBLES[..3]);
+        assert!(nibbles.skip(3).unwrap().to_nibble_values().unwrap() == EXPECTED_NIBBLES[3..]);
+        assert!(nibbles.slice(3..8).unwrap().to_nibble_values().unwrap() == EXPECTED_NIBBLES[3..8]);
+        assert!(nibbles.skip(nibbles.len()).unwrap().is_empty());
+        assert!(nibbles.slice(3..nibbles.len() + 1).is_err());
+        let offset_nibbles = get_sample_offset_nibbles();
+        assert!(offset_nibbles.skip(0).unwrap() == offset_nibbles);
+        assert!(offset_nibbles.skip(1).unwrap() == nibbles.slice(1..13).unwrap());
+    }
+
+    #[test]
+    fn should_concat_nibbles() {
+        let nibbles = get_sample_nibbles();
+        let head = nibbles.take(5).unwrap();
+        let tail = nibbles.skip(5).unwrap();
+        assert!(head.is_odd_length() && tail.is_odd_length());
+        assert!(head.concat(&tail).unwrap() == nibbles);
+        assert!(EMPTY_NIBBLES.concat(&tail).unwrap() == tail);
+    }
+
+    #[test]
+    fn should_err_if_attempting_to_get_nibble_at_length_index() {
+        let nibbles = get_sample_nibbles();
+        assert!(nibbles.get(nibbles.len() - 1).is_ok());
+        assert!(nibbles.get(nibbles.len()).is_err());
+    }
 }
@@ -0,0 +1,111 @@
+use crate::trie_nodes::Node;
+use crate::types::{NoneError, Result};
+use std::ops::Deref;
+
+/**
+ * Node Stacks:
+ *
+ * Finding a key in the trie leaves a stack of the nodes along its path, the
+ * one nearest the root at the bottom, & inserting into the trie builds stacks
+ * of new & stale nodes from it. A stack being empty where a node is expected
+ * is a bug in the trie, so rather than indexing into, or unwrapping pops of,
+ * a bare `Vec`, the checked accessors here return an error instead. Reading
+ * a stack, e.g. to RLP encode a branch, derefs to a slice of its nodes.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeStack(Vec<Node>);
+
+impl NodeStack {
+    pub fn new() -> Self {
+        NodeStack(Vec::new())
+    }
+
+    pub fn push(&mut self, node: Node) {
+        self.0.push(node)
+    }
+
+    // NOTE: Pushes beneath the whole stack, i.e. nearer the root than every other node in it.
+    pub fn push_bottom(&mut self, node: Node) {
+        self.0.insert(0, node)
+    }
+
+    pub fn pop(&mut self) -> Option<Node> {
+        self.0.pop()
+    }
+
+    pub fn pop_checked(&mut self) -> Result<Node> {
+        self.0
+            .pop()
+            .ok_or_else(|| NoneError("Could not pop node: Stack is empty!".into()))
+    }
+
+    pub fn peek(&self) -> Result<&Node> {
+        self.0
+            .last()
+            .ok_or_else(|| NoneError("Could not peek node: Stack is empty!".into()))
+    }
+
+    pub fn peek_bottom(&self) -> Result<&Node> {
+        self.0
+            .first()
+            .ok_or_else(|| NoneError("Could not peek node: Stack is empty!".into()))
+    }
+
+    pub fn into_vec(self) -> Vec<Node> {
+        self.0
+    }
+}
+
+impl Deref for NodeStack {
+    type Target = [Node];
+
+    fn deref(&self) -> &[Node] {
+        &self.0
+    }
+}
+
+impl From<Vec<Node>> for NodeStack {
+    fn from(nodes: Vec<Node>) -> Self {
+        NodeStack(nodes)
+    }
+}
+
+impl IntoIterator for NodeStack {
+    type Item = Node;
+    type IntoIter = std::vec::IntoIter<Node>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        get_sample_branch_node, get_sample_extension_node, get_sample_leaf_node,
+    };
+
+    #[test]
+    fn should_err_when_popping_or_peeking_empty_stack() {
+        let mut stack = NodeStack::new();
+        assert!(stack.pop().is_none());
+        assert!(stack.pop_checked().is_err());
+        assert!(stack.peek().is_err());
+        assert!(stack.peek_bottom().is_err());
+    }
+
+    #[test]
+    fn should_push_pop_and_peek_at_both_ends_of_stack() {
+        let mut stack = NodeStack::new();
+        stack.push(get_sample_extension_node());
+        stack.push(get_sample_leaf_node());
+        stack.push_bottom(get_sample_branch_node());
+        assert!(stack.len() == 3);
+        assert!(*stack.peek().unwrap() == get_sample_leaf_node());
+        assert!(*stack.peek_bottom().unwrap() == get_sample_branch_node());
+        assert!(stack.pop_checked().unwrap() == get_sample_leaf_node());
+        assert!(stack.pop_checked().unwrap() == get_sample_extension_node());
+        assert!(stack.into_vec() == vec![get_sample_branch_node()]);
+    }
+}
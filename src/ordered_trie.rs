@@ -0,0 +1,101 @@
+use crate::trie::{put_in_trie_recursively, Trie};
+use crate::trie_keys::ReceiptKey;
+use crate::types::{Bytes, Result};
+use ethereum_types::H256;
+
+/**
+ * Ordered Tries:
+ *
+ * Receipts & transactions tries are both ordered tries, i.e. tries keyed by
+ * the RLP encoded index of each value in a list, exactly as `triehash`'s
+ * `ordered_trie_root` keys them. So given the values in order, this computes
+ * the root in one call, w/o the caller building keys or nibbles themselves,
+ * e.g. to check a block's raw receipts against its header's `receiptsRoot`.
+ * An empty list's root is the hash of the null node, as for an empty trie.
+ */
+pub fn get_ordered_trie(values: &[Bytes]) -> Result<Trie> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| Ok((ReceiptKey::from_index(index)?, value.clone())))
+        .collect::<Result<Vec<(ReceiptKey, Bytes)>>>()
+        .and_then(|key_value_tuples| {
+            put_in_trie_recursively(Trie::get_new_trie()?, key_value_tuples, 0)
+        })
+}
+
+pub fn compute_ordered_root(values: &[Bytes]) -> Result<H256> {
+    get_ordered_trie(values).map(|trie| trie.root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::HASHED_NULL_NODE;
+    use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
+    use crate::test_utils::{
+        get_sample_receipts, get_sample_tx_hashes_1, RECEIPTS_ROOT_1, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::convert_h256_to_prefixed_hex;
+    use proptest::prelude::*;
+
+    struct ReferenceKeccakHasher;
+
+    impl hash_db::Hasher for ReferenceKeccakHasher {
+        type Out = [u8; 32];
+        type StdHasher = hash256_std_hasher::Hash256StdHasher;
+        const LENGTH: usize = 32;
+
+        fn hash(bytes: &[u8]) -> Self::Out {
+            tiny_keccak::keccak256(bytes)
+        }
+    }
+
+    fn get_reference_root(values: &[Bytes]) -> H256 {
+        H256::from(triehash::ordered_trie_root::<ReferenceKeccakHasher, _>(
+            values,
+        ))
+    }
+
+    #[test]
+    fn should_compute_ordered_root_of_no_values_as_hashed_null_node() {
+        let result = compute_ordered_root(&[]).unwrap();
+        assert!(result == HASHED_NULL_NODE);
+        assert!(result == get_reference_root(&[]));
+    }
+
+    #[test]
+    fn should_compute_ordered_root_of_single_value() {
+        let values = vec![vec![0xc0; 48]];
+        assert!(compute_ordered_root(&values).unwrap() == get_reference_root(&values));
+    }
+
+    #[test]
+    fn should_compute_ordered_root_matching_receipts_root() {
+        let mut receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        let values = get_rlp_encoded_receipts_and_key_tuples(&receipts)
+            .unwrap()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<Bytes>>();
+        let result = compute_ordered_root(&values).unwrap();
+        assert!(convert_h256_to_prefixed_hex(result).unwrap() == RECEIPTS_ROOT_1);
+        assert!(result == get_reference_root(&values));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        // NOTE: Values are at least 32 bytes long, so no node is small enough to be inlined.
+        #[test]
+        fn should_match_reference_ordered_root(
+            values in prop::collection::vec(prop::collection::vec(any::<u8>(), 32..96), 0..300)
+        ) {
+            prop_assert_eq!(compute_ordered_root(&values).unwrap(), get_reference_root(&values));
+        }
+    }
+}
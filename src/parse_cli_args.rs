@@ -1,36 +1,601 @@
-use docopt::Docopt;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use log::LevelFilter;
-use simplelog::*;
 
-use crate::{errors::AppError, types::Result, usage_info::USAGE_INFO, utils::convert_hex_to_h256};
+use std::sync::Arc;
 
-#[allow(non_snake_case)]
-#[derive(Debug, Deserialize)]
+use crate::{
+    batch_tuner::BatchTuner, binary_envelope::ProofCodec, chain_profile::ChainProfile,
+    config_file::maybe_apply_config_file_to_cli_args, hardfork::Hardfork,
+    logger::initialize_logger, proof_signing::SignatureScheme,
+    proof_size_report::set_proof_size_report_enabled, telemetry::Telemetry, types::Result,
+    usage_info::ABOUT_INFO,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    Hex,
+    Json,
+    Binary,
+    Eip1186,
+    Ssz,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Generate a merkle proof of the receipt pertaining to the given transaction hash.
+    ProveReceipt {
+        /// A 32-byte long hex transaction hash, w/ or w/o its `0x` prefix.
+        tx_hash: String,
+    },
+    /// Generate a full proof of a log: the block header, the receipt & its merkle proof, & the
+    /// log's index in the receipt, verifiable against the block hash alone.
+    ProveLog {
+        /// A 32-byte long hex transaction hash, w/ or w/o its `0x` prefix.
+        tx_hash: String,
+        /// Index of the log in the transaction's receipt.
+        log_index: usize,
+    },
+    /// Generate a merkle proof of the receipt at the given index in the given block, w/o needing
+    /// its transaction hash.
+    ProveIndex {
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        #[arg(long)]
+        block: String,
+        /// Index of the transaction in its block.
+        #[arg(long)]
+        index: usize,
+    },
+    /// Generate merkle proofs of every receipt in the given block.
+    ProveBlock {
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        block: String,
+        /// Only prove receipts w/ a log whose first topic is this 32-byte prefixed hex hash.
+        #[arg(long, value_name = "HASH")]
+        topic0: Option<String>,
+        /// Only prove receipts w/ a log emitted by this prefixed hex address.
+        #[arg(long, value_name = "ADDRESS")]
+        address: Option<String>,
+    },
+    /// Generate merkle proofs of the receipts in a range of blocks, streamed to a directory or an
+    /// NDJSON file as each block is processed, resuming from a checkpoint if interrupted.
+    ProveRange {
+        /// The first block number in the range.
+        from: u64,
+        /// The last block number in the range, inclusive.
+        to: u64,
+        /// Only prove receipts w/ a log whose first topic is this 32-byte prefixed hex hash.
+        #[arg(long, value_name = "HASH")]
+        topic0: Option<String>,
+        /// Only prove receipts w/ a log emitted by this prefixed hex address.
+        #[arg(long, value_name = "ADDRESS")]
+        address: Option<String>,
+        /// Directory to write each block's proofs to, as a JSON array named by its number.
+        #[arg(
+            long,
+            value_name = "DIR",
+            required_unless_present = "ndjson",
+            conflicts_with = "ndjson"
+        )]
+        out_dir: Option<String>,
+        /// NDJSON file to append the proofs to, one per line.
+        #[arg(long, value_name = "PATH")]
+        ndjson: Option<String>,
+        /// Number of blocks to fetch the receipts of, build the tries of & extract the proofs
+        /// from at once, capped at `--max-tries-in-memory`.
+        #[arg(long, value_name = "NUM", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
+        /// Number of blocks' receipts tries to keep cached in memory, spilling older ones to the
+        /// `--cache-dir`, or a temporary dir. Up to twice `--jobs` more may be in flight at once.
+        #[arg(long, value_name = "NUM", default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+        max_tries_in_memory: u32,
+        /// Resume an interrupted run w/ the same range & filters from its checkpoint, rather than
+        /// starting over.
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Verify the proof(s) in a JSON or binary proof file against their receipts root, or a trusted one.
+    Verify {
+        /// Path to a JSON or binary proof file, as output by `--format json` or `--format binary`.
+        #[arg(value_name = "PROOF.JSON")]
+        proof_path: String,
+        /// A trusted receipts root the proofs must prove against, printing their receipts.
+        #[arg(long, value_name = "ROOT", conflicts_with = "block")]
+        root: Option<String>,
+        /// A trusted block hash, whose header's receipts root the proofs must prove against.
+        #[arg(long, value_name = "BLOCK_HASH")]
+        block: Option<String>,
+        /// A trusted signer's hex public key, which every proof must be signed by.
+        #[arg(long, value_name = "PUBLIC_KEY")]
+        signer: Option<String>,
+    },
+    /// Check the proof(s) in a JSON proof file are still canonical & unexpired, via RPC.
+    CheckStale {
+        /// Path to a JSON proof file, as output by `--format json`.
+        #[arg(value_name = "PROOF.JSON")]
+        proof_path: String,
+    },
+    /// Recompute the receipts root of the given block & compare it to the header's.
+    Root {
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        block: String,
+    },
+    /// Triage why the given block's computed receipts root differs from its header's.
+    ExplainRootMismatch {
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        block: String,
+    },
+    /// Build the receipts trie of the given block & export it as a binary snapshot file.
+    ExportTrie {
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        block: String,
+        /// Path to write the trie snapshot to.
+        #[arg(value_name = "SNAPSHOT")]
+        snapshot_path: String,
+    },
+    /// Generate a hex proof of the receipt at the given index from a trie snapshot, offline.
+    ProveFromTrie {
+        /// Path to a trie snapshot, as written by `export-trie`.
+        #[arg(value_name = "SNAPSHOT")]
+        snapshot_path: String,
+        /// Index of the transaction in its block.
+        tx_index: usize,
+    },
+    /// Generate a proof of the receipt at the given index from a Firehose block protobuf, w/o RPC.
+    #[cfg(feature = "firehose")]
+    ProveFromFirehose {
+        /// Path to a binary `sf.ethereum.type.v2.Block` protobuf, as streamed by Firehose.
+        #[arg(value_name = "BLOCK.PB")]
+        block_path: String,
+        /// Index of the transaction in its block.
+        tx_index: usize,
+    },
+    /// Generate & verify account & storage proofs of the given storage slot via `eth_getProof`.
+    ProveStorage {
+        /// A 20-byte long hex account address, w/ or w/o its `0x` prefix.
+        address: String,
+        /// A prefixed hex storage slot, of up to 32 bytes.
+        slot: String,
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        block: String,
+    },
+    /// Build a trie of the hex key/value pairs in a JSON or CSV file, printing its root & proofs of
+    /// the values under the given keys, offline.
+    ProveMapping {
+        /// Path to a JSON object of keys to values, JSON array of `{"key": ..., "value": ...}`, or
+        /// `.csv` file of `key,value` lines.
+        #[arg(value_name = "MAPPING")]
+        mapping_path: String,
+        /// A hex key to prove the value under. Repeatable.
+        #[arg(long = "key", value_name = "KEY")]
+        keys: Vec<String>,
+        /// Key the trie by the keccak hash of each key, as state & storage tries are.
+        #[arg(long)]
+        secure: bool,
+    },
+    /// Diagnose the environment: the endpoint, its chain id, method support & archive depth,
+    /// clock skew & output paths, suggesting a fix for each problem found.
+    Doctor,
+    /// Serve proofs over HTTP, caching the receipts tries of recently proven blocks.
+    Serve {
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on.
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Read tx hashes or NDJSON proof requests from stdin, writing one JSON proof per line to stdout.
+    Pipe,
+    /// Interactively explore a block's receipts trie: its receipts, nodes & proofs.
+    Repl,
+    /// Write a curated set of proofs covering edge shapes, for testing verifiers against, offline.
+    GenVectors {
+        /// Directory to write the vectors to.
+        #[arg(long, value_name = "DIR")]
+        out: String,
+    },
+    /// Record a block's header & receipts as test fixtures, in the layout the tests read.
+    RecordFixtures {
+        /// Block number, block hash or `latest`.
+        block: String,
+        /// Directory to write the fixtures to.
+        #[arg(long, value_name = "DIR")]
+        out: String,
+    },
+    /// List the proof format versions this binary reads & writes, w/ their JSON schemas.
+    Formats,
+    /// Debugging aids for the tool's internals, e.g. the shape of a block's receipts trie.
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommand,
+    },
+    /// Decrypt output previously encrypted via `--encrypt-to` or `--encryption-key-file`.
+    Decrypt {
+        /// Path to the encrypted output.
+        #[arg(value_name = "ENCRYPTED")]
+        encrypted_path: String,
+        /// Path to an age identity file, as written by `age-keygen`.
+        #[arg(long, value_name = "PATH")]
+        identity_file: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DebugCommand {
+    /// Build the receipts trie of the given block & write its node graph as a Graphviz DOT file.
+    DumpTrie {
+        /// A block number, a 32-byte long hex block hash, or `latest`, `finalized` or `safe`.
+        #[arg(long)]
+        block: String,
+        /// Path to write the DOT file to.
+        #[arg(long, value_name = "PATH")]
+        out: String,
+    },
+    /// Put, get & prove arbitrary hex keys & values in an in-memory trie, interactively, for
+    /// reproducing insertion edge cases.
+    Repl,
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "rusty-receipt-proof-maker", version, about = ABOUT_INFO)]
 pub struct CliArgs {
-    pub flag_verbose: bool,
-    pub arg_txhash: String,
+    #[command(subcommand)]
+    pub command: CliCommand,
+
+    /// RPC endpoint of the node to query. Defaults to the `ENDPOINT` env var or `.env` file.
+    #[arg(long, global = true)]
+    pub endpoint: Option<String>,
+
+    /// Log level: `off`, `error`, `warn`, `info`, `debug` or `trace`. Logs go to stderr.
+    #[arg(long, global = true, default_value = "info")]
+    pub log_level: LevelFilter,
+
+    /// Log format. JSON logs carry each event's enclosing spans, e.g. the RPC call or trie build
+    /// it happened in, for correlating failures in batch runs.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Output format.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Hex)]
+    pub format: OutputFormat,
+
+    /// Output light JSON proof bundles, holding only the block hash, receipts root, tx index &
+    /// proof nodes, for consumers already tracking headers.
+    #[arg(long, global = true)]
+    pub light: bool,
+
+    /// Carry the keccak hash of the receipt in full JSON proof bundles in place of the receipt
+    /// itself, for verifiers only binding to the receipt hash.
+    #[arg(long, global = true, conflicts_with = "light")]
+    pub value_hash: bool,
+
+    /// Omit from JSON proof bundles' proofs the child hashes verifiers can recompute from the
+    /// next node, shrinking them. The `verify` subcommand decompresses them.
+    #[arg(long, global = true)]
+    pub compress_proofs: bool,
+
+    /// Compress `--format binary` proofs w/ this codec, wrapped in an envelope naming it. The
+    /// `verify` subcommand decompresses them.
+    #[arg(long, global = true, value_enum, value_name = "CODEC")]
+    pub compress: Option<ProofCodec>,
+
+    /// Evaluate the conditions in this TOML file against the proven receipt before emitting its
+    /// proof, e.g. `status == 1`, recording their results in full JSON proof bundles.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub assertions: Option<String>,
+
+    /// Write output to this file instead of stdout.
+    #[arg(short, long, global = true, value_name = "PATH")]
+    pub output: Option<String>,
+
+    /// Append a JSON-lines record of each generated proof to this file.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub audit_log: Option<String>,
+
+    /// Append a JSON-lines record of coarse usage events (proofs generated, verification
+    /// failures, RPC fallbacks) to this file. Nothing is ever sent over the network.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub telemetry_log: Option<String>,
+
+    /// Write the run's metrics (RPC latencies & errors, trie build times, proofs generated) to
+    /// this file in the Prometheus text format once the command finishes.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub metrics_file: Option<String>,
+
+    /// Print a breakdown of RPC call & trie build times, & the batch sizes & concurrency chosen
+    /// per endpoint, to stderr once the command finishes.
+    #[arg(long, global = true)]
+    pub timings: bool,
+
+    /// Print the sizes of the receipt proofs generated to stderr once the command finishes: nodes
+    /// per proof, total bytes, the deepest path, & the saving of a multiproof of them all.
+    #[arg(long, global = true)]
+    pub size_report: bool,
+
+    /// TOML file of the gas prices to estimate full JSON proof bundles' on-chain verification
+    /// costs w/, recorded in their metadata. Prices left out keep their defaults.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub gas_model: Option<String>,
+
+    /// Mark full JSON proof bundles valid until this many blocks past the current chain head.
+    #[arg(long, global = true, value_name = "BLOCKS")]
+    pub valid_for: Option<u64>,
+
+    /// Refuse to prove receipts of blocks w/ fewer than this many blocks on top of them.
+    #[arg(long, global = true, value_name = "BLOCKS")]
+    pub min_confirmations: Option<u64>,
+
+    /// Refuse to prove receipts of blocks not yet at or below the node's `finalized` block.
+    #[arg(long, global = true)]
+    pub require_finalized: bool,
+
+    /// Chain whose receipt types & encoding to expect.
+    #[arg(long, global = true, value_enum, default_value_t = ChainProfile::Mainnet)]
+    pub chain: ChainProfile,
+
+    /// Receipt encoding rules to use. Detected from the chain id & block number if not given.
+    #[arg(long, global = true, value_enum)]
+    pub hardfork: Option<Hardfork>,
+
+    /// Don't ask the node to compress RPC responses, for providers with broken compression.
+    #[arg(long, global = true)]
+    pub no_compression: bool,
+
+    /// Seconds to wait for an RPC response before giving up. Defaults to the `RPC_TIMEOUT` env
+    /// var, else 5. Archive-node queries of old blocks may need longer.
+    #[arg(long, global = true, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Seconds to wait to connect to the node. Defaults to the `RPC_CONNECT_TIMEOUT` env var,
+    /// else 5.
+    #[arg(long, global = true, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub connect_timeout: Option<u64>,
+
+    /// Maximum idle connections to the node kept in the pool for reuse. Defaults to the
+    /// `RPC_POOL_MAX_IDLE_PER_HOST` env var, else unlimited.
+    #[arg(long, global = true, value_name = "NUM")]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Don't keep connections to the node alive for reuse, e.g. for load balancers that drop
+    /// idle connections. Also set via the `RPC_NO_KEEP_ALIVE` env var.
+    #[arg(long, global = true)]
+    pub no_keep_alive: bool,
+
+    /// Rate limit RPC calls to this many per second, to stay under a provider's quota. Defaults
+    /// to the `RPC_MAX_REQUESTS_PER_SECOND` env var, else unlimited.
+    #[arg(long, global = true, value_name = "NUM", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_requests_per_second: Option<u32>,
+
+    /// Pin an endpoint's host to an IP, bypassing DNS, as `<host>=<ip>`. Repeatable. Defaults to
+    /// the comma separated `RPC_RESOLVE` env var.
+    #[arg(long, global = true, value_name = "HOST=IP")]
+    pub resolve: Vec<String>,
+
+    /// Resolve endpoint hosts via this DNS-over-HTTPS JSON API, e.g.
+    /// `https://1.1.1.1/dns-query`. Defaults to the `RPC_DOH_URL` env var.
+    #[arg(long, global = true, value_name = "URL")]
+    pub doh_url: Option<String>,
+
+    /// A second, independent endpoint that must agree on the hash & receipts root of every block
+    /// proven, guarding against one endpoint feeding in wrong receipts.
+    #[arg(long, global = true, value_name = "ENDPOINT")]
+    pub cross_check: Option<String>,
+
+    /// JSON file of trusted checkpoints, i.e. block numbers & hashes, e.g. from a light client.
+    /// Every block proven must be an ancestor of one, checked by walking back its parent hashes.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub trusted_headers: Option<String>,
+
+    /// Cache the receipts tries of blocks proven in this directory, reusing them across runs.
+    #[arg(long, global = true, value_name = "DIR")]
+    pub cache_dir: Option<String>,
+
+    /// TOML config file of defaults for the endpoint(s), chain, cache dir, RPC & output settings.
+    /// Defaults to `./config.toml` if present. Flags & env vars take precedence over it.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<String>,
+
+    // NOTE: Not a flag, but the config file's endpoints, tried in order when no other is given.
+    #[arg(skip)]
+    pub config_endpoints: Vec<String>,
+
+    // NOTE: Not a flag, but the run's receipt batch tunings, shared by everything it fetches.
+    #[arg(skip)]
+    pub batch_tuner: Arc<BatchTuner>,
+
+    // NOTE: Not a flag, but the sink telemetry events go to, a file one if `--telemetry-log` is given.
+    #[arg(skip)]
+    pub telemetry: Telemetry,
+
+    /// Warn about trie nodes found only under byte-reversed hashes & treat them as missing,
+    /// rather than erroring. For debugging interop w/ libraries that mangle hash byte order.
+    #[arg(long, global = true)]
+    pub lenient_lookups: bool,
+
+    /// Refuse receipts w/ fields we don't model, naming them, rather than keeping them verbatim
+    /// alongside the receipt's other non-consensus fields.
+    #[arg(long, global = true)]
+    pub strict_receipts: bool,
+
+    /// Record the receipts trie's root after each receipt inserted into it, & dump them w/ the
+    /// receipts' encodings to this JSON file. On a `root` mismatch, bisects them against the
+    /// node's raw receipts to find the first wrongly encoded receipt.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub audit_trie_roots: Option<String>,
+
+    /// Read the endpoint API key from this file, in place of the `API_KEY` env var. The key is
+    /// substituted for `{API_KEY}` in the endpoint & redacted from all logs & errors.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub api_key_file: Option<String>,
+
+    /// Encrypt output to this age recipient (`age1...`), for transport via untrusted storage.
+    #[arg(
+        long,
+        global = true,
+        value_name = "RECIPIENT",
+        conflicts_with = "encryption_key_file"
+    )]
+    pub encrypt_to: Option<String>,
+
+    /// Encrypt output with AES-256-GCM using the 32-byte hex key in this file. Also the key
+    /// `decrypt` uses for such output.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub encryption_key_file: Option<String>,
+
+    /// Sign full JSON proof bundles w/ the 32-byte hex private key in this file, for consumers to
+    /// check they came from us via `verify --signer`.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub sign_key: Option<String>,
+
+    /// Signature scheme of the `--sign-key`.
+    #[arg(long, global = true, value_enum, default_value_t = SignatureScheme::Secp256k1)]
+    pub sign_scheme: SignatureScheme,
 }
 
 pub fn parse_cli_args() -> Result<CliArgs> {
-    match Docopt::new(USAGE_INFO).and_then(|d| d.deserialize::<CliArgs>()) {
-        Ok(cli_args) => {
-            TermLogger::init(
-                if cli_args.flag_verbose {
-                    LevelFilter::Trace
-                } else {
-                    LevelFilter::Info
-                },
-                Config::default(),
-                TerminalMode::Mixed,
-            )?;
-            info!("✔ CLI Args parsed successfully!");
-            info!("✔ Verbose mode: {}", cli_args.flag_verbose);
-            info!(
-                "✔ Transaction hash: {}",
-                convert_hex_to_h256(cli_args.arg_txhash.clone())?
-            );
-            Ok(cli_args)
+    let matches = CliArgs::command().get_matches();
+    let cli_args = CliArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    initialize_logger(cli_args.log_level, cli_args.log_format)?;
+    let cli_args = maybe_apply_config_file_to_cli_args(cli_args, &matches)?;
+    let cli_args = CliArgs {
+        telemetry: Telemetry::from_telemetry_log(&cli_args.telemetry_log),
+        ..cli_args
+    };
+    set_proof_size_report_enabled(cli_args.size_report);
+    info!("✔ CLI Args parsed successfully!");
+    info!("✔ Log level: {}", cli_args.log_level);
+    info!("✔ Command: {:?}", cli_args.command);
+    Ok(cli_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_valid_tx_hash_hex;
+
+    #[test]
+    fn should_parse_prove_receipt_subcommand_with_defaults() {
+        let tx_hash = get_valid_tx_hash_hex();
+        let result =
+            CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker", "prove-receipt", &tx_hash])
+                .unwrap();
+        assert!(result.endpoint.is_none());
+        assert!(result.output.is_none());
+        assert!(result.audit_log.is_none());
+        assert!(!result.light);
+        assert!(result.chain == ChainProfile::Mainnet);
+        assert!(!result.no_compression);
+        assert!(result.format == OutputFormat::Hex);
+        assert!(result.log_level == LevelFilter::Info);
+        assert!(result.log_format == LogFormat::Text);
+        match result.command {
+            CliCommand::ProveReceipt { tx_hash: parsed } => assert!(parsed == tx_hash),
+            _ => panic!("Should have parsed `prove-receipt` subcommand!"),
+        }
+    }
+
+    #[test]
+    fn should_parse_prove_index_subcommand() {
+        let result = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-index",
+            "--block",
+            "8233333",
+            "--index",
+            "14",
+        ])
+        .unwrap();
+        match result.command {
+            CliCommand::ProveIndex { block, index } => assert!(block == "8233333" && index == 14),
+            _ => panic!("Should have parsed `prove-index` subcommand!"),
         }
-        Err(e) => Err(AppError::Custom(e.to_string())),
+    }
+
+    #[test]
+    fn should_parse_debug_dump_trie_subcommand() {
+        let result = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "debug",
+            "dump-trie",
+            "--block",
+            "8233333",
+            "--out",
+            "./trie.dot",
+        ])
+        .unwrap();
+        match result.command {
+            CliCommand::Debug {
+                command: DebugCommand::DumpTrie { block, out },
+            } => assert!(block == "8233333" && out == "./trie.dot"),
+            _ => panic!("Should have parsed `debug dump-trie` subcommand!"),
+        }
+    }
+
+    #[test]
+    fn should_parse_global_flags_after_subcommand() {
+        let result = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "root",
+            "8233333",
+            "--endpoint",
+            "http://localhost:8546",
+            "--log-level",
+            "trace",
+            "--log-format",
+            "json",
+            "--format",
+            "json",
+            "--output",
+            "./root.json",
+        ])
+        .unwrap();
+        assert!(result.format == OutputFormat::Json);
+        assert!(result.log_level == LevelFilter::Trace);
+        assert!(result.log_format == LogFormat::Json);
+        assert!(result.output == Some("./root.json".to_string()));
+        assert!(result.endpoint == Some("http://localhost:8546".to_string()));
+        match result.command {
+            CliCommand::Root { block } => assert!(block == "8233333"),
+            _ => panic!("Should have parsed `root` subcommand!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_parse_unknown_output_format() {
+        let result = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "verify",
+            "./proof.json",
+            "--format",
+            "xml",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_fail_to_parse_both_encryption_flags() {
+        let result = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "root",
+            "latest",
+            "--encrypt-to",
+            "age1c0ffee",
+            "--encryption-key-file",
+            "./key",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_fail_to_parse_without_subcommand() {
+        let result = CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker"]);
+        assert!(result.is_err());
     }
 }
@@ -1,95 +1,36 @@
-use crate::constants::{EXTENSION_NODE_STRING, LEAF_NODE_STRING};
-use crate::errors::AppError;
-use crate::nibble_utils::{
-    convert_nibble_to_bytes, get_length_in_nibbles, get_nibble_at_index, get_nibbles_from_bytes,
-    get_nibbles_from_offset_bytes, get_zero_nibble, prefix_nibbles_with_byte,
-    replace_nibble_in_nibbles_at_nibble_index, set_nibble_offset_to_one, set_nibble_offset_to_zero,
-    slice_nibbles_at_nibble_index, Nibbles,
-};
+use crate::nibble_utils::Nibbles;
+use crate::trie_nodes::NodeKind;
 use crate::types::{Bytes, Result};
 
-const ODD_LENGTH_LEAF_PREFIX_NIBBLE: u8 = 3u8; // [00000011]
-const EVEN_LENGTH_LEAF_PREFIX_BYTE: u8 = 32u8; // [00100000]
-const EVEN_LENGTH_LEAF_PREFIX_NIBBLE: u8 = 2u8; // [00000010]
-const EVEN_LENGTH_EXTENSION_PREFIX_BYTE: u8 = 0u8; // [00000000]
-const ODD_LENGTH_EXTENSION_PREFIX_NIBBLE: u8 = 1u8; // [00000001]
-const EVEN_LENGTH_EXTENSION_PREFIX_NIBBLE: u8 = 0u8; // [00000000]
-
-fn get_leaf_prefix_nibble() -> Nibbles {
-    get_nibbles_from_offset_bytes(vec![ODD_LENGTH_LEAF_PREFIX_NIBBLE])
-}
-
-fn get_extension_prefix_nibble() -> Nibbles {
-    get_nibbles_from_offset_bytes(vec![ODD_LENGTH_EXTENSION_PREFIX_NIBBLE])
-}
-
-fn encode_even_length_extension_path_from_nibbles(nibbles: Nibbles) -> Result<Bytes> {
-    prefix_nibbles_with_byte(nibbles, vec![EVEN_LENGTH_EXTENSION_PREFIX_BYTE])
-}
-
-fn encode_even_length_leaf_path_from_nibbles(nibbles: Nibbles) -> Result<Bytes> {
-    prefix_nibbles_with_byte(nibbles, vec![EVEN_LENGTH_LEAF_PREFIX_BYTE])
-}
-
-fn encode_odd_length_path_from_nibbles(nibbles: Nibbles, prefix_nibble: Nibbles) -> Result<Bytes> {
-    replace_nibble_in_nibbles_at_nibble_index(set_nibble_offset_to_zero(nibbles), prefix_nibble, 0)
-        .and_then(convert_nibble_to_bytes)
-}
-
-fn encode_odd_length_extension_path_from_nibbles(nibbles: Nibbles) -> Result<Bytes> {
-    encode_odd_length_path_from_nibbles(nibbles, get_extension_prefix_nibble())
-}
-
-fn encode_odd_length_leaf_path_from_nibbles(nibbles: Nibbles) -> Result<Bytes> {
-    encode_odd_length_path_from_nibbles(nibbles, get_leaf_prefix_nibble())
-}
-
-fn decode_odd_length_nibbles(nibbles: Nibbles) -> Result<Nibbles> {
-    replace_nibble_in_nibbles_at_nibble_index(nibbles, get_zero_nibble(), 0)
-        .map(set_nibble_offset_to_one)
-}
-
-pub fn decode_path_to_nibbles_and_node_type(path: Bytes) -> Result<(Nibbles, &'static str)> {
-    let nibbles = get_nibbles_from_bytes(path);
-    match get_nibble_at_index(&nibbles, 0)? {
-        EVEN_LENGTH_LEAF_PREFIX_NIBBLE => {
-            Ok((slice_nibbles_at_nibble_index(nibbles, 2)?, LEAF_NODE_STRING))
-        }
-        EVEN_LENGTH_EXTENSION_PREFIX_NIBBLE => Ok((
-            slice_nibbles_at_nibble_index(nibbles, 2)?,
-            EXTENSION_NODE_STRING,
-        )),
-        ODD_LENGTH_LEAF_PREFIX_NIBBLE => {
-            Ok((decode_odd_length_nibbles(nibbles)?, LEAF_NODE_STRING))
-        }
-        ODD_LENGTH_EXTENSION_PREFIX_NIBBLE => {
-            Ok((decode_odd_length_nibbles(nibbles)?, EXTENSION_NODE_STRING))
-        }
-        _ => Err(AppError::Custom(
-            "✘ Malformed path - cannot determine node type!".to_string(),
-        )),
-    }
+/**
+ * Path Codec:
+ *
+ * Leaf & extension nodes store their paths hex-prefix encoded, w/ the flag
+ * nibble marking which of the two the node is. The encoding itself lives w/
+ * `Nibbles` in the `nibble_utils` module, so this only maps that flag to &
+ * from a `NodeKind`.
+ */
+pub fn decode_path_to_nibbles_and_node_type(path: Bytes) -> Result<(Nibbles, NodeKind)> {
+    Nibbles::from_hex_prefix_encoding(&path).map(|(nibbles, is_leaf)| match is_leaf {
+        true => (nibbles, NodeKind::Leaf),
+        false => (nibbles, NodeKind::Extension),
+    })
 }
 
 pub fn encode_extension_path_from_nibbles(nibbles: Nibbles) -> Result<Bytes> {
-    match get_length_in_nibbles(&nibbles) % 2 == 0 {
-        true => encode_even_length_extension_path_from_nibbles(nibbles),
-        false => encode_odd_length_extension_path_from_nibbles(nibbles),
-    }
+    nibbles.to_hex_prefix_encoding(false)
 }
 
 pub fn encode_leaf_path_from_nibbles(nibbles: Nibbles) -> Result<Bytes> {
-    match get_length_in_nibbles(&nibbles) % 2 == 0 {
-        true => encode_even_length_leaf_path_from_nibbles(nibbles),
-        false => encode_odd_length_leaf_path_from_nibbles(nibbles),
-    }
+    nibbles.to_hex_prefix_encoding(true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::EMPTY_NIBBLES;
+    use crate::errors::AppError;
     use crate::nibble_utils::{get_nibbles_from_bytes, get_nibbles_from_offset_bytes};
-    use hex;
 
     /*
      * Test vectors are from the spec @:
@@ -133,28 +74,28 @@ mod tests {
     #[test]
     fn should_encode_odd_length_extension_path_correctly() {
         let (sample, expected_result) = get_odd_extension_path_sample();
-        let result = encode_odd_length_extension_path_from_nibbles(sample).unwrap();
+        let result = sample.to_hex_prefix_encoding(false).unwrap();
         assert!(result == expected_result);
     }
 
     #[test]
     fn should_encode_even_length_extension_path_correctly() {
         let (sample, expected_result) = get_even_extension_path_sample();
-        let result = encode_even_length_extension_path_from_nibbles(sample).unwrap();
+        let result = sample.to_hex_prefix_encoding(false).unwrap();
         assert!(result == expected_result);
     }
 
     #[test]
     fn should_encode_odd_length_leaf_path_correctly() {
         let (sample, expected_result) = get_odd_leaf_path_sample();
-        let result = encode_odd_length_leaf_path_from_nibbles(sample).unwrap();
+        let result = sample.to_hex_prefix_encoding(true).unwrap();
         assert!(result == expected_result);
     }
 
     #[test]
     fn should_encode_even_length_leaf_path_correctly() {
         let (sample, expected_result) = get_even_leaf_path_sample();
-        let result = encode_even_length_leaf_path_from_nibbles(sample).unwrap();
+        let result = sample.to_hex_prefix_encoding(true).unwrap();
         assert!(result == expected_result);
     }
 
@@ -190,7 +131,7 @@ mod tests {
     fn should_decode_even_path_to_nibbles_and_leaf_node_type_correctly() {
         let (expected_nibbles, path) = get_even_leaf_path_sample();
         let (result_nibbles, result_type) = decode_path_to_nibbles_and_node_type(path).unwrap();
-        assert!(result_type == "leaf".to_string());
+        assert!(result_type == NodeKind::Leaf);
         assert!(expected_nibbles.data == result_nibbles.data);
     }
 
@@ -198,7 +139,7 @@ mod tests {
     fn should_decode_odd_path_to_nibbles_and_leaf_node_type_correctly() {
         let (expected_nibbles, path) = get_odd_leaf_path_sample();
         let (result_nibbles, result_type) = decode_path_to_nibbles_and_node_type(path).unwrap();
-        assert!(result_type == "leaf".to_string());
+        assert!(result_type == NodeKind::Leaf);
         assert!(expected_nibbles.data == result_nibbles.data);
     }
 
@@ -206,7 +147,7 @@ mod tests {
     fn should_decode_odd_path_to_nibbles_and_extension_node_type_correctly() {
         let (expected_nibbles, path) = get_odd_extension_path_sample();
         let (result_nibbles, result_type) = decode_path_to_nibbles_and_node_type(path).unwrap();
-        assert!(result_type == "extension".to_string());
+        assert!(result_type == NodeKind::Extension);
         assert!(expected_nibbles.data == result_nibbles.data);
     }
 
@@ -214,7 +155,7 @@ mod tests {
     fn should_decode_even_path_to_nibbles_and_extension_node_type_correctly() {
         let (expected_nibbles, path) = get_even_extension_path_sample();
         let (result_nibbles, result_type) = decode_path_to_nibbles_and_node_type(path).unwrap();
-        assert!(result_type == "extension".to_string());
+        assert!(result_type == NodeKind::Extension);
         assert!(expected_nibbles.data == result_nibbles.data);
     }
 
@@ -233,16 +174,39 @@ mod tests {
     #[test]
     fn should_decode_odd_length_leaf_path_to_nibbles_correctly() {
         let (expected_nibbles, path) = get_odd_leaf_path_sample();
-        let encoded_nibbles = get_nibbles_from_bytes(path);
-        let result = decode_odd_length_nibbles(encoded_nibbles).unwrap();
-        assert!(result.data == expected_nibbles.data);
+        let (result, _) = Nibbles::from_hex_prefix_encoding(&path).unwrap();
+        assert!(result == expected_nibbles);
     }
 
     #[test]
     fn should_decode_odd_length_extension_path_to_nibbles_correctly() {
         let (expected_nibbles, path) = get_odd_extension_path_sample();
-        let encoded_nibbles = get_nibbles_from_bytes(path);
-        let result = decode_odd_length_nibbles(encoded_nibbles).unwrap();
-        assert!(result.data == expected_nibbles.data);
+        let (result, _) = Nibbles::from_hex_prefix_encoding(&path).unwrap();
+        assert!(result == expected_nibbles);
+    }
+
+    #[test]
+    fn should_error_when_decoding_an_empty_path() {
+        match decode_path_to_nibbles_and_node_type(vec![]) {
+            Err(AppError::Custom(e)) => assert!(e.contains("cannot determine node type")),
+            _ => panic!("Should not decode an empty path!"),
+        }
+    }
+
+    #[test]
+    fn should_error_when_decoding_even_length_path_with_non_zero_padding_nibble() {
+        let wrong_path = hex::decode("2f1cb8").unwrap();
+        match decode_path_to_nibbles_and_node_type(wrong_path) {
+            Err(AppError::Custom(e)) => assert!(e.contains("non-zero padding nibble")),
+            _ => panic!("Should not decode a path w/ non-zero padding!"),
+        }
+    }
+
+    #[test]
+    fn should_hex_prefix_encode_empty_nibbles() {
+        let result = EMPTY_NIBBLES.to_hex_prefix_encoding(true).unwrap();
+        assert!(result == vec![0x20]);
+        let (nibbles, is_leaf) = Nibbles::from_hex_prefix_encoding(&result).unwrap();
+        assert!(nibbles.is_empty() && is_leaf);
     }
 }
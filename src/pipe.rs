@@ -0,0 +1,129 @@
+use crate::input_parsing::{normalize_block_id, normalize_tx_hash};
+use crate::parse_cli_args::CliArgs;
+use crate::serve::{ProofServer, Route};
+use crate::types::Result;
+use std::io::{self, BufRead, Write};
+
+/**
+ * Pipe:
+ *
+ * Shell pipelines & programs in other languages would rather talk to a
+ * subprocess than stand up the HTTP server. So `pipe` reads requests from
+ * stdin, one per line, & writes one line of JSON per request to stdout, in
+ * order, flushing after each so a caller can wait on its reply. A request is
+ * either a bare tx hash or an NDJSON object: `{"tx_hash": ...}`, or
+ * `{"block": ..., "index": ...}` for the receipt at that index in the block.
+ * Replies are proof bundles as `serve` responds w/, or `{"error": ...}`, so a
+ * bad request doesn't end the stream. Tries are cached across requests just
+ * as `serve` caches them.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum PipeRequest {
+    Receipt { tx_hash: String },
+    ReceiptInBlock { block: String, index: usize },
+}
+
+pub fn get_pipe_route(line: &str) -> Route {
+    let line = line.trim();
+    let request = match line.starts_with('{') {
+        false => PipeRequest::Receipt {
+            tx_hash: line.to_string(),
+        },
+        true => match serde_json::from_str::<PipeRequest>(line) {
+            Ok(request) => request,
+            Err(_) => {
+                return Route::BadRequest(format!(
+                    "✘ Expected `{{\"tx_hash\": ...}}` or `{{\"block\": ..., \"index\": ...}}`, got: {}",
+                    line
+                ))
+            }
+        },
+    };
+    match request {
+        PipeRequest::Receipt { tx_hash } => match normalize_tx_hash(&tx_hash) {
+            Ok(tx_hash) => Route::ProveReceipt(tx_hash),
+            Err(e) => Route::BadRequest(e.to_string()),
+        },
+        PipeRequest::ReceiptInBlock { block, index } => match normalize_block_id(&block) {
+            Ok(block_id) => Route::ProveReceiptInBlock(block_id, index),
+            Err(e) => Route::BadRequest(e.to_string()),
+        },
+    }
+}
+
+pub fn run_pipe<R: BufRead, W: Write>(
+    proof_server: &ProofServer,
+    input: R,
+    mut output: W,
+) -> Result<usize> {
+    let mut num_requests = 0;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (_, json) = proof_server.handle_route(get_pipe_route(&line), "", &None);
+        writeln!(output, "{}", json)?;
+        output.flush()?;
+        num_requests += 1;
+    }
+    Ok(num_requests)
+}
+
+// NOTE: Proofs are streamed to stdout as they're made, so there's no output left to return.
+pub fn pipe(cli_args: &CliArgs) -> Result<String> {
+    let proof_server = ProofServer::new(cli_args)?;
+    info!("✔ Reading proof requests from stdin, one per line...");
+    let num_requests = run_pipe(&proof_server, io::stdin().lock(), io::stdout().lock())?;
+    info!("✔ Replied to {} request(s)!", num_requests);
+    Ok(String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SAMPLE_TX_HASH;
+    use clap::Parser;
+    use serde_json::Value as Json;
+
+    #[test]
+    fn should_get_pipe_routes_from_tx_hashes_and_ndjson_requests() {
+        let expected = Route::ProveReceipt(SAMPLE_TX_HASH.to_string());
+        assert!(get_pipe_route(SAMPLE_TX_HASH) == expected);
+        assert!(get_pipe_route(&format!(" {}\r", &SAMPLE_TX_HASH[2..])) == expected);
+        assert!(get_pipe_route(&format!("{{\"tx_hash\": \"{}\"}}", SAMPLE_TX_HASH)) == expected);
+        assert!(
+            get_pipe_route("{\"block\": \"0x10\", \"index\": 3}")
+                == Route::ProveReceiptInBlock("16".to_string(), 3)
+        );
+        [
+            "0xc0ffee",
+            "{\"block\": \"latest\"}",
+            "{\"block\": \"nope\", \"index\": 1}",
+            "{\"tx_hash\": 1}",
+        ]
+        .iter()
+        .for_each(|line| match get_pipe_route(line) {
+            Route::BadRequest(_) => (),
+            _ => panic!("Should be a bad request route for: {}", line),
+        });
+    }
+
+    #[test]
+    fn should_reply_to_each_request_w_one_line_of_json() {
+        let cli_args = CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker", "pipe"]).unwrap();
+        let proof_server = ProofServer::new(&cli_args).unwrap();
+        let input = "0xc0ffee\n\n{\"block\": \"nope\", \"index\": 1}\n";
+        let mut output = Vec::new();
+        let result = run_pipe(&proof_server, input.as_bytes(), &mut output).unwrap();
+        let lines = String::from_utf8(output).unwrap();
+        let replies = lines
+            .lines()
+            .map(|line| serde_json::from_str::<Json>(line).unwrap())
+            .collect::<Vec<Json>>();
+        assert!(result == 2);
+        assert!(replies.len() == 2);
+        assert!(replies.iter().all(|reply| reply["error"].is_string()));
+    }
+}
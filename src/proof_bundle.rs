@@ -0,0 +1,418 @@
+use crate::block_header::get_verified_rlp_encoded_block_header;
+use crate::build_metadata::BuildMetadata;
+use crate::errors::AppError;
+use crate::finality::Finality;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::network::{get_detected_network, set_network_in_proof_bundle};
+use crate::proof_compression::compress_receipt_proof;
+use crate::proof_formats::{stamp_format_version, ProofFormatKind};
+use crate::proof_signing::ProofSignature;
+use crate::receipt_assertions::AssertionResult;
+use crate::rlp_codec::Encoder;
+use crate::state::State;
+use crate::trie_keys::{ReceiptKey, TrieKey};
+use crate::trie_nodes::Node;
+use crate::types::{Block, Bytes, HexProof, Result};
+use crate::utils::{
+    convert_bytes_to_hex, convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex,
+    decode_prefixed_hex,
+};
+use ethereum_types::H256;
+use serde_json::Value as Json;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub tx_hash: String,
+    pub tx_index: usize,
+    pub block_hash: String,
+    pub block_number: u64,
+    pub receipts_root: String,
+    pub key: String,
+    // NOTE: Empty when the bundle carries the keccak hash of the value in its stead.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_hash: Option<String>,
+    pub proof: Vec<String>,
+    // NOTE: The RLP encoded block header, anchoring the receipts root to the block hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    // NOTE: The chain head's height when the proof was made, & the height it's valid until.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+    // NOTE: Whether the proof omits the hashes of its nodes' children, for verifiers to recompute.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed: bool,
+    // NOTE: The results of any `--assertions` evaluated against the proven receipt.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<AssertionResult>,
+    // NOTE: The chain id & net version of the network the proof was made on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_version: Option<String>,
+    // NOTE: The block's confirmations & finality when proven, w/ `--min-confirmations` or `--require-finalized`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finality: Option<Finality>,
+    // NOTE: How, when & from which receipts the proof was made, for it to be audited & reproduced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<BuildMetadata>,
+    // NOTE: A `--sign-key` signature over the rest of the bundle, vouching for who made it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ProofSignature>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightProofBundle {
+    pub block_hash: String,
+    pub receipts_root: String,
+    pub tx_index: usize,
+    pub proof: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compressed: bool,
+}
+
+// NOTE: An `eth_getProof` account proof against the state root, & a storage slot proof against
+// that account's storage root. An empty `value` means the proof shows the key is absent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageProofBundle {
+    pub address: String,
+    pub block_hash: String,
+    pub block_number: u64,
+    pub state_root: String,
+    pub account_key: String,
+    pub account_value: String,
+    pub account_proof: Vec<String>,
+    pub slot: String,
+    pub storage_root: String,
+    pub storage_key: String,
+    pub storage_value: String,
+    pub storage_proof: Vec<String>,
+}
+
+// NOTE: Everything a bridge relayer needs to prove a log against a block hash in one artifact:
+// the header, the receipt, its proof against the header's receipts root, & the log's index in
+// the receipt. The block hash itself is omitted since verifiers must get it from a trusted source.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FullProof {
+    pub header: String,
+    pub tx_index: usize,
+    pub receipt: String,
+    pub proof: Vec<String>,
+    pub log_index: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AnyProofBundle {
+    Full(Box<ProofBundle>),
+    Light(LightProofBundle),
+    Storage(StorageProofBundle),
+}
+
+pub fn get_proof_bundle(
+    block: &Block,
+    receipts_root: H256,
+    index: usize,
+    branch: &[Node],
+) -> Result<ProofBundle> {
+    let encoded_nodes = branch
+        .iter()
+        .map(|node| node.get_rlp_encoding())
+        .collect::<Result<Vec<Bytes>>>()?;
+    Ok(ProofBundle {
+        tx_index: index,
+        block_number: block.number.as_u64(),
+        block_hash: convert_h256_to_prefixed_hex(block.hash)?,
+        receipts_root: convert_h256_to_prefixed_hex(receipts_root)?,
+        tx_hash: match block.transactions.get(index) {
+            Some(tx_hash) => convert_h256_to_prefixed_hex(*tx_hash)?,
+            None => {
+                return Err(AppError::Custom(format!(
+                    "✘ No transaction in block at index: {}",
+                    index
+                )))
+            }
+        },
+        key: convert_bytes_to_prefixed_hex(ReceiptKey::from_index(index)?.as_bytes()),
+        value: match branch.last().and_then(|node| node.get_value()) {
+            Some(value) => convert_bytes_to_prefixed_hex(&value),
+            None => {
+                return Err(AppError::Custom(
+                    "✘ Branch has no value to prove!".to_string(),
+                ))
+            }
+        },
+        proof: encoded_nodes
+            .iter()
+            .map(|encoded_node| convert_bytes_to_prefixed_hex(encoded_node))
+            .collect(),
+        value_hash: None,
+        header: Some(convert_bytes_to_prefixed_hex(
+            &get_verified_rlp_encoded_block_header(block)?,
+        )),
+        head_block_number: None,
+        valid_until: None,
+        compressed: false,
+        assertions: Vec::new(),
+        chain_id: None,
+        net_version: None,
+        finality: None,
+        metadata: None,
+        signature: None,
+    })
+    .map(|bundle| set_network_in_proof_bundle(bundle, &get_detected_network()))
+}
+
+pub fn get_proof_bundle_from_state(state: &State) -> Result<ProofBundle> {
+    info!("✔ Getting proof bundle from state...");
+    get_proof_bundle(
+        state.get_block_from_state()?,
+        state.get_receipts_trie_from_state()?.root,
+        *state.get_index_from_state()?,
+        state.get_branch_from_state()?,
+    )
+}
+
+// NOTE: For consumers only binding to the receipt hash, who'd rather not pay for its calldata.
+pub fn hash_value_in_proof_bundle(bundle: ProofBundle) -> Result<ProofBundle> {
+    let value_hash = keccak_hash_bytes(&decode_prefixed_hex(bundle.value.clone())?)?;
+    Ok(ProofBundle {
+        value: String::new(),
+        value_hash: Some(convert_h256_to_prefixed_hex(value_hash)?),
+        ..bundle
+    })
+}
+
+pub fn maybe_hash_value_in_proof_bundle(
+    bundle: ProofBundle,
+    value_hash: bool,
+) -> Result<ProofBundle> {
+    match value_hash {
+        true => hash_value_in_proof_bundle(bundle),
+        false => Ok(bundle),
+    }
+}
+
+pub fn compress_proof_in_proof_bundle(bundle: ProofBundle) -> Result<ProofBundle> {
+    match bundle.compressed {
+        true => Ok(bundle),
+        false => Ok(ProofBundle {
+            proof: compress_receipt_proof(bundle.tx_index, &bundle.proof)?,
+            compressed: true,
+            ..bundle
+        }),
+    }
+}
+
+pub fn maybe_compress_proof_in_proof_bundle(
+    bundle: ProofBundle,
+    compress: bool,
+) -> Result<ProofBundle> {
+    match compress {
+        true => compress_proof_in_proof_bundle(bundle),
+        false => Ok(bundle),
+    }
+}
+
+pub fn get_light_proof_bundle(bundle: &ProofBundle) -> LightProofBundle {
+    LightProofBundle {
+        tx_index: bundle.tx_index,
+        proof: bundle.proof.clone(),
+        compressed: bundle.compressed,
+        block_hash: bundle.block_hash.clone(),
+        receipts_root: bundle.receipts_root.clone(),
+    }
+}
+
+pub fn get_proof_bundle_json(bundle: &ProofBundle, light: bool) -> Result<Json> {
+    match light {
+        true => stamp_format_version(
+            serde_json::to_value(get_light_proof_bundle(bundle))?,
+            ProofFormatKind::LightReceipt,
+        ),
+        false => stamp_format_version(serde_json::to_value(bundle)?, ProofFormatKind::Receipt),
+    }
+}
+
+pub fn get_full_proof(bundle: &ProofBundle, log_index: usize) -> Result<FullProof> {
+    if bundle.compressed {
+        return Err(AppError::Custom(
+            "✘ Cannot make a full proof from a compressed proof bundle!".to_string(),
+        ));
+    }
+    if bundle.value.is_empty() {
+        return Err(AppError::Custom(
+            "✘ Proof bundle has no value to make a full proof with!".to_string(),
+        ));
+    }
+    Ok(FullProof {
+        log_index,
+        tx_index: bundle.tx_index,
+        proof: bundle.proof.clone(),
+        receipt: bundle.value.clone(),
+        header: match &bundle.header {
+            Some(header) => header.clone(),
+            None => {
+                return Err(AppError::Custom(
+                    "✘ Proof bundle has no block header to make a full proof with!".to_string(),
+                ))
+            }
+        },
+    })
+}
+
+pub fn rlp_encode_full_proof(full_proof: &FullProof) -> Result<Bytes> {
+    let mut encoder = Encoder::new_list(5);
+    encoder
+        .append(&decode_prefixed_hex(full_proof.header.clone())?)
+        .append(&full_proof.tx_index)
+        .append(&decode_prefixed_hex(full_proof.receipt.clone())?)
+        .append_raw(&rlp_encode_proof_nodes(&full_proof.proof)?)
+        .append(&full_proof.log_index);
+    Ok(encoder.out())
+}
+
+pub fn decode_proof_nodes(hex_nodes: &[String]) -> Result<Vec<Bytes>> {
+    hex_nodes
+        .iter()
+        .map(|hex_node| decode_prefixed_hex(hex_node.to_string()))
+        .collect()
+}
+
+pub fn rlp_encode_proof_nodes(hex_nodes: &[String]) -> Result<Bytes> {
+    decode_proof_nodes(hex_nodes).map(|encoded_nodes| {
+        let mut encoder = Encoder::new_list(encoded_nodes.len());
+        encoded_nodes.iter().for_each(|encoded_node| {
+            encoder.append_raw(encoded_node);
+        });
+        encoder.out()
+    })
+}
+
+pub fn rlp_encode_proof_nodes_from_proof_bundle(bundle: &ProofBundle) -> Result<Bytes> {
+    rlp_encode_proof_nodes(&bundle.proof)
+}
+
+pub fn get_hex_proof_from_proof_bundle(bundle: &ProofBundle) -> Result<HexProof> {
+    rlp_encode_proof_nodes_from_proof_bundle(bundle).map(convert_bytes_to_hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_header::rlp_encode_block_header;
+    use crate::rlp_codec::Decoder;
+    use crate::test_utils::{
+        get_expected_block, get_sample_leaf_node, get_sample_proof_1, get_sample_proof_bundle_1,
+        PROOF_1_INDEX, RECEIPTS_ROOT_1, SAMPLE_BLOCK_HASH,
+    };
+
+    #[test]
+    fn should_get_proof_bundle_from_state() {
+        let result = get_sample_proof_bundle_1();
+        assert!(result.tx_index == PROOF_1_INDEX);
+        assert!(result.key == "0x0e");
+        assert!(result.block_number == 8233333);
+        assert!(result.block_hash == SAMPLE_BLOCK_HASH);
+        assert!(result.receipts_root == RECEIPTS_ROOT_1);
+        assert!(!result.proof.is_empty());
+    }
+
+    #[test]
+    fn should_embed_verified_block_header_in_proof_bundle() {
+        let mut block = get_expected_block();
+        let result = get_proof_bundle(&block, H256::zero(), 0, &[get_sample_leaf_node()]).unwrap();
+        let expected_header = rlp_encode_block_header(&block).unwrap();
+        assert!(result.header == Some(convert_bytes_to_prefixed_hex(&expected_header)));
+        block.receipts_root = H256::zero();
+        assert!(get_proof_bundle(&block, H256::zero(), 0, &[get_sample_leaf_node()]).is_err());
+    }
+
+    #[test]
+    fn should_get_same_hex_proof_from_proof_bundle_as_from_branch() {
+        let bundle = get_sample_proof_bundle_1();
+        let result = get_hex_proof_from_proof_bundle(&bundle).unwrap();
+        assert!(result == get_sample_proof_1());
+    }
+
+    #[test]
+    fn should_serde_proof_bundle_to_and_from_json() {
+        let bundle = get_sample_proof_bundle_1();
+        let json = serde_json::to_string(&bundle).unwrap();
+        let result: ProofBundle = serde_json::from_str(&json).unwrap();
+        assert!(result == bundle);
+    }
+
+    #[test]
+    fn should_get_light_proof_bundle_json_without_header_or_receipt_fields() {
+        let bundle = get_sample_proof_bundle_1();
+        let result = get_proof_bundle_json(&bundle, true).unwrap();
+        assert!(result["tx_index"] == PROOF_1_INDEX);
+        assert!(result["block_hash"] == SAMPLE_BLOCK_HASH);
+        assert!(result["receipts_root"] == RECEIPTS_ROOT_1);
+        assert!(result.get("value").is_none());
+        assert!(result.get("tx_hash").is_none());
+        assert!(result.get("block_number").is_none());
+        assert!(
+            result.to_string().len()
+                < get_proof_bundle_json(&bundle, false)
+                    .unwrap()
+                    .to_string()
+                    .len()
+        );
+    }
+
+    #[test]
+    fn should_deserialize_full_and_light_proof_bundles_as_any_proof_bundle() {
+        let bundle = get_sample_proof_bundle_1();
+        let full_json = get_proof_bundle_json(&bundle, false).unwrap();
+        let light_json = get_proof_bundle_json(&bundle, true).unwrap();
+        let full_result: AnyProofBundle = serde_json::from_value(full_json).unwrap();
+        let light_result: AnyProofBundle = serde_json::from_value(light_json).unwrap();
+        assert!(full_result == AnyProofBundle::Full(Box::new(bundle.clone())));
+        assert!(light_result == AnyProofBundle::Light(get_light_proof_bundle(&bundle)));
+    }
+
+    #[test]
+    fn should_get_full_proof_from_proof_bundle_with_header() {
+        let mut bundle = get_sample_proof_bundle_1();
+        assert!(get_full_proof(&bundle, 0).is_err());
+        let header = rlp_encode_block_header(&get_expected_block()).unwrap();
+        bundle.header = Some(convert_bytes_to_prefixed_hex(&header));
+        let result = get_full_proof(&bundle, 3).unwrap();
+        assert!(result.log_index == 3);
+        assert!(result.receipt == bundle.value);
+        let rlp_encoded_full_proof = rlp_encode_full_proof(&result).unwrap();
+        let decoded_header: Bytes = Decoder::new(&rlp_encoded_full_proof).val_at(0).unwrap();
+        assert!(decoded_header == header);
+    }
+
+    #[test]
+    fn should_replace_value_in_proof_bundle_with_its_hash() {
+        let bundle = get_sample_proof_bundle_1();
+        let expected_hash =
+            keccak_hash_bytes(&decode_prefixed_hex(bundle.value.clone()).unwrap()).unwrap();
+        let result = maybe_hash_value_in_proof_bundle(bundle.clone(), true).unwrap();
+        assert!(result.value_hash == Some(convert_h256_to_prefixed_hex(expected_hash).unwrap()));
+        assert!(result.proof == bundle.proof);
+        assert!(get_full_proof(&result, 0).is_err());
+        let json = get_proof_bundle_json(&result, false).unwrap();
+        assert!(json.get("value").is_none());
+        let any_bundle: AnyProofBundle = serde_json::from_value(json).unwrap();
+        assert!(any_bundle == AnyProofBundle::Full(Box::new(result)));
+        assert!(maybe_hash_value_in_proof_bundle(bundle.clone(), false).unwrap() == bundle);
+    }
+
+    #[test]
+    fn should_fail_to_get_proof_bundle_for_index_not_in_block() {
+        let block = get_expected_block();
+        let out_of_bounds_index = block.transactions.len();
+        match get_proof_bundle(&block, H256::zero(), out_of_bounds_index, &[]) {
+            Err(AppError::Custom(e)) => assert!(e.contains("No transaction in block")),
+            _ => panic!("Should not get proof bundle for index not in block!"),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::errors::AppError;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::nibble_utils::Nibbles;
+use crate::proof_bundle::decode_proof_nodes;
+use crate::rlp_codec::{rlp_encode, Decoder, DecoderError, Encoder, RLP_EMPTY_STRING};
+use crate::trie_keys::{ReceiptKey, TrieKey};
+use crate::types::{Byte, Bytes, Result};
+use crate::utils::convert_bytes_to_prefixed_hex;
+
+const BRANCH_NODE_NUM_ITEMS: usize = 17;
+const EXTENSION_NODE_CHILD_INDEX: usize = 1;
+
+/**
+ * Proof Compression:
+ *
+ * Every node of a proof bar the last commits to the next one by its hash,
+ * which a verifier can recompute from that next node. Compressed proofs omit
+ * those hashes, leaving an empty string in their place, saving 32 bytes per
+ * node, which is much of a short extension node. Which of a node's items held
+ * the hash follows from the node's type & the key: a branch's child at the
+ * key's next nibble, or an extension's only child. Leaves commit to nothing.
+ * So decompressing walks the key down the proof to find those items, then
+ * fills them in from the last node upwards, hashing each node once it's whole.
+ */
+fn get_rlp_error(e: DecoderError) -> AppError {
+    AppError::Custom(format!("✘ Error decoding RLP encoded proof node: {}", e))
+}
+
+// NOTE: Returns the index of the item committing to the next node, & the key nibbles it consumes.
+fn get_child_item_index(encoded_node: &[Byte], key: &[Byte]) -> Result<Option<(usize, usize)>> {
+    let rlp = Decoder::new(encoded_node);
+    match rlp.get_item_count().map_err(get_rlp_error)? {
+        BRANCH_NODE_NUM_ITEMS => match key.first() {
+            Some(nibble) => Ok(Some((*nibble as usize, 1))),
+            None => Ok(None),
+        },
+        2 => {
+            let path = rlp
+                .at(0)
+                .and_then(|item| item.get_data())
+                .map_err(get_rlp_error)?;
+            match Nibbles::from_hex_prefix_encoding(path)? {
+                (_, true) => Ok(None),
+                (path_nibbles, false) => Ok(Some((EXTENSION_NODE_CHILD_INDEX, path_nibbles.len()))),
+            }
+        }
+        num_items => Err(AppError::Custom(format!(
+            "✘ Malformed proof node w/ {} items!",
+            num_items
+        ))),
+    }
+}
+
+fn get_child_item_indices(key: &[Byte], proof: &[Bytes]) -> Result<Vec<usize>> {
+    let mut remaining_key = key;
+    proof
+        .iter()
+        .take(proof.len().saturating_sub(1))
+        .map(
+            |encoded_node| match get_child_item_index(encoded_node, remaining_key)? {
+                Some((item_index, num_nibbles)) if num_nibbles <= remaining_key.len() => {
+                    remaining_key = &remaining_key[num_nibbles..];
+                    Ok(item_index)
+                }
+                _ => Err(AppError::Custom(
+                    "✘ Proof continues past the end of its key!".to_string(),
+                )),
+            },
+        )
+        .collect()
+}
+
+fn get_item_data(encoded_node: &[Byte], item_index: usize) -> Result<Bytes> {
+    Decoder::new(encoded_node)
+        .at(item_index)
+        .and_then(|item| item.get_data().map(|data| data.to_vec()))
+        .map_err(get_rlp_error)
+}
+
+fn replace_item_in_node(encoded_node: &[Byte], item_index: usize, item: &[Byte]) -> Result<Bytes> {
+    let rlp = Decoder::new(encoded_node);
+    let num_items = rlp.get_item_count().map_err(get_rlp_error)?;
+    let mut encoder = Encoder::new_list(num_items);
+    for index in 0..num_items {
+        match index == item_index {
+            true => encoder.append_raw(item),
+            false => encoder.append_raw(rlp.at(index).map_err(get_rlp_error)?.get_raw()),
+        };
+    }
+    Ok(encoder.out())
+}
+
+pub fn compress_proof(key: &[Byte], proof: &[Bytes]) -> Result<Vec<Bytes>> {
+    let child_item_indices = get_child_item_indices(key, proof)?;
+    proof
+        .iter()
+        .enumerate()
+        .map(
+            |(index, encoded_node)| match child_item_indices.get(index) {
+                None => Ok(encoded_node.clone()),
+                Some(item_index) => {
+                    let next_node_hash = keccak_hash_bytes(&proof[index + 1])?;
+                    match get_item_data(encoded_node, *item_index)? == next_node_hash.as_bytes() {
+                        true => replace_item_in_node(encoded_node, *item_index, &RLP_EMPTY_STRING),
+                        false => Err(AppError::Custom(format!(
+                            "✘ Proof node {} does not commit to the hash of the next node!",
+                            index
+                        ))),
+                    }
+                }
+            },
+        )
+        .collect()
+}
+
+pub fn decompress_proof(key: &[Byte], compressed_proof: &[Bytes]) -> Result<Vec<Bytes>> {
+    let child_item_indices = get_child_item_indices(key, compressed_proof)?;
+    let mut proof = compressed_proof.to_vec();
+    for (index, item_index) in child_item_indices.iter().enumerate().rev() {
+        if !get_item_data(&proof[index], *item_index)?.is_empty() {
+            return Err(AppError::Custom(format!(
+                "✘ Proof node {} is not compressed!",
+                index
+            )));
+        }
+        let next_node_hash = keccak_hash_bytes(&proof[index + 1])?;
+        proof[index] =
+            replace_item_in_node(&proof[index], *item_index, &rlp_encode(&next_node_hash))?;
+    }
+    Ok(proof)
+}
+
+fn get_receipt_key_nibbles(tx_index: usize) -> Result<Bytes> {
+    ReceiptKey::from_index(tx_index)?
+        .to_nibbles()
+        .to_nibble_values()
+}
+
+pub fn compress_receipt_proof(tx_index: usize, hex_proof: &[String]) -> Result<Vec<String>> {
+    compress_proof(
+        &get_receipt_key_nibbles(tx_index)?,
+        &decode_proof_nodes(hex_proof)?,
+    )
+    .map(|proof| {
+        proof
+            .iter()
+            .map(|encoded_node| convert_bytes_to_prefixed_hex(encoded_node))
+            .collect()
+    })
+}
+
+pub fn decompress_receipt_proof(tx_index: usize, hex_proof: &[String]) -> Result<Vec<Bytes>> {
+    decompress_proof(
+        &get_receipt_key_nibbles(tx_index)?,
+        &decode_proof_nodes(hex_proof)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    fn get_sample_proof_size(hex_proof: &[String]) -> usize {
+        hex_proof.iter().map(|hex_node| hex_node.len()).sum()
+    }
+
+    #[test]
+    fn should_compress_and_decompress_receipt_proof() {
+        let bundle = get_sample_proof_bundle_1();
+        let compressed_proof = compress_receipt_proof(bundle.tx_index, &bundle.proof).unwrap();
+        assert!(compressed_proof.len() == bundle.proof.len());
+        assert!(compressed_proof.last() == bundle.proof.last());
+        let min_hex_chars_saved = (bundle.proof.len() - 1) * 32 * 2;
+        assert!(
+            get_sample_proof_size(&bundle.proof) - get_sample_proof_size(&compressed_proof)
+                >= min_hex_chars_saved
+        );
+        let result = decompress_receipt_proof(bundle.tx_index, &compressed_proof).unwrap();
+        assert!(result == decode_proof_nodes(&bundle.proof).unwrap());
+    }
+
+    #[test]
+    fn should_fail_to_compress_proof_not_committing_to_next_node() {
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.proof.swap(0, 1);
+        assert!(compress_receipt_proof(bundle.tx_index, &bundle.proof).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_decompress_uncompressed_proof() {
+        let bundle = get_sample_proof_bundle_1();
+        match decompress_receipt_proof(bundle.tx_index, &bundle.proof) {
+            Err(AppError::Custom(e)) => assert!(e.contains("is not compressed")),
+            _ => panic!("Should not decompress uncompressed proof!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_decompress_proof_continuing_past_its_key() {
+        let bundle = get_sample_proof_bundle_1();
+        let mut compressed_proof = compress_receipt_proof(bundle.tx_index, &bundle.proof).unwrap();
+        compressed_proof.push(compressed_proof.last().unwrap().clone());
+        assert!(decompress_receipt_proof(bundle.tx_index, &compressed_proof).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use crate::errors::AppError;
+use crate::get_block::get_block_by_number;
+use crate::get_endpoint::get_endpoint;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::proof_bundle::{AnyProofBundle, ProofBundle};
+use crate::types::Result;
+use crate::utils::{convert_h256_to_prefixed_hex, convert_hex_to_h256};
+use crate::verify_proof::read_proof_bundles_from_file;
+use ethereum_types::H256;
+
+/**
+ * Proof Expiry:
+ *
+ * A receipt proof is only as good as its block's place in the canonical
+ * chain, so full proof bundles record the chain head's height when they were
+ * made, & optionally a `valid_until` block height, past which consumers should
+ * regenerate rather than submit them. The freshness check re-fetches the
+ * proof's block by number to catch proofs whose block has since been reorged
+ * out, & compares the chain head against `valid_until` to catch expired ones.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofExpiry {
+    pub head_block_number: u64,
+    pub valid_until: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ProofFreshness {
+    Fresh {
+        confirmations: u64,
+    },
+    Reorged {
+        canonical_block_hash: String,
+    },
+    Expired {
+        head_block_number: u64,
+        valid_until: u64,
+    },
+}
+
+impl ProofFreshness {
+    pub fn is_fresh(&self) -> bool {
+        matches!(self, ProofFreshness::Fresh { .. })
+    }
+}
+
+pub fn get_chain_head_block_number(endpoint: &str) -> Result<u64> {
+    get_block_by_number(endpoint, "latest").map(|block| block.number.as_u64())
+}
+
+pub fn get_proof_expiry(endpoint: &str, valid_for: &Option<u64>) -> Result<ProofExpiry> {
+    get_chain_head_block_number(endpoint).map(|head_block_number| {
+        info!("✔ Chain head at proof generation: {}", head_block_number);
+        ProofExpiry {
+            head_block_number,
+            valid_until: valid_for.map(|num_blocks| head_block_number + num_blocks),
+        }
+    })
+}
+
+pub fn maybe_get_proof_expiry(endpoint: &str, cli_args: &CliArgs) -> Result<Option<ProofExpiry>> {
+    // NOTE: Only full JSON proof bundles carry expiry metadata.
+    match (cli_args.format, cli_args.light) {
+        (OutputFormat::Json, false) => get_proof_expiry(endpoint, &cli_args.valid_for).map(Some),
+        _ => Ok(None),
+    }
+}
+
+pub fn set_proof_expiry_in_proof_bundle(
+    bundle: ProofBundle,
+    maybe_expiry: &Option<ProofExpiry>,
+) -> ProofBundle {
+    match maybe_expiry {
+        Some(expiry) => ProofBundle {
+            head_block_number: Some(expiry.head_block_number),
+            valid_until: expiry.valid_until,
+            ..bundle
+        },
+        None => bundle,
+    }
+}
+
+pub fn get_proof_freshness(
+    block_hash: &H256,
+    canonical_block_hash: &H256,
+    block_number: u64,
+    head_block_number: u64,
+    valid_until: Option<u64>,
+) -> Result<ProofFreshness> {
+    Ok(match valid_until {
+        _ if block_hash != canonical_block_hash => ProofFreshness::Reorged {
+            canonical_block_hash: convert_h256_to_prefixed_hex(*canonical_block_hash)?,
+        },
+        Some(valid_until) if head_block_number > valid_until => ProofFreshness::Expired {
+            head_block_number,
+            valid_until,
+        },
+        _ => ProofFreshness::Fresh {
+            confirmations: head_block_number.saturating_sub(block_number),
+        },
+    })
+}
+
+fn get_block_hash_number_and_expiry(bundle: &AnyProofBundle) -> Result<(String, u64, Option<u64>)> {
+    match bundle {
+        AnyProofBundle::Full(bundle) => Ok((
+            bundle.block_hash.clone(),
+            bundle.block_number,
+            bundle.valid_until,
+        )),
+        AnyProofBundle::Storage(bundle) => {
+            Ok((bundle.block_hash.clone(), bundle.block_number, None))
+        }
+        AnyProofBundle::Light(_) => Err(AppError::Custom(
+            "✘ Light proof bundles have no block number to check the canonical chain at!"
+                .to_string(),
+        )),
+    }
+}
+
+pub fn check_proof_bundle_freshness(
+    endpoint: &str,
+    bundle: &AnyProofBundle,
+    head_block_number: u64,
+) -> Result<ProofFreshness> {
+    let (block_hash, block_number, valid_until) = get_block_hash_number_and_expiry(bundle)?;
+    get_block_by_number(endpoint, &block_number.to_string()).and_then(|canonical_block| {
+        get_proof_freshness(
+            &convert_hex_to_h256(block_hash)?,
+            &canonical_block.hash,
+            block_number,
+            head_block_number,
+            valid_until,
+        )
+    })
+}
+
+pub fn format_proof_freshnesses(
+    freshnesses: &[ProofFreshness],
+    format: OutputFormat,
+) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Json | OutputFormat::Eip1186 => serde_json::to_string_pretty(freshnesses)?,
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => freshnesses
+            .iter()
+            .enumerate()
+            .map(|(i, freshness)| match freshness {
+                ProofFreshness::Fresh { confirmations } => {
+                    format!(
+                        "✔ Proof {} is canonical w/ {} confirmations",
+                        i, confirmations
+                    )
+                }
+                ProofFreshness::Reorged {
+                    canonical_block_hash,
+                } => format!(
+                    "✘ Proof {} is stale: its block was reorged out by {}",
+                    i, canonical_block_hash
+                ),
+                ProofFreshness::Expired {
+                    head_block_number,
+                    valid_until,
+                } => format!(
+                    "✘ Proof {} has expired: chain head {} is past its valid until {}",
+                    i, head_block_number, valid_until
+                ),
+            })
+            .collect::<Vec<String>>()
+            .join("\n"),
+    })
+}
+
+pub fn check_proof_bundles_in_file_are_fresh(cli_args: &CliArgs, path: &str) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    let head_block_number = get_chain_head_block_number(&endpoint)?;
+    let freshnesses = read_proof_bundles_from_file(path)?
+        .iter()
+        .map(|bundle| check_proof_bundle_freshness(&endpoint, bundle, head_block_number))
+        .collect::<Result<Vec<ProofFreshness>>>()?;
+    let output = format_proof_freshnesses(&freshnesses, cli_args.format)?;
+    match freshnesses.iter().all(ProofFreshness::is_fresh) {
+        true => Ok(output),
+        false => Err(AppError::Custom(output)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    #[test]
+    fn should_set_proof_expiry_in_proof_bundle() {
+        let bundle = get_sample_proof_bundle_1();
+        let expiry = ProofExpiry {
+            head_block_number: 8233340,
+            valid_until: Some(8233400),
+        };
+        let result = set_proof_expiry_in_proof_bundle(bundle.clone(), &Some(expiry));
+        assert!(result.head_block_number == Some(8233340));
+        assert!(result.valid_until == Some(8233400));
+        assert!(set_proof_expiry_in_proof_bundle(bundle.clone(), &None) == bundle);
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json["valid_until"] == 8233400);
+        assert!(serde_json::to_value(&bundle)
+            .unwrap()
+            .get("valid_until")
+            .is_none());
+    }
+
+    #[test]
+    fn should_get_fresh_proof_freshness_for_canonical_block() {
+        let hash = H256::zero();
+        let result = get_proof_freshness(&hash, &hash, 100, 112, Some(120)).unwrap();
+        assert!(result == ProofFreshness::Fresh { confirmations: 12 });
+    }
+
+    #[test]
+    fn should_get_reorged_proof_freshness_for_non_canonical_block() {
+        let canonical_hash = H256::from_slice(&[0xc0; 32]);
+        let result = get_proof_freshness(&H256::zero(), &canonical_hash, 100, 112, None).unwrap();
+        assert!(!result.is_fresh());
+        match result {
+            ProofFreshness::Reorged {
+                canonical_block_hash,
+            } => assert!(canonical_block_hash.starts_with("0xc0c0")),
+            _ => panic!("Proof of non-canonical block should be reorged!"),
+        }
+    }
+
+    #[test]
+    fn should_get_expired_proof_freshness_past_valid_until() {
+        let hash = H256::zero();
+        let result = get_proof_freshness(&hash, &hash, 100, 121, Some(120)).unwrap();
+        assert!(
+            result
+                == ProofFreshness::Expired {
+                    head_block_number: 121,
+                    valid_until: 120
+                }
+        );
+    }
+
+    #[test]
+    fn should_format_proof_freshnesses() {
+        let freshnesses = vec![
+            ProofFreshness::Fresh { confirmations: 12 },
+            ProofFreshness::Expired {
+                head_block_number: 121,
+                valid_until: 120,
+            },
+        ];
+        let hex = format_proof_freshnesses(&freshnesses, OutputFormat::Hex).unwrap();
+        assert!(hex.lines().count() == 2);
+        assert!(hex.contains("has expired"));
+        let json: serde_json::Value = serde_json::from_str(
+            &format_proof_freshnesses(&freshnesses, OutputFormat::Json).unwrap(),
+        )
+        .unwrap();
+        assert!(json[0]["status"] == "fresh");
+        assert!(json[1]["valid_until"] == 120);
+    }
+}
@@ -0,0 +1,533 @@
+use crate::constants::TRIE_SNAPSHOT_VERSION;
+use crate::errors::AppError;
+use crate::parse_cli_args::OutputFormat;
+use crate::proof_bundle::AnyProofBundle;
+use crate::types::Result;
+use serde_json::Value as Json;
+
+/**
+ * Proof Formats:
+ *
+ * A registry of every proof format the binary emits, each w/ the versions
+ * of it the binary can read, a changelog of those versions, & the JSON
+ * schema of the current one. Serializers stamp the current version into
+ * their output & readers check a proof's version against the registry
+ * before deserializing it as the format its fields say it is. Proofs
+ * predating the registry carry no version & are read as version 1. The
+ * `formats` subcommand prints the registry, so integrators can confirm a
+ * binary reads what they store before upgrading to it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProofFormatKind {
+    Receipt,
+    LightReceipt,
+    Storage,
+    Log,
+    TrieSnapshot,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FormatVersion {
+    pub version: u32,
+    pub since: &'static str,
+    pub changes: &'static str,
+}
+
+pub struct ProofFormat {
+    pub kind: ProofFormatKind,
+    pub description: &'static str,
+    pub versions: &'static [FormatVersion],
+    schema: fn() -> Option<Json>,
+}
+
+impl ProofFormat {
+    pub fn current_version(&self) -> u32 {
+        self.versions.last().map(|v| v.version).unwrap_or_default()
+    }
+
+    pub fn min_supported_version(&self) -> u32 {
+        self.versions.first().map(|v| v.version).unwrap_or_default()
+    }
+
+    pub fn get_schema(&self) -> Option<Json> {
+        (self.schema)()
+    }
+
+    pub fn check_version(&self, version: u32) -> Result<()> {
+        match self.versions.iter().any(|v| v.version == version) {
+            true => Ok(()),
+            false => Err(AppError::Custom(format!(
+                "✘ Unsupported {} format version: {}!\n✘ This binary reads versions {} to {}. Run the `formats` subcommand for details.",
+                get_format_name(self.kind),
+                version,
+                self.min_supported_version(),
+                self.current_version(),
+            ))),
+        }
+    }
+}
+
+pub static PROOF_FORMATS: [ProofFormat; 5] = [
+    ProofFormat {
+        kind: ProofFormatKind::Receipt,
+        description: "Full JSON receipt proof bundle, as output by `prove-receipt`, `prove-index`, `prove-block` & `serve`.",
+        versions: &[
+            FormatVersion {
+                version: 1,
+                since: "0.2.0",
+                changes: "Tx hash & index, block hash & number, receipts root, key, the receipt as `value` (or its keccak hash as `value_hash`) & proof nodes, optionally w/ the RLP encoded header & `head_block_number` & `valid_until` expiry metadata.",
+            },
+            FormatVersion {
+                version: 2,
+                since: "0.2.0",
+                changes: "Adds the optional `compressed` flag, set when the proof nodes omit the child hashes recomputable from the next node.",
+            },
+            FormatVersion {
+                version: 3,
+                since: "0.2.0",
+                changes: "Adds the optional `assertions`, the result of each `--assertions` condition evaluated against the proven receipt.",
+            },
+            FormatVersion {
+                version: 4,
+                since: "0.2.0",
+                changes: "Adds the optional `chain_id` & `net_version` of the network the proof was made on.",
+            },
+            FormatVersion {
+                version: 5,
+                since: "0.2.0",
+                changes: "Adds the optional `finality`, the block's confirmations & whether it was finalized when proven.",
+            },
+            FormatVersion {
+                version: 6,
+                since: "0.2.0",
+                changes: "Adds the optional `metadata`, the crate version, chain id, endpoint fingerprint, timestamp & receipts digest it was made w/.",
+            },
+            FormatVersion {
+                version: 7,
+                since: "0.2.0",
+                changes: "Adds the optional `signature`, a `--sign-key` signature over the rest of the bundle.",
+            },
+            FormatVersion {
+                version: 8,
+                since: "0.2.0",
+                changes: "Adds the optional `gas_estimate` to the `metadata`, the estimated gas of verifying the proof on-chain under the `--gas-model`.",
+            },
+        ],
+        schema: get_receipt_schema,
+    },
+    ProofFormat {
+        kind: ProofFormatKind::LightReceipt,
+        description: "Light JSON receipt proof bundle, as output w/ `--light`.",
+        versions: &[
+            FormatVersion {
+                version: 1,
+                since: "0.2.0",
+                changes: "Block hash, receipts root, tx index & proof nodes.",
+            },
+            FormatVersion {
+                version: 2,
+                since: "0.2.0",
+                changes: "Adds the optional `compressed` flag, as for receipt proof bundles.",
+            },
+        ],
+        schema: get_light_receipt_schema,
+    },
+    ProofFormat {
+        kind: ProofFormatKind::Storage,
+        description: "JSON account & storage proof bundle, as output by `prove-storage`.",
+        versions: &[FormatVersion {
+            version: 1,
+            since: "0.2.0",
+            changes: "Address, block hash & number, state root, account key, value & proof, slot, storage root, storage key, value & proof.",
+        }],
+        schema: get_storage_schema,
+    },
+    ProofFormat {
+        kind: ProofFormatKind::Log,
+        description: "JSON full log proof, as output by `prove-log`.",
+        versions: &[FormatVersion {
+            version: 1,
+            since: "0.2.0",
+            changes: "RLP encoded header, tx index, receipt, proof nodes & log index.",
+        }],
+        schema: get_log_schema,
+    },
+    ProofFormat {
+        kind: ProofFormatKind::TrieSnapshot,
+        description: "Binary receipts trie snapshot, as written by `export-trie`. Its version is the first item of its RLP list.",
+        versions: &[FormatVersion {
+            version: TRIE_SNAPSHOT_VERSION as u32,
+            since: "0.2.0",
+            changes: "The RLP list of the version, the root & the trie's nodes, sorted by hash.",
+        }],
+        schema: get_no_schema,
+    },
+];
+
+pub fn get_format_name(kind: ProofFormatKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|json| json.as_str().map(|name| name.to_string()))
+        .unwrap_or_default()
+}
+
+pub fn get_proof_format(kind: ProofFormatKind) -> &'static ProofFormat {
+    PROOF_FORMATS
+        .iter()
+        .find(|format| format.kind == kind)
+        .expect("✘ Every proof format kind should be in the registry!")
+}
+
+fn get_hex_schema() -> Json {
+    json!({ "type": "string", "pattern": "^0x([0-9a-fA-F]{2})*$" })
+}
+
+fn get_hash_schema() -> Json {
+    json!({ "type": "string", "pattern": "^0x[0-9a-fA-F]{64}$" })
+}
+
+fn get_uint_schema() -> Json {
+    json!({ "type": "integer", "minimum": 0 })
+}
+
+fn get_object_schema(kind: ProofFormatKind, required: &[&str], properties: Json) -> Json {
+    let mut properties = properties;
+    properties["version"] = json!({ "const": get_proof_format(kind).current_version() });
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": get_format_name(kind),
+        "type": "object",
+        "required": required,
+        "properties": properties,
+    })
+}
+
+fn get_receipt_schema() -> Option<Json> {
+    let mut schema = get_object_schema(
+        ProofFormatKind::Receipt,
+        &[
+            "tx_hash",
+            "tx_index",
+            "block_hash",
+            "block_number",
+            "receipts_root",
+            "key",
+            "proof",
+        ],
+        json!({
+            "tx_hash": get_hash_schema(),
+            "tx_index": get_uint_schema(),
+            "block_hash": get_hash_schema(),
+            "block_number": get_uint_schema(),
+            "receipts_root": get_hash_schema(),
+            "key": get_hex_schema(),
+            "value": get_hex_schema(),
+            "value_hash": get_hash_schema(),
+            "proof": { "type": "array", "items": get_hex_schema() },
+            "header": get_hex_schema(),
+            "head_block_number": get_uint_schema(),
+            "valid_until": get_uint_schema(),
+            "compressed": { "type": "boolean" },
+            "assertions": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["assertion", "passed"],
+                    "properties": {
+                        "assertion": { "type": "string" },
+                        "passed": { "type": "boolean" },
+                    },
+                },
+            },
+            "chain_id": get_uint_schema(),
+            "net_version": { "type": "string" },
+            "finality": {
+                "type": "object",
+                "required": ["confirmations", "finalized", "finalized_block_number"],
+                "properties": {
+                    "confirmations": get_uint_schema(),
+                    "finalized": { "type": "boolean" },
+                    "finalized_block_number": get_uint_schema(),
+                },
+            },
+            "metadata": {
+                "type": "object",
+                "required": ["crate_version", "endpoint_fingerprint", "timestamp", "receipts_digest"],
+                "properties": {
+                    "crate_version": { "type": "string" },
+                    "chain_id": get_uint_schema(),
+                    "endpoint_fingerprint": get_hash_schema(),
+                    "timestamp": { "type": "string" },
+                    "receipts_digest": get_hash_schema(),
+                    "gas_estimate": {
+                        "type": "object",
+                        "required": ["calldata_bytes", "calldata_gas", "keccak_gas", "execution_gas", "total_gas"],
+                        "properties": {
+                            "calldata_bytes": get_uint_schema(),
+                            "calldata_gas": get_uint_schema(),
+                            "keccak_gas": get_uint_schema(),
+                            "execution_gas": get_uint_schema(),
+                            "total_gas": get_uint_schema(),
+                        },
+                    },
+                },
+            },
+            "signature": {
+                "type": "object",
+                "required": ["scheme", "public_key", "signature"],
+                "properties": {
+                    "scheme": { "enum": ["secp256k1", "ed25519"] },
+                    "public_key": get_hex_schema(),
+                    "signature": get_hex_schema(),
+                },
+            },
+        }),
+    );
+    schema["oneOf"] = json!([{ "required": ["value"] }, { "required": ["value_hash"] }]);
+    Some(schema)
+}
+
+fn get_light_receipt_schema() -> Option<Json> {
+    Some(get_object_schema(
+        ProofFormatKind::LightReceipt,
+        &["block_hash", "receipts_root", "tx_index", "proof"],
+        json!({
+            "block_hash": get_hash_schema(),
+            "receipts_root": get_hash_schema(),
+            "tx_index": get_uint_schema(),
+            "proof": { "type": "array", "items": get_hex_schema() },
+            "compressed": { "type": "boolean" },
+        }),
+    ))
+}
+
+fn get_storage_schema() -> Option<Json> {
+    let fields = [
+        "address",
+        "block_hash",
+        "block_number",
+        "state_root",
+        "account_key",
+        "account_value",
+        "account_proof",
+        "slot",
+        "storage_root",
+        "storage_key",
+        "storage_value",
+        "storage_proof",
+    ];
+    Some(get_object_schema(
+        ProofFormatKind::Storage,
+        &fields,
+        json!({
+            "address": { "type": "string", "pattern": "^0x[0-9a-fA-F]{40}$" },
+            "block_hash": get_hash_schema(),
+            "block_number": get_uint_schema(),
+            "state_root": get_hash_schema(),
+            "account_key": get_hash_schema(),
+            "account_value": get_hex_schema(),
+            "account_proof": { "type": "array", "items": get_hex_schema() },
+            "slot": get_hash_schema(),
+            "storage_root": get_hash_schema(),
+            "storage_key": get_hash_schema(),
+            "storage_value": get_hex_schema(),
+            "storage_proof": { "type": "array", "items": get_hex_schema() },
+        }),
+    ))
+}
+
+fn get_log_schema() -> Option<Json> {
+    Some(get_object_schema(
+        ProofFormatKind::Log,
+        &["header", "tx_index", "receipt", "proof", "log_index"],
+        json!({
+            "header": get_hex_schema(),
+            "tx_index": get_uint_schema(),
+            "receipt": get_hex_schema(),
+            "proof": { "type": "array", "items": get_hex_schema() },
+            "log_index": get_uint_schema(),
+        }),
+    ))
+}
+
+fn get_no_schema() -> Option<Json> {
+    None
+}
+
+pub fn stamp_format_version(mut json: Json, kind: ProofFormatKind) -> Result<Json> {
+    match json.as_object_mut() {
+        Some(object) => {
+            object.insert(
+                "version".to_string(),
+                json!(get_proof_format(kind).current_version()),
+            );
+            Ok(json)
+        }
+        None => Err(AppError::Custom(format!(
+            "✘ Cannot stamp a version into a non-object {} proof!",
+            get_format_name(kind)
+        ))),
+    }
+}
+
+pub fn get_proof_format_kind_of_json(json: &Json) -> Result<ProofFormatKind> {
+    let has_field = |field: &str| json.get(field).is_some();
+    if has_field("storage_proof") {
+        Ok(ProofFormatKind::Storage)
+    } else if has_field("log_index") {
+        Ok(ProofFormatKind::Log)
+    } else if has_field("key") {
+        Ok(ProofFormatKind::Receipt)
+    } else if has_field("proof") && has_field("tx_index") {
+        Ok(ProofFormatKind::LightReceipt)
+    } else {
+        Err(AppError::Custom(
+            "✘ Unrecognised proof format! Run the `formats` subcommand for those supported."
+                .to_string(),
+        ))
+    }
+}
+
+pub fn get_format_version_of_json(json: &Json) -> Result<u32> {
+    match json.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or_else(|| {
+                AppError::Custom(format!("✘ Invalid proof format version: {}", version))
+            }),
+    }
+}
+
+pub fn deserialize_any_proof_bundle(json: Json) -> Result<AnyProofBundle> {
+    let kind = get_proof_format_kind_of_json(&json)?;
+    get_proof_format(kind).check_version(get_format_version_of_json(&json)?)?;
+    Ok(match kind {
+        ProofFormatKind::Receipt => AnyProofBundle::Full(serde_json::from_value(json)?),
+        ProofFormatKind::LightReceipt => AnyProofBundle::Light(serde_json::from_value(json)?),
+        ProofFormatKind::Storage => AnyProofBundle::Storage(serde_json::from_value(json)?),
+        ProofFormatKind::Log | ProofFormatKind::TrieSnapshot => {
+            return Err(AppError::Custom(format!(
+                "✘ A {} proof is not a proof bundle!",
+                get_format_name(kind)
+            )))
+        }
+    })
+}
+
+pub fn format_proof_formats(format: OutputFormat) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Json | OutputFormat::Eip1186 => serde_json::to_string_pretty(&json!({
+            "binary_version": env!("CARGO_PKG_VERSION"),
+            "formats": PROOF_FORMATS
+                .iter()
+                .map(|proof_format| {
+                    json!({
+                        "name": proof_format.kind,
+                        "description": proof_format.description,
+                        "current_version": proof_format.current_version(),
+                        "min_supported_version": proof_format.min_supported_version(),
+                        "versions": proof_format.versions,
+                        "schema": proof_format.get_schema(),
+                    })
+                })
+                .collect::<Vec<Json>>(),
+        }))?,
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => PROOF_FORMATS
+            .iter()
+            .map(|proof_format| {
+                let versions = proof_format
+                    .versions
+                    .iter()
+                    .map(|v| format!("  v{} (since {}): {}", v.version, v.since, v.changes))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!(
+                    "{} v{} (reads v{} to v{}): {}\n{}",
+                    get_format_name(proof_format.kind),
+                    proof_format.current_version(),
+                    proof_format.min_supported_version(),
+                    proof_format.current_version(),
+                    proof_format.description,
+                    versions
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_bundle::{get_light_proof_bundle, get_proof_bundle_json};
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    #[test]
+    fn should_have_a_format_for_every_kind_w_ascending_versions() {
+        PROOF_FORMATS.iter().for_each(|proof_format| {
+            assert!(get_proof_format(proof_format.kind).kind == proof_format.kind);
+            assert!(proof_format
+                .versions
+                .windows(2)
+                .all(|pair| pair[0].version < pair[1].version));
+        });
+        assert!(
+            get_proof_format(ProofFormatKind::TrieSnapshot).current_version()
+                == TRIE_SNAPSHOT_VERSION as u32
+        );
+    }
+
+    #[test]
+    fn should_stamp_current_version_into_serialized_bundles() {
+        let bundle = get_sample_proof_bundle_1();
+        let json = get_proof_bundle_json(&bundle, false).unwrap();
+        let light_json = get_proof_bundle_json(&bundle, true).unwrap();
+        assert!(json["version"] == get_proof_format(ProofFormatKind::Receipt).current_version());
+        assert!(get_proof_format_kind_of_json(&json).unwrap() == ProofFormatKind::Receipt);
+        assert!(
+            get_proof_format_kind_of_json(&light_json).unwrap() == ProofFormatKind::LightReceipt
+        );
+    }
+
+    #[test]
+    fn should_deserialize_bundles_by_their_format() {
+        let bundle = get_sample_proof_bundle_1();
+        let light_json = get_proof_bundle_json(&bundle, true).unwrap();
+        let mut unversioned_json = serde_json::to_value(&bundle).unwrap();
+        assert!(
+            deserialize_any_proof_bundle(light_json).unwrap()
+                == AnyProofBundle::Light(get_light_proof_bundle(&bundle))
+        );
+        assert!(
+            deserialize_any_proof_bundle(unversioned_json.clone()).unwrap()
+                == AnyProofBundle::Full(Box::new(bundle))
+        );
+        unversioned_json["version"] = json!(1337);
+        match deserialize_any_proof_bundle(unversioned_json) {
+            Err(AppError::Custom(e)) => assert!(e.contains("Unsupported receipt format version")),
+            _ => panic!("Should not deserialize bundle of unsupported version!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_deserialize_unrecognised_formats() {
+        assert!(deserialize_any_proof_bundle(json!({ "foo": "bar" })).is_err());
+        assert!(deserialize_any_proof_bundle(json!({ "log_index": 0 })).is_err());
+    }
+
+    #[test]
+    fn should_format_proof_formats() {
+        let json: Json =
+            serde_json::from_str(&format_proof_formats(OutputFormat::Json).unwrap()).unwrap();
+        let formats = json["formats"].as_array().unwrap();
+        assert!(formats.len() == PROOF_FORMATS.len());
+        assert!(formats[0]["name"] == "receipt");
+        assert!(formats[0]["schema"]["required"][0] == "tx_hash");
+        assert!(formats[4]["schema"].is_null());
+        let hex = format_proof_formats(OutputFormat::Hex).unwrap();
+        assert!(hex.starts_with("receipt v8 (reads v1 to v8)"));
+    }
+}
@@ -0,0 +1,319 @@
+use crate::errors::AppError;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::proof_bundle::{AnyProofBundle, ProofBundle};
+use crate::types::{Bytes, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, convert_hex_to_bytes, decode_prefixed_hex};
+use clap::ValueEnum;
+use ed25519_dalek::{Signer, Verifier};
+use ethereum_types::H256;
+use k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey as EcdsaVerifyingKey};
+use std::convert::TryInto;
+use std::fs;
+
+const PRIVATE_KEY_LENGTH: usize = 32;
+const ECDSA_SIGNATURE_LENGTH: usize = 64;
+const ETH_RECOVERY_ID_OFFSET: u8 = 27;
+
+/**
+ * Proof Signing:
+ *
+ * A proof bundle proves its receipt against the receipts root it carries, but
+ * says nothing of who made it, so consumers can't tell proofs from our
+ * instance from ones swapped in by whoever sits between us. So w/ `--sign-key`,
+ * full JSON bundles carry a `signature` by that key over the keccak hash of
+ * the bundle's canonical encoding: its compact JSON, w/o the signature, w/
+ * fields in the order `ProofBundle` declares them. secp256k1 signatures are
+ * the 65-byte `r || s || v` of `ecrecover`, ed25519 signatures the usual 64
+ * bytes. Any signature a bundle carries is checked when it's verified, &
+ * `verify --signer` additionally requires every bundle be signed by that key.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureScheme {
+    Secp256k1,
+    Ed25519,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofSignature {
+    pub scheme: SignatureScheme,
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Clone)]
+pub enum ProofSigningKey {
+    Secp256k1(k256::ecdsa::SigningKey),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl ProofSigningKey {
+    pub fn from_bytes(scheme: SignatureScheme, bytes: &[u8]) -> Result<Self> {
+        let invalid_key = || {
+            AppError::Custom(format!(
+                "✘ Signing key must be {} bytes of hex!",
+                PRIVATE_KEY_LENGTH
+            ))
+        };
+        let bytes: [u8; PRIVATE_KEY_LENGTH] = bytes.try_into().map_err(|_| invalid_key())?;
+        match scheme {
+            SignatureScheme::Secp256k1 => k256::ecdsa::SigningKey::from_slice(&bytes)
+                .map(ProofSigningKey::Secp256k1)
+                .map_err(|_| invalid_key()),
+            SignatureScheme::Ed25519 => Ok(ProofSigningKey::Ed25519(
+                ed25519_dalek::SigningKey::from_bytes(&bytes),
+            )),
+        }
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            ProofSigningKey::Secp256k1(_) => SignatureScheme::Secp256k1,
+            ProofSigningKey::Ed25519(_) => SignatureScheme::Ed25519,
+        }
+    }
+
+    // NOTE: secp256k1 public keys are SEC1 encoded & compressed.
+    pub fn get_public_key(&self) -> String {
+        match self {
+            ProofSigningKey::Secp256k1(key) => {
+                convert_bytes_to_prefixed_hex(key.verifying_key().to_encoded_point(true).as_bytes())
+            }
+            ProofSigningKey::Ed25519(key) => {
+                convert_bytes_to_prefixed_hex(key.verifying_key().as_bytes())
+            }
+        }
+    }
+
+    pub fn sign_digest(&self, digest: &H256) -> Result<Bytes> {
+        match self {
+            ProofSigningKey::Secp256k1(key) => {
+                let (signature, recovery_id) = key
+                    .sign_prehash_recoverable(digest.as_bytes())
+                    .map_err(|e| AppError::Custom(format!("✘ Error signing proof: {}", e)))?;
+                let mut bytes = signature.to_bytes().to_vec();
+                bytes.push(recovery_id.to_byte() + ETH_RECOVERY_ID_OFFSET);
+                Ok(bytes)
+            }
+            ProofSigningKey::Ed25519(key) => Ok(key.sign(digest.as_bytes()).to_bytes().to_vec()),
+        }
+    }
+}
+
+pub fn read_proof_signing_key(path: &str, scheme: SignatureScheme) -> Result<ProofSigningKey> {
+    fs::read_to_string(path)
+        .map_err(|e| AppError::Custom(format!("✘ Error reading signing key {}: {}", path, e)))
+        .and_then(|hex| convert_hex_to_bytes(hex.trim().to_string()))
+        .and_then(|bytes| ProofSigningKey::from_bytes(scheme, &bytes))
+}
+
+pub fn maybe_read_proof_signing_key(
+    maybe_path: &Option<String>,
+    scheme: SignatureScheme,
+) -> Result<Option<ProofSigningKey>> {
+    maybe_path
+        .as_ref()
+        .map(|path| {
+            read_proof_signing_key(path, scheme).inspect(|key| {
+                info!(
+                    "✔ Signing proofs w/ {:?} key: {}",
+                    key.scheme(),
+                    key.get_public_key()
+                )
+            })
+        })
+        .transpose()
+}
+
+pub fn get_proof_bundle_signing_digest(bundle: &ProofBundle) -> Result<H256> {
+    let unsigned_bundle = ProofBundle {
+        signature: None,
+        ..bundle.clone()
+    };
+    keccak_hash_bytes(&serde_json::to_vec(&unsigned_bundle)?)
+}
+
+pub fn sign_proof_bundle(bundle: ProofBundle, key: &ProofSigningKey) -> Result<ProofBundle> {
+    let signature = key.sign_digest(&get_proof_bundle_signing_digest(&bundle)?)?;
+    Ok(ProofBundle {
+        signature: Some(ProofSignature {
+            scheme: key.scheme(),
+            public_key: key.get_public_key(),
+            signature: convert_bytes_to_prefixed_hex(&signature),
+        }),
+        ..bundle
+    })
+}
+
+pub fn maybe_sign_proof_bundle(
+    bundle: ProofBundle,
+    maybe_key: &Option<ProofSigningKey>,
+) -> Result<ProofBundle> {
+    match maybe_key {
+        Some(key) => sign_proof_bundle(bundle, key),
+        None => Ok(bundle),
+    }
+}
+
+fn get_invalid_signature_error(bundle: &ProofBundle) -> AppError {
+    AppError::Custom(format!(
+        "✘ Invalid signature over proof of tx index {}!",
+        bundle.tx_index
+    ))
+}
+
+fn verify_secp256k1_signature(digest: &H256, public_key: &[u8], signature: &[u8]) -> Option<()> {
+    if signature.len() != ECDSA_SIGNATURE_LENGTH + 1 {
+        return None;
+    }
+    let (signature, v) = signature.split_at(ECDSA_SIGNATURE_LENGTH);
+    let recovery_id = RecoveryId::from_byte(v[0].checked_sub(ETH_RECOVERY_ID_OFFSET)?)?;
+    let signature = EcdsaSignature::from_slice(signature).ok()?;
+    let recovered =
+        EcdsaVerifyingKey::recover_from_prehash(digest.as_bytes(), &signature, recovery_id).ok()?;
+    match recovered == EcdsaVerifyingKey::from_sec1_bytes(public_key).ok()? {
+        true => Some(()),
+        false => None,
+    }
+}
+
+fn verify_ed25519_signature(digest: &H256, public_key: &[u8], signature: &[u8]) -> Option<()> {
+    let public_key = ed25519_dalek::VerifyingKey::from_bytes(public_key.try_into().ok()?).ok()?;
+    let signature = ed25519_dalek::Signature::from_slice(signature).ok()?;
+    public_key.verify(digest.as_bytes(), &signature).ok()
+}
+
+pub fn verify_proof_bundle_signature(bundle: &ProofBundle) -> Result<()> {
+    let proof_signature = match &bundle.signature {
+        Some(proof_signature) => proof_signature,
+        None => {
+            return Err(AppError::Custom(format!(
+                "✘ Proof of tx index {} is not signed!",
+                bundle.tx_index
+            )))
+        }
+    };
+    let digest = get_proof_bundle_signing_digest(bundle)?;
+    let public_key = decode_prefixed_hex(proof_signature.public_key.clone())?;
+    let signature = decode_prefixed_hex(proof_signature.signature.clone())?;
+    match proof_signature.scheme {
+        SignatureScheme::Secp256k1 => verify_secp256k1_signature(&digest, &public_key, &signature),
+        SignatureScheme::Ed25519 => verify_ed25519_signature(&digest, &public_key, &signature),
+    }
+    .ok_or_else(|| get_invalid_signature_error(bundle))
+}
+
+pub fn check_proof_bundle_signed_by(bundle: &AnyProofBundle, signer: &str) -> Result<()> {
+    let bundle = match bundle {
+        AnyProofBundle::Full(bundle) => bundle,
+        _ => {
+            return Err(AppError::Custom(
+                "✘ Only full proof bundles carry signatures!".to_string(),
+            ))
+        }
+    };
+    verify_proof_bundle_signature(bundle)?;
+    let expected_public_key = decode_prefixed_hex(signer.to_string())?;
+    let signature = bundle
+        .signature
+        .as_ref()
+        .ok_or_else(|| get_invalid_signature_error(bundle))?;
+    match decode_prefixed_hex(signature.public_key.clone())? == expected_public_key {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Proof of tx index {} is signed by {}, not the trusted signer {}!",
+            bundle.tx_index, signature.public_key, signer
+        ))),
+    }
+}
+
+pub fn check_proof_bundles_signed_by(bundles: &[AnyProofBundle], signer: &str) -> Result<()> {
+    bundles
+        .iter()
+        .try_for_each(|bundle| check_proof_bundle_signed_by(bundle, signer))?;
+    info!(
+        "✔ {} proof(s) signed by trusted signer: {}",
+        bundles.len(),
+        signer
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_bundle::get_light_proof_bundle;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    fn get_sample_signing_key(scheme: SignatureScheme) -> ProofSigningKey {
+        ProofSigningKey::from_bytes(scheme, &[0x2a; PRIVATE_KEY_LENGTH]).unwrap()
+    }
+
+    #[test]
+    fn should_sign_and_verify_proof_bundle_w_either_scheme() {
+        [SignatureScheme::Secp256k1, SignatureScheme::Ed25519]
+            .iter()
+            .for_each(|scheme| {
+                let key = get_sample_signing_key(*scheme);
+                let bundle = sign_proof_bundle(get_sample_proof_bundle_1(), &key).unwrap();
+                assert!(verify_proof_bundle_signature(&bundle).is_ok());
+                let json = serde_json::to_string(&bundle).unwrap();
+                let parsed: ProofBundle = serde_json::from_str(&json).unwrap();
+                assert!(verify_proof_bundle_signature(&parsed).is_ok());
+                let tampered = ProofBundle {
+                    tx_index: bundle.tx_index + 1,
+                    ..bundle.clone()
+                };
+                assert!(verify_proof_bundle_signature(&tampered).is_err());
+            });
+    }
+
+    #[test]
+    fn should_sign_secp256k1_proofs_recoverably() {
+        let key = get_sample_signing_key(SignatureScheme::Secp256k1);
+        let bundle = sign_proof_bundle(get_sample_proof_bundle_1(), &key).unwrap();
+        let signature = decode_prefixed_hex(bundle.signature.unwrap().signature).unwrap();
+        assert!(signature.len() == ECDSA_SIGNATURE_LENGTH + 1);
+        assert!(signature[ECDSA_SIGNATURE_LENGTH] >= ETH_RECOVERY_ID_OFFSET);
+    }
+
+    #[test]
+    fn should_check_proof_bundles_signed_by_trusted_signer() {
+        let key = get_sample_signing_key(SignatureScheme::Ed25519);
+        let other_key = ProofSigningKey::from_bytes(SignatureScheme::Ed25519, &[0x01; 32]).unwrap();
+        let unsigned_bundle = get_sample_proof_bundle_1();
+        let bundle = sign_proof_bundle(unsigned_bundle.clone(), &key).unwrap();
+        let signer = key.get_public_key();
+        assert!(check_proof_bundle_signed_by(
+            &AnyProofBundle::Full(Box::new(bundle.clone())),
+            &signer
+        )
+        .is_ok());
+        assert!(check_proof_bundle_signed_by(
+            &AnyProofBundle::Full(Box::new(bundle.clone())),
+            &other_key.get_public_key()
+        )
+        .is_err());
+        assert!(check_proof_bundle_signed_by(
+            &AnyProofBundle::Full(Box::new(unsigned_bundle)),
+            &signer
+        )
+        .is_err());
+        let light_bundle = AnyProofBundle::Light(get_light_proof_bundle(&bundle));
+        assert!(check_proof_bundle_signed_by(&light_bundle, &signer).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_read_malformed_signing_key() {
+        assert!(ProofSigningKey::from_bytes(SignatureScheme::Ed25519, &[0x2a; 31]).is_err());
+        assert!(ProofSigningKey::from_bytes(SignatureScheme::Secp256k1, &[0x00; 32]).is_err());
+        let path = std::env::temp_dir().join("rrpm-proof-signing-key");
+        fs::write(&path, format!("0x{}\n", "2a".repeat(PRIVATE_KEY_LENGTH))).unwrap();
+        let result = read_proof_signing_key(path.to_str().unwrap(), SignatureScheme::Secp256k1);
+        fs::remove_file(&path).unwrap();
+        assert!(
+            result.unwrap().get_public_key()
+                == get_sample_signing_key(SignatureScheme::Secp256k1).get_public_key()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::trie_nodes::Node;
+use crate::types::Result;
+use ethereum_types::H256;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/**
+ * Proof Size Reports:
+ *
+ * Bridge teams budget the calldata of the proofs they'll submit on-chain, so
+ * w/ `--size-report` each receipt proof pulled from a trie is recorded, & a
+ * report printed to stderr once the command finishes, like `--timings`: the
+ * nodes per proof, total bytes, the deepest path, & how those bytes compare
+ * to a multiproof of the same receipts, i.e. one carrying each node shared
+ * between proofs, such as the root, only once. Nodes are deduplicated by
+ * hash, so proofs from different blocks' tries never share any. Recording is
+ * off unless enabled, so a long running `serve` doesn't accumulate nodes.
+ */
+static PROOF_SIZE_REPORT_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static PROOF_SIZES: Mutex<ProofSizes> = Mutex::new(ProofSizes::new());
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofSizes {
+    pub num_proofs: usize,
+    pub num_nodes: usize,
+    pub min_nodes: usize,
+    pub max_nodes: usize,
+    pub total_bytes: usize,
+    pub deepest_tx_index: usize,
+    pub multiproof_nodes: BTreeMap<H256, usize>,
+}
+
+impl ProofSizes {
+    pub const fn new() -> Self {
+        ProofSizes {
+            num_proofs: 0,
+            num_nodes: 0,
+            min_nodes: 0,
+            max_nodes: 0,
+            total_bytes: 0,
+            deepest_tx_index: 0,
+            multiproof_nodes: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, tx_index: usize, branch: &[Node]) -> Result<()> {
+        for node in branch {
+            self.multiproof_nodes
+                .insert(node.get_hash()?, node.get_rlp_encoding()?.len());
+        }
+        self.total_bytes += branch
+            .iter()
+            .map(|node| node.get_rlp_encoding().map(|encoding| encoding.len()))
+            .sum::<Result<usize>>()?;
+        if self.num_proofs == 0 || branch.len() < self.min_nodes {
+            self.min_nodes = branch.len();
+        }
+        if branch.len() > self.max_nodes {
+            self.max_nodes = branch.len();
+            self.deepest_tx_index = tx_index;
+        }
+        self.num_proofs += 1;
+        self.num_nodes += branch.len();
+        Ok(())
+    }
+
+    pub fn get_multiproof_bytes(&self) -> usize {
+        self.multiproof_nodes.values().sum()
+    }
+
+    pub fn get_compression_ratio(&self) -> f64 {
+        self.total_bytes as f64 / self.get_multiproof_bytes().max(1) as f64
+    }
+}
+
+pub fn set_proof_size_report_enabled(enabled: bool) {
+    PROOF_SIZE_REPORT_ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn maybe_record_proof_size(tx_index: usize, branch: &[Node]) -> Result<()> {
+    match PROOF_SIZE_REPORT_ENABLED.load(Ordering::SeqCst) {
+        false => Ok(()),
+        true => match PROOF_SIZES.lock() {
+            Ok(mut proof_sizes) => proof_sizes.record(tx_index, branch),
+            Err(_) => Ok(()),
+        },
+    }
+}
+
+pub fn format_proof_size_report(proof_sizes: &ProofSizes) -> String {
+    match proof_sizes.num_proofs {
+        0 => "✔ Proof sizes: No proofs generated".to_string(),
+        num_proofs => [
+            "✔ Proof sizes:".to_string(),
+            format!("  {:<24} {}", "proofs", num_proofs),
+            format!(
+                "  {:<24} {} min, {:.1} mean, {} max",
+                "nodes per proof",
+                proof_sizes.min_nodes,
+                proof_sizes.num_nodes as f64 / num_proofs as f64,
+                proof_sizes.max_nodes
+            ),
+            format!(
+                "  {:<24} {} ({:.1} per proof)",
+                "total bytes",
+                proof_sizes.total_bytes,
+                proof_sizes.total_bytes as f64 / num_proofs as f64
+            ),
+            format!(
+                "  {:<24} {} nodes, at tx index {}",
+                "deepest path", proof_sizes.max_nodes, proof_sizes.deepest_tx_index
+            ),
+            format!(
+                "  {:<24} {} bytes in {} unique nodes, {:.2}x smaller",
+                "as a multiproof",
+                proof_sizes.get_multiproof_bytes(),
+                proof_sizes.multiproof_nodes.len(),
+                proof_sizes.get_compression_ratio()
+            ),
+        ]
+        .join("\n"),
+    }
+}
+
+pub fn maybe_print_proof_size_report(enabled: bool) {
+    if enabled {
+        if let Ok(proof_sizes) = PROOF_SIZES.lock() {
+            eprintln!("{}", format_proof_size_report(&proof_sizes));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_branch_from_trie::get_branch_from_trie;
+    use crate::test_utils::{
+        get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    #[test]
+    fn should_record_proof_sizes_w_shared_nodes_once_in_multiproof() {
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let branches = (0..get_sample_tx_hashes_1().len())
+            .map(|index| get_branch_from_trie(&trie, index).unwrap())
+            .collect::<Vec<_>>();
+        let mut proof_sizes = ProofSizes::new();
+        branches
+            .iter()
+            .enumerate()
+            .for_each(|(index, branch)| proof_sizes.record(index, branch).unwrap());
+        let expected_max_nodes = branches.iter().map(|branch| branch.len()).max().unwrap();
+        assert!(proof_sizes.num_proofs == branches.len());
+        assert!(proof_sizes.max_nodes == expected_max_nodes);
+        assert!(branches[proof_sizes.deepest_tx_index].len() == expected_max_nodes);
+        assert!(proof_sizes.min_nodes <= proof_sizes.max_nodes);
+        // NOTE: Every proof starts at the root, so the multiproof must be smaller.
+        assert!(proof_sizes.get_multiproof_bytes() < proof_sizes.total_bytes);
+        assert!(proof_sizes.get_compression_ratio() > 1.0);
+        let report = format_proof_size_report(&proof_sizes);
+        assert!(report.contains(&format!("proofs                   {}", branches.len())));
+        assert!(report.contains(&format!("{} nodes, at tx index", expected_max_nodes)));
+    }
+
+    #[test]
+    fn should_format_empty_proof_size_report() {
+        let result = format_proof_size_report(&ProofSizes::new());
+        assert!(result.contains("No proofs generated"));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::audit_log::maybe_append_proof_bundles_to_audit_log;
+use crate::binary_envelope::maybe_compress_binary_proof_output;
+use crate::binary_proof::format_proof_bundles_as_binary;
+use crate::build_metadata::{get_build_metadata, set_build_metadata_in_proof_bundle};
+use crate::constants::HASHED_NULL_NODE;
+use crate::eip1186_proof::format_proof_bundles_as_eip1186;
+use crate::errors::AppError;
+use crate::finality::{maybe_check_block_finality, set_finality_in_proof_bundle};
+use crate::gas_estimate::{maybe_read_gas_model_file, maybe_set_gas_estimate_in_proof_bundle};
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::get_endpoint::get_endpoint;
+use crate::get_receipts_trie::get_block_and_receipts_trie;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::proof_bundle::{
+    get_hex_proof_from_proof_bundle, get_proof_bundle, get_proof_bundle_json,
+    maybe_compress_proof_in_proof_bundle, maybe_hash_value_in_proof_bundle, ProofBundle,
+};
+use crate::proof_expiry::{maybe_get_proof_expiry, set_proof_expiry_in_proof_bundle};
+use crate::proof_signing::{maybe_read_proof_signing_key, maybe_sign_proof_bundle};
+use crate::rlp_codec::decode_receipt;
+use crate::ssz_proof::format_proof_bundles_as_ssz;
+use crate::telemetry::TelemetryEvent;
+use crate::trie::Trie;
+use crate::trie_nodes::Node;
+use crate::types::{Block, Log, Receipt, Result};
+use crate::utils::{convert_h256_to_prefixed_hex, convert_hex_to_address, convert_hex_to_h256};
+use ethereum_types::{Address, H256};
+
+/**
+ * Log Filters:
+ *
+ * Proving every receipt in a block is wasteful when only some matter, e.g.
+ * those w/ a token's `Transfer` events. So `prove-block` takes `--topic0` &
+ * `--address` filters, & only proves the receipts w/ a log matching them all:
+ * emitted by that address, w/ that first topic. Each receipt is decoded from
+ * its leaf in the trie to check its logs, so this works for cached tries too.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    pub topic0: Option<H256>,
+    pub address: Option<Address>,
+}
+
+impl LogFilter {
+    pub fn from_cli_args(
+        maybe_topic0: &Option<String>,
+        maybe_address: &Option<String>,
+    ) -> Result<Option<Self>> {
+        if maybe_topic0.is_none() && maybe_address.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            topic0: maybe_topic0.clone().map(convert_hex_to_h256).transpose()?,
+            address: maybe_address
+                .clone()
+                .map(convert_hex_to_address)
+                .transpose()?,
+        }))
+    }
+
+    pub fn matches_log(&self, log: &Log) -> bool {
+        self.address.is_none_or(|address| log.address == address)
+            && self
+                .topic0
+                .is_none_or(|topic0| log.topics.first() == Some(&topic0))
+    }
+
+    pub fn matches_receipt(&self, receipt: &Receipt) -> bool {
+        receipt.logs.iter().any(|log| self.matches_log(log))
+    }
+}
+
+fn branch_matches_log_filter(branch: &[Node], maybe_filter: &Option<LogFilter>) -> Result<bool> {
+    match maybe_filter {
+        None => Ok(true),
+        Some(filter) => match branch.last().and_then(|node| node.get_value()) {
+            Some(value) => decode_receipt(&value).map(|receipt| filter.matches_receipt(&receipt)),
+            None => Err(AppError::Custom(
+                "✘ Branch has no receipt to filter!".to_string(),
+            )),
+        },
+    }
+}
+
+// NOTE: A block w/ no txs has an empty receipts trie, so its header's receipts root must be its root.
+fn check_empty_block_has_empty_receipts_root(block: &Block) -> Result<()> {
+    match block.receipts_root == HASHED_NULL_NODE {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Block {} has no transactions, but its receipts root isn't the empty trie's: {}",
+            block.number,
+            convert_h256_to_prefixed_hex(block.receipts_root)?
+        ))),
+    }
+}
+
+pub fn get_proof_bundles_for_matching_receipts(
+    block: &Block,
+    trie: &Trie,
+    maybe_filter: &Option<LogFilter>,
+) -> Result<Vec<ProofBundle>> {
+    if block.transactions.is_empty() {
+        check_empty_block_has_empty_receipts_root(block)?;
+        info!(
+            "✔ Block {} has no transactions, so has no receipts to prove!",
+            block.number
+        );
+        return Ok(Vec::new());
+    }
+    info!(
+        "✔ Pulling {} branches from trie...",
+        block.transactions.len()
+    );
+    let bundles = (0..block.transactions.len())
+        .map(|index| {
+            let branch = get_branch_from_trie(trie, index)?;
+            match branch_matches_log_filter(&branch, maybe_filter)? {
+                true => get_proof_bundle(block, trie.root, index, &branch).map(Some),
+                false => Ok(None),
+            }
+        })
+        .filter_map(Result::transpose)
+        .collect::<Result<Vec<ProofBundle>>>()?;
+    if maybe_filter.is_some() {
+        info!(
+            "✔ {} of {} receipts have logs matching the filter",
+            bundles.len(),
+            block.transactions.len()
+        );
+    }
+    Ok(bundles)
+}
+
+pub fn get_proof_bundles_for_all_receipts(block: &Block, trie: &Trie) -> Result<Vec<ProofBundle>> {
+    get_proof_bundles_for_matching_receipts(block, trie, &None)
+}
+
+pub fn format_proof_bundles(
+    bundles: &[ProofBundle],
+    format: OutputFormat,
+    light: bool,
+) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(
+            &bundles
+                .iter()
+                .map(|bundle| get_proof_bundle_json(bundle, light))
+                .collect::<Result<Vec<_>>>()?,
+        )?),
+        OutputFormat::Hex => Ok(bundles
+            .iter()
+            .map(get_hex_proof_from_proof_bundle)
+            .collect::<Result<Vec<_>>>()?
+            .join("\n")),
+        OutputFormat::Binary => format_proof_bundles_as_binary(bundles),
+        OutputFormat::Eip1186 => format_proof_bundles_as_eip1186(bundles),
+        OutputFormat::Ssz => format_proof_bundles_as_ssz(bundles),
+    }
+}
+
+pub fn prove_block(
+    cli_args: &CliArgs,
+    block_id: &str,
+    maybe_topic0: &Option<String>,
+    maybe_address: &Option<String>,
+) -> Result<String> {
+    let maybe_filter = LogFilter::from_cli_args(maybe_topic0, maybe_address)?;
+    let maybe_signing_key = maybe_read_proof_signing_key(&cli_args.sign_key, cli_args.sign_scheme)?;
+    let gas_model = maybe_read_gas_model_file(&cli_args.gas_model)?.unwrap_or_default();
+    let endpoint = get_endpoint(cli_args)?;
+    get_block_and_receipts_trie(&endpoint, block_id, cli_args)
+        .and_then(|(block, trie)| {
+            let maybe_finality = maybe_check_block_finality(&endpoint, &block, cli_args)?;
+            let maybe_build_metadata = match cli_args.light {
+                true => None,
+                false => Some(get_build_metadata(&endpoint, &trie)?),
+            };
+            get_proof_bundles_for_matching_receipts(&block, &trie, &maybe_filter)
+                .map(|bundles| (bundles, maybe_finality, maybe_build_metadata))
+        })
+        .and_then(|(bundles, maybe_finality, maybe_build_metadata)| {
+            let maybe_expiry = maybe_get_proof_expiry(&endpoint, cli_args)?;
+            bundles
+                .into_iter()
+                .map(|bundle| set_proof_expiry_in_proof_bundle(bundle, &maybe_expiry))
+                .map(|bundle| set_finality_in_proof_bundle(bundle, &maybe_finality))
+                .map(|bundle| set_build_metadata_in_proof_bundle(bundle, &maybe_build_metadata))
+                .map(|bundle| maybe_hash_value_in_proof_bundle(bundle, cli_args.value_hash))
+                .collect::<Result<Vec<ProofBundle>>>()
+        })
+        .and_then(|bundles| {
+            maybe_append_proof_bundles_to_audit_log(&cli_args.audit_log, &bundles)?;
+            // NOTE: Hex proofs have nowhere to flag compression, so only the others are compressed.
+            let compress = cli_args.compress_proofs && cli_args.format != OutputFormat::Hex;
+            let bundles = bundles
+                .into_iter()
+                .map(|bundle| maybe_compress_proof_in_proof_bundle(bundle, compress))
+                .map(|bundle| {
+                    bundle.and_then(|bundle| {
+                        maybe_set_gas_estimate_in_proof_bundle(bundle, &gas_model)
+                    })
+                })
+                .map(|bundle| {
+                    bundle.and_then(|bundle| maybe_sign_proof_bundle(bundle, &maybe_signing_key))
+                })
+                .collect::<Result<Vec<ProofBundle>>>()?;
+            format_proof_bundles(&bundles, cli_args.format, cli_args.light)
+                .and_then(|output| {
+                    maybe_compress_binary_proof_output(output, cli_args.format, cli_args.compress)
+                })
+                .inspect(|_| {
+                    cli_args
+                        .telemetry
+                        .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                            proof_type: "receipt",
+                            num_proofs: bundles.len(),
+                        })
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+    use crate::proof_bundle::LightProofBundle;
+    use crate::test_utils::{
+        get_expected_block, get_sample_proof_1, get_sample_receipts, get_sample_tx_hashes_1,
+        PROOF_1_INDEX, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::convert_hex_strings_to_h256s;
+    use crate::verify_proof::verify_proof_bundle;
+
+    fn get_sample_block_and_trie_1() -> (Block, Trie) {
+        let mut block = get_expected_block();
+        let tx_hashes = get_sample_tx_hashes_1();
+        let receipts =
+            get_sample_receipts(SAMPLE_RECEIPT_JSONS_1_PATH.to_string(), tx_hashes.clone());
+        block.transactions = convert_hex_strings_to_h256s(tx_hashes).unwrap();
+        (block, get_receipts_trie_from_receipts(&receipts).unwrap())
+    }
+
+    #[test]
+    fn should_get_verifiable_proof_bundles_for_all_receipts() {
+        let (block, trie) = get_sample_block_and_trie_1();
+        let result = get_proof_bundles_for_all_receipts(&block, &trie).unwrap();
+        assert!(result.len() == block.transactions.len());
+        result.iter().enumerate().for_each(|(index, bundle)| {
+            assert!(bundle.tx_index == index);
+            assert!(verify_proof_bundle(bundle).is_err());
+            // NOTE: The sample receipts aren't from the sample block, so its header can't anchor them.
+            verify_proof_bundle(&ProofBundle {
+                header: None,
+                ..bundle.clone()
+            })
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn should_only_get_proof_bundles_for_receipts_w_logs_matching_filter() {
+        let (block, trie) = get_sample_block_and_trie_1();
+        let receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let log = receipts
+            .iter()
+            .flat_map(|receipt| receipt.logs.iter())
+            .find(|log| !log.topics.is_empty())
+            .unwrap();
+        let filter = LogFilter {
+            topic0: Some(log.topics[0]),
+            address: Some(log.address),
+        };
+        let mut expected_indices = receipts
+            .iter()
+            .filter(|receipt| filter.matches_receipt(receipt))
+            .map(|receipt| receipt.transaction_index.as_usize())
+            .collect::<Vec<usize>>();
+        expected_indices.sort();
+        let result =
+            get_proof_bundles_for_matching_receipts(&block, &trie, &Some(filter.clone())).unwrap();
+        let no_match_filter = LogFilter {
+            address: Some(Address::repeat_byte(0xff)),
+            ..filter
+        };
+        let no_match_result =
+            get_proof_bundles_for_matching_receipts(&block, &trie, &Some(no_match_filter)).unwrap();
+        assert!(!expected_indices.is_empty() && expected_indices.len() < receipts.len());
+        assert!(
+            result
+                .iter()
+                .map(|bundle| bundle.tx_index)
+                .collect::<Vec<usize>>()
+                == expected_indices
+        );
+        assert!(no_match_result.is_empty());
+    }
+
+    #[test]
+    fn should_format_proof_bundles_as_hex_lines() {
+        let (block, trie) = get_sample_block_and_trie_1();
+        let bundles = get_proof_bundles_for_all_receipts(&block, &trie).unwrap();
+        let result = format_proof_bundles(&bundles, OutputFormat::Hex, false).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert!(lines.len() == bundles.len());
+        assert!(lines[PROOF_1_INDEX] == get_sample_proof_1());
+    }
+
+    #[test]
+    fn should_format_proof_bundles_as_json_array() {
+        let (block, trie) = get_sample_block_and_trie_1();
+        let bundles = get_proof_bundles_for_all_receipts(&block, &trie).unwrap();
+        let result = format_proof_bundles(&bundles, OutputFormat::Json, false).unwrap();
+        let parsed: Vec<ProofBundle> = serde_json::from_str(&result).unwrap();
+        assert!(parsed == bundles);
+    }
+
+    #[test]
+    fn should_format_proof_bundles_as_light_json_array() {
+        let (block, trie) = get_sample_block_and_trie_1();
+        let bundles = get_proof_bundles_for_all_receipts(&block, &trie).unwrap();
+        let result = format_proof_bundles(&bundles, OutputFormat::Json, true).unwrap();
+        let parsed: Vec<LightProofBundle> = serde_json::from_str(&result).unwrap();
+        assert!(parsed.len() == bundles.len());
+        assert!(parsed[PROOF_1_INDEX].proof == bundles[PROOF_1_INDEX].proof);
+    }
+
+    #[test]
+    fn should_get_no_proof_bundles_for_empty_block() {
+        let mut block = get_expected_block();
+        block.transactions = Vec::new();
+        block.receipts_root = HASHED_NULL_NODE;
+        let trie = get_receipts_trie_from_receipts(&[]).unwrap();
+        let result = get_proof_bundles_for_all_receipts(&block, &trie).unwrap();
+        assert!(result.is_empty());
+        assert!(format_proof_bundles(&result, OutputFormat::Json, false).unwrap() == "[]");
+    }
+
+    #[test]
+    fn should_fail_to_get_proof_bundles_for_empty_block_w_non_empty_receipts_root() {
+        let mut block = get_expected_block();
+        block.transactions = Vec::new();
+        let trie = get_receipts_trie_from_receipts(&[]).unwrap();
+        match get_proof_bundles_for_all_receipts(&block, &trie) {
+            Err(AppError::Custom(e)) => assert!(e.contains("has no transactions")),
+            _ => panic!("Should not get proof bundles for empty block w/ non-empty root!"),
+        }
+    }
+
+    #[test]
+    fn should_get_verifiable_proof_bundle_for_single_tx_block() {
+        let (mut block, _) = get_sample_block_and_trie_1();
+        let mut receipt = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .remove(0);
+        receipt.transaction_index = 0.into();
+        block.transactions = vec![receipt.transaction_hash];
+        let trie = get_receipts_trie_from_receipts(&[receipt]).unwrap();
+        let result = get_proof_bundles_for_all_receipts(&block, &trie).unwrap();
+        assert!(result.len() == 1);
+        verify_proof_bundle(&ProofBundle {
+            header: None,
+            ..result[0].clone()
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,437 @@
+use crate::errors::AppError;
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::nibble_utils::{get_common_prefix_nibbles, get_nibbles_from_bytes, Nibbles};
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::telemetry::TelemetryEvent;
+use crate::trie::Trie;
+use crate::trie_keys::{SecureKey, TrieKey};
+use crate::trie_nodes::NodeKind;
+use crate::types::{Byte, Bytes, Result};
+use crate::utils::{
+    convert_bytes_to_hex, convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex,
+    decode_prefixed_hex,
+};
+use serde_json::{json, Value as Json};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/**
+ * Proving Mappings:
+ *
+ * The trie needn't only hold receipts, so this anchors any off-chain dataset
+ * of hex key/value pairs in an MPT root, w/ proofs of its values in the same
+ * format as receipts' proofs. Mappings are read from a JSON file, either an
+ * object of keys to values or an array of `{"key": ..., "value": ...}`, or a
+ * CSV file of `key,value` lines, w/ an optional `key,value` header. Keys go
+ * in the trie as is, or, w/ `--secure`, by their keccak hash, as in state &
+ * storage tries. Keys must be unique & values non-empty, since an MPT holds
+ * one value per key & an empty value is no value at all. Like the RLP encoded
+ * tx indices of receipts tries, unhashed keys mustn't prefix one another, as
+ * the trie holds values only in leaves, never in branches, & so a requested
+ * key merely prefixing stored ones is refused, as it has no value. Nor does
+ * the trie inline nodes whose RLP is under 32 bytes in their parents, as an
+ * MPT does, so mappings of keys & values short enough to make any such node
+ * are refused, rather than anchored in a root no other MPT would compute.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingEntry {
+    key: String,
+    value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum MappingJson {
+    Object(BTreeMap<String, String>),
+    Array(Vec<MappingEntry>),
+}
+
+fn decode_mapping_hex(hex: &str, line: &str) -> Result<Bytes> {
+    decode_prefixed_hex(hex.trim().to_string())
+        .map_err(|_| AppError::Custom(format!("✘ Mapping has invalid hex in: {}", line)))
+}
+
+fn parse_mapping_json(contents: &str) -> Result<Vec<(String, String)>> {
+    match serde_json::from_str::<MappingJson>(contents).map_err(|_| {
+        AppError::Custom(
+            "✘ Expected a JSON object of keys to values, or an array of `{\"key\": ..., \"value\": ...}`!"
+                .to_string(),
+        )
+    })? {
+        MappingJson::Object(entries) => Ok(entries.into_iter().collect()),
+        MappingJson::Array(entries) => Ok(entries
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect()),
+    }
+}
+
+fn parse_mapping_csv(contents: &str) -> Result<Vec<(String, String)>> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .filter(|(i, line)| !(*i == 0 && line.eq_ignore_ascii_case("key,value")))
+        .map(
+            |(_, line)| match line.split(',').collect::<Vec<&str>>().as_slice() {
+                [key, value] => Ok((key.to_string(), value.to_string())),
+                _ => Err(AppError::Custom(format!(
+                    "✘ Expected a `key,value` CSV line, got: {}",
+                    line
+                ))),
+            },
+        )
+        .collect()
+}
+
+pub fn parse_mapping(contents: &str, is_csv: bool) -> Result<Vec<(Bytes, Bytes)>> {
+    let entries = match is_csv {
+        true => parse_mapping_csv(contents)?,
+        false => parse_mapping_json(contents)?,
+    };
+    let mut keys = HashSet::new();
+    entries
+        .iter()
+        .map(|(key, value)| {
+            let line = format!("{},{}", key, value);
+            let key = decode_mapping_hex(key, &line)?;
+            let value = decode_mapping_hex(value, &line)?;
+            if value.is_empty() {
+                return Err(AppError::Custom(format!(
+                    "✘ Mapping has an empty value in: {}",
+                    line
+                )));
+            }
+            match keys.insert(key.clone()) {
+                true => Ok((key, value)),
+                false => Err(AppError::Custom(format!(
+                    "✘ Mapping has a duplicate key: {}",
+                    convert_bytes_to_prefixed_hex(&key)
+                ))),
+            }
+        })
+        .collect()
+}
+
+fn get_mapping_key_nibbles(key: &[Byte], secure: bool) -> Result<Nibbles> {
+    match secure {
+        true => Ok(SecureKey::from_raw_key(key)?.to_nibbles()),
+        false => Ok(get_nibbles_from_bytes(key.to_vec())),
+    }
+}
+
+// NOTE: Sorted, any key prefixing another also prefixes the key right after it.
+fn check_mapping_keys_are_prefix_free(entries: &[(Bytes, Bytes)]) -> Result<()> {
+    let mut keys = entries.iter().map(|(key, _)| key).collect::<Vec<&Bytes>>();
+    keys.sort();
+    match keys.windows(2).find(|pair| pair[1].starts_with(pair[0])) {
+        Some(pair) => Err(AppError::Custom(format!(
+            "✘ Mapping key {} prefixes key {} - use `--secure` to hash them!",
+            convert_bytes_to_prefixed_hex(pair[0]),
+            convert_bytes_to_prefixed_hex(pair[1])
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn check_mapping_trie_has_no_inlinable_nodes(trie: Trie) -> Result<Trie> {
+    match trie.has_inlinable_nodes()? {
+        false => Ok(trie),
+        true => Err(AppError::Custom(
+            "✘ Mapping makes trie nodes under 32 bytes, which an MPT would inline rather than hash - use longer keys or values!"
+                .to_string(),
+        )),
+    }
+}
+
+pub fn build_mapping_trie(entries: &[(Bytes, Bytes)], secure: bool) -> Result<Trie> {
+    if !secure {
+        check_mapping_keys_are_prefix_free(entries)?;
+    }
+    entries
+        .iter()
+        .try_fold(Trie::get_new_trie()?, |trie, (key, value)| {
+            trie.put(get_mapping_key_nibbles(key, secure)?, value.clone())
+        })
+        .and_then(check_mapping_trie_has_no_inlinable_nodes)
+}
+
+// NOTE: Finding stops at a branch once the key's used up, else at the node whose path it diverges from.
+fn is_prefix_of_stored_key(trie: &Trie, nibbles: Nibbles) -> Result<bool> {
+    if trie.is_empty() {
+        return Ok(false);
+    }
+    trie.find(nibbles)
+        .and_then(|(_, found_stack, remaining_key)| match found_stack.last() {
+            Some(node) if node.get_type() == NodeKind::Branch => Ok(remaining_key.is_empty()),
+            Some(node) => get_common_prefix_nibbles(remaining_key, node.get_key()).map(
+                |(_, remaining_key, remaining_node_key)| {
+                    remaining_key.is_empty() && !remaining_node_key.is_empty()
+                },
+            ),
+            None => Ok(false),
+        })
+}
+
+pub fn get_mapping_proof(trie: &Trie, key: &[Byte], secure: bool) -> Result<Bytes> {
+    let nibbles = get_mapping_key_nibbles(key, secure)?;
+    match trie.contains_key(nibbles.clone())? {
+        true => trie
+            .find(nibbles)
+            .and_then(|(_, found_stack, _)| rlp_encode_node_stack(&found_stack)),
+        false => match is_prefix_of_stored_key(trie, nibbles)? {
+            true => Err(AppError::Custom(format!(
+                "✘ Mapping key {} only prefixes stored keys, so has no value to prove!",
+                convert_bytes_to_prefixed_hex(key)
+            ))),
+            false => Err(AppError::Custom(format!(
+                "✘ No value in mapping under key: {}",
+                convert_bytes_to_prefixed_hex(key)
+            ))),
+        },
+    }
+}
+
+fn get_mapping_proof_json(trie: &Trie, key: &[Byte], secure: bool) -> Result<Json> {
+    let proof = get_mapping_proof(trie, key, secure)?;
+    Ok(json!({
+        "key": convert_bytes_to_prefixed_hex(key),
+        "value": trie
+            .get(get_mapping_key_nibbles(key, secure)?)?
+            .map(|value| convert_bytes_to_prefixed_hex(&value)),
+        "proof": convert_bytes_to_hex(proof),
+    }))
+}
+
+pub fn format_mapping_proofs(
+    trie: &Trie,
+    keys: &[Bytes],
+    secure: bool,
+    format: OutputFormat,
+) -> Result<String> {
+    let root = convert_h256_to_prefixed_hex(trie.root)?;
+    match format {
+        OutputFormat::Hex => std::iter::once(Ok(root))
+            .chain(
+                keys.iter()
+                    .map(|key| get_mapping_proof(trie, key, secure).map(convert_bytes_to_hex)),
+            )
+            .collect::<Result<Vec<String>>>()
+            .map(|lines| lines.join("\n")),
+        OutputFormat::Json => Ok(json!({
+            "root": root,
+            "secure": secure,
+            "num_entries": trie.len()?,
+            "proofs": keys
+                .iter()
+                .map(|key| get_mapping_proof_json(trie, key, secure))
+                .collect::<Result<Vec<Json>>>()?,
+        })
+        .to_string()),
+        OutputFormat::Binary | OutputFormat::Eip1186 | OutputFormat::Ssz => Err(AppError::Custom(
+            "✘ Mapping proofs are only available in hex or JSON format!".to_string(),
+        )),
+    }
+}
+
+pub fn prove_mapping(
+    cli_args: &CliArgs,
+    path: &str,
+    hex_keys: &[String],
+    secure: bool,
+) -> Result<String> {
+    let is_csv = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let entries = parse_mapping(&fs::read_to_string(path)?, is_csv)?;
+    let keys = hex_keys
+        .iter()
+        .map(|key| decode_mapping_hex(key, key))
+        .collect::<Result<Vec<Bytes>>>()?;
+    let trie = build_mapping_trie(&entries, secure)?;
+    info!(
+        "✔ Built trie of {} mapping entries w/ root: {}",
+        entries.len(),
+        convert_h256_to_prefixed_hex(trie.root)?
+    );
+    format_mapping_proofs(&trie, &keys, secure, cli_args.format).inspect(|_| {
+        cli_args
+            .telemetry
+            .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                proof_type: "mapping",
+                num_proofs: keys.len(),
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlp_codec::Decoder;
+    use crate::verifier::verify_merkle_proof;
+    use ethereum_types::H256;
+
+    struct ReferenceKeccakHasher;
+
+    impl hash_db::Hasher for ReferenceKeccakHasher {
+        type Out = [u8; 32];
+        type StdHasher = hash256_std_hasher::Hash256StdHasher;
+        const LENGTH: usize = 32;
+
+        fn hash(bytes: &[u8]) -> Self::Out {
+            tiny_keccak::keccak256(bytes)
+        }
+    }
+
+    const SAMPLE_MAPPING_JSON: &str = "{\"0x01\": \"0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee\", \"0x0203\": \"0xdecaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0\", \"0xabcd\": \"0xbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeef\"}";
+
+    const SAMPLE_MAPPING_CSV: &str = "key,value\n0xabcd,0xbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeef\n\n0x01,0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee\n0x0203,0xdecaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0\n";
+
+    fn verify_mapping_proof(trie: &Trie, key: &[Byte], secure: bool) -> Bytes {
+        let proof = get_mapping_proof(trie, key, secure).unwrap();
+        let proof_nodes: Vec<Bytes> = Decoder::new(&proof)
+            .iter()
+            .map(|node| node.get_raw().to_vec())
+            .collect();
+        let key = get_mapping_key_nibbles(key, secure)
+            .and_then(|nibbles| nibbles.to_nibble_values())
+            .unwrap();
+        verify_merkle_proof(trie.root.as_fixed_bytes(), &key, &proof_nodes).unwrap()
+    }
+
+    #[test]
+    fn should_parse_same_mapping_from_json_object_array_or_csv() {
+        let array_json = "[{\"key\": \"abcd\", \"value\": \"beefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeef\"}, {\"key\": \"0x01\", \"value\": \"0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee\"}, {\"key\": \"0x0203\", \"value\": \"0xdecaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0\"}]";
+        let mut expected = parse_mapping(SAMPLE_MAPPING_JSON, false).unwrap();
+        let mut from_array = parse_mapping(array_json, false).unwrap();
+        let mut from_csv = parse_mapping(SAMPLE_MAPPING_CSV, true).unwrap();
+        [&mut expected, &mut from_array, &mut from_csv]
+            .iter_mut()
+            .for_each(|entries| entries.sort());
+        assert!(expected.len() == 3);
+        assert!(from_array == expected);
+        assert!(from_csv == expected);
+    }
+
+    #[test]
+    fn should_fail_to_parse_malformed_mappings() {
+        [
+            ("{\"0x01\": \"0x\"}", false),
+            ("[{\"key\": \"0x01\", \"value\": \"0x02\"}, {\"key\": \"01\", \"value\": \"0x03\"}]", false),
+            ("{\"0xzz\": \"0x01\"}", false),
+            ("[\"0x01\"]", false),
+            ("0x01,0x02,0x03", true),
+            ("0x01", true),
+        ]
+        .iter()
+        .for_each(|(contents, is_csv)| assert!(parse_mapping(contents, *is_csv).is_err()));
+    }
+
+    #[test]
+    fn should_build_mapping_trie_and_prove_its_values() {
+        let entries = parse_mapping(SAMPLE_MAPPING_JSON, false).unwrap();
+        [false, true].iter().for_each(|secure| {
+            let trie = build_mapping_trie(&entries, *secure).unwrap();
+            assert!(trie.len().unwrap() == entries.len());
+            entries.iter().for_each(|(key, value)| {
+                assert!(verify_mapping_proof(&trie, key, *secure) == *value);
+            });
+            assert!(get_mapping_proof(&trie, &[0xff], *secure).is_err());
+        });
+        let trie = build_mapping_trie(&entries, false).unwrap();
+        let secure_trie = build_mapping_trie(&entries, true).unwrap();
+        assert!(trie.root != secure_trie.root);
+    }
+
+    #[test]
+    fn should_only_build_mapping_trie_w_prefixing_keys_if_secure() {
+        let entries = parse_mapping("0x01,0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee\n0x0102,0xdecaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0", true).unwrap();
+        assert!(build_mapping_trie(&entries, false).is_err());
+        let trie = build_mapping_trie(&entries, true).unwrap();
+        entries.iter().for_each(|(key, value)| {
+            assert!(verify_mapping_proof(&trie, key, true) == *value);
+        });
+    }
+
+    #[test]
+    fn should_format_mapping_proofs_as_hex_or_json() {
+        let entries = parse_mapping(SAMPLE_MAPPING_JSON, false).unwrap();
+        let trie = build_mapping_trie(&entries, false).unwrap();
+        let keys = vec![vec![0xab, 0xcd], vec![0x01]];
+        let root = convert_h256_to_prefixed_hex(trie.root).unwrap();
+        let hex = format_mapping_proofs(&trie, &keys, false, OutputFormat::Hex).unwrap();
+        let lines = hex.lines().collect::<Vec<&str>>();
+        assert!(lines.len() == 3);
+        assert!(lines[0] == root);
+        let json: Json = serde_json::from_str(
+            &format_mapping_proofs(&trie, &keys, false, OutputFormat::Json).unwrap(),
+        )
+        .unwrap();
+        assert!(json["root"] == root);
+        assert!(json["num_entries"] == 3);
+        assert!(
+            json["proofs"][0]["value"]
+                == "0xbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeefbeef"
+        );
+        assert!(json["proofs"][1]["proof"] == lines[2]);
+        assert!(format_mapping_proofs(&trie, &keys, false, OutputFormat::Binary).is_err());
+    }
+
+    #[test]
+    fn should_not_prove_prefix_of_stored_key() {
+        let entries = parse_mapping(
+            "0x0102,0xdecaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0",
+            true,
+        )
+        .unwrap();
+        let trie = build_mapping_trie(&entries, false).unwrap();
+        assert!(verify_mapping_proof(&trie, &[0x01, 0x02], false) == entries[0].1);
+        assert!(get_mapping_proof(&trie, &[0x01], false).is_err());
+    }
+
+    #[test]
+    fn should_refuse_to_prove_key_prefixing_stored_keys() {
+        let entries = parse_mapping(
+            "0x1200,0xdecaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0decaf0\n0x1234,0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee",
+            true,
+        )
+        .unwrap();
+        let trie = build_mapping_trie(&entries, false).unwrap();
+        entries.iter().for_each(|(key, value)| {
+            assert!(verify_mapping_proof(&trie, key, false) == *value);
+        });
+        assert!(trie
+            .get(get_nibbles_from_bytes(vec![0x12]))
+            .unwrap()
+            .is_none());
+        assert!(is_prefix_of_stored_key(&trie, get_nibbles_from_bytes(vec![0x12])).unwrap());
+        assert!(!is_prefix_of_stored_key(&trie, get_nibbles_from_bytes(vec![0x13])).unwrap());
+        assert!(get_mapping_proof(&trie, &[0x12], false).is_err());
+        assert!(get_mapping_proof_json(&trie, &[0x12], false).is_err());
+        assert!(format_mapping_proofs(&trie, &[vec![0x12]], false, OutputFormat::Json).is_err());
+    }
+
+    #[test]
+    fn should_match_reference_root_or_refuse_mapping_w_inlinable_nodes() {
+        let short_entries =
+            parse_mapping("{\"0x0102\": \"0xaabb\", \"0x0304\": \"0xccdd\"}", false).unwrap();
+        assert!(build_mapping_trie(&short_entries, false).is_err());
+        let expected_short_secure_root =
+            triehash::sec_trie_root::<ReferenceKeccakHasher, _, _, _>(short_entries.clone());
+        assert!(
+            build_mapping_trie(&short_entries, true).unwrap().root
+                == H256::from(expected_short_secure_root)
+        );
+        let entries = parse_mapping(SAMPLE_MAPPING_JSON, false).unwrap();
+        let expected_root = triehash::trie_root::<ReferenceKeccakHasher, _, _, _>(entries.clone());
+        assert!(build_mapping_trie(&entries, false).unwrap().root == H256::from(expected_root));
+        let expected_secure_root =
+            triehash::sec_trie_root::<ReferenceKeccakHasher, _, _, _>(entries.clone());
+        assert!(
+            build_mapping_trie(&entries, true).unwrap().root == H256::from(expected_secure_root)
+        );
+    }
+}
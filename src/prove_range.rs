@@ -0,0 +1,678 @@
+use crate::block_pipeline::{run_block_pipeline, BlockPipelineStages};
+use crate::errors::AppError;
+use crate::get_block::get_block_by_block_id;
+use crate::get_endpoint::get_endpoint;
+use crate::get_receipts_trie::{
+    build_receipts_trie_of_block, get_receipts_of_block, maybe_get_cached_receipts_trie_of_block,
+};
+use crate::parse_cli_args::CliArgs;
+use crate::proof_bundle::{
+    get_proof_bundle_json, maybe_compress_proof_in_proof_bundle, maybe_hash_value_in_proof_bundle,
+};
+use crate::prove_block::{get_proof_bundles_for_matching_receipts, LogFilter};
+use crate::trie::Trie;
+use crate::trie_cache::TrieCache;
+use crate::trie_checkpoint::write_atomically;
+use crate::trusted_headers::{maybe_read_trusted_headers_file, TrustedHeaders};
+use crate::types::{Block, Receipt, Result};
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+pub const RANGE_CHECKPOINT_FILE_NAME: &str = "checkpoint.json";
+
+/**
+ * Range Proofs:
+ *
+ * `prove-range` proves the receipts of every block in a range, w/ the same
+ * log filters as `prove-block`, streaming each block's proofs out as soon as
+ * it's processed: either to a directory, as a JSON array per block w/ any
+ * proofs named by its number, or appended to an NDJSON file, one proof per
+ * line. Backfills over thousands of blocks can't afford to restart from
+ * scratch, so after each block a checkpoint records the next block to prove,
+ * the number of proofs of each block w/ any, & for NDJSON the file's length
+ * so far. Blocks w/o matching receipts, the bulk of a filtered backfill, go
+ * uncounted, keeping the checkpoint small. A rerun w/ `--resume` & the same
+ * range & filters resumes from it, first truncating any proofs of a half-
+ * written block, whilst a rerun w/o it starts over. The checkpoint is removed
+ * once the range is done. Blocks are proven w/ up to `--jobs` of them in
+ * flight at once, their tries built in parallel, but their proofs are still
+ * written in block order. Only the last `--max-tries-in-memory` tries built
+ * are cached in memory, older ones being spilled to the `--cache-dir`, or a
+ * temporary dir. That's not every trie in memory though: up to twice `--jobs`
+ * blocks are in flight at once, each w/ its receipts, or a trie being built,
+ * read back from disk, or proven from after its eviction, so up to that many
+ * more tries can be in memory. Jobs are capped at `--max-tries-in-memory`,
+ * so at worst three times that many tries are in memory at once.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RangeOutput {
+    Dir(String),
+    Ndjson(String),
+}
+
+impl RangeOutput {
+    pub fn from_cli_args(
+        maybe_out_dir: &Option<String>,
+        maybe_ndjson: &Option<String>,
+    ) -> Result<Self> {
+        match (maybe_out_dir, maybe_ndjson) {
+            (Some(out_dir), None) => Ok(Self::Dir(out_dir.clone())),
+            (None, Some(ndjson)) => Ok(Self::Ndjson(ndjson.clone())),
+            _ => Err(AppError::Custom(
+                "✘ Range proofs need exactly one of `--out-dir` or `--ndjson`!".to_string(),
+            )),
+        }
+    }
+
+    fn get_checkpoint_path(&self) -> String {
+        match self {
+            Self::Dir(dir) => Path::new(dir)
+                .join(RANGE_CHECKPOINT_FILE_NAME)
+                .to_string_lossy()
+                .to_string(),
+            Self::Ndjson(path) => format!("{}.{}", path, RANGE_CHECKPOINT_FILE_NAME),
+        }
+    }
+
+    fn get_path(&self) -> &str {
+        match self {
+            Self::Dir(path) | Self::Ndjson(path) => path,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeCheckpoint {
+    pub from: u64,
+    pub to: u64,
+    pub topic0: Option<String>,
+    pub address: Option<String>,
+    pub next_block: u64,
+    pub ndjson_length: u64,
+    #[serde(default)]
+    pub proof_counts: BTreeMap<u64, usize>,
+}
+
+impl RangeCheckpoint {
+    fn is_of_same_run(&self, other: &Self) -> bool {
+        self.from == other.from
+            && self.to == other.to
+            && self.topic0 == other.topic0
+            && self.address == other.address
+    }
+
+    pub fn get_num_proofs(&self) -> usize {
+        self.proof_counts.values().sum()
+    }
+}
+
+fn read_range_checkpoint(path: &str, expected: &RangeCheckpoint) -> Result<RangeCheckpoint> {
+    let checkpoint = fs::read_to_string(path)
+        .map_err(AppError::from)
+        .and_then(|json| Ok(serde_json::from_str::<RangeCheckpoint>(&json)?))
+        .map_err(|e| {
+            AppError::Custom(format!(
+                "✘ Could not resume from range checkpoint at {}!\n✘ {}",
+                path, e
+            ))
+        })?;
+    match checkpoint.is_of_same_run(expected) {
+        true => Ok(checkpoint),
+        false => Err(AppError::Custom(format!(
+            "✘ Range checkpoint at {} is of blocks {} to {} w/ other filters - rerun w/ those, or w/o `--resume` to start over!",
+            path, checkpoint.from, checkpoint.to
+        ))),
+    }
+}
+
+// NOTE: Resuming w/o a checkpoint starts from the first block, so `--resume` is safe to always pass.
+fn maybe_read_range_checkpoint(
+    path: &str,
+    expected: &RangeCheckpoint,
+    resume: bool,
+) -> Result<Option<RangeCheckpoint>> {
+    match (Path::new(path).exists(), resume) {
+        (false, _) => Ok(None),
+        (true, false) => {
+            warn!(
+                "✘ Ignoring range checkpoint at {} - pass `--resume` to resume from it!",
+                path
+            );
+            Ok(None)
+        }
+        (true, true) => read_range_checkpoint(path, expected).map(|checkpoint| {
+            info!(
+                "✔ Resuming range proofs after block {}, w/ {} proof(s) so far",
+                checkpoint.next_block.saturating_sub(1),
+                checkpoint.get_num_proofs()
+            );
+            Some(checkpoint)
+        }),
+    }
+}
+
+fn write_range_checkpoint(path: &str, checkpoint: &RangeCheckpoint) -> Result<()> {
+    write_atomically(path, |temp_path| {
+        Ok(fs::write(temp_path, serde_json::to_string(checkpoint)?)?)
+    })
+}
+
+fn prepare_range_output(
+    output: &RangeOutput,
+    maybe_checkpoint: &Option<RangeCheckpoint>,
+) -> Result<()> {
+    match output {
+        RangeOutput::Dir(dir) => Ok(fs::create_dir_all(dir)?),
+        RangeOutput::Ndjson(path) => {
+            if let Some(parent) = Path::new(path).parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(false)
+                .open(path)?;
+            // NOTE: Drops the proofs of any block written after the checkpoint, or all on a fresh run.
+            Ok(file.set_len(
+                maybe_checkpoint
+                    .as_ref()
+                    .map_or(0, |checkpoint| checkpoint.ndjson_length),
+            )?)
+        }
+    }
+}
+
+fn write_block_proofs(output: &RangeOutput, block_number: u64, proofs: &[Json]) -> Result<u64> {
+    match output {
+        RangeOutput::Dir(dir) => {
+            if !proofs.is_empty() {
+                let path = Path::new(dir).join(format!("{}.json", block_number));
+                write_atomically(&path.to_string_lossy(), |temp_path| {
+                    Ok(fs::write(temp_path, serde_json::to_string_pretty(proofs)?)?)
+                })?;
+            }
+            Ok(0)
+        }
+        RangeOutput::Ndjson(path) => {
+            let mut file = OpenOptions::new().append(true).open(path)?;
+            proofs
+                .iter()
+                .try_for_each(|proof| -> Result<()> { Ok(writeln!(file, "{}", proof)?) })?;
+            file.sync_data()?;
+            Ok(file.metadata()?.len())
+        }
+    }
+}
+
+pub fn prove_range_w_stages<S>(
+    from: u64,
+    to: u64,
+    maybe_topic0: &Option<String>,
+    maybe_address: &Option<String>,
+    output: &RangeOutput,
+    options: RangeOptions,
+    stages: &S,
+) -> Result<String>
+where
+    S: BlockPipelineStages<Proofs = Vec<Json>>,
+{
+    let checkpoint_path = output.get_checkpoint_path();
+    let mut checkpoint = RangeCheckpoint {
+        from,
+        to,
+        topic0: maybe_topic0.clone(),
+        address: maybe_address.clone(),
+        next_block: from,
+        ndjson_length: 0,
+        proof_counts: BTreeMap::new(),
+    };
+    let maybe_resumed = maybe_read_range_checkpoint(&checkpoint_path, &checkpoint, options.resume)?;
+    prepare_range_output(output, &maybe_resumed)?;
+    if let Some(resumed) = maybe_resumed {
+        checkpoint = resumed;
+    }
+    let block_numbers = checkpoint.next_block..=to;
+    run_block_pipeline(
+        stages,
+        block_numbers,
+        get_range_jobs(&options),
+        |block_number, proofs| {
+            info!(
+                "✔ Proved {} receipt(s) in block {}",
+                proofs.len(),
+                block_number
+            );
+            let ndjson_length = write_block_proofs(output, block_number, &proofs)?;
+            if !proofs.is_empty() {
+                checkpoint.proof_counts.insert(block_number, proofs.len());
+            }
+            checkpoint.next_block = block_number + 1;
+            checkpoint.ndjson_length = ndjson_length;
+            write_range_checkpoint(&checkpoint_path, &checkpoint)
+        },
+    )?;
+    fs::remove_file(&checkpoint_path).or_else(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    })?;
+    Ok(format!(
+        "✔ Proved {} receipt(s) in blocks {} to {} into: {}",
+        checkpoint.get_num_proofs(),
+        from,
+        to,
+        output.get_path()
+    ))
+}
+
+enum BlockReceipts {
+    Cached(Trie),
+    Fetched(Vec<Receipt>),
+}
+
+struct RangeStages<'a> {
+    cli_args: &'a CliArgs,
+    endpoint: String,
+    maybe_filter: Option<LogFilter>,
+    maybe_trusted_headers: Option<TrustedHeaders>,
+    trie_cache: Mutex<TrieCache>,
+}
+
+impl RangeStages<'_> {
+    fn lock_trie_cache(&self) -> Result<MutexGuard<'_, TrieCache>> {
+        self.trie_cache
+            .lock()
+            .map_err(|_| AppError::Custom("✘ Trie cache lock poisoned!".to_string()))
+    }
+}
+
+impl BlockPipelineStages for RangeStages<'_> {
+    type Block = Block;
+    type Receipts = (Block, BlockReceipts);
+    type Trie = Block;
+    type Proofs = Vec<Json>;
+
+    fn fetch_block(&self, block_number: u64) -> Result<Block> {
+        get_block_by_block_id(&self.endpoint, &block_number.to_string())
+    }
+
+    fn fetch_receipts(&self, block: Block) -> Result<(Block, BlockReceipts)> {
+        let receipts = match maybe_get_cached_receipts_trie_of_block(
+            &self.endpoint,
+            &block,
+            self.cli_args,
+            &self.maybe_trusted_headers,
+        )? {
+            Some(trie) => BlockReceipts::Cached(trie),
+            None => BlockReceipts::Fetched(get_receipts_of_block(
+                &self.endpoint,
+                &block,
+                self.cli_args,
+            )?),
+        };
+        Ok((block, receipts))
+    }
+
+    // NOTE: The trie is handed on via the cache, so only the last few built stay in memory.
+    fn build_trie(&self, (block, receipts): (Block, BlockReceipts)) -> Result<Block> {
+        let trie = match receipts {
+            BlockReceipts::Cached(trie) => trie,
+            BlockReceipts::Fetched(receipts) => {
+                build_receipts_trie_of_block(&block, &receipts, self.cli_args)?
+            }
+        };
+        self.lock_trie_cache()?.insert(block.hash, Arc::new(trie));
+        Ok(block)
+    }
+
+    fn extract_proofs(&self, block: Block) -> Result<Vec<Json>> {
+        let trie = self.lock_trie_cache()?.get(&block.hash).ok_or_else(|| {
+            AppError::Custom(format!(
+                "✘ Receipts trie of block {} was evicted w/o being spilled!",
+                block.number
+            ))
+        })?;
+        get_proof_bundles_for_matching_receipts(&block, &trie, &self.maybe_filter)?
+            .into_iter()
+            .map(|bundle| {
+                maybe_hash_value_in_proof_bundle(bundle, self.cli_args.value_hash)
+                    .and_then(|bundle| {
+                        maybe_compress_proof_in_proof_bundle(bundle, self.cli_args.compress_proofs)
+                    })
+                    .and_then(|bundle| get_proof_bundle_json(&bundle, self.cli_args.light))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeOptions {
+    pub jobs: usize,
+    pub max_tries_in_memory: usize,
+    pub resume: bool,
+}
+
+pub fn get_range_jobs(options: &RangeOptions) -> usize {
+    let max_jobs = options.max_tries_in_memory.max(1);
+    if options.jobs > max_jobs {
+        warn!(
+            "✘ Capping `--jobs` at {}, the `--max-tries-in-memory`, since each job holds a trie in memory!",
+            max_jobs
+        );
+    }
+    options.jobs.clamp(1, max_jobs)
+}
+
+// NOTE: Spilled tries go in the cache dir, where they're reusable, else in a dir for this run only.
+fn get_spill_dir(cli_args: &CliArgs) -> (String, bool) {
+    match &cli_args.cache_dir {
+        Some(cache_dir) => (cache_dir.clone(), false),
+        None => (
+            std::env::temp_dir()
+                .join(format!("rrpm-spilled-tries-{}", std::process::id()))
+                .to_string_lossy()
+                .to_string(),
+            true,
+        ),
+    }
+}
+
+pub fn prove_range(
+    cli_args: &CliArgs,
+    from: u64,
+    to: u64,
+    maybe_topic0: &Option<String>,
+    maybe_address: &Option<String>,
+    output: &RangeOutput,
+    options: RangeOptions,
+) -> Result<String> {
+    let (spill_dir, is_temp_spill_dir) = get_spill_dir(cli_args);
+    let stages = RangeStages {
+        cli_args,
+        endpoint: get_endpoint(cli_args)?,
+        maybe_filter: LogFilter::from_cli_args(maybe_topic0, maybe_address)?,
+        maybe_trusted_headers: maybe_read_trusted_headers_file(&cli_args.trusted_headers)?,
+        trie_cache: Mutex::new(TrieCache::new_w_spill_dir(
+            options.max_tries_in_memory,
+            &spill_dir,
+        )),
+    };
+    let result = prove_range_w_stages(
+        from,
+        to,
+        maybe_topic0,
+        maybe_address,
+        output,
+        options,
+        &stages,
+    );
+    if is_temp_spill_dir && Path::new(&spill_dir).exists() {
+        if let Err(e) = fs::remove_dir_all(&spill_dir) {
+            warn!("✘ Could not remove spilled tries at {}: {}", spill_dir, e);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_temp_range_output(name: &str, ndjson: bool) -> RangeOutput {
+        let path = std::env::temp_dir().join(format!("rrpm-range-{}", name));
+        let path = path.to_str().unwrap().to_string();
+        let _ = fs::remove_dir_all(&path);
+        let _ = fs::remove_file(&path);
+        match ndjson {
+            true => RangeOutput::Ndjson(path),
+            false => RangeOutput::Dir(path),
+        }
+    }
+
+    // NOTE: Odd blocks have no matching receipts, & even ones as many as their number.
+    fn get_sample_proofs(block_number: u64) -> Vec<Json> {
+        match block_number % 2 {
+            0 => (0..block_number)
+                .map(|tx_index| json!({ "block_number": block_number, "tx_index": tx_index }))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    struct SampleRangeStages {
+        interrupted_at: Option<u64>,
+        matching: bool,
+        fetched_block_numbers: Mutex<Vec<u64>>,
+    }
+
+    impl SampleRangeStages {
+        fn new(interrupted_at: Option<u64>, matching: bool) -> Self {
+            Self {
+                interrupted_at,
+                matching,
+                fetched_block_numbers: Mutex::new(vec![]),
+            }
+        }
+
+        fn get_fetched_block_numbers(&self) -> Vec<u64> {
+            self.fetched_block_numbers.lock().unwrap().clone()
+        }
+    }
+
+    impl BlockPipelineStages for SampleRangeStages {
+        type Block = u64;
+        type Receipts = u64;
+        type Trie = u64;
+        type Proofs = Vec<Json>;
+
+        fn fetch_block(&self, block_number: u64) -> Result<u64> {
+            self.fetched_block_numbers
+                .lock()
+                .unwrap()
+                .push(block_number);
+            Ok(block_number)
+        }
+
+        fn fetch_receipts(&self, block_number: u64) -> Result<u64> {
+            match self.interrupted_at == Some(block_number) {
+                true => Err(AppError::Custom("✘ Interrupted!".to_string())),
+                false => Ok(block_number),
+            }
+        }
+
+        fn build_trie(&self, block_number: u64) -> Result<u64> {
+            Ok(block_number)
+        }
+
+        fn extract_proofs(&self, block_number: u64) -> Result<Vec<Json>> {
+            match self.matching {
+                true => Ok(get_sample_proofs(block_number)),
+                false => Ok(vec![]),
+            }
+        }
+    }
+
+    fn get_range_options(resume: bool) -> RangeOptions {
+        RangeOptions {
+            jobs: 2,
+            max_tries_in_memory: 16,
+            resume,
+        }
+    }
+
+    fn read_range_output(output: &RangeOutput) -> Vec<(String, String)> {
+        match output {
+            RangeOutput::Ndjson(path) => vec![(path.clone(), fs::read_to_string(path).unwrap())],
+            RangeOutput::Dir(dir) => {
+                let mut files = fs::read_dir(dir)
+                    .unwrap()
+                    .map(|entry| {
+                        let path = entry.unwrap().path();
+                        (
+                            path.file_name().unwrap().to_str().unwrap().to_string(),
+                            fs::read_to_string(path).unwrap(),
+                        )
+                    })
+                    .collect::<Vec<(String, String)>>();
+                files.sort();
+                files
+            }
+        }
+    }
+
+    fn remove_range_output(output: &RangeOutput) {
+        match output {
+            RangeOutput::Dir(dir) => fs::remove_dir_all(dir).unwrap(),
+            RangeOutput::Ndjson(path) => fs::remove_file(path).unwrap(),
+        }
+    }
+
+    fn assert_interrupted_range_resumes(name: &str, ndjson: bool) {
+        let expected_output = get_temp_range_output(&format!("{}-expected", name), ndjson);
+        let output = get_temp_range_output(name, ndjson);
+        let (from, to, interrupted_at) = (1, 8, 5);
+        let stages = SampleRangeStages::new(None, true);
+        let options = get_range_options(false);
+        prove_range_w_stages(from, to, &None, &None, &expected_output, options, &stages).unwrap();
+        let stages = SampleRangeStages::new(Some(interrupted_at), true);
+        let interrupted_result =
+            prove_range_w_stages(from, to, &None, &None, &output, options, &stages);
+        let interrupted_checkpoint: RangeCheckpoint =
+            serde_json::from_str(&fs::read_to_string(output.get_checkpoint_path()).unwrap())
+                .unwrap();
+        // NOTE: As if interrupted midway through writing a block's proofs.
+        if let RangeOutput::Ndjson(path) = &output {
+            let mut file = OpenOptions::new().append(true).open(path).unwrap();
+            write!(file, "{{\"block_number\":").unwrap();
+        }
+        let stages = SampleRangeStages::new(None, true);
+        let options = get_range_options(true);
+        let resumed_result =
+            prove_range_w_stages(from, to, &None, &None, &output, options, &stages);
+        let resumed_block_numbers = stages.get_fetched_block_numbers();
+        let checkpoint_removed = !Path::new(&output.get_checkpoint_path()).exists();
+        let (expected, result) = (
+            read_range_output(&expected_output),
+            read_range_output(&output),
+        );
+        remove_range_output(&expected_output);
+        remove_range_output(&output);
+        assert!(interrupted_result.is_err());
+        assert!(interrupted_checkpoint.next_block == interrupted_at);
+        assert!(interrupted_checkpoint.proof_counts == BTreeMap::from([(2, 2), (4, 4)]));
+        // NOTE: The count includes the proofs written before the interruption.
+        assert!(resumed_result.unwrap().contains("Proved 20 receipt(s)"));
+        assert!(resumed_block_numbers == (interrupted_at..=to).collect::<Vec<u64>>());
+        assert!(checkpoint_removed);
+        assert!(result
+            .iter()
+            .map(|(_, contents)| contents)
+            .eq(expected.iter().map(|(_, contents)| contents)));
+    }
+
+    #[test]
+    fn should_resume_interrupted_range_proofs_to_ndjson() {
+        assert_interrupted_range_resumes("ndjson", true);
+    }
+
+    #[test]
+    fn should_resume_interrupted_range_proofs_to_dir() {
+        assert_interrupted_range_resumes("dir", false);
+    }
+
+    #[test]
+    fn should_cap_range_jobs_at_max_tries_in_memory() {
+        let options = RangeOptions {
+            jobs: 32,
+            max_tries_in_memory: 16,
+            resume: false,
+        };
+        assert!(get_range_jobs(&options) == 16);
+        assert!(get_range_jobs(&get_range_options(false)) == 2);
+    }
+
+    #[test]
+    fn should_start_over_w_o_resume_despite_checkpoint() {
+        let output = get_temp_range_output("start-over", true);
+        let stages = SampleRangeStages::new(Some(3), true);
+        let _ = prove_range_w_stages(
+            1,
+            4,
+            &None,
+            &None,
+            &output,
+            get_range_options(false),
+            &stages,
+        );
+        let stages = SampleRangeStages::new(None, true);
+        let result = prove_range_w_stages(
+            1,
+            4,
+            &None,
+            &None,
+            &output,
+            get_range_options(false),
+            &stages,
+        );
+        let block_numbers = stages.get_fetched_block_numbers();
+        let num_lines = fs::read_to_string(output.get_path())
+            .unwrap()
+            .lines()
+            .count();
+        remove_range_output(&output);
+        assert!(result.unwrap().contains("Proved 6 receipt(s)"));
+        assert!(block_numbers == vec![1, 2, 3, 4]);
+        assert!(num_lines == 6);
+    }
+
+    #[test]
+    fn should_not_resume_from_checkpoint_of_different_filters() {
+        let output = get_temp_range_output("filters", true);
+        let stages = SampleRangeStages::new(Some(3), true);
+        let _ = prove_range_w_stages(
+            1,
+            4,
+            &None,
+            &None,
+            &output,
+            get_range_options(false),
+            &stages,
+        );
+        let topic0 = Some(format!("0x{}", "dd".repeat(32)));
+        let stages = SampleRangeStages::new(None, false);
+        let resumed_result = prove_range_w_stages(
+            1,
+            4,
+            &topic0,
+            &None,
+            &output,
+            get_range_options(true),
+            &stages,
+        );
+        let resumed_block_numbers = stages.get_fetched_block_numbers();
+        let stages = SampleRangeStages::new(None, false);
+        prove_range_w_stages(
+            1,
+            4,
+            &topic0,
+            &None,
+            &output,
+            get_range_options(false),
+            &stages,
+        )
+        .unwrap();
+        let block_numbers = stages.get_fetched_block_numbers();
+        let contents = fs::read_to_string(output.get_path()).unwrap();
+        remove_range_output(&output);
+        match resumed_result {
+            Err(AppError::Custom(message)) => assert!(message.contains("other filters")),
+            _ => panic!("Should not resume from checkpoint of different filters!"),
+        }
+        assert!(resumed_block_numbers.is_empty());
+        assert!(block_numbers == vec![1, 2, 3, 4]);
+        assert!(contents.is_empty());
+    }
+}
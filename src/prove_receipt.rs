@@ -0,0 +1,364 @@
+use crate::audit_log::maybe_append_proof_to_audit_log;
+use crate::binary_envelope::maybe_compress_binary_proof_output;
+use crate::binary_proof::format_proof_bundles_as_binary;
+use crate::build_metadata::{
+    get_build_metadata, set_build_metadata_in_proof_bundle, BuildMetadata,
+};
+use crate::connect_to_node::connect_to_node;
+use crate::cross_check::maybe_cross_check_block_and_receipts_trie_in_state;
+use crate::eip1186_proof::format_proof_bundles_as_eip1186;
+use crate::errors::AppError;
+use crate::finality::{maybe_check_block_finality, set_finality_in_proof_bundle, Finality};
+use crate::gas_estimate::maybe_set_gas_estimate_in_proof_bundle;
+use crate::get_block::{get_block_by_block_id, get_block_from_tx_hash_in_state_and_set_in_state};
+use crate::get_branch_from_trie::get_branch_from_trie_and_put_in_state;
+use crate::get_endpoint::{get_endpoint, get_endpoint_and_set_in_state};
+use crate::get_hex_proof_from_branch::get_hex_proof_from_branch_in_state;
+use crate::get_receipts::get_all_receipts_from_block_in_state_and_set_in_state;
+use crate::get_receipts_trie::get_receipts_trie_and_set_in_state;
+use crate::get_tx_index::get_tx_index_and_add_to_state;
+use crate::initialize_state_from_cli_args::initialize_state_from_cli_args;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::proof_bundle::{
+    get_full_proof, get_proof_bundle_from_state, get_proof_bundle_json,
+    maybe_compress_proof_in_proof_bundle, maybe_hash_value_in_proof_bundle, rlp_encode_full_proof,
+    FullProof,
+};
+use crate::proof_expiry::{maybe_get_proof_expiry, set_proof_expiry_in_proof_bundle, ProofExpiry};
+use crate::proof_formats::{stamp_format_version, ProofFormatKind};
+use crate::proof_signing::maybe_sign_proof_bundle;
+use crate::receipt_assertions::{
+    maybe_apply_assertions_to_proof_bundle, maybe_read_assertions_file, Assertions,
+};
+use crate::ssz_proof::format_proof_bundles_as_ssz;
+use crate::state::State;
+use crate::telemetry::TelemetryEvent;
+use crate::trusted_headers::maybe_check_block_in_state_against_trusted_headers;
+use crate::types::{Block, Result};
+use crate::utils::{convert_bytes_to_hex, convert_h256_to_prefixed_hex, convert_hex_to_h256};
+use crate::verify_proof::verify_full_proof;
+
+// NOTE: What's known of the proof's block beyond the proof itself, for full JSON bundles to carry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct ProofMetadata {
+    maybe_expiry: Option<ProofExpiry>,
+    maybe_finality: Option<Finality>,
+    maybe_build_metadata: Option<BuildMetadata>,
+}
+
+fn format_proof_in_state(
+    state: State,
+    format: OutputFormat,
+    light: bool,
+    value_hash: bool,
+    compress: bool,
+    metadata: &ProofMetadata,
+    maybe_assertions: &Option<Assertions>,
+) -> Result<String> {
+    let maybe_signing_key = state.proof_signing_key.clone();
+    let gas_model = state.gas_model.clone().unwrap_or_default();
+    match format {
+        // NOTE: Hex proofs can't record assertions' results, but are still gated on them.
+        OutputFormat::Hex => match maybe_assertions {
+            Some(_) => get_proof_bundle_from_state(&state)
+                .and_then(|bundle| maybe_apply_assertions_to_proof_bundle(bundle, maybe_assertions))
+                .and_then(|_| get_hex_proof_from_branch_in_state(state)),
+            None => get_hex_proof_from_branch_in_state(state),
+        },
+        OutputFormat::Json => get_proof_bundle_from_state(&state)
+            .map(|bundle| set_proof_expiry_in_proof_bundle(bundle, &metadata.maybe_expiry))
+            .map(|bundle| set_finality_in_proof_bundle(bundle, &metadata.maybe_finality))
+            .map(|bundle| {
+                set_build_metadata_in_proof_bundle(bundle, &metadata.maybe_build_metadata)
+            })
+            .and_then(|bundle| maybe_apply_assertions_to_proof_bundle(bundle, maybe_assertions))
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, value_hash))
+            .and_then(|bundle| maybe_compress_proof_in_proof_bundle(bundle, compress))
+            .and_then(|bundle| maybe_set_gas_estimate_in_proof_bundle(bundle, &gas_model))
+            .and_then(|bundle| maybe_sign_proof_bundle(bundle, &maybe_signing_key))
+            .and_then(|bundle| get_proof_bundle_json(&bundle, light))
+            .and_then(|json| Ok(serde_json::to_string_pretty(&json)?)),
+        OutputFormat::Binary => get_proof_bundle_from_state(&state)
+            .and_then(|bundle| maybe_apply_assertions_to_proof_bundle(bundle, maybe_assertions))
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, value_hash))
+            .and_then(|bundle| maybe_compress_proof_in_proof_bundle(bundle, compress))
+            .and_then(|bundle| format_proof_bundles_as_binary(&[bundle])),
+        OutputFormat::Eip1186 => get_proof_bundle_from_state(&state)
+            .and_then(|bundle| maybe_apply_assertions_to_proof_bundle(bundle, maybe_assertions))
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, value_hash))
+            .and_then(|bundle| maybe_compress_proof_in_proof_bundle(bundle, compress))
+            .and_then(|bundle| format_proof_bundles_as_eip1186(&[bundle])),
+        OutputFormat::Ssz => get_proof_bundle_from_state(&state)
+            .and_then(|bundle| maybe_apply_assertions_to_proof_bundle(bundle, maybe_assertions))
+            .and_then(|bundle| maybe_hash_value_in_proof_bundle(bundle, value_hash))
+            .and_then(|bundle| maybe_compress_proof_in_proof_bundle(bundle, compress))
+            .and_then(|bundle| format_proof_bundles_as_ssz(&[bundle])),
+    }
+}
+
+fn format_full_proof(full_proof: &FullProof, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Hex => rlp_encode_full_proof(full_proof).map(convert_bytes_to_hex),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&stamp_format_version(
+            serde_json::to_value(full_proof)?,
+            ProofFormatKind::Log,
+        )?)?),
+        OutputFormat::Binary | OutputFormat::Eip1186 | OutputFormat::Ssz => Err(AppError::Custom(
+            "✘ Full proofs are only available in hex or JSON format!".to_string(),
+        )),
+    }
+}
+
+fn get_state_with_receipt_branch(cli_args: &CliArgs, tx_hash: &str) -> Result<State> {
+    initialize_state_from_cli_args(cli_args, tx_hash)
+        .and_then(get_endpoint_and_set_in_state)
+        .and_then(connect_to_node)
+        .and_then(get_block_from_tx_hash_in_state_and_set_in_state)
+        .and_then(get_all_receipts_from_block_in_state_and_set_in_state)
+        .and_then(get_tx_index_and_add_to_state)
+        .and_then(get_receipts_trie_and_set_in_state)
+        .and_then(maybe_cross_check_block_and_receipts_trie_in_state)
+        .and_then(maybe_check_block_in_state_against_trusted_headers)
+        .and_then(get_branch_from_trie_and_put_in_state)
+        .and_then(maybe_append_proof_to_audit_log)
+}
+
+// NOTE: W/ the block & index known, the receipt needn't be fetched by tx hash to find them.
+fn get_state_with_receipt_branch_at_index(
+    cli_args: &CliArgs,
+    block_id: &str,
+    tx_index: usize,
+) -> Result<State> {
+    let endpoint = get_endpoint(cli_args)?;
+    let block = get_block_by_block_id(&endpoint, block_id)?;
+    let tx_hash = get_tx_hash_at_index(&block, tx_index)?;
+    initialize_state_from_cli_args(cli_args, &tx_hash)
+        .and_then(|state| state.set_block_in_state(block))
+        .and_then(|state| state.set_index_in_state(tx_index))
+        .and_then(get_all_receipts_from_block_in_state_and_set_in_state)
+        .and_then(get_receipts_trie_and_set_in_state)
+        .and_then(maybe_cross_check_block_and_receipts_trie_in_state)
+        .and_then(maybe_check_block_in_state_against_trusted_headers)
+        .and_then(get_branch_from_trie_and_put_in_state)
+        .and_then(maybe_append_proof_to_audit_log)
+}
+
+fn get_tx_hash_at_index(block: &Block, tx_index: usize) -> Result<String> {
+    match block.transactions.get(tx_index) {
+        Some(tx_hash) => convert_h256_to_prefixed_hex(*tx_hash),
+        None if block.transactions.is_empty() => Err(AppError::Custom(format!(
+            "✘ Block {} has no transactions, so has no receipts to prove!",
+            block.number
+        ))),
+        None => Err(AppError::Custom(format!(
+            "✘ No transaction at index {} in block {}, which has {}!",
+            tx_index,
+            block.number,
+            block.transactions.len()
+        ))),
+    }
+}
+
+fn format_receipt_proof_in_state(
+    cli_args: &CliArgs,
+    state: State,
+    maybe_assertions: &Option<Assertions>,
+) -> Result<String> {
+    let endpoint = state.get_endpoint_from_state()?;
+    let metadata = ProofMetadata {
+        maybe_finality: maybe_check_block_finality(
+            endpoint,
+            state.get_block_from_state()?,
+            cli_args,
+        )?,
+        maybe_expiry: maybe_get_proof_expiry(endpoint, cli_args)?,
+        // NOTE: Only full JSON bundles have a `metadata` section, so only they walk the trie for it.
+        maybe_build_metadata: match cli_args.format == OutputFormat::Json && !cli_args.light {
+            true => Some(get_build_metadata(
+                endpoint,
+                state.get_receipts_trie_from_state()?,
+            )?),
+            false => None,
+        },
+    };
+    format_proof_in_state(
+        state,
+        cli_args.format,
+        cli_args.light,
+        cli_args.value_hash,
+        cli_args.compress_proofs,
+        &metadata,
+        maybe_assertions,
+    )
+    .and_then(|output| {
+        maybe_compress_binary_proof_output(output, cli_args.format, cli_args.compress)
+    })
+    .inspect(|_| {
+        cli_args
+            .telemetry
+            .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                proof_type: "receipt",
+                num_proofs: 1,
+            })
+    })
+}
+
+pub fn prove_receipt(cli_args: &CliArgs, tx_hash: &str) -> Result<String> {
+    let maybe_assertions = maybe_read_assertions_file(&cli_args.assertions)?;
+    get_state_with_receipt_branch(cli_args, tx_hash)
+        .and_then(|state| format_receipt_proof_in_state(cli_args, state, &maybe_assertions))
+}
+
+pub fn prove_index(cli_args: &CliArgs, block_id: &str, tx_index: usize) -> Result<String> {
+    let maybe_assertions = maybe_read_assertions_file(&cli_args.assertions)?;
+    get_state_with_receipt_branch_at_index(cli_args, block_id, tx_index)
+        .and_then(|state| format_receipt_proof_in_state(cli_args, state, &maybe_assertions))
+}
+
+pub fn prove_log(cli_args: &CliArgs, tx_hash: &str, log_index: usize) -> Result<String> {
+    let state = get_state_with_receipt_branch(cli_args, tx_hash)?;
+    maybe_check_block_finality(
+        state.get_endpoint_from_state()?,
+        state.get_block_from_state()?,
+        cli_args,
+    )?;
+    let bundle = get_proof_bundle_from_state(&state)?;
+    get_full_proof(&bundle, log_index)
+        .and_then(|full_proof| {
+            verify_full_proof(
+                &convert_hex_to_h256(bundle.block_hash.clone())?,
+                &full_proof,
+            )?;
+            info!("✔ Full proof verified against block hash!");
+            format_full_proof(&full_proof, cli_args.format)
+        })
+        .inspect(|_| {
+            cli_args
+                .telemetry
+                .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                    proof_type: "log",
+                    num_proofs: 1,
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_bundle::{LightProofBundle, ProofBundle};
+    use crate::proof_signing::{ProofSigningKey, SignatureScheme};
+    use crate::test_utils::{
+        get_expected_block, get_sample_proof_1, get_sample_tx_hashes_1,
+        get_valid_state_with_receipts_trie_index_and_branch, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    fn get_sample_state_with_block_and_branch() -> State {
+        get_valid_state_with_receipts_trie_index_and_branch(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .and_then(|state| state.set_block_in_state(get_expected_block()))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_format_proof_in_state_as_hex() {
+        let state = get_sample_state_with_block_and_branch();
+        let result = format_proof_in_state(
+            state,
+            OutputFormat::Hex,
+            false,
+            false,
+            false,
+            &ProofMetadata::default(),
+            &None,
+        )
+        .unwrap();
+        assert!(result == get_sample_proof_1());
+    }
+
+    #[test]
+    fn should_format_proof_in_state_as_json() {
+        let state = get_sample_state_with_block_and_branch();
+        let result = format_proof_in_state(
+            state,
+            OutputFormat::Json,
+            false,
+            false,
+            false,
+            &ProofMetadata::default(),
+            &None,
+        )
+        .unwrap();
+        let bundle: ProofBundle = serde_json::from_str(&result).unwrap();
+        assert!(bundle.tx_index == 14);
+    }
+
+    #[test]
+    fn should_sign_json_proof_w_signing_key_in_state() {
+        let key = ProofSigningKey::from_bytes(SignatureScheme::Ed25519, &[0x2a; 32]).unwrap();
+        let state = get_sample_state_with_block_and_branch()
+            .set_proof_signing_key_in_state(key.clone())
+            .unwrap();
+        let result = format_proof_in_state(
+            state,
+            OutputFormat::Json,
+            false,
+            false,
+            false,
+            &ProofMetadata::default(),
+            &None,
+        )
+        .unwrap();
+        let bundle: ProofBundle = serde_json::from_str(&result).unwrap();
+        assert!(bundle.signature.unwrap().public_key == key.get_public_key());
+    }
+
+    #[test]
+    fn should_format_full_proof_as_hex_and_json() {
+        let state = get_sample_state_with_block_and_branch();
+        let bundle = get_proof_bundle_from_state(&state).unwrap();
+        let full_proof = get_full_proof(&bundle, 1).unwrap();
+        let hex = format_full_proof(&full_proof, OutputFormat::Hex).unwrap();
+        assert!(hex == convert_bytes_to_hex(rlp_encode_full_proof(&full_proof).unwrap()));
+        let json = format_full_proof(&full_proof, OutputFormat::Json).unwrap();
+        assert!(serde_json::from_str::<FullProof>(&json).unwrap() == full_proof);
+    }
+
+    #[test]
+    fn should_get_tx_hash_at_index_in_block() {
+        let block = get_expected_block();
+        let result = get_tx_hash_at_index(&block, 1).unwrap();
+        assert!(result == convert_h256_to_prefixed_hex(block.transactions[1]).unwrap());
+        match get_tx_hash_at_index(&block, block.transactions.len()) {
+            Err(AppError::Custom(e)) => assert!(e.contains("No transaction at index")),
+            _ => panic!("Should not get tx hash at index beyond block's txs!"),
+        }
+        let empty_block = Block {
+            transactions: Vec::new(),
+            ..block
+        };
+        match get_tx_hash_at_index(&empty_block, 0) {
+            Err(AppError::Custom(e)) => assert!(e.contains("has no transactions")),
+            _ => panic!("Should not get tx hash in block w/o txs!"),
+        }
+    }
+
+    #[test]
+    fn should_format_proof_in_state_as_light_json() {
+        let state = get_sample_state_with_block_and_branch();
+        let result = format_proof_in_state(
+            state,
+            OutputFormat::Json,
+            true,
+            false,
+            false,
+            &ProofMetadata::default(),
+            &None,
+        )
+        .unwrap();
+        let bundle: LightProofBundle = serde_json::from_str(&result).unwrap();
+        assert!(bundle.tx_index == 14);
+        assert!(!result.contains("\"value\""));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::constants::HASHED_NULL_NODE;
+use crate::errors::AppError;
+use crate::get_block::get_block_by_block_id;
+use crate::get_endpoint::get_endpoint;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::get_rpc_call_jsons::get_proof_json;
+use crate::json_rpc_client::call_json_rpc;
+use crate::make_rpc_call::{check_response_text, deserialize_to_account_proof_rpc_response};
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::proof_bundle::{rlp_encode_proof_nodes, StorageProofBundle};
+use crate::proof_formats::{stamp_format_version, ProofFormatKind};
+use crate::rlp_codec::{rlp_encode_account, rlp_encode_storage_value};
+use crate::telemetry::TelemetryEvent;
+use crate::trie_keys::{StateKey, StorageKey, TrieKey};
+use crate::trusted_headers::{
+    maybe_check_block_against_trusted_headers, maybe_read_trusted_headers_file,
+};
+use crate::types::{AccountProofJson, Block, Result, StorageProofJson};
+use crate::utils::{
+    convert_bytes_to_hex, convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex,
+    convert_hex_to_address, convert_hex_to_h256, convert_hex_to_left_padded_h256,
+    convert_hex_to_u256,
+};
+use crate::verify_proof::verify_storage_proof_bundle;
+use ethereum_types::H256;
+
+pub fn get_account_proof(
+    endpoint: &str,
+    address: &str,
+    slot: &H256,
+    block: &Block,
+) -> Result<AccountProofJson> {
+    info!("✔ Getting proof of slot {:?} of account: {}", slot, address);
+    get_proof_json(
+        address,
+        &convert_h256_to_prefixed_hex(*slot)?,
+        format!("0x{:x}", block.number),
+    )
+    .and_then(|rpc_json| call_json_rpc(endpoint, rpc_json))
+    .and_then(check_response_text)
+    .and_then(deserialize_to_account_proof_rpc_response)
+    .map(|res| res.result)
+}
+
+fn get_storage_proof_for_slot<'a>(
+    account_proof: &'a AccountProofJson,
+    slot: &H256,
+) -> Result<&'a StorageProofJson> {
+    account_proof
+        .storageProof
+        .iter()
+        .find(|storage_proof| {
+            convert_hex_to_left_padded_h256(storage_proof.key.clone()).ok() == Some(*slot)
+        })
+        .ok_or_else(|| {
+            AppError::Custom(format!(
+                "✘ No storage proof for slot {:?} in `eth_getProof` response!",
+                slot
+            ))
+        })
+}
+
+fn get_account_value(account_proof: &AccountProofJson) -> Result<String> {
+    let nonce = convert_hex_to_u256(account_proof.nonce.clone())?;
+    let balance = convert_hex_to_u256(account_proof.balance.clone())?;
+    let storage_root = convert_hex_to_h256(account_proof.storageHash.clone())?;
+    let code_hash = convert_hex_to_h256(account_proof.codeHash.clone())?;
+    // NOTE: Empty accounts aren't in the state trie, so their proof is one of absence.
+    match nonce.is_zero()
+        && balance.is_zero()
+        && storage_root == HASHED_NULL_NODE
+        && code_hash == keccak_hash_bytes(&[])?
+    {
+        true => Ok("0x".to_string()),
+        false => rlp_encode_account(&nonce, &balance, &storage_root, &code_hash)
+            .map(|rlp_encoded_account| convert_bytes_to_prefixed_hex(&rlp_encoded_account)),
+    }
+}
+
+pub fn get_storage_proof_bundle(
+    block: &Block,
+    account_proof: &AccountProofJson,
+    slot: &H256,
+) -> Result<StorageProofBundle> {
+    let storage_proof = get_storage_proof_for_slot(account_proof, slot)?;
+    let storage_value = convert_hex_to_u256(storage_proof.value.clone())?;
+    Ok(StorageProofBundle {
+        block_number: block.number.as_u64(),
+        block_hash: convert_h256_to_prefixed_hex(block.hash)?,
+        state_root: convert_h256_to_prefixed_hex(block.state_root)?,
+        address: account_proof.address.to_lowercase(),
+        account_key: convert_bytes_to_prefixed_hex(
+            StateKey::from_address(&convert_hex_to_address(account_proof.address.clone())?)?
+                .as_bytes(),
+        ),
+        account_value: get_account_value(account_proof)?,
+        account_proof: account_proof.accountProof.clone(),
+        slot: convert_h256_to_prefixed_hex(*slot)?,
+        storage_root: account_proof.storageHash.clone(),
+        storage_key: convert_bytes_to_prefixed_hex(StorageKey::from_slot(slot)?.as_bytes()),
+        storage_value: match storage_value.is_zero() {
+            true => "0x".to_string(),
+            false => convert_bytes_to_prefixed_hex(&rlp_encode_storage_value(&storage_value)?),
+        },
+        storage_proof: storage_proof.proof.clone(),
+    })
+}
+
+pub fn format_storage_proof_bundle(
+    bundle: &StorageProofBundle,
+    format: OutputFormat,
+) -> Result<String> {
+    match format {
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(&stamp_format_version(
+            serde_json::to_value(bundle)?,
+            ProofFormatKind::Storage,
+        )?)?),
+        OutputFormat::Hex => Ok(format!(
+            "{}\n{}",
+            convert_bytes_to_hex(rlp_encode_proof_nodes(&bundle.account_proof)?),
+            convert_bytes_to_hex(rlp_encode_proof_nodes(&bundle.storage_proof)?),
+        )),
+        OutputFormat::Binary | OutputFormat::Eip1186 | OutputFormat::Ssz => Err(AppError::Custom(
+            "✘ Storage proofs are only available in hex or JSON format!".to_string(),
+        )),
+    }
+}
+
+pub fn prove_storage(
+    cli_args: &CliArgs,
+    address: &str,
+    slot: &str,
+    block_id: &str,
+) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    let slot = convert_hex_to_left_padded_h256(slot.to_string())?;
+    let maybe_trusted_headers = maybe_read_trusted_headers_file(&cli_args.trusted_headers)?;
+    let block = get_block_by_block_id(&endpoint, block_id)?;
+    maybe_check_block_against_trusted_headers(&endpoint, &block, &maybe_trusted_headers)?;
+    get_account_proof(&endpoint, address, &slot, &block)
+        .and_then(|account_proof| get_storage_proof_bundle(&block, &account_proof, &slot))
+        .and_then(|bundle| {
+            verify_storage_proof_bundle(&bundle)?;
+            info!("✔ Account & storage proofs verified against block's state root!");
+            format_storage_proof_bundle(&bundle, cli_args.format)
+        })
+        .inspect(|_| {
+            cli_args
+                .telemetry
+                .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                    proof_type: "storage",
+                    num_proofs: 1,
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make_rpc_call::deserialize_to_account_proof_rpc_response;
+    use crate::proof_bundle::AnyProofBundle;
+    use crate::test_utils::{
+        get_expected_block, SAMPLE_GET_PROOF_JSON_PATH, SAMPLE_GET_PROOF_STATE_ROOT,
+    };
+    use std::fs;
+
+    const PRESENT_SLOT: u64 = 2;
+    const ABSENT_SLOT: u64 = 99;
+
+    fn get_sample_block_and_account_proof() -> (Block, AccountProofJson) {
+        let mut block = get_expected_block();
+        block.state_root = convert_hex_to_h256(SAMPLE_GET_PROOF_STATE_ROOT.to_string()).unwrap();
+        let account_proof = fs::read_to_string(SAMPLE_GET_PROOF_JSON_PATH)
+            .map_err(AppError::from)
+            .and_then(deserialize_to_account_proof_rpc_response)
+            .unwrap()
+            .result;
+        (block, account_proof)
+    }
+
+    fn get_sample_storage_proof_bundle(slot: u64) -> StorageProofBundle {
+        let (block, account_proof) = get_sample_block_and_account_proof();
+        get_storage_proof_bundle(&block, &account_proof, &H256::from_low_u64_be(slot)).unwrap()
+    }
+
+    #[test]
+    fn should_get_verifiable_storage_proof_bundle() {
+        let result = get_sample_storage_proof_bundle(PRESENT_SLOT);
+        assert!(result.state_root == SAMPLE_GET_PROOF_STATE_ROOT);
+        assert!(result.storage_value == "0x820d09"); // NOTE: RLP encoding of 3337.
+        verify_storage_proof_bundle(&result).unwrap();
+    }
+
+    #[test]
+    fn should_get_verifiable_storage_proof_bundle_for_absent_slot() {
+        let result = get_sample_storage_proof_bundle(ABSENT_SLOT);
+        assert!(result.storage_value == "0x");
+        verify_storage_proof_bundle(&result).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_verify_storage_proof_bundle_with_wrong_value() {
+        let mut present = get_sample_storage_proof_bundle(PRESENT_SLOT);
+        let mut absent = get_sample_storage_proof_bundle(ABSENT_SLOT);
+        present.storage_value = "0x820539".to_string();
+        absent.storage_value = "0x820539".to_string();
+        assert!(verify_storage_proof_bundle(&present).is_err());
+        assert!(verify_storage_proof_bundle(&absent).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_verify_storage_proof_bundle_against_wrong_state_root() {
+        let mut bundle = get_sample_storage_proof_bundle(PRESENT_SLOT);
+        bundle.state_root = convert_h256_to_prefixed_hex(get_expected_block().state_root).unwrap();
+        match verify_storage_proof_bundle(&bundle) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not hash to expected hash")),
+            _ => panic!("Should not verify storage proof against wrong state root!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_get_storage_proof_bundle_for_slot_not_in_response() {
+        let (block, account_proof) = get_sample_block_and_account_proof();
+        assert!(get_storage_proof_bundle(&block, &account_proof, &H256::zero()).is_err());
+    }
+
+    #[test]
+    fn should_format_storage_proof_bundle() {
+        let bundle = get_sample_storage_proof_bundle(PRESENT_SLOT);
+        let hex = format_storage_proof_bundle(&bundle, OutputFormat::Hex).unwrap();
+        let json = format_storage_proof_bundle(&bundle, OutputFormat::Json).unwrap();
+        assert!(hex.lines().count() == 2);
+        match serde_json::from_str(&json).unwrap() {
+            AnyProofBundle::Storage(result) => assert!(result == bundle),
+            _ => panic!("Should deserialize storage proof bundle as such!"),
+        }
+    }
+}
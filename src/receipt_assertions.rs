@@ -0,0 +1,439 @@
+use crate::errors::AppError;
+use crate::proof_bundle::ProofBundle;
+use crate::rlp_codec::decode_receipt;
+use crate::types::{Receipt, Result};
+use crate::utils::{
+    convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex, decode_prefixed_hex,
+};
+use ethereum_types::U256;
+use serde_json::Value as Json;
+use std::fs;
+use std::str::FromStr;
+
+/**
+ * Receipt Assertions:
+ *
+ * Automated pipelines often only want a proof if the receipt says what they
+ * expect, e.g. that the tx succeeded or emitted a certain event. The TOML file
+ * given via `--assertions` lists conditions of the form `<path> <op> <value>`,
+ * evaluated against the receipt decoded from the proven value itself, e.g.
+ * `status == 1`, `logs[0].address == 0x..` or `logs[*].topics contains 0x..`.
+ * Paths are dotted field names w/ `[n]` indices, where `[*]` selects every
+ * item & an assertion holds if it holds for any selected value. Operators are
+ * `==`, `!=`, `<`, `<=`, `>`, `>=` & `contains`, for arrays & hex strings.
+ * Numbers compare numerically, in decimal or `0x` hex, & strings compare case
+ * insensitively. In `gate` mode, the default, no proof is emitted unless all
+ * assertions pass. In `record` mode it always is. Either way each assertion's
+ * result is recorded in full JSON proof bundles.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssertionMode {
+    #[default]
+    Gate,
+    Record,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AssertionsFile {
+    #[serde(default)]
+    mode: AssertionMode,
+    assertions: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+    All,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assertion {
+    pub source: String,
+    pub path: Vec<PathSegment>,
+    pub operator: Operator,
+    pub expected: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assertions {
+    pub mode: AssertionMode,
+    pub assertions: Vec<Assertion>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssertionResult {
+    pub assertion: String,
+    pub passed: bool,
+}
+
+fn get_invalid_assertion_err(source: &str, reason: &str) -> AppError {
+    AppError::Custom(format!("✘ Invalid assertion `{}`: {}!", source, reason))
+}
+
+fn parse_operator(source: &str, operator: &str) -> Result<Operator> {
+    match operator {
+        "==" => Ok(Operator::Eq),
+        "!=" => Ok(Operator::Ne),
+        "<" => Ok(Operator::Lt),
+        "<=" => Ok(Operator::Le),
+        ">" => Ok(Operator::Gt),
+        ">=" => Ok(Operator::Ge),
+        "contains" => Ok(Operator::Contains),
+        _ => Err(get_invalid_assertion_err(
+            source,
+            &format!("unknown operator `{}`", operator),
+        )),
+    }
+}
+
+fn parse_path_segment(source: &str, segment: &str) -> Result<Vec<PathSegment>> {
+    let mut parts = segment.split('[');
+    let field = parts.next().unwrap_or_default();
+    if field.is_empty() {
+        return Err(get_invalid_assertion_err(
+            source,
+            "empty field name in path",
+        ));
+    }
+    std::iter::once(Ok(PathSegment::Field(field.to_string())))
+        .chain(parts.map(|index| {
+            match index.strip_suffix(']') {
+                Some("*") => Ok(PathSegment::All),
+                Some(index) => index
+                    .parse::<usize>()
+                    .map(PathSegment::Index)
+                    .map_err(|_| get_invalid_assertion_err(source, "invalid index in path")),
+                None => Err(get_invalid_assertion_err(source, "unclosed `[` in path")),
+            }
+        }))
+        .collect()
+}
+
+pub fn parse_assertion(source: &str) -> Result<Assertion> {
+    let mut tokens = source.split_whitespace();
+    let (path, operator) = match (tokens.next(), tokens.next()) {
+        (Some(path), Some(operator)) => (path, operator),
+        _ => {
+            return Err(get_invalid_assertion_err(
+                source,
+                "expected `<path> <op> <value>`",
+            ))
+        }
+    };
+    let expected = tokens.collect::<Vec<&str>>().join(" ");
+    if expected.is_empty() {
+        return Err(get_invalid_assertion_err(source, "missing value"));
+    }
+    Ok(Assertion {
+        source: source.to_string(),
+        path: path
+            .split('.')
+            .map(|segment| parse_path_segment(source, segment))
+            .collect::<Result<Vec<Vec<PathSegment>>>>()?
+            .concat(),
+        operator: parse_operator(source, operator)?,
+        expected,
+    })
+}
+
+pub fn parse_assertions_file(toml_string: &str) -> Result<Assertions> {
+    let assertions_file: AssertionsFile = toml::from_str(toml_string)
+        .map_err(|e| AppError::Custom(format!("✘ Error parsing assertions file!\n✘ {}", e)))?;
+    Ok(Assertions {
+        mode: assertions_file.mode,
+        assertions: assertions_file
+            .assertions
+            .iter()
+            .map(|source| parse_assertion(source))
+            .collect::<Result<Vec<Assertion>>>()?,
+    })
+}
+
+pub fn maybe_read_assertions_file(maybe_path: &Option<String>) -> Result<Option<Assertions>> {
+    match maybe_path {
+        None => Ok(None),
+        Some(path) => {
+            info!("✔ Reading receipt assertions from: {}", path);
+            let toml_string = fs::read_to_string(path).map_err(|e| {
+                AppError::Custom(format!(
+                    "✘ Error reading assertions file {}!\n✘ {}",
+                    path, e
+                ))
+            })?;
+            parse_assertions_file(&toml_string).map(Some)
+        }
+    }
+}
+
+pub fn get_proven_receipt_json(bundle: &ProofBundle) -> Result<Json> {
+    let mut json = get_decoded_receipt_json(&decode_receipt(&decode_prefixed_hex(
+        bundle.value.clone(),
+    )?)?)?;
+    json["tx_hash"] = json!(bundle.tx_hash);
+    json["tx_index"] = json!(bundle.tx_index);
+    json["block_hash"] = json!(bundle.block_hash);
+    json["block_number"] = json!(bundle.block_number);
+    Ok(json)
+}
+
+// NOTE: Only a decoded receipt's consensus fields are known, so only those are included.
+pub fn get_decoded_receipt_json(receipt: &Receipt) -> Result<Json> {
+    Ok(json!({
+        "type": receipt.tx_type,
+        "status": match receipt.root {
+            Some(_) => None,
+            None => Some(receipt.status as u8),
+        },
+        "root": receipt.root.map(convert_h256_to_prefixed_hex).transpose()?,
+        "cumulative_gas_used": receipt.cumulative_gas_used.low_u64(),
+        "logs": receipt
+            .logs
+            .iter()
+            .map(|log| {
+                Ok(json!({
+                    "address": convert_bytes_to_prefixed_hex(log.address.as_bytes()),
+                    "topics": log
+                        .topics
+                        .iter()
+                        .map(|topic| convert_h256_to_prefixed_hex(*topic))
+                        .collect::<Result<Vec<String>>>()?,
+                    "data": convert_bytes_to_prefixed_hex(&log.data),
+                }))
+            })
+            .collect::<Result<Vec<Json>>>()?,
+    }))
+}
+
+fn select_values(json: &Json, path: &[PathSegment]) -> Vec<Json> {
+    path.iter().fold(vec![json.clone()], |values, segment| {
+        values
+            .iter()
+            .flat_map(|value| match segment {
+                PathSegment::Field(field) => value.get(field).cloned().into_iter().collect(),
+                PathSegment::Index(index) => value.get(index).cloned().into_iter().collect(),
+                PathSegment::All => value.as_array().cloned().unwrap_or_default(),
+            })
+            .collect()
+    })
+}
+
+fn parse_number(string: &str) -> Option<U256> {
+    match string.strip_prefix("0x") {
+        Some(hex) => U256::from_str(hex).ok(),
+        None => U256::from_dec_str(string).ok(),
+    }
+}
+
+fn is_equal(value: &Json, expected: &str) -> bool {
+    match (value.as_u64(), parse_number(expected)) {
+        (Some(number), Some(expected_number)) => U256::from(number) == expected_number,
+        _ => match value {
+            Json::String(string) => string.eq_ignore_ascii_case(expected),
+            Json::Bool(boolean) => expected.parse::<bool>() == Ok(*boolean),
+            Json::Null => expected == "null",
+            _ => false,
+        },
+    }
+}
+
+fn holds(operator: Operator, value: &Json, expected: &str) -> bool {
+    let numbers = value.as_u64().map(U256::from).zip(parse_number(expected));
+    match operator {
+        Operator::Eq => is_equal(value, expected),
+        Operator::Ne => !is_equal(value, expected),
+        Operator::Lt => numbers.is_some_and(|(number, expected)| number < expected),
+        Operator::Le => numbers.is_some_and(|(number, expected)| number <= expected),
+        Operator::Gt => numbers.is_some_and(|(number, expected)| number > expected),
+        Operator::Ge => numbers.is_some_and(|(number, expected)| number >= expected),
+        Operator::Contains => match (value.as_array(), value.as_str()) {
+            (Some(items), _) => items.iter().any(|item| is_equal(item, expected)),
+            (_, Some(string)) => string
+                .to_lowercase()
+                .contains(&expected.trim_start_matches("0x").to_lowercase()),
+            _ => false,
+        },
+    }
+}
+
+pub fn evaluate_assertion(assertion: &Assertion, receipt_json: &Json) -> bool {
+    select_values(receipt_json, &assertion.path)
+        .iter()
+        .any(|value| holds(assertion.operator, value, &assertion.expected))
+}
+
+pub fn evaluate_assertions(
+    assertions: &Assertions,
+    bundle: &ProofBundle,
+) -> Result<Vec<AssertionResult>> {
+    let receipt_json = get_proven_receipt_json(bundle)?;
+    Ok(assertions
+        .assertions
+        .iter()
+        .map(|assertion| {
+            let passed = evaluate_assertion(assertion, &receipt_json);
+            match passed {
+                true => info!("✔ Receipt assertion passed: {}", assertion.source),
+                false => warn!("✘ Receipt assertion failed: {}", assertion.source),
+            };
+            AssertionResult {
+                assertion: assertion.source.clone(),
+                passed,
+            }
+        })
+        .collect())
+}
+
+pub fn maybe_apply_assertions_to_proof_bundle(
+    bundle: ProofBundle,
+    maybe_assertions: &Option<Assertions>,
+) -> Result<ProofBundle> {
+    let assertions = match maybe_assertions {
+        Some(assertions) => assertions,
+        None => return Ok(bundle),
+    };
+    let results = evaluate_assertions(assertions, &bundle)?;
+    let failures = results
+        .iter()
+        .filter(|result| !result.passed)
+        .map(|result| format!("✘ {}", result.assertion))
+        .collect::<Vec<String>>();
+    match assertions.mode == AssertionMode::Gate && !failures.is_empty() {
+        true => Err(AppError::Custom(format!(
+            "✘ {} receipt assertion(s) failed, so no proof was emitted:\n{}",
+            failures.len(),
+            failures.join("\n")
+        ))),
+        false => Ok(ProofBundle {
+            assertions: results,
+            ..bundle
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_sample_proof_bundle_1;
+
+    fn get_sample_assertions(mode: &str, assertions: &[&str]) -> Assertions {
+        parse_assertions_file(&format!(
+            "mode = \"{}\"\nassertions = {}",
+            mode,
+            serde_json::to_string(assertions).unwrap()
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_parse_assertion() {
+        let result = parse_assertion("logs[*].topics contains 0xabc").unwrap();
+        assert!(
+            result.path
+                == vec![
+                    PathSegment::Field("logs".to_string()),
+                    PathSegment::All,
+                    PathSegment::Field("topics".to_string()),
+                ]
+        );
+        assert!(result.operator == Operator::Contains);
+        assert!(result.expected == "0xabc");
+    }
+
+    #[test]
+    fn should_fail_to_parse_malformed_assertions() {
+        assert!(parse_assertion("status ~= 1").is_err());
+        assert!(parse_assertion("status ==").is_err());
+        assert!(parse_assertion("logs[x].address == 0x0").is_err());
+        assert!(parse_assertion("logs[0.address == 0x0").is_err());
+        assert!(parse_assertions_file("assertoins = []").is_err());
+    }
+
+    #[test]
+    fn should_evaluate_assertions_against_proven_receipt() {
+        let bundle = get_sample_proof_bundle_1();
+        let receipt_json = get_proven_receipt_json(&bundle).unwrap();
+        let address = receipt_json["logs"][0]["address"]
+            .as_str()
+            .unwrap()
+            .to_uppercase();
+        let topic = receipt_json["logs"][0]["topics"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let gas = receipt_json["cumulative_gas_used"].as_u64().unwrap();
+        let passing = [
+            "status == 1".to_string(),
+            format!("logs[0].address == 0x{}", &address[2..]),
+            format!("logs[*].topics contains {}", topic),
+            format!("cumulative_gas_used >= 0x{:x}", gas),
+            format!("cumulative_gas_used < {}", gas + 1),
+            format!("tx_index == {}", bundle.tx_index),
+        ];
+        let failing = [
+            "status == 0".to_string(),
+            "logs[99].address == 0x0".to_string(),
+            format!("logs[*].topics contains 0x{}", "ff".repeat(32)),
+        ];
+        passing.iter().for_each(|source| {
+            assert!(evaluate_assertion(
+                &parse_assertion(source).unwrap(),
+                &receipt_json
+            ))
+        });
+        failing.iter().for_each(|source| {
+            assert!(!evaluate_assertion(
+                &parse_assertion(source).unwrap(),
+                &receipt_json
+            ))
+        });
+    }
+
+    #[test]
+    fn should_gate_proof_bundle_on_failed_assertions() {
+        let assertions = Some(get_sample_assertions(
+            "gate",
+            &["status == 1", "status == 0"],
+        ));
+        match maybe_apply_assertions_to_proof_bundle(get_sample_proof_bundle_1(), &assertions) {
+            Err(AppError::Custom(e)) => {
+                assert!(e.contains("1 receipt assertion(s) failed") && e.contains("status == 0"))
+            }
+            _ => panic!("Should not emit proof bundle w/ failed assertions!"),
+        }
+    }
+
+    #[test]
+    fn should_record_assertion_results_in_proof_bundle() {
+        let assertions = Some(get_sample_assertions(
+            "record",
+            &["status == 1", "status == 0"],
+        ));
+        let result =
+            maybe_apply_assertions_to_proof_bundle(get_sample_proof_bundle_1(), &assertions)
+                .unwrap();
+        assert!(
+            result
+                .assertions
+                .iter()
+                .map(|r| r.passed)
+                .collect::<Vec<bool>>()
+                == [true, false]
+        );
+        let unasserted = maybe_apply_assertions_to_proof_bundle(get_sample_proof_bundle_1(), &None);
+        assert!(unasserted.unwrap().assertions.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::get_block::{deserialize_block_json_to_block_struct, get_block_by_block_id_json};
+use crate::get_endpoint::get_endpoint;
+use crate::get_rpc_call_jsons::get_transaction_receipt_json;
+use crate::json_rpc_client::call_json_rpc;
+use crate::make_rpc_call::{check_response_text, deserialize_to_block_rpc_response};
+use crate::parse_cli_args::CliArgs;
+use crate::types::Result;
+use crate::utils::convert_h256_to_prefixed_hex;
+use serde_json::Value as Json;
+use std::fs;
+use std::path::Path;
+
+pub const BLOCK_FIXTURE_FILE_NAME: &str = "sample_block_json";
+pub const RECEIPT_FIXTURES_DIR_NAME: &str = "sample_receipt_jsons";
+pub const TX_HASHES_FIXTURE_FILE_NAME: &str = "tx_hashes.json";
+
+/**
+ * Record Fixtures:
+ *
+ * Tests for new hardforks & chains need a real block's header & receipts,
+ * which are tedious & error prone to hand-craft. This fetches them & writes
+ * them in the layout `test_utils` reads: the raw `eth_getBlockByNumber`
+ * response as `sample_block_json`, & each raw `eth_getTransactionReceipt`
+ * response in `sample_receipt_jsons/`, named by tx hash. The block's tx hashes
+ * are listed, in order, in `tx_hashes.json`. Responses have their ids reset
+ * & their keys sorted, so recording the same block twice yields identical
+ * files, whatever the node.
+ */
+pub fn normalise_rpc_response(rpc_response_text: &str) -> Result<String> {
+    let mut rpc_response: Json = serde_json::from_str(rpc_response_text)?;
+    rpc_response["id"] = json!(1);
+    Ok(rpc_response.to_string())
+}
+
+fn fetch_rpc_response(endpoint: &str, rpc_json: Json) -> Result<String> {
+    call_json_rpc(endpoint, rpc_json)
+        .and_then(check_response_text)
+        .and_then(|rpc_response_text| normalise_rpc_response(&rpc_response_text))
+}
+
+pub fn record_fixtures_from_endpoint(
+    endpoint: &str,
+    block_id: &str,
+    out_dir: &str,
+) -> Result<String> {
+    info!(
+        "✔ Recording fixtures for block {} in: {}",
+        block_id, out_dir
+    );
+    let block_fixture = fetch_rpc_response(endpoint, get_block_by_block_id_json(block_id)?)?;
+    let block = deserialize_to_block_rpc_response(block_fixture.clone())
+        .and_then(|res| deserialize_block_json_to_block_struct(res.result))?;
+    let tx_hashes = block
+        .transactions
+        .iter()
+        .map(|tx_hash| convert_h256_to_prefixed_hex(*tx_hash))
+        .collect::<Result<Vec<String>>>()?;
+    let receipts_dir = Path::new(out_dir).join(RECEIPT_FIXTURES_DIR_NAME);
+    fs::create_dir_all(&receipts_dir)?;
+    tx_hashes.iter().try_for_each(|tx_hash| -> Result<()> {
+        debug!("✔ Recording receipt fixture for tx: {}", tx_hash);
+        let receipt_fixture = fetch_rpc_response(endpoint, get_transaction_receipt_json(tx_hash)?)?;
+        Ok(fs::write(receipts_dir.join(tx_hash), receipt_fixture)?)
+    })?;
+    fs::write(
+        Path::new(out_dir).join(BLOCK_FIXTURE_FILE_NAME),
+        block_fixture,
+    )?;
+    fs::write(
+        Path::new(out_dir).join(TX_HASHES_FIXTURE_FILE_NAME),
+        serde_json::to_string_pretty(&tx_hashes)?,
+    )?;
+    Ok(format!(
+        "✔ Fixtures for block {} & its {} receipts written to: {}",
+        block.number,
+        tx_hashes.len(),
+        out_dir
+    ))
+}
+
+pub fn record_fixtures(cli_args: &CliArgs, block_id: &str, out_dir: &str) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    record_fixtures_from_endpoint(&endpoint, block_id, out_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, SAMPLE_BLOCK_JSON_PATH,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use std::sync::Arc;
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    fn get_temp_fixtures_dir(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-fixtures-{}", name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn serve_sample_block_and_receipts() -> (String, Arc<Server>, thread::JoinHandle<()>) {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let endpoint = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let server_clone = server.clone();
+        let handle = thread::spawn(move || {
+            for mut request in server_clone.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let rpc_json: Json = serde_json::from_str(&body).unwrap();
+                let response = match rpc_json["method"].as_str().unwrap() {
+                    "eth_getTransactionReceipt" => fs::read_to_string(format!(
+                        "{}{}",
+                        SAMPLE_RECEIPT_JSONS_1_PATH,
+                        rpc_json["params"][0].as_str().unwrap()
+                    ))
+                    .unwrap(),
+                    // NOTE: The sample block's receipts aren't all sampled, so it lists only those that are.
+                    _ => {
+                        let mut block_json: Json = serde_json::from_str(
+                            &fs::read_to_string(SAMPLE_BLOCK_JSON_PATH).unwrap(),
+                        )
+                        .unwrap();
+                        block_json["result"]["transactions"] = json!(get_sample_tx_hashes_1());
+                        block_json.to_string()
+                    }
+                };
+                request.respond(Response::from_string(response)).unwrap();
+            }
+        });
+        (endpoint, server, handle)
+    }
+
+    #[test]
+    fn should_normalise_rpc_response() {
+        let result =
+            normalise_rpc_response(r#"{"result":{"b":1,"a":2},"jsonrpc":"2.0","id":"7"}"#).unwrap();
+        assert!(result == r#"{"id":1,"jsonrpc":"2.0","result":{"a":2,"b":1}}"#);
+    }
+
+    #[test]
+    fn should_record_fixtures_readable_by_test_utils() {
+        let out_dir = get_temp_fixtures_dir("record");
+        let expected_block = get_expected_block();
+        let block_id = expected_block.number.to_string();
+        let (endpoint, server, handle) = serve_sample_block_and_receipts();
+        let result = record_fixtures_from_endpoint(&endpoint, &block_id, &out_dir);
+        let block_fixture =
+            fs::read_to_string(Path::new(&out_dir).join(BLOCK_FIXTURE_FILE_NAME)).unwrap();
+        let rerecord_result = record_fixtures_from_endpoint(&endpoint, &block_id, &out_dir);
+        server.unblock();
+        handle.join().unwrap();
+        assert!(result.is_ok() && rerecord_result.is_ok());
+        assert!(
+            fs::read_to_string(Path::new(&out_dir).join(BLOCK_FIXTURE_FILE_NAME)).unwrap()
+                == block_fixture
+        );
+        let block = deserialize_to_block_rpc_response(block_fixture)
+            .and_then(|res| deserialize_block_json_to_block_struct(res.result))
+            .unwrap();
+        assert!(block.hash == expected_block.hash);
+        assert!(block.receipts_root == expected_block.receipts_root);
+        let tx_hashes: Vec<String> = serde_json::from_str(
+            &fs::read_to_string(Path::new(&out_dir).join(TX_HASHES_FIXTURE_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        assert!(tx_hashes == get_sample_tx_hashes_1());
+        let receipts_path = format!("{}/{}/", out_dir, RECEIPT_FIXTURES_DIR_NAME);
+        assert!(
+            get_sample_receipts(receipts_path, tx_hashes)
+                == get_sample_receipts(
+                    SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+                    get_sample_tx_hashes_1()
+                )
+        );
+        fs::remove_dir_all(out_dir).unwrap();
+    }
+}
@@ -0,0 +1,351 @@
+use crate::errors::AppError;
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::get_endpoint::get_endpoint;
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::get_receipts_trie::get_block_and_receipts_trie;
+use crate::parse_cli_args::CliArgs;
+use crate::trie::Trie;
+use crate::trie_nodes::{get_node_from_database, Node, NodeKind};
+use crate::trie_snapshot::import_trie_snapshot;
+use crate::types::{Database, NodeStack, Result};
+use crate::utils::{
+    convert_bytes_to_h256, convert_bytes_to_hex, convert_bytes_to_prefixed_hex,
+    convert_h256_to_prefixed_hex, convert_hex_to_bytes, convert_hex_to_h256,
+};
+use crate::what_if::replace_receipt_in_trie;
+use ethereum_types::H256;
+use serde_json::Value as Json;
+use std::io::{self, BufRead, Write};
+
+const REPL_PROMPT: &str = "rrpm> ";
+const REPL_HELP_INFO: &str = "Commands:
+  load <block>       Build the receipts trie of the given block, via RPC.
+  open <snapshot>    Load a receipts trie from a snapshot written by `export-trie`.
+  get <index>        Print the RLP encoded receipt at the given index.
+  node <hash>        Print the decoded trie node w/ the given hash.
+  proof <index>      Print the hex proof of the receipt at the given index.
+  stats              Print the loaded trie's root, node counts & longest proof.
+  what-if <index> <rlp>
+                     Replace the receipt at the given index w/ the given hex RLP,
+                     printing the new root & changed proof nodes. The edited
+                     trie stays loaded, for further edits or proofs.
+  help               Print this message.
+  exit               Leave the REPL.";
+
+/**
+ * REPL:
+ *
+ * An interactive session for exploring a block's receipts trie whilst
+ * debugging a verifier integration. A trie is loaded once, via RPC or from a
+ * snapshot, after which its receipts, nodes & proofs can be inspected w/o
+ * refetching anything. A failing command prints its error & the session
+ * carries on.
+ */
+pub struct ReplSession<'a> {
+    cli_args: &'a CliArgs,
+    maybe_trie: Option<Trie>,
+}
+
+impl<'a> ReplSession<'a> {
+    pub fn new(cli_args: &'a CliArgs) -> Self {
+        ReplSession {
+            cli_args,
+            maybe_trie: None,
+        }
+    }
+
+    fn get_trie(&self) -> Result<&Trie> {
+        self.maybe_trie.as_ref().ok_or_else(|| {
+            AppError::Custom(
+                "✘ No trie loaded - use `load <block>` or `open <snapshot>` first!".to_string(),
+            )
+        })
+    }
+
+    fn set_trie(&mut self, trie: Trie) -> Result<String> {
+        let root = convert_h256_to_prefixed_hex(trie.root)?;
+        self.maybe_trie = Some(trie);
+        Ok(format!("✔ Loaded receipts trie w/ root: {}", root))
+    }
+
+    fn load(&mut self, block_id: &str) -> Result<String> {
+        let endpoint = get_endpoint(self.cli_args)?;
+        get_block_and_receipts_trie(&endpoint, block_id, self.cli_args)
+            .and_then(|(_, trie)| self.set_trie(trie))
+    }
+
+    fn open(&mut self, path: &str) -> Result<String> {
+        import_trie_snapshot(path).and_then(|trie| self.set_trie(trie))
+    }
+
+    fn get_branch(&self, index: &str) -> Result<NodeStack> {
+        get_branch_from_trie(self.get_trie()?, parse_index(index)?)
+    }
+
+    fn get(&self, index: &str) -> Result<String> {
+        self.get_branch(index)?
+            .last()
+            .and_then(|node| node.get_value())
+            .map(|value| convert_bytes_to_prefixed_hex(&value))
+            .ok_or_else(|| AppError::Custom("✘ Branch has no value!".to_string()))
+    }
+
+    fn node(&self, hash: &str) -> Result<String> {
+        let hash = convert_hex_to_h256(hash.to_string())?;
+        match get_node_from_database(&self.get_trie()?.database, &hash)? {
+            Some(node) => Ok(serde_json::to_string_pretty(&get_node_json(&node)?)?),
+            None => Err(AppError::Custom(format!(
+                "✘ No node in trie w/ hash: {}",
+                convert_h256_to_prefixed_hex(hash)?
+            ))),
+        }
+    }
+
+    fn proof(&self, index: &str) -> Result<String> {
+        rlp_encode_node_stack(&self.get_branch(index)?).map(convert_bytes_to_hex)
+    }
+
+    fn stats(&self) -> Result<String> {
+        let trie = self.get_trie()?;
+        let nodes = get_reachable_nodes_and_depths(&trie.database, &trie.root, 1)?;
+        let count_nodes_of_type = |node_type: NodeKind| {
+            nodes
+                .iter()
+                .filter(|(node, _)| node.get_type() == node_type)
+                .count()
+        };
+        Ok(serde_json::to_string_pretty(&json!({
+            "root": convert_h256_to_prefixed_hex(trie.root)?,
+            "num_receipts": count_nodes_of_type(NodeKind::Leaf),
+            "num_branch_nodes": count_nodes_of_type(NodeKind::Branch),
+            "num_extension_nodes": count_nodes_of_type(NodeKind::Extension),
+            "max_proof_length": nodes.iter().map(|(_, depth)| *depth).max().unwrap_or(0),
+        }))?)
+    }
+
+    fn what_if(&mut self, index: &str, hex_value: &str) -> Result<String> {
+        let value = convert_hex_to_bytes(hex_value.to_string())?;
+        let (trie, report) = replace_receipt_in_trie(self.get_trie()?, parse_index(index)?, value)?;
+        self.maybe_trie = Some(trie);
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+}
+
+pub trait Repl {
+    // NOTE: Returns `None` when the session should end.
+    fn execute(&mut self, line: &str) -> Option<Result<String>>;
+}
+
+impl<'a> Repl for ReplSession<'a> {
+    fn execute(&mut self, line: &str) -> Option<Result<String>> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        Some(match words.as_slice() {
+            [] => Ok(String::new()),
+            ["exit"] | ["quit"] => return None,
+            ["help"] => Ok(REPL_HELP_INFO.to_string()),
+            ["load", block_id] => self.load(block_id),
+            ["open", path] => self.open(path),
+            ["get", index] => self.get(index),
+            ["node", hash] => self.node(hash),
+            ["proof", index] => self.proof(index),
+            ["stats"] => self.stats(),
+            ["what-if", index, hex_value] => self.what_if(index, hex_value),
+            _ => Err(AppError::Custom(format!(
+                "✘ Unrecognised command: `{}` - try `help`!",
+                line.trim()
+            ))),
+        })
+    }
+}
+
+fn parse_index(index: &str) -> Result<usize> {
+    index
+        .parse::<usize>()
+        .map_err(|_| AppError::Custom(format!("✘ Not a valid index: {}", index)))
+}
+
+// NOTE: The database can hold nodes orphaned by insertions ∴ only count those under the root.
+fn get_reachable_nodes_and_depths(
+    database: &Database,
+    hash: &H256,
+    depth: usize,
+) -> Result<Vec<(Node, usize)>> {
+    let node = match get_node_from_database(database, hash)? {
+        Some(node) => node,
+        None => return Ok(vec![]),
+    };
+    let child_hashes = match (node.get_extension(), node.get_branch()) {
+        (Some(extension), _) => vec![extension.value.clone()],
+        (_, Some(branch)) => branch.branches.iter().flatten().cloned().collect(),
+        _ => vec![],
+    };
+    child_hashes
+        .iter()
+        .map(|child_hash| {
+            convert_bytes_to_h256(child_hash).and_then(|child_hash| {
+                get_reachable_nodes_and_depths(database, &child_hash, depth + 1)
+            })
+        })
+        .collect::<Result<Vec<Vec<(Node, usize)>>>>()
+        .map(|descendants| {
+            std::iter::once((node, depth))
+                .chain(descendants.into_iter().flatten())
+                .collect()
+        })
+}
+
+pub fn get_node_json(node: &Node) -> Result<Json> {
+    let hex_key = node
+        .get_key()
+        .to_nibble_values()?
+        .iter()
+        .map(|nibble| format!("{:x}", nibble))
+        .collect::<String>();
+    Ok(
+        match (node.get_leaf(), node.get_extension(), node.get_branch()) {
+            (Some(leaf), _, _) => json!({
+                "type": NodeKind::Leaf,
+                "key_nibbles": hex_key,
+                "value": convert_bytes_to_prefixed_hex(&leaf.value),
+            }),
+            (_, Some(extension), _) => json!({
+                "type": NodeKind::Extension,
+                "key_nibbles": hex_key,
+                "child": convert_bytes_to_prefixed_hex(&extension.value),
+            }),
+            (_, _, Some(branch)) => json!({
+                "type": NodeKind::Branch,
+                "children": branch
+                    .branches
+                    .iter()
+                    .map(|child| child.as_ref().map(|hash| convert_bytes_to_prefixed_hex(hash)))
+                    .collect::<Vec<Option<String>>>(),
+                "value": branch.value.as_ref().map(|value| convert_bytes_to_prefixed_hex(value)),
+            }),
+            _ => {
+                return Err(AppError::Custom(
+                    "✘ Cannot describe empty node!".to_string(),
+                ))
+            }
+        },
+    )
+}
+
+pub fn run_repl_session<S: Repl, R: BufRead, W: Write>(
+    session: &mut S,
+    reader: R,
+    mut writer: W,
+) -> Result<()> {
+    write!(writer, "{}", REPL_PROMPT)?;
+    writer.flush()?;
+    for line in reader.lines() {
+        match session.execute(&line?) {
+            None => break,
+            Some(Ok(output)) if output.is_empty() => (),
+            Some(Ok(output)) => writeln!(writer, "{}", output)?,
+            Some(Err(e)) => writeln!(writer, "{}", e)?,
+        };
+        write!(writer, "{}", REPL_PROMPT)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+pub fn run_repl(cli_args: &CliArgs) -> Result<String> {
+    info!("✔ Starting REPL - type `help` for commands...");
+    let mut session = ReplSession::new(cli_args);
+    run_repl_session(&mut session, io::stdin().lock(), io::stdout())?;
+    Ok("✔ Exiting REPL!".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        get_sample_proof_1, get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1,
+        PROOF_1_INDEX, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use clap::Parser;
+
+    fn get_sample_cli_args() -> CliArgs {
+        CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker", "repl"]).unwrap()
+    }
+
+    fn get_sample_trie() -> Trie {
+        get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+    }
+
+    #[test]
+    fn should_error_when_no_trie_loaded() {
+        let cli_args = get_sample_cli_args();
+        let mut session = ReplSession::new(&cli_args);
+        match session.execute("stats") {
+            Some(Err(AppError::Custom(e))) => assert!(e.contains("No trie loaded")),
+            _ => panic!("Should not get stats w/o a trie!"),
+        }
+    }
+
+    #[test]
+    fn should_explore_trie_in_repl_session() {
+        let cli_args = get_sample_cli_args();
+        let mut session = ReplSession::new(&cli_args);
+        let trie = get_sample_trie();
+        let root = convert_h256_to_prefixed_hex(trie.root).unwrap();
+        session.set_trie(trie).unwrap();
+        let proof = session
+            .execute(&format!("proof {}", PROOF_1_INDEX))
+            .unwrap()
+            .unwrap();
+        assert!(proof == get_sample_proof_1());
+        let root_node: Json =
+            serde_json::from_str(&session.execute(&format!("node {}", root)).unwrap().unwrap())
+                .unwrap();
+        assert!(root_node["type"] == "branch");
+        let stats: Json =
+            serde_json::from_str(&session.execute("stats").unwrap().unwrap()).unwrap();
+        assert!(stats["num_receipts"] == get_sample_tx_hashes_1().len());
+        assert!(stats["max_proof_length"] == 3);
+        assert!(session.execute("get 0").unwrap().unwrap().starts_with("0x"));
+        assert!(session.execute("frobnicate").unwrap().is_err());
+        assert!(session.execute("exit").is_none());
+    }
+
+    #[test]
+    fn should_edit_trie_in_repl_session() {
+        let cli_args = get_sample_cli_args();
+        let mut session = ReplSession::new(&cli_args);
+        session.set_trie(get_sample_trie()).unwrap();
+        let report: Json = serde_json::from_str(
+            &session
+                .execute(&format!("what-if {} 0xc0", PROOF_1_INDEX))
+                .unwrap()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(report["old_root"] != report["new_root"]);
+        assert!(
+            session
+                .execute(&format!("get {}", PROOF_1_INDEX))
+                .unwrap()
+                .unwrap()
+                == "0xc0"
+        );
+        assert!(session.execute("what-if 0 0xzz").unwrap().is_err());
+    }
+
+    #[test]
+    fn should_run_repl_session_until_exit() {
+        let cli_args = get_sample_cli_args();
+        let mut session = ReplSession::new(&cli_args);
+        session.set_trie(get_sample_trie()).unwrap();
+        let input = format!("proof {}\nexit\nproof 0\n", PROOF_1_INDEX);
+        let mut output = Vec::new();
+        run_repl_session(&mut session, input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&get_sample_proof_1()));
+        assert!(output.matches(REPL_PROMPT).count() == 2);
+    }
+}
@@ -1,44 +1,525 @@
-use crate::nibble_utils::{get_nibbles_from_bytes, Nibbles};
-use crate::types::{Bytes, Receipt, Result};
-use ethereum_types::U256;
-use rlp::RlpStream;
+use crate::chain_profile::LEGACY_TX_TYPE;
+use crate::errors::AppError;
+use crate::trie_keys::ReceiptKey;
+use crate::types::{Byte, Bytes, Log, Receipt, ReceiptRpcFields, Result};
+use ethereum_types::{Address, H256, U256};
+use rlp::{Rlp, RlpStream};
+use std::fmt;
+
+const RLP_STRING_PREFIX: Byte = 0x80;
+const RLP_LIST_PREFIX: Byte = 0xc0;
+const RLP_MAX_SHORT_PAYLOAD_LENGTH: usize = 55;
+const RECEIPT_LOGS_INDEX: usize = 3;
+
+pub const RLP_EMPTY_STRING: [Byte; 1] = [RLP_STRING_PREFIX];
+pub const RLP_EMPTY_LIST: [Byte; 1] = [RLP_LIST_PREFIX];
+
+/**
+ * RLP Codec Layer:
+ *
+ * The rest of the crate encodes & decodes RLP via the `Encoder` & `Decoder`
+ * here, never via the `rlp` crate itself. So moving to another RLP crate,
+ * e.g. `alloy-rlp` or `fastrlp`, or offering one behind a feature, means
+ * reimplementing only these two & `RlpValue`, the trait of the values they
+ * encode & decode, which for now is implemented for whatever the `rlp` crate
+ * can encode & decode. Decoding errors are the crate agnostic `DecoderError`.
+ * The streaming receipt encoder below writes RLP by hand, & the `Encodable`
+ * impls in `types.rs` it's tested against are its reference encoding.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecoderError(String);
+
+impl fmt::Display for DecoderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<rlp::DecoderError> for DecoderError {
+    fn from(e: rlp::DecoderError) -> DecoderError {
+        DecoderError(e.to_string())
+    }
+}
+
+impl From<DecoderError> for AppError {
+    fn from(e: DecoderError) -> AppError {
+        AppError::Custom(e.to_string())
+    }
+}
+
+pub type DecoderResult<T> = std::result::Result<T, DecoderError>;
+
+pub trait RlpValue: Sized {
+    fn append_to(&self, encoder: &mut Encoder);
+    fn decode_from(decoder: &Decoder) -> DecoderResult<Self>;
+}
+
+impl<T: rlp::Encodable + rlp::Decodable> RlpValue for T {
+    fn append_to(&self, encoder: &mut Encoder) {
+        encoder.stream.append(self);
+    }
+
+    fn decode_from(decoder: &Decoder) -> DecoderResult<Self> {
+        Ok(decoder.rlp.as_val()?)
+    }
+}
+
+#[derive(Default)]
+pub struct Encoder {
+    stream: RlpStream,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_list(num_items: usize) -> Self {
+        Encoder {
+            stream: RlpStream::new_list(num_items),
+        }
+    }
+
+    pub fn begin_list(&mut self, num_items: usize) -> &mut Self {
+        self.stream.begin_list(num_items);
+        self
+    }
+
+    pub fn append<T: RlpValue>(&mut self, value: &T) -> &mut Self {
+        value.append_to(self);
+        self
+    }
+
+    pub fn append_empty(&mut self) -> &mut Self {
+        self.stream.append_empty_data();
+        self
+    }
+
+    // NOTE: Appends an item that's already RLP encoded, e.g. a trie node, as is.
+    pub fn append_raw(&mut self, encoded_item: &[Byte]) -> &mut Self {
+        self.stream.append_raw(encoded_item, 1);
+        self
+    }
+
+    pub fn out(self) -> Bytes {
+        self.stream.out()
+    }
+}
+
+pub fn rlp_encode<T: RlpValue>(value: &T) -> Bytes {
+    let mut encoder = Encoder::new();
+    encoder.append(value);
+    encoder.out()
+}
+
+#[derive(Clone, Debug)]
+pub struct Decoder<'a> {
+    rlp: Rlp<'a>,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [Byte]) -> Self {
+        Decoder {
+            rlp: Rlp::new(bytes),
+        }
+    }
+
+    // NOTE: The length of the first item's encoding, header & payload, so any trailing bytes show.
+    pub fn get_encoded_length(&self) -> DecoderResult<usize> {
+        Ok(self.rlp.payload_info()?.total())
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.rlp.is_list()
+    }
+
+    pub fn get_item_count(&self) -> DecoderResult<usize> {
+        Ok(self.rlp.item_count()?)
+    }
+
+    pub fn at(&self, index: usize) -> DecoderResult<Decoder<'a>> {
+        Ok(Decoder {
+            rlp: self.rlp.at(index)?,
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Decoder<'a>> + '_ {
+        self.rlp.iter().map(|rlp| Decoder { rlp })
+    }
+
+    pub fn get_data(&self) -> DecoderResult<&'a [Byte]> {
+        Ok(self.rlp.data()?)
+    }
+
+    pub fn get_raw(&self) -> &'a [Byte] {
+        self.rlp.as_raw()
+    }
+
+    pub fn as_val<T: RlpValue>(&self) -> DecoderResult<T> {
+        T::decode_from(self)
+    }
+
+    pub fn as_list<T: RlpValue>(&self) -> DecoderResult<Vec<T>> {
+        match self.is_list() {
+            true => self.iter().map(|item| item.as_val()).collect(),
+            false => Err(DecoderError("RlpExpectedToBeList".to_string())),
+        }
+    }
+
+    pub fn val_at<T: RlpValue>(&self, index: usize) -> DecoderResult<T> {
+        self.at(index)?.as_val()
+    }
+
+    pub fn list_at<T: RlpValue>(&self, index: usize) -> DecoderResult<Vec<T>> {
+        self.at(index)?.as_list()
+    }
+}
+
+/**
+ * Streaming Receipt Encoder:
+ *
+ * `RlpStream` doesn't know a list's length until it's finished, so it
+ * splices each list's header in afterwards, shifting everything after it,
+ * & starts every stream w/ a fresh buffer. For blocks w/ thousands of logs
+ * that's a lot of copying & allocation churn. Receipts' shapes are known up
+ * front, so this encoder computes every item's length first & writes the
+ * encoding front to back into one reusable buffer, sized exactly. Its output
+ * is byte for byte that of the `Encodable` impls in `types.rs`.
+ */
+fn get_num_length_bytes(length: usize) -> usize {
+    (usize::BITS - length.leading_zeros()).div_ceil(8) as usize
+}
+
+fn get_rlp_header_length(payload_length: usize) -> usize {
+    match payload_length <= RLP_MAX_SHORT_PAYLOAD_LENGTH {
+        true => 1,
+        false => 1 + get_num_length_bytes(payload_length),
+    }
+}
+
+fn get_rlp_string_length(bytes: &[Byte]) -> usize {
+    match bytes {
+        [byte] if *byte < RLP_STRING_PREFIX => 1,
+        _ => get_rlp_header_length(bytes.len()) + bytes.len(),
+    }
+}
+
+fn get_rlp_list_length(payload_length: usize) -> usize {
+    get_rlp_header_length(payload_length) + payload_length
+}
+
+fn get_big_endian_bytes(value: &U256) -> [Byte; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn strip_leading_zeroes(bytes: &[Byte]) -> &[Byte] {
+    let first_non_zero_index = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    &bytes[first_non_zero_index..]
+}
+
+fn get_log_payload_length(log: &Log) -> usize {
+    get_rlp_string_length(log.address.as_bytes())
+        + get_rlp_list_length(log.topics.len() * get_rlp_string_length(&[0; 32]))
+        + get_rlp_string_length(&log.data)
+}
+
+fn get_logs_payload_length(logs: &[Log]) -> usize {
+    logs.iter()
+        .map(|log| get_rlp_list_length(get_log_payload_length(log)))
+        .sum()
+}
+
+fn get_status_or_post_state_bytes(receipt: &Receipt) -> &[Byte] {
+    match (&receipt.root, receipt.status) {
+        (Some(root), _) => root.as_bytes(),
+        (None, true) => &[1],
+        (None, false) => &[],
+    }
+}
+
+pub struct ReceiptEncoder {
+    buffer: Bytes,
+}
+
+impl Default for ReceiptEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReceiptEncoder {
+    pub fn new() -> Self {
+        ReceiptEncoder { buffer: Vec::new() }
+    }
+
+    fn write_header(&mut self, prefix: Byte, payload_length: usize) {
+        match payload_length <= RLP_MAX_SHORT_PAYLOAD_LENGTH {
+            true => self.buffer.push(prefix + payload_length as Byte),
+            false => {
+                let num_length_bytes = get_num_length_bytes(payload_length);
+                let length_bytes = payload_length.to_be_bytes();
+                self.buffer
+                    .push(prefix + RLP_MAX_SHORT_PAYLOAD_LENGTH as Byte + num_length_bytes as Byte);
+                self.buffer
+                    .extend_from_slice(&length_bytes[length_bytes.len() - num_length_bytes..]);
+            }
+        }
+    }
+
+    fn write_string(&mut self, bytes: &[Byte]) {
+        match bytes {
+            [byte] if *byte < RLP_STRING_PREFIX => self.buffer.push(*byte),
+            _ => {
+                self.write_header(RLP_STRING_PREFIX, bytes.len());
+                self.buffer.extend_from_slice(bytes);
+            }
+        }
+    }
+
+    fn write_log(&mut self, log: &Log) {
+        self.write_header(RLP_LIST_PREFIX, get_log_payload_length(log));
+        self.write_string(log.address.as_bytes());
+        self.write_header(
+            RLP_LIST_PREFIX,
+            log.topics.len() * get_rlp_string_length(&[0; 32]),
+        );
+        log.topics
+            .iter()
+            .for_each(|topic| self.write_string(topic.as_bytes()));
+        self.write_string(&log.data);
+    }
+
+    pub fn encode(&mut self, receipt: &Receipt) -> &[Byte] {
+        let status_or_post_state = get_status_or_post_state_bytes(receipt);
+        let cumulative_gas_used = get_big_endian_bytes(&receipt.cumulative_gas_used);
+        let cumulative_gas_used = strip_leading_zeroes(&cumulative_gas_used);
+        let logs_payload_length = get_logs_payload_length(&receipt.logs);
+        let deposit_nonce = receipt.deposit_nonce.as_ref().map(get_big_endian_bytes);
+        let deposit_receipt_version = match receipt.deposit_nonce {
+            Some(_) => receipt
+                .deposit_receipt_version
+                .as_ref()
+                .map(get_big_endian_bytes),
+            None => None,
+        };
+        let deposit_fields = [deposit_nonce, deposit_receipt_version];
+        let deposit_fields = deposit_fields
+            .iter()
+            .flatten()
+            .map(|bytes| strip_leading_zeroes(bytes));
+        let payload_length = get_rlp_string_length(status_or_post_state)
+            + get_rlp_string_length(cumulative_gas_used)
+            + get_rlp_string_length(receipt.logs_bloom.as_bytes())
+            + get_rlp_list_length(logs_payload_length)
+            + deposit_fields
+                .clone()
+                .map(get_rlp_string_length)
+                .sum::<usize>();
+        let is_typed = receipt.tx_type != LEGACY_TX_TYPE;
+        self.buffer.clear();
+        self.buffer
+            .reserve(usize::from(is_typed) + get_rlp_list_length(payload_length));
+        if is_typed {
+            self.buffer.push(receipt.tx_type);
+        }
+        self.write_header(RLP_LIST_PREFIX, payload_length);
+        self.write_string(status_or_post_state);
+        self.write_string(cumulative_gas_used);
+        self.write_string(receipt.logs_bloom.as_bytes());
+        self.write_header(RLP_LIST_PREFIX, logs_payload_length);
+        receipt.logs.iter().for_each(|log| self.write_log(log));
+        deposit_fields.for_each(|bytes| self.write_string(bytes));
+        &self.buffer
+    }
+}
 
 fn rlp_encode_receipt(receipt: &Receipt) -> Result<Bytes> {
-    let mut rlp_stream = RlpStream::new();
-    rlp_stream.append(receipt);
-    Ok(rlp_stream.out())
+    Ok(ReceiptEncoder::new().encode(receipt).to_vec())
 }
 
 pub fn rlp_encode_transaction_index(index: &U256) -> Result<Bytes> {
-    let mut rlp_stream = RlpStream::new();
-    rlp_stream.append(&index.as_usize());
-    Ok(rlp_stream.out())
+    Ok(rlp_encode(&index.as_usize()))
+}
+
+pub fn rlp_encode_account(
+    nonce: &U256,
+    balance: &U256,
+    storage_root: &H256,
+    code_hash: &H256,
+) -> Result<Bytes> {
+    let mut encoder = Encoder::new_list(4);
+    encoder
+        .append(nonce)
+        .append(balance)
+        .append(storage_root)
+        .append(code_hash);
+    Ok(encoder.out())
+}
+
+pub fn get_storage_root_from_rlp_encoded_account(rlp_encoded_account: &[Byte]) -> Result<H256> {
+    Decoder::new(rlp_encoded_account)
+        .val_at(2)
+        .map_err(|e| AppError::Custom(format!("✘ Error decoding RLP encoded account: {}", e)))
+}
+
+pub fn rlp_encode_storage_value(value: &U256) -> Result<Bytes> {
+    Ok(rlp_encode(value))
 }
 
 pub fn get_rlp_encoded_receipt_and_encoded_key_tuple(
     receipt: &Receipt,
-) -> Result<(Nibbles, Bytes)> {
+) -> Result<(ReceiptKey, Bytes)> {
     rlp_encode_receipt(receipt).and_then(|rlp_encoded_receipt| {
         Ok((
-            get_nibbles_from_bytes(rlp_encode_transaction_index(&receipt.transaction_index)?),
+            ReceiptKey::from_index(receipt.transaction_index.as_usize())?,
             rlp_encoded_receipt,
         ))
     })
 }
 
-pub fn get_rlp_encoded_receipts_and_nibble_tuples(
+pub fn get_rlp_encoded_receipts_and_key_tuples(
     receipts: &[Receipt],
-) -> Result<Vec<(Nibbles, Bytes)>> {
+) -> Result<Vec<(ReceiptKey, Bytes)>> {
+    // NOTE: One encoder for the whole block, so its buffer is reused rather than reallocated.
+    let mut encoder = ReceiptEncoder::new();
     receipts
         .iter()
-        .map(|receipt| get_rlp_encoded_receipt_and_encoded_key_tuple(receipt))
-        .collect::<Result<Vec<(Nibbles, Bytes)>>>()
+        .map(|receipt| {
+            Ok((
+                ReceiptKey::from_index(receipt.transaction_index.as_usize())?,
+                encoder.encode(receipt).to_vec(),
+            ))
+        })
+        .collect::<Result<Vec<(ReceiptKey, Bytes)>>>()
+}
+
+// NOTE: Raw receipts are already encoded, so are keyed by their position, i.e. their tx index.
+pub fn get_raw_receipts_and_key_tuples(
+    raw_receipts: Vec<Bytes>,
+) -> Result<Vec<(ReceiptKey, Bytes)>> {
+    raw_receipts
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw_receipt)| {
+            decode_receipt(&raw_receipt).map_err(|e| {
+                AppError::Custom(format!("✘ Raw receipt #{} is invalid: {}", index, e))
+            })?;
+            Ok((ReceiptKey::from_index(index)?, raw_receipt))
+        })
+        .collect()
+}
+
+pub fn get_rlp_encoded_log_from_rlp_encoded_receipt(
+    rlp_encoded_receipt: &[Byte],
+    log_index: usize,
+) -> Result<Bytes> {
+    // NOTE: A typed receipt is its type byte followed by its RLP list, so strip the former.
+    let rlp_list = match rlp_encoded_receipt.first() {
+        Some(first_byte) if *first_byte < RLP_LIST_PREFIX => &rlp_encoded_receipt[1..],
+        _ => rlp_encoded_receipt,
+    };
+    let logs = Decoder::new(rlp_list)
+        .at(RECEIPT_LOGS_INDEX)
+        .map_err(|e| AppError::Custom(format!("✘ Error decoding RLP encoded receipt: {}", e)))?;
+    let num_logs = logs
+        .get_item_count()
+        .map_err(|e| AppError::Custom(format!("✘ Error decoding receipt's logs: {}", e)))?;
+    match log_index < num_logs {
+        true => logs
+            .at(log_index)
+            .map(|log| log.get_raw().to_vec())
+            .map_err(|e| AppError::Custom(format!("✘ Error decoding receipt's log: {}", e))),
+        false => Err(AppError::Custom(format!(
+            "✘ No log at index {} in receipt with {} log(s)!",
+            log_index, num_logs
+        ))),
+    }
+}
+
+fn get_receipt_decoding_err(e: DecoderError) -> AppError {
+    AppError::Custom(format!("✘ Error decoding RLP encoded receipt: {}", e))
+}
+
+fn decode_rlp_encoded_log(rlp_log: Decoder) -> Result<Log> {
+    Ok(Log {
+        address: rlp_log.val_at(0).map_err(get_receipt_decoding_err)?,
+        topics: rlp_log.list_at(1).map_err(get_receipt_decoding_err)?,
+        data: rlp_log.val_at(2).map_err(get_receipt_decoding_err)?,
+    })
+}
+
+fn decode_optional_field<T: RlpValue>(rlp: &Decoder, index: usize) -> Result<Option<T>> {
+    match index < rlp.get_item_count().map_err(get_receipt_decoding_err)? {
+        true => rlp
+            .val_at(index)
+            .map(Some)
+            .map_err(get_receipt_decoding_err),
+        false => Ok(None),
+    }
+}
+
+/**
+ * Receipt Decoding:
+ *
+ * The inverse of the encoding above, for legacy & typed receipts alike, so
+ * verifiers can hand back a structured receipt rather than opaque bytes. Only
+ * the consensus fields are encoded, so only those are decoded: the type,
+ * status or pre-Byzantium post-tx state root, cumulative gas used, logs bloom,
+ * logs & any deposit fields. The rest, e.g. the tx hash & index, are zeroed
+ * for callers that know them, e.g. from a proof bundle, to fill in.
+ */
+pub fn decode_receipt(rlp_encoded_receipt: &[Byte]) -> Result<Receipt> {
+    let (tx_type, rlp_list) = match rlp_encoded_receipt.first() {
+        Some(first_byte) if *first_byte < RLP_LIST_PREFIX => {
+            (*first_byte, &rlp_encoded_receipt[1..])
+        }
+        _ => (LEGACY_TX_TYPE, rlp_encoded_receipt),
+    };
+    let rlp = Decoder::new(rlp_list);
+    let status_or_root: Bytes = rlp.val_at(0).map_err(get_receipt_decoding_err)?;
+    let (status, root) = match status_or_root.len() == H256::len_bytes() {
+        true => (false, Some(H256::from_slice(&status_or_root))),
+        false => (status_or_root == [1], None),
+    };
+    Ok(Receipt {
+        tx_type,
+        status,
+        root,
+        cumulative_gas_used: rlp.val_at(1).map_err(get_receipt_decoding_err)?,
+        logs_bloom: rlp.val_at(2).map_err(get_receipt_decoding_err)?,
+        logs: rlp
+            .at(RECEIPT_LOGS_INDEX)
+            .map_err(get_receipt_decoding_err)?
+            .iter()
+            .map(decode_rlp_encoded_log)
+            .collect::<Result<Vec<Log>>>()?,
+        deposit_nonce: decode_optional_field(&rlp, 4)?,
+        deposit_receipt_version: decode_optional_field(&rlp, 5)?,
+        to: None,
+        from: Address::zero(),
+        gas_used: U256::zero(),
+        block_hash: H256::zero(),
+        transaction_hash: H256::zero(),
+        block_number: U256::zero(),
+        transaction_index: U256::zero(),
+        contract_address: None,
+        rpc_fields: ReceiptRpcFields::default(),
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nibble_utils::{get_nibbles_from_bytes, Nibbles};
     use crate::test_utils::{get_expected_receipt, get_expected_receipt_2, get_expected_receipt_3};
+    use crate::trie_keys::TrieKey;
+    use ethereum_types::H256;
+    use rlp::Rlp;
 
     fn get_expected_receipt_nibbles() -> Nibbles {
         get_nibbles_from_bytes(vec![0x60])
@@ -167,6 +648,38 @@ mod tests {
         ]
     }
 
+    #[test]
+    fn should_decode_what_encoder_encodes() {
+        let hash = H256::from_low_u64_be(0xc0ffee);
+        let raw_item = rlp_encode(&vec![0xde, 0xad]);
+        let mut encoder = Encoder::new_list(4);
+        encoder
+            .append(&hash)
+            .append(&U256::from(1337))
+            .append_empty()
+            .append_raw(&raw_item);
+        let encoded = encoder.out();
+        let decoder = Decoder::new(&encoded);
+        assert!(decoder.get_encoded_length().unwrap() == encoded.len());
+        assert!(decoder.get_item_count().unwrap() == 4);
+        assert!(decoder.val_at::<H256>(0).unwrap() == hash);
+        assert!(decoder.val_at::<U256>(1).unwrap() == U256::from(1337));
+        assert!(decoder.at(2).unwrap().get_data().unwrap().is_empty());
+        assert!(decoder.at(3).unwrap().get_raw() == raw_item.as_slice());
+        assert!(decoder.as_list::<Bytes>().unwrap().len() == 4);
+        assert!(rlp_encode(&Vec::<u8>::new()) == RLP_EMPTY_STRING);
+    }
+
+    #[test]
+    fn should_fail_to_decode_malformed_rlp_w_decoder_error() {
+        let result = Decoder::new(&[0xc2, 0x01]).val_at::<U256>(1);
+        assert!(result.is_err());
+        match AppError::from(result.unwrap_err()) {
+            AppError::Custom(e) => assert!(!e.is_empty()),
+            _ => panic!("Decoder errors should convert to custom app errors!"),
+        }
+    }
+
     #[test]
     fn should_rlp_encode_receipt() {
         let result = rlp_encode_receipt(&get_expected_receipt()).unwrap();
@@ -191,23 +704,89 @@ mod tests {
     fn should_get_encoded_receipt_and_hash_tuple() {
         let result =
             get_rlp_encoded_receipt_and_encoded_key_tuple(&get_expected_receipt()).unwrap();
-        assert!(result.0 == get_expected_receipt_nibbles());
+        assert!(result.0.to_nibbles() == get_expected_receipt_nibbles());
         assert!(result.1 == get_rlp_encoded_receipt_1());
     }
 
     #[test]
     fn should_get_encoded_receipts_and_hash_tuples() {
         let receipts = vec![get_expected_receipt(), get_expected_receipt()];
-        let results = get_rlp_encoded_receipts_and_nibble_tuples(&receipts).unwrap();
+        let results = get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap();
         results
             .iter()
             .map(|result| {
-                assert!(result.0 == get_expected_receipt_nibbles());
+                assert!(result.0.to_nibbles() == get_expected_receipt_nibbles());
                 assert!(result.1 == get_rlp_encoded_receipt_1());
             })
             .for_each(drop);
     }
 
+    #[test]
+    fn should_encode_post_state_root_in_place_of_status_in_pre_byzantium_receipt() {
+        let root = H256::from_slice(&[0xc0; 32]);
+        let mut receipt = get_expected_receipt();
+        receipt.root = Some(root);
+        let result = rlp_encode_receipt(&receipt).unwrap();
+        let encoded_root: Bytes = Rlp::new(&result).val_at(0).unwrap();
+        assert!(encoded_root == root.as_bytes());
+        assert!(result != get_rlp_encoded_receipt_1());
+    }
+
+    #[test]
+    fn should_prefix_typed_receipt_encoding_with_tx_type() {
+        let mut receipt = get_expected_receipt();
+        receipt.tx_type = 0x02;
+        let result = rlp_encode_receipt(&receipt).unwrap();
+        assert!(result[0] == 0x02);
+        assert!(result[1..] == get_rlp_encoded_receipt_1()[..]);
+    }
+
+    #[test]
+    fn should_append_deposit_fields_to_optimism_deposit_receipt_encoding() {
+        let mut receipt = get_expected_receipt();
+        receipt.tx_type = 0x7e;
+        receipt.deposit_nonce = Some(U256::from(1337));
+        receipt.deposit_receipt_version = Some(U256::one());
+        let result = rlp_encode_receipt(&receipt).unwrap();
+        let decoded = Rlp::new(&result[1..]);
+        assert!(result[0] == 0x7e);
+        assert!(decoded.item_count().unwrap() == 6);
+        assert!(decoded.val_at::<U256>(4).unwrap() == U256::from(1337));
+        assert!(decoded.val_at::<U256>(5).unwrap() == U256::one());
+    }
+
+    #[test]
+    fn should_stream_encode_receipts_identically_to_rlp_stream() {
+        let mut pre_byzantium_receipt = get_expected_receipt_2();
+        pre_byzantium_receipt.root = Some(H256::from_slice(&[0xc0; 32]));
+        let mut deposit_receipt = get_expected_receipt();
+        deposit_receipt.tx_type = 0x7e;
+        deposit_receipt.deposit_nonce = Some(U256::zero());
+        deposit_receipt.deposit_receipt_version = Some(U256::one());
+        let mut long_log_receipt = get_expected_receipt_2();
+        long_log_receipt.logs[0].data = vec![0xff; 70_000];
+        long_log_receipt.logs[1].data = vec![0x01];
+        long_log_receipt.cumulative_gas_used = U256::zero();
+        let receipts = [
+            get_expected_receipt(),
+            get_expected_receipt_2(),
+            get_expected_receipt_3(),
+            pre_byzantium_receipt,
+            deposit_receipt,
+            long_log_receipt,
+        ];
+        let mut encoder = ReceiptEncoder::new();
+        receipts.iter().for_each(|receipt| {
+            let mut rlp_stream = RlpStream::new();
+            rlp_stream.append(receipt);
+            let expected_result = match receipt.tx_type {
+                LEGACY_TX_TYPE => rlp_stream.out(),
+                tx_type => [vec![tx_type], rlp_stream.out()].concat(),
+            };
+            assert!(encoder.encode(receipt) == &expected_result[..]);
+        });
+    }
+
     #[test]
     fn should_encode_tx_receipt() {
         let index_u256 = U256::from_dec_str("3").unwrap();
@@ -216,6 +795,20 @@ mod tests {
         assert!(result == expected_result);
     }
 
+    #[test]
+    fn should_get_storage_root_from_rlp_encoded_account() {
+        let storage_root = H256::from_slice(&[0xc0; 32]);
+        let rlp_encoded_account = rlp_encode_account(
+            &U256::one(),
+            &U256::from(1337),
+            &storage_root,
+            &H256::zero(),
+        )
+        .unwrap();
+        let result = get_storage_root_from_rlp_encoded_account(&rlp_encoded_account).unwrap();
+        assert!(result == storage_root);
+    }
+
     #[test]
     fn should_encode_tx_receipt_of_0() {
         let index_u256 = U256::from_dec_str("0").unwrap();
@@ -223,4 +816,54 @@ mod tests {
         let expected_result = vec![0x80];
         assert!(result == expected_result);
     }
+
+    #[test]
+    fn should_get_rlp_encoded_log_from_rlp_encoded_typed_receipt() {
+        let mut receipt = get_expected_receipt_2();
+        receipt.tx_type = 0x02;
+        let rlp_encoded_receipt = rlp_encode_receipt(&receipt).unwrap();
+        let result = get_rlp_encoded_log_from_rlp_encoded_receipt(&rlp_encoded_receipt, 1).unwrap();
+        assert!(result == rlp::encode(&receipt.logs[1]).to_vec());
+        let num_logs = receipt.logs.len();
+        match get_rlp_encoded_log_from_rlp_encoded_receipt(&rlp_encoded_receipt, num_logs) {
+            Err(AppError::Custom(e)) => assert!(e.contains("No log at index")),
+            _ => panic!("Should not get log beyond receipt's logs!"),
+        }
+    }
+
+    #[test]
+    fn should_decode_rlp_encoded_typed_receipt() {
+        let mut receipt = get_expected_receipt_2();
+        receipt.tx_type = 0x02;
+        let result = decode_receipt(&rlp_encode_receipt(&receipt).unwrap()).unwrap();
+        assert!(result.tx_type == 0x02);
+        assert!(result.status == receipt.status && result.root.is_none());
+        assert!(result.cumulative_gas_used == receipt.cumulative_gas_used);
+        assert!(result.logs_bloom == receipt.logs_bloom);
+        assert!(result.logs == receipt.logs);
+    }
+
+    #[test]
+    fn should_decode_receipts_back_to_their_consensus_fields() {
+        let mut deposit_receipt = get_expected_receipt_3();
+        deposit_receipt.tx_type = 0x7e;
+        deposit_receipt.deposit_nonce = Some(U256::from(7));
+        deposit_receipt.deposit_receipt_version = Some(U256::one());
+        let mut pre_byzantium_receipt = get_expected_receipt();
+        pre_byzantium_receipt.root = Some(H256::repeat_byte(0xab));
+        vec![
+            get_expected_receipt(),
+            deposit_receipt,
+            pre_byzantium_receipt,
+        ]
+        .into_iter()
+        .for_each(|receipt| {
+            let encoded = rlp_encode_receipt(&receipt).unwrap();
+            let result = decode_receipt(&encoded).unwrap();
+            assert!(rlp_encode_receipt(&result).unwrap() == encoded);
+            assert!(result.deposit_nonce == receipt.deposit_nonce);
+            assert!(result.deposit_receipt_version == receipt.deposit_receipt_version);
+            assert!(result.root == receipt.root);
+        });
+    }
 }
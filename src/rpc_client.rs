@@ -0,0 +1,409 @@
+use crate::constants::{
+    REQWEST_TIMEOUT_TIME, RPC_CONNECT_TIMEOUT_ENV_VAR, RPC_DOH_URL_ENV_VAR,
+    RPC_MAX_REQUESTS_PER_SECOND_ENV_VAR, RPC_NO_KEEP_ALIVE_ENV_VAR,
+    RPC_POOL_MAX_IDLE_PER_HOST_ENV_VAR, RPC_RESOLVE_ENV_VAR, RPC_TIMEOUT_ENV_VAR,
+};
+use crate::dns_resolution::{get_endpoint_domain, parse_host_pin, resolve_host_via_doh};
+use crate::errors::AppError;
+use crate::get_endpoint::maybe_run_dot_env;
+use crate::parse_cli_args::CliArgs;
+use crate::telemetry::Telemetry;
+use crate::types::Result;
+use reqwest::blocking::Client;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/**
+ * RPC Client:
+ *
+ * Archive nodes can take far longer than a local node to answer for old
+ * blocks, so the request & connect timeouts are configurable, as are the
+ * connection pool's idle connections per host & whether connections are kept
+ * alive for reuse at all, & RPC calls can be rate limited to stay under a
+ * provider's quota, or left uncompressed for providers w/ broken compression.
+ * Endpoint hosts may be pinned to IPs, or resolved via DoH, as per the
+ * `dns_resolution` module. Each setting comes from its CLI flag, else its env
+ * var, which may be set in the `.env` file like `ENDPOINT`, else the config
+ * file, else a default. One `RpcClient` is built from them per command &
+ * shared by every RPC call it makes, so that kept alive connections actually
+ * get reused, & the rate limit holds across the command's endpoints.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcClientConfig {
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub pool_max_idle_per_host: usize,
+    pub keep_alive: bool,
+    pub max_requests_per_second: Option<u32>,
+    pub host_pins: Vec<(String, IpAddr)>,
+    pub doh_url: Option<String>,
+    pub compression: bool,
+    pub telemetry: Telemetry,
+}
+
+impl Default for RpcClientConfig {
+    fn default() -> Self {
+        RpcClientConfig {
+            timeout: Duration::from_secs(REQWEST_TIMEOUT_TIME),
+            connect_timeout: Duration::from_secs(REQWEST_TIMEOUT_TIME),
+            pool_max_idle_per_host: usize::MAX,
+            keep_alive: true,
+            max_requests_per_second: None,
+            host_pins: vec![],
+            doh_url: None,
+            compression: true,
+            telemetry: Telemetry::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SharedRpcClient {
+    config: RpcClientConfig,
+    client: Client,
+}
+
+impl SharedRpcClient {
+    fn new(config: RpcClientConfig) -> Result<Self> {
+        Ok(SharedRpcClient {
+            client: build_rpc_client(&config)?,
+            config,
+        })
+    }
+
+    fn is_host_pinned(&self, host: &str) -> bool {
+        self.config
+            .host_pins
+            .iter()
+            .any(|(pinned_host, _)| pinned_host == host)
+    }
+
+    // NOTE: Rebuilding the client drops its pooled connections, but only once per newly resolved host.
+    fn get_client_for_endpoint(&mut self, endpoint: &str) -> Result<Client> {
+        if let (Some(doh_url), Some(host)) = (&self.config.doh_url, get_endpoint_domain(endpoint)) {
+            if !self.is_host_pinned(&host) {
+                let ip = resolve_host_via_doh(doh_url, &host, self.config.timeout)?;
+                self.config.host_pins.push((host, ip));
+                self.client = build_rpc_client(&self.config)?;
+            }
+        }
+        Ok(self.client.clone())
+    }
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+    request_interval: Option<Duration>,
+    next_request_time: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct RpcClient {
+    compression: bool,
+    telemetry: Telemetry,
+    shared_client: Mutex<SharedRpcClient>,
+    rate_limiter: Mutex<RateLimiter>,
+}
+
+fn get_env_var<T: FromStr>(env_var: &str) -> Result<Option<T>> {
+    match std::env::var(env_var) {
+        Ok(value) => value.trim().parse::<T>().map(Some).map_err(|_| {
+            AppError::Custom(format!(
+                "✘ Invalid value for env var {}: {}",
+                env_var, value
+            ))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn get_timeout(cli_secs: Option<u64>, env_var: &str, default: Duration) -> Result<Duration> {
+    match cli_secs.map_or_else(|| get_env_var::<u64>(env_var), |secs| Ok(Some(secs)))? {
+        Some(0) => Err(AppError::Custom(format!(
+            "✘ {} must be at least 1 second!",
+            env_var
+        ))),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Ok(default),
+    }
+}
+
+fn get_host_pins(cli_host_pins: &[String]) -> Result<Vec<(String, IpAddr)>> {
+    let host_pins = match cli_host_pins.is_empty() {
+        false => cli_host_pins.to_vec(),
+        true => get_env_var::<String>(RPC_RESOLVE_ENV_VAR)?
+            .map(|host_pins| host_pins.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+    };
+    host_pins
+        .iter()
+        .filter(|host_pin| !host_pin.trim().is_empty())
+        .map(|host_pin| parse_host_pin(host_pin))
+        .collect()
+}
+
+pub fn get_rpc_client_config(cli_args: &CliArgs) -> Result<RpcClientConfig> {
+    maybe_run_dot_env()?;
+    let default_config = RpcClientConfig::default();
+    Ok(RpcClientConfig {
+        timeout: get_timeout(
+            cli_args.timeout,
+            RPC_TIMEOUT_ENV_VAR,
+            default_config.timeout,
+        )?,
+        connect_timeout: get_timeout(
+            cli_args.connect_timeout,
+            RPC_CONNECT_TIMEOUT_ENV_VAR,
+            default_config.connect_timeout,
+        )?,
+        pool_max_idle_per_host: match cli_args.pool_max_idle_per_host {
+            Some(max) => max,
+            None => get_env_var(RPC_POOL_MAX_IDLE_PER_HOST_ENV_VAR)?
+                .unwrap_or(default_config.pool_max_idle_per_host),
+        },
+        keep_alive: !cli_args.no_keep_alive
+            && !get_env_var::<bool>(RPC_NO_KEEP_ALIVE_ENV_VAR)?.unwrap_or(false),
+        max_requests_per_second: match cli_args.max_requests_per_second {
+            Some(max) => Some(max),
+            None => get_env_var(RPC_MAX_REQUESTS_PER_SECOND_ENV_VAR)?,
+        }
+        .filter(|max| *max > 0),
+        host_pins: get_host_pins(&cli_args.resolve)?,
+        doh_url: match cli_args.doh_url {
+            Some(ref doh_url) => Some(doh_url.clone()),
+            None => get_env_var(RPC_DOH_URL_ENV_VAR)?,
+        },
+        compression: !cli_args.no_compression,
+        telemetry: cli_args.telemetry.clone(),
+    })
+}
+
+// NOTE: Reqwest's decompression features are left off, so we negotiate & decompress ourselves.
+fn build_rpc_client(config: &RpcClientConfig) -> Result<Client> {
+    // NOTE: Reqwest ignores a pinned address' port in favour of the endpoint's.
+    Ok(config
+        .host_pins
+        .iter()
+        .fold(Client::builder(), |builder, (host, ip)| {
+            builder.resolve(host, SocketAddr::new(*ip, 0))
+        })
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout)
+        .pool_max_idle_per_host(match config.keep_alive {
+            true => config.pool_max_idle_per_host,
+            false => 0,
+        })
+        .build()?)
+}
+
+impl RpcClient {
+    pub fn new(config: RpcClientConfig) -> Result<Self> {
+        info!(
+            "✔ RPC timeout: {}s, connect timeout: {}s, keep-alive: {}",
+            config.timeout.as_secs(),
+            config.connect_timeout.as_secs(),
+            config.keep_alive
+        );
+        if let Some(max) = config.max_requests_per_second {
+            info!("✔ Rate limiting RPC calls to {} per second", max);
+        }
+        config
+            .host_pins
+            .iter()
+            .for_each(|(host, ip)| info!("✔ Pinning RPC host {} to {}", host, ip));
+        if !config.compression {
+            info!("✔ RPC response compression disabled");
+        }
+        Ok(RpcClient {
+            compression: config.compression,
+            telemetry: config.telemetry.clone(),
+            rate_limiter: Mutex::new(RateLimiter {
+                request_interval: config
+                    .max_requests_per_second
+                    .map(|max| Duration::from_secs(1) / max),
+                next_request_time: None,
+            }),
+            shared_client: Mutex::new(SharedRpcClient::new(config)?),
+        })
+    }
+
+    pub fn is_compression_enabled(&self) -> bool {
+        self.compression
+    }
+
+    pub fn get_telemetry(&self) -> &Telemetry {
+        &self.telemetry
+    }
+
+    pub fn from_cli_args(cli_args: &CliArgs) -> Result<Self> {
+        get_rpc_client_config(cli_args).and_then(Self::new)
+    }
+
+    // NOTE: Reserves the next free slot under the lock but sleeps outside it, so calls queue fairly.
+    pub fn wait_for_rate_limit(&self) -> Result<()> {
+        let wait_time = {
+            let mut rate_limiter = self
+                .rate_limiter
+                .lock()
+                .map_err(|_| AppError::Custom("✘ Rate limiter lock poisoned!".to_string()))?;
+            let request_interval = match rate_limiter.request_interval {
+                Some(request_interval) => request_interval,
+                None => return Ok(()),
+            };
+            let now = Instant::now();
+            let request_time = rate_limiter
+                .next_request_time
+                .map_or(now, |next_request_time| next_request_time.max(now));
+            rate_limiter.next_request_time = Some(request_time + request_interval);
+            request_time - now
+        };
+        if !wait_time.is_zero() {
+            debug!("✔ Rate limited, waiting {}ms", wait_time.as_millis());
+            thread::sleep(wait_time);
+        }
+        Ok(())
+    }
+
+    pub fn get_client(&self, endpoint: &str) -> Result<Client> {
+        self.shared_client
+            .lock()
+            .map_err(|_| AppError::Custom("✘ RPC client lock poisoned!".to_string()))?
+            .get_client_for_endpoint(endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn get_cli_args(flags: &[&str]) -> CliArgs {
+        let mut args = vec!["rusty-receipt-proof-maker", "root", "latest"];
+        args.extend_from_slice(flags);
+        CliArgs::try_parse_from(args).unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn should_get_default_rpc_client_config() {
+        let result = get_rpc_client_config(&get_cli_args(&[])).unwrap();
+        assert!(result == RpcClientConfig::default());
+    }
+
+    #[test]
+    #[serial]
+    fn should_prefer_cli_flags_to_env_vars() {
+        std::env::set_var(RPC_TIMEOUT_ENV_VAR, "60");
+        std::env::set_var(RPC_CONNECT_TIMEOUT_ENV_VAR, "2");
+        let result = get_rpc_client_config(&get_cli_args(&[
+            "--timeout",
+            "120",
+            "--pool-max-idle-per-host",
+            "4",
+            "--no-keep-alive",
+            "--no-compression",
+        ]));
+        std::env::remove_var(RPC_TIMEOUT_ENV_VAR);
+        std::env::remove_var(RPC_CONNECT_TIMEOUT_ENV_VAR);
+        let expected_result = RpcClientConfig {
+            timeout: Duration::from_secs(120),
+            connect_timeout: Duration::from_secs(2),
+            pool_max_idle_per_host: 4,
+            keep_alive: false,
+            max_requests_per_second: None,
+            host_pins: vec![],
+            doh_url: None,
+            compression: false,
+            telemetry: Telemetry::default(),
+        };
+        assert!(result.unwrap() == expected_result);
+    }
+
+    #[test]
+    #[serial]
+    fn should_fail_to_get_rpc_client_config_w_invalid_env_var() {
+        std::env::set_var(RPC_TIMEOUT_ENV_VAR, "soon");
+        let result = get_rpc_client_config(&get_cli_args(&[]));
+        std::env::remove_var(RPC_TIMEOUT_ENV_VAR);
+        match result {
+            Err(AppError::Custom(e)) => assert!(e.contains(RPC_TIMEOUT_ENV_VAR)),
+            _ => panic!("Should fail w/ invalid env var!"),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn should_get_host_pins_from_env_var_unless_flagged() {
+        std::env::set_var(
+            RPC_RESOLVE_ENV_VAR,
+            "a.example.com=203.0.113.7, b.example.com=::1",
+        );
+        let env_result = get_rpc_client_config(&get_cli_args(&[]));
+        let cli_result =
+            get_rpc_client_config(&get_cli_args(&["--resolve", "c.example.com=10.0.0.1"]));
+        std::env::remove_var(RPC_RESOLVE_ENV_VAR);
+        assert!(env_result.unwrap().host_pins.len() == 2);
+        assert!(
+            cli_result.unwrap().host_pins
+                == vec![("c.example.com".to_string(), "10.0.0.1".parse().unwrap())]
+        );
+    }
+
+    #[test]
+    fn should_connect_to_pinned_ip_w_original_host() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let host = request
+                .headers()
+                .iter()
+                .find(|header| header.field.equiv("Host"))
+                .map(|header| header.value.to_string());
+            request.respond(tiny_http::Response::empty(200)).unwrap();
+            host
+        });
+        let config = RpcClientConfig {
+            host_pins: vec![(
+                "rpc.example.invalid".to_string(),
+                "127.0.0.1".parse().unwrap(),
+            )],
+            ..RpcClientConfig::default()
+        };
+        let url = format!("http://rpc.example.invalid:{}/", port);
+        let result = build_rpc_client(&config).unwrap().get(&url).send().unwrap();
+        assert!(result.status().is_success());
+        assert!(handle.join().unwrap() == Some(format!("rpc.example.invalid:{}", port)));
+    }
+
+    #[test]
+    fn should_rate_limit_calls_per_rpc_client() {
+        let config = RpcClientConfig {
+            max_requests_per_second: Some(20),
+            ..RpcClientConfig::default()
+        };
+        let rate_limited_client = RpcClient::new(config).unwrap();
+        let other_client = RpcClient::new(RpcClientConfig::default()).unwrap();
+        let start_time = Instant::now();
+        (0..3).for_each(|_| rate_limited_client.wait_for_rate_limit().unwrap());
+        assert!(start_time.elapsed() >= Duration::from_millis(100));
+        let start_time = Instant::now();
+        (0..3).for_each(|_| other_client.wait_for_rate_limit().unwrap());
+        assert!(start_time.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn should_fail_to_parse_zero_timeout_flag() {
+        let result = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "root",
+            "latest",
+            "--timeout",
+            "0",
+        ]);
+        assert!(result.is_err());
+    }
+}
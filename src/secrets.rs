@@ -0,0 +1,217 @@
+use crate::constants::{
+    API_KEY_ENV_VAR, API_KEY_PLACEHOLDER, MIN_SECRET_LENGTH_IN_URL, REDACTED_STRING,
+};
+use crate::errors::AppError;
+use crate::types::Result;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+static SECRETS_TO_REDACT: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+pub fn register_secret(secret: &str) {
+    if let Ok(mut secrets) = SECRETS_TO_REDACT.write() {
+        if !secret.is_empty() && !secrets.iter().any(|s| s == secret) {
+            secrets.push(secret.to_string());
+        }
+    }
+}
+
+pub fn redact_secrets(message: &str) -> String {
+    match SECRETS_TO_REDACT.read() {
+        Err(_) => message.to_string(),
+        Ok(secrets) => secrets
+            .iter()
+            .fold(message.to_string(), |redacted, secret| {
+                redacted.replace(secret, REDACTED_STRING)
+            }),
+    }
+}
+
+fn looks_like_secret(url_part: &str) -> bool {
+    url_part.len() >= MIN_SECRET_LENGTH_IN_URL
+        && url_part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn register_secrets_in_endpoint(endpoint: &str) {
+    // NOTE: Providers embed keys in the path or query (or userinfo) ∴ redact key-like parts.
+    if let Ok(url) = reqwest::Url::parse(endpoint) {
+        if let Some(password) = url.password() {
+            register_secret(password);
+        }
+        url.path_segments()
+            .into_iter()
+            .flatten()
+            .chain(url.query().into_iter().flat_map(|q| q.split('&')))
+            .map(|part| part.rsplit('=').next().unwrap_or(part))
+            .filter(|part| looks_like_secret(part))
+            .for_each(register_secret);
+    }
+}
+
+fn read_api_key_from_file(path: &str) -> Result<String> {
+    match fs::read_to_string(path)?.trim() {
+        "" => Err(AppError::Custom(format!(
+            "✘ API key file is empty: {}",
+            path
+        ))),
+        api_key => Ok(api_key.to_string()),
+    }
+}
+
+pub fn get_api_key(api_key_file: &Option<String>) -> Result<Option<String>> {
+    let api_key = match api_key_file {
+        Some(path) => Some(read_api_key_from_file(path)?),
+        None => std::env::var(API_KEY_ENV_VAR).ok(),
+    };
+    if let Some(key) = &api_key {
+        register_secret(key);
+    }
+    Ok(api_key)
+}
+
+pub fn insert_api_key_into_endpoint(endpoint: &str, api_key: &Option<String>) -> Result<String> {
+    match (endpoint.contains(API_KEY_PLACEHOLDER), api_key) {
+        (true, Some(key)) => Ok(endpoint.replace(API_KEY_PLACEHOLDER, key)),
+        (true, None) => Err(AppError::Custom(format!(
+            "✘ Endpoint contains {} but no API key was provided!",
+            API_KEY_PLACEHOLDER
+        ))),
+        (false, Some(_)) => {
+            warn!(
+                "✘ API key provided but endpoint has no {}!",
+                API_KEY_PLACEHOLDER
+            );
+            Ok(endpoint.to_string())
+        }
+        (false, None) => Ok(endpoint.to_string()),
+    }
+}
+
+// NOTE: Lets long-running modes rotate keys w/o a restart: `kill -HUP` then `maybe_reload`.
+pub struct SecretsStore {
+    api_key_file: Option<String>,
+    api_key: RwLock<Option<String>>,
+    reload_requested: Arc<AtomicBool>,
+}
+
+impl SecretsStore {
+    pub fn new(api_key_file: Option<String>) -> Result<SecretsStore> {
+        Ok(SecretsStore {
+            api_key: RwLock::new(get_api_key(&api_key_file)?),
+            reload_requested: Arc::new(AtomicBool::new(false)),
+            api_key_file,
+        })
+    }
+
+    pub fn get_api_key(&self) -> Option<String> {
+        self.api_key.read().ok().and_then(|key| key.clone())
+    }
+
+    pub fn reload(&self) -> Result<()> {
+        info!("✔ Reloading API key...");
+        let new_api_key = get_api_key(&self.api_key_file)?;
+        match self.api_key.write() {
+            Ok(mut api_key) => {
+                *api_key = new_api_key;
+                Ok(())
+            }
+            Err(_) => Err(AppError::Custom("✘ API key lock poisoned!".to_string())),
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn reload_on_sighup(&self) -> Result<()> {
+        signal_hook::flag::register(
+            signal_hook::consts::SIGHUP,
+            Arc::clone(&self.reload_requested),
+        )?;
+        Ok(())
+    }
+
+    pub fn request_reload(&self) {
+        self.reload_requested.store(true, Ordering::SeqCst)
+    }
+
+    pub fn maybe_reload(&self) -> Result<bool> {
+        match self.reload_requested.swap(false, Ordering::SeqCst) {
+            true => self.reload().map(|_| true),
+            false => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_sample_api_key_file(name: &str, api_key: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-api-key-{}", name));
+        fs::write(&path, format!("{}\n", api_key)).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn should_redact_registered_secrets() {
+        let secret = "c0ffeec0ffeec0ffeec0ffee01";
+        register_secret(secret);
+        let result = redact_secrets(&format!("✘ Error calling: https://x.io/v3/{}", secret));
+        assert!(result == "✘ Error calling: https://x.io/v3/<redacted>");
+    }
+
+    #[test]
+    fn should_register_key_like_parts_of_endpoint() {
+        let key = "9aa3d95b3bc440fa88ea12eaa4456161";
+        let password = "hunter2";
+        register_secrets_in_endpoint(&format!(
+            "https://user:{}@mainnet.infura.io/v3/{}?apikey={}",
+            password, key, key
+        ));
+        let result = redact_secrets(&format!("{} {} mainnet v3", key, password));
+        assert!(result == "<redacted> <redacted> mainnet v3");
+    }
+
+    #[test]
+    fn should_not_register_short_path_segments_of_endpoint() {
+        register_secrets_in_endpoint("http://localhost:8545/rpc/v1");
+        assert!(redact_secrets("http://localhost:8545/rpc/v1") == "http://localhost:8545/rpc/v1");
+    }
+
+    #[test]
+    fn should_insert_api_key_into_endpoint() {
+        let api_key = Some("abc".to_string());
+        let result = insert_api_key_into_endpoint("https://x.io/v3/{API_KEY}", &api_key).unwrap();
+        assert!(result == "https://x.io/v3/abc");
+    }
+
+    #[test]
+    fn should_fail_to_insert_missing_api_key_into_endpoint() {
+        assert!(insert_api_key_into_endpoint("https://x.io/v3/{API_KEY}", &None).is_err());
+    }
+
+    #[test]
+    fn should_get_api_key_from_file_and_redact_it() {
+        let path = write_sample_api_key_file("get", "file-key-0000000001");
+        let result = get_api_key(&Some(path.clone())).unwrap();
+        assert!(result == Some("file-key-0000000001".to_string()));
+        assert!(redact_secrets("file-key-0000000001") == REDACTED_STRING);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_reload_rotated_api_key_when_requested() {
+        let path = write_sample_api_key_file("rotate", "old-key-0000000001");
+        let store = SecretsStore::new(Some(path.clone())).unwrap();
+        assert!(store.get_api_key() == Some("old-key-0000000001".to_string()));
+        fs::write(&path, "new-key-0000000001").unwrap();
+        assert!(!store.maybe_reload().unwrap());
+        assert!(store.get_api_key() == Some("old-key-0000000001".to_string()));
+        store.request_reload();
+        assert!(store.maybe_reload().unwrap());
+        assert!(store.get_api_key() == Some("new-key-0000000001".to_string()));
+        assert!(redact_secrets("old-key-0000000001 new-key-0000000001") == "<redacted> <redacted>");
+        fs::remove_file(path).unwrap();
+    }
+}
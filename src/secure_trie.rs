@@ -0,0 +1,141 @@
+use crate::errors::AppError;
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::trie::Trie;
+use crate::trie_keys::{SecureKey, TrieKey};
+use crate::types::{Byte, Bytes, NodeStack, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex};
+use ethereum_types::H256;
+
+/**
+ * Secure Tries:
+ *
+ * Receipts tries are keyed by tx index as is, but state & storage tries, &
+ * secure tries generally, key each value by the keccak hash of its raw key,
+ * e.g. an account's address or a storage slot, so an attacker can't choose
+ * keys which deepen the trie. This wraps a `Trie` so callers put, get &
+ * prove by raw key, w/ the hashing done for them, so the trie can rebuild
+ * account & storage tries as well as receipts ones. The wrapped trie is
+ * still there for all else, e.g. exporting it or absorbing it into another.
+ */
+#[derive(Clone)]
+pub struct SecureTrie {
+    pub trie: Trie,
+}
+
+impl SecureTrie {
+    pub fn new() -> Result<Self> {
+        Trie::get_new_trie().map(Self::from_trie)
+    }
+
+    pub fn from_trie(trie: Trie) -> Self {
+        SecureTrie { trie }
+    }
+
+    pub fn root(&self) -> H256 {
+        self.trie.root
+    }
+
+    pub fn put(mut self, raw_key: &[Byte], value: Bytes) -> Result<Self> {
+        self.put_mut(raw_key, value).map(|_| self)
+    }
+
+    pub fn put_mut(&mut self, raw_key: &[Byte], value: Bytes) -> Result<()> {
+        self.trie
+            .put_mut(SecureKey::from_raw_key(raw_key)?.to_nibbles(), value)
+    }
+
+    pub fn get(&self, raw_key: &[Byte]) -> Result<Option<Bytes>> {
+        self.trie
+            .get(SecureKey::from_raw_key(raw_key)?.to_nibbles())
+    }
+
+    pub fn contains_key(&self, raw_key: &[Byte]) -> Result<bool> {
+        self.trie
+            .contains_key(SecureKey::from_raw_key(raw_key)?.to_nibbles())
+    }
+
+    pub fn get_branch(&self, raw_key: &[Byte]) -> Result<NodeStack> {
+        match self.contains_key(raw_key)? {
+            true => Ok(self
+                .trie
+                .find(SecureKey::from_raw_key(raw_key)?.to_nibbles())?
+                .1),
+            false => Err(AppError::Custom(format!(
+                "✘ No value in secure trie w/ root {} under key: {}",
+                convert_h256_to_prefixed_hex(self.root())?,
+                convert_bytes_to_prefixed_hex(raw_key)
+            ))),
+        }
+    }
+
+    pub fn get_proof(&self, raw_key: &[Byte]) -> Result<Bytes> {
+        self.get_branch(raw_key)
+            .and_then(|branch| rlp_encode_node_stack(&branch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlp_codec::Decoder;
+    use crate::trie::put_in_trie_recursively;
+    use crate::trie_keys::StateKey;
+    use crate::verifier::verify_merkle_proof;
+    use ethereum_types::Address;
+
+    fn get_sample_addresses() -> Vec<Address> {
+        (1..=8u8)
+            .map(|byte| Address::from_slice(&[byte; 20]))
+            .collect()
+    }
+
+    fn get_sample_secure_trie() -> SecureTrie {
+        get_sample_addresses()
+            .iter()
+            .enumerate()
+            .try_fold(SecureTrie::new().unwrap(), |trie, (i, address)| {
+                trie.put(address.as_bytes(), vec![0xc0, i as Byte])
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn should_build_same_trie_as_w_state_keys() {
+        let key_value_tuples = get_sample_addresses()
+            .iter()
+            .enumerate()
+            .map(|(i, address)| {
+                (
+                    StateKey::from_address(address).unwrap(),
+                    vec![0xc0, i as Byte],
+                )
+            })
+            .collect::<Vec<(StateKey, Bytes)>>();
+        let expected_trie =
+            put_in_trie_recursively(Trie::get_new_trie().unwrap(), key_value_tuples, 0).unwrap();
+        assert!(get_sample_secure_trie().root() == expected_trie.root);
+    }
+
+    #[test]
+    fn should_get_and_prove_values_by_raw_key() {
+        let trie = get_sample_secure_trie();
+        let addresses = get_sample_addresses();
+        let missing_address = Address::from_slice(&[0xff; 20]);
+        assert!(trie.get(addresses[3].as_bytes()).unwrap() == Some(vec![0xc0, 3]));
+        assert!(trie.get(missing_address.as_bytes()).unwrap().is_none());
+        assert!(!trie.contains_key(missing_address.as_bytes()).unwrap());
+        assert!(trie.get_proof(missing_address.as_bytes()).is_err());
+        let proof = trie.get_proof(addresses[3].as_bytes()).unwrap();
+        let proof_nodes: Vec<Bytes> = Decoder::new(&proof)
+            .iter()
+            .map(|node| node.get_raw().to_vec())
+            .collect();
+        let key = SecureKey::from_raw_key(addresses[3].as_bytes())
+            .unwrap()
+            .to_nibbles()
+            .to_nibble_values()
+            .unwrap();
+        let result = verify_merkle_proof(trie.root().as_fixed_bytes(), &key, &proof_nodes);
+        assert!(result.unwrap() == vec![0xc0, 3]);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::audit_log::{append_entry_to_audit_log, get_audit_log_entry_from_proof_bundle};
+use crate::build_metadata::{get_build_metadata, set_build_metadata_in_proof_bundle};
+use crate::errors::AppError;
+use crate::finality::{maybe_check_block_finality, set_finality_in_proof_bundle};
+use crate::gas_estimate::{
+    maybe_read_gas_model_file, maybe_set_gas_estimate_in_proof_bundle, GasModel,
+};
+use crate::get_block::get_block_by_block_id;
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::get_endpoint::get_endpoint_template;
+use crate::get_receipts::{check_receipt_fields, get_receipt_from_tx_hash};
+use crate::get_receipts_trie::get_receipts_trie_of_block;
+use crate::input_parsing::{normalize_block_id, normalize_tx_hash};
+use crate::json_rpc_client::register_http_json_rpc_client;
+use crate::metrics::{
+    increment_counter, observe_histogram, render_metrics, HTTP_REQUESTS_TOTAL,
+    HTTP_REQUEST_DURATION_SECONDS, METRICS_CONTENT_TYPE, TRIE_CACHE_HITS_TOTAL,
+    TRIE_CACHE_MISSES_TOTAL,
+};
+use crate::parse_cli_args::CliArgs;
+use crate::proof_bundle::{
+    get_proof_bundle, get_proof_bundle_json, maybe_compress_proof_in_proof_bundle,
+    maybe_hash_value_in_proof_bundle,
+};
+use crate::proof_expiry::{get_proof_expiry, set_proof_expiry_in_proof_bundle};
+use crate::proof_signing::{
+    maybe_read_proof_signing_key, maybe_sign_proof_bundle, ProofSigningKey,
+};
+use crate::rpc_client::RpcClient;
+use crate::secrets::{
+    insert_api_key_into_endpoint, redact_secrets, register_secrets_in_endpoint, SecretsStore,
+};
+use crate::telemetry::TelemetryEvent;
+use crate::trie::Trie;
+use crate::trie_cache::TrieCache;
+use crate::trusted_headers::{maybe_read_trusted_headers_file, TrustedHeaders};
+use crate::types::{Block, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+use crate::verify_proof::{parse_proof_bundles, verify_proof_bundles};
+use serde_json::Value as Json;
+use std::io::Read;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::info_span;
+
+const NUM_WORKER_THREADS: usize = 8;
+const TRIE_CACHE_CAPACITY: usize = 64;
+const MAX_REQUEST_BODY_SIZE: u64 = 16 * 1024 * 1024;
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/**
+ * Serve:
+ *
+ * Spawning the binary per proof pays for a process, a connection & a whole
+ * receipts trie every time. The daemon instead serves proofs over HTTP from
+ * a pool of workers, keeping the tries of recently proven blocks in an LRU
+ * cache keyed by block hash, so a relayer proving many receipts of one block
+ * fetches its receipts once. Responses are always JSON, honouring `--light`,
+ * `--value-hash`, `--valid-for` & `--audit-log` as the one-shot commands do.
+ * `kill -HUP` reloads the API key from `--api-key-file` w/o a restart.
+ * `GET /metrics` exposes the process' metrics for Prometheus to scrape.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Route {
+    ProveReceipt(String),
+    ProveReceiptInBlock(String, usize),
+    Verify,
+    Metrics,
+    BadRequest(String),
+    MethodNotAllowed,
+    NotFound,
+}
+
+pub fn get_route(method: &Method, url: &str) -> Route {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>();
+    let route = match segments.as_slice() {
+        ["proof", "receipt", tx_hash] => match normalize_tx_hash(tx_hash) {
+            Ok(tx_hash) => Route::ProveReceipt(tx_hash),
+            Err(e) => Route::BadRequest(e.to_string()),
+        },
+        ["proof", "block", block_id, "index", index] => {
+            match (normalize_block_id(block_id), index.parse::<usize>()) {
+                (Ok(block_id), Ok(index)) => Route::ProveReceiptInBlock(block_id, index),
+                (Err(e), _) => Route::BadRequest(e.to_string()),
+                (_, Err(_)) => Route::BadRequest(format!("✘ Invalid tx index: {}", index)),
+            }
+        }
+        ["verify"] => Route::Verify,
+        ["metrics"] => Route::Metrics,
+        _ => return Route::NotFound,
+    };
+    let expected_method = match route {
+        Route::Verify => Method::Post,
+        _ => Method::Get,
+    };
+    match *method == expected_method {
+        true => route,
+        false => Route::MethodNotAllowed,
+    }
+}
+
+fn get_route_name(route: &Route) -> &'static str {
+    match route {
+        Route::ProveReceipt(_) => "prove_receipt",
+        Route::ProveReceiptInBlock(..) => "prove_receipt_in_block",
+        Route::Verify => "verify",
+        Route::Metrics => "metrics",
+        Route::BadRequest(_) => "bad_request",
+        Route::MethodNotAllowed => "method_not_allowed",
+        Route::NotFound => "not_found",
+    }
+}
+
+fn get_error_json(error: &AppError) -> Json {
+    json!({ "error": redact_secrets(error.to_string().trim()) })
+}
+
+pub struct ProofServer<'a> {
+    cli_args: &'a CliArgs,
+    endpoint_template: String,
+    secrets: SecretsStore,
+    trie_cache: Mutex<TrieCache>,
+    maybe_signing_key: Option<ProofSigningKey>,
+    gas_model: GasModel,
+    maybe_trusted_headers: Option<TrustedHeaders>,
+    rpc_client: Arc<RpcClient>,
+}
+
+impl<'a> ProofServer<'a> {
+    pub fn new(cli_args: &'a CliArgs) -> Result<Self> {
+        let rpc_client = Arc::new(RpcClient::from_cli_args(cli_args)?);
+        if let Some(endpoint) = &cli_args.cross_check {
+            register_http_json_rpc_client(endpoint, &rpc_client);
+        }
+        Ok(ProofServer {
+            cli_args,
+            endpoint_template: get_endpoint_template(cli_args)?,
+            secrets: SecretsStore::new(cli_args.api_key_file.clone())?,
+            trie_cache: Mutex::new(TrieCache::new(TRIE_CACHE_CAPACITY)),
+            maybe_signing_key: maybe_read_proof_signing_key(
+                &cli_args.sign_key,
+                cli_args.sign_scheme,
+            )?,
+            gas_model: maybe_read_gas_model_file(&cli_args.gas_model)?.unwrap_or_default(),
+            maybe_trusted_headers: maybe_read_trusted_headers_file(&cli_args.trusted_headers)?,
+            rpc_client,
+        })
+    }
+
+    fn get_endpoint(&self) -> Result<String> {
+        self.secrets.maybe_reload()?;
+        insert_api_key_into_endpoint(&self.endpoint_template, &self.secrets.get_api_key()).inspect(
+            |endpoint| {
+                register_secrets_in_endpoint(endpoint);
+                register_http_json_rpc_client(endpoint, &self.rpc_client);
+            },
+        )
+    }
+
+    fn lock_trie_cache(&self) -> Result<MutexGuard<'_, TrieCache>> {
+        self.trie_cache
+            .lock()
+            .map_err(|_| AppError::Custom("✘ Trie cache lock poisoned!".to_string()))
+    }
+
+    fn get_receipts_trie(&self, endpoint: &str, block: &Block) -> Result<Arc<Trie>> {
+        if let Some(trie) = self.lock_trie_cache()?.get(&block.hash) {
+            debug!("✔ Trie cache hit for block {}", block.number);
+            increment_counter(TRIE_CACHE_HITS_TOTAL, &[]);
+            return Ok(trie);
+        }
+        increment_counter(TRIE_CACHE_MISSES_TOTAL, &[]);
+        // NOTE: The lock isn't held while building, so a slow block doesn't stall the others.
+        let trie = Arc::new(get_receipts_trie_of_block(
+            endpoint,
+            block,
+            self.cli_args,
+            &self.maybe_trusted_headers,
+        )?);
+        self.lock_trie_cache()?.insert(block.hash, trie.clone());
+        Ok(trie)
+    }
+
+    fn get_proof(
+        &self,
+        endpoint: &str,
+        block: &Block,
+        index: usize,
+        requester: &Option<String>,
+    ) -> Result<Json> {
+        if index >= block.transactions.len() {
+            return Err(AppError::Custom(format!(
+                "✘ No transaction in block at index: {}",
+                index
+            )));
+        }
+        let trie = self.get_receipts_trie(endpoint, block)?;
+        let maybe_expiry = match self.cli_args.light {
+            true => None,
+            false => Some(get_proof_expiry(endpoint, &self.cli_args.valid_for)?),
+        };
+        let maybe_finality = maybe_check_block_finality(endpoint, block, self.cli_args)?;
+        let maybe_build_metadata = match self.cli_args.light {
+            true => None,
+            false => Some(get_build_metadata(endpoint, &trie)?),
+        };
+        let bundle = get_branch_from_trie(&trie, index)
+            .and_then(|branch| get_proof_bundle(block, trie.root, index, &branch))
+            .map(|bundle| set_proof_expiry_in_proof_bundle(bundle, &maybe_expiry))
+            .map(|bundle| set_finality_in_proof_bundle(bundle, &maybe_finality))
+            .map(|bundle| set_build_metadata_in_proof_bundle(bundle, &maybe_build_metadata))
+            .and_then(|bundle| {
+                maybe_hash_value_in_proof_bundle(bundle, self.cli_args.value_hash)
+            })?;
+        if let Some(path) = &self.cli_args.audit_log {
+            get_audit_log_entry_from_proof_bundle(&bundle, requester.clone())
+                .and_then(|entry| append_entry_to_audit_log(path, &entry))?;
+        }
+        let bundle = maybe_compress_proof_in_proof_bundle(bundle, self.cli_args.compress_proofs)
+            .and_then(|bundle| maybe_set_gas_estimate_in_proof_bundle(bundle, &self.gas_model))
+            .and_then(|bundle| maybe_sign_proof_bundle(bundle, &self.maybe_signing_key))?;
+        self.cli_args
+            .telemetry
+            .emit_telemetry_event(TelemetryEvent::ProofGenerated {
+                proof_type: "receipt",
+                num_proofs: 1,
+            });
+        get_proof_bundle_json(&bundle, self.cli_args.light)
+    }
+
+    fn prove_receipt(&self, tx_hash: &str, requester: &Option<String>) -> Result<Json> {
+        let endpoint = self.get_endpoint()?;
+        let receipt = get_receipt_from_tx_hash(&endpoint, tx_hash)?;
+        check_receipt_fields(&receipt, self.cli_args.strict_receipts)?;
+        let block = get_block_by_block_id(
+            &endpoint,
+            &convert_h256_to_prefixed_hex(receipt.block_hash)?,
+        )?;
+        self.get_proof(
+            &endpoint,
+            &block,
+            receipt.transaction_index.as_usize(),
+            requester,
+        )
+    }
+
+    fn prove_receipt_in_block(
+        &self,
+        block_id: &str,
+        index: usize,
+        requester: &Option<String>,
+    ) -> Result<Json> {
+        let endpoint = self.get_endpoint()?;
+        let block = get_block_by_block_id(&endpoint, block_id)?;
+        self.get_proof(&endpoint, &block, index, requester)
+    }
+
+    pub fn handle_route(
+        &self,
+        route: Route,
+        body: &str,
+        requester: &Option<String>,
+    ) -> (u16, Json) {
+        match route {
+            Route::ProveReceipt(tx_hash) => match self.prove_receipt(&tx_hash, requester) {
+                Ok(json) => (200, json),
+                Err(e) => (502, get_error_json(&e)),
+            },
+            Route::ProveReceiptInBlock(block_id, index) => {
+                match self.prove_receipt_in_block(&block_id, index, requester) {
+                    Ok(json) => (200, json),
+                    Err(e) => (502, get_error_json(&e)),
+                }
+            }
+            Route::Verify => match parse_proof_bundles(body) {
+                Err(e) => (400, get_error_json(&e)),
+                Ok(bundles) => match verify_proof_bundles(&bundles, &self.cli_args.telemetry) {
+                    Ok(_) => (
+                        200,
+                        json!({ "verified": true, "num_proofs": bundles.len() }),
+                    ),
+                    Err(e) => {
+                        let mut json = get_error_json(&e);
+                        json["verified"] = json!(false);
+                        (422, json)
+                    }
+                },
+            },
+            // NOTE: Served as plain text over HTTP, but wrapped here for library callers wanting JSON.
+            Route::Metrics => (200, json!({ "metrics": render_metrics() })),
+            Route::BadRequest(e) => (400, get_error_json(&AppError::Custom(e))),
+            Route::MethodNotAllowed => (405, json!({ "error": "✘ Method not allowed!" })),
+            Route::NotFound => (404, json!({ "error": "✘ Not found!" })),
+        }
+    }
+
+    fn handle_request(&self, mut request: Request) -> Result<()> {
+        let start_time = Instant::now();
+        let route = get_route(request.method(), request.url());
+        let route_name = get_route_name(&route);
+        let _span = info_span!("http_request", route = route_name).entered();
+        let requester = request.remote_addr().map(|address| address.to_string());
+        let mut body = String::new();
+        if route == Route::Verify {
+            request
+                .as_reader()
+                .take(MAX_REQUEST_BODY_SIZE)
+                .read_to_string(&mut body)?;
+        }
+        let (status_code, response_body, content_type) = match route {
+            Route::Metrics => (200, render_metrics(), METRICS_CONTENT_TYPE),
+            route => {
+                let (status_code, json) = self.handle_route(route, &body, &requester);
+                (status_code, json.to_string(), JSON_CONTENT_TYPE)
+            }
+        };
+        increment_counter(
+            HTTP_REQUESTS_TOTAL,
+            &[("route", route_name), ("status", &status_code.to_string())],
+        );
+        observe_histogram(
+            HTTP_REQUEST_DURATION_SECONDS,
+            &[("route", route_name)],
+            start_time.elapsed().as_secs_f64(),
+        );
+        info!(
+            "✔ {} {} ➜ {}",
+            request.method(),
+            redact_secrets(request.url()),
+            status_code
+        );
+        let content_type = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes())
+            .map_err(|_| AppError::Custom("✘ Invalid response header!".to_string()))?;
+        Ok(request.respond(
+            Response::from_string(response_body)
+                .with_status_code(status_code)
+                .with_header(content_type),
+        )?)
+    }
+}
+
+pub fn serve(cli_args: &CliArgs, host: &str, port: u16) -> Result<String> {
+    let proof_server = ProofServer::new(cli_args)?;
+    #[cfg(unix)]
+    proof_server.secrets.reload_on_sighup()?;
+    let http_server = Server::http((host, port)).map_err(|e| {
+        AppError::Custom(format!("✘ Could not listen on {}:{}!\n✘ {}", host, port, e))
+    })?;
+    info!("✔ Serving proofs on http://{}:{}...", host, port);
+    std::thread::scope(|scope| {
+        (0..NUM_WORKER_THREADS).for_each(|_| {
+            scope.spawn(|| {
+                for request in http_server.incoming_requests() {
+                    if let Err(e) = proof_server.handle_request(request) {
+                        warn!("✘ Could not respond to request: {}", e);
+                    }
+                }
+            });
+        })
+    });
+    Ok("✔ Server stopped!".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{get_sample_proof_bundle_1, SAMPLE_TX_HASH};
+    use clap::Parser;
+
+    fn get_sample_cli_args() -> CliArgs {
+        CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker", "serve"]).unwrap()
+    }
+
+    #[test]
+    fn should_get_routes() {
+        let tx_hash_path = format!("/proof/receipt/{}", SAMPLE_TX_HASH);
+        assert!(
+            get_route(&Method::Get, &tx_hash_path) == Route::ProveReceipt(SAMPLE_TX_HASH.into())
+        );
+        assert!(
+            get_route(&Method::Get, "/proof/block/8233333/index/7?x=y")
+                == Route::ProveReceiptInBlock("8233333".to_string(), 7)
+        );
+        assert!(
+            get_route(
+                &Method::Get,
+                &format!("/proof/receipt/{}", &SAMPLE_TX_HASH[2..])
+            ) == Route::ProveReceipt(SAMPLE_TX_HASH.into())
+        );
+        assert!(get_route(&Method::Post, "/verify") == Route::Verify);
+        assert!(get_route(&Method::Get, "/verify") == Route::MethodNotAllowed);
+        assert!(get_route(&Method::Get, "/metrics") == Route::Metrics);
+        assert!(get_route(&Method::Post, "/metrics") == Route::MethodNotAllowed);
+        assert!(get_route(&Method::Post, &tx_hash_path) == Route::MethodNotAllowed);
+        assert!(get_route(&Method::Get, "/proof/receipt/") == Route::NotFound);
+        assert!(get_route(&Method::Get, "/proofs") == Route::NotFound);
+    }
+
+    #[test]
+    fn should_get_bad_request_routes_for_invalid_params() {
+        let bad_tx_hash = get_route(&Method::Get, "/proof/receipt/0xc0ffee");
+        let bad_block = get_route(&Method::Get, "/proof/block/0xg0ffee/index/1");
+        let bad_index = get_route(&Method::Get, "/proof/block/latest/index/-1");
+        [bad_tx_hash, bad_block, bad_index]
+            .iter()
+            .for_each(|route| match route {
+                Route::BadRequest(_) => (),
+                _ => panic!("Should be a bad request route!"),
+            });
+    }
+
+    #[test]
+    fn should_verify_posted_proof_bundles() {
+        let cli_args = get_sample_cli_args();
+        let server = ProofServer::new(&cli_args).unwrap();
+        let bundle = get_sample_proof_bundle_1();
+        let body = serde_json::to_string(&vec![bundle.clone(), bundle]).unwrap();
+        let (status_code, json) = server.handle_route(Route::Verify, &body, &None);
+        assert!(status_code == 200);
+        assert!(json["verified"] == true);
+        assert!(json["num_proofs"] == 2);
+    }
+
+    #[test]
+    fn should_fail_to_verify_bad_posted_proof_bundles() {
+        let cli_args = get_sample_cli_args();
+        let server = ProofServer::new(&cli_args).unwrap();
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.proof.pop();
+        let body = serde_json::to_string(&bundle).unwrap();
+        let (status_code, json) = server.handle_route(Route::Verify, &body, &None);
+        assert!(status_code == 422);
+        assert!(json["verified"] == false);
+        let (status_code, _) = server.handle_route(Route::Verify, "not json", &None);
+        assert!(status_code == 400);
+    }
+
+    #[test]
+    fn should_serve_metrics() {
+        let cli_args = get_sample_cli_args();
+        let server = ProofServer::new(&cli_args).unwrap();
+        let (status_code, json) = server.handle_route(Route::Metrics, "", &None);
+        assert!(status_code == 200);
+        let type_line = format!("# TYPE {} counter", TRIE_CACHE_HITS_TOTAL);
+        assert!(json["metrics"].as_str().unwrap().contains(&type_line));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::errors::AppError;
+use crate::metrics::{increment_counter, RPC_DEDUPLICATED_TOTAL};
+use crate::types::Result;
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+type FlightResult<T> = std::result::Result<T, String>;
+
+/**
+ * Single Flight:
+ *
+ * The daemon's workers proving receipts of the same block at once would each
+ * fetch that block & its receipts, hitting the endpoint w/ storms of identical
+ * requests. Instead, the first caller for a key leads the fetch whilst any
+ * others arriving before it's done wait for & share its result. Nothing is
+ * cached past the flight: a key fetched again later is fetched anew. Errors
+ * are shared too, as their messages, since one failing fetch means all would.
+ */
+struct Flight<T> {
+    result: Mutex<Option<FlightResult<T>>>,
+    done: Condvar,
+}
+
+pub struct SingleFlight<T> {
+    flights: Mutex<BTreeMap<String, Arc<Flight<T>>>>,
+}
+
+// NOTE: Lands the flight even if the leader panics, so its followers are never left waiting.
+struct FlightGuard<'a, T> {
+    single_flight: &'a SingleFlight<T>,
+    key: String,
+    flight: Arc<Flight<T>>,
+}
+
+impl<T> Drop for FlightGuard<'_, T> {
+    fn drop(&mut self) {
+        lock(&self.single_flight.flights).remove(&self.key);
+        let mut result = lock(&self.flight.result);
+        if result.is_none() {
+            *result = Some(Err("✘ In-flight fetch was abandoned!".to_string()));
+        }
+        self.flight.done.notify_all();
+    }
+}
+
+// NOTE: Flights hold no invariants a panicking holder could break, so poisoning is ignored.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl<T: Clone> SingleFlight<T> {
+    pub const fn new() -> Self {
+        SingleFlight {
+            flights: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn num_in_flight(&self) -> usize {
+        lock(&self.flights).len()
+    }
+
+    pub fn run<F>(&self, key: String, fetch: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let mut flights = lock(&self.flights);
+        if let Some(flight) = flights.get(&key).cloned() {
+            drop(flights);
+            return wait_for_flight(&flight);
+        }
+        let flight = Arc::new(Flight {
+            result: Mutex::new(None),
+            done: Condvar::new(),
+        });
+        flights.insert(key.clone(), flight.clone());
+        drop(flights);
+        let guard = FlightGuard {
+            single_flight: self,
+            key,
+            flight,
+        };
+        let result = fetch();
+        *lock(&guard.flight.result) = Some(match result {
+            Ok(ref value) => Ok(value.clone()),
+            Err(ref e) => Err(e.to_string()),
+        });
+        result
+    }
+}
+
+impl<T: Clone> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn wait_for_flight<T: Clone>(flight: &Flight<T>) -> Result<T> {
+    let mut result = lock(&flight.result);
+    while result.is_none() {
+        result = flight
+            .done
+            .wait(result)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+    match result.as_ref() {
+        Some(Ok(value)) => Ok(value.clone()),
+        Some(Err(e)) => Err(AppError::Custom(e.clone())),
+        None => unreachable!(),
+    }
+}
+
+// NOTE: Request ids are ignored, since requests differing only by id fetch the same thing.
+pub fn get_rpc_flight_key(endpoint: &str, rpc_json: &Json) -> String {
+    format!("{} {} {}", endpoint, rpc_json["method"], rpc_json["params"])
+}
+
+pub fn run_counted_flight<T, F>(
+    single_flight: &SingleFlight<T>,
+    key: String,
+    method: &str,
+    fetch: F,
+) -> Result<T>
+where
+    T: Clone,
+    F: FnOnce() -> Result<T>,
+{
+    let mut is_leader = false;
+    let result = single_flight.run(key, || {
+        is_leader = true;
+        fetch()
+    });
+    if !is_leader {
+        debug!("✔ Shared an in-flight {} fetch", method);
+        increment_counter(RPC_DEDUPLICATED_TOTAL, &[("method", method)]);
+    }
+    result
+}
+
+pub fn run_rpc_flight<T, F>(
+    single_flight: &SingleFlight<T>,
+    endpoint: &str,
+    rpc_json: Json,
+    fetch: F,
+) -> Result<T>
+where
+    T: Clone,
+    F: FnOnce(Json) -> Result<T>,
+{
+    let key = get_rpc_flight_key(endpoint, &rpc_json);
+    let method = rpc_json["method"].as_str().unwrap_or("unknown").to_string();
+    run_counted_flight(single_flight, key, &method, || fetch(rpc_json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn should_share_one_fetch_between_concurrent_callers() {
+        let num_callers = 8;
+        let single_flight = Arc::new(SingleFlight::new());
+        let num_fetches = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(num_callers));
+        let handles = (0..num_callers)
+            .map(|_| {
+                let single_flight = single_flight.clone();
+                let num_fetches = num_fetches.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    single_flight.run("block".to_string(), || {
+                        num_fetches.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(200));
+                        Ok(42)
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap().unwrap())
+            .collect::<Vec<u64>>();
+        assert!(results.iter().all(|result| *result == 42));
+        assert!(num_fetches.load(Ordering::SeqCst) == 1);
+        assert!(single_flight.num_in_flight() == 0);
+    }
+
+    #[test]
+    fn should_fetch_anew_once_flight_has_landed() {
+        let single_flight = SingleFlight::new();
+        let mut num_fetches = 0;
+        single_flight.run("block".to_string(), || Ok(1)).unwrap();
+        let result = single_flight
+            .run("block".to_string(), || {
+                num_fetches += 1;
+                Ok(2)
+            })
+            .unwrap();
+        assert!(result == 2);
+        assert!(num_fetches == 1);
+    }
+
+    #[test]
+    fn should_share_errors_with_waiting_callers() {
+        let single_flight = Arc::new(SingleFlight::<u64>::new());
+        let leader_single_flight = single_flight.clone();
+        let leader = thread::spawn(move || {
+            leader_single_flight.run("block".to_string(), || {
+                thread::sleep(Duration::from_millis(200));
+                Err(AppError::Custom("✘ Node unreachable!".to_string()))
+            })
+        });
+        while single_flight.num_in_flight() == 0 {
+            thread::yield_now();
+        }
+        let follower_result = single_flight.run("block".to_string(), || Ok(1));
+        assert!(leader.join().unwrap().is_err());
+        match follower_result {
+            Err(AppError::Custom(e)) => assert!(e == "✘ Node unreachable!"),
+            _ => panic!("Follower should share the leader's error!"),
+        }
+    }
+
+    #[test]
+    fn should_ignore_request_ids_in_rpc_flight_keys() {
+        let rpc_json_1 =
+            json!({"id": 1, "method": "eth_getBlockByNumber", "params": ["0x1", false]});
+        let rpc_json_2 =
+            json!({"id": 2, "method": "eth_getBlockByNumber", "params": ["0x1", false]});
+        let rpc_json_3 =
+            json!({"id": 1, "method": "eth_getBlockByNumber", "params": ["0x2", false]});
+        let endpoint = "http://localhost:8545";
+        assert!(
+            get_rpc_flight_key(endpoint, &rpc_json_1) == get_rpc_flight_key(endpoint, &rpc_json_2)
+        );
+        assert!(
+            get_rpc_flight_key(endpoint, &rpc_json_1) != get_rpc_flight_key(endpoint, &rpc_json_3)
+        );
+    }
+}
@@ -0,0 +1,587 @@
+use crate::chain_profile::{LEGACY_TX_TYPE, OPTIMISM_DEPOSIT_TX_TYPE};
+use crate::constants::HASH_LENGTH;
+use crate::errors::AppError;
+use crate::logs_bloom::convert_bytes_to_bloom;
+use crate::proof_bundle::{decode_proof_nodes, ProofBundle};
+use crate::rlp_codec::decode_receipt;
+use crate::types::{Byte, Bytes, Log, Receipt, ReceiptRpcFields, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, convert_hex_to_h256, decode_prefixed_hex};
+use ethereum_types::{Address, H256, U256};
+use std::convert::TryFrom;
+
+pub const MAX_TOPICS_PER_LOG: usize = 4;
+pub const MAX_LOG_DATA_SIZE: usize = 1 << 24;
+pub const MAX_LOGS_PER_RECEIPT: usize = 1 << 21;
+pub const MAX_PROOF_NODES: usize = 1 << 6;
+pub const MAX_PROOF_NODE_SIZE: usize = 1 << 25;
+pub const MAX_RECEIPT_PROOFS: usize = 1 << 20;
+pub const PRE_BYZANTIUM_RECEIPT_SELECTOR: Byte = 0x7f;
+
+const OFFSET_LENGTH: usize = 4;
+const UINT64_LENGTH: usize = 8;
+const BLOOM_LENGTH: usize = 256;
+const ADDRESS_LENGTH: usize = 20;
+const MAX_UNION_SELECTOR: Byte = 0x7f;
+
+/**
+ * SSZ Proofs:
+ *
+ * Consensus-layer light clients speak SSZ, not RLP or JSON, so w/ `--format
+ * ssz` receipt proofs are output as one hex string of the SSZ serialization
+ * of a `List[ReceiptProof, MAX_RECEIPT_PROOFS]`, each proof a container of:
+ *
+ *   block_hash: Bytes32, block_number: uint64, receipts_root: Bytes32,
+ *   tx_hash: Bytes32, tx_index: uint64, receipt: Receipt,
+ *   proof: List[ByteList[MAX_PROOF_NODE_SIZE], MAX_PROOF_NODES]
+ *
+ * The receipt is decoded from the proven value into an SSZ union, whose
+ * selector is the receipt's EIP-2718 tx type, `0` for legacy receipts. Every
+ * type's receipt is a `(status: boolean, cumulative_gas_used: uint64,
+ * logs_bloom: ByteVector[256], logs: List[Log, MAX_LOGS_PER_RECEIPT])`, each
+ * log an `(address: Bytes20, topics: List[Bytes32, 4], data: ByteList)`, bar
+ * two: Optimism deposit receipts, under their type `0x7e`, add their deposit
+ * nonce & receipt version, each a `List[uint64, 1]` since either may be
+ * absent, & pre-Byzantium receipts, under selector `0x7f`, the last an SSZ
+ * union may use, have a `post_state_root: Bytes32` in place of a status. The
+ * proof nodes are those of the bundle, i.e. the RLP encoded trie nodes from
+ * root to leaf, so they need the receipt itself & can't be compressed.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SszReceiptProof {
+    pub block_hash: H256,
+    pub block_number: u64,
+    pub receipts_root: H256,
+    pub tx_hash: H256,
+    pub tx_index: u64,
+    pub receipt: Receipt,
+    pub proof: Vec<Bytes>,
+}
+
+enum SszField {
+    Fixed(Bytes),
+    Variable(Bytes),
+}
+
+fn check_length(name: &str, length: usize, max_length: usize) -> Result<()> {
+    match length <= max_length {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ SSZ {} too long: {} > {}!",
+            name, length, max_length
+        ))),
+    }
+}
+
+fn encode_uint64(value: u64) -> Bytes {
+    value.to_le_bytes().to_vec()
+}
+
+fn convert_u256_to_u64(name: &str, value: U256) -> Result<u64> {
+    match value > U256::from(u64::MAX) {
+        true => Err(AppError::Custom(format!(
+            "✘ SSZ {} doesn't fit a uint64: {}!",
+            name, value
+        ))),
+        false => Ok(value.as_u64()),
+    }
+}
+
+fn encode_offset(offset: usize) -> Result<Bytes> {
+    u32::try_from(offset)
+        .map(|offset| offset.to_le_bytes().to_vec())
+        .map_err(|_| AppError::Custom(format!("✘ SSZ offset too large: {}!", offset)))
+}
+
+// NOTE: Variable sized fields are replaced by offsets to them, & appended after the fixed ones.
+fn encode_container(fields: Vec<SszField>) -> Result<Bytes> {
+    let fixed_length = fields
+        .iter()
+        .map(|field| match field {
+            SszField::Fixed(bytes) => bytes.len(),
+            SszField::Variable(_) => OFFSET_LENGTH,
+        })
+        .sum::<usize>();
+    let mut fixed_part = Vec::with_capacity(fixed_length);
+    let mut variable_part = Vec::new();
+    for field in fields {
+        match field {
+            SszField::Fixed(bytes) => fixed_part.extend_from_slice(&bytes),
+            SszField::Variable(bytes) => {
+                fixed_part.extend_from_slice(&encode_offset(fixed_length + variable_part.len())?);
+                variable_part.extend_from_slice(&bytes);
+            }
+        }
+    }
+    Ok([fixed_part, variable_part].concat())
+}
+
+fn encode_variable_size_list(items: Vec<Bytes>) -> Result<Bytes> {
+    encode_container(items.into_iter().map(SszField::Variable).collect())
+}
+
+fn encode_optional_uint64(maybe_value: &Option<U256>, name: &str) -> Result<Bytes> {
+    match maybe_value {
+        Some(value) => convert_u256_to_u64(name, *value).map(encode_uint64),
+        None => Ok(vec![]),
+    }
+}
+
+fn encode_log(log: &Log) -> Result<Bytes> {
+    check_length("log topics", log.topics.len(), MAX_TOPICS_PER_LOG)?;
+    check_length("log data", log.data.len(), MAX_LOG_DATA_SIZE)?;
+    encode_container(vec![
+        SszField::Fixed(log.address.as_bytes().to_vec()),
+        SszField::Variable(
+            log.topics
+                .iter()
+                .flat_map(|topic| topic.as_bytes().to_vec())
+                .collect(),
+        ),
+        SszField::Variable(log.data.clone()),
+    ])
+}
+
+pub fn get_receipt_union_selector(receipt: &Receipt) -> Byte {
+    match receipt.root {
+        Some(_) => PRE_BYZANTIUM_RECEIPT_SELECTOR,
+        None => receipt.tx_type,
+    }
+}
+
+pub fn encode_receipt_to_ssz(receipt: &Receipt) -> Result<Bytes> {
+    check_length("receipt logs", receipt.logs.len(), MAX_LOGS_PER_RECEIPT)?;
+    let selector = get_receipt_union_selector(receipt);
+    if selector > MAX_UNION_SELECTOR {
+        return Err(AppError::Custom(format!(
+            "✘ Receipts of tx type 0x{:02x} have no SSZ union selector!",
+            selector
+        )));
+    }
+    let status_or_root = match receipt.root {
+        Some(root) => root.as_bytes().to_vec(),
+        None => vec![receipt.status as Byte],
+    };
+    let mut fields = vec![
+        SszField::Fixed(status_or_root),
+        SszField::Fixed(encode_uint64(convert_u256_to_u64(
+            "cumulative gas used",
+            receipt.cumulative_gas_used,
+        )?)),
+        SszField::Fixed(receipt.logs_bloom.as_bytes().to_vec()),
+        SszField::Variable(encode_variable_size_list(
+            receipt
+                .logs
+                .iter()
+                .map(encode_log)
+                .collect::<Result<Vec<Bytes>>>()?,
+        )?),
+    ];
+    if selector == OPTIMISM_DEPOSIT_TX_TYPE {
+        fields.push(SszField::Variable(encode_optional_uint64(
+            &receipt.deposit_nonce,
+            "deposit nonce",
+        )?));
+        fields.push(SszField::Variable(encode_optional_uint64(
+            &receipt.deposit_receipt_version,
+            "deposit receipt version",
+        )?));
+    }
+    Ok([vec![selector], encode_container(fields)?].concat())
+}
+
+pub fn get_ssz_receipt_proof(bundle: &ProofBundle) -> Result<SszReceiptProof> {
+    // NOTE: The receipt is decoded from the proven value, & SSZ consumers walk the proof themselves.
+    match bundle.value.is_empty() || bundle.compressed {
+        true => Err(AppError::Custom(
+            "✘ SSZ proofs need the receipt & uncompressed proof nodes!".to_string(),
+        )),
+        false => Ok(SszReceiptProof {
+            block_hash: convert_hex_to_h256(bundle.block_hash.clone())?,
+            block_number: bundle.block_number,
+            receipts_root: convert_hex_to_h256(bundle.receipts_root.clone())?,
+            tx_hash: convert_hex_to_h256(bundle.tx_hash.clone())?,
+            tx_index: bundle.tx_index as u64,
+            receipt: decode_receipt(&decode_prefixed_hex(bundle.value.clone())?)?,
+            proof: decode_proof_nodes(&bundle.proof)?,
+        }),
+    }
+}
+
+pub fn encode_ssz_receipt_proof(ssz_proof: &SszReceiptProof) -> Result<Bytes> {
+    check_length("proof", ssz_proof.proof.len(), MAX_PROOF_NODES)?;
+    ssz_proof
+        .proof
+        .iter()
+        .try_for_each(|node| check_length("proof node", node.len(), MAX_PROOF_NODE_SIZE))?;
+    encode_container(vec![
+        SszField::Fixed(ssz_proof.block_hash.as_bytes().to_vec()),
+        SszField::Fixed(encode_uint64(ssz_proof.block_number)),
+        SszField::Fixed(ssz_proof.receipts_root.as_bytes().to_vec()),
+        SszField::Fixed(ssz_proof.tx_hash.as_bytes().to_vec()),
+        SszField::Fixed(encode_uint64(ssz_proof.tx_index)),
+        SszField::Variable(encode_receipt_to_ssz(&ssz_proof.receipt)?),
+        SszField::Variable(encode_variable_size_list(ssz_proof.proof.clone())?),
+    ])
+}
+
+pub fn encode_proof_bundles_to_ssz(bundles: &[ProofBundle]) -> Result<Bytes> {
+    check_length("receipt proofs", bundles.len(), MAX_RECEIPT_PROOFS)?;
+    bundles
+        .iter()
+        .map(|bundle| {
+            get_ssz_receipt_proof(bundle).and_then(|proof| encode_ssz_receipt_proof(&proof))
+        })
+        .collect::<Result<Vec<Bytes>>>()
+        .and_then(encode_variable_size_list)
+}
+
+pub fn format_proof_bundles_as_ssz(bundles: &[ProofBundle]) -> Result<String> {
+    encode_proof_bundles_to_ssz(bundles).map(|encoded| convert_bytes_to_prefixed_hex(&encoded))
+}
+
+fn get_ssz_decoding_err(what: &str) -> AppError {
+    AppError::Custom(format!("✘ Could not decode SSZ {}!", what))
+}
+
+fn decode_offset(bytes: &[Byte]) -> Result<usize> {
+    let mut offset = [0u8; OFFSET_LENGTH];
+    offset.copy_from_slice(bytes);
+    Ok(u32::from_le_bytes(offset) as usize)
+}
+
+fn decode_uint64(bytes: &[Byte]) -> u64 {
+    let mut value = [0u8; UINT64_LENGTH];
+    value.copy_from_slice(bytes);
+    u64::from_le_bytes(value)
+}
+
+fn decode_boolean(bytes: &[Byte]) -> Result<bool> {
+    match bytes {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(get_ssz_decoding_err("boolean")),
+    }
+}
+
+// NOTE: Field sizes of `None` are variable sized fields, found via their offsets.
+fn decode_container<'a>(
+    bytes: &'a [Byte],
+    field_sizes: &[Option<usize>],
+) -> Result<Vec<&'a [Byte]>> {
+    let fixed_length = field_sizes
+        .iter()
+        .map(|size| size.unwrap_or(OFFSET_LENGTH))
+        .sum::<usize>();
+    if bytes.len() < fixed_length {
+        return Err(get_ssz_decoding_err("container: too short"));
+    }
+    let mut position = 0;
+    let mut fields = Vec::with_capacity(field_sizes.len());
+    let mut offsets = Vec::new();
+    for size in field_sizes {
+        match size {
+            Some(size) => fields.push(Some(&bytes[position..position + size])),
+            None => {
+                offsets.push((
+                    fields.len(),
+                    decode_offset(&bytes[position..position + OFFSET_LENGTH])?,
+                ));
+                fields.push(None);
+            }
+        }
+        position += size.unwrap_or(OFFSET_LENGTH);
+    }
+    for (position, (index, offset)) in offsets.iter().enumerate() {
+        let end = offsets
+            .get(position + 1)
+            .map(|(_, next_offset)| *next_offset)
+            .unwrap_or(bytes.len());
+        if (position == 0 && *offset != fixed_length) || *offset > end || end > bytes.len() {
+            return Err(get_ssz_decoding_err("container: bad offset"));
+        }
+        fields[*index] = Some(&bytes[*offset..end]);
+    }
+    if offsets.is_empty() && bytes.len() != fixed_length {
+        return Err(get_ssz_decoding_err("container: too long"));
+    }
+    Ok(fields.into_iter().flatten().collect())
+}
+
+fn decode_variable_size_list(bytes: &[Byte], max_length: usize) -> Result<Vec<&[Byte]>> {
+    if bytes.is_empty() {
+        return Ok(vec![]);
+    }
+    let first_offset = decode_offset(
+        bytes
+            .get(..OFFSET_LENGTH)
+            .ok_or_else(|| get_ssz_decoding_err("list"))?,
+    )?;
+    if first_offset == 0 || !first_offset.is_multiple_of(OFFSET_LENGTH) {
+        return Err(get_ssz_decoding_err("list: bad offset"));
+    }
+    let num_items = first_offset / OFFSET_LENGTH;
+    check_length("list", num_items, max_length)?;
+    decode_container(bytes, &vec![None; num_items])
+}
+
+fn decode_fixed_size_list(
+    bytes: &[Byte],
+    item_size: usize,
+    max_length: usize,
+) -> Result<Vec<&[Byte]>> {
+    if !bytes.len().is_multiple_of(item_size) {
+        return Err(get_ssz_decoding_err(
+            "list: length not a multiple of its items'",
+        ));
+    }
+    check_length("list", bytes.len() / item_size, max_length)?;
+    Ok(bytes.chunks(item_size).collect())
+}
+
+fn decode_optional_uint64(bytes: &[Byte]) -> Result<Option<U256>> {
+    decode_fixed_size_list(bytes, UINT64_LENGTH, 1)
+        .map(|items| items.first().map(|item| U256::from(decode_uint64(item))))
+}
+
+fn decode_log(bytes: &[Byte]) -> Result<Log> {
+    let fields = decode_container(bytes, &[Some(ADDRESS_LENGTH), None, None])?;
+    check_length("log data", fields[2].len(), MAX_LOG_DATA_SIZE)?;
+    Ok(Log {
+        address: Address::from_slice(fields[0]),
+        topics: decode_fixed_size_list(fields[1], HASH_LENGTH, MAX_TOPICS_PER_LOG)?
+            .into_iter()
+            .map(H256::from_slice)
+            .collect(),
+        data: fields[2].to_vec(),
+    })
+}
+
+pub fn decode_receipt_from_ssz(bytes: &[Byte]) -> Result<Receipt> {
+    let (selector, body) = bytes
+        .split_first()
+        .ok_or_else(|| get_ssz_decoding_err("receipt: no union selector"))?;
+    let status_or_root_size = match *selector {
+        PRE_BYZANTIUM_RECEIPT_SELECTOR => HASH_LENGTH,
+        _ => 1,
+    };
+    let mut field_sizes = vec![
+        Some(status_or_root_size),
+        Some(UINT64_LENGTH),
+        Some(BLOOM_LENGTH),
+        None,
+    ];
+    if *selector == OPTIMISM_DEPOSIT_TX_TYPE {
+        field_sizes.extend_from_slice(&[None, None]);
+    }
+    let fields = decode_container(body, &field_sizes)?;
+    let (tx_type, status, root) = match *selector {
+        PRE_BYZANTIUM_RECEIPT_SELECTOR => {
+            (LEGACY_TX_TYPE, false, Some(H256::from_slice(fields[0])))
+        }
+        selector if selector > MAX_UNION_SELECTOR => {
+            return Err(get_ssz_decoding_err("receipt: union selector out of range"))
+        }
+        selector => (selector, decode_boolean(fields[0])?, None),
+    };
+    let (deposit_nonce, deposit_receipt_version) = match *selector == OPTIMISM_DEPOSIT_TX_TYPE {
+        true => (
+            decode_optional_uint64(fields[4])?,
+            decode_optional_uint64(fields[5])?,
+        ),
+        false => (None, None),
+    };
+    Ok(Receipt {
+        tx_type,
+        status,
+        root,
+        cumulative_gas_used: U256::from(decode_uint64(fields[1])),
+        logs_bloom: convert_bytes_to_bloom(fields[2])?,
+        logs: decode_variable_size_list(fields[3], MAX_LOGS_PER_RECEIPT)?
+            .into_iter()
+            .map(decode_log)
+            .collect::<Result<Vec<Log>>>()?,
+        deposit_nonce,
+        deposit_receipt_version,
+        to: None,
+        from: Address::zero(),
+        gas_used: U256::zero(),
+        block_hash: H256::zero(),
+        transaction_hash: H256::zero(),
+        block_number: U256::zero(),
+        transaction_index: U256::zero(),
+        contract_address: None,
+        rpc_fields: ReceiptRpcFields::default(),
+    })
+}
+
+pub fn decode_ssz_receipt_proof(bytes: &[Byte]) -> Result<SszReceiptProof> {
+    let fields = decode_container(
+        bytes,
+        &[
+            Some(HASH_LENGTH),
+            Some(UINT64_LENGTH),
+            Some(HASH_LENGTH),
+            Some(HASH_LENGTH),
+            Some(UINT64_LENGTH),
+            None,
+            None,
+        ],
+    )?;
+    let proof = decode_variable_size_list(fields[6], MAX_PROOF_NODES)?;
+    proof
+        .iter()
+        .try_for_each(|node| check_length("proof node", node.len(), MAX_PROOF_NODE_SIZE))?;
+    Ok(SszReceiptProof {
+        block_hash: H256::from_slice(fields[0]),
+        block_number: decode_uint64(fields[1]),
+        receipts_root: H256::from_slice(fields[2]),
+        tx_hash: H256::from_slice(fields[3]),
+        tx_index: decode_uint64(fields[4]),
+        receipt: decode_receipt_from_ssz(fields[5])?,
+        proof: proof.into_iter().map(|node| node.to_vec()).collect(),
+    })
+}
+
+pub fn decode_ssz_receipt_proofs(bytes: &[Byte]) -> Result<Vec<SszReceiptProof>> {
+    decode_variable_size_list(bytes, MAX_RECEIPT_PROOFS)?
+        .into_iter()
+        .map(decode_ssz_receipt_proof)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+    use crate::proof_bundle::compress_proof_in_proof_bundle;
+    use crate::prove_block::get_proof_bundles_for_all_receipts;
+    use crate::test_utils::{
+        get_expected_block, get_expected_receipt, get_sample_receipts, get_sample_tx_hashes_1,
+        PROOF_1_INDEX, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::utils::convert_hex_strings_to_h256s;
+
+    fn get_sample_proof_bundles() -> Vec<ProofBundle> {
+        let mut block = get_expected_block();
+        let tx_hashes = get_sample_tx_hashes_1();
+        let receipts =
+            get_sample_receipts(SAMPLE_RECEIPT_JSONS_1_PATH.to_string(), tx_hashes.clone());
+        block.transactions = convert_hex_strings_to_h256s(tx_hashes).unwrap();
+        let trie = get_receipts_trie_from_receipts(&receipts).unwrap();
+        get_proof_bundles_for_all_receipts(&block, &trie).unwrap()
+    }
+
+    // NOTE: Only a receipt's consensus fields are serialized, so only those round trip.
+    fn get_consensus_receipt(receipt: Receipt) -> Receipt {
+        Receipt {
+            to: None,
+            from: Address::zero(),
+            gas_used: U256::zero(),
+            block_hash: H256::zero(),
+            transaction_hash: H256::zero(),
+            block_number: U256::zero(),
+            transaction_index: U256::zero(),
+            contract_address: None,
+            rpc_fields: ReceiptRpcFields::default(),
+            ..receipt
+        }
+    }
+
+    #[test]
+    fn should_serialize_container_w_offsets_to_variable_size_fields() {
+        let result = encode_container(vec![
+            SszField::Fixed(encode_uint64(0x0102)),
+            SszField::Variable(vec![0xaa, 0xbb]),
+            SszField::Variable(vec![0xcc]),
+        ])
+        .unwrap();
+        let expected_result = vec![
+            0x02, 0x01, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 18, 0, 0, 0, 0xaa, 0xbb, 0xcc,
+        ];
+        assert!(result == expected_result);
+        let fields = decode_container(&result, &[Some(UINT64_LENGTH), None, None]).unwrap();
+        assert!(fields == vec![&result[..8], &[0xaa, 0xbb][..], &[0xcc][..]]);
+    }
+
+    #[test]
+    fn should_fail_to_decode_container_w_bad_offsets() {
+        let mut encoded = encode_container(vec![
+            SszField::Variable(vec![0xaa, 0xbb]),
+            SszField::Variable(vec![0xcc]),
+        ])
+        .unwrap();
+        encoded[0] = 7;
+        assert!(decode_container(&encoded, &[None, None]).is_err());
+        assert!(decode_container(&[0, 0], &[Some(UINT64_LENGTH)]).is_err());
+        assert!(decode_container(&[0; 9], &[Some(UINT64_LENGTH)]).is_err());
+    }
+
+    #[test]
+    fn should_serialize_receipt_as_union_keyed_by_tx_type() {
+        let receipt = get_consensus_receipt(get_expected_receipt());
+        let encoded = encode_receipt_to_ssz(&receipt).unwrap();
+        assert!(encoded[0] == LEGACY_TX_TYPE);
+        assert!(encoded[1] == receipt.status as Byte);
+        assert!(decode_receipt_from_ssz(&encoded).unwrap() == receipt);
+        let typed_receipt = Receipt {
+            tx_type: 0x02,
+            ..receipt.clone()
+        };
+        let encoded = encode_receipt_to_ssz(&typed_receipt).unwrap();
+        assert!(encoded[0] == 0x02);
+        assert!(decode_receipt_from_ssz(&encoded).unwrap() == typed_receipt);
+    }
+
+    #[test]
+    fn should_serialize_pre_byzantium_and_deposit_receipts_under_own_selectors() {
+        let receipt = get_consensus_receipt(get_expected_receipt());
+        let pre_byzantium_receipt = Receipt {
+            root: Some(H256::repeat_byte(0xc0)),
+            status: false,
+            ..receipt.clone()
+        };
+        let encoded = encode_receipt_to_ssz(&pre_byzantium_receipt).unwrap();
+        assert!(encoded[0] == PRE_BYZANTIUM_RECEIPT_SELECTOR);
+        assert!(decode_receipt_from_ssz(&encoded).unwrap() == pre_byzantium_receipt);
+        let deposit_receipt = Receipt {
+            tx_type: OPTIMISM_DEPOSIT_TX_TYPE,
+            deposit_nonce: Some(U256::from(1337)),
+            deposit_receipt_version: None,
+            ..receipt
+        };
+        let encoded = encode_receipt_to_ssz(&deposit_receipt).unwrap();
+        assert!(encoded[0] == OPTIMISM_DEPOSIT_TX_TYPE);
+        assert!(decode_receipt_from_ssz(&encoded).unwrap() == deposit_receipt);
+    }
+
+    #[test]
+    fn should_round_trip_proof_bundles_through_ssz() {
+        let bundles = get_sample_proof_bundles();
+        let encoded = encode_proof_bundles_to_ssz(&bundles).unwrap();
+        let result = decode_ssz_receipt_proofs(&encoded).unwrap();
+        assert!(result.len() == bundles.len());
+        let bundle = &bundles[PROOF_1_INDEX];
+        let ssz_proof = &result[PROOF_1_INDEX];
+        assert!(*ssz_proof == get_ssz_receipt_proof(bundle).unwrap());
+        assert!(ssz_proof.tx_index == PROOF_1_INDEX as u64);
+        assert!(ssz_proof.proof == decode_proof_nodes(&bundle.proof).unwrap());
+        assert!(
+            ssz_proof.receipt
+                == decode_receipt(&decode_prefixed_hex(bundle.value.clone()).unwrap()).unwrap()
+        );
+        let hex = format_proof_bundles_as_ssz(&bundles).unwrap();
+        assert!(decode_prefixed_hex(hex).unwrap() == encoded);
+    }
+
+    #[test]
+    fn should_fail_to_get_ssz_proof_of_compressed_or_value_hash_bundles() {
+        let bundle = get_sample_proof_bundles()[PROOF_1_INDEX].clone();
+        let compressed_bundle = compress_proof_in_proof_bundle(bundle.clone()).unwrap();
+        assert!(format_proof_bundles_as_ssz(&[compressed_bundle]).is_err());
+        let value_hash_bundle = ProofBundle {
+            value: String::new(),
+            ..bundle
+        };
+        assert!(format_proof_bundles_as_ssz(&[value_hash_bundle]).is_err());
+    }
+}
@@ -1,8 +1,15 @@
+use crate::batch_tuner::BatchTuner;
+use crate::chain_profile::ChainProfile;
 use crate::errors::AppError;
+use crate::gas_estimate::GasModel;
+use crate::hardfork::Hardfork;
+use crate::proof_signing::ProofSigningKey;
 use crate::trie::Trie;
+use crate::trusted_headers::TrustedHeaders;
 use crate::types::{Block, Database, NodeStack, Receipt, Result};
 use crate::utils::{get_no_overwrite_state_err, get_not_in_state_err};
 use ethereum_types::H256;
+use std::sync::Arc;
 
 pub struct State {
     pub tx_hash: H256,
@@ -11,9 +18,21 @@ pub struct State {
     pub index: Option<usize>,
     pub tx_hash_string: String,
     pub endpoint: Option<String>,
+    pub cross_check_endpoint: Option<String>,
     pub branch: Option<NodeStack>,
     pub receipts_trie: Option<Trie>,
     pub receipts: Option<Vec<Receipt>>,
+    pub audit_log_path: Option<String>,
+    pub trie_root_audit_path: Option<String>,
+    pub trie_cache_dir: Option<String>,
+    pub hardfork: Option<Hardfork>,
+    pub chain_profile: Option<ChainProfile>,
+    pub lenient_lookups: bool,
+    pub strict_receipts: bool,
+    pub batch_tuner: Arc<BatchTuner>,
+    pub proof_signing_key: Option<ProofSigningKey>,
+    pub gas_model: Option<GasModel>,
+    pub trusted_headers: Option<TrustedHeaders>,
 }
 
 impl State {
@@ -24,9 +43,21 @@ impl State {
             index: None,
             branch: None,
             endpoint: None,
+            cross_check_endpoint: None,
             receipts: None,
             tx_hash_string,
             receipts_trie: None,
+            audit_log_path: None,
+            trie_root_audit_path: None,
+            trie_cache_dir: None,
+            hardfork: None,
+            chain_profile: None,
+            lenient_lookups: false,
+            strict_receipts: false,
+            batch_tuner: Arc::new(BatchTuner::default()),
+            proof_signing_key: None,
+            gas_model: None,
+            trusted_headers: None,
             database: std::collections::HashMap::new(),
         })
     }
@@ -61,6 +92,21 @@ impl State {
         }
     }
 
+    pub fn set_cross_check_endpoint_in_state(
+        mut self,
+        cross_check_endpoint: String,
+    ) -> Result<State> {
+        match self.cross_check_endpoint {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "cross_check_endpoint",
+            ))),
+            None => {
+                self.cross_check_endpoint = Some(cross_check_endpoint);
+                Ok(self)
+            }
+        }
+    }
+
     pub fn set_receipts_in_state(mut self, receipts: Vec<Receipt>) -> Result<State> {
         match self.receipts {
             Some(_) => Err(AppError::Custom(get_no_overwrite_state_err("receipts"))),
@@ -81,6 +127,21 @@ impl State {
         }
     }
 
+    pub fn set_lenient_lookups_in_state(mut self, lenient_lookups: bool) -> Result<State> {
+        self.lenient_lookups = lenient_lookups;
+        Ok(self)
+    }
+
+    pub fn set_strict_receipts_in_state(mut self, strict_receipts: bool) -> Result<State> {
+        self.strict_receipts = strict_receipts;
+        Ok(self)
+    }
+
+    pub fn set_batch_tuner_in_state(mut self, batch_tuner: Arc<BatchTuner>) -> Result<State> {
+        self.batch_tuner = batch_tuner;
+        Ok(self)
+    }
+
     pub fn set_receipts_trie_in_state(mut self, receipts_trie: Trie) -> Result<State> {
         match self.receipts_trie {
             Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
@@ -93,6 +154,107 @@ impl State {
         }
     }
 
+    pub fn set_audit_log_path_in_state(mut self, audit_log_path: String) -> Result<State> {
+        match self.audit_log_path {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "audit_log_path",
+            ))),
+            None => {
+                self.audit_log_path = Some(audit_log_path);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_trie_cache_dir_in_state(mut self, trie_cache_dir: String) -> Result<State> {
+        match self.trie_cache_dir {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "trie_cache_dir",
+            ))),
+            None => {
+                self.trie_cache_dir = Some(trie_cache_dir);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_trie_root_audit_path_in_state(
+        mut self,
+        trie_root_audit_path: String,
+    ) -> Result<State> {
+        match self.trie_root_audit_path {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "trie_root_audit_path",
+            ))),
+            None => {
+                self.trie_root_audit_path = Some(trie_root_audit_path);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_hardfork_in_state(mut self, hardfork: Hardfork) -> Result<State> {
+        match self.hardfork {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err("hardfork"))),
+            None => {
+                self.hardfork = Some(hardfork);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_chain_profile_in_state(mut self, chain_profile: ChainProfile) -> Result<State> {
+        match self.chain_profile {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "chain_profile",
+            ))),
+            None => {
+                self.chain_profile = Some(chain_profile);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_proof_signing_key_in_state(
+        mut self,
+        proof_signing_key: ProofSigningKey,
+    ) -> Result<State> {
+        match self.proof_signing_key {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "proof_signing_key",
+            ))),
+            None => {
+                self.proof_signing_key = Some(proof_signing_key);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_gas_model_in_state(mut self, gas_model: GasModel) -> Result<State> {
+        match self.gas_model {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err("gas_model"))),
+            None => {
+                self.gas_model = Some(gas_model);
+                Ok(self)
+            }
+        }
+    }
+
+    pub fn set_trusted_headers_in_state(
+        mut self,
+        trusted_headers: TrustedHeaders,
+    ) -> Result<State> {
+        match self.trusted_headers {
+            Some(_) => Err(AppError::Custom(get_no_overwrite_state_err(
+                "trusted_headers",
+            ))),
+            None => {
+                self.trusted_headers = Some(trusted_headers);
+                Ok(self)
+            }
+        }
+    }
+
     pub fn get_block_from_state(&self) -> Result<&Block> {
         match &self.block {
             Some(block) => Ok(block),
@@ -134,15 +296,25 @@ impl State {
             None => Err(AppError::Custom(get_not_in_state_err("receipts_trie"))),
         }
     }
+
+    pub fn get_chain_profile_from_state(&self) -> Result<&ChainProfile> {
+        match &self.chain_profile {
+            Some(chain_profile) => Ok(chain_profile),
+            None => Err(AppError::Custom(get_not_in_state_err("chain_profile"))),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::proof_signing::SignatureScheme;
     use crate::test_utils::{
         assert_block_is_correct, assert_receipt_is_correct, get_expected_block,
         get_expected_receipt, get_valid_initial_state, get_valid_tx_hash_h256,
     };
+    use crate::trusted_headers::TrustedCheckpoint;
+    use crate::utils::convert_h256_to_prefixed_hex;
 
     #[test]
     fn should_get_initial_state_correctly() {
@@ -311,13 +483,146 @@ mod tests {
 
     #[test]
     fn should_set_branch_in_state() {
-        let expected_branch: NodeStack = Vec::new();
+        let expected_branch = NodeStack::new();
         let state = get_valid_initial_state().unwrap();
         let new_state = State::set_branch_in_state(state, expected_branch.clone()).unwrap();
         let result = State::get_branch_from_state(&new_state).unwrap();
         assert!(result == &expected_branch);
     }
 
+    #[test]
+    fn should_set_audit_log_path_in_state() {
+        let expected_path = "./audit.jsonl".to_string();
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.audit_log_path.is_none());
+        let new_state = State::set_audit_log_path_in_state(state, expected_path.clone()).unwrap();
+        assert!(new_state.audit_log_path == Some(expected_path.clone()));
+        match State::set_audit_log_path_in_state(new_state, expected_path) {
+            Err(AppError::Custom(e)) => {
+                assert!(e == "✘ Cannot overwrite audit_log_path in state!")
+            }
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_proof_signing_key_in_state() {
+        let key = ProofSigningKey::from_bytes(SignatureScheme::Ed25519, &[0x01; 32]).unwrap();
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.proof_signing_key.is_none());
+        let new_state = State::set_proof_signing_key_in_state(state, key.clone()).unwrap();
+        assert!(
+            new_state
+                .proof_signing_key
+                .as_ref()
+                .map(ProofSigningKey::get_public_key)
+                == Some(key.get_public_key())
+        );
+        match State::set_proof_signing_key_in_state(new_state, key) {
+            Err(AppError::Custom(e)) => {
+                assert!(e == "✘ Cannot overwrite proof_signing_key in state!")
+            }
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_gas_model_in_state() {
+        let gas_model = GasModel {
+            base_gas: 0,
+            ..GasModel::default()
+        };
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.gas_model.is_none());
+        let new_state = State::set_gas_model_in_state(state, gas_model.clone()).unwrap();
+        assert!(new_state.gas_model == Some(gas_model.clone()));
+        match State::set_gas_model_in_state(new_state, gas_model) {
+            Err(AppError::Custom(e)) => assert!(e == "✘ Cannot overwrite gas_model in state!"),
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_trie_root_audit_path_in_state() {
+        let path = "./trie-root-audit.json".to_string();
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.trie_root_audit_path.is_none());
+        let new_state = State::set_trie_root_audit_path_in_state(state, path.clone()).unwrap();
+        assert!(new_state.trie_root_audit_path == Some(path.clone()));
+        match State::set_trie_root_audit_path_in_state(new_state, path) {
+            Err(AppError::Custom(e)) => {
+                assert!(e == "✘ Cannot overwrite trie_root_audit_path in state!")
+            }
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_trie_cache_dir_in_state() {
+        let dir = "./trie-cache".to_string();
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.trie_cache_dir.is_none());
+        let new_state = State::set_trie_cache_dir_in_state(state, dir.clone()).unwrap();
+        assert!(new_state.trie_cache_dir == Some(dir.clone()));
+        match State::set_trie_cache_dir_in_state(new_state, dir) {
+            Err(AppError::Custom(e)) => assert!(e == "✘ Cannot overwrite trie_cache_dir in state!"),
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_trusted_headers_in_state() {
+        let checkpoint = TrustedCheckpoint {
+            number: 1,
+            hash: convert_h256_to_prefixed_hex(H256::zero()).unwrap(),
+        };
+        let trusted_headers =
+            TrustedHeaders::from_checkpoints_and_headers(&[checkpoint], vec![]).unwrap();
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.trusted_headers.is_none());
+        let new_state =
+            State::set_trusted_headers_in_state(state, trusted_headers.clone()).unwrap();
+        assert!(new_state.trusted_headers == Some(trusted_headers.clone()));
+        match State::set_trusted_headers_in_state(new_state, trusted_headers) {
+            Err(AppError::Custom(e)) => {
+                assert!(e == "✘ Cannot overwrite trusted_headers in state!")
+            }
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_cross_check_endpoint_in_state() {
+        let endpoint = "http://localhost:8546".to_string();
+        let state = get_valid_initial_state().unwrap();
+        assert!(state.cross_check_endpoint.is_none());
+        let new_state = State::set_cross_check_endpoint_in_state(state, endpoint.clone()).unwrap();
+        assert!(new_state.cross_check_endpoint == Some(endpoint.clone()));
+        match State::set_cross_check_endpoint_in_state(new_state, endpoint) {
+            Err(AppError::Custom(e)) => {
+                assert!(e == "✘ Cannot overwrite cross_check_endpoint in state!")
+            }
+            _ => panic!("Overwriting state should not have succeeded!"),
+        }
+    }
+
+    #[test]
+    fn should_set_strict_receipts_in_state() {
+        let state = get_valid_initial_state().unwrap();
+        assert!(!state.strict_receipts);
+        let new_state = State::set_strict_receipts_in_state(state, true).unwrap();
+        assert!(new_state.strict_receipts);
+    }
+
+    #[test]
+    fn should_set_batch_tuner_in_state() {
+        let state = get_valid_initial_state().unwrap();
+        let batch_tuner = Arc::new(BatchTuner::default());
+        assert!(!Arc::ptr_eq(&state.batch_tuner, &batch_tuner));
+        let new_state = State::set_batch_tuner_in_state(state, batch_tuner.clone()).unwrap();
+        assert!(Arc::ptr_eq(&new_state.batch_tuner, &batch_tuner));
+    }
+
     #[test]
     fn should_err_when_attempting_to_overwrite_index_in_state() {
         let expected_index: usize = 1337;
@@ -0,0 +1,172 @@
+use crate::metrics::record_telemetry_event;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Arc;
+
+/**
+ * Telemetry:
+ *
+ * Opt-in hooks reporting coarse usage events to a sink an embedding
+ * application sets in the CLI args it runs commands w/, so it can route them
+ * to its own analytics. The crate itself never sends events anywhere: with no
+ * sink set they're simply dropped, & the only sink it ships appends them to a
+ * local file. Sinks can't fail the operation being reported on, so they
+ * should handle their own errors.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    ProofGenerated {
+        proof_type: &'static str,
+        num_proofs: usize,
+    },
+    VerificationFailed {
+        num_proofs: usize,
+    },
+    RpcFallbackUsed {
+        fallback: &'static str,
+    },
+}
+
+pub trait TelemetrySink: Send + Sync {
+    fn on_event(&self, event: &TelemetryEvent);
+}
+
+impl<F: Fn(&TelemetryEvent) + Send + Sync> TelemetrySink for F {
+    fn on_event(&self, event: &TelemetryEvent) {
+        self(event)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Telemetry {
+    maybe_sink: Option<Arc<dyn TelemetrySink>>,
+}
+
+impl fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Telemetry")
+            .field("has_sink", &self.maybe_sink.is_some())
+            .finish()
+    }
+}
+
+// NOTE: Sinks can't be compared, so telemetry is only equal if it reports to the very same one.
+impl PartialEq for Telemetry {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.maybe_sink, &other.maybe_sink) {
+            (Some(sink), Some(other_sink)) => Arc::ptr_eq(sink, other_sink),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Telemetry {}
+
+impl Telemetry {
+    pub fn new(sink: Arc<dyn TelemetrySink>) -> Self {
+        Telemetry {
+            maybe_sink: Some(sink),
+        }
+    }
+
+    pub fn from_telemetry_log(maybe_path: &Option<String>) -> Self {
+        match maybe_path {
+            Some(path) => {
+                info!("✔ Logging telemetry events to: {}", path);
+                Self::new(Arc::new(FileTelemetrySink::new(path)))
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn emit_telemetry_event(&self, event: TelemetryEvent) {
+        record_telemetry_event(&event);
+        if let Some(sink) = &self.maybe_sink {
+            trace!("✔ Emitting telemetry event: {:?}", event);
+            sink.on_event(&event);
+        }
+    }
+}
+
+pub struct FileTelemetrySink {
+    path: String,
+}
+
+impl FileTelemetrySink {
+    pub fn new(path: &str) -> Self {
+        FileTelemetrySink {
+            path: path.to_string(),
+        }
+    }
+}
+
+impl TelemetrySink for FileTelemetrySink {
+    fn on_event(&self, event: &TelemetryEvent) {
+        let line = json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "data": event,
+        });
+        if let Err(e) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+        {
+            warn!("✘ Could not write telemetry event to {}: {}", self.path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Mutex;
+
+    #[test]
+    fn should_route_telemetry_events_to_callback_sink() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = events.clone();
+        let telemetry = Telemetry::new(Arc::new(move |event: &TelemetryEvent| {
+            events_clone.lock().unwrap().push(event.clone())
+        }));
+        let event = TelemetryEvent::ProofGenerated {
+            proof_type: "test",
+            num_proofs: 1337,
+        };
+        telemetry.emit_telemetry_event(event.clone());
+        Telemetry::default().emit_telemetry_event(event.clone());
+        assert!(*events.lock().unwrap() == vec![event]);
+    }
+
+    #[test]
+    fn should_append_telemetry_events_to_file() {
+        let path = std::env::temp_dir().join("rrpm-telemetry-test.jsonl");
+        let path_str = path.to_str().unwrap().to_string();
+        let _ = fs::remove_file(&path);
+        let telemetry = Telemetry::from_telemetry_log(&Some(path_str));
+        telemetry.emit_telemetry_event(TelemetryEvent::VerificationFailed { num_proofs: 1337 });
+        let lines = fs::read_to_string(&path).unwrap();
+        let events = lines
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<serde_json::Value>>();
+        assert!(events.len() == 1);
+        assert!(events[0]["data"]["event"] == "verification_failed");
+        assert!(events[0]["data"]["num_proofs"] == 1337);
+        assert!(events[0]["timestamp"].is_string());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_only_equal_telemetry_w_the_same_sink() {
+        let sink: Arc<dyn TelemetrySink> = Arc::new(|_: &TelemetryEvent| ());
+        let telemetry = Telemetry::new(sink.clone());
+        assert!(telemetry == Telemetry::new(sink));
+        assert!(telemetry != Telemetry::new(Arc::new(|_: &TelemetryEvent| ())));
+        assert!(telemetry != Telemetry::default());
+    }
+}
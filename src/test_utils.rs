@@ -10,7 +10,8 @@ use crate::make_rpc_call::{
     deserialize_to_block_rpc_response, deserialize_to_receipt_rpc_response,
 };
 use crate::nibble_utils::{get_nibbles_from_bytes, get_nibbles_from_offset_bytes, Nibbles};
-use crate::rlp_codec::get_rlp_encoded_receipts_and_nibble_tuples;
+use crate::proof_bundle::{get_proof_bundle_from_state, ProofBundle};
+use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
 use crate::state::State;
 use crate::trie::{put_in_trie_recursively, Trie};
 use crate::trie_nodes::Node;
@@ -27,6 +28,7 @@ pub const SAMPLE_BLOCK_JSON_PATH: &str = "./test_utils/sample_block_json";
 pub const SAMPLE_RECEIPT_JSON_PATH: &str = "./test_utils/sample_receipt_json";
 pub const SAMPLE_RECEIPT_JSON_PATH_2: &str = "./test_utils/sample_receipt_json_2";
 pub const SAMPLE_RECEIPT_JSON_PATH_3: &str = "./test_utils/sample_receipt_json_3";
+pub const SAMPLE_GET_PROOF_JSON_PATH: &str = "./test_utils/sample_get_proof_json";
 pub const SAMPLE_RECEIPT_JSONS_1_PATH: &str = "./test_utils/sample_receipt_jsons_1/";
 pub const SAMPLE_RECEIPT_JSONS_2_PATH: &str = "./test_utils/sample_receipt_jsons_2/";
 pub const SAMPLE_RECEIPT_JSONS_3_PATH: &str = "./test_utils/sample_receipt_jsons_3/";
@@ -40,6 +42,9 @@ pub const RECEIPTS_ROOT_3: &str =
     "0x4c9bb7d6a6c74445c15e5915262c49c69cd14b3e19620302f2c10303fef1e392";
 pub const SAMPLE_BLOCK_HASH: &str =
     "0x1ddd540f36ea0ed23e732c1709a46c31ba047b98f1d99e623f1644154311fe10";
+// NOTE: State root of the (synthetic) state trie the sample `eth_getProof` response is from.
+pub const SAMPLE_GET_PROOF_STATE_ROOT: &str =
+    "0xe7d6607aff2a7ee2300623b02cce59525cf5dd2629c170607da763253240b931";
 
 pub fn get_sample_tx_hashes_1() -> Vec<String> {
     vec![
@@ -423,7 +428,7 @@ pub fn get_sample_trie_with_sample_receipts(path: String, tx_hashes: Vec<String>
     let index = 0;
     let receipts = get_sample_receipts(path, tx_hashes);
     let trie = Trie::get_new_trie().unwrap();
-    let key_value_tuples = get_rlp_encoded_receipts_and_nibble_tuples(&receipts).unwrap();
+    let key_value_tuples = get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap();
     put_in_trie_recursively(trie, key_value_tuples, index).unwrap()
 }
 
@@ -515,6 +520,12 @@ pub fn get_expected_block() -> Block {
     deserialize_block_json_to_block_struct(res.result).unwrap()
 }
 
+// NOTE: The `result` of a sample RPC response, for a `MockJsonRpcClient` to answer calls w/.
+pub fn get_sample_rpc_result(path: &str) -> serde_json::Value {
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    json["result"].clone()
+}
+
 pub fn get_expected_receipt() -> Receipt {
     let string = fs::read_to_string(SAMPLE_RECEIPT_JSON_PATH).unwrap();
     let res = deserialize_to_receipt_rpc_response(string).unwrap();
@@ -560,8 +571,7 @@ pub fn assert_block_is_correct(block: Block) {
     assert!(block.number == sample_block.number);
     assert!(block.parent_hash == sample_block.parent_hash);
     assert!(block.receipts_root == sample_block.receipts_root);
-    assert!(block.seal_fields.0 == sample_block.seal_fields.0);
-    assert!(block.seal_fields.1 == sample_block.seal_fields.1);
+    assert!(block.seal_fields == sample_block.seal_fields);
     assert!(block.sha3_uncles == sample_block.sha3_uncles);
     assert!(block.size == sample_block.size);
     assert!(block.state_root == sample_block.state_root);
@@ -575,10 +585,7 @@ pub fn assert_block_is_correct(block: Block) {
 pub fn convert_hex_string_to_nibbles(hex_string: String) -> Result<Nibbles> {
     match hex_string.len() % 2 == 0 {
         true => Ok(get_nibbles_from_bytes(hex::decode(hex_string)?)),
-        false => Ok(get_nibbles_from_offset_bytes(hex::decode(format!(
-            "0{}",
-            hex_string
-        ))?)),
+        false => Nibbles::from_offset_bytes(&hex::decode(format!("0{}", hex_string))?),
     }
 }
 
@@ -632,6 +639,21 @@ pub fn get_sample_proof_3() -> String {
     "f905dff90131a0d02cd140be36772a3f2625d6555048db04d497ef93597c869f6120e3702f3fe1a0c5bd323412a3b70bf1404191a2a6f4f6d495aa3b06d4e98e0e0c7b876e3b90dba0cfe6b3f0576ed08d3aa1de057380b86387e97d256a9f6eb7f98f42304dc42cefa055aa466e458379e87e1e8f417122b1d8ce1487697601e24c804b20ad2ceb2008a00a454257e8826bc555f13e2610d09cb2bb5b93c61dc468c13619b2346e4d0f34a0294e8f7d67169822f67feea8f87103ffb463113cf658c452b1e7f176b9e3d2c2a0392ff864c6c538a951f66c8eb0b7953bcc01df7d64cf7ac8ca34dcdf7bdb31daa0b46ef2b2d6bfd406775e7e9689d9fec350f90ccafafe415df1ccbcc0cf4a0fc9a0e5516a81d7b05620820dec62757160e20a983bfacb90aabe02f9975ae89f43518080808080808080f851a0747db39fb51628381cc6d2a560570da0f91bcfa781c691542e84eec9f35661baa079076420da046b598254d6396c4b79b5594202c7eadf166e9ee6a7ccd35c19c2808080808080808080808080808080f8918080808080808080a0664372997bac3a2edfd8af5f4bdecbc8372662795a239c041be431be86c210b6a056cc8b139adb64a9315157318fe768c9943c6bb5547592c3a95724ac2c5bdc69a07692b0a375db5a16b3146695a0713749f167421af35b74517057fa33fe6aad92a0d5fc06d27773e9bb61c2b0af38ca8777db11984b6bb80932027238812e58f96c8080808080f90211a00784bcc7304124860945acee21f5d3e1c0f22c4e92f4862844488e248a00ca63a0caf1f87971b250c69e6ae82a0de31da470816275c9a76b6b664d4449f7e419dfa0a943dba7d889d750fc7ea1df3da9adde37a8504a0a4e666e9b7cf4657cec3a5aa0c431d9e786fb4d2a848fb3ab136dffd9dcc5fddcbf06cff2569e0e4d2020f90fa0c8d0503cecdf9fa895b33e90c10c3f92a3631a22d9ded83b6d0f70e7d783a4eca03b06a864bb8bdcf4dfa03b0091de96c402d926b3419b06eb52bcb320900a6078a0a3b9eaa76caa76d0f5109d0070943ba511ca5de6e90c81e3e7d9424e139c9ea2a0bd201ba74d14629643fcff259e13d1ed3b97931e2281de7d9645a5aa0f447ee6a08bd9b8440aae70c9e5e4ea823b9032821c83ea279850eca09c9b56ecdd45c8bda0c51456b3501d56795eecbbdc02f3daf3df4c1c14fcb707c24436295d36918de2a0ef9bfcf2238f8e727b4d0b011c32f0d8a4487f89acc72e7463578d13293c50ada0e176430d0660a4721470d1d20f00874c4181dcf52437bd9acca6f30c562a2d0ca0187bc38a520124d273c9349f2eb769825705f25f05d291725af21d1c238538b7a09d12823107a14133fd0b9ac1c69a31df0c02e8c1e500a678d9da2c0a4f5bfe77a033a1d714ac6c19648e43b4d9cca44045f25d5efa98cdc2b0f8a488f66d1218e9a046af5a1b7116c8d8f29eb9ed7bb3a4cf8405da10d1336e8a999c6b3fe410c02c80f901ae20b901aaf901a701835dd373b9010000000000000000000000000000000000000000000000040000000000000000000000000000000000000000000010010000000000000000000000000000000000000000000000000000000008040000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000002000000000000000100000000000000000000000000080000000000000000000000000000000000000000000000402000000000000000400000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f89df89b94dac17f958d2ee523a2206206994597c13d831ec7f863a0ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3efa0000000000000000000000000be40f004e5581cae4e8acc2193e5522106aca308a0000000000000000000000000288bf776b5f1d6d659c9bda07f4c4c78192600a2a00000000000000000000000000000000000000000000000000000000011d4f8d0".to_string()
 }
 
+pub fn get_sample_proof_bundle_1() -> ProofBundle {
+    get_valid_state_with_receipts_trie_index_and_branch(
+        SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+        get_sample_tx_hashes_1(),
+    )
+    .and_then(|state| state.set_block_in_state(get_expected_block()))
+    .and_then(|state| get_proof_bundle_from_state(&state))
+    .map(|bundle| ProofBundle {
+        // NOTE: The sample receipts aren't from the sample block, so its header can't anchor them.
+        header: None,
+        ..bundle
+    })
+    .unwrap()
+}
+
 mod tests {
     use super::*;
     use crate::errors::AppError;
@@ -0,0 +1,83 @@
+use crate::batch_tuner::BatchTuner;
+use crate::metrics::{get_histogram_totals, RPC_DURATION_SECONDS, TRIE_BUILD_DURATION_SECONDS};
+
+/**
+ * Timings:
+ *
+ * A quick breakdown of where a run's time went, printed to stderr after the
+ * command w/ `--timings`, so it never mixes w/ the proof on stdout: the count,
+ * total & mean latency of the RPC calls made, by method, the time spent
+ * building receipts tries, & the batch size & concurrency each endpoint's
+ * receipt fetches settled on. For anything more, use `--metrics-file`.
+ */
+fn format_duration_line(label: &str, count: u64, total_seconds: f64) -> String {
+    format!(
+        "  {:<32} {:>6} × {:>9.3}s mean, {:>9.3}s total",
+        label,
+        count,
+        total_seconds / count.max(1) as f64,
+        total_seconds
+    )
+}
+
+pub fn format_timings(batch_tuner: &BatchTuner) -> String {
+    let rpc_lines = get_histogram_totals(RPC_DURATION_SECONDS)
+        .into_iter()
+        .map(|(labels, count, sum)| format_duration_line(&labels.join(","), count, sum));
+    let trie_lines = get_histogram_totals(TRIE_BUILD_DURATION_SECONDS)
+        .into_iter()
+        .map(|(_, count, sum)| format_duration_line("receipts trie builds", count, sum));
+    let tuning_lines = batch_tuner
+        .get_endpoint_tunings()
+        .into_iter()
+        .map(|(endpoint, tuning)| {
+            format!(
+                "  {}: batches of {} receipts, {} at once ({} batches, {} failed, {:.3}s total)",
+                endpoint,
+                tuning.params.batch_size,
+                tuning.params.concurrency,
+                tuning.num_batches,
+                tuning.num_failed_batches,
+                tuning.total_batch_seconds
+            )
+        });
+    std::iter::once("✔ RPC calls:".to_string())
+        .chain(rpc_lines)
+        .chain(std::iter::once("✔ Trie builds:".to_string()))
+        .chain(trie_lines)
+        .chain(std::iter::once("✔ Receipt batching:".to_string()))
+        .chain(tuning_lines)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn maybe_print_timings(enabled: bool, batch_tuner: &BatchTuner) {
+    if enabled {
+        eprintln!("{}", format_timings(batch_tuner));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::observe_histogram;
+    use std::time::Duration;
+
+    #[test]
+    fn should_format_timings() {
+        let batch_tuner = BatchTuner::default();
+        let endpoint = "http://localhost:8545/timings-test";
+        observe_histogram(RPC_DURATION_SECONDS, &[("method", "timings_test")], 0.5);
+        observe_histogram(RPC_DURATION_SECONDS, &[("method", "timings_test")], 1.5);
+        batch_tuner.record_batch_result(endpoint, 16, Duration::from_millis(10), true);
+        let result = format_timings(&batch_tuner);
+        let rpc_line = result
+            .lines()
+            .find(|line| line.contains("timings_test"))
+            .unwrap();
+        assert!(rpc_line.contains("2 ×"));
+        assert!(rpc_line.contains("1.000s mean"));
+        assert!(rpc_line.contains("2.000s total"));
+        assert!(result.contains(&format!("{}: batches of 32 receipts, 3 at once", endpoint)));
+    }
+}
@@ -1,19 +1,31 @@
-use crate::constants::{EMPTY_NIBBLES, HASHED_NULL_NODE};
+use crate::constants::{EMPTY_NIBBLES, HASHED_NULL_NODE, HASH_LENGTH, TRIE_SNAPSHOT_VERSION};
 use crate::errors::AppError;
-use crate::get_database::{get_new_database, put_thing_in_database, remove_thing_from_database};
+use crate::get_database::{
+    get_new_database, put_counted_thing_in_database, put_thing_in_database,
+    remove_counted_thing_from_database,
+};
+use crate::get_keccak_hash::keccak_hash_bytes;
 use crate::nibble_utils::{
     convert_nibble_to_usize, get_common_prefix_nibbles, get_nibble_at_index,
     get_nibbles_from_bytes, split_at_first_nibble, Nibbles,
 };
-use crate::trie_nodes::{get_node_from_database, Node};
-use crate::types::{Bytes, Database, NodeStack, NoneError, Result};
-use crate::utils::{convert_bytes_to_h256, convert_h256_to_bytes};
+use crate::proof_formats::{get_proof_format, ProofFormatKind};
+use crate::rlp_codec::{Decoder, Encoder};
+use crate::trie_keys::TrieKey;
+use crate::trie_nodes::{get_node_from_database_w_lenience, rlp_decode_node, Node, NodeKind};
+use crate::types::{Bytes, Database, NodeStack, NoneError, RefCounts, Result};
+use crate::utils::{convert_bytes_to_h256, convert_h256_to_bytes, convert_h256_to_prefixed_hex};
 use ethereum_types::H256;
+use std::collections::HashSet;
+use std::fs;
 
 #[derive(Clone)]
 pub struct Trie {
     pub root: H256,
     pub database: Database,
+    pub ref_counts: RefCounts,
+    pub absorbed_roots: Vec<H256>,
+    pub lenient_lookups: bool,
 }
 
 impl Trie {
@@ -21,76 +33,171 @@ impl Trie {
         Ok(Trie {
             root: HASHED_NULL_NODE,
             database: get_new_database()?,
+            ref_counts: RefCounts::new(),
+            absorbed_roots: Vec::new(),
+            lenient_lookups: false,
         })
     }
 
-    pub fn put(self, key: Nibbles, value: Bytes) -> Result<Self> {
+    /**
+     * Snapshot Format:
+     *
+     * The snapshot is the RLP list `[version, root, [node, node, ...]]`, where
+     * each node is the RLP encoding of a node in the database. Nodes are sorted
+     * by hash so a given trie always exports to identical bytes. The database
+     * keys are not stored since they are the hashes of the nodes, & so are
+     * recomputed on import, which also guards against corrupted snapshots.
+     * Nor are their reference counts, which are recounted on import from the
+     * children of the nodes reachable from the root.
+     */
+    pub fn export(&self, path: &str) -> Result<()> {
+        info!("✔ Exporting trie snapshot to: {}", path);
+        let mut hashes: Vec<&H256> = self.database.keys().collect();
+        hashes.sort();
+        let mut encoder = Encoder::new_list(3);
+        encoder.append(&TRIE_SNAPSHOT_VERSION);
+        encoder.append(&convert_h256_to_bytes(self.root));
+        encoder.begin_list(hashes.len());
+        hashes.iter().for_each(|hash| {
+            encoder.append(&self.database[hash]);
+        });
+        Ok(fs::write(path, encoder.out())?)
+    }
+
+    pub fn import(path: &str) -> Result<Trie> {
+        info!("✔ Importing trie snapshot from: {}", path);
+        let snapshot_bytes = fs::read(path)?;
+        let snapshot = Decoder::new(&snapshot_bytes);
+        let version: u8 = snapshot
+            .val_at(0)
+            .map_err(|e| AppError::Custom(e.to_string()))?;
+        get_proof_format(ProofFormatKind::TrieSnapshot).check_version(version as u32)?;
+        let root_bytes: Bytes = snapshot
+            .val_at(1)
+            .map_err(|e| AppError::Custom(e.to_string()))?;
+        let encoded_nodes: Vec<Bytes> = snapshot
+            .list_at(2)
+            .map_err(|e| AppError::Custom(e.to_string()))?;
+        let root = convert_bytes_to_h256(&root_bytes)?;
+        let database = encoded_nodes.into_iter().try_fold(
+            get_new_database()?,
+            |mut database, encoded_node| {
+                keccak_hash_bytes(&encoded_node)
+                    .and_then(|hash| put_thing_in_database(&mut database, hash, encoded_node))
+                    .map(|_| database)
+            },
+        )?;
+        let trie = Trie {
+            root,
+            ref_counts: count_node_references(&database, &root)?,
+            database,
+            absorbed_roots: Vec::new(),
+            lenient_lookups: false,
+        };
+        match trie.root == HASHED_NULL_NODE || trie.database.contains_key(&trie.root) {
+            true => Ok(trie),
+            false => Err(AppError::Custom(
+                "✘ Trie snapshot does not contain its root node!".to_string(),
+            )),
+        }
+    }
+
+    /**
+     * DOT Visualization:
+     *
+     * Renders the nodes reachable from the root as a Graphviz digraph, since
+     * reading trace logs is a painful way to debug branch & extension splits.
+     * Each node is labelled w/ its kind & truncated hash, leaves & extensions
+     * w/ their path nibbles too, & colored by kind. Edges out of branches are
+     * labelled w/ the nibble taken. Render it via e.g. `dot -Tsvg trie.dot`.
+     */
+    pub fn to_dot(&self) -> Result<String> {
+        let mut lines = vec![
+            "digraph trie {".to_string(),
+            "    node [shape=box, style=filled, fontname=monospace];".to_string(),
+        ];
+        let mut visited_hashes = HashSet::new();
+        let mut hashes_to_visit = match self.root == HASHED_NULL_NODE {
+            true => vec![],
+            false => vec![self.root],
+        };
+        while let Some(hash) = hashes_to_visit.pop() {
+            if !visited_hashes.insert(hash) {
+                continue;
+            }
+            let node = self.get_node(&hash)?.ok_or_else(|| {
+                AppError::Custom(format!(
+                    "✘ Cannot render trie - node not in db: {}",
+                    convert_h256_to_prefixed_hex(hash).unwrap_or_default()
+                ))
+            })?;
+            lines.push(get_dot_node(&hash, &node)?);
+            for (maybe_nibble, child_hash) in get_child_hashes_w_nibbles(&node)? {
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\"{};",
+                    convert_h256_to_prefixed_hex(hash)?,
+                    convert_h256_to_prefixed_hex(child_hash)?,
+                    maybe_nibble
+                        .map_or(String::new(), |nibble| format!(" [label=\"{:x}\"]", nibble))
+                ));
+                hashes_to_visit.push(child_hash);
+            }
+        }
+        lines.push("}".to_string());
+        Ok(lines.join("\n"))
+    }
+
+    pub fn put(mut self, key: Nibbles, value: Bytes) -> Result<Self> {
+        self.put_mut(key, value).map(|_| self)
+    }
+
+    pub fn put_mut(&mut self, key: Nibbles, value: Bytes) -> Result<()> {
         trace!("Putting new value in trie under path: {:?}", key);
         match self.root == HASHED_NULL_NODE {
             true => {
                 trace!("Trie empty ∴ creating new leaf node...");
-                Node::get_new_leaf_node(key, value)
-                    .and_then(|leaf| self.update_trie_database(vec![leaf], Vec::new()))
+                Node::get_new_leaf_node(key, value).and_then(|leaf| {
+                    self.update_trie_database(NodeStack::from(vec![leaf]), NodeStack::new())
+                })
             }
             false => {
                 trace!("Trie not-empty ∴ finding nearest node to key...");
-                Trie::find(self, key)
-                    .and_then(|(self_, target_key, found_stack, remaining_key)| {
-                        self_.process_found_node_stack(
-                            target_key,
-                            found_stack,
-                            remaining_key,
-                            value,
-                        )
+                self.find(key)
+                    .and_then(|(target_key, found_stack, remaining_key)| {
+                        self.process_found_node_stack(target_key, found_stack, remaining_key, value)
                     })
-                    .and_then(
-                        |(self_, target_key, old_stack, new_stack, stack_to_delete)| {
-                            self_.update_stale_nodes(
-                                target_key,
-                                old_stack,
-                                new_stack,
-                                stack_to_delete,
-                            )
-                        },
-                    )
-                    .and_then(|(self_, _, _, new_stack, stack_to_delete)| {
-                        self_.update_trie_database(new_stack, stack_to_delete)
+                    .and_then(|(target_key, old_stack, new_stack, stack_to_delete)| {
+                        self.update_stale_nodes(target_key, old_stack, new_stack, stack_to_delete)
+                    })
+                    .and_then(|(_, _, new_stack, stack_to_delete)| {
+                        self.update_trie_database(new_stack, stack_to_delete)
                     })
             }
         }
     }
 
     fn process_found_node_stack(
-        self,
+        &self,
         target_key: Nibbles,
         mut found_stack: NodeStack,
         remaining_key: Nibbles,
         value: Bytes,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
-        match found_stack.pop() {
-            Some(node) => match node.get_type() {
-                "leaf" => {
-                    self.process_from_leaf_node(target_key, node, found_stack, remaining_key, value)
-                }
-                "branch" => self.process_from_branch_node(
-                    target_key,
-                    node,
-                    found_stack,
-                    remaining_key,
-                    value,
-                ),
-                "extension" => self.process_from_extension_node(
-                    target_key,
-                    node,
-                    found_stack,
-                    remaining_key,
-                    value,
-                ),
-                _ => Err(AppError::Custom("✘ Node type not recognized!".to_string())),
-            },
-            None => Err(AppError::Custom(
-                "✘ Cannot process node stack: It's empty!".to_string(),
-            )),
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
+        let node = found_stack.pop_checked()?;
+        match node.get_type() {
+            NodeKind::Leaf => {
+                self.process_from_leaf_node(target_key, node, found_stack, remaining_key, value)
+            }
+            NodeKind::Branch => {
+                self.process_from_branch_node(target_key, node, found_stack, remaining_key, value)
+            }
+            NodeKind::Extension => self.process_from_extension_node(
+                target_key,
+                node,
+                found_stack,
+                remaining_key,
+                value,
+            ),
         }
     }
     /**
@@ -118,7 +225,6 @@ impl Trie {
      * two-nibble-long extension node. The extension node now fully depleted
      * is condemned to the delete stack.
      *
-     * FIXME: TODO:
      * Case two has a similar sub-case as above, when the common-prefix consumes
      * all but the LAST nibble of the extension. Here again we don't need a new
      * extension creating, because the new branch will inherit the hash the
@@ -129,15 +235,15 @@ impl Trie {
      *
      */
     fn process_from_extension_node(
-        self,
+        &self,
         target_key: Nibbles,
         current_ext_node: Node,
         found_stack: NodeStack,
         remaining_key: Nibbles,
         value: Bytes,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
         trace!("Processing from extension node...");
-        let mut new_stack: NodeStack = Vec::new();
+        let mut new_stack = NodeStack::new();
         get_common_prefix_nibbles(remaining_key, current_ext_node.get_key()).and_then(
             |(common_prefix, key_remainder, node_key_remainder)| {
                 trace!(
@@ -169,8 +275,8 @@ impl Trie {
                                 )?;
                                 new_stack.push(new_branch);
                                 new_stack.push(new_leaf);
-                                let stack_to_delete = vec![current_ext_node];
-                                Ok((self, target_key, found_stack, new_stack, stack_to_delete))
+                                let stack_to_delete = NodeStack::from(vec![current_ext_node]);
+                                Ok((target_key, found_stack, new_stack, stack_to_delete))
                             },
                         ),
                         _ => split_at_first_nibble(&node_key_remainder).and_then(
@@ -201,7 +307,7 @@ impl Trie {
                                 new_stack.push(new_branch);
                                 new_stack.push(new_ext);
                                 new_stack.push(new_leaf);
-                                Ok((self, target_key, found_stack, new_stack, Vec::new()))
+                                Ok((target_key, found_stack, new_stack, NodeStack::new()))
                             },
                         ),
                     },
@@ -214,12 +320,18 @@ impl Trie {
                             split_at_first_nibble(&key_remainder)?;
                         let (node_key_remainder_first_nibble, node_key_remainder_nibbles) =
                             split_at_first_nibble(&node_key_remainder)?;
-                        let ext_below_branch = Node::get_new_extension_node(
-                            node_key_remainder_nibbles,
-                            current_ext_node.get_value().ok_or_else(|| {
-                                NoneError("Could not get extension node value!".into())
-                            })?,
-                        )?;
+                        let ext_value = current_ext_node.get_value().ok_or_else(|| {
+                            NoneError("Could not get extension node value!".into())
+                        })?;
+                        // NOTE: If the prefix consumes all but the ext's last nibble, the
+                        // branch points straight at the ext's child, w/ no ext below it.
+                        let maybe_ext_below_branch = match node_key_remainder_nibbles.is_empty() {
+                            true => None,
+                            false => Some(Node::get_new_extension_node(
+                                node_key_remainder_nibbles,
+                                ext_value.clone(),
+                            )?),
+                        };
                         let new_leaf = Node::get_new_leaf_node(key_remainder_nibbles, value)?;
                         let empty_branch = Node::get_new_branch_node(None)?;
                         let updated_branch = empty_branch.update_branch_at_index(
@@ -227,17 +339,26 @@ impl Trie {
                             convert_nibble_to_usize(key_remainder_first_nibble),
                         )?;
                         let final_branch = updated_branch.update_branch_at_index(
-                            Some(convert_h256_to_bytes(ext_below_branch.get_hash()?)),
+                            Some(match &maybe_ext_below_branch {
+                                Some(ext_below_branch) => {
+                                    convert_h256_to_bytes(ext_below_branch.get_hash()?)
+                                }
+                                None => ext_value,
+                            }),
                             convert_nibble_to_usize(node_key_remainder_first_nibble),
                         )?;
                         let final_branch_hash = convert_h256_to_bytes(final_branch.get_hash()?);
                         let ext_above_branch =
                             Node::get_new_extension_node(common_prefix, final_branch_hash)?;
-                        new_stack.push(new_leaf);
-                        new_stack.push(ext_below_branch);
-                        new_stack.push(final_branch);
+                        // NOTE: The node nearest the root must be first in the new stack.
                         new_stack.push(ext_above_branch);
-                        Ok((self, target_key, found_stack, new_stack, Vec::new()))
+                        new_stack.push(final_branch);
+                        if let Some(ext_below_branch) = maybe_ext_below_branch {
+                            new_stack.push(ext_below_branch);
+                        };
+                        new_stack.push(new_leaf);
+                        let stack_to_delete = NodeStack::from(vec![current_ext_node]);
+                        Ok((target_key, found_stack, new_stack, stack_to_delete))
                     }
                 }
             },
@@ -269,20 +390,20 @@ impl Trie {
      *
      */
     fn process_from_leaf_node(
-        self,
+        &self,
         target_key: Nibbles,
         current_leaf_node: Node,
         found_stack: NodeStack,
         remaining_key: Nibbles,
         value: Bytes,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
         trace!("Processing from leaf node...");
-        let mut new_stack: NodeStack = Vec::new();
+        let mut new_stack = NodeStack::new();
         match remaining_key.len() {
             0 => Node::get_new_leaf_node(current_leaf_node.get_key(), value).map(|new_leaf| {
                 trace!("No key remaining ∴ creating new leaf node");
                 new_stack.push(new_leaf);
-                (self, target_key, found_stack, new_stack, Vec::new())
+                (target_key, found_stack, new_stack, NodeStack::new())
             }),
             _ => {
                 get_common_prefix_nibbles(remaining_key.clone(), current_leaf_node.get_key()) // FIXME: rm clones
@@ -313,7 +434,7 @@ impl Trie {
                                     new_stack.push(updated_branch);
                                     new_stack.push(new_leaf_1);
                                     new_stack.push(new_leaf_2);
-                                    Ok((self, target_key, found_stack, new_stack, Vec::new()))
+                                    Ok((target_key, found_stack, new_stack, NodeStack::new()))
                                 },
                             ),
                             _ => split_at_first_nibble(&node_key_remainder).and_then(
@@ -357,7 +478,7 @@ impl Trie {
                                     new_stack.push(updated_branch);
                                     new_stack.push(new_leaf_1);
                                     new_stack.push(new_leaf_2);
-                                    Ok((self, target_key, found_stack, new_stack, Vec::new()))
+                                    Ok((target_key, found_stack, new_stack, NodeStack::new()))
                                 },
                             ),
                         }
@@ -375,14 +496,19 @@ impl Trie {
      *
      */
     fn process_from_branch_node(
-        self,
+        &self,
         target_key: Nibbles,
         current_branch_node: Node,
         found_stack: NodeStack,
         remaining_key: Nibbles,
         value: Bytes,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
         trace!("Processing from branch node...");
+        if remaining_key.is_empty() {
+            return Err(AppError::Custom(
+                "✘ Cannot put value under key ending at a branch!".to_string(),
+            ));
+        }
         split_at_first_nibble(&remaining_key)
             .and_then(|(first_nibble, remaining_nibbles)| {
                 trace!("Creating new leaf & updating branch node...");
@@ -392,40 +518,40 @@ impl Trie {
                     Some(new_leaf_hash),
                     convert_nibble_to_usize(first_nibble),
                 )?;
-                let new_stack: NodeStack = vec![updated_branch, new_leaf];
+                let new_stack = NodeStack::from(vec![updated_branch, new_leaf]);
                 Ok(new_stack)
             })
-            .map(|new_stack| (self, target_key, found_stack, new_stack, Vec::new()))
+            .map(|new_stack| (target_key, found_stack, new_stack, NodeStack::new()))
     }
 
     fn update_stale_nodes(
-        self,
+        &self,
         target_key: Nibbles,
         mut old_stack: NodeStack,
         new_stack: NodeStack,
         stack_to_delete: NodeStack,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
         match old_stack.pop() {
             Some(current_node) => match current_node.get_type() {
-                "branch" => self.update_nodes_from_old_branch_node(
+                NodeKind::Branch => self.update_nodes_from_old_branch_node(
                     target_key,
                     current_node,
                     old_stack,
                     new_stack,
                     stack_to_delete,
                 ),
-                "extension" => self.update_nodes_from_old_extension_node(
+                NodeKind::Extension => self.update_nodes_from_old_extension_node(
                     target_key,
                     current_node,
                     old_stack,
                     new_stack,
                     stack_to_delete,
                 ),
-                _ => Err(AppError::Custom(
+                NodeKind::Leaf => Err(AppError::Custom(
                     "✘ Error updating old nodes: Wrong node type!".to_string(),
                 )),
             },
-            None => Ok((self, target_key, old_stack, new_stack, stack_to_delete)),
+            None => Ok((target_key, old_stack, new_stack, stack_to_delete)),
         }
     }
     /**
@@ -439,20 +565,20 @@ impl Trie {
      *
      */
     fn update_nodes_from_old_extension_node(
-        self,
+        &self,
         target_key: Nibbles,
         current_node: Node,
         old_stack: NodeStack,
         mut new_stack: NodeStack,
         mut stack_to_delete: NodeStack,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
         trace!("Updating stale nodes from old extension node...");
-        let target_node_hash = new_stack[0].get_hash()?;
+        let target_node_hash = new_stack.peek_bottom()?.get_hash()?;
         let updated_extension_node = Node::get_new_extension_node(
             current_node.get_key(),
             convert_h256_to_bytes(target_node_hash),
         )?;
-        new_stack.insert(0, updated_extension_node);
+        new_stack.push_bottom(updated_extension_node);
         stack_to_delete.push(current_node);
         self.update_stale_nodes(target_key, old_stack, new_stack, stack_to_delete)
     }
@@ -461,9 +587,9 @@ impl Trie {
      *
      * Here we take the old branch node and update it to contain the next node
      * in line's hash, placed at the correct index in the branches. Which
-     * latter is calculated by finding out how much of the target key so far
-     * is accounted for in the new_node stack, and getting the nibble
-     * immediately before that.
+     * latter is calculated by finding out how much of the target key is
+     * accounted for by the old nodes above this branch, and getting the
+     * nibble immediately after that.
      *
      * This updated branch node is then unshifted into the `new_node` stack,
      * and the old branch node condemned to the `stack_to_delete` for later
@@ -471,24 +597,23 @@ impl Trie {
      *
      */
     fn update_nodes_from_old_branch_node(
-        self,
+        &self,
         target_key: Nibbles,
         current_node: Node,
         old_stack: NodeStack,
         mut new_stack: NodeStack,
         mut stack_to_delete: NodeStack,
-    ) -> Result<(Self, Nibbles, NodeStack, NodeStack, NodeStack)> {
+    ) -> Result<(Nibbles, NodeStack, NodeStack, NodeStack)> {
         trace!("Updating stale nodes from old branch node...");
-        let target_node_hash = new_stack[0].get_hash()?;
-        let key_partial_length = get_key_length_accounted_for_in_stack(&new_stack);
-        let nibble_index = target_key.len() - key_partial_length - 1;
+        let target_node_hash = new_stack.peek_bottom()?.get_hash()?;
+        let nibble_index = get_key_length_accounted_for_in_stack(&old_stack);
         let byte = get_nibble_at_index(&target_key, nibble_index)?;
         let nibble = get_nibbles_from_bytes(vec![byte]);
         let branch_index = convert_nibble_to_usize(nibble);
         let updated_node = current_node
             .clone()
             .update_branch_at_index(Some(convert_h256_to_bytes(target_node_hash)), branch_index)?;
-        new_stack.insert(0, updated_node);
+        new_stack.push_bottom(updated_node);
         stack_to_delete.push(current_node);
         self.update_stale_nodes(target_key, old_stack, new_stack, stack_to_delete)
     }
@@ -501,102 +626,109 @@ impl Trie {
      *
      */
     fn update_trie_database(
-        self,
+        &mut self,
         mut new_stack: NodeStack,
         mut stack_to_delete: NodeStack,
-    ) -> Result<Self> {
+    ) -> Result<()> {
         match !stack_to_delete.is_empty() {
             true => {
-                let node = stack_to_delete
-                    .pop()
-                    .ok_or_else(|| NoneError("Could not pop stack!".into()))?;
+                let node = stack_to_delete.pop_checked()?;
                 trace!(
                     "Removing {} from database w/ hash: {}",
                     node.get_type(),
                     node.get_hash()?
                 );
-                self.remove_node_from_database(node)
-                    .and_then(|new_self| new_self.update_trie_database(new_stack, stack_to_delete))
+                self.remove_node_from_database(&node)?;
+                self.update_trie_database(new_stack, stack_to_delete)
             }
             false => match new_stack.len() {
-                0 => Ok(self),
+                0 => Ok(()),
                 1 => {
-                    let node = new_stack
-                        .pop()
-                        .ok_or_else(|| NoneError("Could not pop stack!".into()))?;
+                    let node = new_stack.pop_checked()?;
                     let next_root_hash = node.get_hash()?;
                     trace!(
                         "Putting new {} in database w/ hash: {}",
                         node.get_type(),
                         next_root_hash
                     );
-                    self.put_node_in_database(node).and_then(|new_self| {
-                        trace!("Updating root hash to {}\n", next_root_hash);
-                        new_self.update_root_hash(next_root_hash)
-                    })
+                    self.put_node_in_database(&node)?;
+                    trace!("Updating root hash to {}\n", next_root_hash);
+                    self.root = next_root_hash;
+                    Ok(())
                 }
                 _ => {
-                    let node = new_stack
-                        .pop()
-                        .ok_or_else(|| NoneError("Could not pop stack!".into()))?;
+                    let node = new_stack.pop_checked()?;
                     trace!(
                         "Putting new {} in database w/ hash: {}",
                         node.get_type(),
                         node.get_hash()?
                     );
-                    self.put_node_in_database(node).and_then(|new_self| {
-                        new_self.update_trie_database(new_stack, stack_to_delete)
-                    })
+                    self.put_node_in_database(&node)?;
+                    self.update_trie_database(new_stack, stack_to_delete)
                 }
             },
         }
     }
 
-    pub fn find(self, target_key: Nibbles) -> Result<(Self, Nibbles, NodeStack, Nibbles)> {
-        get_node_from_database(&self.database, &self.root).and_then(|maybe_node| match maybe_node {
-            Some(node) => Trie::find_path(self, target_key.clone(), vec![node], target_key),
-            None => Err(AppError::Custom(
-                "✘ Find Error: Could not find root node in db!".to_string(),
-            )),
-        })
+    // NOTE: W/ `lenient_lookups`, a node found only under its byte-reversed hash is warned about & missed.
+    pub fn set_lenient_lookups(mut self, lenient_lookups: bool) -> Self {
+        self.set_lenient_lookups_mut(lenient_lookups);
+        self
+    }
+
+    pub fn set_lenient_lookups_mut(&mut self, lenient_lookups: bool) {
+        self.lenient_lookups = lenient_lookups;
+    }
+
+    fn get_node(&self, hash: &H256) -> Result<Option<Node>> {
+        get_node_from_database_w_lenience(&self.database, hash, self.lenient_lookups)
+    }
+
+    pub fn find(&self, target_key: Nibbles) -> Result<(Nibbles, NodeStack, Nibbles)> {
+        self.get_node(&self.root)
+            .and_then(|maybe_node| match maybe_node {
+                Some(node) => {
+                    self.find_path(target_key.clone(), NodeStack::from(vec![node]), target_key)
+                }
+                None => Err(AppError::Custom(
+                    "✘ Find Error: Could not find root node in db!".to_string(),
+                )),
+            })
     }
 
     fn find_path(
-        self,
+        &self,
         target_key: Nibbles,
         mut found_stack: NodeStack,
         remaining_key: Nibbles,
-    ) -> Result<(Self, Nibbles, NodeStack, Nibbles)> {
+    ) -> Result<(Nibbles, NodeStack, Nibbles)> {
         match found_stack.pop() {
             None => {
                 trace!("No node in top of stack");
-                Ok((self, target_key, found_stack, remaining_key))
+                Ok((target_key, found_stack, remaining_key))
             }
             Some(current_node) => match current_node.get_type() {
-                "leaf" => Self::continue_finding_from_leaf(
+                NodeKind::Leaf => Self::continue_finding_from_leaf(
                     self,
                     target_key,
                     current_node,
                     found_stack,
                     remaining_key,
                 ),
-                "branch" => Self::continue_finding_from_branch(
+                NodeKind::Branch => Self::continue_finding_from_branch(
                     self,
                     target_key,
                     current_node,
                     found_stack,
                     remaining_key,
                 ),
-                "extension" => Self::continue_finding_from_extension(
+                NodeKind::Extension => Self::continue_finding_from_extension(
                     self,
                     target_key,
                     current_node,
                     found_stack,
                     remaining_key,
                 ),
-                _ => Err(AppError::Custom(
-                    "✘ Find Error: Node type not recognized!".to_string(),
-                )),
             },
         }
     }
@@ -608,11 +740,12 @@ impl Trie {
      * target key and the leaf key. Once determined, we consider the two cases
      * of what remains of the target key:
      *
-     * 1) No key remains.
-     * 2) Some or all the key remains.
+     * 1) No key remains, nor any of the leaf's key.
+     * 2) Some or all the key remains, or some of the leaf's key does.
      *
      * In the first case, we have a full match and so return stack including
-     * this leaf node along with an empty key.
+     * this leaf node along with an empty key. A key that runs out before the
+     * leaf's does is merely a prefix of the leaf's path, not a match.
      *
      * In case 2) we have no match but this is the closest node we got to. The
      * curent node is pushed back on the stack, which latter is returned along
@@ -620,26 +753,28 @@ impl Trie {
      *
      */
     fn continue_finding_from_leaf(
-        self,
+        &self,
         target_key: Nibbles,
         leaf_node: Node,
         mut found_stack: NodeStack,
         key: Nibbles,
-    ) -> Result<(Self, Nibbles, NodeStack, Nibbles)> {
+    ) -> Result<(Nibbles, NodeStack, Nibbles)> {
         trace!("Leaf node found");
-        get_common_prefix_nibbles(key.clone(), leaf_node.get_key()).map(|(_, remaining_key, _)| {
-            found_stack.push(leaf_node);
-            match remaining_key.len() {
-                0 => {
-                    trace!("Wohoo! Leaf node matches fully!");
-                    (self, target_key, found_stack, EMPTY_NIBBLES)
-                }
-                _ => {
-                    trace!("Leaf node has some | no match");
-                    (self, target_key, found_stack, key)
+        get_common_prefix_nibbles(key.clone(), leaf_node.get_key()).map(
+            |(_, remaining_key, remaining_node_key)| {
+                found_stack.push(leaf_node);
+                match remaining_key.is_empty() && remaining_node_key.is_empty() {
+                    true => {
+                        trace!("Wohoo! Leaf node matches fully!");
+                        (target_key, found_stack, EMPTY_NIBBLES)
+                    }
+                    false => {
+                        trace!("Leaf node has some | no match");
+                        (target_key, found_stack, key)
+                    }
                 }
-            }
-        })
+            },
+        )
     }
     /**
      *
@@ -662,12 +797,12 @@ impl Trie {
      *
      */
     fn continue_finding_from_extension(
-        self,
+        &self,
         target_key: Nibbles,
         extension_node: Node,
         mut found_stack: NodeStack,
         key: Nibbles,
-    ) -> Result<(Self, Nibbles, NodeStack, Nibbles)> {
+    ) -> Result<(Nibbles, NodeStack, Nibbles)> {
         trace!("Extension node found");
         get_common_prefix_nibbles(key.clone(), extension_node.get_key()).and_then(
             |(common_prefix, remaining_key, remaining_node_key)| {
@@ -680,16 +815,16 @@ impl Trie {
                 match common_prefix.len() {
                     0 => {
                         trace!("Extension & key have no common prefix");
-                        Ok((self, target_key, found_stack, key))
+                        Ok((target_key, found_stack, key))
                     }
-                    _ => match remaining_node_key.len() > 0 {
+                    _ => match !remaining_node_key.is_empty() {
                         true => {
                             trace!("Extension partial match");
-                            Ok((self, target_key, found_stack, key))
+                            Ok((target_key, found_stack, key))
                         }
                         false => {
                             trace!("Extension full match, continuing...");
-                            match get_node_from_database(&self.database, next_node_hash)? {
+                            match self.get_node(next_node_hash)? {
                                 Some(next_node) => {
                                     found_stack.push(next_node);
                                     Self::find_path(self, target_key, found_stack, remaining_key)
@@ -731,82 +866,447 @@ impl Trie {
      *
      * The second case is not yet currently handled. // TODO!
      *
+     * A key used up on arriving at the branch has no first nibble to slice
+     * off, & so ends the search at the branch itself.
+     *
      */
     fn continue_finding_from_branch(
-        self,
+        &self,
         target_key: Nibbles,
         branch_node: Node,
         mut found_stack: NodeStack,
         key: Nibbles,
-    ) -> Result<(Self, Nibbles, NodeStack, Nibbles)> {
+    ) -> Result<(Nibbles, NodeStack, Nibbles)> {
         trace!("Branch node found");
         found_stack.push(branch_node.clone());
+        if key.is_empty() {
+            trace!("Key used up at branch");
+            return Ok((target_key, found_stack, key));
+        }
         split_at_first_nibble(&key).and_then(|(first_nibble, remaining_nibbles)| match &branch_node
-            .branch
+            .get_branch()
             .ok_or_else(|| NoneError("Could not unwrap branch!".into()))?
             .branches[convert_nibble_to_usize(first_nibble)]
         {
             None => {
                 trace!("No hash at next nibble index in branch");
-                Ok((self, target_key, found_stack, key))
+                Ok((target_key, found_stack, key))
             }
-            Some(bytes) => {
-                match get_node_from_database(&self.database, &convert_bytes_to_h256(bytes)?)? {
-                    Some(next_node) => {
-                        trace!(
-                            "Next node retrieved from hash in {}",
-                            "branch, continuing..."
-                        );
-                        found_stack.push(next_node);
-                        Self::find_path(self, target_key, found_stack, remaining_nibbles)
-                    }
-                    None => Err(AppError::Custom(
-                        "✘ Find Error: Branch child not in db!".to_string(),
-                    )),
+            Some(bytes) => match self.get_node(&convert_bytes_to_h256(bytes)?)? {
+                Some(next_node) => {
+                    trace!(
+                        "Next node retrieved from hash in {}",
+                        "branch, continuing..."
+                    );
+                    found_stack.push(next_node);
+                    Self::find_path(self, target_key, found_stack, remaining_nibbles)
                 }
-            }
+                None => Err(AppError::Custom(
+                    "✘ Find Error: Branch child not in db!".to_string(),
+                )),
+            },
         })
     }
 
+    /**
+     * Size & Membership:
+     *
+     * Lets callers check a trie holds what they expect, e.g. one receipt per
+     * tx in its block, before extracting proofs from it. A key is contained,
+     * & `get` returns its value, if walking it from the root consumes the
+     * whole key, & the whole path of any leaf it ends at, & ends at a leaf,
+     * or at a branch holding a value, but never at an extension, whose value
+     * is its child's hash. The
+     * length is the number of values reachable from the root, i.e. of leaves
+     * & valued branches, & the node count that of the nodes reachable from
+     * it, so neither counts stale nodes nor those of absorbed tries.
+     */
+    pub fn contains_key(&self, key: Nibbles) -> Result<bool> {
+        self.get(key).map(|maybe_value| maybe_value.is_some())
+    }
+
+    pub fn get(&self, key: Nibbles) -> Result<Option<Bytes>> {
+        match self.is_empty() {
+            true => Ok(None),
+            false => self.get_from_node(&self.root, key),
+        }
+    }
+
+    // NOTE: Walks the key itself, since `find` can't tell a leaf whose path the key only prefixes from a match.
+    fn get_from_node(&self, hash: &H256, key: Nibbles) -> Result<Option<Bytes>> {
+        let node = self.get_node(hash)?.ok_or_else(|| {
+            AppError::Custom(format!(
+                "✘ No node in trie w/ hash: {}",
+                convert_h256_to_prefixed_hex(*hash).unwrap_or_default()
+            ))
+        })?;
+        match node.get_type() {
+            NodeKind::Leaf => get_common_prefix_nibbles(key, node.get_key()).map(
+                |(_, remaining_key, remaining_node_key)| match remaining_key.is_empty()
+                    && remaining_node_key.is_empty()
+                {
+                    true => node.get_value(),
+                    false => None,
+                },
+            ),
+            NodeKind::Extension => get_common_prefix_nibbles(key, node.get_key()).and_then(
+                |(_, remaining_key, remaining_node_key)| match remaining_node_key.is_empty() {
+                    true => self.get_from_node(
+                        &convert_bytes_to_h256(&node.get_value().ok_or_else(|| {
+                            NoneError("Could not unwrap extension node!".into())
+                        })?)?,
+                        remaining_key,
+                    ),
+                    false => Ok(None),
+                },
+            ),
+            NodeKind::Branch => match key.is_empty() {
+                true => Ok(node.get_value()),
+                false => {
+                    split_at_first_nibble(&key).and_then(|(first_nibble, remaining_nibbles)| {
+                        match &node
+                            .get_branch()
+                            .ok_or_else(|| NoneError("Could not unwrap branch!".into()))?
+                            .branches[convert_nibble_to_usize(first_nibble)]
+                        {
+                            Some(bytes) => self
+                                .get_from_node(&convert_bytes_to_h256(bytes)?, remaining_nibbles),
+                            None => Ok(None),
+                        }
+                    })
+                }
+            },
+        }
+    }
+
+    // NOTE: Only reads, but takes `&mut self` like `put_mut` so a trie held mutably, e.g. behind a lock, proves in place.
+    pub fn get_proof_mut(&mut self, key: Nibbles) -> Result<NodeStack> {
+        match self.contains_key(key.clone())? {
+            true => self.find(key).map(|(_, found_stack, _)| found_stack),
+            false => Err(AppError::Custom(format!(
+                "✘ No value in trie under key: {:?}",
+                key
+            ))),
+        }
+    }
+
+    pub fn len(&self) -> Result<usize> {
+        Ok(self
+            .get_reachable_nodes()?
+            .iter()
+            .filter(|node| node.get_type() != NodeKind::Extension && node.get_value().is_some())
+            .count())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root == HASHED_NULL_NODE
+    }
+
+    pub fn node_count(&self) -> Result<usize> {
+        Ok(self.get_reachable_nodes()?.len())
+    }
+
+    // NOTE: An MPT inlines non-root nodes under 32 bytes in their parents, which this trie never does.
+    pub fn has_inlinable_nodes(&self) -> Result<bool> {
+        for node in self.get_reachable_nodes()? {
+            if node.get_hash()? != self.root && node.get_rlp_encoding()?.len() < HASH_LENGTH {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_reachable_nodes(&self) -> Result<Vec<Node>> {
+        let mut nodes = Vec::new();
+        let mut visited_hashes = HashSet::new();
+        let mut hashes_to_visit = match self.is_empty() {
+            true => vec![],
+            false => vec![self.root],
+        };
+        while let Some(hash) = hashes_to_visit.pop() {
+            if !visited_hashes.insert(hash) {
+                continue;
+            }
+            let node = self.get_node(&hash)?.ok_or_else(|| {
+                AppError::Custom(format!(
+                    "✘ No node in trie w/ hash: {}",
+                    convert_h256_to_prefixed_hex(hash).unwrap_or_default()
+                ))
+            })?;
+            hashes_to_visit.extend(get_child_hashes(&node)?);
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
     pub fn update_root_hash(mut self, new_hash: H256) -> Result<Self> {
+        self.update_root_hash_mut(new_hash).map(|_| self)
+    }
+
+    pub fn update_root_hash_mut(&mut self, new_hash: H256) -> Result<()> {
         self.root = new_hash;
-        Ok(self)
+        Ok(())
     }
 
-    fn put_node_in_database(self, node: Node) -> Result<Self> {
-        Ok(Trie {
-            root: self.root,
-            database: put_thing_in_database(
-                self.database,
-                node.get_hash()?,
-                node.get_rlp_encoding()?,
-            )?,
-        })
+    /**
+     * Absorbing Tries:
+     *
+     * Merges another trie's nodes into this one's database & registers its
+     * root, so a single store can serve proofs for many blocks' independently
+     * built tries via `switch_root`. Nodes are keyed by their hash ∴ nodes
+     * common to both tries simply dedupe, & a key holding two different nodes
+     * means a corrupt database, which is an error. Shared nodes' reference
+     * counts are summed, so a later `put` making one stale in one trie leaves
+     * it in the database for the other.
+     */
+    pub fn absorb(mut self, other: Trie) -> Result<Self> {
+        self.absorb_mut(other).map(|_| self)
     }
 
-    fn remove_node_from_database(self, node: Node) -> Result<Self> {
-        Ok(Trie {
-            root: self.root,
-            database: remove_thing_from_database(self.database, &node.get_hash()?)?,
-        })
+    pub fn absorb_mut(&mut self, other: Trie) -> Result<()> {
+        info!(
+            "✔ Absorbing {} nodes of trie w/ root: {}",
+            other.database.len(),
+            convert_h256_to_prefixed_hex(other.root)?
+        );
+        // NOTE: Nodes w/o a reference count, e.g. inserted directly, are referenced once.
+        let other_ref_counts = &other.ref_counts;
+        other
+            .database
+            .into_iter()
+            .try_for_each(|(hash, encoded_node)| match self.database.get(&hash) {
+                Some(existing_node) if *existing_node != encoded_node => {
+                    Err(AppError::Custom(format!(
+                        "✘ Cannot absorb trie - conflicting nodes under key: {}",
+                        convert_h256_to_prefixed_hex(hash)?
+                    )))
+                }
+                Some(_) => {
+                    *self.ref_counts.entry(hash).or_insert(0) +=
+                        other_ref_counts.get(&hash).copied().unwrap_or(1);
+                    Ok(())
+                }
+                None => {
+                    self.ref_counts
+                        .insert(hash, other_ref_counts.get(&hash).copied().unwrap_or(1));
+                    put_thing_in_database(&mut self.database, hash, encoded_node)
+                }
+            })?;
+        std::iter::once(other.root)
+            .chain(other.absorbed_roots)
+            .for_each(|root| self.register_root(root));
+        Ok(())
+    }
+
+    fn register_root(&mut self, root: H256) {
+        if root != HASHED_NULL_NODE && root != self.root && !self.absorbed_roots.contains(&root) {
+            self.absorbed_roots.push(root);
+        }
+    }
+
+    pub fn switch_root(mut self, root: H256) -> Result<Self> {
+        self.switch_root_mut(root).map(|_| self)
+    }
+
+    pub fn switch_root_mut(&mut self, root: H256) -> Result<()> {
+        match root == self.root || self.absorbed_roots.contains(&root) {
+            false => Err(AppError::Custom(format!(
+                "✘ Cannot switch to unregistered trie root: {}",
+                convert_h256_to_prefixed_hex(root)?
+            ))),
+            true => {
+                let old_root = self.root;
+                self.absorbed_roots
+                    .retain(|absorbed_root| *absorbed_root != root);
+                self.update_root_hash_mut(root)?;
+                self.register_root(old_root);
+                Ok(())
+            }
+        }
+    }
+
+    /**
+     * Pruning:
+     *
+     * Not every node a put makes stale is condemned to the delete stack, & so
+     * after many puts the database holds nodes no longer reachable from any
+     * root. Pruning walks from the root & any absorbed roots, marking each node
+     * reachable from them, then drops every other node. Nodes are looked up as
+     * on any walk of the trie, so a node missing from the database is an error
+     * rather than a reason to drop what hangs beneath it.
+     */
+    pub fn prune(mut self) -> Result<Self> {
+        self.prune_mut().map(|_| self)
+    }
+
+    pub fn prune_mut(&mut self) -> Result<()> {
+        let reachable_hashes = self.get_reachable_hashes()?;
+        let num_nodes = self.database.len();
+        self.database
+            .retain(|hash, _| reachable_hashes.contains(hash));
+        self.ref_counts
+            .retain(|hash, _| reachable_hashes.contains(hash));
+        info!(
+            "✔ Pruned {} unreachable nodes from trie database, leaving {}",
+            num_nodes - self.database.len(),
+            self.database.len()
+        );
+        Ok(())
+    }
+
+    fn get_reachable_hashes(&self) -> Result<HashSet<H256>> {
+        let mut reachable_hashes = HashSet::new();
+        let mut hashes_to_visit = std::iter::once(self.root)
+            .chain(self.absorbed_roots.iter().cloned())
+            .filter(|root| *root != HASHED_NULL_NODE)
+            .collect::<Vec<H256>>();
+        while let Some(hash) = hashes_to_visit.pop() {
+            let node = self.get_node(&hash)?.ok_or_else(|| {
+                AppError::Custom(format!(
+                    "✘ Cannot prune trie - node not in db: {}",
+                    convert_h256_to_prefixed_hex(hash).unwrap_or_default()
+                ))
+            })?;
+            if reachable_hashes.insert(hash) {
+                hashes_to_visit.extend(get_child_hashes(&node)?);
+            }
+        }
+        Ok(reachable_hashes)
+    }
+
+    fn put_node_in_database(&mut self, node: &Node) -> Result<()> {
+        put_counted_thing_in_database(
+            &mut self.database,
+            &mut self.ref_counts,
+            node.get_hash()?,
+            node.get_rlp_encoding()?,
+        )
+    }
+
+    fn remove_node_from_database(&mut self, node: &Node) -> Result<()> {
+        remove_counted_thing_from_database(
+            &mut self.database,
+            &mut self.ref_counts,
+            &node.get_hash()?,
+        )
+    }
+}
+
+// NOTE: Only reachable parents reference a node, so stale ones left by puts count once until pruned.
+fn count_node_references(database: &Database, root: &H256) -> Result<RefCounts> {
+    let mut ref_counts = database
+        .keys()
+        .map(|hash| (*hash, 0))
+        .collect::<RefCounts>();
+    let mut visited_hashes = HashSet::new();
+    let mut hashes_to_visit = vec![*root];
+    if let Some(ref_count) = ref_counts.get_mut(root) {
+        *ref_count += 1;
+    }
+    while let Some(hash) = hashes_to_visit.pop() {
+        if !visited_hashes.insert(hash) {
+            continue;
+        }
+        if let Some(encoded_node) = database.get(&hash) {
+            for child_hash in get_child_hashes(&rlp_decode_node(encoded_node.clone())?)? {
+                if let Some(ref_count) = ref_counts.get_mut(&child_hash) {
+                    *ref_count += 1;
+                    hashes_to_visit.push(child_hash);
+                }
+            }
+        }
+    }
+    Ok(ref_counts
+        .into_iter()
+        .map(|(hash, ref_count)| (hash, ref_count.max(1)))
+        .collect())
+}
+
+// NOTE: Receipts tries never inline nodes, so only 32 byte children are hashes of other nodes.
+fn get_child_hashes(node: &Node) -> Result<Vec<H256>> {
+    match (node.get_extension(), node.get_branch()) {
+        (Some(extension), _) => vec![extension.value.clone()],
+        (_, Some(branch)) => branch.branches.iter().flatten().cloned().collect(),
+        _ => vec![],
     }
+    .iter()
+    .filter(|child| child.len() == HASH_LENGTH)
+    .map(|child| convert_bytes_to_h256(child))
+    .collect()
+}
+
+// NOTE: Pushed in reverse so the DOT walk visits a branch's children in nibble order.
+fn get_child_hashes_w_nibbles(node: &Node) -> Result<Vec<(Option<usize>, H256)>> {
+    match (node.get_extension(), node.get_branch()) {
+        (Some(extension), _) => vec![(None, &extension.value)],
+        (_, Some(branch)) => branch
+            .branches
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(nibble, child)| child.as_ref().map(|child| (Some(nibble), child)))
+            .collect(),
+        _ => vec![],
+    }
+    .into_iter()
+    .filter(|(_, child)| child.len() == HASH_LENGTH)
+    .map(|(maybe_nibble, child)| Ok((maybe_nibble, convert_bytes_to_h256(child)?)))
+    .collect()
+}
+
+fn get_dot_node(hash: &H256, node: &Node) -> Result<String> {
+    let hash_hex = convert_h256_to_prefixed_hex(*hash)?;
+    let path_nibbles = node
+        .get_key()
+        .to_nibble_values()?
+        .iter()
+        .map(|nibble| format!("{:x}", nibble))
+        .collect::<String>();
+    let (color, detail) = match node.get_type() {
+        NodeKind::Leaf => (
+            "palegreen",
+            format!(
+                "\\npath: {}\\nvalue: {} bytes",
+                path_nibbles,
+                node.get_value().unwrap_or_default().len()
+            ),
+        ),
+        NodeKind::Extension => ("lightskyblue", format!("\\npath: {}", path_nibbles)),
+        NodeKind::Branch => (
+            "khaki",
+            node.get_value().map_or(String::new(), |value| {
+                format!("\\nvalue: {} bytes", value.len())
+            }),
+        ),
+    };
+    Ok(format!(
+        "    \"{}\" [label=\"{}\\n{}…{}\", fillcolor={}];",
+        hash_hex,
+        node.get_type(),
+        &hash_hex[..10],
+        detail,
+        color
+    ))
 }
 
 fn get_key_length_accounted_for_in_stack(node_stack: &[Node]) -> usize {
     node_stack.iter().map(|node| node.get_key_length()).sum()
 }
 
-pub fn put_in_trie_recursively(
-    trie: Trie,
-    key_value_tuples: Vec<(Nibbles, Bytes)>,
+pub fn put_in_trie_recursively<K: TrieKey>(
+    mut trie: Trie,
+    key_value_tuples: Vec<(K, Bytes)>,
     i: usize,
 ) -> Result<Trie> {
     match i == key_value_tuples.len() {
         true => Ok(trie),
         false => {
             trace!("Putting item #{} in trie recursively...", i + 1);
-            trie.put(key_value_tuples[i].0.clone(), key_value_tuples[i].1.clone())
-                .and_then(|new_trie| put_in_trie_recursively(new_trie, key_value_tuples, i + 1))
+            trie.put_mut(
+                key_value_tuples[i].0.to_nibbles(),
+                key_value_tuples[i].1.clone(),
+            )
+            .and_then(|_| put_in_trie_recursively(trie, key_value_tuples, i + 1))
         }
     }
 }
@@ -814,15 +1314,99 @@ pub fn put_in_trie_recursively(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::get_branch_from_trie::get_branch_from_trie;
     use crate::get_database::get_thing_from_database;
-    use crate::rlp_codec::get_rlp_encoded_receipts_and_nibble_tuples;
+    use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+    use crate::nibble_utils::get_nibbles_from_offset_bytes;
+    use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
     use crate::test_utils::{
         convert_hex_string_to_nibbles, get_sample_branch_node, get_sample_extension_node,
-        get_sample_leaf_node, get_sample_receipts, get_sample_tx_hashes_1, get_sample_tx_hashes_2,
-        get_sample_tx_hashes_3, RECEIPTS_ROOT_1, RECEIPTS_ROOT_2, RECEIPTS_ROOT_3,
+        get_sample_leaf_node, get_sample_proof_1, get_sample_receipts,
+        get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1, get_sample_tx_hashes_2,
+        get_sample_tx_hashes_3, PROOF_1_INDEX, RECEIPTS_ROOT_1, RECEIPTS_ROOT_2, RECEIPTS_ROOT_3,
         SAMPLE_RECEIPT_JSONS_1_PATH, SAMPLE_RECEIPT_JSONS_2_PATH, SAMPLE_RECEIPT_JSONS_3_PATH,
     };
-    use crate::utils::{convert_h256_to_prefixed_hex, convert_hex_to_h256};
+    use crate::trie_keys::ReceiptKey;
+    use crate::utils::{convert_bytes_to_hex, convert_hex_to_h256};
+    use crate::verify_proof::verify_merkle_proof;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    fn get_sample_snapshot_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("rrpm-trie-snapshot-{}", name))
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn should_export_and_import_trie_snapshot() {
+        let path = get_sample_snapshot_path("round-trip");
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        trie.export(&path).unwrap();
+        let result = Trie::import(&path).unwrap();
+        assert!(result.root == trie.root);
+        assert!(result.database == trie.database);
+        assert!(result.ref_counts == trie.ref_counts);
+        assert!(convert_h256_to_prefixed_hex(result.root).unwrap() == RECEIPTS_ROOT_1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_render_trie_as_dot() {
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let result = trie.to_dot().unwrap();
+        let num_nodes = result.matches("fillcolor=").count();
+        let num_edges = result.matches(" -> ").count();
+        assert!(result.starts_with("digraph trie {"));
+        assert!(result.ends_with('}'));
+        assert!(result.contains(&format!("\"{}\" [label=\"branch", RECEIPTS_ROOT_1)));
+        assert!(result.matches("fillcolor=palegreen").count() == get_sample_tx_hashes_1().len());
+        assert!(num_edges == num_nodes - 1);
+        assert!(
+            Trie::get_new_trie()
+                .unwrap()
+                .to_dot()
+                .unwrap()
+                .lines()
+                .count()
+                == 3
+        );
+    }
+
+    #[test]
+    fn should_export_and_import_empty_trie_snapshot() {
+        let path = get_sample_snapshot_path("empty");
+        Trie::get_new_trie().unwrap().export(&path).unwrap();
+        let result = Trie::import(&path).unwrap();
+        assert!(result.root == HASHED_NULL_NODE);
+        assert!(result.database.is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_import_trie_snapshot_missing_root_node() {
+        let path = get_sample_snapshot_path("missing-root");
+        let mut trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let root = trie.root;
+        trie.database.remove(&root);
+        trie.export(&path).unwrap();
+        match Trie::import(&path) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not contain its root")),
+            _ => panic!("Should not import snapshot without root node!"),
+        }
+        fs::remove_file(&path).unwrap();
+    }
 
     #[test]
     fn should_get_empty_trie() {
@@ -845,6 +1429,52 @@ mod tests {
         assert!(thing_from_db == expected_thing_from_db)
     }
 
+    #[test]
+    fn should_put_sample_receipts_1_in_trie_in_place() {
+        let key_value_tuples = get_rlp_encoded_receipts_and_key_tuples(&get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        ))
+        .unwrap();
+        let mut trie = Trie::get_new_trie().unwrap();
+        key_value_tuples
+            .into_iter()
+            .for_each(|(key, value)| trie.put_mut(key.to_nibbles(), value).unwrap());
+        assert!(trie.root == convert_hex_to_h256(RECEIPTS_ROOT_1.to_string()).unwrap());
+    }
+
+    #[test]
+    fn should_absorb_switch_root_prune_and_prove_trie_in_place() {
+        let trie_1 = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let mut trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_2_PATH.to_string(),
+            get_sample_tx_hashes_2(),
+        );
+        let root_1 = trie_1.root;
+        let root_2 = trie.root;
+        let key = ReceiptKey::from_index(PROOF_1_INDEX).unwrap().to_nibbles();
+        let expected_proof_1 = get_branch_from_trie(&trie_1, PROOF_1_INDEX).unwrap();
+        let expected_trie = trie
+            .clone()
+            .absorb(trie_1.clone())
+            .and_then(|trie| trie.switch_root(root_1))
+            .and_then(|trie| trie.prune())
+            .unwrap();
+        trie.absorb_mut(trie_1).unwrap();
+        trie.switch_root_mut(root_1).unwrap();
+        trie.prune_mut().unwrap();
+        assert!(trie.root == expected_trie.root);
+        assert!(trie.absorbed_roots == vec![root_2]);
+        assert!(trie.database == expected_trie.database);
+        assert!(trie.get_proof_mut(key).unwrap() == expected_proof_1);
+        assert!(trie
+            .get_proof_mut(get_nibbles_from_bytes(vec![0xff]))
+            .is_err());
+    }
+
     #[test]
     fn should_update_root_hash() {
         let trie = Trie::get_new_trie().unwrap();
@@ -863,12 +1493,12 @@ mod tests {
     fn should_put_node_in_database_in_trie() {
         let node_key = convert_hex_string_to_nibbles("c0ffe".to_string()).unwrap();
         let node_value = vec![0xde, 0xca, 0xff];
-        let trie = Trie::get_new_trie().unwrap();
+        let mut trie = Trie::get_new_trie().unwrap();
         let node = Node::get_new_leaf_node(node_key.clone(), node_value.clone()).unwrap();
         let expected_result = node.get_rlp_encoding().unwrap();
         let node_hash = node.get_hash().unwrap();
-        let updated_trie = trie.put_node_in_database(node.clone()).unwrap();
-        let result = get_thing_from_database(&updated_trie.database, &node_hash).unwrap();
+        trie.put_node_in_database(&node).unwrap();
+        let result = get_thing_from_database(&trie.database, &node_hash).unwrap();
         assert!(result == expected_result);
     }
 
@@ -876,18 +1506,18 @@ mod tests {
     fn should_remove_node_from_database() {
         let node_key = convert_hex_string_to_nibbles("c0ffe".to_string()).unwrap();
         let node_value = vec![0xde, 0xca, 0xff];
-        let trie = Trie::get_new_trie().unwrap();
+        let mut trie = Trie::get_new_trie().unwrap();
         let node = Node::get_new_leaf_node(node_key.clone(), node_value.clone()).unwrap();
         let node_hash = node.get_hash().unwrap();
-        let updated_trie = trie.put_node_in_database(node.clone()).unwrap();
-        assert!(updated_trie.database.contains_key(&node_hash));
-        let resulting_trie = updated_trie.remove_node_from_database(node).unwrap();
-        assert!(!resulting_trie.database.contains_key(&node_hash));
+        trie.put_node_in_database(&node).unwrap();
+        assert!(trie.database.contains_key(&node_hash));
+        trie.remove_node_from_database(&node).unwrap();
+        assert!(!trie.database.contains_key(&node_hash));
     }
 
     #[test]
     fn should_sum_length_of_key_so_far_in_found_stack() {
-        let mut found_stack: NodeStack = Vec::new();
+        let mut found_stack = NodeStack::new();
         let leaf_node = get_sample_leaf_node();
         let branch_node = get_sample_branch_node();
         let extension_node = get_sample_extension_node();
@@ -909,7 +1539,7 @@ mod tests {
             get_sample_tx_hashes_1(),
         );
         let trie = Trie::get_new_trie().unwrap();
-        let key_value_tuples = get_rlp_encoded_receipts_and_nibble_tuples(&receipts).unwrap();
+        let key_value_tuples = get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap();
         let updated_trie = put_in_trie_recursively(trie, key_value_tuples, index).unwrap();
         let root_hex = convert_h256_to_prefixed_hex(updated_trie.root).unwrap();
         assert!(root_hex == RECEIPTS_ROOT_1);
@@ -925,7 +1555,7 @@ mod tests {
             get_sample_tx_hashes_2(),
         );
         let trie = Trie::get_new_trie().unwrap();
-        let key_value_tuples = get_rlp_encoded_receipts_and_nibble_tuples(&receipts).unwrap();
+        let key_value_tuples = get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap();
         let updated_trie = put_in_trie_recursively(trie, key_value_tuples, index).unwrap();
         let root_hex = convert_h256_to_prefixed_hex(updated_trie.root).unwrap();
         assert!(root_hex == RECEIPTS_ROOT_2);
@@ -941,9 +1571,394 @@ mod tests {
             get_sample_tx_hashes_3(),
         );
         let trie = Trie::get_new_trie().unwrap();
-        let key_value_tuples = get_rlp_encoded_receipts_and_nibble_tuples(&receipts).unwrap();
+        let key_value_tuples = get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap();
         let updated_trie = put_in_trie_recursively(trie, key_value_tuples, index).unwrap();
         let root_hex = convert_h256_to_prefixed_hex(updated_trie.root).unwrap();
         assert!(root_hex == RECEIPTS_ROOT_3);
     }
+
+    #[test]
+    fn should_absorb_trie_and_prove_from_either_root() {
+        let trie_1 = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let trie_2 = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_2_PATH.to_string(),
+            get_sample_tx_hashes_2(),
+        );
+        let root_1 = trie_1.root;
+        let root_2 = trie_2.root;
+        let expected_proof_2 =
+            rlp_encode_node_stack(&get_branch_from_trie(&trie_2, 0).unwrap()).unwrap();
+        let num_nodes = trie_1.database.len() + trie_2.database.len();
+        let merged_trie = trie_2.absorb(trie_1).unwrap();
+        assert!(merged_trie.root == root_2);
+        assert!(merged_trie.absorbed_roots == vec![root_1]);
+        assert!(merged_trie.database.len() <= num_nodes);
+        let proof_2 = rlp_encode_node_stack(&get_branch_from_trie(&merged_trie, 0).unwrap());
+        assert!(proof_2.unwrap() == expected_proof_2);
+        let merged_trie = merged_trie.switch_root(root_1).unwrap();
+        assert!(merged_trie.absorbed_roots == vec![root_2]);
+        let proof_1 = get_branch_from_trie(&merged_trie, PROOF_1_INDEX)
+            .and_then(|branch| rlp_encode_node_stack(&branch))
+            .unwrap();
+        assert!(convert_bytes_to_hex(proof_1) == get_sample_proof_1());
+    }
+
+    #[test]
+    fn should_not_absorb_trie_with_conflicting_nodes() {
+        let trie = Trie::get_new_trie()
+            .unwrap()
+            .put(
+                convert_hex_string_to_nibbles("c0ffe".to_string()).unwrap(),
+                vec![0xde],
+            )
+            .unwrap();
+        let mut corrupt_trie = trie.clone();
+        corrupt_trie.database.insert(trie.root, vec![0xba, 0xd0]);
+        match trie.absorb(corrupt_trie) {
+            Err(AppError::Custom(e)) => assert!(e.contains("conflicting nodes")),
+            _ => panic!("Should not absorb trie w/ conflicting nodes!"),
+        }
+    }
+
+    #[test]
+    fn should_not_switch_to_unregistered_root() {
+        let trie = Trie::get_new_trie().unwrap();
+        match trie.switch_root(H256::zero()) {
+            Err(AppError::Custom(e)) => assert!(e.contains("unregistered trie root")),
+            _ => panic!("Should not switch to unregistered root!"),
+        }
+    }
+
+    /**
+     * Property Tests:
+     *
+     * These put random key/value sets in a `Trie`, in random order, & check
+     * its root matches that of `triehash`'s reference implementation. Keys
+     * are drawn from a tiny nibble alphabet so they share long prefixes,
+     * which exercises the branch & extension splitting paths. Values are at
+     * least 32 bytes long, so no node is ever small enough to be inlined.
+     * Every key's proof is then round-tripped through the verifier.
+     */
+    struct ReferenceKeccakHasher;
+
+    impl hash_db::Hasher for ReferenceKeccakHasher {
+        type Out = [u8; 32];
+        type StdHasher = hash256_std_hasher::Hash256StdHasher;
+        const LENGTH: usize = 32;
+
+        fn hash(bytes: &[u8]) -> Self::Out {
+            tiny_keccak::keccak256(bytes)
+        }
+    }
+
+    struct FuzzKey(Bytes);
+
+    impl TrieKey for FuzzKey {
+        fn as_bytes(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    fn get_hashes_of_nodes_on_paths<K: TrieKey>(
+        trie: &Trie,
+        key_value_tuples: &[(K, Bytes)],
+    ) -> HashSet<H256> {
+        key_value_tuples
+            .iter()
+            .flat_map(|(key, _)| trie.find(key.to_nibbles()).unwrap().1)
+            .map(|node| node.get_hash().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn should_prune_trie_to_only_reachable_nodes_after_bulk_insertion() {
+        let key_value_tuples = (0..300)
+            .map(|i| {
+                (
+                    ReceiptKey::from_index(i).unwrap(),
+                    vec![(i % 256) as u8; 48],
+                )
+            })
+            .collect::<Vec<(ReceiptKey, Bytes)>>();
+        let order = (0..300)
+            .rev()
+            .step_by(2)
+            .chain((0..300).step_by(2))
+            .collect::<Vec<usize>>();
+        let mut trie = put_in_trie_recursively(
+            Trie::get_new_trie().unwrap(),
+            get_shuffled_key_value_tuples(key_value_tuples.clone(), &order),
+            0,
+        )
+        .unwrap();
+        let orphan_node = get_sample_leaf_node();
+        trie.put_node_in_database(&orphan_node).unwrap();
+        let root = trie.root;
+        let reachable_hashes = get_hashes_of_nodes_on_paths(&trie, &key_value_tuples);
+        assert!(trie.database.len() > reachable_hashes.len());
+        let result = trie.prune().unwrap();
+        assert!(result.root == root);
+        assert!(result.database.len() == reachable_hashes.len());
+        assert!(!result
+            .database
+            .contains_key(&orphan_node.get_hash().unwrap()));
+        assert_proofs_verify(&result, &key_value_tuples);
+    }
+
+    #[test]
+    fn should_keep_nodes_of_absorbed_tries_when_pruning() {
+        let trie_1 = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let trie_2 = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_2_PATH.to_string(),
+            get_sample_tx_hashes_2(),
+        );
+        let root_1 = trie_1.root;
+        let merged_trie = trie_2.absorb(trie_1).unwrap().prune().unwrap();
+        let proof_1 =
+            get_branch_from_trie(&merged_trie.switch_root(root_1).unwrap(), PROOF_1_INDEX)
+                .and_then(|branch| rlp_encode_node_stack(&branch))
+                .unwrap();
+        assert!(convert_bytes_to_hex(proof_1) == get_sample_proof_1());
+    }
+
+    #[test]
+    fn should_keep_nodes_shared_w_absorbed_trie_when_put_makes_them_stale() {
+        let get_key = |byte| get_nibbles_from_bytes(vec![byte]);
+        let trie_1 = Trie::get_new_trie()
+            .unwrap()
+            .put(get_key(0x01), vec![0xc0])
+            .and_then(|trie| trie.put(get_key(0x02), vec![0xff]))
+            .unwrap();
+        let trie_2 = Trie::get_new_trie()
+            .unwrap()
+            .put(get_key(0x01), vec![0xc0])
+            .and_then(|trie| trie.put(get_key(0x02), vec![0xff]))
+            .and_then(|trie| trie.put(get_key(0x30), vec![0xee]))
+            .unwrap();
+        let root_1 = trie_1.root;
+        // NOTE: Both tries hold the branch under nibble `0` w/ leaves at nibbles `1` & `2`.
+        let stack_1 = trie_1.find(get_key(0x01)).unwrap().1;
+        let shared_branch_hash = stack_1[stack_1.len() - 2].get_hash().unwrap();
+        let merged_trie = trie_2.absorb(trie_1).unwrap();
+        assert!(merged_trie.ref_counts[&shared_branch_hash] == 2);
+        let merged_trie = merged_trie.put(get_key(0x01), vec![0xde]).unwrap();
+        assert!(merged_trie.ref_counts[&shared_branch_hash] == 1);
+        let (_, stack, remaining_key) = merged_trie
+            .switch_root(root_1)
+            .unwrap()
+            .find(get_key(0x01))
+            .unwrap();
+        assert!(remaining_key.is_empty());
+        assert!(stack.last().unwrap().get_value() == Some(vec![0xc0]));
+    }
+
+    #[test]
+    fn should_get_trie_len_node_count_and_check_contains_key() {
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let num_receipts = get_sample_tx_hashes_1().len();
+        let get_key = |index| ReceiptKey::from_index(index).unwrap().to_nibbles();
+        assert!(trie.len().unwrap() == num_receipts);
+        assert!(trie.node_count().unwrap() == trie.clone().prune().unwrap().database.len());
+        assert!(trie.contains_key(get_key(0)).unwrap());
+        assert!(trie.contains_key(get_key(num_receipts - 1)).unwrap());
+        assert!(!trie.contains_key(get_key(num_receipts)).unwrap());
+        assert!(!trie.contains_key(EMPTY_NIBBLES).unwrap());
+        let empty_trie = Trie::get_new_trie().unwrap();
+        assert!(empty_trie.is_empty());
+        assert!(empty_trie.len().unwrap() == 0);
+        assert!(empty_trie.node_count().unwrap() == 0);
+        assert!(!empty_trie.contains_key(get_key(0)).unwrap());
+    }
+
+    #[test]
+    fn should_not_match_prefix_or_extension_of_stored_key() {
+        let value = vec![0xc0; 40];
+        let stored_key = get_nibbles_from_bytes(vec![0x12, 0x34]);
+        let trie = Trie::get_new_trie()
+            .unwrap()
+            .put(stored_key.clone(), value.clone())
+            .unwrap();
+        let prefix_keys = vec![
+            get_nibbles_from_bytes(vec![0x12]),
+            get_nibbles_from_offset_bytes(vec![0x01, 0x23]),
+        ];
+        let extension_keys = vec![
+            get_nibbles_from_bytes(vec![0x12, 0x34, 0x56]),
+            get_nibbles_from_offset_bytes(vec![0x01, 0x23, 0x45]),
+        ];
+        assert!(trie.get(stored_key.clone()).unwrap() == Some(value));
+        assert!(trie.contains_key(stored_key).unwrap());
+        prefix_keys
+            .into_iter()
+            .chain(extension_keys)
+            .for_each(|key| {
+                assert!(trie.get(key.clone()).unwrap().is_none());
+                assert!(!trie.contains_key(key).unwrap());
+            });
+    }
+
+    #[test]
+    fn should_not_match_key_used_up_at_branch_or_above_leaf() {
+        let keys_and_values = vec![
+            (get_nibbles_from_bytes(vec![0x10, 0x20]), vec![0xc1; 40]),
+            (get_nibbles_from_bytes(vec![0x12]), vec![0xc2; 40]),
+            (get_nibbles_from_bytes(vec![0x14]), vec![0xc3; 40]),
+        ];
+        let trie = keys_and_values
+            .iter()
+            .fold(Trie::get_new_trie(), |trie, (key, value)| {
+                trie.and_then(|trie| trie.put(key.clone(), value.clone()))
+            })
+            .unwrap();
+        let unmatched_keys = vec![
+            get_nibbles_from_offset_bytes(vec![0x01]),
+            get_nibbles_from_bytes(vec![0x10]),
+            get_nibbles_from_offset_bytes(vec![0x01, 0x02]),
+            get_nibbles_from_bytes(vec![0x11]),
+            get_nibbles_from_offset_bytes(vec![0x01, 0x20]),
+        ];
+        keys_and_values.into_iter().for_each(|(key, value)| {
+            assert!(trie.get(key.clone()).unwrap() == Some(value));
+            assert!(trie.contains_key(key).unwrap());
+        });
+        unmatched_keys.into_iter().for_each(|key| {
+            assert!(trie.get(key.clone()).unwrap().is_none());
+            assert!(!trie.contains_key(key).unwrap());
+        });
+    }
+
+    #[test]
+    fn should_fail_to_put_key_used_up_at_branch() {
+        let trie = Trie::get_new_trie()
+            .unwrap()
+            .put(get_nibbles_from_bytes(vec![0x10]), vec![0xc1; 40])
+            .unwrap()
+            .put(get_nibbles_from_bytes(vec![0x12]), vec![0xc2; 40])
+            .unwrap();
+        assert!(trie
+            .put(get_nibbles_from_offset_bytes(vec![0x01]), vec![0xc3; 40])
+            .is_err());
+    }
+
+    #[test]
+    fn should_prune_empty_trie() {
+        let result = Trie::get_new_trie().unwrap().prune().unwrap();
+        assert!(result.root == HASHED_NULL_NODE);
+        assert!(result.database.is_empty());
+    }
+
+    fn get_shuffled_key_value_tuples<K: TrieKey>(
+        key_value_tuples: Vec<(K, Bytes)>,
+        order: &[usize],
+    ) -> Vec<(K, Bytes)> {
+        let mut maybe_tuples: Vec<Option<(K, Bytes)>> =
+            key_value_tuples.into_iter().map(Some).collect();
+        order
+            .iter()
+            .filter_map(|i| maybe_tuples[*i].take())
+            .collect()
+    }
+
+    fn assert_proofs_verify<K: TrieKey>(trie: &Trie, key_value_tuples: &[(K, Bytes)]) {
+        key_value_tuples.iter().for_each(|(key, value)| {
+            let (_, branch, remaining_key) = trie.find(key.to_nibbles()).unwrap();
+            assert!(remaining_key.is_empty());
+            let proof = branch
+                .iter()
+                .map(|node| node.get_rlp_encoding())
+                .collect::<Result<Vec<Bytes>>>()
+                .unwrap();
+            assert!(&verify_merkle_proof(trie.root, key, &proof).unwrap() == value);
+        });
+    }
+
+    fn get_shuffled_order_strategy(num_items: usize) -> impl Strategy<Value = Vec<usize>> {
+        Just((0..num_items).collect::<Vec<usize>>()).prop_shuffle()
+    }
+
+    fn get_values_and_order_strategy(
+        max_num_values: usize,
+    ) -> impl Strategy<Value = (Vec<Bytes>, Vec<usize>)> {
+        prop::collection::vec(
+            prop::collection::vec(any::<u8>(), 32..96),
+            1..max_num_values,
+        )
+        .prop_flat_map(|values| {
+            let order = get_shuffled_order_strategy(values.len());
+            (Just(values), order)
+        })
+    }
+
+    fn get_prefix_sharing_key_values_and_order_strategy(
+        max_num_values: usize,
+    ) -> impl Strategy<Value = (BTreeMap<Bytes, Bytes>, Vec<usize>)> {
+        prop::collection::btree_map(
+            prop::collection::vec(prop::sample::select(vec![0x00u8, 0x01, 0x10, 0x11]), 4),
+            prop::collection::vec(any::<u8>(), 32..64),
+            1..max_num_values,
+        )
+        .prop_flat_map(|key_value_map| {
+            let order = get_shuffled_order_strategy(key_value_map.len());
+            (Just(key_value_map), order)
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn should_match_reference_root_for_receipt_keyed_values(
+            (values, order) in get_values_and_order_strategy(300)
+        ) {
+            let key_value_tuples = values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (ReceiptKey::from_index(i).unwrap(), value.clone()))
+                .collect::<Vec<(ReceiptKey, Bytes)>>();
+            let trie = put_in_trie_recursively(
+                Trie::get_new_trie().unwrap(),
+                get_shuffled_key_value_tuples(key_value_tuples.clone(), &order),
+                0,
+            )
+            .unwrap();
+            let expected_root = triehash::ordered_trie_root::<ReferenceKeccakHasher, _>(&values);
+            prop_assert_eq!(trie.root, H256::from(expected_root));
+            assert_proofs_verify(&trie, &key_value_tuples);
+        }
+
+        #[test]
+        fn should_match_reference_root_for_keys_sharing_long_prefixes(
+            (key_value_map, order) in get_prefix_sharing_key_values_and_order_strategy(64)
+        ) {
+            let key_value_tuples = key_value_map
+                .iter()
+                .map(|(key, value)| (FuzzKey(key.clone()), value.clone()))
+                .collect::<Vec<(FuzzKey, Bytes)>>();
+            let trie = put_in_trie_recursively(
+                Trie::get_new_trie().unwrap(),
+                get_shuffled_key_value_tuples(
+                    key_value_map
+                        .iter()
+                        .map(|(key, value)| (FuzzKey(key.clone()), value.clone()))
+                        .collect(),
+                    &order,
+                ),
+                0,
+            )
+            .unwrap();
+            let expected_root =
+                triehash::trie_root::<ReferenceKeccakHasher, _, _, _>(key_value_map);
+            prop_assert_eq!(trie.root, H256::from(expected_root));
+            assert_proofs_verify(&trie, &key_value_tuples);
+        }
+    }
 }
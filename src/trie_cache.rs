@@ -0,0 +1,186 @@
+use crate::trie::Trie;
+use crate::trie_checkpoint::write_atomically;
+use crate::utils::convert_h256_to_prefixed_hex;
+use ethereum_types::H256;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/**
+ * Trie Cache:
+ *
+ * An LRU cache of receipts tries keyed by block hash, holding at most its
+ * capacity's worth in memory, since every `Trie` owns its whole node map.
+ * W/ a spill dir, the least recently used tries aren't just dropped but
+ * spilled there as snapshots, named like those in the `--cache-dir`, & a
+ * later miss reads them back in, so long runs stay w/in a memory budget w/o
+ * rebuilding tries. A spilled snapshot is only read back if its root is the
+ * one spilled, & tries already on disk, e.g. cached, aren't written again.
+ */
+pub struct TrieCache {
+    capacity: usize,
+    tries: HashMap<H256, Arc<Trie>>,
+    recency: VecDeque<H256>,
+    maybe_spill_dir: Option<String>,
+    spilled_roots: HashMap<H256, H256>,
+}
+
+impl TrieCache {
+    pub fn new(capacity: usize) -> Self {
+        TrieCache {
+            capacity,
+            tries: HashMap::new(),
+            recency: VecDeque::new(),
+            maybe_spill_dir: None,
+            spilled_roots: HashMap::new(),
+        }
+    }
+
+    pub fn new_w_spill_dir(capacity: usize, spill_dir: &str) -> Self {
+        TrieCache {
+            maybe_spill_dir: Some(spill_dir.to_string()),
+            ..Self::new(capacity)
+        }
+    }
+
+    fn get_spill_path(&self, block_hash: &H256) -> Option<String> {
+        let spill_dir = self.maybe_spill_dir.as_ref()?;
+        let block_hash = convert_h256_to_prefixed_hex(*block_hash).ok()?;
+        Some(
+            Path::new(spill_dir)
+                .join(format!("{}.trie", block_hash))
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+
+    // NOTE: Spilling is best effort, so failing to spill a trie only means rebuilding it.
+    fn maybe_spill(&mut self, block_hash: H256, trie: &Trie) {
+        let path = match self.get_spill_path(&block_hash) {
+            Some(path) => path,
+            None => return,
+        };
+        let result = match Path::new(&path).exists() {
+            true => Ok(()),
+            false => Path::new(&path)
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .map_err(Into::into)
+                .and_then(|_| write_atomically(&path, |temp_path| trie.export(temp_path))),
+        };
+        match result {
+            Ok(_) => {
+                self.spilled_roots.insert(block_hash, trie.root);
+            }
+            Err(e) => warn!("✘ Could not spill receipts trie to {}: {}", path, e),
+        }
+    }
+
+    fn maybe_read_spilled(&self, block_hash: &H256) -> Option<Trie> {
+        let spilled_root = self.spilled_roots.get(block_hash)?;
+        let path = self.get_spill_path(block_hash)?;
+        match Trie::import(&path) {
+            Ok(trie) if trie.root == *spilled_root => Some(trie),
+            Ok(_) => {
+                warn!("✘ Spilled receipts trie at {} has the wrong root!", path);
+                None
+            }
+            Err(e) => {
+                warn!("✘ Could not read spilled receipts trie at {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    fn touch(&mut self, block_hash: &H256) {
+        self.recency.retain(|hash| hash != block_hash);
+        self.recency.push_back(*block_hash);
+    }
+
+    pub fn get(&mut self, block_hash: &H256) -> Option<Arc<Trie>> {
+        if let Some(trie) = self.tries.get(block_hash).cloned() {
+            self.touch(block_hash);
+            return Some(trie);
+        }
+        let trie = Arc::new(self.maybe_read_spilled(block_hash)?);
+        debug!("✔ Read spilled receipts trie back into memory");
+        self.insert(*block_hash, trie.clone());
+        Some(trie)
+    }
+
+    pub fn insert(&mut self, block_hash: H256, trie: Arc<Trie>) {
+        self.tries.insert(block_hash, trie);
+        self.touch(&block_hash);
+        while self.tries.len() > self.capacity {
+            if let Some(least_recent_hash) = self.recency.pop_front() {
+                if let Some(least_recent_trie) = self.tries.remove(&least_recent_hash) {
+                    self.maybe_spill(least_recent_hash, &least_recent_trie);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_receipts_trie::get_receipts_trie_from_receipts;
+    use crate::test_utils::{
+        get_sample_receipts, get_sample_tx_hashes_1, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    fn get_sample_trie() -> Arc<Trie> {
+        Arc::new(
+            get_receipts_trie_from_receipts(&get_sample_receipts(
+                SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+                get_sample_tx_hashes_1(),
+            ))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn should_evict_least_recently_used_tries_from_cache() {
+        let trie = get_sample_trie();
+        let mut cache = TrieCache::new(2);
+        cache.insert(H256::from_low_u64_be(1), trie.clone());
+        cache.insert(H256::from_low_u64_be(2), trie.clone());
+        assert!(cache.get(&H256::from_low_u64_be(1)).is_some());
+        cache.insert(H256::from_low_u64_be(3), trie);
+        assert!(cache.len() == 2);
+        assert!(cache.get(&H256::from_low_u64_be(1)).is_some());
+        assert!(cache.get(&H256::from_low_u64_be(2)).is_none());
+        assert!(cache.get(&H256::from_low_u64_be(3)).is_some());
+    }
+
+    #[test]
+    fn should_spill_evicted_tries_to_disk_and_read_them_back() {
+        let dir = std::env::temp_dir().join("rrpm-trie-cache-spill");
+        let dir = dir.to_str().unwrap().to_string();
+        let _ = fs::remove_dir_all(&dir);
+        let trie = get_sample_trie();
+        let mut cache = TrieCache::new_w_spill_dir(1, &dir);
+        cache.insert(H256::from_low_u64_be(1), trie.clone());
+        cache.insert(H256::from_low_u64_be(2), trie.clone());
+        let num_in_memory = cache.len();
+        let spilled_path = cache.get_spill_path(&H256::from_low_u64_be(1)).unwrap();
+        let was_spilled = Path::new(&spilled_path).exists();
+        let result = cache.get(&H256::from_low_u64_be(1));
+        let num_in_memory_after_read = cache.len();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(num_in_memory == 1);
+        assert!(was_spilled);
+        assert!(result.unwrap().root == trie.root);
+        assert!(num_in_memory_after_read == 1);
+        assert!(cache.get(&H256::from_low_u64_be(3)).is_none());
+    }
+}
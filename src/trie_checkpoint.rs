@@ -0,0 +1,279 @@
+use crate::errors::AppError;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::rlp_codec::Encoder;
+use crate::trie::{put_in_trie_recursively, Trie};
+use crate::trie_keys::TrieKey;
+use crate::trie_snapshot::get_trie_cache_path;
+use crate::types::{Block, Bytes, Result};
+use crate::utils::convert_h256_to_prefixed_hex;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub const TRIE_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/**
+ * Trie Checkpoints:
+ *
+ * Building the receipts trie of an enormous block on a slow machine can take
+ * long enough that an interruption midway hurts. So w/ a `--cache-dir`, the
+ * partially built trie is checkpointed there every few seconds: a snapshot of
+ * the trie so far, alongside a record of its root & the index of the next
+ * receipt to put in it. An interrupted build of the same block then resumes
+ * from the last checkpoint rather than from receipt 0. A checkpoint is only
+ * resumed from if it was made from the exact same receipt encodings, e.g. not
+ * if built w/ another `--chain`, & if the snapshot's root is the one recorded.
+ * Both files are written atomically, & removed once the build completes.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieCheckpoint {
+    pub block_hash: String,
+    pub receipts_hash: String,
+    pub root: String,
+    pub next_receipt_index: usize,
+}
+
+struct CheckpointPaths {
+    checkpoint: String,
+    snapshot: String,
+}
+
+fn get_checkpoint_paths(
+    maybe_cache_dir: &Option<String>,
+    block: &Block,
+) -> Option<CheckpointPaths> {
+    Some(CheckpointPaths {
+        checkpoint: get_trie_cache_path(maybe_cache_dir, block, "checkpoint.json")?,
+        snapshot: get_trie_cache_path(maybe_cache_dir, block, "checkpoint.trie")?,
+    })
+}
+
+pub fn get_receipts_hash<K: TrieKey>(key_value_tuples: &[(K, Bytes)]) -> Result<String> {
+    let mut encoder = Encoder::new_list(key_value_tuples.len());
+    key_value_tuples.iter().for_each(|(_, value)| {
+        encoder.append(value);
+    });
+    convert_h256_to_prefixed_hex(keccak_hash_bytes(&encoder.out())?)
+}
+
+pub fn write_atomically(path: &str, write: impl FnOnce(&str) -> Result<()>) -> Result<()> {
+    let temp_path = format!("{}.tmp", path);
+    write(&temp_path)?;
+    Ok(fs::rename(temp_path, path)?)
+}
+
+fn write_checkpoint(
+    paths: &CheckpointPaths,
+    checkpoint: &TrieCheckpoint,
+    trie: &Trie,
+) -> Result<()> {
+    Path::new(&paths.checkpoint)
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)?;
+    write_atomically(&paths.snapshot, |path| trie.export(path))?;
+    write_atomically(&paths.checkpoint, |path| {
+        Ok(fs::write(path, serde_json::to_string(checkpoint)?)?)
+    })
+}
+
+// NOTE: Checkpointing is best effort, so failing to write one never fails the build.
+fn maybe_write_checkpoint(paths: &CheckpointPaths, checkpoint: &TrieCheckpoint, trie: &Trie) {
+    match write_checkpoint(paths, checkpoint, trie) {
+        Ok(_) => info!(
+            "✔ Checkpointed receipts trie before receipt #{}",
+            checkpoint.next_receipt_index
+        ),
+        Err(e) => warn!("✘ Could not checkpoint receipts trie: {}", e),
+    }
+}
+
+fn remove_checkpoint(paths: &CheckpointPaths) {
+    [&paths.checkpoint, &paths.snapshot]
+        .iter()
+        .filter(|path| Path::new(path).exists())
+        .for_each(|path| {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("✘ Could not remove trie checkpoint at {}: {}", path, e);
+            }
+        });
+}
+
+fn read_checkpoint(paths: &CheckpointPaths, expected: &TrieCheckpoint) -> Result<(Trie, usize)> {
+    let checkpoint: TrieCheckpoint = serde_json::from_str(&fs::read_to_string(&paths.checkpoint)?)?;
+    if checkpoint.block_hash != expected.block_hash
+        || checkpoint.receipts_hash != expected.receipts_hash
+    {
+        return Err(AppError::Custom(
+            "✘ Trie checkpoint was made from different receipts!".to_string(),
+        ));
+    }
+    let trie = Trie::import(&paths.snapshot)?;
+    match convert_h256_to_prefixed_hex(trie.root)? == checkpoint.root {
+        true => Ok((trie, checkpoint.next_receipt_index)),
+        false => Err(AppError::Custom(
+            "✘ Trie checkpoint snapshot does not have the checkpointed root!".to_string(),
+        )),
+    }
+}
+
+fn maybe_resume_from_checkpoint(
+    paths: &CheckpointPaths,
+    expected: &TrieCheckpoint,
+) -> Option<(Trie, usize)> {
+    if !Path::new(&paths.checkpoint).exists() {
+        return None;
+    }
+    match read_checkpoint(paths, expected) {
+        Ok((trie, next_receipt_index)) => {
+            info!(
+                "✔ Resuming receipts trie build from checkpoint at receipt #{}",
+                next_receipt_index
+            );
+            Some((trie, next_receipt_index))
+        }
+        Err(e) => {
+            warn!("✘ Ignoring trie checkpoint at {}: {}", paths.checkpoint, e);
+            None
+        }
+    }
+}
+
+pub fn put_in_trie_w_checkpoints<K: TrieKey>(
+    maybe_cache_dir: &Option<String>,
+    block: &Block,
+    key_value_tuples: Vec<(K, Bytes)>,
+    checkpoint_interval: Duration,
+) -> Result<Trie> {
+    let paths = match get_checkpoint_paths(maybe_cache_dir, block) {
+        Some(paths) => paths,
+        None => return put_in_trie_recursively(Trie::get_new_trie()?, key_value_tuples, 0),
+    };
+    let mut checkpoint = TrieCheckpoint {
+        block_hash: convert_h256_to_prefixed_hex(block.hash)?,
+        receipts_hash: get_receipts_hash(&key_value_tuples)?,
+        root: String::new(),
+        next_receipt_index: 0,
+    };
+    let (trie, start_index) = match maybe_resume_from_checkpoint(&paths, &checkpoint) {
+        Some(resumed) => resumed,
+        None => (Trie::get_new_trie()?, 0),
+    };
+    let num_receipts = key_value_tuples.len();
+    let mut last_checkpoint_time = Instant::now();
+    let trie = key_value_tuples
+        .into_iter()
+        .enumerate()
+        .skip(start_index)
+        .try_fold(trie, |mut trie, (i, (key, value))| -> Result<Trie> {
+            trie.put_mut(key.to_nibbles(), value)?;
+            if i + 1 < num_receipts && last_checkpoint_time.elapsed() >= checkpoint_interval {
+                checkpoint.root = convert_h256_to_prefixed_hex(trie.root)?;
+                checkpoint.next_receipt_index = i + 1;
+                maybe_write_checkpoint(&paths, &checkpoint, &trie);
+                last_checkpoint_time = Instant::now();
+            }
+            Ok(trie)
+        })?;
+    remove_checkpoint(&paths);
+    Ok(trie)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, RECEIPTS_ROOT_1,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::trie_keys::ReceiptKey;
+
+    fn get_sample_key_value_tuples() -> Vec<(ReceiptKey, Bytes)> {
+        get_rlp_encoded_receipts_and_key_tuples(&get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        ))
+        .unwrap()
+    }
+
+    fn get_temp_trie_cache_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("rrpm-trie-checkpoint-{}", name));
+        dir.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn should_resume_trie_build_from_checkpoint() {
+        let dir = get_temp_trie_cache_dir("resume");
+        let block = get_expected_block();
+        let key_value_tuples = get_sample_key_value_tuples();
+        let paths = get_checkpoint_paths(&Some(dir.clone()), &block).unwrap();
+        let num_checkpointed = 10;
+        let partial_trie = put_in_trie_recursively(
+            Trie::get_new_trie().unwrap(),
+            key_value_tuples[..num_checkpointed].to_vec(),
+            0,
+        )
+        .unwrap();
+        let checkpoint = TrieCheckpoint {
+            block_hash: convert_h256_to_prefixed_hex(block.hash).unwrap(),
+            receipts_hash: get_receipts_hash(&key_value_tuples).unwrap(),
+            root: convert_h256_to_prefixed_hex(partial_trie.root).unwrap(),
+            next_receipt_index: num_checkpointed,
+        };
+        write_checkpoint(&paths, &checkpoint, &partial_trie).unwrap();
+        let resumed = maybe_resume_from_checkpoint(&paths, &checkpoint);
+        let result = put_in_trie_w_checkpoints(
+            &Some(dir.clone()),
+            &block,
+            key_value_tuples,
+            TRIE_CHECKPOINT_INTERVAL,
+        )
+        .unwrap();
+        let checkpoint_removed = !Path::new(&paths.checkpoint).exists();
+        fs::remove_dir_all(dir).unwrap();
+        assert!(resumed.unwrap().1 == num_checkpointed);
+        assert!(convert_h256_to_prefixed_hex(result.root).unwrap() == RECEIPTS_ROOT_1);
+        assert!(checkpoint_removed);
+    }
+
+    #[test]
+    fn should_not_resume_from_checkpoint_of_different_receipts() {
+        let dir = get_temp_trie_cache_dir("different");
+        let block = get_expected_block();
+        let key_value_tuples = get_sample_key_value_tuples();
+        let paths = get_checkpoint_paths(&Some(dir.clone()), &block).unwrap();
+        let checkpoint = TrieCheckpoint {
+            block_hash: convert_h256_to_prefixed_hex(block.hash).unwrap(),
+            receipts_hash: get_receipts_hash(&key_value_tuples[1..]).unwrap(),
+            root: convert_h256_to_prefixed_hex(Trie::get_new_trie().unwrap().root).unwrap(),
+            next_receipt_index: 1,
+        };
+        write_checkpoint(&paths, &checkpoint, &Trie::get_new_trie().unwrap()).unwrap();
+        let expected = TrieCheckpoint {
+            receipts_hash: get_receipts_hash(&key_value_tuples).unwrap(),
+            ..checkpoint
+        };
+        let result = maybe_resume_from_checkpoint(&paths, &expected);
+        fs::remove_dir_all(dir).unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn should_checkpoint_trie_build_and_clean_up_once_built() {
+        let dir = get_temp_trie_cache_dir("checkpoint");
+        let block = get_expected_block();
+        let result = put_in_trie_w_checkpoints(
+            &Some(dir.clone()),
+            &block,
+            get_sample_key_value_tuples(),
+            Duration::ZERO,
+        )
+        .unwrap();
+        let paths = get_checkpoint_paths(&Some(dir.clone()), &block).unwrap();
+        let checkpoint_removed =
+            !Path::new(&paths.checkpoint).exists() && !Path::new(&paths.snapshot).exists();
+        fs::remove_dir_all(dir).unwrap();
+        assert!(convert_h256_to_prefixed_hex(result.root).unwrap() == RECEIPTS_ROOT_1);
+        assert!(checkpoint_removed);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::nibble_utils::{get_nibbles_from_bytes, Nibbles};
+use crate::rlp_codec::rlp_encode_transaction_index;
+use crate::types::{Byte, Bytes, Result};
+use crate::utils::convert_h256_to_bytes;
+use ethereum_types::{Address, H256, U256};
+
+/**
+ * Trie Keys:
+ *
+ * Receipts & transactions tries are keyed by the RLP encoding of the index
+ * of the transaction in its block, whereas the state trie is keyed by the
+ * keccak hash of an account's address, & a storage trie by the keccak hash
+ * of the 32-byte slot. Any other secure trie is keyed by the keccak hash of
+ * whatever raw key it's put under. All are just bytes, & so are easily
+ * confused with one another, or with a transaction hash. These wrappers can
+ * only be built from what their trie is actually keyed by, & are what the
+ * trie building & proof verification entry points accept.
+ */
+pub trait TrieKey {
+    fn as_bytes(&self) -> &[Byte];
+
+    fn to_nibbles(&self) -> Nibbles {
+        get_nibbles_from_bytes(self.as_bytes().to_vec())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptKey(Bytes);
+
+impl ReceiptKey {
+    pub fn from_index(index: usize) -> Result<Self> {
+        rlp_encode_transaction_index(&U256::from(index)).map(ReceiptKey)
+    }
+}
+
+impl TrieKey for ReceiptKey {
+    fn as_bytes(&self) -> &[Byte] {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateKey(Bytes);
+
+impl StateKey {
+    pub fn from_address(address: &Address) -> Result<Self> {
+        keccak_hash_bytes(address.as_bytes())
+            .map(convert_h256_to_bytes)
+            .map(StateKey)
+    }
+}
+
+impl TrieKey for StateKey {
+    fn as_bytes(&self) -> &[Byte] {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageKey(Bytes);
+
+impl StorageKey {
+    pub fn from_slot(slot: &H256) -> Result<Self> {
+        keccak_hash_bytes(slot.as_bytes())
+            .map(convert_h256_to_bytes)
+            .map(StorageKey)
+    }
+}
+
+impl TrieKey for StorageKey {
+    fn as_bytes(&self) -> &[Byte] {
+        &self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecureKey(Bytes);
+
+impl SecureKey {
+    pub fn from_raw_key(raw_key: &[Byte]) -> Result<Self> {
+        keccak_hash_bytes(raw_key)
+            .map(convert_h256_to_bytes)
+            .map(SecureKey)
+    }
+}
+
+impl TrieKey for SecureKey {
+    fn as_bytes(&self) -> &[Byte] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::{put_in_trie_recursively, Trie};
+    use crate::utils::{convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex};
+
+    #[test]
+    fn should_get_receipt_key_from_index() {
+        let result = ReceiptKey::from_index(10).unwrap();
+        let expected_nibbles = Nibbles {
+            data: vec![0x0a],
+            offset: 0,
+        };
+        assert!(result.as_bytes() == [0x0a]);
+        assert!(result.to_nibbles() == expected_nibbles);
+    }
+
+    #[test]
+    fn should_rlp_encode_index_zero_in_receipt_key() {
+        let result = ReceiptKey::from_index(0).unwrap();
+        assert!(result.as_bytes() == [0x80]);
+    }
+
+    #[test]
+    fn should_get_state_key_from_address() {
+        let address = Address::from_slice(&[0u8; 20]);
+        let result = StateKey::from_address(&address).unwrap();
+        assert!(
+            convert_bytes_to_prefixed_hex(result.as_bytes())
+                == "0x5380c7b7ae81a58eb98d9c78de4a1fd7fd9535fc953ed2be602daaa41767312a"
+        );
+    }
+
+    #[test]
+    fn should_get_storage_key_from_slot() {
+        let result = StorageKey::from_slot(&H256::zero()).unwrap();
+        assert!(
+            convert_bytes_to_prefixed_hex(result.as_bytes())
+                == "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+    }
+
+    #[test]
+    fn should_get_secure_key_matching_state_key() {
+        let address = Address::from_slice(&[0u8; 20]);
+        let result = SecureKey::from_raw_key(address.as_bytes()).unwrap();
+        assert!(result.as_bytes() == StateKey::from_address(&address).unwrap().as_bytes());
+    }
+
+    #[test]
+    fn should_build_trie_from_state_keys() {
+        let key_value_tuples = vec![(
+            StateKey::from_address(&Address::from_slice(&[0u8; 20])).unwrap(),
+            vec![0xc0, 0xff, 0xee],
+        )];
+        let result =
+            put_in_trie_recursively(Trie::get_new_trie().unwrap(), key_value_tuples, 0).unwrap();
+        assert!(convert_h256_to_prefixed_hex(result.root).unwrap().len() == 66);
+        assert!(result.database.len() == 1);
+    }
+}
@@ -1,6 +1,5 @@
-use crate::constants::{
-    BRANCH_NODE_STRING, EMPTY_NIBBLES, EXTENSION_NODE_STRING, LEAF_NODE_STRING,
-};
+use crate::byte_order::check_key_is_not_byte_reversed;
+use crate::constants::EMPTY_NIBBLES;
 use crate::errors::AppError;
 use crate::get_database::get_thing_from_database;
 use crate::get_keccak_hash::keccak_hash_bytes;
@@ -9,17 +8,62 @@ use crate::path_codec::{
     decode_path_to_nibbles_and_node_type, encode_extension_path_from_nibbles,
     encode_leaf_path_from_nibbles,
 };
+use crate::rlp_codec::{Decoder, Encoder};
 use crate::types::{Bytes, ChildNodes, Database, Result};
+use crate::verifier::{check_proof_node_size, VerifierError};
 use ethereum_types::H256;
-use rlp::{Rlp, RlpStream};
+use std::fmt;
+use std::sync::OnceLock;
 
 static NO_NODE_IN_STRUCT_ERR: &str = "✘ No node present in struct to rlp-encode!";
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Node {
-    pub leaf: Option<LeafNode>,
-    pub branch: Option<BranchNode>,
-    pub extension: Option<ExtensionNode>,
+    leaf: Option<LeafNode>,
+    branch: Option<BranchNode>,
+    extension: Option<ExtensionNode>,
+    cache: NodeCache,
+}
+
+/**
+ * Node Cache:
+ *
+ * A node's RLP encoding & hash are needed several times over whilst inserting
+ * into the trie, so they're memoized on first use rather than recomputed each
+ * time. A node's fields are private, so it can't be mutated in place, only
+ * rebuilt via its constructors, ∴ a cache can never go stale. It's derived
+ * data ∴ it plays no part in node equality.
+ */
+#[derive(Debug, Clone, Default)]
+struct NodeCache {
+    rlp_encoding: OnceLock<Bytes>,
+    hash: OnceLock<H256>,
+}
+
+impl PartialEq for NodeCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for NodeCache {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Leaf,
+    Branch,
+    Extension,
+}
+
+impl fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeKind::Leaf => write!(f, "leaf"),
+            NodeKind::Branch => write!(f, "branch"),
+            NodeKind::Extension => write!(f, "extension"),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -58,6 +102,7 @@ impl Node {
                 path_nibbles,
                 encoded_path,
             }),
+            cache: NodeCache::default(),
         })
     }
 
@@ -74,6 +119,7 @@ impl Node {
                 path_nibbles,
                 encoded_path,
             }),
+            cache: NodeCache::default(),
         })
     }
 
@@ -85,9 +131,22 @@ impl Node {
                 value,
                 branches: get_empty_child_nodes(),
             }),
+            cache: NodeCache::default(),
         })
     }
 
+    pub fn get_leaf(&self) -> Option<&LeafNode> {
+        self.leaf.as_ref()
+    }
+
+    pub fn get_extension(&self) -> Option<&ExtensionNode> {
+        self.extension.as_ref()
+    }
+
+    pub fn get_branch(&self) -> Option<&BranchNode> {
+        self.branch.as_ref()
+    }
+
     pub fn update_branch_at_index(self, new_value: Option<Bytes>, index: usize) -> Result<Self> {
         if let Some(branch) = self.branch {
             Ok(Node {
@@ -97,6 +156,7 @@ impl Node {
                     value: branch.value,
                     branches: update_child_nodes(branch.branches, new_value, index)?,
                 }),
+                cache: NodeCache::default(),
             })
         } else {
             Err(AppError::Custom(
@@ -106,40 +166,55 @@ impl Node {
     }
 
     pub fn get_rlp_encoding(&self) -> Result<Bytes> {
-        let mut rlp_stream = RlpStream::new();
+        if let Some(rlp_encoding) = self.cache.rlp_encoding.get() {
+            return Ok(rlp_encoding.clone());
+        }
+        let rlp_encoding = self.rlp_encode()?;
+        let _ = self.cache.rlp_encoding.set(rlp_encoding.clone());
+        Ok(rlp_encoding)
+    }
+
+    pub fn get_hash(&self) -> Result<H256> {
+        if let Some(hash) = self.cache.hash.get() {
+            return Ok(*hash);
+        }
+        let hash = self
+            .get_rlp_encoding()
+            .and_then(|encoded| keccak_hash_bytes(&encoded))?;
+        let _ = self.cache.hash.set(hash);
+        Ok(hash)
+    }
+
+    fn rlp_encode(&self) -> Result<Bytes> {
+        let mut encoder = Encoder::new();
         if let Some(leaf) = &self.leaf {
-            rlp_stream.begin_list(2);
-            rlp_stream.append(&leaf.encoded_path);
-            rlp_stream.append(&leaf.value);
-            Ok(rlp_stream.out())
+            encoder.begin_list(2);
+            encoder.append(&leaf.encoded_path);
+            encoder.append(&leaf.value);
+            Ok(encoder.out())
         } else if let Some(extension) = &self.extension {
-            rlp_stream.begin_list(2);
-            rlp_stream.append(&extension.encoded_path);
-            rlp_stream.append(&extension.value);
-            Ok(rlp_stream.out())
+            encoder.begin_list(2);
+            encoder.append(&extension.encoded_path);
+            encoder.append(&extension.value);
+            Ok(encoder.out())
         } else if let Some(branch) = &self.branch {
-            rlp_stream.begin_list(17);
+            encoder.begin_list(17);
             for i in 0..branch.branches.len() {
                 match &branch.branches[i] {
-                    None => rlp_stream.append_empty_data(),
-                    Some(thing) => rlp_stream.append(&thing.clone()),
+                    None => encoder.append_empty(),
+                    Some(thing) => encoder.append(thing),
                 };
             }
             match &branch.value {
-                None => rlp_stream.append_empty_data(),
-                Some(value) => rlp_stream.append(&value.clone()),
+                None => encoder.append_empty(),
+                Some(value) => encoder.append(value),
             };
-            Ok(rlp_stream.out())
+            Ok(encoder.out())
         } else {
             Err(AppError::Custom(NO_NODE_IN_STRUCT_ERR.to_string()))
         }
     }
 
-    pub fn get_hash(&self) -> Result<H256> {
-        self.get_rlp_encoding()
-            .and_then(|encoded| keccak_hash_bytes(&encoded))
-    }
-
     pub fn get_key(&self) -> Nibbles {
         if let Some(leaf_node) = &self.leaf {
             leaf_node.path_nibbles.clone()
@@ -176,28 +251,40 @@ impl Node {
         }
     }
 
-    pub fn get_type(&self) -> &'static str {
+    pub fn get_type(&self) -> NodeKind {
         if self.leaf.is_some() {
-            LEAF_NODE_STRING
+            NodeKind::Leaf
         } else if self.branch.is_some() {
-            BRANCH_NODE_STRING
+            NodeKind::Branch
         } else {
-            EXTENSION_NODE_STRING
+            NodeKind::Extension
         }
     }
 }
 
 pub fn rlp_decode_node(rlp_data: Bytes) -> Result<Node> {
-    match Rlp::new(&rlp_data).as_list() {
+    check_proof_node_size(&rlp_data)?;
+    let rlp = Decoder::new(&rlp_data);
+    // NOTE: Any bytes trailing the node's one RLP item would otherwise be silently ignored.
+    match rlp.get_encoded_length() {
+        Ok(encoded_length) if encoded_length == rlp_data.len() => (),
+        _ => return Err(VerifierError::MalformedRlp.into()),
+    }
+    match rlp.as_list::<Bytes>() {
         Err(e) => Err(AppError::Custom(e.to_string())),
         Ok(list) => match list.len() {
             2 => {
                 let path: &Bytes = &list[0];
                 let value: &Bytes = &list[1];
                 let (path_nibbles, node_type) = decode_path_to_nibbles_and_node_type(path.clone())?;
-                match node_type == LEAF_NODE_STRING {
-                    true => Node::get_new_leaf_node(path_nibbles, value.to_vec()),
-                    false => Node::get_new_extension_node(path_nibbles, value.to_vec()),
+                match node_type {
+                    NodeKind::Leaf => Node::get_new_leaf_node(path_nibbles, value.to_vec()),
+                    NodeKind::Extension => {
+                        Node::get_new_extension_node(path_nibbles, value.to_vec())
+                    }
+                    NodeKind::Branch => Err(AppError::Custom(
+                        "✘ Two item nodes are never branch nodes!".to_string(),
+                    )),
                 }
             }
             17 => {
@@ -220,6 +307,7 @@ pub fn rlp_decode_node(rlp_data: Bytes) -> Result<Node> {
                             None
                         },
                     }),
+                    cache: NodeCache::default(),
                 })
             }
             _ => Err(AppError::Custom(
@@ -246,21 +334,38 @@ fn update_child_nodes(
 }
 
 pub fn get_node_from_database(database: &Database, key: &H256) -> Result<Option<Node>> {
+    get_node_from_database_w_lenience(database, key, false)
+}
+
+// NOTE: A node under the byte-reversed key is never returned, since it can't hash to the key.
+pub fn get_node_from_database_w_lenience(
+    database: &Database,
+    key: &H256,
+    lenient_lookups: bool,
+) -> Result<Option<Node>> {
     match get_thing_from_database(database, key) {
-        None => Ok(None),
-        Some(encoded_node) => Ok(Some(rlp_decode_node(encoded_node)?)),
+        Some(encoded_node) => rlp_decode_node(encoded_node).map(Some),
+        None => match check_key_is_not_byte_reversed(database, key) {
+            Err(e) if lenient_lookups => {
+                warn!("{}", e);
+                Ok(None)
+            }
+            result => result.map(|_| None),
+        },
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::byte_order::reverse_h256_byte_order;
     use crate::get_database::{get_new_database, put_thing_in_database};
     use crate::nibble_utils::{get_length_in_nibbles, get_nibbles_from_bytes};
     use crate::test_utils::{
         get_sample_branch_node, get_sample_extension_node, get_sample_leaf_node,
     };
     use crate::utils::convert_hex_to_h256;
+    use crate::verifier::MAX_PROOF_NODE_SIZE;
     use hex;
 
     fn get_sample_leaf_node_expected_encoding() -> Bytes {
@@ -306,7 +411,7 @@ mod tests {
         expected_raw.append(&mut value.clone());
         let result = Node::get_new_leaf_node(path_nibbles.clone(), value.clone()).unwrap();
         let node_type = result.clone().get_type();
-        assert!(node_type == LEAF_NODE_STRING);
+        assert!(node_type == NodeKind::Leaf);
         if let Some(_) = result.extension {
             panic!("{}", panic_str)
         } else if let Some(_) = result.branch {
@@ -355,7 +460,7 @@ mod tests {
             encode_extension_path_from_nibbles(path_nibbles.clone()).unwrap();
         let result = Node::get_new_extension_node(path_nibbles.clone(), value.clone()).unwrap();
         let node_type = result.clone().get_type();
-        assert!(node_type == EXTENSION_NODE_STRING);
+        assert!(node_type == NodeKind::Extension);
         let mut expected_raw = expected_encoded_path.clone();
         expected_raw.append(&mut value.clone());
         if let Some(_) = result.leaf {
@@ -402,7 +507,7 @@ mod tests {
             panic!("{}", panic_str)
         }
         let node_type = result.clone().get_type();
-        assert!(node_type == BRANCH_NODE_STRING);
+        assert!(node_type == NodeKind::Branch);
         match result.branch {
             None => panic!("{}", panic_str),
             Some(branch) => {
@@ -425,7 +530,7 @@ mod tests {
             panic!("{}", panic_str)
         }
         let node_type = result.clone().get_type();
-        assert!(node_type == "branch".to_string());
+        assert!(node_type == NodeKind::Branch);
         match result.branch {
             None => panic!("{}", panic_str),
             Some(branch) => {
@@ -451,6 +556,21 @@ mod tests {
         assert!(result.clone().branch.unwrap().branches[index] == Some(branch_value));
     }
 
+    #[test]
+    fn should_change_memoized_hash_when_branch_is_updated() {
+        let branch_node = get_sample_branch_node();
+        let hash = branch_node.get_hash().unwrap();
+        let rlp_encoding = branch_node.get_rlp_encoding().unwrap();
+        let result = branch_node
+            .update_branch_at_index(Some(hex::decode("c0ffee").unwrap()), 0)
+            .unwrap();
+        assert!(result.get_hash().unwrap() != hash);
+        assert!(result.get_rlp_encoding().unwrap() != rlp_encoding);
+        assert!(
+            result.get_hash().unwrap() == keccak_hash_bytes(&result.rlp_encode().unwrap()).unwrap()
+        );
+    }
+
     #[test]
     fn should_fail_to_update_branch_of_non_branch_node_correctly() {
         let expected_error = "✘ Cannot update branches - not a branch node!";
@@ -553,36 +673,44 @@ mod tests {
         assert!(result == node);
     }
 
+    #[test]
+    fn should_fail_to_rlp_decode_node_w_trailing_bytes_or_too_large() {
+        let mut rlp_encoded_node = get_sample_leaf_node().get_rlp_encoding().unwrap();
+        rlp_encoded_node.push(0x80);
+        assert!(rlp_decode_node(rlp_encoded_node).is_err());
+        assert!(rlp_decode_node(vec![0xc0; MAX_PROOF_NODE_SIZE + 1]).is_err());
+    }
+
     #[test]
     fn should_get_leaf_node_from_database() {
-        let database = get_new_database().unwrap();
+        let mut database = get_new_database().unwrap();
         let node = get_sample_leaf_node();
         let key = node.get_hash().unwrap();
         let rlp_encoded_node = node.get_rlp_encoding().unwrap();
-        let updated_database = put_thing_in_database(database, key, rlp_encoded_node).unwrap();
-        let result = get_node_from_database(&updated_database, &key).unwrap();
+        put_thing_in_database(&mut database, key, rlp_encoded_node).unwrap();
+        let result = get_node_from_database(&database, &key).unwrap();
         assert!(result == Some(node));
     }
 
     #[test]
     fn should_get_branch_node_from_database() {
-        let database = get_new_database().unwrap();
+        let mut database = get_new_database().unwrap();
         let node = get_sample_branch_node();
         let key = node.get_hash().unwrap();
         let rlp_encoded_node = node.get_rlp_encoding().unwrap();
-        let updated_database = put_thing_in_database(database, key, rlp_encoded_node).unwrap();
-        let result = get_node_from_database(&updated_database, &key).unwrap();
+        put_thing_in_database(&mut database, key, rlp_encoded_node).unwrap();
+        let result = get_node_from_database(&database, &key).unwrap();
         assert!(result == Some(node));
     }
 
     #[test]
     fn should_get_extension_node_from_database() {
-        let database = get_new_database().unwrap();
+        let mut database = get_new_database().unwrap();
         let node = get_sample_branch_node();
         let key = node.get_hash().unwrap();
         let rlp_encoded_node = node.get_rlp_encoding().unwrap();
-        let updated_database = put_thing_in_database(database, key, rlp_encoded_node).unwrap();
-        let result = get_node_from_database(&updated_database, &key).unwrap();
+        put_thing_in_database(&mut database, key, rlp_encoded_node).unwrap();
+        let result = get_node_from_database(&database, &key).unwrap();
         assert!(result == Some(node));
     }
 
@@ -595,6 +723,20 @@ mod tests {
         assert!(result == None);
     }
 
+    #[test]
+    fn should_report_or_miss_node_under_byte_reversed_key_depending_on_lenience() {
+        let mut database = get_new_database().unwrap();
+        let node = get_sample_branch_node();
+        let key = node.get_hash().unwrap();
+        database.insert(
+            reverse_h256_byte_order(&key),
+            node.get_rlp_encoding().unwrap(),
+        );
+        assert!(get_node_from_database(&database, &key).is_err());
+        let result = get_node_from_database_w_lenience(&database, &key, true).unwrap();
+        assert!(result.is_none());
+    }
+
     #[test]
     fn should_get_key_length_of_leaf_node() {
         let node = get_sample_leaf_node();
@@ -618,4 +760,22 @@ mod tests {
         let result = node.get_key_length();
         assert!(result == expected_result);
     }
+
+    #[test]
+    fn should_memoize_rlp_encoding_and_hash_of_node() {
+        let node = get_sample_leaf_node();
+        let expected_hash = keccak_hash_bytes(&get_sample_leaf_node_expected_encoding()).unwrap();
+        assert!(node.get_hash().unwrap() == expected_hash);
+        assert!(node.cache.hash.get() == Some(&expected_hash));
+        assert!(node.get_hash().unwrap() == expected_hash);
+        assert!(node.get_rlp_encoding().unwrap() == get_sample_leaf_node_expected_encoding());
+    }
+
+    #[test]
+    fn should_ignore_cache_when_comparing_nodes() {
+        let node = get_sample_branch_node();
+        let hashed_node = get_sample_branch_node();
+        hashed_node.get_hash().unwrap();
+        assert!(node == hashed_node);
+    }
 }
@@ -0,0 +1,181 @@
+use crate::errors::AppError;
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::nibble_utils::{get_nibbles_from_bytes, Nibbles};
+use crate::repl::{get_node_json, run_repl_session, Repl};
+use crate::trie::Trie;
+use crate::trie_nodes::{get_node_from_database, NodeKind};
+use crate::types::{NodeStack, Result};
+use crate::utils::{
+    convert_bytes_to_hex, convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex,
+    convert_hex_to_bytes, convert_hex_to_h256,
+};
+use std::io;
+
+const TRIE_REPL_HELP_INFO: &str = "Commands:
+  put <key> <value>  Put the hex value in the trie under the hex key, printing
+                     the new root.
+  get <key>          Print the hex value under the hex key.
+  proof <key>        Print the hex proof of the value under the hex key.
+  root               Print the trie's root.
+  show <hash>        Print the decoded trie node w/ the given hash.
+  dot                Print the trie's node graph in Graphviz DOT format.
+  reset              Start over from an empty trie.
+  help               Print this message.
+  exit               Leave the REPL.";
+
+/**
+ * Trie REPL:
+ *
+ * Reproducing an insertion edge case, e.g. a leaf split into an extension &
+ * branch, otherwise means writing a test for it. This session instead starts
+ * from an empty in-memory trie, into which arbitrary hex keys & values are
+ * put, w/ the resulting root, nodes & proofs inspectable after each. Unlike
+ * receipts tries, keys are taken as is, rather than as tx indices.
+ */
+pub struct TrieReplSession {
+    trie: Trie,
+}
+
+impl TrieReplSession {
+    pub fn new() -> Result<Self> {
+        Ok(TrieReplSession {
+            trie: Trie::get_new_trie()?,
+        })
+    }
+
+    fn parse_key(hex_key: &str) -> Result<Nibbles> {
+        convert_hex_to_bytes(hex_key.to_string()).map(get_nibbles_from_bytes)
+    }
+
+    fn get_branch(&self, hex_key: &str) -> Result<NodeStack> {
+        self.trie
+            .find(Self::parse_key(hex_key)?)
+            .and_then(
+                |(_, found_stack, remaining_key)| match remaining_key.len() {
+                    0 => Ok(found_stack),
+                    _ => Err(AppError::Custom(format!(
+                        "✘ No value in trie under key: {}",
+                        hex_key
+                    ))),
+                },
+            )
+    }
+
+    fn put(&mut self, hex_key: &str, hex_value: &str) -> Result<String> {
+        let value = convert_hex_to_bytes(hex_value.to_string())?;
+        self.trie.put_mut(Self::parse_key(hex_key)?, value)?;
+        self.root()
+    }
+
+    // NOTE: An extension node's value is its child's hash, not a value put in the trie.
+    fn get(&self, hex_key: &str) -> Result<String> {
+        self.get_branch(hex_key)?
+            .last()
+            .filter(|node| node.get_type() != NodeKind::Extension)
+            .and_then(|node| node.get_value())
+            .map(|value| convert_bytes_to_prefixed_hex(&value))
+            .ok_or_else(|| AppError::Custom(format!("✘ No value in trie under key: {}", hex_key)))
+    }
+
+    fn proof(&self, hex_key: &str) -> Result<String> {
+        self.get(hex_key)?;
+        rlp_encode_node_stack(&self.get_branch(hex_key)?).map(convert_bytes_to_hex)
+    }
+
+    fn root(&self) -> Result<String> {
+        convert_h256_to_prefixed_hex(self.trie.root)
+    }
+
+    fn show(&self, hash: &str) -> Result<String> {
+        let hash = convert_hex_to_h256(hash.to_string())?;
+        match get_node_from_database(&self.trie.database, &hash)? {
+            Some(node) => Ok(serde_json::to_string_pretty(&get_node_json(&node)?)?),
+            None => Err(AppError::Custom(format!(
+                "✘ No node in trie w/ hash: {}",
+                convert_h256_to_prefixed_hex(hash)?
+            ))),
+        }
+    }
+
+    fn reset(&mut self) -> Result<String> {
+        self.trie = Trie::get_new_trie()?;
+        self.root()
+    }
+}
+
+impl Repl for TrieReplSession {
+    fn execute(&mut self, line: &str) -> Option<Result<String>> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        Some(match words.as_slice() {
+            [] => Ok(String::new()),
+            ["exit"] | ["quit"] => return None,
+            ["help"] => Ok(TRIE_REPL_HELP_INFO.to_string()),
+            ["put", hex_key, hex_value] => self.put(hex_key, hex_value),
+            ["get", hex_key] => self.get(hex_key),
+            ["proof", hex_key] => self.proof(hex_key),
+            ["root"] => self.root(),
+            ["show", hash] => self.show(hash),
+            ["dot"] => self.trie.to_dot(),
+            ["reset"] => self.reset(),
+            _ => Err(AppError::Custom(format!(
+                "✘ Unrecognised command: `{}` - try `help`!",
+                line.trim()
+            ))),
+        })
+    }
+}
+
+pub fn run_trie_repl() -> Result<String> {
+    info!("✔ Starting trie REPL on an empty trie - type `help` for commands...");
+    let mut session = TrieReplSession::new()?;
+    run_repl_session(&mut session, io::stdin().lock(), io::stdout())?;
+    Ok("✔ Exiting trie REPL!".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::HASHED_NULL_NODE;
+    use crate::rlp_codec::Decoder;
+    use crate::types::Bytes;
+    use crate::verifier::verify_merkle_proof;
+    use serde_json::Value as Json;
+
+    fn execute(session: &mut TrieReplSession, line: &str) -> Result<String> {
+        session.execute(line).unwrap()
+    }
+
+    #[test]
+    fn should_put_get_and_prove_values_in_trie_repl_session() {
+        let mut session = TrieReplSession::new().unwrap();
+        let empty_root = execute(&mut session, "root").unwrap();
+        assert!(empty_root == convert_h256_to_prefixed_hex(HASHED_NULL_NODE).unwrap());
+        execute(&mut session, "put 0x01 0xc0ffee").unwrap();
+        // NOTE: Shares a nibble w/ the first key, so splits its leaf into an extension & branch.
+        let root = execute(&mut session, "put 0x02 0xdecaf0").unwrap();
+        assert!(root != empty_root);
+        assert!(execute(&mut session, "get 0x01").unwrap() == "0xc0ffee");
+        assert!(execute(&mut session, "get 02").unwrap() == "0xdecaf0");
+        assert!(execute(&mut session, "get 0x03").is_err());
+        assert!(execute(&mut session, "get 0x").is_err());
+        let root_node: Json =
+            serde_json::from_str(&execute(&mut session, &format!("show {}", root)).unwrap())
+                .unwrap();
+        assert!(root_node["type"] == "extension");
+        let proof = convert_hex_to_bytes(execute(&mut session, "proof 0x02").unwrap()).unwrap();
+        let proof_nodes: Vec<Bytes> = Decoder::new(&proof)
+            .iter()
+            .map(|node| node.get_raw().to_vec())
+            .collect();
+        let root_hash = convert_hex_to_h256(root.clone()).unwrap();
+        let result = verify_merkle_proof(root_hash.as_fixed_bytes(), &[0, 2], &proof_nodes);
+        assert!(result.unwrap() == vec![0xde, 0xca, 0xf0]);
+        assert!(execute(&mut session, "dot")
+            .unwrap()
+            .contains("fillcolor=khaki"));
+        assert!(execute(&mut session, "reset").unwrap() == empty_root);
+        assert!(execute(&mut session, "get 0x01").is_err());
+        assert!(execute(&mut session, "put 0xzz 0x01").is_err());
+        assert!(session.execute("exit").is_none());
+    }
+}
@@ -0,0 +1,269 @@
+use crate::explain_root_mismatch::get_raw_receipts;
+use crate::telemetry::Telemetry;
+use crate::trie::Trie;
+use crate::trie_checkpoint::write_atomically;
+use crate::trie_keys::{ReceiptKey, TrieKey};
+use crate::types::{Block, Bytes, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex};
+use std::fs;
+use std::sync::Mutex;
+
+static LAST_TRIE_ROOT_AUDIT: Mutex<Option<TrieRootAudit>> = Mutex::new(None);
+
+/**
+ * Trie Root Audit:
+ *
+ * A computed receipts root differing from the header's says nothing of which
+ * receipt was encoded wrongly, & a block may hold hundreds. So w/
+ * `--audit-trie-roots`, receipts tries are built one receipt at a time, w/ the
+ * root after each insertion recorded alongside the receipt's encoding, & the
+ * sequence dumped to the given JSON file. Since the root after inserting
+ * receipts `0..=i` only depends on those receipts, the node's raw receipts'
+ * sequence agrees w/ ours up to the first wrongly encoded receipt & differs
+ * from there on, so on a `root` mismatch the two are bisected to find it.
+ * Tries served from the `--cache-dir` aren't rebuilt, & so aren't audited.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieRootAuditEntry {
+    pub index: usize,
+    pub receipt: String,
+    pub root: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieRootAudit {
+    pub block_number: u64,
+    pub block_hash: String,
+    pub header_receipts_root: String,
+    pub entries: Vec<TrieRootAuditEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_divergent_index: Option<usize>,
+}
+
+fn set_last_trie_root_audit(audit: &TrieRootAudit) {
+    if let Ok(mut last_audit) = LAST_TRIE_ROOT_AUDIT.lock() {
+        *last_audit = Some(audit.clone());
+    }
+}
+
+fn get_last_trie_root_audit_of_block(block: &Block) -> Option<TrieRootAudit> {
+    let block_hash = convert_h256_to_prefixed_hex(block.hash).ok()?;
+    LAST_TRIE_ROOT_AUDIT
+        .lock()
+        .ok()?
+        .clone()
+        .filter(|audit| audit.block_hash == block_hash)
+}
+
+pub fn put_in_trie_w_roots<K: TrieKey>(
+    key_value_tuples: &[(K, Bytes)],
+) -> Result<(Trie, Vec<String>)> {
+    key_value_tuples.iter().try_fold(
+        (
+            Trie::get_new_trie()?,
+            Vec::with_capacity(key_value_tuples.len()),
+        ),
+        |(mut trie, mut roots), (key, value)| {
+            trie.put_mut(key.to_nibbles(), value.clone())?;
+            roots.push(convert_h256_to_prefixed_hex(trie.root)?);
+            Ok((trie, roots))
+        },
+    )
+}
+
+fn write_trie_root_audit(path: &str, audit: &TrieRootAudit) -> Result<()> {
+    write_atomically(path, |temp_path| {
+        Ok(fs::write(temp_path, serde_json::to_string(audit)?)?)
+    })
+}
+
+pub fn put_in_trie_w_root_audit<K: TrieKey>(
+    block: &Block,
+    key_value_tuples: Vec<(K, Bytes)>,
+    path: &str,
+) -> Result<Trie> {
+    let (trie, roots) = put_in_trie_w_roots(&key_value_tuples)?;
+    let audit = TrieRootAudit {
+        block_number: block.number.as_u64(),
+        block_hash: convert_h256_to_prefixed_hex(block.hash)?,
+        header_receipts_root: convert_h256_to_prefixed_hex(block.receipts_root)?,
+        entries: key_value_tuples
+            .iter()
+            .zip(roots)
+            .enumerate()
+            .map(|(index, ((_, value), root))| TrieRootAuditEntry {
+                index,
+                receipt: convert_bytes_to_prefixed_hex(value),
+                root,
+            })
+            .collect(),
+        first_divergent_index: None,
+    };
+    write_trie_root_audit(path, &audit)?;
+    info!(
+        "✔ Dumped roots after each of {} receipts inserted to: {}",
+        audit.entries.len(),
+        path
+    );
+    set_last_trie_root_audit(&audit);
+    Ok(trie)
+}
+
+// NOTE: Bisects, since once the sequences diverge they never agree again.
+pub fn find_first_divergent_index<T: PartialEq>(roots: &[T], other_roots: &[T]) -> Option<usize> {
+    let (mut low, mut high) = (0, roots.len().min(other_roots.len()));
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match roots[mid] == other_roots[mid] {
+            true => low = mid + 1,
+            false => high = mid,
+        }
+    }
+    match low == roots.len() && low == other_roots.len() {
+        true => None,
+        false => Some(low),
+    }
+}
+
+pub fn bisect_root_mismatch(
+    audit: &TrieRootAudit,
+    raw_receipts: &[Bytes],
+) -> Result<Option<usize>> {
+    let key_value_tuples = raw_receipts
+        .iter()
+        .enumerate()
+        .map(|(index, raw_receipt)| Ok((ReceiptKey::from_index(index)?, raw_receipt.clone())))
+        .collect::<Result<Vec<(ReceiptKey, Bytes)>>>()?;
+    let (_, raw_roots) = put_in_trie_w_roots(&key_value_tuples)?;
+    let roots: Vec<String> = audit
+        .entries
+        .iter()
+        .map(|entry| entry.root.clone())
+        .collect();
+    Ok(find_first_divergent_index(&roots, &raw_roots))
+}
+
+pub fn format_first_divergent_receipt(
+    audit: &TrieRootAudit,
+    raw_receipts: &[Bytes],
+    maybe_index: Option<usize>,
+) -> String {
+    match maybe_index {
+        None => "✘ Every receipt's encoding matches the node's raw receipts, so the node's receipts don't match its own header!".to_string(),
+        Some(index) => format!(
+            "✘ Receipt #{} is the first whose encoding diverges from the node's raw receipts!\n✘ Encoded: {}\n✘ Node's:  {}",
+            index,
+            audit
+                .entries
+                .get(index)
+                .map(|entry| entry.receipt.clone())
+                .unwrap_or_else(|| "nothing".to_string()),
+            raw_receipts
+                .get(index)
+                .map(|raw_receipt| convert_bytes_to_prefixed_hex(raw_receipt))
+                .unwrap_or_else(|| "nothing".to_string()),
+        ),
+    }
+}
+
+pub fn maybe_bisect_root_mismatch(
+    endpoint: &str,
+    block: &Block,
+    maybe_audit_path: &Option<String>,
+    telemetry: &Telemetry,
+) -> Result<Option<String>> {
+    let (path, mut audit) = match (maybe_audit_path, get_last_trie_root_audit_of_block(block)) {
+        (Some(path), Some(audit)) => (path, audit),
+        _ => return Ok(None),
+    };
+    let raw_receipts = match get_raw_receipts(endpoint, block, telemetry) {
+        Some(raw_receipts) => raw_receipts,
+        None => {
+            return Ok(Some(format!(
+                "✘ Node did not serve raw receipts to bisect against. Roots after each insertion are in: {}",
+                path
+            )))
+        }
+    };
+    info!("✔ Bisecting receipts trie roots against the node's raw receipts...");
+    audit.first_divergent_index = bisect_root_mismatch(&audit, &raw_receipts)?;
+    write_trie_root_audit(path, &audit)?;
+    Ok(Some(format_first_divergent_receipt(
+        &audit,
+        &raw_receipts,
+        audit.first_divergent_index,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlp_codec::get_rlp_encoded_receipts_and_key_tuples;
+    use crate::test_utils::{
+        get_expected_block, get_sample_receipts, get_sample_tx_hashes_1, RECEIPTS_ROOT_1,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+
+    fn get_sample_key_value_tuples() -> Vec<(ReceiptKey, Bytes)> {
+        let mut receipts = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        receipts.sort_by_key(|receipt| receipt.transaction_index);
+        get_rlp_encoded_receipts_and_key_tuples(&receipts).unwrap()
+    }
+
+    #[test]
+    fn should_find_first_divergent_index() {
+        assert!(find_first_divergent_index(&[1, 2, 3, 4], &[1, 2, 3, 4]).is_none());
+        assert!(find_first_divergent_index(&[1, 2, 3, 4], &[1, 2, 7, 8]) == Some(2));
+        assert!(find_first_divergent_index(&[1, 2, 3, 4], &[5, 6, 7, 8]) == Some(0));
+        assert!(find_first_divergent_index(&[1, 2, 3, 4], &[1, 2]) == Some(2));
+        assert!(find_first_divergent_index::<u8>(&[], &[]).is_none());
+    }
+
+    #[test]
+    fn should_record_root_after_each_insertion() {
+        let key_value_tuples = get_sample_key_value_tuples();
+        let (trie, roots) = put_in_trie_w_roots(&key_value_tuples).unwrap();
+        assert!(roots.len() == key_value_tuples.len());
+        assert!(roots.last().unwrap() == RECEIPTS_ROOT_1);
+        assert!(convert_h256_to_prefixed_hex(trie.root).unwrap() == RECEIPTS_ROOT_1);
+        let (_, first_root) = put_in_trie_w_roots(&key_value_tuples[..1]).unwrap();
+        assert!(first_root[0] == roots[0]);
+    }
+
+    #[test]
+    fn should_dump_trie_root_audit_and_bisect_to_divergent_receipt() {
+        let path = std::env::temp_dir().join("rrpm-trie-root-audit.json");
+        let path = path.to_str().unwrap();
+        let block = get_expected_block();
+        let key_value_tuples = get_sample_key_value_tuples();
+        let mut raw_receipts: Vec<Bytes> = key_value_tuples
+            .iter()
+            .map(|(_, encoded)| encoded.clone())
+            .collect();
+        put_in_trie_w_root_audit(&block, key_value_tuples, path).unwrap();
+        let audit: TrieRootAudit =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+        assert!(get_last_trie_root_audit_of_block(&block) == Some(audit.clone()));
+        // NOTE: W/o an audit path there's nothing to bisect, so the node's never asked.
+        assert!(
+            maybe_bisect_root_mismatch("", &block, &None, &Telemetry::default())
+                .unwrap()
+                .is_none()
+        );
+        assert!(audit.entries.last().unwrap().root == RECEIPTS_ROOT_1);
+        assert!(bisect_root_mismatch(&audit, &raw_receipts)
+            .unwrap()
+            .is_none());
+        raw_receipts[5].insert(0, 0x02);
+        let result = bisect_root_mismatch(&audit, &raw_receipts).unwrap();
+        assert!(result == Some(5));
+        assert!(
+            format_first_divergent_receipt(&audit, &raw_receipts, result)
+                .starts_with("✘ Receipt #5 is the first")
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::errors::AppError;
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::get_endpoint::get_endpoint;
+use crate::get_hex_proof_from_branch::rlp_encode_node_stack;
+use crate::get_receipts_trie::get_block_and_receipts_trie;
+use crate::manifest::{
+    check_receipts_root_against_manifest, get_manifest_path, verify_manifest, write_manifest,
+};
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::telemetry::{Telemetry, TelemetryEvent};
+use crate::trie::Trie;
+use crate::types::{Block, HexProof, Result};
+use crate::utils::{convert_bytes_to_hex, convert_h256_to_prefixed_hex};
+use std::fs;
+use std::path::Path;
+
+pub fn export_receipts_trie(cli_args: &CliArgs, block_id: &str, path: &str) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    get_block_and_receipts_trie(&endpoint, block_id, cli_args).and_then(|(block, trie)| {
+        trie.export(path)?;
+        write_manifest(&get_manifest_path(path), &[path], trie.root)?;
+        Ok(format!(
+            "✔ Receipts trie for block {} w/ root {} exported to: {}",
+            block.number,
+            convert_h256_to_prefixed_hex(trie.root)?,
+            path
+        ))
+    })
+}
+
+pub fn dump_receipts_trie_as_dot(cli_args: &CliArgs, block_id: &str, path: &str) -> Result<String> {
+    let endpoint = get_endpoint(cli_args)?;
+    get_block_and_receipts_trie(&endpoint, block_id, cli_args).and_then(|(block, trie)| {
+        fs::write(path, trie.to_dot()?)?;
+        Ok(format!(
+            "✔ Receipts trie for block {} w/ root {} dumped as DOT to: {}",
+            block.number,
+            convert_h256_to_prefixed_hex(trie.root)?,
+            path
+        ))
+    })
+}
+
+pub fn import_trie_snapshot(path: &str) -> Result<Trie> {
+    let manifest_path = get_manifest_path(path);
+    match Path::new(&manifest_path).exists() {
+        true => verify_manifest(&manifest_path).and_then(|manifest| {
+            Trie::import(path).and_then(|trie| {
+                check_receipts_root_against_manifest(&manifest, trie.root).map(|_| trie)
+            })
+        }),
+        false => {
+            warn!("✘ No checksum manifest found at {}!", manifest_path);
+            Trie::import(path)
+        }
+    }
+}
+
+pub fn get_hex_proof_from_trie_snapshot(path: &str, index: usize) -> Result<HexProof> {
+    import_trie_snapshot(path)
+        .and_then(|trie| get_branch_from_trie(&trie, index))
+        .and_then(|branch| rlp_encode_node_stack(&branch))
+        .map(convert_bytes_to_hex)
+}
+
+pub fn prove_from_trie_snapshot(
+    path: &str,
+    index: usize,
+    format: OutputFormat,
+    telemetry: &Telemetry,
+) -> Result<String> {
+    match format {
+        OutputFormat::Hex => get_hex_proof_from_trie_snapshot(path, index),
+        OutputFormat::Json | OutputFormat::Binary | OutputFormat::Eip1186 | OutputFormat::Ssz => {
+            Err(AppError::Custom(
+                "✘ Proofs from trie snapshots are only available in hex format!".to_string(),
+            ))
+        }
+    }
+    .inspect(|_| {
+        telemetry.emit_telemetry_event(TelemetryEvent::ProofGenerated {
+            proof_type: "receipt",
+            num_proofs: 1,
+        })
+    })
+}
+
+// NOTE: The path of a file in the trie cache dir named for a block, if there's a cache dir.
+pub fn get_trie_cache_path(
+    maybe_cache_dir: &Option<String>,
+    block: &Block,
+    extension: &str,
+) -> Option<String> {
+    let trie_cache_dir = maybe_cache_dir.as_ref()?;
+    let block_hash = convert_h256_to_prefixed_hex(block.hash).ok()?;
+    Some(
+        Path::new(&trie_cache_dir)
+            .join(format!("{}.{}", block_hash, extension))
+            .to_string_lossy()
+            .to_string(),
+    )
+}
+
+fn get_cached_trie_path(maybe_cache_dir: &Option<String>, block: &Block) -> Option<String> {
+    get_trie_cache_path(maybe_cache_dir, block, "trie")
+}
+
+// NOTE: Cached tries are only trusted if their root is the block's, e.g. not if built w/ another `--chain`.
+pub fn maybe_get_cached_receipts_trie(
+    maybe_cache_dir: &Option<String>,
+    block: &Block,
+) -> Option<Trie> {
+    let path = get_cached_trie_path(maybe_cache_dir, block)?;
+    if !Path::new(&path).exists() {
+        return None;
+    }
+    match Trie::import(&path) {
+        Ok(trie) if trie.root == block.receipts_root => {
+            info!("✔ Using cached receipts trie for block {}", block.number);
+            Some(trie)
+        }
+        Ok(_) => {
+            warn!("✘ Cached receipts trie at {} has the wrong root!", path);
+            None
+        }
+        Err(e) => {
+            warn!("✘ Could not read cached receipts trie at {}: {}", path, e);
+            None
+        }
+    }
+}
+
+pub fn remove_cached_receipts_trie(maybe_cache_dir: &Option<String>, block: &Block) {
+    let path = match get_cached_trie_path(maybe_cache_dir, block) {
+        Some(path) if Path::new(&path).exists() => path,
+        _ => return,
+    };
+    match fs::remove_file(&path) {
+        Ok(_) => info!(
+            "✔ Removed cached receipts trie of reorged block at {}",
+            path
+        ),
+        Err(e) => warn!("✘ Could not remove cached receipts trie at {}: {}", path, e),
+    }
+}
+
+// NOTE: Caching is best effort, so failing to write the cache never fails the command.
+pub fn maybe_cache_receipts_trie(maybe_cache_dir: &Option<String>, block: &Block, trie: &Trie) {
+    let path = match get_cached_trie_path(maybe_cache_dir, block) {
+        Some(path) if trie.root == block.receipts_root => path,
+        _ => return,
+    };
+    let result = Path::new(&path)
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(AppError::from)
+        .and_then(|_| trie.export(&path));
+    if let Err(e) = result {
+        warn!("✘ Could not cache receipts trie at {}: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        get_expected_block, get_sample_proof_1, get_sample_trie_with_sample_receipts,
+        get_sample_tx_hashes_1, PROOF_1_INDEX, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use ethereum_types::H256;
+
+    fn export_sample_trie_snapshot(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-trie-snapshot-{}", name));
+        let path_string = path.to_str().unwrap().to_string();
+        get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .export(&path_string)
+        .unwrap();
+        path_string
+    }
+
+    #[test]
+    fn should_get_same_hex_proof_from_trie_snapshot_as_from_trie() {
+        let path = export_sample_trie_snapshot("prove");
+        let result = prove_from_trie_snapshot(
+            &path,
+            PROOF_1_INDEX,
+            OutputFormat::Hex,
+            &Telemetry::default(),
+        )
+        .unwrap();
+        assert!(result == get_sample_proof_1());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_import_trie_snapshot_matching_its_manifest() {
+        let path = export_sample_trie_snapshot("manifest");
+        let manifest_path = get_manifest_path(&path);
+        let trie = Trie::import(&path).unwrap();
+        write_manifest(&manifest_path, &[&path], trie.root).unwrap();
+        assert!(import_trie_snapshot(&path).unwrap().root == trie.root);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&manifest_path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_import_trie_snapshot_not_matching_its_manifest() {
+        let path = export_sample_trie_snapshot("bad-manifest");
+        let manifest_path = get_manifest_path(&path);
+        write_manifest(&manifest_path, &[&path], H256::zero()).unwrap();
+        match import_trie_snapshot(&path) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not match manifest")),
+            _ => panic!("Should not import trie snapshot not matching its manifest!"),
+        }
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&manifest_path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_prove_from_trie_snapshot_as_json() {
+        let path = export_sample_trie_snapshot("json");
+        assert!(prove_from_trie_snapshot(
+            &path,
+            PROOF_1_INDEX,
+            OutputFormat::Json,
+            &Telemetry::default()
+        )
+        .is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_cache_receipts_trie_only_if_its_root_is_the_blocks() {
+        let dir = std::env::temp_dir().join("rrpm-trie-cache");
+        let trie = get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        );
+        let block = Block {
+            receipts_root: trie.root,
+            ..get_expected_block()
+        };
+        let wrong_root_block = Block {
+            receipts_root: H256::zero(),
+            hash: H256::from_low_u64_be(1),
+            ..get_expected_block()
+        };
+        let cache_dir = Some(dir.to_str().unwrap().to_string());
+        assert!(maybe_get_cached_receipts_trie(&cache_dir, &block).is_none());
+        assert!(maybe_get_cached_receipts_trie(&None, &block).is_none());
+        maybe_cache_receipts_trie(&cache_dir, &block, &trie);
+        maybe_cache_receipts_trie(&cache_dir, &wrong_root_block, &trie);
+        let result = maybe_get_cached_receipts_trie(&cache_dir, &block);
+        let wrong_root_result = maybe_get_cached_receipts_trie(&cache_dir, &wrong_root_block);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.unwrap().root == trie.root);
+        assert!(wrong_root_result.is_none());
+    }
+}
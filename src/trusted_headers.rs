@@ -0,0 +1,349 @@
+use crate::block_header::{
+    get_verified_parent_hash_and_number_from_rlp_encoded_block_header,
+    get_verified_rlp_encoded_block_header, rlp_encode_block_header,
+};
+use crate::errors::AppError;
+use crate::get_block::get_block_by_blockhash;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::state::State;
+use crate::types::{Block, Bytes, Result};
+use crate::utils::{convert_h256_to_prefixed_hex, convert_hex_to_h256, decode_prefixed_hex};
+use ethereum_types::H256;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+pub const MAX_TRUSTED_HEADER_WALK: u64 = 8_192;
+
+/**
+ * Trusted Headers:
+ *
+ * A proof is only as good as the block hash anchoring it, & taking that hash
+ * from the same endpoint serving the receipts means trusting that endpoint
+ * twice. W/ `--trusted-headers`, the header of every block proven must instead
+ * be an ancestor of a checkpoint in the given file: a block number & hash the
+ * user trusts, e.g. from a light client. From the nearest checkpoint at or
+ * above the block, the chain is walked back one parent hash at a time to the
+ * block's height, & the hash found there must be the block's. Headers along
+ * the way are read from the file's `headers` if there, else fetched from the
+ * endpoint, but each must hash to the hash its child committed to, so the
+ * endpoint can't forge any of them. Walks are capped at a few thousand blocks.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct TrustedCheckpoint {
+    pub number: u64,
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TrustedHeadersFile {
+    checkpoints: Vec<TrustedCheckpoint>,
+    #[serde(default)]
+    headers: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustedHeaders {
+    checkpoints: BTreeMap<u64, H256>,
+    headers: HashMap<H256, Bytes>,
+}
+
+impl TrustedHeaders {
+    pub fn from_checkpoints_and_headers(
+        checkpoints: &[TrustedCheckpoint],
+        rlp_encoded_headers: Vec<Bytes>,
+    ) -> Result<Self> {
+        if checkpoints.is_empty() {
+            return Err(AppError::Custom(
+                "✘ Trusted headers need at least one checkpoint!".to_string(),
+            ));
+        }
+        let mut trusted_headers = Self::default();
+        checkpoints
+            .iter()
+            .try_for_each(|checkpoint| -> Result<()> {
+                let hash = convert_hex_to_h256(checkpoint.hash.clone())?;
+                match trusted_headers.checkpoints.insert(checkpoint.number, hash) {
+                    Some(other_hash) if other_hash != hash => Err(AppError::Custom(format!(
+                        "✘ Trusted headers have conflicting checkpoints at block {}!",
+                        checkpoint.number
+                    ))),
+                    _ => Ok(()),
+                }
+            })?;
+        // NOTE: Keyed by their hash, so a header is only ever used where its child committed to it.
+        trusted_headers.headers = rlp_encoded_headers
+            .into_iter()
+            .map(|header| Ok((keccak_hash_bytes(&header)?, header)))
+            .collect::<Result<HashMap<H256, Bytes>>>()?;
+        Ok(trusted_headers)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: TrustedHeadersFile = serde_json::from_str(json)?;
+        Self::from_checkpoints_and_headers(
+            &file.checkpoints,
+            file.headers
+                .into_iter()
+                .map(decode_prefixed_hex)
+                .collect::<Result<Vec<Bytes>>>()?,
+        )
+    }
+}
+
+pub fn maybe_read_trusted_headers_file(
+    maybe_path: &Option<String>,
+) -> Result<Option<TrustedHeaders>> {
+    match maybe_path {
+        None => Ok(None),
+        Some(path) => {
+            info!("✔ Reading trusted headers from: {}", path);
+            fs::read_to_string(path)
+                .map_err(AppError::from)
+                .and_then(|json| TrustedHeaders::from_json(&json))
+                .map(Some)
+                .map_err(|e| {
+                    AppError::Custom(format!(
+                        "✘ Error reading trusted headers file {}!\n{}",
+                        path, e
+                    ))
+                })
+        }
+    }
+}
+
+pub fn check_block_against_trusted_headers(
+    trusted_headers: &TrustedHeaders,
+    block: &Block,
+    fetch_header: impl Fn(H256) -> Result<Bytes>,
+) -> Result<()> {
+    get_verified_rlp_encoded_block_header(block)?;
+    let block_number = block.number.as_u64();
+    let (checkpoint_number, checkpoint_hash) = trusted_headers
+        .checkpoints
+        .range(block_number..)
+        .next()
+        .map(|(number, hash)| (*number, *hash))
+        .ok_or_else(|| {
+            AppError::Custom(format!(
+                "✘ No trusted checkpoint at or above block {}!",
+                block_number
+            ))
+        })?;
+    if checkpoint_number - block_number > MAX_TRUSTED_HEADER_WALK {
+        return Err(AppError::Custom(format!(
+            "✘ Nearest trusted checkpoint, at block {}, is over {} blocks above block {}!",
+            checkpoint_number, MAX_TRUSTED_HEADER_WALK, block_number
+        )));
+    }
+    info!(
+        "✔ Checking block {} against trusted checkpoint at block {}...",
+        block_number, checkpoint_number
+    );
+    let ancestor_hash = (block_number..checkpoint_number).rev().try_fold(
+        checkpoint_hash,
+        |hash, parent_number| -> Result<H256> {
+            let header = match trusted_headers.headers.get(&hash) {
+                Some(header) => header.clone(),
+                None => fetch_header(hash)?,
+            };
+            let (parent_hash, number) =
+                get_verified_parent_hash_and_number_from_rlp_encoded_block_header(&header, &hash)?;
+            match number == (parent_number + 1).into() {
+                true => Ok(parent_hash),
+                false => Err(AppError::Custom(format!(
+                    "✘ Header w/ hash {} is of block {}, not {}!",
+                    convert_h256_to_prefixed_hex(hash)?,
+                    number,
+                    parent_number + 1
+                ))),
+            }
+        },
+    )?;
+    match ancestor_hash == block.hash {
+        true => {
+            info!(
+                "✔ Block {} is an ancestor of trusted checkpoint at block {}!",
+                block_number, checkpoint_number
+            );
+            Ok(())
+        }
+        false => Err(AppError::Custom(format!(
+            "✘ Block {} is not an ancestor of trusted checkpoint at block {}!\n✘ Block hash:   {}\n✘ Trusted hash: {}",
+            block_number,
+            checkpoint_number,
+            convert_h256_to_prefixed_hex(block.hash)?,
+            convert_h256_to_prefixed_hex(ancestor_hash)?
+        ))),
+    }
+}
+
+fn fetch_rlp_encoded_block_header(endpoint: &str, block_hash: H256) -> Result<Bytes> {
+    get_block_by_blockhash(endpoint, block_hash).and_then(|block| rlp_encode_block_header(&block))
+}
+
+pub fn maybe_check_block_against_trusted_headers(
+    endpoint: &str,
+    block: &Block,
+    maybe_trusted_headers: &Option<TrustedHeaders>,
+) -> Result<()> {
+    match maybe_trusted_headers {
+        Some(trusted_headers) => {
+            check_block_against_trusted_headers(trusted_headers, block, |hash| {
+                fetch_rlp_encoded_block_header(endpoint, hash)
+            })
+        }
+        None => Ok(()),
+    }
+}
+
+pub fn maybe_check_block_in_state_against_trusted_headers(state: State) -> Result<State> {
+    maybe_check_block_against_trusted_headers(
+        state.get_endpoint_from_state()?,
+        state.get_block_from_state()?,
+        &state.trusted_headers,
+    )?;
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_expected_block;
+    use crate::utils::convert_bytes_to_prefixed_hex;
+
+    // NOTE: The sample block followed by the given number of descendants, each committing to the last.
+    fn get_sample_chain(num_descendants: usize) -> Vec<Block> {
+        (0..num_descendants).fold(vec![get_expected_block()], |mut chain, _| {
+            let parent = chain.last().unwrap();
+            let mut child = parent.clone();
+            child.parent_hash = parent.hash;
+            child.number = parent.number + 1;
+            child.hash = keccak_hash_bytes(&rlp_encode_block_header(&child).unwrap()).unwrap();
+            chain.push(child);
+            chain
+        })
+    }
+
+    fn get_checkpoint(block: &Block) -> TrustedCheckpoint {
+        TrustedCheckpoint {
+            number: block.number.as_u64(),
+            hash: convert_h256_to_prefixed_hex(block.hash).unwrap(),
+        }
+    }
+
+    fn get_unfetchable_header(_: H256) -> Result<Bytes> {
+        Err(AppError::Custom("✘ Header unavailable!".to_string()))
+    }
+
+    #[test]
+    fn should_check_block_is_ancestor_of_trusted_checkpoint_via_file_headers() {
+        let chain = get_sample_chain(3);
+        let headers = chain[1..]
+            .iter()
+            .map(|block| rlp_encode_block_header(block).unwrap())
+            .collect();
+        let trusted_headers =
+            TrustedHeaders::from_checkpoints_and_headers(&[get_checkpoint(&chain[3])], headers)
+                .unwrap();
+        let result = check_block_against_trusted_headers(
+            &trusted_headers,
+            &chain[0],
+            get_unfetchable_header,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_check_block_is_ancestor_of_trusted_checkpoint_via_fetched_headers() {
+        let chain = get_sample_chain(2);
+        let trusted_headers =
+            TrustedHeaders::from_checkpoints_and_headers(&[get_checkpoint(&chain[2])], vec![])
+                .unwrap();
+        let result = check_block_against_trusted_headers(&trusted_headers, &chain[0], |hash| {
+            let block = chain.iter().find(|block| block.hash == hash).unwrap();
+            rlp_encode_block_header(block)
+        });
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_reject_block_not_an_ancestor_of_trusted_checkpoint() {
+        let chain = get_sample_chain(2);
+        let mut forked_parent = chain[1].clone();
+        forked_parent.parent_hash = H256::repeat_byte(0xff);
+        let forked_parent_header = rlp_encode_block_header(&forked_parent).unwrap();
+        let mut forked_child = chain[2].clone();
+        forked_child.parent_hash = keccak_hash_bytes(&forked_parent_header).unwrap();
+        let forked_child_header = rlp_encode_block_header(&forked_child).unwrap();
+        forked_child.hash = keccak_hash_bytes(&forked_child_header).unwrap();
+        let trusted_headers = TrustedHeaders::from_checkpoints_and_headers(
+            &[get_checkpoint(&forked_child)],
+            vec![forked_parent_header, forked_child_header],
+        )
+        .unwrap();
+        match check_block_against_trusted_headers(
+            &trusted_headers,
+            &chain[0],
+            get_unfetchable_header,
+        ) {
+            Err(AppError::Custom(e)) => assert!(e.contains("is not an ancestor")),
+            _ => panic!("Should reject block not an ancestor of trusted checkpoint!"),
+        }
+    }
+
+    #[test]
+    fn should_reject_forged_header_fetched_from_endpoint() {
+        let chain = get_sample_chain(2);
+        let trusted_headers =
+            TrustedHeaders::from_checkpoints_and_headers(&[get_checkpoint(&chain[2])], vec![])
+                .unwrap();
+        let result = check_block_against_trusted_headers(&trusted_headers, &chain[0], |_| {
+            rlp_encode_block_header(&chain[0])
+        });
+        match result {
+            Err(AppError::Custom(e)) => assert!(e.contains("not to block hash")),
+            _ => panic!("Should reject forged header fetched from endpoint!"),
+        }
+    }
+
+    #[test]
+    fn should_reject_block_above_all_trusted_checkpoints() {
+        let chain = get_sample_chain(1);
+        let trusted_headers =
+            TrustedHeaders::from_checkpoints_and_headers(&[get_checkpoint(&chain[0])], vec![])
+                .unwrap();
+        assert!(check_block_against_trusted_headers(
+            &trusted_headers,
+            &chain[0],
+            get_unfetchable_header
+        )
+        .is_ok());
+        assert!(check_block_against_trusted_headers(
+            &trusted_headers,
+            &chain[1],
+            get_unfetchable_header
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn should_parse_trusted_headers_json() {
+        let chain = get_sample_chain(1);
+        let checkpoint_json = |block: &Block| {
+            json!({
+                "number": block.number.as_u64(),
+                "hash": convert_h256_to_prefixed_hex(block.hash).unwrap(),
+            })
+        };
+        let json = json!({
+            "checkpoints": [checkpoint_json(&chain[0]), checkpoint_json(&chain[1])],
+            "headers": [convert_bytes_to_prefixed_hex(&rlp_encode_block_header(&chain[1]).unwrap())],
+        });
+        let result = TrustedHeaders::from_json(&json.to_string()).unwrap();
+        assert!(result.checkpoints.len() == 2);
+        assert!(result.headers.contains_key(&chain[1].hash));
+        assert!(TrustedHeaders::from_json("{\"checkpoints\": []}").is_err());
+        assert!(TrustedHeaders::from_json("{\"checkpoints\": [], \"chekpoints\": []}").is_err());
+    }
+}
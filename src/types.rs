@@ -1,19 +1,19 @@
 use crate::errors::AppError;
-use crate::trie_nodes::Node;
 use ethereum_types::{Address, Bloom, H256, U256};
 use rlp::{Encodable, RlpStream};
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::result;
 
 pub type Byte = u8;
 pub type Bytes = Vec<Byte>;
 pub type HexProof = String;
-pub type NodeStack = Vec<Node>;
 pub type Database = HashMap<H256, Bytes>;
+pub type RefCounts = HashMap<H256, usize>;
 pub type ChildNodes = [Option<Bytes>; 16];
 pub type Result<T> = result::Result<T, AppError>;
 pub(crate) use crate::errors::AppError::NoneError;
+pub use crate::node_stack::NodeStack;
 
 #[derive(Debug, Deserialize)]
 pub struct BlockRpcResponse {
@@ -25,9 +25,20 @@ pub struct ReceiptRpcResponse {
     pub result: ReceiptJson,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BatchedReceiptRpcResponse {
+    pub id: usize,
+    pub result: ReceiptJson,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountProofRpcResponse {
+    pub result: AccountProofJson,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Block {
-    pub author: Address,
+    pub author: Option<Address>,
     pub difficulty: U256,
     pub extra_data: Bytes,
     pub gas_limit: U256,
@@ -40,20 +51,27 @@ pub struct Block {
     pub number: U256,
     pub parent_hash: H256,
     pub receipts_root: H256,
-    pub seal_fields: (Bytes, U256),
+    pub seal_fields: Option<(Bytes, U256)>,
     pub sha3_uncles: H256,
     pub size: U256,
     pub state_root: H256,
     pub timestamp: U256,
-    pub total_difficulty: U256,
+    pub total_difficulty: Option<U256>,
     pub transactions: Vec<H256>,
     pub transactions_root: H256,
     pub uncles: Vec<H256>,
+    pub base_fee_per_gas: Option<U256>,
+    pub withdrawals_root: Option<H256>,
+    pub blob_gas_used: Option<U256>,
+    pub excess_blob_gas: Option<U256>,
+    pub parent_beacon_block_root: Option<H256>,
+    pub requests_hash: Option<H256>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Receipt {
-    pub to: Address,
+    // NOTE: A contract creation has no recipient, & only it has a contract address.
+    pub to: Option<Address>,
     pub from: Address,
     pub status: bool,
     pub gas_used: U256,
@@ -62,26 +80,65 @@ pub struct Receipt {
     pub cumulative_gas_used: U256,
     pub block_number: U256,
     pub transaction_index: U256,
-    pub contract_address: Address,
+    pub contract_address: Option<Address>,
     pub logs: Vec<Log>,
-    //pub root: H256,
+    pub root: Option<H256>,
     pub logs_bloom: Bloom,
+    pub tx_type: Byte,
+    pub deposit_nonce: Option<U256>,
+    pub deposit_receipt_version: Option<U256>,
+    #[serde(default)]
+    pub rpc_fields: ReceiptRpcFields,
+}
+
+/**
+ * Receipt RPC Fields:
+ *
+ * Nodes' receipts carry fields beyond those RLP encoded into the receipts
+ * trie, e.g. the effective gas price of EIP-1559 txs, or the blob gas of
+ * EIP-4844 ones. None of them are needed to prove a receipt, so they're kept
+ * apart from its consensus fields, never touching its encoding, but still
+ * preserved, so a receipt re-serialized from ours loses nothing a node gave
+ * us. Fields we don't model, e.g. L2s' fee fields, or those of future forks,
+ * are kept verbatim in `other` when parsing leniently, the default, whilst
+ * `--strict-receipts` refuses receipts w/ any such field.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ReceiptRpcFields {
+    // NOTE: Absent from pre-London receipts.
+    pub effective_gas_price: Option<U256>,
+    pub blob_gas_used: Option<U256>,
+    pub blob_gas_price: Option<U256>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub other: BTreeMap<String, serde_json::Value>,
 }
 
 impl Encodable for Receipt {
     fn rlp_append(&self, rlp_stream: &mut RlpStream) {
-        let rlp = rlp_stream.begin_list(4);
-        match &self.status {
-            true => rlp.append(&self.status),
-            false => rlp.append_empty_data(),
+        let num_deposit_fields = match (&self.deposit_nonce, &self.deposit_receipt_version) {
+            (None, _) => 0,
+            (Some(_), None) => 1,
+            (Some(_), Some(_)) => 2,
+        };
+        let rlp = rlp_stream.begin_list(4 + num_deposit_fields);
+        match (&self.root, &self.status) {
+            (Some(root), _) => rlp.append(root),
+            (None, true) => rlp.append(&self.status),
+            (None, false) => rlp.append_empty_data(),
         };
         rlp.append(&self.cumulative_gas_used)
             .append(&self.logs_bloom)
             .append_list(&self.logs);
+        if let Some(deposit_nonce) = &self.deposit_nonce {
+            rlp.append(deposit_nonce);
+            if let Some(deposit_receipt_version) = &self.deposit_receipt_version {
+                rlp.append(deposit_receipt_version);
+            }
+        }
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Log {
     pub address: Address,
     pub topics: Vec<H256>,
@@ -101,7 +158,7 @@ impl Encodable for Log {
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize)]
 pub struct BlockJson {
-    pub author: String,
+    pub author: Option<String>,
     pub difficulty: String,
     pub extraData: String,
     pub gasLimit: String,
@@ -114,22 +171,35 @@ pub struct BlockJson {
     pub number: String,
     pub parentHash: String,
     pub receiptsRoot: String,
-    pub sealFields: (String, String),
+    pub sealFields: Option<(String, String)>,
     pub sha3Uncles: String,
     pub size: String,
     pub stateRoot: String,
     pub timestamp: String,
-    pub totalDifficulty: String,
+    pub totalDifficulty: Option<String>,
     pub transactions: Vec<String>,
     pub transactionsRoot: String,
     pub uncles: Vec<String>,
+    pub baseFeePerGas: Option<String>,
+    pub withdrawalsRoot: Option<String>,
+    pub blobGasUsed: Option<String>,
+    pub excessBlobGas: Option<String>,
+    pub parentBeaconBlockRoot: Option<String>,
+    pub requestsHash: Option<String>,
 }
 
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize)]
 pub struct ReceiptJson {
     pub from: String,
-    pub status: String,
+    pub status: Option<String>,
+    pub root: Option<String>,
+    pub r#type: Option<String>,
+    pub depositNonce: Option<String>,
+    pub depositReceiptVersion: Option<String>,
+    pub effectiveGasPrice: Option<String>,
+    pub blobGasUsed: Option<String>,
+    pub blobGasPrice: Option<String>,
     pub gasUsed: String,
     pub blockHash: String,
     pub logsBloom: String,
@@ -140,6 +210,28 @@ pub struct ReceiptJson {
     pub transactionIndex: String,
     pub cumulativeGasUsed: String,
     pub contractAddress: serde_json::Value,
+    // NOTE: Every field not named above, for lenient parsing to keep & strict parsing to refuse.
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize)]
+pub struct AccountProofJson {
+    pub address: String,
+    pub nonce: String,
+    pub balance: String,
+    pub codeHash: String,
+    pub storageHash: String,
+    pub accountProof: Vec<String>,
+    pub storageProof: Vec<StorageProofJson>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StorageProofJson {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
 }
 
 #[allow(non_snake_case)]
@@ -147,7 +239,7 @@ pub struct ReceiptJson {
 pub struct LogJson {
     pub data: String,
     pub removed: bool,
-    pub r#type: String,
+    pub r#type: Option<String>,
     pub address: String,
     pub logIndex: String,
     pub blockHash: String,
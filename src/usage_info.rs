@@ -1,4 +1,4 @@
-pub static USAGE_INFO: &str = "
+pub static ABOUT_INFO: &str = "
 ❍ Rusty Receipt Proof Maker ❍
 
     Copyright Provable 2019
@@ -6,20 +6,5 @@ pub static USAGE_INFO: &str = "
 
 ❍ Info ❍
 
-This tool generates a merkle receipt proof of the receipt pertaining to the given transaction hash.
-
-***
-
-Usage:  rusty-receipt-proof-maker [--help]
-        rusty-receipt-proof-maker <txhash> [--verbose | -v]
-
-Options:
-
-    --help              ❍ Show this message.
-
-    -v, --verbose       ❍ Enable verbose mode for additional output.
-
-    <txhash>            ❍ A transaction hash of an Ethereum transaction
-                        ➔ Format: A 32-byte long, prefixed hex string.
-
-";
+This tool generates merkle receipt proofs of the receipts pertaining to given transaction hashes, \
+and verifies them against the receipts root they were made from.";
@@ -6,6 +6,7 @@ use crate::{
 };
 use ethereum_types::{Address, H256, U256};
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::path::Path;
 
 pub fn convert_json_value_to_string(value: Value) -> Result<String> {
@@ -67,6 +68,17 @@ pub fn convert_hex_to_h256(hex: String) -> Result<H256> {
     })
 }
 
+pub fn convert_hex_to_left_padded_h256(hex: String) -> Result<H256> {
+    decode_prefixed_hex(hex).and_then(|bytes| match bytes.len() <= HASH_LENGTH {
+        true => Ok(H256::from_slice(
+            &[vec![0u8; HASH_LENGTH - bytes.len()], bytes].concat(),
+        )),
+        false => Err(AppError::Custom(
+            "✘ Too many bytes in hex to create H256 type!".into(),
+        )),
+    })
+}
+
 pub fn convert_hex_strings_to_h256s(hex_strings: Vec<String>) -> Result<Vec<H256>> {
     hex_strings.into_iter().map(convert_hex_to_h256).collect()
 }
@@ -104,10 +116,27 @@ pub fn convert_bytes_to_hex(bytes: Bytes) -> String {
     hex::encode(bytes)
 }
 
+pub fn convert_bytes_to_prefixed_hex(bytes: &[Byte]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
 pub fn convert_h256_to_bytes(hash: H256) -> Bytes {
     hash.as_bytes().to_vec()
 }
 
+// NOTE: Returns each item appearing more than once, w/ the positions it appears at.
+pub fn get_duplicates_with_positions<T: Ord + Clone>(items: &[T]) -> Vec<(T, Vec<usize>)> {
+    let mut positions: BTreeMap<T, Vec<usize>> = BTreeMap::new();
+    items
+        .iter()
+        .enumerate()
+        .for_each(|(position, item)| positions.entry(item.clone()).or_default().push(position));
+    positions
+        .into_iter()
+        .filter(|(_, positions)| positions.len() > 1)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,6 +247,12 @@ mod tests {
         assert!(result.as_bytes() == expected_result_bytes);
     }
 
+    #[test]
+    fn should_convert_short_hex_to_left_padded_h256() {
+        let result = convert_hex_to_left_padded_h256("0x539".to_string()).unwrap();
+        assert!(result == H256::from_low_u64_be(1337));
+    }
+
     #[test]
     fn should_fail_to_convert_short_hex_to_h256_correctly() {
         let short_hash = "0xc5acf860fa849b72fc78855dcbc4e9b968a8af5cdaf79f03beeca78e6a9cec";
@@ -391,4 +426,21 @@ mod tests {
         let result = convert_bytes_to_hex(bytes);
         assert!(result == expected_result);
     }
+
+    #[test]
+    fn should_convert_bytes_to_prefixed_hex() {
+        let bytes = vec![0xc0, 0xff, 0xee];
+        let expected_result: String = "0xc0ffee".to_string();
+        let result = convert_bytes_to_prefixed_hex(&bytes);
+        assert!(result == expected_result);
+    }
+
+    #[test]
+    fn should_get_duplicates_with_positions() {
+        let items = vec!["a", "b", "a", "c", "b", "a"];
+        let expected_result = vec![("a", vec![0, 2, 5]), ("b", vec![1, 4])];
+        let result = get_duplicates_with_positions(&items);
+        assert!(result == expected_result);
+        assert!(get_duplicates_with_positions(&["a", "b"]).is_empty());
+    }
 }
@@ -0,0 +1,47 @@
+use crate::constants::{HASH_HEX_CHARS, HEX_PREFIX_LENGTH};
+use crate::input_parsing::parse_block_id;
+use crate::types::Result;
+
+pub fn is_block_hash(block_id: &str) -> bool {
+    block_id.starts_with("0x") && block_id.len() == HEX_PREFIX_LENGTH + HASH_HEX_CHARS
+}
+
+pub fn validate_block_id(block_id: &str) -> Result<()> {
+    parse_block_id(block_id).map(|_| ()).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SAMPLE_BLOCK_HASH;
+
+    #[test]
+    fn should_validate_block_number() {
+        assert!(validate_block_id("8233333").is_ok());
+    }
+
+    #[test]
+    fn should_validate_latest() {
+        assert!(validate_block_id("latest").is_ok());
+    }
+
+    #[test]
+    fn should_validate_hex_block_number_and_block_tags() {
+        ["0x7da275", "finalized", "safe"]
+            .iter()
+            .for_each(|block_id| assert!(validate_block_id(block_id).is_ok()));
+    }
+
+    #[test]
+    fn should_validate_block_hash() {
+        assert!(is_block_hash(SAMPLE_BLOCK_HASH));
+        assert!(validate_block_id(SAMPLE_BLOCK_HASH).is_ok());
+    }
+
+    #[test]
+    fn should_fail_to_validate_malformed_block_ids() {
+        ["", "0xc0ffeeg", "earliest", "-1"]
+            .iter()
+            .for_each(|block_id| assert!(validate_block_id(block_id).is_err()));
+    }
+}
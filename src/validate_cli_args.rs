@@ -1,8 +1,203 @@
-use crate::parse_cli_args::CliArgs;
+use crate::errors::AppError;
+use crate::input_parsing::{normalize_address, normalize_block_id, normalize_tx_hash, InputError};
+use crate::parse_cli_args::{CliArgs, CliCommand, DebugCommand};
 use crate::types::Result;
-use crate::validate_tx_hash::validate_tx_hash;
+use crate::validate_block_id::is_block_hash;
+use crate::validate_storage_args::validate_storage_slot;
 
-pub fn validate_cli_args(cli_args: CliArgs) -> Result<CliArgs> {
+// NOTE: Rewrites the input in its canonical form, so later stages needn't care how it was given.
+fn normalize_in_place(
+    input: &mut String,
+    normalize: fn(&str) -> std::result::Result<String, InputError>,
+) -> Result<()> {
+    *input = normalize(input)?;
+    Ok(())
+}
+
+fn maybe_normalize_address_in_place(maybe_address: &mut Option<String>) -> Result<()> {
+    maybe_address.as_mut().map_or(Ok(()), |address| {
+        normalize_in_place(address, normalize_address)
+    })
+}
+
+fn validate_hash(hash: &str) -> Result<()> {
+    match is_block_hash(hash) {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Expected a prefixed 32-byte hex hash, got: {}",
+            hash
+        ))),
+    }
+}
+
+fn validate_block_range(from: u64, to: u64) -> Result<()> {
+    match from <= to {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Range start {} is after its end {}!",
+            from, to
+        ))),
+    }
+}
+
+pub fn validate_cli_args(mut cli_args: CliArgs) -> Result<CliArgs> {
     info!("✔ Validating CLI args...");
-    validate_tx_hash(cli_args.arg_txhash.clone()).map(|_| cli_args)
+    match &mut cli_args.command {
+        CliCommand::ProveReceipt { tx_hash } | CliCommand::ProveLog { tx_hash, .. } => {
+            normalize_in_place(tx_hash, normalize_tx_hash)
+        }
+        CliCommand::ProveBlock {
+            block,
+            topic0,
+            address,
+        } => normalize_in_place(block, normalize_block_id)
+            .and_then(|_| topic0.as_deref().map_or(Ok(()), validate_hash))
+            .and_then(|_| maybe_normalize_address_in_place(address)),
+        CliCommand::ProveRange {
+            from,
+            to,
+            topic0,
+            address,
+            ..
+        } => validate_block_range(*from, *to)
+            .and_then(|_| topic0.as_deref().map_or(Ok(()), validate_hash))
+            .and_then(|_| maybe_normalize_address_in_place(address)),
+        CliCommand::Root { block }
+        | CliCommand::ProveIndex { block, .. }
+        | CliCommand::ExplainRootMismatch { block }
+        | CliCommand::ExportTrie { block, .. }
+        | CliCommand::RecordFixtures { block, .. }
+        | CliCommand::Debug {
+            command: DebugCommand::DumpTrie { block, .. },
+        } => normalize_in_place(block, normalize_block_id),
+        CliCommand::ProveStorage {
+            address,
+            slot,
+            block,
+        } => normalize_in_place(address, normalize_address)
+            .and_then(|_| validate_storage_slot(slot))
+            .and_then(|_| normalize_in_place(block, normalize_block_id)),
+        CliCommand::Verify { root, block, .. } => root
+            .iter()
+            .chain(block.iter())
+            .map(String::as_str)
+            .try_for_each(validate_hash),
+        CliCommand::Pipe => {
+            match cli_args.encrypt_to.is_some() || cli_args.encryption_key_file.is_some() {
+                true => Err(AppError::Custom(
+                    "✘ `pipe` streams proofs as they're made, so cannot encrypt them!".to_string(),
+                )),
+                false => Ok(()),
+            }
+        }
+        CliCommand::CheckStale { .. }
+        | CliCommand::ProveFromTrie { .. }
+        | CliCommand::ProveMapping { .. }
+        | CliCommand::GenVectors { .. }
+        | CliCommand::Doctor
+        | CliCommand::Formats
+        | CliCommand::Serve { .. }
+        | CliCommand::Repl
+        | CliCommand::Decrypt { .. }
+        | CliCommand::Debug {
+            command: DebugCommand::Repl,
+        } => Ok(()),
+        #[cfg(feature = "firehose")]
+        CliCommand::ProveFromFirehose { .. } => Ok(()),
+    }?;
+    Ok(cli_args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn should_validate_prove_receipt_cli_args() {
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-receipt",
+            "0xd6f577a93332e015438fcca4e73f538b1829acbd7eb0cf9ee5a0a73ff2752cc6",
+        ])
+        .unwrap();
+        assert!(validate_cli_args(cli_args).is_ok());
+    }
+
+    #[test]
+    fn should_normalize_unprefixed_hex_cli_args() {
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-storage",
+            "C02AAA39B223FE8D0A0E5C4F27EAD9083C756CC2",
+            "0x0",
+            "0x7da275",
+        ])
+        .unwrap();
+        match validate_cli_args(cli_args).unwrap().command {
+            CliCommand::ProveStorage { address, block, .. } => {
+                assert!(address == "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+                assert!(block == "8233589");
+            }
+            _ => panic!("Should have parsed `prove-storage` command!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_validate_prove_receipt_cli_args_with_bad_tx_hash() {
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-receipt",
+            "0xc0ffee",
+        ])
+        .unwrap();
+        assert!(validate_cli_args(cli_args).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_validate_prove_storage_cli_args_with_bad_address() {
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-storage",
+            "0xc0ffee",
+            "0x0",
+            "latest",
+        ])
+        .unwrap();
+        assert!(validate_cli_args(cli_args).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_validate_root_cli_args_with_bad_block_id() {
+        let cli_args =
+            CliArgs::try_parse_from(vec!["rusty-receipt-proof-maker", "root", "earliest"]).unwrap();
+        assert!(validate_cli_args(cli_args).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_validate_prove_range_cli_args_with_backwards_range() {
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "prove-range",
+            "100",
+            "99",
+            "--ndjson",
+            "proofs.ndjson",
+        ])
+        .unwrap();
+        assert!(validate_cli_args(cli_args).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_validate_verify_cli_args_with_bad_root() {
+        let cli_args = CliArgs::try_parse_from(vec![
+            "rusty-receipt-proof-maker",
+            "verify",
+            "proof.json",
+            "--root",
+            "0xc0ffee",
+        ])
+        .unwrap();
+        assert!(validate_cli_args(cli_args).is_err());
+    }
 }
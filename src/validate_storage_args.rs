@@ -0,0 +1,70 @@
+use crate::constants::{HASH_HEX_CHARS, HEX_PREFIX_LENGTH};
+use crate::errors::AppError;
+use crate::input_parsing::parse_address;
+use crate::types::Result;
+use crate::utils::decode_prefixed_hex;
+
+fn is_prefixed_hex_of_max_length(hex: &str, max_hex_chars: usize) -> bool {
+    hex.starts_with("0x")
+        && hex.len() > HEX_PREFIX_LENGTH
+        && hex.len() <= HEX_PREFIX_LENGTH + max_hex_chars
+        && decode_prefixed_hex(hex.to_string()).is_ok()
+}
+
+pub fn validate_address(address: &str) -> Result<()> {
+    parse_address(address).map(|_| ()).map_err(Into::into)
+}
+
+pub fn validate_storage_slot(slot: &str) -> Result<()> {
+    match is_prefixed_hex_of_max_length(slot, HASH_HEX_CHARS) {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Storage slot must be at most 32 bytes of prefixed hex, got: {}",
+            slot
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_validate_address_w_or_wo_prefix() {
+        assert!(validate_address("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").is_ok());
+        assert!(validate_address("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").is_ok());
+    }
+
+    #[test]
+    fn should_fail_to_validate_malformed_addresses() {
+        [
+            "",
+            "0xc0ffee",
+            "weth.eth",
+            "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cg2",
+        ]
+        .iter()
+        .for_each(|address| assert!(validate_address(address).is_err()));
+    }
+
+    #[test]
+    fn should_validate_short_and_full_length_storage_slots() {
+        assert!(validate_storage_slot("0x0").is_ok());
+        assert!(validate_storage_slot(
+            "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn should_fail_to_validate_malformed_storage_slots() {
+        [
+            "",
+            "0x",
+            "1",
+            "0xc0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffeec0ffee000000",
+        ]
+        .iter()
+        .for_each(|slot| assert!(validate_storage_slot(slot).is_err()));
+    }
+}
@@ -1,61 +1,28 @@
-use crate::errors::AppError;
+use crate::input_parsing::parse_tx_hash;
 use crate::types::Result;
 
-use crate::constants::{HASH_HEX_CHARS, HEX_PREFIX_LENGTH};
-
-fn check_tx_hash_prefix(tx_hash: String) -> Result<String> {
-    match tx_hash.starts_with("0x") {
-        true => Ok(tx_hash),
-        _ => Err(AppError::Custom(
-            "✘ Passed in transaction hash has no hex prefix!".to_string(),
-        )),
-    }
-}
-
-fn check_tx_hash_length(tx_hash: String) -> Result<String> {
-    let expected_len = HEX_PREFIX_LENGTH + HASH_HEX_CHARS;
-    match tx_hash.len() == expected_len {
-        true => Ok(tx_hash),
-        _ => Err(AppError::Custom(
-            "✘ Passed in transaction hash is wrong length!".to_string(),
-        )),
-    }
-}
-
 pub fn validate_tx_hash(tx_hash: String) -> Result<()> {
-    check_tx_hash_prefix(tx_hash)
-        .and_then(check_tx_hash_length)
-        .and(Ok(()))
+    parse_tx_hash(&tx_hash).map(|_| ()).map_err(Into::into)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::{HASH_HEX_CHARS, HEX_PREFIX_LENGTH};
+    use crate::errors::AppError;
     use crate::test_utils::get_valid_tx_hash_hex;
 
     #[test]
-    fn should_return_hash_when_checking_prefixed_hash() {
-        let prefixed_hex = "0xc0ffee".to_string();
-        let result = check_tx_hash_prefix(prefixed_hex.clone()).unwrap();
-        assert!(result == prefixed_hex);
-    }
-
-    #[test]
-    fn should_error_when_checking_unprefixed_hash() {
-        let expected_err = "✘ Passed in transaction hash has no hex prefix!";
-        let unprefixed_hex = "c0ffee".to_string();
-        match check_tx_hash_prefix(unprefixed_hex.clone()) {
-            Err(AppError::Custom(e)) => assert!(e == expected_err),
-            _ => panic!("Should error when checking unprefixed hex!"),
-        }
+    fn should_validate_prefixed_hash() {
+        let valid_hash = get_valid_tx_hash_hex();
+        assert!(valid_hash.len() == HEX_PREFIX_LENGTH + HASH_HEX_CHARS);
+        assert!(validate_tx_hash(valid_hash).is_ok());
     }
 
     #[test]
-    fn should_return_hash_if_correct_length() {
-        let valid_hash = get_valid_tx_hash_hex();
-        assert!(valid_hash.len() == HEX_PREFIX_LENGTH + HASH_HEX_CHARS);
-        let result = check_tx_hash_length(valid_hash.clone()).unwrap();
-        assert!(result == valid_hash)
+    fn should_validate_unprefixed_hash() {
+        let unprefixed_hash = get_valid_tx_hash_hex()[HEX_PREFIX_LENGTH..].to_string();
+        assert!(validate_tx_hash(unprefixed_hash).is_ok());
     }
 
     #[test]
@@ -63,10 +30,10 @@ mod tests {
         let short_hash = "0xc0ffee".to_string();
         let expected_len = HEX_PREFIX_LENGTH + HASH_HEX_CHARS;
         assert!(short_hash.len() < expected_len);
-        let expected_err = "✘ Passed in transaction hash is wrong length!".to_string();
-        match check_tx_hash_length(short_hash.clone()) {
+        let expected_err = "✘ Transaction hash must be 32 bytes of hex, got: 0xc0ffee".to_string();
+        match validate_tx_hash(short_hash) {
             Err(AppError::Custom(e)) => assert!(e == expected_err),
-            _ => panic!("Should error when checking unprefixed hex!"),
+            _ => panic!("Should error when checking short hash!"),
         }
     }
 
@@ -76,10 +43,9 @@ mod tests {
             "0x8aa208025cf2b43ac4b1fada62f707f82a6e2159ebd2e3aad3c94f4907e92c94c0ffee".to_string();
         let expected_len = HEX_PREFIX_LENGTH + HASH_HEX_CHARS;
         assert!(long_hash.len() > expected_len);
-        let expected_err = "✘ Passed in transaction hash is wrong length!".to_string();
-        match check_tx_hash_length(long_hash.clone()) {
-            Err(AppError::Custom(e)) => assert!(e == expected_err),
-            _ => panic!("Should error when checking unprefixed hex!"),
+        match validate_tx_hash(long_hash) {
+            Err(AppError::Custom(e)) => assert!(e.contains("must be 32 bytes of hex")),
+            _ => panic!("Should error when checking long hash!"),
         }
     }
 }
@@ -0,0 +1,532 @@
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "keccak-sha3")]
+use sha3::{Digest, Keccak256};
+
+/**
+ * Verifier:
+ *
+ * The core of proof verification, free of std & of every dependency bar the
+ * keccak backend, so it can be embedded in constrained environments such as
+ * zkVM guests & embedded light clients. Build it alone via:
+ *
+ * `cargo build --no-default-features --features verifier`
+ *
+ * It carries its own minimal RLP decoder, since the `rlp` crate needs std.
+ * Hashes are plain byte arrays & keys are slices of nibble values, so callers
+ * need nothing from the rest of the crate, whose own verification is built
+ * atop this module.
+ */
+const EMPTY_TRIE_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/**
+ * Proof Limits:
+ *
+ * Proofs are attacker-supplied, so they're bounded before any node is hashed
+ * or decoded. No key is longer than a hash's 64 nibbles, & each node but the
+ * last consumes at least one, so no valid proof has more nodes than that plus
+ * the terminating one. A node's size is only bounded by its value's, which
+ * for a receipt is bounded by the block's gas, so the cap is generous.
+ * Nodes must also be exactly one RLP item, w/ no trailing bytes.
+ */
+pub const MAX_KEY_NIBBLES: usize = 64;
+pub const MAX_PROOF_DEPTH: usize = MAX_KEY_NIBBLES + 1;
+pub const MAX_PROOF_NODE_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifierError {
+    MalformedRlp,
+    MalformedNode,
+    MalformedPath,
+    InvalidNibble,
+    InlineNode,
+    ProofEnded,
+    HashMismatch([u8; 32]),
+    ProofContinuesPastLeaf,
+    ProofContinuesPastKey,
+    ExtensionMismatch,
+    MissingBranchChild,
+    NoValueAtKey,
+    MalformedReceipt,
+    KeyTooLong(usize),
+    ProofTooDeep(usize),
+    NodeTooLarge(usize),
+}
+
+impl fmt::Display for VerifierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifierError::MalformedRlp => write!(f, "✘ Proof node is not valid RLP!"),
+            VerifierError::MalformedNode => write!(f, "✘ Cannot decode node from rlp data!"),
+            VerifierError::MalformedPath => write!(f, "✘ Malformed path in proof node!"),
+            VerifierError::InvalidNibble => write!(f, "✘ Key contains an invalid nibble!"),
+            VerifierError::InlineNode => write!(
+                f,
+                "✘ Proof contains an inline node, which is not supported!"
+            ),
+            VerifierError::ProofEnded => write!(f, "✘ Proof ended before reaching a value!"),
+            VerifierError::HashMismatch(hash) => {
+                write!(f, "✘ Proof node does not hash to expected hash: 0x")?;
+                hash.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+            }
+            VerifierError::ProofContinuesPastLeaf => {
+                write!(f, "✘ Proof continues past a leaf node!")
+            }
+            VerifierError::ProofContinuesPastKey => write!(f, "✘ Proof continues past the key!"),
+            VerifierError::ExtensionMismatch => {
+                write!(f, "✘ Extension node in proof does not match key!")
+            }
+            VerifierError::MissingBranchChild => {
+                write!(f, "✘ Branch node in proof has no child at key!")
+            }
+            VerifierError::NoValueAtKey => write!(f, "✘ Proof shows there is no value at key!"),
+            VerifierError::MalformedReceipt => write!(f, "✘ Cannot decode receipt from rlp data!"),
+            VerifierError::KeyTooLong(num_nibbles) => write!(
+                f,
+                "✘ Key of {} nibbles is longer than the max of {}!",
+                num_nibbles, MAX_KEY_NIBBLES
+            ),
+            VerifierError::ProofTooDeep(num_nodes) => write!(
+                f,
+                "✘ Proof of {} nodes is deeper than the max of {}!",
+                num_nodes, MAX_PROOF_DEPTH
+            ),
+            VerifierError::NodeTooLarge(num_bytes) => write!(
+                f,
+                "✘ Proof node of {} bytes is larger than the max of {}!",
+                num_bytes, MAX_PROOF_NODE_SIZE
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifierError {}
+
+pub type VerifierResult<T> = core::result::Result<T, VerifierError>;
+
+enum ProofNode<'a> {
+    Leaf {
+        path: Vec<u8>,
+        value: &'a [u8],
+    },
+    Extension {
+        path: Vec<u8>,
+        child: &'a [u8],
+    },
+    Branch {
+        children: Vec<&'a [u8]>,
+        value: &'a [u8],
+    },
+}
+
+#[cfg(not(feature = "keccak-sha3"))]
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    tiny_keccak::keccak256(bytes)
+}
+
+#[cfg(feature = "keccak-sha3")]
+fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+fn decode_rlp_length(bytes: &[u8], num_bytes: usize) -> VerifierResult<usize> {
+    match num_bytes <= core::mem::size_of::<usize>() && num_bytes <= bytes.len() {
+        true => Ok(bytes[..num_bytes]
+            .iter()
+            .fold(0, |length, byte| (length << 8) | *byte as usize)),
+        false => Err(VerifierError::MalformedRlp),
+    }
+}
+
+// NOTE: Returns the item's payload, whether it's a list, & whatever bytes follow the item.
+fn decode_rlp_item(bytes: &[u8]) -> VerifierResult<(&[u8], bool, &[u8])> {
+    let (prefix, rest) = bytes.split_first().ok_or(VerifierError::MalformedRlp)?;
+    let (is_list, offset, length) = match *prefix {
+        0x00..=0x7f => return Ok((&bytes[..1], false, rest)),
+        0x80..=0xb7 => (false, 0, (prefix - 0x80) as usize),
+        0xb8..=0xbf => {
+            let num_bytes = (prefix - 0xb7) as usize;
+            (false, num_bytes, decode_rlp_length(rest, num_bytes)?)
+        }
+        0xc0..=0xf7 => (true, 0, (prefix - 0xc0) as usize),
+        _ => {
+            let num_bytes = (prefix - 0xf7) as usize;
+            (true, num_bytes, decode_rlp_length(rest, num_bytes)?)
+        }
+    };
+    match offset.checked_add(length) {
+        Some(end) if end <= rest.len() => Ok((&rest[offset..end], is_list, &rest[end..])),
+        _ => Err(VerifierError::MalformedRlp),
+    }
+}
+
+// NOTE: Returns each item's payload & whether it's a list.
+fn decode_rlp_list_payload(payload: &[u8]) -> VerifierResult<Vec<(&[u8], bool)>> {
+    let mut items = Vec::new();
+    let mut remaining = payload;
+    while !remaining.is_empty() {
+        let (item, is_list, rest) = decode_rlp_item(remaining)?;
+        items.push((item, is_list));
+        remaining = rest;
+    }
+    Ok(items)
+}
+
+fn decode_rlp_list(bytes: &[u8]) -> VerifierResult<Vec<(&[u8], bool)>> {
+    match decode_rlp_item(bytes)? {
+        (payload, true, []) => decode_rlp_list_payload(payload),
+        _ => Err(VerifierError::MalformedRlp),
+    }
+}
+
+pub fn check_proof_node_size(encoded_node: &[u8]) -> VerifierResult<()> {
+    match encoded_node.len() <= MAX_PROOF_NODE_SIZE {
+        true => Ok(()),
+        false => Err(VerifierError::NodeTooLarge(encoded_node.len())),
+    }
+}
+
+fn get_string_item(item: (&[u8], bool)) -> VerifierResult<&[u8]> {
+    match item {
+        (payload, false) => Ok(payload),
+        (_, true) => Err(VerifierError::InlineNode),
+    }
+}
+
+pub fn get_nibbles_from_bytes(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+// NOTE: Returns the path's nibbles & whether it belongs to a leaf node.
+fn decode_hex_prefix_path(path: &[u8]) -> VerifierResult<(Vec<u8>, bool)> {
+    match get_nibbles_from_bytes(path).as_slice() {
+        [0, 0, rest @ ..] => Ok((rest.to_vec(), false)),
+        [1, rest @ ..] => Ok((rest.to_vec(), false)),
+        [2, 0, rest @ ..] => Ok((rest.to_vec(), true)),
+        [3, rest @ ..] => Ok((rest.to_vec(), true)),
+        _ => Err(VerifierError::MalformedPath),
+    }
+}
+
+fn decode_proof_node(encoded_node: &[u8]) -> VerifierResult<ProofNode<'_>> {
+    let items = decode_rlp_list(encoded_node)?;
+    match items.len() {
+        2 => {
+            let (path, is_leaf) = decode_hex_prefix_path(get_string_item(items[0])?)?;
+            let value = get_string_item(items[1])?;
+            Ok(match is_leaf {
+                true => ProofNode::Leaf { path, value },
+                false => ProofNode::Extension { path, child: value },
+            })
+        }
+        17 => Ok(ProofNode::Branch {
+            children: items[..16]
+                .iter()
+                .map(|item| get_string_item(*item))
+                .collect::<VerifierResult<Vec<&[u8]>>>()?,
+            value: get_string_item(items[16])?,
+        }),
+        _ => Err(VerifierError::MalformedNode),
+    }
+}
+
+fn get_hash_of_child_node(child: &[u8]) -> VerifierResult<[u8; 32]> {
+    let mut hash = [0u8; 32];
+    match child.len() == hash.len() {
+        true => {
+            hash.copy_from_slice(child);
+            Ok(hash)
+        }
+        false => Err(VerifierError::InlineNode),
+    }
+}
+
+/**
+ * Verifying Proofs:
+ *
+ * Walks the proof from the root, checking each node hashes to the hash its
+ * parent commits to, & following the key's nibbles down to its value. The key
+ * is given as nibble values, i.e. one per byte, each less than sixteen.
+ * Returns `None` if the proof instead shows there is no value at the key.
+ */
+pub fn verify_merkle_proof_of_value_or_absence<P: AsRef<[u8]>>(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[P],
+) -> VerifierResult<Option<Vec<u8>>> {
+    if key.len() > MAX_KEY_NIBBLES {
+        return Err(VerifierError::KeyTooLong(key.len()));
+    }
+    if proof.len() > MAX_PROOF_DEPTH {
+        return Err(VerifierError::ProofTooDeep(proof.len()));
+    }
+    let mut expected_hash = *root;
+    let mut key = key;
+    let mut remaining_proof = proof.iter();
+    loop {
+        let encoded_node = match remaining_proof.next() {
+            Some(encoded_node) => encoded_node.as_ref(),
+            None if expected_hash == EMPTY_TRIE_ROOT => return Ok(None),
+            None => return Err(VerifierError::ProofEnded),
+        };
+        check_proof_node_size(encoded_node)?;
+        if keccak256(encoded_node) != expected_hash {
+            return Err(VerifierError::HashMismatch(expected_hash));
+        }
+        let is_last_node = remaining_proof.len() == 0;
+        match decode_proof_node(encoded_node)? {
+            ProofNode::Leaf { path, value } => {
+                return match (path == key, is_last_node) {
+                    (true, true) => Ok(Some(value.to_vec())),
+                    (false, true) => Ok(None),
+                    (_, false) => Err(VerifierError::ProofContinuesPastLeaf),
+                }
+            }
+            ProofNode::Extension { path, child } => match key.starts_with(&path) {
+                true => {
+                    expected_hash = get_hash_of_child_node(child)?;
+                    key = &key[path.len()..];
+                }
+                false if is_last_node => return Ok(None),
+                false => return Err(VerifierError::ExtensionMismatch),
+            },
+            ProofNode::Branch { children, value } => match key.split_first() {
+                None => {
+                    return match (is_last_node, value.is_empty()) {
+                        (true, true) => Ok(None),
+                        (true, false) => Ok(Some(value.to_vec())),
+                        (false, _) => Err(VerifierError::ProofContinuesPastKey),
+                    }
+                }
+                Some((nibble, remaining_key)) => {
+                    let child = children
+                        .get(*nibble as usize)
+                        .ok_or(VerifierError::InvalidNibble)?;
+                    match (child.is_empty(), is_last_node) {
+                        (true, true) => return Ok(None),
+                        (true, false) => return Err(VerifierError::MissingBranchChild),
+                        (false, _) => {
+                            expected_hash = get_hash_of_child_node(child)?;
+                            key = remaining_key;
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+pub fn verify_merkle_proof<P: AsRef<[u8]>>(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[P],
+) -> VerifierResult<Vec<u8>> {
+    verify_merkle_proof_of_value_or_absence(root, key, proof)?.ok_or(VerifierError::NoValueAtKey)
+}
+
+// NOTE: A receipt's key is the RLP encoding of its tx index.
+pub fn get_receipt_key_nibbles(tx_index: usize) -> Vec<u8> {
+    let index_bytes = tx_index.to_be_bytes();
+    let significant_bytes = &index_bytes[index_bytes.iter().take_while(|b| **b == 0).count()..];
+    let key = match significant_bytes {
+        [] => Vec::from([0x80]),
+        [byte] if *byte < 0x80 => Vec::from([*byte]),
+        bytes => core::iter::once(0x80 + bytes.len() as u8)
+            .chain(bytes.iter().copied())
+            .collect(),
+    };
+    get_nibbles_from_bytes(&key)
+}
+
+pub fn verify_receipt_proof<P: AsRef<[u8]>>(
+    receipts_root: &[u8; 32],
+    tx_index: usize,
+    proof: &[P],
+) -> VerifierResult<Vec<u8>> {
+    verify_merkle_proof(receipts_root, &get_receipt_key_nibbles(tx_index), proof)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedLog<'a> {
+    pub address: &'a [u8],
+    pub topics: Vec<&'a [u8]>,
+    pub data: &'a [u8],
+}
+
+// NOTE: Pre-Byzantium receipts hold a post-state root where later ones hold the tx status.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedReceipt<'a> {
+    pub tx_type: u8,
+    pub status_or_post_state: &'a [u8],
+    pub cumulative_gas_used: &'a [u8],
+    pub logs_bloom: &'a [u8],
+    pub logs: Vec<DecodedLog<'a>>,
+}
+
+fn decode_log(log: (&[u8], bool)) -> VerifierResult<DecodedLog<'_>> {
+    let items = match log {
+        (payload, true) => decode_rlp_list_payload(payload)?,
+        (_, false) => return Err(VerifierError::MalformedReceipt),
+    };
+    match items.as_slice() {
+        [(address, false), (topics, true), (data, false)] => Ok(DecodedLog {
+            address,
+            topics: decode_rlp_list_payload(topics)?
+                .into_iter()
+                .map(get_string_item)
+                .collect::<VerifierResult<Vec<&[u8]>>>()?,
+            data,
+        }),
+        _ => Err(VerifierError::MalformedReceipt),
+    }
+}
+
+/**
+ * Decoding Receipts:
+ *
+ * Splits an RLP encoded receipt, such as a verified proof's value, into its
+ * fields, w/o copying any of them. A typed receipt is its type byte followed
+ * by its RLP list, whilst a legacy receipt is just the latter, & is given a
+ * type of zero. Fields some L2s append to the list, e.g. Optimism's deposit
+ * nonce, are ignored.
+ */
+pub fn decode_receipt(encoded_receipt: &[u8]) -> VerifierResult<DecodedReceipt<'_>> {
+    let (tx_type, rlp_list) = match encoded_receipt.split_first() {
+        Some((tx_type, rlp_list)) if *tx_type < 0xc0 => (*tx_type, rlp_list),
+        _ => (0, encoded_receipt),
+    };
+    match decode_rlp_list(rlp_list)
+        .map_err(|_| VerifierError::MalformedReceipt)?
+        .as_slice()
+    {
+        [(status_or_post_state, false), (cumulative_gas_used, false), (logs_bloom, false), (logs, true), ..] => {
+            Ok(DecodedReceipt {
+                tx_type,
+                status_or_post_state,
+                cumulative_gas_used,
+                logs_bloom,
+                logs: decode_rlp_list_payload(logs)?
+                    .into_iter()
+                    .map(decode_log)
+                    .collect::<VerifierResult<Vec<DecodedLog>>>()?,
+            })
+        }
+        _ => Err(VerifierError::MalformedReceipt),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::constants::HASHED_NULL_NODE;
+    use crate::proof_bundle::decode_proof_nodes;
+    use crate::rlp_codec::Decoder;
+    use crate::test_utils::get_sample_proof_bundle_1;
+    use crate::trie_keys::{ReceiptKey, TrieKey};
+    use crate::utils::{convert_hex_to_h256, decode_prefixed_hex};
+
+    fn get_sample_receipts_root_and_proof() -> ([u8; 32], Vec<Vec<u8>>) {
+        let bundle = get_sample_proof_bundle_1();
+        (
+            convert_hex_to_h256(bundle.receipts_root).unwrap().0,
+            decode_proof_nodes(&bundle.proof).unwrap(),
+        )
+    }
+
+    #[test]
+    fn should_verify_receipt_proof() {
+        let bundle = get_sample_proof_bundle_1();
+        let (receipts_root, proof) = get_sample_receipts_root_and_proof();
+        let result = verify_receipt_proof(&receipts_root, bundle.tx_index, &proof).unwrap();
+        assert!(result == decode_prefixed_hex(bundle.value).unwrap());
+    }
+
+    #[test]
+    fn should_fail_to_verify_receipt_proof_against_wrong_root_or_index() {
+        let bundle = get_sample_proof_bundle_1();
+        let (receipts_root, proof) = get_sample_receipts_root_and_proof();
+        let result = verify_receipt_proof(&[0u8; 32], bundle.tx_index, &proof);
+        assert!(result == Err(VerifierError::HashMismatch([0u8; 32])));
+        assert!(verify_receipt_proof(&receipts_root, bundle.tx_index + 1, &proof).is_err());
+        assert!(verify_receipt_proof(&receipts_root, bundle.tx_index, &proof[..1]).is_err());
+    }
+
+    #[test]
+    fn should_get_receipt_key_nibbles_matching_receipt_key() {
+        [0, 1, 127, 128, 255, 256, 70_000].iter().for_each(|index| {
+            let expected_result = ReceiptKey::from_index(*index)
+                .unwrap()
+                .to_nibbles()
+                .to_nibble_values()
+                .unwrap();
+            assert!(get_receipt_key_nibbles(*index) == expected_result);
+        });
+    }
+
+    #[test]
+    fn should_prove_absence_in_empty_trie_and_match_empty_trie_root() {
+        assert!(EMPTY_TRIE_ROOT == HASHED_NULL_NODE.0);
+        let proof: Vec<Vec<u8>> = Vec::new();
+        let result = verify_merkle_proof_of_value_or_absence(&EMPTY_TRIE_ROOT, &[8, 0], &proof);
+        assert!(result == Ok(None));
+    }
+
+    #[test]
+    fn should_decode_legacy_and_typed_receipts() {
+        let encoded_receipt = decode_prefixed_hex(get_sample_proof_bundle_1().value).unwrap();
+        let expected_num_logs = Decoder::new(&encoded_receipt)
+            .at(3)
+            .unwrap()
+            .get_item_count()
+            .unwrap();
+        let result = decode_receipt(&encoded_receipt).unwrap();
+        assert!(result.tx_type == 0);
+        assert!(result.status_or_post_state == [1]);
+        assert!(result.logs_bloom.len() == 256);
+        assert!(result.logs.len() == expected_num_logs);
+        assert!(result.logs.iter().all(|log| log.address.len() == 20));
+        let typed_receipt = [&[0x02], encoded_receipt.as_slice()].concat();
+        let typed_result = decode_receipt(&typed_receipt).unwrap();
+        assert!(typed_result.tx_type == 2);
+        assert!(typed_result.logs == result.logs);
+        assert!(decode_receipt(&encoded_receipt[..100]).is_err());
+    }
+
+    #[test]
+    fn should_reject_proofs_beyond_limits() {
+        let (root, proof) = get_sample_receipts_root_and_proof();
+        let key = get_receipt_key_nibbles(get_sample_proof_bundle_1().tx_index);
+        let deep_proof = vec![proof[0].clone(); MAX_PROOF_DEPTH + 1];
+        let long_key = vec![0u8; MAX_KEY_NIBBLES + 1];
+        let large_node = vec![0u8; MAX_PROOF_NODE_SIZE + 1];
+        assert!(
+            verify_merkle_proof(&root, &key, &deep_proof)
+                == Err(VerifierError::ProofTooDeep(MAX_PROOF_DEPTH + 1))
+        );
+        assert!(
+            verify_merkle_proof(&root, &long_key, &proof)
+                == Err(VerifierError::KeyTooLong(MAX_KEY_NIBBLES + 1))
+        );
+        assert!(
+            verify_merkle_proof(&root, &key, &[large_node])
+                == Err(VerifierError::NodeTooLarge(MAX_PROOF_NODE_SIZE + 1))
+        );
+    }
+
+    #[test]
+    fn should_reject_malformed_rlp() {
+        assert!(decode_rlp_item(&[]).is_err());
+        assert!(decode_rlp_item(&[0x83, 0xc0, 0xff]).is_err());
+        assert!(decode_rlp_list(&[0xc2, 0x80]).is_err());
+        assert!(decode_rlp_list(&[0x80]).is_err());
+        let (payload, is_list, rest) = decode_rlp_item(&[0x82, 0xc0, 0xff, 0x01]).unwrap();
+        assert!(payload == [0xc0, 0xff] && !is_list && rest == [0x01]);
+    }
+}
@@ -0,0 +1,715 @@
+use crate::binary_envelope::decode_binary_proof_output;
+use crate::block_header::{
+    check_rlp_encoded_block_header, get_verified_receipts_root_from_rlp_encoded_block_header,
+    get_verified_rlp_encoded_block_header,
+};
+use crate::constants::HASHED_NULL_NODE;
+use crate::errors::AppError;
+use crate::get_block::get_block_by_blockhash;
+use crate::get_endpoint::get_endpoint;
+use crate::get_keccak_hash::keccak_hash_bytes;
+use crate::parse_cli_args::{CliArgs, OutputFormat};
+use crate::proof_bundle::{
+    decode_proof_nodes, get_light_proof_bundle, AnyProofBundle, FullProof, LightProofBundle,
+    ProofBundle, StorageProofBundle,
+};
+use crate::proof_compression::decompress_receipt_proof;
+use crate::proof_formats::deserialize_any_proof_bundle;
+use crate::proof_signing::{check_proof_bundles_signed_by, verify_proof_bundle_signature};
+use crate::receipt_assertions::get_decoded_receipt_json;
+use crate::rlp_codec::{
+    decode_receipt, get_rlp_encoded_log_from_rlp_encoded_receipt,
+    get_storage_root_from_rlp_encoded_account,
+};
+use crate::telemetry::{Telemetry, TelemetryEvent};
+use crate::trie_keys::{ReceiptKey, StateKey, StorageKey, TrieKey};
+use crate::types::{Byte, Bytes, Receipt, Result};
+use crate::utils::{
+    convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex, convert_hex_to_address,
+    convert_hex_to_h256, decode_prefixed_hex,
+};
+use crate::validate_storage_args::validate_address;
+use crate::verifier;
+use ethereum_types::{H256, U256};
+use serde_json::Value as Json;
+use std::fs;
+
+pub fn verify_merkle_proof_of_value_or_absence<K: TrieKey>(
+    root: H256,
+    key: &K,
+    proof: &[Bytes],
+) -> Result<Option<Bytes>> {
+    key.to_nibbles().to_nibble_values().and_then(|key_nibbles| {
+        Ok(verifier::verify_merkle_proof_of_value_or_absence(
+            root.as_fixed_bytes(),
+            &key_nibbles,
+            proof,
+        )?)
+    })
+}
+
+pub fn verify_merkle_proof<K: TrieKey>(root: H256, key: &K, proof: &[Bytes]) -> Result<Bytes> {
+    verify_merkle_proof_of_value_or_absence(root, key, proof)?
+        .ok_or_else(|| AppError::Custom("✘ Proof shows there is no value at key!".to_string()))
+}
+
+pub fn verify_light_proof_bundle(bundle: &LightProofBundle) -> Result<Bytes> {
+    info!("✔ Verifying proof for tx index: {}", bundle.tx_index);
+    let proof = match bundle.compressed {
+        true => decompress_receipt_proof(bundle.tx_index, &bundle.proof)?,
+        false => decode_proof_nodes(&bundle.proof)?,
+    };
+    verify_merkle_proof(
+        convert_hex_to_h256(bundle.receipts_root.clone())?,
+        &ReceiptKey::from_index(bundle.tx_index)?,
+        &proof,
+    )
+}
+
+pub fn verify_proof_bundle(bundle: &ProofBundle) -> Result<()> {
+    verify_proof_bundle_and_get_proven_value(bundle).map(|_| ())
+}
+
+pub fn verify_proof_bundle_and_get_proven_value(bundle: &ProofBundle) -> Result<Bytes> {
+    if decode_prefixed_hex(bundle.key.clone())?
+        != ReceiptKey::from_index(bundle.tx_index)?.as_bytes()
+    {
+        return Err(AppError::Custom(format!(
+            "✘ Proof key does not encode tx index: {}",
+            bundle.tx_index
+        )));
+    }
+    if let Some(header) = &bundle.header {
+        check_rlp_encoded_block_header(
+            &decode_prefixed_hex(header.clone())?,
+            &convert_hex_to_h256(bundle.block_hash.clone())?,
+            &convert_hex_to_h256(bundle.receipts_root.clone())?,
+            bundle.block_number,
+        )?;
+    }
+    if bundle.signature.is_some() {
+        verify_proof_bundle_signature(bundle)?;
+    }
+    verify_light_proof_bundle(&get_light_proof_bundle(bundle)).and_then(|proven_value| {
+        check_proven_value_in_proof_bundle(&proven_value, bundle).map(|_| proven_value)
+    })
+}
+
+// NOTE: Decoded receipts lack what isn't in their encoding, so it's filled in from the bundle.
+pub fn verify_proof_bundle_and_get_receipt(bundle: &ProofBundle) -> Result<Receipt> {
+    let mut receipt = verify_proof_bundle_and_get_proven_value(bundle)
+        .and_then(|value| decode_receipt(&value))?;
+    receipt.transaction_hash = convert_hex_to_h256(bundle.tx_hash.clone())?;
+    receipt.transaction_index = U256::from(bundle.tx_index);
+    receipt.block_hash = convert_hex_to_h256(bundle.block_hash.clone())?;
+    receipt.block_number = U256::from(bundle.block_number);
+    Ok(receipt)
+}
+
+pub fn verify_light_proof_bundle_and_get_receipt(bundle: &LightProofBundle) -> Result<Receipt> {
+    let mut receipt = verify_light_proof_bundle(bundle).and_then(|value| decode_receipt(&value))?;
+    receipt.transaction_index = U256::from(bundle.tx_index);
+    receipt.block_hash = convert_hex_to_h256(bundle.block_hash.clone())?;
+    Ok(receipt)
+}
+
+// NOTE: A bundle may carry the value, its hash, or both, but not neither.
+fn check_proven_value_in_proof_bundle(proven_value: &[Byte], bundle: &ProofBundle) -> Result<()> {
+    if let Some(value_hash) = &bundle.value_hash {
+        if keccak_hash_bytes(proven_value)? != convert_hex_to_h256(value_hash.clone())? {
+            return Err(AppError::Custom(format!(
+                "✘ Proven value does not hash to value hash in proof: {}",
+                value_hash
+            )));
+        }
+    }
+    match bundle.value_hash.is_some() && bundle.value.is_empty() {
+        true => Ok(()),
+        false => check_proven_value_or_absence(&Some(proven_value.to_vec()), &bundle.value),
+    }
+}
+
+pub fn verify_full_proof(block_hash: &H256, full_proof: &FullProof) -> Result<Bytes> {
+    info!(
+        "✔ Verifying full proof of log {} of tx index: {}",
+        full_proof.log_index, full_proof.tx_index
+    );
+    let receipts_root = get_verified_receipts_root_from_rlp_encoded_block_header(
+        &decode_prefixed_hex(full_proof.header.clone())?,
+        block_hash,
+    )?;
+    let proven_receipt = verify_merkle_proof(
+        receipts_root,
+        &ReceiptKey::from_index(full_proof.tx_index)?,
+        &decode_proof_nodes(&full_proof.proof)?,
+    )?;
+    check_proven_value_or_absence(&Some(proven_receipt.clone()), &full_proof.receipt)?;
+    get_rlp_encoded_log_from_rlp_encoded_receipt(&proven_receipt, full_proof.log_index)
+}
+
+fn check_key_in_proof<K: TrieKey>(expected_key: &K, hex_key: &str) -> Result<()> {
+    match decode_prefixed_hex(hex_key.to_string())? == expected_key.as_bytes() {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Proof key is not the expected key: {}",
+            convert_bytes_to_prefixed_hex(expected_key.as_bytes())
+        ))),
+    }
+}
+
+fn check_proven_value_or_absence(proven_value: &Option<Bytes>, hex_value: &str) -> Result<()> {
+    match proven_value.clone().unwrap_or_default() == decode_prefixed_hex(hex_value.to_string())? {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Proven value does not match value in proof: {}",
+            convert_bytes_to_prefixed_hex(&proven_value.clone().unwrap_or_default())
+        ))),
+    }
+}
+
+pub fn verify_storage_proof_bundle(bundle: &StorageProofBundle) -> Result<()> {
+    info!(
+        "✔ Verifying proof of slot {} of account: {}",
+        bundle.slot, bundle.address
+    );
+    validate_address(&bundle.address)?;
+    let account_key = StateKey::from_address(&convert_hex_to_address(bundle.address.clone())?)?;
+    let storage_key = StorageKey::from_slot(&convert_hex_to_h256(bundle.slot.clone())?)?;
+    check_key_in_proof(&account_key, &bundle.account_key)?;
+    check_key_in_proof(&storage_key, &bundle.storage_key)?;
+    let proven_account = verify_merkle_proof_of_value_or_absence(
+        convert_hex_to_h256(bundle.state_root.clone())?,
+        &account_key,
+        &decode_proof_nodes(&bundle.account_proof)?,
+    )?;
+    check_proven_value_or_absence(&proven_account, &bundle.account_value)?;
+    let storage_root = match &proven_account {
+        Some(account) => get_storage_root_from_rlp_encoded_account(account)?,
+        None => HASHED_NULL_NODE,
+    };
+    if storage_root != convert_hex_to_h256(bundle.storage_root.clone())? {
+        return Err(AppError::Custom(format!(
+            "✘ Proven account has a different storage root: {}",
+            convert_h256_to_prefixed_hex(storage_root)?
+        )));
+    }
+    verify_merkle_proof_of_value_or_absence(
+        storage_root,
+        &storage_key,
+        &decode_proof_nodes(&bundle.storage_proof)?,
+    )
+    .and_then(|proven_value| check_proven_value_or_absence(&proven_value, &bundle.storage_value))
+}
+
+pub fn verify_any_proof_bundle(bundle: &AnyProofBundle) -> Result<()> {
+    match bundle {
+        AnyProofBundle::Full(bundle) => verify_proof_bundle(bundle),
+        AnyProofBundle::Light(bundle) => verify_light_proof_bundle(bundle).map(|_| ()),
+        AnyProofBundle::Storage(bundle) => verify_storage_proof_bundle(bundle),
+    }
+}
+
+// NOTE: Binary proofs, compressed or not, are one hex string, where JSON ones are an object or array.
+pub fn parse_proof_bundles(json_string: &str) -> Result<Vec<AnyProofBundle>> {
+    if json_string.trim_start().starts_with("0x") {
+        return decode_binary_proof_output(json_string).map(|bundles| {
+            bundles
+                .into_iter()
+                .map(|bundle| AnyProofBundle::Full(Box::new(bundle)))
+                .collect()
+        });
+    }
+    match serde_json::from_str(json_string)? {
+        Json::Array(jsons) => jsons
+            .into_iter()
+            .map(deserialize_any_proof_bundle)
+            .collect(),
+        json => Ok(vec![deserialize_any_proof_bundle(json)?]),
+    }
+}
+
+pub fn read_proof_bundles_from_file(path: &str) -> Result<Vec<AnyProofBundle>> {
+    info!("✔ Reading proof(s) from: {}", path);
+    parse_proof_bundles(&fs::read_to_string(path)?)
+}
+
+pub fn verify_proof_bundles(bundles: &[AnyProofBundle], telemetry: &Telemetry) -> Result<()> {
+    bundles
+        .iter()
+        .map(verify_any_proof_bundle)
+        .collect::<Result<Vec<()>>>()
+        .inspect_err(|_| {
+            telemetry.emit_telemetry_event(TelemetryEvent::VerificationFailed {
+                num_proofs: bundles.len(),
+            })
+        })?;
+    info!("✔ {} proof(s) verified successfully!", bundles.len());
+    Ok(())
+}
+
+pub fn verify_proof_bundles_in_file(
+    path: &str,
+    format: OutputFormat,
+    telemetry: &Telemetry,
+) -> Result<String> {
+    read_proof_bundles_from_file(path).and_then(|bundles| {
+        verify_proof_bundles(&bundles, telemetry)?;
+        Ok(match format {
+            OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => {
+                format!("✔ {} proof(s) verified!", bundles.len())
+            }
+            OutputFormat::Json | OutputFormat::Eip1186 => json!({
+                "verified": true,
+                "num_proofs": bundles.len(),
+            })
+            .to_string(),
+        })
+    })
+}
+
+/**
+ * Anchored Verification:
+ *
+ * A proof only proves its receipt against the receipts root it carries, so
+ * counterparties re-checking the proofs we hand them must anchor that root in
+ * something they trust: either the root itself, via `--root`, or a block
+ * hash, via `--block`, whose header is then fetched & checked to hash to it
+ * for its receipts root. Every proof in the file must then be a receipt proof
+ * against that root, & the receipts proven are decoded & output for them to
+ * inspect.
+ */
+pub fn get_receipts_root_from_block_hash(endpoint: &str, block_hash: &str) -> Result<H256> {
+    let expected_block_hash = convert_hex_to_h256(block_hash.to_string())?;
+    let block = get_block_by_blockhash(endpoint, expected_block_hash)?;
+    get_verified_rlp_encoded_block_header(&block)?;
+    match block.hash == expected_block_hash {
+        true => Ok(block.receipts_root),
+        false => Err(AppError::Custom(format!(
+            "✘ Node returned a block w/ a different hash to: {}",
+            block_hash
+        ))),
+    }
+}
+
+fn get_trusted_receipts_root(
+    cli_args: &CliArgs,
+    maybe_root: &Option<String>,
+    maybe_block_hash: &Option<String>,
+) -> Result<Option<H256>> {
+    match (maybe_root, maybe_block_hash) {
+        (Some(root), _) => convert_hex_to_h256(root.clone()).map(Some),
+        (None, Some(block_hash)) => {
+            let endpoint = get_endpoint(cli_args)?;
+            get_receipts_root_from_block_hash(&endpoint, block_hash).map(Some)
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+fn check_receipts_root_is_trusted(
+    receipts_root: &str,
+    trusted_root: &H256,
+    tx_index: usize,
+) -> Result<()> {
+    match convert_hex_to_h256(receipts_root.to_string())? == *trusted_root {
+        true => Ok(()),
+        false => Err(AppError::Custom(format!(
+            "✘ Proof of tx index {} is against receipts root {}, not the trusted {}!",
+            tx_index,
+            receipts_root,
+            convert_h256_to_prefixed_hex(*trusted_root)?
+        ))),
+    }
+}
+
+pub fn verify_any_proof_bundle_against_receipts_root(
+    bundle: &AnyProofBundle,
+    trusted_root: &H256,
+) -> Result<Receipt> {
+    match bundle {
+        AnyProofBundle::Full(bundle) => {
+            check_receipts_root_is_trusted(&bundle.receipts_root, trusted_root, bundle.tx_index)?;
+            verify_proof_bundle_and_get_receipt(bundle)
+        }
+        AnyProofBundle::Light(bundle) => {
+            check_receipts_root_is_trusted(&bundle.receipts_root, trusted_root, bundle.tx_index)?;
+            verify_light_proof_bundle_and_get_receipt(bundle)
+        }
+        AnyProofBundle::Storage(_) => Err(AppError::Custom(
+            "✘ Storage proofs cannot be verified against a receipts root!".to_string(),
+        )),
+    }
+}
+
+pub fn verify_proof_bundles_in_file_against_receipts_root(
+    path: &str,
+    trusted_root: &H256,
+    format: OutputFormat,
+    telemetry: &Telemetry,
+) -> Result<String> {
+    let bundles = read_proof_bundles_from_file(path)?;
+    let receipts = bundles
+        .iter()
+        .map(|bundle| {
+            let receipt = verify_any_proof_bundle_against_receipts_root(bundle, trusted_root)?;
+            Ok(json!({
+                "tx_index": receipt.transaction_index.as_usize(),
+                "receipt": get_decoded_receipt_json(&receipt)?,
+            }))
+        })
+        .collect::<Result<Vec<Json>>>()
+        .inspect_err(|_| {
+            telemetry.emit_telemetry_event(TelemetryEvent::VerificationFailed {
+                num_proofs: bundles.len(),
+            })
+        })?;
+    let root_hex = convert_h256_to_prefixed_hex(*trusted_root)?;
+    info!(
+        "✔ {} proof(s) verified against receipts root: {}",
+        bundles.len(),
+        root_hex
+    );
+    Ok(match format {
+        OutputFormat::Json | OutputFormat::Eip1186 => json!({
+            "verified": true,
+            "num_proofs": bundles.len(),
+            "receipts_root": root_hex,
+            "receipts": receipts,
+        })
+        .to_string(),
+        OutputFormat::Hex | OutputFormat::Binary | OutputFormat::Ssz => format!(
+            "✔ {} proof(s) verified against receipts root {}!\n{}",
+            bundles.len(),
+            root_hex,
+            serde_json::to_string_pretty(&receipts)?
+        ),
+    })
+}
+
+pub fn verify(
+    cli_args: &CliArgs,
+    path: &str,
+    maybe_root: &Option<String>,
+    maybe_block_hash: &Option<String>,
+    maybe_signer: &Option<String>,
+) -> Result<String> {
+    if let Some(signer) = maybe_signer {
+        read_proof_bundles_from_file(path)
+            .and_then(|bundles| check_proof_bundles_signed_by(&bundles, signer))?;
+    }
+    match get_trusted_receipts_root(cli_args, maybe_root, maybe_block_hash)? {
+        Some(trusted_root) => verify_proof_bundles_in_file_against_receipts_root(
+            path,
+            &trusted_root,
+            cli_args.format,
+            &cli_args.telemetry,
+        ),
+        None => verify_proof_bundles_in_file(path, cli_args.format, &cli_args.telemetry),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_envelope::{maybe_compress_binary_proof_output, ProofCodec};
+    use crate::binary_proof::format_proof_bundles_as_binary;
+    use crate::block_header::rlp_encode_block_header;
+    use crate::proof_bundle::{
+        compress_proof_in_proof_bundle, get_proof_bundle_json, hash_value_in_proof_bundle,
+    };
+    use crate::test_utils::{
+        get_expected_block, get_sample_proof_bundle_1, get_sample_receipts, get_sample_tx_hashes_1,
+        SAMPLE_BLOCK_JSON_PATH, SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use std::thread;
+    use tiny_http::{Response, Server};
+
+    fn write_sample_proof_bundles_to_file(name: &str, json: String) -> String {
+        let path = std::env::temp_dir().join(format!("rrpm-proof-{}.json", name));
+        fs::write(&path, json).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn get_sample_full_proof_and_block_hash() -> (FullProof, H256) {
+        let bundle = get_sample_proof_bundle_1();
+        let mut block = get_expected_block();
+        block.receipts_root = convert_hex_to_h256(bundle.receipts_root.clone()).unwrap();
+        let header = rlp_encode_block_header(&block).unwrap();
+        let full_proof = FullProof {
+            header: convert_bytes_to_prefixed_hex(&header),
+            tx_index: bundle.tx_index,
+            receipt: bundle.value,
+            proof: bundle.proof,
+            log_index: 2,
+        };
+        (full_proof, keccak_hash_bytes(&header).unwrap())
+    }
+
+    #[test]
+    fn should_verify_full_proof_and_return_proven_log() {
+        let (full_proof, block_hash) = get_sample_full_proof_and_block_hash();
+        let result = verify_full_proof(&block_hash, &full_proof).unwrap();
+        let expected_result = get_rlp_encoded_log_from_rlp_encoded_receipt(
+            &decode_prefixed_hex(full_proof.receipt.clone()).unwrap(),
+            2,
+        )
+        .unwrap();
+        assert!(result == expected_result);
+    }
+
+    #[test]
+    fn should_fail_to_verify_full_proof_against_wrong_block_hash_or_receipt() {
+        let (mut full_proof, block_hash) = get_sample_full_proof_and_block_hash();
+        match verify_full_proof(&H256::zero(), &full_proof) {
+            Err(AppError::Custom(e)) => assert!(e.contains("not to block hash")),
+            _ => panic!("Should not verify full proof against wrong block hash!"),
+        }
+        full_proof.receipt = "0xc0ffee".to_string();
+        match verify_full_proof(&block_hash, &full_proof) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not match value in proof")),
+            _ => panic!("Should not verify full proof with wrong receipt!"),
+        }
+    }
+
+    #[test]
+    fn should_verify_proof_bundle() {
+        verify_proof_bundle(&get_sample_proof_bundle_1()).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_verify_proof_bundle_with_wrong_root() {
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.receipts_root = convert_h256_to_prefixed_hex(H256::zero()).unwrap();
+        match verify_proof_bundle(&bundle) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not hash to expected hash")),
+            _ => panic!("Should not verify proof against wrong root!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_verify_proof_bundle_with_wrong_value() {
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.value = "0xc0ffee".to_string();
+        match verify_proof_bundle(&bundle) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not match value in proof")),
+            _ => panic!("Should not verify proof with wrong value!"),
+        }
+    }
+
+    #[test]
+    fn should_fail_to_verify_proof_bundle_with_header_not_anchoring_its_receipts_root() {
+        let block = get_expected_block();
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.header = Some(convert_bytes_to_prefixed_hex(
+            &rlp_encode_block_header(&block).unwrap(),
+        ));
+        match verify_proof_bundle(&bundle) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not match proof's")),
+            _ => panic!("Should not verify proof with header not anchoring its receipts root!"),
+        }
+        bundle.block_hash = convert_h256_to_prefixed_hex(H256::zero()).unwrap();
+        match verify_proof_bundle(&bundle) {
+            Err(AppError::Custom(e)) => assert!(e.contains("not to block hash")),
+            _ => panic!("Should not verify proof with header of other block!"),
+        }
+    }
+
+    #[test]
+    fn should_verify_proof_bundle_carrying_value_hash() {
+        let bundle = hash_value_in_proof_bundle(get_sample_proof_bundle_1()).unwrap();
+        verify_proof_bundle(&bundle).unwrap();
+        let mut wrong_bundle = bundle.clone();
+        wrong_bundle.value_hash = Some(convert_h256_to_prefixed_hex(H256::zero()).unwrap());
+        match verify_proof_bundle(&wrong_bundle) {
+            Err(AppError::Custom(e)) => assert!(e.contains("does not hash to value hash")),
+            _ => panic!("Should not verify proof bundle w/ wrong value hash!"),
+        }
+        let mut hashless_bundle = bundle;
+        hashless_bundle.value_hash = None;
+        assert!(verify_proof_bundle(&hashless_bundle).is_err());
+    }
+
+    #[test]
+    fn should_verify_proof_bundle_without_header() {
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.header = None;
+        verify_proof_bundle(&bundle).unwrap();
+    }
+
+    #[test]
+    fn should_fail_to_verify_proof_bundle_with_wrong_key() {
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.tx_index += 1;
+        assert!(verify_proof_bundle(&bundle).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_verify_truncated_proof() {
+        let mut bundle = get_sample_proof_bundle_1();
+        bundle.proof.pop();
+        assert!(verify_proof_bundle(&bundle).is_err());
+    }
+
+    #[test]
+    fn should_verify_light_proof_bundle_and_return_proven_receipt() {
+        let bundle = get_sample_proof_bundle_1();
+        let result = verify_light_proof_bundle(&get_light_proof_bundle(&bundle)).unwrap();
+        assert!(convert_bytes_to_prefixed_hex(&result) == bundle.value);
+    }
+
+    #[test]
+    fn should_verify_compressed_proof_bundles() {
+        let bundle = compress_proof_in_proof_bundle(get_sample_proof_bundle_1()).unwrap();
+        let json = serde_json::to_string(&vec![
+            get_proof_bundle_json(&bundle, false).unwrap(),
+            get_proof_bundle_json(&bundle, true).unwrap(),
+        ])
+        .unwrap();
+        assert!(json.contains("\"compressed\":true"));
+        let bundles = parse_proof_bundles(&json).unwrap();
+        assert!(verify_proof_bundles(&bundles, &Telemetry::default()).is_ok());
+    }
+
+    #[test]
+    fn should_verify_binary_proof_bundles_w_or_wo_envelope() {
+        let bundles = vec![get_sample_proof_bundle_1(); 2];
+        let binary = format_proof_bundles_as_binary(&bundles).unwrap();
+        let compressed = maybe_compress_binary_proof_output(
+            binary.clone(),
+            OutputFormat::Binary,
+            Some(ProofCodec::Snappy),
+        )
+        .unwrap();
+        [binary, format!("{}\n", compressed)]
+            .iter()
+            .for_each(|hex| {
+                let parsed = parse_proof_bundles(hex).unwrap();
+                assert!(parsed.len() == 2);
+                assert!(verify_proof_bundles(&parsed, &Telemetry::default()).is_ok());
+            });
+    }
+
+    #[test]
+    fn should_fail_to_verify_compressed_proof_bundle_not_flagged_as_such() {
+        let mut bundle = compress_proof_in_proof_bundle(get_sample_proof_bundle_1()).unwrap();
+        bundle.compressed = false;
+        assert!(verify_proof_bundle(&bundle).is_err());
+    }
+
+    #[test]
+    fn should_fail_to_verify_light_proof_bundle_with_wrong_index() {
+        let mut bundle = get_light_proof_bundle(&get_sample_proof_bundle_1());
+        bundle.tx_index += 1;
+        assert!(verify_any_proof_bundle(&AnyProofBundle::Light(bundle)).is_err());
+    }
+
+    #[test]
+    fn should_verify_light_proof_bundles_in_file() {
+        let bundle = get_light_proof_bundle(&get_sample_proof_bundle_1());
+        let path = write_sample_proof_bundles_to_file(
+            "light",
+            serde_json::to_string(&vec![bundle.clone(), bundle]).unwrap(),
+        );
+        let result =
+            verify_proof_bundles_in_file(&path, OutputFormat::Hex, &Telemetry::default()).unwrap();
+        assert!(result.contains("2 proof(s) verified"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_verify_proof_bundles_in_file() {
+        let bundle = get_sample_proof_bundle_1();
+        let single_path =
+            write_sample_proof_bundles_to_file("single", serde_json::to_string(&bundle).unwrap());
+        let array_path = write_sample_proof_bundles_to_file(
+            "array",
+            serde_json::to_string(&vec![bundle.clone(), bundle]).unwrap(),
+        );
+        let single_result =
+            verify_proof_bundles_in_file(&single_path, OutputFormat::Hex, &Telemetry::default())
+                .unwrap();
+        let array_result =
+            verify_proof_bundles_in_file(&array_path, OutputFormat::Json, &Telemetry::default())
+                .unwrap();
+        assert!(single_result.contains("1 proof(s) verified"));
+        assert!(array_result.contains("\"num_proofs\":2"));
+        fs::remove_file(single_path).unwrap();
+        fs::remove_file(array_path).unwrap();
+    }
+
+    #[test]
+    fn should_verify_proof_bundle_and_get_structured_receipt() {
+        let bundle = get_sample_proof_bundle_1();
+        let result = verify_proof_bundle_and_get_receipt(&bundle).unwrap();
+        let expected_receipt = get_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+        .into_iter()
+        .find(|receipt| receipt.transaction_index == U256::from(bundle.tx_index))
+        .unwrap();
+        assert!(result.transaction_hash == convert_hex_to_h256(bundle.tx_hash).unwrap());
+        assert!(result.status == expected_receipt.status);
+        assert!(result.cumulative_gas_used == expected_receipt.cumulative_gas_used);
+        assert!(result.logs == expected_receipt.logs);
+    }
+
+    #[test]
+    fn should_verify_proof_bundles_in_file_against_trusted_receipts_root() {
+        let bundle = get_sample_proof_bundle_1();
+        let path = write_sample_proof_bundles_to_file(
+            "trusted-root",
+            serde_json::to_string(&vec![
+                AnyProofBundle::Full(Box::new(bundle.clone())),
+                AnyProofBundle::Light(get_light_proof_bundle(&bundle)),
+            ])
+            .unwrap(),
+        );
+        let trusted_root = convert_hex_to_h256(bundle.receipts_root.clone()).unwrap();
+        let result: Json = serde_json::from_str(
+            &verify_proof_bundles_in_file_against_receipts_root(
+                &path,
+                &trusted_root,
+                OutputFormat::Json,
+                &Telemetry::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let untrusted_result = verify_proof_bundles_in_file_against_receipts_root(
+            &path,
+            &H256::zero(),
+            OutputFormat::Hex,
+            &Telemetry::default(),
+        );
+        fs::remove_file(path).unwrap();
+        let expected_receipt = get_decoded_receipt_json(
+            &decode_receipt(&decode_prefixed_hex(bundle.value).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert!(result["num_proofs"] == json!(2));
+        assert!(result["receipts"][0]["tx_index"] == json!(bundle.tx_index));
+        assert!(result["receipts"][0]["receipt"] == expected_receipt);
+        assert!(result["receipts"][1]["receipt"] == expected_receipt);
+        assert!(untrusted_result.is_err());
+    }
+
+    #[test]
+    fn should_get_receipts_root_from_block_hash_via_verified_header() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/", server.server_addr().to_ip().unwrap());
+        let handle = thread::spawn(move || {
+            let request = server.recv().unwrap();
+            let block_json = fs::read_to_string(SAMPLE_BLOCK_JSON_PATH).unwrap();
+            request.respond(Response::from_string(block_json)).unwrap();
+        });
+        let block = get_expected_block();
+        let result = get_receipts_root_from_block_hash(
+            &endpoint,
+            &convert_h256_to_prefixed_hex(block.hash).unwrap(),
+        )
+        .unwrap();
+        handle.join().unwrap();
+        assert!(result == block.receipts_root);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::verifier::{
+    decode_receipt, get_nibbles_from_bytes, verify_merkle_proof, DecodedLog, DecodedReceipt,
+};
+use js_sys::{Array, Object, Reflect};
+use std::convert::TryFrom;
+use wasm_bindgen::prelude::*;
+
+/**
+ * WASM Bindings:
+ *
+ * JS bindings over the `verifier` module, so browser dApps & Node relayers
+ * can verify proofs made by the CLI w/o reimplementing trie walking in JS.
+ * Hex arguments may or may not be `0x` prefixed, & bytes cross the boundary
+ * as `Uint8Array`s. Failures are thrown as JS `Error`s.
+ */
+fn decode_hex(hex_string: &str) -> Result<Vec<u8>, String> {
+    hex::decode(hex_string.strip_prefix("0x").unwrap_or(hex_string))
+        .map_err(|e| format!("✘ Invalid hex: {}", e))
+}
+
+fn convert_bytes_to_prefixed_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+// NOTE: Formats an RLP encoded integer as a JSON-RPC style quantity, i.e. w/o leading zeroes.
+fn convert_bytes_to_quantity_hex(bytes: &[u8]) -> String {
+    match hex::encode(bytes).trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        digits => format!("0x{}", digits),
+    }
+}
+
+pub fn verify_receipt_proof_hex(
+    root_hex: &str,
+    key_hex: &str,
+    proof_nodes_hex: &[String],
+) -> Result<Vec<u8>, String> {
+    let root = <[u8; 32]>::try_from(decode_hex(root_hex)?.as_slice())
+        .map_err(|_| "✘ Root must be 32 bytes!".to_string())?;
+    let proof = proof_nodes_hex
+        .iter()
+        .map(|node_hex| decode_hex(node_hex))
+        .collect::<Result<Vec<Vec<u8>>, String>>()?;
+    verify_merkle_proof(
+        &root,
+        &get_nibbles_from_bytes(&decode_hex(key_hex)?),
+        &proof,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Verifies the proof nodes against the receipts root & returns the proven RLP encoded receipt.
+/// The key is the RLP encoded tx index, i.e. a proof bundle's `key` field.
+#[wasm_bindgen(js_name = verifyReceiptProof)]
+pub fn verify_receipt_proof_js(
+    root_hex: &str,
+    key_hex: &str,
+    proof_nodes_hex: Vec<String>,
+) -> Result<Vec<u8>, JsError> {
+    verify_receipt_proof_hex(root_hex, key_hex, &proof_nodes_hex).map_err(|e| JsError::new(&e))
+}
+
+fn set_field(object: &Object, key: &str, value: &JsValue) -> Result<(), JsError> {
+    Reflect::set(object, &JsValue::from_str(key), value)
+        .map(|_| ())
+        .map_err(|_| JsError::new(&format!("✘ Cannot set field: {}", key)))
+}
+
+fn convert_log_to_js_object(log: &DecodedLog) -> Result<Object, JsError> {
+    let object = Object::new();
+    let topics = log
+        .topics
+        .iter()
+        .map(|topic| JsValue::from_str(&convert_bytes_to_prefixed_hex(topic)))
+        .collect::<Array>();
+    set_field(
+        &object,
+        "address",
+        &convert_bytes_to_prefixed_hex(log.address).into(),
+    )?;
+    set_field(&object, "topics", &topics)?;
+    set_field(
+        &object,
+        "data",
+        &convert_bytes_to_prefixed_hex(log.data).into(),
+    )?;
+    Ok(object)
+}
+
+fn convert_receipt_to_js_object(receipt: &DecodedReceipt) -> Result<Object, JsError> {
+    let object = Object::new();
+    set_field(&object, "type", &JsValue::from(receipt.tx_type))?;
+    match receipt.status_or_post_state.len() == 32 {
+        true => set_field(
+            &object,
+            "postState",
+            &convert_bytes_to_prefixed_hex(receipt.status_or_post_state).into(),
+        )?,
+        false => set_field(
+            &object,
+            "status",
+            &JsValue::from(u8::from(receipt.status_or_post_state == [1])),
+        )?,
+    };
+    set_field(
+        &object,
+        "cumulativeGasUsed",
+        &convert_bytes_to_quantity_hex(receipt.cumulative_gas_used).into(),
+    )?;
+    set_field(
+        &object,
+        "logsBloom",
+        &convert_bytes_to_prefixed_hex(receipt.logs_bloom).into(),
+    )?;
+    let logs = receipt
+        .logs
+        .iter()
+        .map(convert_log_to_js_object)
+        .collect::<Result<Vec<Object>, JsError>>()?;
+    set_field(&object, "logs", &logs.into_iter().collect::<Array>())?;
+    Ok(object)
+}
+
+/// Decodes an RLP encoded receipt, e.g. one returned by `verifyReceiptProof`, into an object
+/// holding its `type`, `status` (or pre-Byzantium `postState`), `cumulativeGasUsed`,
+/// `logsBloom` & `logs`.
+#[wasm_bindgen(js_name = decodeReceipt)]
+pub fn decode_receipt_js(bytes: &[u8]) -> Result<JsValue, JsError> {
+    decode_receipt(bytes)
+        .map_err(|e| JsError::new(&e.to_string()))
+        .and_then(|receipt| convert_receipt_to_js_object(&receipt))
+        .map(JsValue::from)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::get_sample_proof_bundle_1;
+    use crate::utils::decode_prefixed_hex;
+
+    #[test]
+    fn should_verify_receipt_proof_from_hex() {
+        let bundle = get_sample_proof_bundle_1();
+        let result =
+            verify_receipt_proof_hex(&bundle.receipts_root, &bundle.key, &bundle.proof).unwrap();
+        assert!(result == decode_prefixed_hex(bundle.value).unwrap());
+    }
+
+    #[test]
+    fn should_fail_to_verify_receipt_proof_from_bad_hex() {
+        let bundle = get_sample_proof_bundle_1();
+        assert!(verify_receipt_proof_hex("0xc0ffee", &bundle.key, &bundle.proof).is_err());
+        assert!(verify_receipt_proof_hex(&bundle.receipts_root, "0xzz", &bundle.proof).is_err());
+        assert!(verify_receipt_proof_hex(&bundle.receipts_root, "0x0f", &bundle.proof).is_err());
+    }
+
+    #[test]
+    fn should_convert_bytes_to_quantity_hex() {
+        assert!(convert_bytes_to_quantity_hex(&[]) == "0x0");
+        assert!(convert_bytes_to_quantity_hex(&[0x0a]) == "0xa");
+        assert!(convert_bytes_to_quantity_hex(&[0x01, 0x00]) == "0x100");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::errors::AppError;
+use crate::get_branch_from_trie::get_branch_from_trie;
+use crate::trie::Trie;
+use crate::trie_keys::{ReceiptKey, TrieKey};
+use crate::trie_nodes::{Node, NodeKind};
+use crate::types::{Bytes, Result};
+use crate::utils::{convert_bytes_to_prefixed_hex, convert_h256_to_prefixed_hex};
+
+/**
+ * What If:
+ *
+ * Researchers constructing fraud-proof counterexamples, or testing how
+ * sensitive a verifier is, need the trie a block would have had were one of
+ * its receipts different. This clones a built trie, replaces the receipt at
+ * a tx index w/ arbitrary bytes, & reports the new root along w/ exactly
+ * which proof nodes changed, by depth, old & new hash & encoding. Replacing a
+ * value never changes the trie's shape, so only the nodes on that receipt's
+ * path can change, & any node off that path is as it was in the original.
+ */
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeChange {
+    pub depth: usize,
+    pub node_type: NodeKind,
+    pub old_hash: String,
+    pub new_hash: String,
+    pub old_node: String,
+    pub new_node: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WhatIfReport {
+    pub tx_index: usize,
+    pub old_root: String,
+    pub new_root: String,
+    pub changed_nodes: Vec<NodeChange>,
+    pub proof: Vec<String>,
+}
+
+fn get_node_change(depth: usize, old_node: &Node, new_node: &Node) -> Result<Option<NodeChange>> {
+    let old_encoding = old_node.get_rlp_encoding()?;
+    let new_encoding = new_node.get_rlp_encoding()?;
+    if old_encoding == new_encoding {
+        return Ok(None);
+    }
+    Ok(Some(NodeChange {
+        depth,
+        node_type: new_node.get_type(),
+        old_hash: convert_h256_to_prefixed_hex(old_node.get_hash()?)?,
+        new_hash: convert_h256_to_prefixed_hex(new_node.get_hash()?)?,
+        old_node: convert_bytes_to_prefixed_hex(&old_encoding),
+        new_node: convert_bytes_to_prefixed_hex(&new_encoding),
+    }))
+}
+
+pub fn replace_receipt_in_trie(
+    trie: &Trie,
+    tx_index: usize,
+    value: Bytes,
+) -> Result<(Trie, WhatIfReport)> {
+    let old_branch = get_branch_from_trie(trie, tx_index)?;
+    let new_trie = trie
+        .clone()
+        .put(ReceiptKey::from_index(tx_index)?.to_nibbles(), value)?;
+    let new_branch = get_branch_from_trie(&new_trie, tx_index)?;
+    if old_branch.len() != new_branch.len() {
+        return Err(AppError::Custom(format!(
+            "✘ Replacing receipt at index {} changed the trie's shape!",
+            tx_index
+        )));
+    }
+    let changed_nodes = old_branch
+        .iter()
+        .zip(new_branch.iter())
+        .enumerate()
+        .map(|(depth, (old_node, new_node))| get_node_change(depth, old_node, new_node))
+        .collect::<Result<Vec<Option<NodeChange>>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    let report = WhatIfReport {
+        tx_index,
+        old_root: convert_h256_to_prefixed_hex(trie.root)?,
+        new_root: convert_h256_to_prefixed_hex(new_trie.root)?,
+        changed_nodes,
+        proof: new_branch
+            .iter()
+            .map(|node| {
+                node.get_rlp_encoding()
+                    .map(|encoding| convert_bytes_to_prefixed_hex(&encoding))
+            })
+            .collect::<Result<Vec<String>>>()?,
+    };
+    Ok((new_trie, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_bundle::decode_proof_nodes;
+    use crate::test_utils::{
+        get_sample_trie_with_sample_receipts, get_sample_tx_hashes_1, PROOF_1_INDEX,
+        SAMPLE_RECEIPT_JSONS_1_PATH,
+    };
+    use crate::verify_proof::verify_merkle_proof;
+
+    fn get_sample_trie() -> Trie {
+        get_sample_trie_with_sample_receipts(
+            SAMPLE_RECEIPT_JSONS_1_PATH.to_string(),
+            get_sample_tx_hashes_1(),
+        )
+    }
+
+    #[test]
+    fn should_replace_receipt_in_trie_and_report_changed_nodes() {
+        let trie = get_sample_trie();
+        let value = vec![0xc0];
+        let (new_trie, report) =
+            replace_receipt_in_trie(&trie, PROOF_1_INDEX, value.clone()).unwrap();
+        assert!(new_trie.root != trie.root);
+        assert!(report.old_root == convert_h256_to_prefixed_hex(trie.root).unwrap());
+        assert!(report.new_root == convert_h256_to_prefixed_hex(new_trie.root).unwrap());
+        assert!(report.changed_nodes.len() == report.proof.len());
+        assert!(report.changed_nodes.last().unwrap().node_type == NodeKind::Leaf);
+        let proof = decode_proof_nodes(&report.proof).unwrap();
+        let key = ReceiptKey::from_index(PROOF_1_INDEX).unwrap();
+        assert!(verify_merkle_proof(new_trie.root, &key, &proof).unwrap() == value);
+    }
+
+    #[test]
+    fn should_leave_original_trie_untouched() {
+        let trie = get_sample_trie();
+        let original_root = trie.root;
+        let other_index = PROOF_1_INDEX + 1;
+        let original_proof = get_branch_from_trie(&trie, other_index).unwrap();
+        let (new_trie, _) = replace_receipt_in_trie(&trie, PROOF_1_INDEX, vec![0xc0]).unwrap();
+        assert!(trie.root == original_root);
+        assert!(get_branch_from_trie(&trie, other_index).unwrap() == original_proof);
+        assert!(get_branch_from_trie(&new_trie, other_index).unwrap() != original_proof);
+    }
+
+    #[test]
+    fn should_report_no_changes_when_replacing_receipt_w_itself() {
+        let trie = get_sample_trie();
+        let value = get_branch_from_trie(&trie, PROOF_1_INDEX)
+            .unwrap()
+            .last()
+            .unwrap()
+            .get_value()
+            .unwrap();
+        let (new_trie, report) = replace_receipt_in_trie(&trie, PROOF_1_INDEX, value).unwrap();
+        assert!(new_trie.root == trie.root);
+        assert!(report.changed_nodes.is_empty());
+    }
+
+    #[test]
+    fn should_fail_to_replace_non_existent_receipt() {
+        let trie = get_sample_trie();
+        let non_existent_index = get_sample_tx_hashes_1().len() + 1;
+        assert!(replace_receipt_in_trie(&trie, non_existent_index, vec![0xc0]).is_err());
+    }
+}
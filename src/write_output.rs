@@ -0,0 +1,34 @@
+use crate::types::Result;
+use std::fs;
+
+// NOTE: Commands streaming their own output, e.g. `pipe`, return none, so nothing's written.
+pub fn write_output(output: &str, output_path: &Option<String>) -> Result<()> {
+    if output.is_empty() {
+        return Ok(());
+    }
+    match output_path {
+        None => {
+            println!("{}", output);
+            Ok(())
+        }
+        Some(path) => {
+            info!("✔ Writing output to: {}", path);
+            Ok(fs::write(path, format!("{}\n", output))?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_write_output_to_file() {
+        let path = std::env::temp_dir().join("rrpm-write-output");
+        let path_string = path.to_str().unwrap().to_string();
+        write_output("c0ffee", &Some(path_string)).unwrap();
+        let result = fs::read_to_string(&path).unwrap();
+        assert!(result == "c0ffee\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
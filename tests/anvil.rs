@@ -0,0 +1,199 @@
+//! End-to-end tests running the full pipeline against a local `anvil` chain.
+//!
+//! Run with: `cargo test --features anvil-tests --test anvil`
+//!
+//! Requires `anvil` on the `PATH`, or its location in the `ANVIL_BIN` env var.
+#![cfg(feature = "anvil-tests")]
+
+use serde_json::{json, Value as Json};
+use std::fs;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const NUM_TRANSFERS: usize = 5;
+const GAS_PRICE: &str = "0x3b9aca00";
+const ANVIL_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+// NOTE: Anvil's first two default (unlocked) dev accounts.
+const SENDER: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+const RECIPIENT: &str = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+// NOTE: Init code which emits a LOG1 w/ topic 0xc0ffee & 32 bytes of data, then deploys nothing.
+const LOG_EMITTING_INIT_CODE: &str = "0x602a60005262c0ffee60206000a100";
+
+struct Anvil {
+    child: Child,
+    endpoint: String,
+}
+
+impl Drop for Anvil {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn get_free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .expect("✘ Could not find a free port!")
+}
+
+fn rpc_call(endpoint: &str, method: &str, params: Json) -> Result<Json, String> {
+    let response: Json = reqwest::blocking::Client::new()
+        .post(endpoint)
+        .json(&json!({ "id": 1, "jsonrpc": "2.0", "method": method, "params": params }))
+        .send()
+        .and_then(|response| response.json())
+        .map_err(|e| e.to_string())?;
+    match response.get("error") {
+        Some(error) => Err(format!("✘ RPC error calling {}: {}", method, error)),
+        None => Ok(response["result"].clone()),
+    }
+}
+
+fn start_anvil() -> Anvil {
+    let port = get_free_port();
+    let child = Command::new(std::env::var("ANVIL_BIN").unwrap_or_else(|_| "anvil".to_string()))
+        .args(["--port", &port.to_string(), "--silent"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("✘ Could not spawn anvil - is it installed & on the PATH?");
+    let anvil = Anvil {
+        child,
+        endpoint: format!("http://127.0.0.1:{}", port),
+    };
+    let started = Instant::now();
+    while rpc_call(&anvil.endpoint, "eth_chainId", json!([])).is_err() {
+        assert!(
+            started.elapsed() < ANVIL_STARTUP_TIMEOUT,
+            "✘ Anvil did not start in time!"
+        );
+        sleep(Duration::from_millis(100));
+    }
+    anvil
+}
+
+fn send_legacy_tx(anvil: &Anvil, to: Option<&str>, data: &str) -> String {
+    let mut tx = json!({ "from": SENDER, "gasPrice": GAS_PRICE, "gas": "0x30d40", "data": data });
+    if let Some(address) = to {
+        tx["to"] = json!(address);
+    }
+    rpc_call(&anvil.endpoint, "eth_sendTransaction", json!([tx]))
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn mine_block_with_txs(anvil: &Anvil) -> (u64, Vec<String>) {
+    rpc_call(&anvil.endpoint, "evm_setAutomine", json!([false])).unwrap();
+    let mut tx_hashes: Vec<String> = (0..NUM_TRANSFERS)
+        .map(|_| send_legacy_tx(anvil, Some(RECIPIENT), "0x"))
+        .collect();
+    tx_hashes.push(send_legacy_tx(anvil, None, LOG_EMITTING_INIT_CODE));
+    rpc_call(&anvil.endpoint, "evm_mine", json!([])).unwrap();
+    let block_number = rpc_call(&anvil.endpoint, "eth_blockNumber", json!([])).unwrap();
+    (
+        u64::from_str_radix(block_number.as_str().unwrap().trim_start_matches("0x"), 16).unwrap(),
+        tx_hashes,
+    )
+}
+
+fn get_temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rrpm-anvil-{}-{}", std::process::id(), name))
+}
+
+fn run_tool(anvil: &Anvil, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rusty-receipt-proof-maker"))
+        .args(args)
+        .args(["--endpoint", &anvil.endpoint, "--log-level", "off"])
+        .output()
+        .expect("✘ Could not run rusty-receipt-proof-maker!")
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "✘ Command failed!\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn should_prove_and_verify_every_receipt_in_local_block() {
+    let anvil = start_anvil();
+    let (_, tx_hashes) = mine_block_with_txs(&anvil);
+    tx_hashes.iter().enumerate().for_each(|(i, tx_hash)| {
+        let proof_path = get_temp_path(&format!("receipt-{}.json", i));
+        let proof_path_str = proof_path.to_str().unwrap();
+        assert_success(&run_tool(
+            &anvil,
+            &[
+                "prove-receipt",
+                tx_hash,
+                "--format",
+                "json",
+                "-o",
+                proof_path_str,
+            ],
+        ));
+        let bundle: Json = serde_json::from_str(&fs::read_to_string(&proof_path).unwrap()).unwrap();
+        assert!(bundle["tx_index"] == i);
+        assert!(bundle["tx_hash"] == tx_hash.as_str());
+        assert_success(&run_tool(&anvil, &["verify", proof_path_str]));
+        fs::remove_file(proof_path).unwrap();
+    });
+}
+
+#[test]
+fn should_compute_receipts_root_matching_local_block_header() {
+    let anvil = start_anvil();
+    let (block_number, _) = mine_block_with_txs(&anvil);
+    let header = rpc_call(
+        &anvil.endpoint,
+        "eth_getBlockByNumber",
+        json!([format!("0x{:x}", block_number), false]),
+    )
+    .unwrap();
+    let output = run_tool(
+        &anvil,
+        &["root", &block_number.to_string(), "--format", "json"],
+    );
+    assert_success(&output);
+    let result: Json = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(result["matches"] == true);
+    assert!(result["computed_receipts_root"] == header["receiptsRoot"]);
+}
+
+#[test]
+fn should_prove_block_and_reject_tampered_proofs() {
+    let anvil = start_anvil();
+    let (block_number, tx_hashes) = mine_block_with_txs(&anvil);
+    let proof_path = get_temp_path("block.json");
+    let proof_path_str = proof_path.to_str().unwrap();
+    assert_success(&run_tool(
+        &anvil,
+        &[
+            "prove-block",
+            &block_number.to_string(),
+            "--format",
+            "json",
+            "-o",
+            proof_path_str,
+        ],
+    ));
+    let mut bundles: Json =
+        serde_json::from_str(&fs::read_to_string(&proof_path).unwrap()).unwrap();
+    assert!(bundles.as_array().unwrap().len() == tx_hashes.len());
+    assert_success(&run_tool(&anvil, &["verify", proof_path_str]));
+    bundles[0]["value"] = json!("0xc0ffee");
+    fs::write(&proof_path, bundles.to_string()).unwrap();
+    assert!(!run_tool(&anvil, &["verify", proof_path_str])
+        .status
+        .success());
+    fs::remove_file(proof_path).unwrap();
+}